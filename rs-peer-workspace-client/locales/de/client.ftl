@@ -0,0 +1,689 @@
+## Shared
+
+select-connection = Verbindung auswählen
+connection-name = Verbindung: { $name }
+none = Keins
+browse = Durchsuchen
+refresh = Aktualisieren
+cancel = Abbrechen
+remove = Entfernen
+run = Ausführen
+logs = Protokolle
+logs-for = Protokolle: { $name }
+default-terminal-profile = Standard-Terminalprofil
+default-shell = Standard-Shell
+folder-local = Lokal
+folder-remote = Entfernt
+transport-disconnected = Getrennt
+tree-expand = { $name } aufklappen
+tree-collapse = { $name } zuklappen
+
+## Crash reports
+
+crash-title = Absturzbericht
+crash-message = Der Client wurde beim letzten Mal unerwartet beendet. Ein Absturzbericht wurde gespeichert unter:
+crash-older =
+    { $count ->
+        [one] Es gibt außerdem 1 älteren Bericht.
+       *[other] Es gibt außerdem { $count } ältere Berichte.
+    }
+crash-open = Bericht öffnen
+crash-copy = In die Zwischenablage kopieren
+crash-dismiss = Verwerfen
+
+## Time
+
+time-ago =
+    { $unit ->
+        [seconds] gerade eben
+        [minutes] vor { $count } Min.
+        [hours]
+            { $count ->
+                [one] vor 1 Stunde
+               *[other] vor { $count } Stunden
+            }
+       *[days]
+            { $count ->
+                [one] gestern
+               *[other] vor { $count } Tagen
+            }
+    }
+time-in =
+    { $unit ->
+        [seconds] gleich
+        [minutes] in { $count } Min.
+        [hours]
+            { $count ->
+                [one] in 1 Stunde
+               *[other] in { $count } Stunden
+            }
+       *[days]
+            { $count ->
+                [one] morgen
+               *[other] in { $count } Tagen
+            }
+    }
+
+## Menu bar
+
+menu-file = Datei
+menu-create-project = Projekt erstellen
+menu-open-project = Projekt öffnen
+menu-save-project = Projekt speichern
+menu-settings = Einstellungen...
+menu-change-passphrase = Passphrase ändern
+menu-encrypt-credentials = Zugangsdaten verschlüsseln
+menu-remove-encryption = Verschlüsselung der Zugangsdaten entfernen
+menu-edit = Bearbeiten
+menu-save = Speichern
+menu-save-all = Alle speichern
+menu-format-document = Dokument formatieren
+menu-formatting = Formatierung
+menu-quick-open = Schnellöffnen
+menu-search-workspace = Im Arbeitsbereich suchen
+menu-add-connection = Verbindung hinzufügen
+menu-add-folder = Ordner hinzufügen
+menu-broadcast = Rundnachricht senden
+menu-record-macro = Makro aufzeichnen
+menu-stop-recording = Makroaufzeichnung beenden ({ $count ->
+        [one] 1 Schritt
+       *[other] { $count } Schritte
+    })
+menu-macros = Makros...
+menu-undo-operation = Letzten Vorgang rückgängig machen
+undo-delete = { $path } wiederherstellen
+undo-rename = { $to } zurück nach { $from } verschieben
+menu-view = Ansicht
+menu-command-palette = Befehlspalette...
+menu-split-vertical = Vertikal teilen
+menu-split-horizontal = Horizontal teilen
+menu-unsplit = Teilung aufheben
+menu-toggle-bottom-panel = Unteren Bereich ein-/ausblenden
+menu-explorer-options = Explorer-Optionen...
+menu-notifications = Benachrichtigungen
+menu-language = Sprache
+menu-terminal = Terminal
+menu-new-terminal = Neues Terminal
+menu-terminal-profiles = Terminalprofile
+menu-notebook = Notebook
+menu-tasks = Aufgaben
+menu-run-configs = Startkonfigurationen
+
+## Dialogs
+
+add-connection-title = Verbindung hinzufügen
+add-connection-name = Eigener Name
+add-connection-proxy-address = Proxy-Adresse
+add-connection-discovering = Suche nach Proxys im lokalen Netzwerk...
+add-connection-discovered = In diesem Netzwerk:
+add-connection-rescan = Erneut suchen
+add-connection-proxy-username = Benutzername
+add-connection-proxy-username-hint = Leer lassen, wenn der Proxy ein gemeinsames Proxy-Passwort verwendet.
+add-connection-proxy-password = Proxy-Passwort
+add-connection-user-password = Passwort
+add-connection-server-name = Servername
+add-connection-browse = Server durchsuchen...
+add-connection-server-password = Server-Passwort
+add-connection-prefer-p2p = Zuerst P2P versuchen
+add-connection-require-e2e = Ende-zu-Ende-Verschlüsselung verlangen
+add-connection-require-e2e-hint = Den Server ablehnen, wenn er den weitergeleiteten Verkehr nicht so verschlüsselt, dass der Proxy ihn nicht lesen kann.
+add-connection-outbound = Ausgehender Proxy
+add-connection-outbound-type = Typ
+add-connection-outbound-none = Keiner
+add-connection-outbound-address = Adresse (Host:Port)
+add-connection-outbound-username = Benutzername
+add-connection-outbound-password = Passwort
+add-connection-ice = ICE-Server
+add-connection-ice-hint = Werden für P2P statt des TURN-Servers des Proxys verwendet.
+add-connection-ice-username = Benutzername
+add-connection-ice-password = Passwort
+add-connection-ice-remove = Entfernen
+add-connection-ice-add = Server hinzufügen
+add-connection-env = Umgebungsvariablen
+add-connection-env-hint = Werden für jeden Befehl auf dieser Verbindung gesetzt; Terminalprofile und Startkonfigurationen können sie überschreiben.
+add-connection-keyring = Im System-Schlüsselbund speichern
+add-connection-keyring-hint = Passwörter im Geheimnisspeicher der Plattform statt in der Projektdatei ablegen
+add-connection-keyring-unavailable = In diesem Build ist kein dauerhafter System-Schlüsselbund verfügbar
+add-connection-submit = Hinzufügen
+edit-connection-title = Verbindung bearbeiten
+edit-connection-name-fixed = Der Name einer bestehenden Verbindung kann nicht geändert werden.
+edit-connection-submit = Speichern
+add-connection-test = Testen
+add-connection-test-hint = Passwörter und Servername beim Proxy prüfen, ohne die Verbindung hinzuzufügen
+connection-test-ok-turn = Verbindung zum Server hergestellt. Der Proxy bietet TURN an, P2P kann also versucht werden.
+connection-test-ok-relay = Verbindung zum Server hergestellt. Der Proxy bietet keinen TURN-Server an, der Verkehr läuft über das WebSocket-Relay.
+connection-test-ok-ice = Verbindung zum Server hergestellt. P2P verwendet die ICE-Server dieser Verbindung.
+connection-test-proxy-auth = Der Proxy hat das Proxy-Passwort abgelehnt: { $reason }
+connection-test-unknown-server = Beim Proxy ist kein Server mit diesem Namen registriert.
+connection-test-rejected = Der Proxy hat die Sitzung abgelehnt: { $reason }
+connection-test-failed = Der Proxy ist nicht erreichbar: { $error }
+server-no-metadata = Keine Metadaten gemeldet
+server-details = { $hostname } · { $os }/{ $arch } · Agent { $version }
+server-browser-title = Server
+server-browser-proxy = Registriert bei { $proxy }
+server-browser-refresh = Aktualisieren
+server-browser-filter = Nach Name, Host oder Tag filtern
+server-browser-empty = Keine Server gefunden.
+server-browser-name = Name
+server-browser-host = Host
+server-browser-platform = Plattform
+server-browser-agent = Agent
+server-browser-tags = Tags
+server-browser-choose = Diesen Server verwenden
+
+banner-dismiss = Schließen
+
+broadcast-title = Rundnachricht senden
+broadcast-message = Nachricht an alle mit diesem Server verbundenen Clients
+broadcast-send = Senden
+
+add-folder-title = Ordner hinzufügen
+add-folder-name = Anzeigename
+add-folder-submit = Ordner hinzufügen
+
+new-terminal-title = Neues Terminal
+terminal-host = Host
+new-terminal-open = Terminal öffnen
+
+new-entry-file-title = Neue Datei
+new-entry-folder-title = Neuer Ordner
+new-entry-parent = In { $path }
+new-entry-name = Name
+new-entry-create = Erstellen
+
+rename-title = Umbenennen / Verschieben
+rename-from = Von { $path }
+rename-target = Neuer Pfad
+rename-submit = Umbenennen
+
+properties-title = Eigenschaften
+properties-name = Name
+properties-location = Ort
+properties-path = Pfad
+properties-type = Typ
+properties-type-file = Datei
+properties-type-folder = Ordner
+properties-link-target = Linkziel
+properties-size = Größe
+properties-size-value = { $size } ({ $bytes } Bytes)
+properties-modified = Geändert
+properties-permissions = Berechtigungen
+properties-owner = Besitzer
+properties-free-space = Freier Speicher
+properties-free-space-value = { $available } von { $total }
+properties-unknown = Unbekannt
+properties-class-owner = Besitzer
+properties-class-group = Gruppe
+properties-class-others = Andere
+properties-read = Lesen
+properties-write = Schreiben
+properties-execute = Ausführen
+properties-read-only = Schreibgeschützt
+properties-apply = Anwenden
+properties-close = Schließen
+
+notify-menu-command-finished = Wenn ein Befehl im Hintergrund endet
+notify-menu-min-duration = Erst nach mindestens
+notify-menu-bell = Wenn ein Befehl die Glocke läutet
+format-menu-on-save = Beim Speichern formatieren
+format-menu-local-paths = Formatierer für lokale Dateien (leer nutzt PATH)
+notify-command-finished = Befehl auf { $connection } beendet
+notify-command-failed = Befehl auf { $connection } fehlgeschlagen
+notify-command-body = { $command } ({ $duration })
+
+quick-open-title = Schnellöffnen
+quick-open-hint = Teil eines Dateinamens oder Pfads eingeben
+quick-open-indexing = Entfernte Ordner werden indiziert...
+quick-open-no-folders = Fügen Sie dem Projekt einen Ordner hinzu, um seine Dateien zu durchsuchen.
+quick-open-truncated = Einige Ordner enthalten mehr Dateien, als der Index fasst; nicht jede Datei wird aufgeführt.
+quick-open-no-matches = Keine passenden Dateien.
+
+settings-title = Einstellungen
+settings-theme = Design
+settings-theme-system = System
+settings-theme-dark = Dunkel
+settings-theme-light = Hell
+settings-editor-font = Editor-Schrift
+settings-font-monospace = Festbreite
+settings-font-proportional = Proportional
+settings-font-size = Schriftgröße
+settings-tab-width = Tabulatorbreite
+settings-word-wrap = Lange Zeilen umbrechen
+settings-content-cache = Dateicache
+settings-clear-cache = Cache leeren
+settings-preview = Zwölf Boxkämpfer jagen Viktor quer über den großen Sylter Deich. 0123456789 ()[]
+settings-reset = Auf Standard zurücksetzen
+
+explorer-options-title = Explorer-Optionen
+explorer-options-hide-hidden = Versteckte Dateien ausblenden
+explorer-options-show-size = Größen anzeigen
+explorer-options-show-modified = Änderungszeiten anzeigen
+explorer-options-sort = Sortieren nach
+explorer-options-sort-name = Name
+explorer-options-sort-size = Größe
+explorer-options-sort-modified = Geändert
+explorer-options-open-on = Öffnen per
+explorer-options-single-click = Einfachklick
+explorer-options-double-click = Doppelklick
+explorer-options-expand-on-label = Ordner durch Klick auf den Namen aufklappen
+explorer-options-preview-tabs = Dateien in einem Vorschau-Tab öffnen
+explorer-options-read-ahead = Kleine Dateien neben einer geöffneten entfernten Datei vorab laden
+explorer-options-excludes = Ausschlussmuster
+explorer-options-excludes-hint = Ein Name oder Glob pro Zeile, z. B. node_modules oder *.log.
+explorer-options-text-extensions = Textdatei-Endungen
+explorer-options-text-extensions-hint = Dateien mit diesen Endungen öffnen im Editor, andere in einer schreibgeschützten Ansicht.
+explorer-options-apply = Übernehmen
+
+viewer-not-listed = Dieser Dateityp ist nicht in den Textdatei-Endungen.
+viewer-open-in-editor = Im Editor öffnen
+viewer-binary = Binärdatei, die ersten { $shown } von { $size } Bytes werden angezeigt.
+viewer-too-large = Diese Datei ist { $size } groß und zu groß, um sie ganz zu öffnen. Sie wird seitenweise angezeigt.
+viewer-head = Anfang
+viewer-previous = Zurück
+viewer-next = Weiter
+viewer-tail = Ende
+viewer-range = Bytes { $from } bis { $to }
+media-open-external = Im Systembetrachter öffnen
+media-pdf-info = PDF { $version }, { $pages } Seiten
+media-pdf-external = PDF-Seiten werden hier nicht dargestellt. Mit „Im Systembetrachter öffnen“ lässt sich das Dokument lesen.
+media-svg-source = SVG-Bilder werden hier nicht gerastert. Der Quelltext wird angezeigt.
+
+remote-picker-title = Entfernten Ordner auswählen
+remote-picker-selected = Ausgewählt
+remote-picker-use = Ordner verwenden
+remote-picker-go-to = Gehe zu
+remote-picker-roots = Wurzeln anzeigen
+remote-picker-show-files = Dateien anzeigen
+remote-picker-folder-name = Ordnername
+remote-picker-open-file = Datei öffnen
+
+passphrase-unlock-title = Projekt entsperren
+passphrase-encrypt-title = Zugangsdaten verschlüsseln
+passphrase-unlock-prompt = Die Passwörter dieses Projekts sind verschlüsselt. Gib die Passphrase ein, um es zu öffnen.
+passphrase-encrypt-prompt = Verbindungspasswörter werden beim Speichern des Projekts mit dieser Passphrase verschlüsselt.
+passphrase = Passphrase
+passphrase-confirm = Passphrase bestätigen
+passphrase-unlock = Entsperren
+passphrase-encrypt-and-save = Verschlüsseln und speichern
+passphrase-empty = Die Passphrase darf nicht leer sein.
+passphrase-mismatch = Die Passphrasen stimmen nicht überein.
+
+elevate-title = Sitzungsrechte erhöhen
+elevate-prompt = { $name } ist schreibgeschützt. Gib das Passwort zur Rechteerhöhung des Servers ein, um Dateiänderungen und Befehle für begrenzte Zeit zu erlauben.
+elevate-credential = Passwort zur Rechteerhöhung
+elevate-submit = Rechte erhöhen
+
+unsaved-title = Ungespeicherte Änderungen
+unsaved-message =
+    { $count ->
+        [one] Diese Datei enthält ungespeicherte Änderungen.
+       *[other] Die folgenden Dateien enthalten ungespeicherte Änderungen.
+    }
+unsaved-save = Speichern
+unsaved-discard = Verwerfen
+
+profiles-title = Terminalprofile
+profiles-empty = Keine Profile für diese Verbindung.
+profiles-new = Neues Profil
+profiles-name = Profilname
+profiles-shell = Shell (leer für Server-Standard)
+profiles-cwd = Arbeitsverzeichnis
+profiles-env = Umgebung (SCHLÜSSEL=WERT pro Zeile)
+profiles-startup = Startbefehle (einer pro Zeile)
+profiles-save = Profil speichern
+
+tasks-title = Aufgaben
+tasks-none = Keine Aufgaben in diesem Projekt.
+tasks-new = Neue Aufgabe
+tasks-name = Aufgabenname
+tasks-command = Befehl
+tasks-cwd = Arbeitsverzeichnis
+tasks-depends-on = Läuft, nachdem diese Aufgaben erfolgreich waren
+tasks-save = Aufgabe speichern
+tasks-run = Ausführen:
+tasks-edit = Aufgaben bearbeiten...
+tasks-clear-finished = Abgeschlossene entfernen
+tasks-stage = Stufe { $stage }
+tasks-waiting = Wartet
+tasks-running = Läuft
+tasks-succeeded = Erfolgreich
+tasks-failed = Fehlgeschlagen: { $reason }
+tasks-skipped = Übersprungen, weil eine Abhängigkeit fehlschlug
+
+runs-title = Startkonfigurationen
+runs-none = Keine Startkonfigurationen
+runs-new = Neue Startkonfiguration
+runs-name = Name
+runs-command = Befehl
+runs-cwd = Arbeitsverzeichnis
+runs-env = Umgebung (überschreibt die der Verbindung)
+runs-save = Startkonfiguration speichern
+runs-edit = Startkonfigurationen bearbeiten...
+runs-start = Ausführen
+runs-stop = Stoppen
+
+git-detached = losgelöster HEAD
+git-changes =
+    { $count ->
+        [one] 1 nicht committete Änderung
+       *[other] { $count } nicht committete Änderungen
+    }
+git-commit = Committen...
+git-refresh = Git-Status aktualisieren
+git-commit-title = Commit: { $folder }
+git-on-branch = Auf Branch { $branch }
+git-no-changes = Keine Änderungen zum Committen.
+git-message = Commit-Nachricht
+git-commit-button = Committen
+lsp-problems = { $errors } Fehler, { $warnings } Warnungen
+
+env-name = Name
+env-value = Wert
+env-secret = Geheim
+env-secret-hint = Wert in der Anzeige verbergen
+env-add = Variable hinzufügen
+
+operations-title = Vorgänge
+operations-empty = Noch keine Vorgänge. Übertragungen, Kopien, Suchen, das Speichern mehrerer Dateien und Aufgabenläufe erscheinen hier.
+operations-clear-finished = Abgeschlossene entfernen
+operations-queued = Wartend
+operations-running = Läuft
+operations-done = Fertig
+operations-cancelled = Abgebrochen
+operations-failed = Fehlgeschlagen
+
+notebook-title = Notebook
+notebook-kernel = Kernel
+notebook-kernel-name = Kernel: { $name }
+notebook-interrupt = Unterbrechen
+notebook-shutdown = Beenden
+notebook-start = Starten
+notebook-add-cell = Zelle hinzufügen
+notebook-run-all = Alle ausführen
+
+diff-title = { $path } vergleichen
+diff-side-by-side = Nebeneinander
+diff-inline = Untereinander
+diff-accept-all = Alle entfernten übernehmen
+diff-keep-all = Alle lokalen behalten
+diff-summary =
+    { $count ->
+        [one] 1 Änderung
+       *[other] { $count } Änderungen
+    } zwischen dem lokalen Puffer (links) und { $path } auf { $connection } (rechts).
+diff-unchanged-lines = ... { $count } unveränderte Zeilen ...
+diff-keep-local = Lokal behalten
+diff-accept-remote = Entfernt übernehmen
+diff-apply = Anwenden
+diff-apply-save = Anwenden und speichern
+
+## Explorer
+
+explorer-title = Explorer
+explorer-open-terminal = Terminal hier öffnen
+explorer-new-file = Neue Datei...
+explorer-new-folder = Neuer Ordner...
+explorer-upload = Datei hochladen...
+explorer-search = In Ordner suchen...
+explorer-download = Herunterladen...
+explorer-tail = Datei verfolgen
+explorer-copy = Kopieren
+explorer-paste = „{ $name }“ einfügen
+explorer-rename = Umbenennen / Verschieben...
+explorer-delete = Löschen
+explorer-properties = Eigenschaften...
+explorer-pin = Anheften
+explorer-unpin = Lösen
+explorer-pinned = Angeheftet
+address-hint = Absoluten Pfad eingeben und Enter drücken
+address-close = Schließen
+
+connections-title = Verbindungen
+connections-connected = Verbunden
+connections-connecting = Verbindet
+connections-disconnected = Getrennt
+connections-reconnect = Erneut verbinden
+connections-disconnect = Trennen
+connections-edit = Bearbeiten
+connections-remove = Entfernen
+connections-add = Verbindung hinzufügen...
+connection-details = Verbindungsdetails
+connection-none = Keine Verbindungen.
+connection-transport = Transport
+connection-identity = Server-Identität
+connection-access = Zugriff
+connection-access-full = Voll
+connection-access-read-only = Nur lesen
+connection-access-elevated = Erhöht bis { $time }
+connection-elevate = Rechte erhöhen...
+connection-end-elevation = Erhöhung beenden
+connection-collect-diagnostics = Diagnosedaten sammeln
+connection-collect-diagnostics-hint = Packt Systeminformationen, aktuelle Logs, Journal und Absturzberichte des Servers in ein Archiv und lädt es herunter.
+connection-stats = Statistik
+connection-waiting = Warte auf Daten...
+connection-candidate-pair = Kandidatenpaar
+connection-rtt = RTT
+connection-p2p-bytes = P2P gesendet / empfangen
+connection-retransmits = Neuübertragungen
+connection-relay-bytes = Relay gesendet / empfangen
+connection-throughput = Relay-Durchsatz
+connection-throughput-value = hoch { $up }/s, runter { $down }/s
+connection-outgoing-queue = Ausgehende Warteschlange
+connection-relay-encryption = Relay-Verschlüsselung
+connection-relay-encrypted = Ende-zu-Ende
+connection-relay-plain = Keine
+connection-system = System
+system-loading = Systeminformationen werden geladen...
+system-os = Betriebssystem
+system-kernel = Kernel { $kernel } auf { $host }
+system-uptime = Laufzeit
+system-cpu = CPU
+system-cpu-value = { $usage } % von { $cores } Kernen
+system-load = Durchschnittliche Last { $load }
+system-memory = Arbeitsspeicher
+system-swap = Auslagerung
+connection-environment = Umgebung
+connection-detecting-tools = Werkzeuge werden erkannt...
+connection-tool-missing = nicht gefunden
+
+## Editor
+
+editor-empty-title = Keine Datei geöffnet
+editor-empty-hint = Öffne eine .txt-, .py- oder .m-Datei im Explorer.
+editor-pane-empty = Wähle einen Tab, um ihn in diesem Bereich anzuzeigen.
+editor-transport = Transport: { $transport }
+editor-compare = Mit entfernter Datei vergleichen
+editor-save = Speichern
+editor-resolve-conflicts = Konflikte auflösen
+lock-held-by-me = Von dir gesperrt bis { $until }
+lock-held-by-other = Gesperrt von { $owner } seit { $since }
+lock-acquire = Sperren
+lock-release = Entsperren
+tab-show-in-other-pane = Im anderen Bereich anzeigen
+tab-close = Schließen
+tab-close-named = { $title } schließen
+tab-close-others = Andere schließen
+tab-close-all = Alle schließen
+
+## Bottom panel
+
+bottom-output = Ausgabe
+bottom-tasks = Aufgaben
+bottom-terminal = Terminal
+bottom-services = Dienste
+bottom-transfers = Übertragungen
+bottom-search = Suche
+bottom-workspace-search = Arbeitsbereichssuche
+bottom-logs = Protokollanzeige
+bottom-ports = Weitergeleitete Ports
+bottom-processes = Prozesse
+
+output-level = Anzeigen
+output-level-info = Alles
+output-level-warn = Warnungen und Fehler
+output-level-error = Nur Fehler
+output-info = Info
+output-warn = Warnung
+output-error = Fehler
+output-search = Suchen
+output-copy = Kopieren
+output-save = Speichern...
+output-clear = Leeren
+output-general = Allgemein
+
+logs-empty = Klicke im Explorer mit der rechten Maustaste auf eine entfernte Datei und wähle Datei verfolgen, um sie hier anzuzeigen.
+logs-pause = Anhalten
+logs-resume = Fortsetzen
+logs-clear = Leeren
+logs-filter = Filter
+logs-stop = Beenden
+logs-restart = Neu starten
+logs-close = Schließen
+logs-held = { $count } neue Zeilen während der Pause zurückgehalten
+
+terminal-empty = Kein Terminal geöffnet.
+terminal-container = Container: { $name }
+terminal-cwd = Arbeitsverzeichnis: { $path }
+terminal-expand-all = Alle aufklappen
+terminal-collapse-all = Alle zuklappen
+terminal-clear = Leeren
+terminal-copy = Kopieren
+terminal-rerun = Erneut ausführen
+terminal-command = Befehl
+terminal-command-for = Befehl für { $title }
+terminal-history = Befehlsverlauf (Strg+R)
+terminal-history-title = Verlauf von { $title }
+terminal-history-hint = Befehle durchsuchen
+terminal-history-empty = Keine passenden Befehle
+
+services-filter = Filter
+services-empty = Wähle eine Verbindung, um ihre Dienste aufzulisten.
+service-start = Starten
+service-stop = Stoppen
+service-restart = Neu starten
+
+transfer-clear-finished = Abgeschlossene entfernen
+transfer-empty = Keine Übertragungen. Nutze das Kontextmenü des Explorers auf einer entfernten Datei oder einem Ordner oder ziehe Dateien auf einen entfernten Ordner.
+transfer-chunk = Block { $size } x{ $window }
+transfer-rtt = RTT { $ms } ms
+transfer-rtt-unknown = RTT unbekannt
+transfer-queued = Wartet
+transfer-done = Fertig
+transfer-cancelled = Abgebrochen
+transfer-failed = Fehlgeschlagen
+
+search-folder = Ordner
+search-pattern = Suchen
+search-regex = Regulärer Ausdruck
+search-match-case = Groß-/Kleinschreibung
+search-replace-mode = Ersetzen
+search-replacement = Ersetzen durch
+search-run = Suchen
+search-replace-selected = Auswahl ersetzen ({ $count })
+search-preview-stale = Erneut suchen, um die neue Ersetzung in der Vorschau zu sehen.
+search-empty = Wähle eine Verbindung und einen Ordner oder klicke mit der rechten Maustaste auf einen entfernten Ordner und wähle In Ordner suchen...
+search-no-results = Keine Treffer.
+search-truncated = Die ersten { $count } Treffer werden angezeigt. Grenze die Suche ein, um den Rest zu sehen.
+search-summary =
+    { $matches ->
+        [one] 1 Treffer
+       *[other] { $matches } Treffer
+    } in { $files ->
+        [one] 1 Datei
+       *[other] { $files } Dateien
+    } ersetzt.
+
+workspace-search-empty = Durchsuche alle Projektordner auf einmal. Gib ein Muster ein und drücke Enter.
+workspace-search-summary =
+    { $matches ->
+        [one] 1 Treffer
+       *[other] { $matches } Treffer
+    } in { $files ->
+        [one] 1 Datei
+       *[other] { $files } Dateien
+    } aus { $folders ->
+        [one] 1 Ordner
+       *[other] { $folders } Ordnern
+    }
+workspace-search-local = lokal
+workspace-search-searching = wird durchsucht...
+workspace-search-matches =
+    { $count ->
+        [one] 1 Treffer
+       *[other] { $count } Treffer
+    }
+workspace-search-failed = fehlgeschlagen
+workspace-search-cancelled = abgebrochen
+workspace-search-truncated = Es werden nur die ersten Treffer dieses Ordners angezeigt.
+
+command-category-macro = Makro
+command-run-macro = Makro { $name } ausführen
+command-category-connection = Verbindung
+command-reconnect = Mit { $name } verbinden
+command-collect-diagnostics = Diagnosedaten von { $name } sammeln
+command-run-task = Aufgabe { $name } ausführen
+command-run-config = { $name } ausführen
+command-language = Sprache auf { $language } umstellen
+command-show-panel = Bereich { $panel } anzeigen
+command-palette-entry = { $category }: { $command }
+command-palette-title = Befehlspalette
+command-palette-hint = Befehl eingeben
+command-palette-no-matches = Keine passenden Befehle.
+
+merge-title = { $path } zusammenführen
+merge-summary = { $count } Konflikte, { $unresolved } ungelöst. Änderungen auf nur einer Seite sind bereits übernommen.
+merge-base = Basis
+merge-mine = Meine
+merge-theirs = Ihre
+merge-take-mine = Meine übernehmen
+merge-take-theirs = Ihre übernehmen
+merge-take-both = Beide übernehmen
+merge-take-base = Basis übernehmen
+merge-result = Ergebnis
+merge-unresolved = Ungelöst
+merge-apply = Übernehmen
+merge-apply-save = Übernehmen und speichern
+
+macros-title = Makros
+macros-empty = Noch keine Makros. Zeichne eines auf, um eine Befehlsfolge erneut auszuführen.
+macros-steps = { $count ->
+        [one] 1 Schritt
+       *[other] { $count } Schritte
+    }
+macros-shortcut-hint = z. B. Ctrl+Alt+1
+macros-run = Ausführen
+macros-delete = Löschen
+macros-record = Aufzeichnen
+macros-stop-recording = Aufzeichnung beenden
+macros-import = Importieren...
+macros-export = Exportieren...
+
+ports-listen-port = Lauschport
+ports-target = Ziel
+ports-add = Weiterleiten
+ports-empty = Keine weitergeleiteten Ports. Wähle eine Verbindung, eine Richtung und das Ziel, um einen TCP-Port durch die Sitzung weiterzuleiten.
+ports-local = Lokal: hier lauschen, vom Server verbinden
+ports-remote = Entfernt: auf dem Server lauschen, von hier verbinden
+ports-stopped = Angehalten
+ports-waiting = Warte auf Verbindung
+ports-active = { $count } offen
+ports-failed = Fehlgeschlagen
+ports-last-error = Letzter Fehler: { $error }
+ports-start = Starten
+ports-stop = Stoppen
+
+processes-filter = Filter
+processes-empty = Wähle eine Verbindung, um die Prozesse auf ihrem Server aufzulisten.
+processes-pid = PID
+processes-name = Name
+processes-cpu = CPU
+processes-memory = Speicher
+processes-status = Status
+processes-watch = Beobachten
+processes-unwatch = Nicht mehr beobachten
+processes-watch-status = CPU { $cpu } %, Speicher { $memory }, { $status }
+processes-exited = Beendet
+processes-end = Beenden
+processes-end-hint = Den Prozess bitten, sich zu beenden (SIGTERM)
+processes-kill = Abwürgen
+processes-kill-hint = Den Prozess sofort beenden (SIGKILL)
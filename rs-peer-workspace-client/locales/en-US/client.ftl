@@ -0,0 +1,689 @@
+## Shared
+
+select-connection = Select connection
+connection-name = Connection: { $name }
+none = None
+browse = Browse
+refresh = Refresh
+cancel = Cancel
+remove = Remove
+run = Run
+logs = Logs
+logs-for = Logs: { $name }
+default-terminal-profile = Default Terminal Profile
+default-shell = Default shell
+folder-local = Local
+folder-remote = Remote
+transport-disconnected = Disconnected
+tree-expand = Expand { $name }
+tree-collapse = Collapse { $name }
+
+## Crash reports
+
+crash-title = Crash Report
+crash-message = The client closed unexpectedly last time. A crash report was saved to:
+crash-older =
+    { $count ->
+        [one] There is also 1 older report.
+       *[other] There are also { $count } older reports.
+    }
+crash-open = Open Report
+crash-copy = Copy to Clipboard
+crash-dismiss = Dismiss
+
+## Time
+
+time-ago =
+    { $unit ->
+        [seconds] just now
+        [minutes] { $count } min ago
+        [hours]
+            { $count ->
+                [one] 1 hour ago
+               *[other] { $count } hours ago
+            }
+       *[days]
+            { $count ->
+                [one] yesterday
+               *[other] { $count } days ago
+            }
+    }
+time-in =
+    { $unit ->
+        [seconds] in a moment
+        [minutes] in { $count } min
+        [hours]
+            { $count ->
+                [one] in 1 hour
+               *[other] in { $count } hours
+            }
+       *[days]
+            { $count ->
+                [one] tomorrow
+               *[other] in { $count } days
+            }
+    }
+
+## Menu bar
+
+menu-file = File
+menu-create-project = Create Project
+menu-open-project = Open Project
+menu-save-project = Save Project
+menu-settings = Settings...
+menu-change-passphrase = Change Passphrase
+menu-encrypt-credentials = Encrypt Credentials
+menu-remove-encryption = Remove Credential Encryption
+menu-edit = Edit
+menu-save = Save
+menu-save-all = Save All
+menu-format-document = Format Document
+menu-formatting = Formatting
+menu-quick-open = Quick Open
+menu-search-workspace = Search in Workspace
+menu-add-connection = Add Connection
+menu-add-folder = Add Folder
+menu-broadcast = Broadcast Message
+menu-record-macro = Record Macro
+menu-stop-recording = Stop Recording Macro ({ $count ->
+        [one] 1 step
+       *[other] { $count } steps
+    })
+menu-macros = Macros...
+menu-undo-operation = Undo Last Operation
+undo-delete = Restore { $path }
+undo-rename = Move { $to } back to { $from }
+menu-view = View
+menu-command-palette = Command Palette...
+menu-split-vertical = Split Vertically
+menu-split-horizontal = Split Horizontally
+menu-unsplit = Unsplit
+menu-toggle-bottom-panel = Toggle Bottom Panel
+menu-explorer-options = Explorer Options...
+menu-notifications = Notifications
+menu-language = Language
+menu-terminal = Terminal
+menu-new-terminal = New Terminal
+menu-terminal-profiles = Terminal Profiles
+menu-notebook = Notebook
+menu-tasks = Tasks
+menu-run-configs = Run Configurations
+
+## Dialogs
+
+add-connection-title = Add Connection
+add-connection-name = Custom Name
+add-connection-proxy-address = Proxy Address
+add-connection-discovering = Looking for proxies on the local network...
+add-connection-discovered = On this network:
+add-connection-rescan = Scan again
+add-connection-proxy-username = Username
+add-connection-proxy-username-hint = Leave empty when the proxy uses a shared proxy password.
+add-connection-proxy-password = Proxy Password
+add-connection-user-password = Password
+add-connection-server-name = Server Name
+add-connection-browse = Browse Servers...
+add-connection-server-password = Server Password
+add-connection-prefer-p2p = Try P2P first
+add-connection-require-e2e = Require end-to-end encryption
+add-connection-require-e2e-hint = Refuse the server unless it encrypts relayed traffic so that the proxy cannot read it.
+add-connection-outbound = Outbound Proxy
+add-connection-outbound-type = Type
+add-connection-outbound-none = None
+add-connection-outbound-address = Address (host:port)
+add-connection-outbound-username = Username
+add-connection-outbound-password = Password
+add-connection-ice = ICE servers
+add-connection-ice-hint = Used for P2P instead of the proxy's TURN server.
+add-connection-ice-username = Username
+add-connection-ice-password = Password
+add-connection-ice-remove = Remove
+add-connection-ice-add = Add Server
+add-connection-env = Environment variables
+add-connection-env-hint = Set for every command run on this connection; terminal profiles and run configurations can override them.
+add-connection-keyring = Store in system keyring
+add-connection-keyring-hint = Keep passwords in the platform secret store instead of the project file
+add-connection-keyring-unavailable = No persistent system keyring is available in this build
+add-connection-submit = Add
+edit-connection-title = Edit Connection
+edit-connection-name-fixed = The name of an existing connection cannot be changed.
+edit-connection-submit = Save
+add-connection-test = Test
+add-connection-test-hint = Check the passwords and server name with the proxy without adding the connection
+connection-test-ok-turn = Connected to the server. The proxy offers TURN, so P2P can be attempted.
+connection-test-ok-relay = Connected to the server. The proxy offers no TURN server, so traffic goes through the WebSocket relay.
+connection-test-ok-ice = Connected to the server. P2P uses this connection's ICE servers.
+connection-test-proxy-auth = The proxy rejected the proxy password: { $reason }
+connection-test-unknown-server = No server with this name is registered with the proxy.
+connection-test-rejected = The proxy refused the session: { $reason }
+connection-test-failed = Could not reach the proxy: { $error }
+server-no-metadata = No metadata reported
+server-details = { $hostname } · { $os }/{ $arch } · agent { $version }
+server-browser-title = Servers
+server-browser-proxy = Registered with { $proxy }
+server-browser-refresh = Refresh
+server-browser-filter = Filter by name, host or tag
+server-browser-empty = No servers found.
+server-browser-name = Name
+server-browser-host = Host
+server-browser-platform = Platform
+server-browser-agent = Agent
+server-browser-tags = Tags
+server-browser-choose = Use this server
+
+banner-dismiss = Dismiss
+
+broadcast-title = Broadcast Message
+broadcast-message = Message to every client connected to this server
+broadcast-send = Send
+
+add-folder-title = Add Folder
+add-folder-name = Display Name
+add-folder-submit = Add Folder
+
+new-terminal-title = New Terminal
+terminal-host = Host
+new-terminal-open = Open Terminal
+
+new-entry-file-title = New File
+new-entry-folder-title = New Folder
+new-entry-parent = In { $path }
+new-entry-name = Name
+new-entry-create = Create
+
+rename-title = Rename / Move
+rename-from = From { $path }
+rename-target = New path
+rename-submit = Rename
+
+properties-title = Properties
+properties-name = Name
+properties-location = Location
+properties-path = Path
+properties-type = Type
+properties-type-file = File
+properties-type-folder = Folder
+properties-link-target = Link target
+properties-size = Size
+properties-size-value = { $size } ({ $bytes } bytes)
+properties-modified = Modified
+properties-permissions = Permissions
+properties-owner = Owner
+properties-free-space = Free space
+properties-free-space-value = { $available } of { $total }
+properties-unknown = Unknown
+properties-class-owner = Owner
+properties-class-group = Group
+properties-class-others = Others
+properties-read = Read
+properties-write = Write
+properties-execute = Execute
+properties-read-only = Read-only
+properties-apply = Apply
+properties-close = Close
+
+notify-menu-command-finished = When a command finishes in the background
+notify-menu-min-duration = Only after at least
+notify-menu-bell = When a command rings the bell
+format-menu-on-save = Format on save
+format-menu-local-paths = Formatters for local files (empty uses PATH)
+notify-command-finished = Command finished on { $connection }
+notify-command-failed = Command failed on { $connection }
+notify-command-body = { $command } ({ $duration })
+
+quick-open-title = Quick Open
+quick-open-hint = Type part of a file name or path
+quick-open-indexing = Indexing remote folders...
+quick-open-no-folders = Add a folder to the project to search its files.
+quick-open-truncated = Some folders have more files than the index holds; not every file is listed.
+quick-open-no-matches = No matching files.
+
+settings-title = Settings
+settings-theme = Theme
+settings-theme-system = System
+settings-theme-dark = Dark
+settings-theme-light = Light
+settings-editor-font = Editor font
+settings-font-monospace = Monospace
+settings-font-proportional = Proportional
+settings-font-size = Font size
+settings-tab-width = Tab width
+settings-word-wrap = Wrap long lines
+settings-content-cache = File cache
+settings-clear-cache = Clear Cache
+settings-preview = The quick brown fox jumps over the lazy dog. 0123456789 ()[]
+settings-reset = Reset to Defaults
+
+explorer-options-title = Explorer Options
+explorer-options-hide-hidden = Hide hidden files
+explorer-options-show-size = Show sizes
+explorer-options-show-modified = Show modification times
+explorer-options-sort = Sort by
+explorer-options-sort-name = Name
+explorer-options-sort-size = Size
+explorer-options-sort-modified = Modified
+explorer-options-open-on = Open with
+explorer-options-single-click = Single click
+explorer-options-double-click = Double click
+explorer-options-expand-on-label = Expand folders by clicking their name
+explorer-options-preview-tabs = Open files in a preview tab
+explorer-options-read-ahead = Prefetch small files next to an opened remote file
+explorer-options-excludes = Exclude patterns
+explorer-options-excludes-hint = One name or glob per line, e.g. node_modules or *.log.
+explorer-options-text-extensions = Text file extensions
+explorer-options-text-extensions-hint = Files with these extensions open in the editor; others open in a read-only viewer.
+explorer-options-apply = Apply
+
+viewer-not-listed = This file type is not in the text file extensions.
+viewer-open-in-editor = Open in Editor
+viewer-binary = Binary file, showing the first { $shown } of { $size } bytes.
+viewer-too-large = This file is { $size }, too large to open whole. It is shown a page at a time.
+viewer-head = Head
+viewer-previous = Previous
+viewer-next = Next
+viewer-tail = Tail
+viewer-range = Bytes { $from } to { $to }
+media-open-external = Open in System Viewer
+media-pdf-info = PDF { $version }, { $pages } pages
+media-pdf-external = PDF pages are not rendered here. Use Open in System Viewer to read the document.
+media-svg-source = SVG images are not rasterized here. Showing the source.
+
+remote-picker-title = Remote Folder Picker
+remote-picker-selected = Selected
+remote-picker-use = Use Folder
+remote-picker-go-to = Go to
+remote-picker-roots = Show Roots
+remote-picker-show-files = Show files
+remote-picker-folder-name = Folder name
+remote-picker-open-file = Open File
+
+passphrase-unlock-title = Unlock Project
+passphrase-encrypt-title = Encrypt Credentials
+passphrase-unlock-prompt = This project's passwords are encrypted. Enter the passphrase to open it.
+passphrase-encrypt-prompt = Connection passwords will be encrypted with this passphrase when the project is saved.
+passphrase = Passphrase
+passphrase-confirm = Confirm passphrase
+passphrase-unlock = Unlock
+passphrase-encrypt-and-save = Encrypt and Save
+passphrase-empty = Passphrase cannot be empty.
+passphrase-mismatch = Passphrases do not match.
+
+elevate-title = Elevate Session
+elevate-prompt = { $name } is read-only. Enter the server's elevation password to allow file changes and commands for a limited time.
+elevate-credential = Elevation password
+elevate-submit = Elevate
+
+unsaved-title = Unsaved Changes
+unsaved-message =
+    { $count ->
+        [one] This file has unsaved changes.
+       *[other] The following files have unsaved changes.
+    }
+unsaved-save = Save
+unsaved-discard = Discard
+
+profiles-title = Terminal Profiles
+profiles-empty = No profiles for this connection.
+profiles-new = New Profile
+profiles-name = Profile Name
+profiles-shell = Shell (empty for server default)
+profiles-cwd = Working Directory
+profiles-env = Environment (KEY=VALUE per line)
+profiles-startup = Startup Commands (one per line)
+profiles-save = Save Profile
+
+tasks-title = Tasks
+tasks-none = No tasks in this project.
+tasks-new = New Task
+tasks-name = Task Name
+tasks-command = Command
+tasks-cwd = Working Directory
+tasks-depends-on = Runs after these tasks succeed
+tasks-save = Save Task
+tasks-run = Run:
+tasks-edit = Edit Tasks...
+tasks-clear-finished = Clear Finished
+tasks-stage = Stage { $stage }
+tasks-waiting = Waiting
+tasks-running = Running
+tasks-succeeded = Succeeded
+tasks-failed = Failed: { $reason }
+tasks-skipped = Skipped because a dependency failed
+
+runs-title = Run Configurations
+runs-none = No run configurations
+runs-new = New Run Configuration
+runs-name = Name
+runs-command = Command
+runs-cwd = Working Directory
+runs-env = Environment (over the connection's)
+runs-save = Save Run Configuration
+runs-edit = Edit Run Configurations...
+runs-start = Run
+runs-stop = Stop
+
+git-detached = detached HEAD
+git-changes =
+    { $count ->
+        [one] 1 uncommitted change
+       *[other] { $count } uncommitted changes
+    }
+git-commit = Commit...
+git-refresh = Refresh Git Status
+git-commit-title = Commit: { $folder }
+git-on-branch = On branch { $branch }
+git-no-changes = No changes to commit.
+git-message = Commit message
+git-commit-button = Commit
+lsp-problems = { $errors } errors, { $warnings } warnings
+
+env-name = Name
+env-value = Value
+env-secret = Secret
+env-secret-hint = Mask the value where it is shown
+env-add = Add Variable
+
+operations-title = Operations
+operations-empty = No operations yet. Transfers, copies, searches, saves of several files and task runs appear here.
+operations-clear-finished = Clear Finished
+operations-queued = Queued
+operations-running = Running
+operations-done = Done
+operations-cancelled = Cancelled
+operations-failed = Failed
+
+notebook-title = Notebook
+notebook-kernel = Kernel
+notebook-kernel-name = Kernel: { $name }
+notebook-interrupt = Interrupt
+notebook-shutdown = Shut Down
+notebook-start = Start
+notebook-add-cell = Add Cell
+notebook-run-all = Run All
+
+diff-title = Compare { $path }
+diff-side-by-side = Side by side
+diff-inline = Inline
+diff-accept-all = Accept All Remote
+diff-keep-all = Keep All Local
+diff-summary =
+    { $count ->
+        [one] 1 change
+       *[other] { $count } changes
+    } between the local buffer (left) and { $path } on { $connection } (right).
+diff-unchanged-lines = ... { $count } unchanged lines ...
+diff-keep-local = Keep local
+diff-accept-remote = Accept remote
+diff-apply = Apply
+diff-apply-save = Apply and Save
+
+## Explorer
+
+explorer-title = Explorer
+explorer-open-terminal = Open Terminal Here
+explorer-new-file = New File...
+explorer-new-folder = New Folder...
+explorer-upload = Upload File...
+explorer-search = Search in Folder...
+explorer-download = Download...
+explorer-tail = Tail File
+explorer-copy = Copy
+explorer-paste = Paste "{ $name }"
+explorer-rename = Rename / Move...
+explorer-delete = Delete
+explorer-properties = Properties...
+explorer-pin = Pin
+explorer-unpin = Unpin
+explorer-pinned = Pinned
+address-hint = Type an absolute path and press Enter
+address-close = Close
+
+connections-title = Connections
+connections-connected = Connected
+connections-connecting = Connecting
+connections-disconnected = Disconnected
+connections-reconnect = Reconnect
+connections-disconnect = Disconnect
+connections-edit = Edit
+connections-remove = Remove
+connections-add = Add Connection...
+connection-details = Connection Details
+connection-none = No connections.
+connection-transport = Transport
+connection-identity = Server identity
+connection-access = Access
+connection-access-full = Full
+connection-access-read-only = Read-only
+connection-access-elevated = Elevated until { $time }
+connection-elevate = Elevate...
+connection-end-elevation = End Elevation
+connection-collect-diagnostics = Collect Diagnostics
+connection-collect-diagnostics-hint = Bundles the server's system information, recent logs, journal and crash reports and downloads the archive.
+connection-stats = Stats
+connection-waiting = Waiting for data...
+connection-candidate-pair = Candidate pair
+connection-rtt = RTT
+connection-p2p-bytes = P2P sent / received
+connection-retransmits = Retransmits
+connection-relay-bytes = Relay sent / received
+connection-throughput = Relay throughput
+connection-throughput-value = up { $up }/s, down { $down }/s
+connection-outgoing-queue = Outgoing queue
+connection-relay-encryption = Relay encryption
+connection-relay-encrypted = End-to-end
+connection-relay-plain = None
+connection-system = System
+system-loading = Loading system information...
+system-os = Operating system
+system-kernel = Kernel { $kernel } on { $host }
+system-uptime = Uptime
+system-cpu = CPU
+system-cpu-value = { $usage }% of { $cores } cores
+system-load = Load average { $load }
+system-memory = Memory
+system-swap = Swap
+connection-environment = Environment
+connection-detecting-tools = Detecting tools...
+connection-tool-missing = not found
+
+## Editor
+
+editor-empty-title = No file open
+editor-empty-hint = Open a .txt, .py, or .m file from the explorer.
+editor-pane-empty = Select a tab to show it in this pane.
+editor-transport = Transport: { $transport }
+editor-compare = Compare with Remote
+editor-save = Save
+editor-resolve-conflicts = Resolve Conflicts
+lock-held-by-me = Locked by you until { $until }
+lock-held-by-other = Locked by { $owner } since { $since }
+lock-acquire = Lock
+lock-release = Unlock
+tab-show-in-other-pane = Show in Other Pane
+tab-close = Close
+tab-close-named = Close { $title }
+tab-close-others = Close Others
+tab-close-all = Close All
+
+## Bottom panel
+
+bottom-output = Output
+bottom-tasks = Tasks
+bottom-terminal = Terminal
+bottom-services = Services
+bottom-transfers = Transfers
+bottom-search = Search
+bottom-workspace-search = Workspace Search
+bottom-logs = Log Viewer
+bottom-ports = Forwarded Ports
+bottom-processes = Processes
+
+output-level = Show
+output-level-info = All
+output-level-warn = Warnings and errors
+output-level-error = Errors only
+output-info = Info
+output-warn = Warning
+output-error = Error
+output-search = Search
+output-copy = Copy
+output-save = Save...
+output-clear = Clear
+output-general = General
+
+logs-empty = Right-click a remote file in the explorer and choose Tail File to follow it here.
+logs-pause = Pause
+logs-resume = Resume
+logs-clear = Clear
+logs-filter = Filter
+logs-stop = Stop
+logs-restart = Restart
+logs-close = Close
+logs-held = { $count } new lines held while paused
+
+terminal-empty = No terminal open.
+terminal-container = Container: { $name }
+terminal-cwd = Working directory: { $path }
+terminal-expand-all = Expand all
+terminal-collapse-all = Collapse all
+terminal-clear = Clear
+terminal-copy = Copy
+terminal-rerun = Re-run
+terminal-command = Command
+terminal-command-for = Command for { $title }
+terminal-history = Command history (Ctrl+R)
+terminal-history-title = History of { $title }
+terminal-history-hint = Search commands
+terminal-history-empty = No matching commands
+
+services-filter = Filter
+services-empty = Select a connection to list its services.
+service-start = Start
+service-stop = Stop
+service-restart = Restart
+
+transfer-clear-finished = Clear Finished
+transfer-empty = No transfers. Use the explorer context menu on a remote file or folder, or drop files onto a remote folder.
+transfer-chunk = chunk { $size } x{ $window }
+transfer-rtt = RTT { $ms } ms
+transfer-rtt-unknown = RTT unknown
+transfer-queued = Queued
+transfer-done = Done
+transfer-cancelled = Cancelled
+transfer-failed = Failed
+
+search-folder = Folder
+search-pattern = Find
+search-regex = Regex
+search-match-case = Match case
+search-replace-mode = Replace
+search-replacement = Replace with
+search-run = Search
+search-replace-selected = Replace Selected ({ $count })
+search-preview-stale = Search again to preview the new replacement.
+search-empty = Pick a connection and folder, or right-click a remote folder and choose Search in Folder...
+search-no-results = No matches.
+search-truncated = Showing the first { $count } matches. Narrow the search to see the rest.
+search-summary =
+    Replaced { $matches ->
+        [one] 1 match
+       *[other] { $matches } matches
+    } in { $files ->
+        [one] 1 file
+       *[other] { $files } files
+    }.
+
+workspace-search-empty = Search every project folder at once. Type a pattern and press Enter.
+workspace-search-summary =
+    { $matches ->
+        [one] 1 match
+       *[other] { $matches } matches
+    } in { $files ->
+        [one] 1 file
+       *[other] { $files } files
+    } across { $folders ->
+        [one] 1 folder
+       *[other] { $folders } folders
+    }
+workspace-search-local = local
+workspace-search-searching = searching...
+workspace-search-matches =
+    { $count ->
+        [one] 1 match
+       *[other] { $count } matches
+    }
+workspace-search-failed = failed
+workspace-search-cancelled = cancelled
+workspace-search-truncated = Only the first matches of this folder are shown.
+
+command-category-macro = Macro
+command-run-macro = Run macro { $name }
+command-category-connection = Connection
+command-reconnect = Connect to { $name }
+command-collect-diagnostics = Collect diagnostics from { $name }
+command-run-task = Run task { $name }
+command-run-config = Run { $name }
+command-language = Switch Language to { $language }
+command-show-panel = Show { $panel } Panel
+command-palette-entry = { $category }: { $command }
+command-palette-title = Command Palette
+command-palette-hint = Type a command
+command-palette-no-matches = No matching commands.
+
+merge-title = Merge { $path }
+merge-summary = { $count } conflicts, { $unresolved } unresolved. Changes made on only one side are already merged.
+merge-base = Base
+merge-mine = Mine
+merge-theirs = Theirs
+merge-take-mine = Take Mine
+merge-take-theirs = Take Theirs
+merge-take-both = Take Both
+merge-take-base = Take Base
+merge-result = Result
+merge-unresolved = Unresolved
+merge-apply = Apply
+merge-apply-save = Apply and Save
+
+macros-title = Macros
+macros-empty = No macros yet. Record one to replay a sequence of commands.
+macros-steps = { $count ->
+        [one] 1 step
+       *[other] { $count } steps
+    }
+macros-shortcut-hint = e.g. Ctrl+Alt+1
+macros-run = Run
+macros-delete = Delete
+macros-record = Record
+macros-stop-recording = Stop Recording
+macros-import = Import...
+macros-export = Export...
+
+ports-listen-port = Listen port
+ports-target = Target
+ports-add = Forward
+ports-empty = No forwarded ports. Pick a connection, a direction and the target to forward a TCP port through the session.
+ports-local = Local: listen here, connect from the server
+ports-remote = Remote: listen on the server, connect from here
+ports-stopped = Stopped
+ports-waiting = Waiting for connection
+ports-active = { $count } open
+ports-failed = Failed
+ports-last-error = Last error: { $error }
+ports-start = Start
+ports-stop = Stop
+
+processes-filter = Filter
+processes-empty = Select a connection to list the processes running on its server.
+processes-pid = PID
+processes-name = Name
+processes-cpu = CPU
+processes-memory = Memory
+processes-status = Status
+processes-watch = Watch
+processes-unwatch = Stop Watching
+processes-watch-status = CPU { $cpu }%, memory { $memory }, { $status }
+processes-exited = Exited
+processes-end = End
+processes-end-hint = Ask the process to terminate (SIGTERM)
+processes-kill = Kill
+processes-kill-hint = Kill the process immediately (SIGKILL)
@@ -0,0 +1,32 @@
+use eframe::egui;
+use egui::accesskit::Role;
+
+use super::i18n::Localizer;
+
+/// Small `v`/`>` toggle that screen readers announce as "Expand <name>"/"Collapse <name>".
+pub fn expander(ui: &mut egui::Ui, i18n: &Localizer, open: bool, name: &str) -> egui::Response {
+    let response = ui.small_button(if open { "v" } else { ">" });
+    let label = i18n.tr_args(
+        if open { "tree-collapse" } else { "tree-expand" },
+        &[("name", name.into())],
+    );
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, &label));
+    response
+}
+
+/// Exposes an explorer row as a tree item; `expanded` is `None` for files.
+pub fn tree_item(response: &egui::Response, expanded: Option<bool>) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_role(Role::TreeItem);
+        if let Some(expanded) = expanded {
+            node.set_expanded(expanded);
+        }
+    });
+}
+
+/// Button whose visible text is a glyph; `label` is what assistive technologies read.
+pub fn icon_button(ui: &mut egui::Ui, icon: &str, label: &str) -> egui::Response {
+    let response = ui.small_button(icon).on_hover_text(label);
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, label));
+    response
+}
@@ -1,33 +1,63 @@
 use std::fs;
 use std::path::PathBuf;
+use std::time::Instant;
 
+use rs_peer_workspace_core::{
+    credentials, spawn_connection, spawn_connection_test, spawn_discovery, spawn_server_listing,
+    ConnectionCommand, ConnectionEvent,
+};
 use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use rs_peer_workspace_shared::content::FileData;
+use rs_peer_workspace_shared::history::CommandHistory;
 use rs_peer_workspace_shared::project::{
-    default_connection_form_addr, display_name_for_path, EditorSource, FolderSource,
-    ProjectConnection, ProjectFile, ProjectFolder, TerminalTab,
+    default_connection_form_addr, display_name_for_path, EditorSource, FolderSource, IceServer,
+    OutboundProxy, ProjectConnection, ProjectFile, ProjectFolder, TerminalBlock, TerminalProfile,
+    TerminalTab,
 };
+use rs_peer_workspace_shared::secrets::seal_credentials;
 use uuid::Uuid;
 
-use crate::net::{spawn_connection, ConnectionCommand, ConnectionEvent};
-
+use super::env::clean_env;
+use super::notify::{take_bell, CommandOutcome};
 use super::state::WorkspaceApp;
-use super::tree::tree_from_entry;
-use super::types::{BottomTab, ConnectionForm, ConnectionState, FolderForm, PendingAction, TerminalForm};
+use super::tree::{tree_from_entry, DirectoryLoads};
+use super::transfers::format_bytes;
+use super::types::{
+    Banner, BottomTab, ConnectionForm, ConnectionState, FolderForm, JournalOperation, NotebookCell, PassphraseForm,
+    PathCompletions, PendingAction, TaskKind, TerminalForm, ViewerContent,
+};
+
+const CONTAINER_LOG_LINES: usize = 200;
 
 impl WorkspaceApp {
     pub fn reset_project(&mut self) {
         self.disconnect_all();
+        self.fail_transfers(None, "project closed");
         self.project = ProjectFile::default();
         self.project_path = None;
+        self.project_passphrase = None;
         self.pending.clear();
         self.explorer_cache.clear();
+        self.read_ahead.clear();
         self.explorer_expanded.clear();
+        self.directory_loads = DirectoryLoads::default();
+        self.journal.clear();
+        self.copy_source = None;
+        self.path_completions = PathCompletions::default();
         self.open_files.clear();
         self.selected_editor = None;
+        self.secondary_editor = None;
         self.terminals.clear();
         self.selected_terminal = None;
+        self.session_restore.clear();
+        self.file_locks.clear();
+        self.diff_view = None;
+        self.merge_view = None;
+        self.log_views.clear();
+        self.selected_log = 0;
+        self.restore_selected_editor = None;
         self.connections.clear();
-        self.output_lines.push("Created new project.".to_string());
+        self.output.info("Created new project.");
     }
 
     fn disconnect_all(&mut self) {
@@ -36,39 +66,157 @@ impl WorkspaceApp {
         }
     }
 
+    fn start_connection(&mut self, connection: ProjectConnection) {
+        let command_tx = spawn_connection(connection.clone(), self.event_tx.clone());
+        let connect_task = Uuid::new_v4();
+        self.task_manager.start(
+            connect_task,
+            TaskKind::Connect,
+            format!("[{}] connecting", connection.name),
+        );
+        self.connections.insert(
+            connection.name,
+            ConnectionState {
+                command_tx,
+                connected: false,
+                transport: "Connecting".to_string(),
+                stats: None,
+                tools: None,
+                access: None,
+                connect_task,
+                system: None,
+            },
+        );
+    }
+
+    /// Whether the connection task for `name` has ended and can be started again.
+    pub fn connection_closed(&self, name: &str) -> bool {
+        self.connections
+            .get(name)
+            .is_none_or(|state| !state.connected && state.transport == "Disconnected")
+    }
+
+    pub fn reconnect(&mut self, name: &str) {
+        if !self.connection_closed(name) {
+            return;
+        }
+        if let Some(connection) = self
+            .project
+            .connections
+            .iter()
+            .find(|connection| connection.name == name)
+            .cloned()
+        {
+            self.start_connection(connection);
+        }
+    }
+
+    /// Ends the connection task for `name`. The task stops without reporting back, so
+    /// the connection is marked closed here.
+    pub fn disconnect(&mut self, name: &str) {
+        if let Some(state) = self.connections.get(name) {
+            let _ = state.command_tx.send(ConnectionCommand::Disconnect);
+            self.connection_lost(name, "disconnected");
+        }
+    }
+
+    /// Disconnects `name` and removes it, with the folders opened and the paths
+    /// pinned through it, from the project.
+    pub fn remove_connection(&mut self, name: &str) {
+        self.disconnect(name);
+        self.fail_transfers(Some(name), "connection removed");
+        self.connections.remove(name);
+        self.project.connections.retain(|connection| connection.name != name);
+        self.project.folders.retain(|folder| {
+            !matches!(&folder.source, FolderSource::Remote { connection_name, .. } if connection_name == name)
+        });
+        self.project
+            .pinned
+            .retain(|pinned| pinned.source.connection_name() != Some(name));
+        self.output.connection_info(name, "removed from the project");
+    }
+
+    /// Adds the connection in the form, or saves the one being edited.
     pub fn add_connection(&mut self) {
+        let editing = self.connection_form.editing.clone();
         let name = self.connection_form.name.trim();
         if name.is_empty() {
-            self.output_lines.push("Connection name is required.".to_string());
+            self.output.warn("Connection name is required.");
+            return;
+        }
+        if editing.is_none() && self.project.connections.iter().any(|item| item.name == name) {
+            self.output
+                .warn(format!("A connection named {name} already exists; use Edit to change it."));
             return;
         }
         if self.connection_form.proxy_addr.trim().is_empty() {
-            self.output_lines.push("Proxy address is required.".to_string());
+            self.output.warn("Proxy address is required.");
             return;
         }
 
-        let connection = ProjectConnection {
+        let outbound_proxy = match self.form_outbound_proxy() {
+            Ok(outbound_proxy) => outbound_proxy,
+            Err(err) => {
+                self.output.warn(err);
+                return;
+            }
+        };
+
+        let ice_servers = match self.form_ice_servers() {
+            Ok(ice_servers) => ice_servers,
+            Err(err) => {
+                self.output.warn(err);
+                return;
+            }
+        };
+
+        let existing = self.project.connections.iter().find(|item| item.name == name);
+        let terminal_profiles = existing
+            .map(|item| item.terminal_profiles.clone())
+            .unwrap_or_default();
+        let existing_ref = existing.and_then(|item| item.credential_ref.clone());
+        let server_name = self.connection_form.server_name.trim().to_string();
+        let server_fingerprint = existing
+            .filter(|item| item.server_name == server_name)
+            .and_then(|item| item.server_fingerprint.clone());
+        let mut connection = ProjectConnection {
             name: name.to_string(),
             proxy_addr: self.connection_form.proxy_addr.trim().to_string(),
+            proxy_username: self.connection_form.proxy_username.trim().to_string(),
             proxy_password: self.connection_form.proxy_password.clone(),
-            server_name: self.connection_form.server_name.trim().to_string(),
+            server_name,
             server_password: self.connection_form.server_password.clone(),
             prefer_p2p: self.connection_form.prefer_p2p,
+            require_e2e: self.connection_form.require_e2e,
+            credential_ref: None,
+            server_fingerprint,
+            terminal_profiles,
+            outbound_proxy,
+            ice_servers,
+            env: clean_env(&self.connection_form.env),
         };
+        if self.connection_form.use_keyring {
+            connection.credential_ref =
+                Some(existing_ref.clone().unwrap_or_else(credentials::new_reference));
+            if let Err(err) = credentials::store(&connection) {
+                self.output.error(format!(
+                    "Failed to store credentials in system keyring: {err}; keeping them in the project file."
+                ));
+                connection.credential_ref = None;
+            }
+        }
+        if let Some(reference) = existing_ref
+            && connection.credential_ref.as_ref() != Some(&reference)
+        {
+            credentials::delete(&reference);
+        }
 
-        self.project.connections.retain(|item| item.name != connection.name);
-        self.project.connections.push(connection.clone());
-
-        let command_tx = spawn_connection(connection.clone(), self.event_tx.clone());
-        self.connections.insert(
-            connection.name.clone(),
-            ConnectionState {
-                command_tx,
-                connected: false,
-                transport: "Connecting".to_string(),
-            },
-        );
-        self.task_lines.push(format!("[{}] connecting...", connection.name));
+        if editing.is_some() {
+            self.update_connection(connection);
+        } else {
+            self.project.connections.push(connection.clone());
+            self.start_connection(connection);
+        }
         self.connection_form = ConnectionForm {
             proxy_addr: default_connection_form_addr(),
             prefer_p2p: true,
@@ -76,13 +224,165 @@ impl WorkspaceApp {
         };
     }
 
+    /// Replaces the project's settings for `connection`. A running connection whose
+    /// settings changed is disconnected and started again with the new ones.
+    fn update_connection(&mut self, connection: ProjectConnection) {
+        let Some(existing) = self
+            .project
+            .connections
+            .iter_mut()
+            .find(|item| item.name == connection.name)
+        else {
+            return;
+        };
+        if *existing == connection {
+            return;
+        }
+        *existing = connection.clone();
+        self.output
+            .connection_info(&connection.name, "connection settings updated");
+        if !self.connection_closed(&connection.name) {
+            self.disconnect(&connection.name);
+            self.start_connection(connection);
+        }
+    }
+
+    pub fn list_proxy_servers(&mut self) {
+        let proxy_addr = self.connection_form.proxy_addr.trim().to_string();
+        if proxy_addr.is_empty() {
+            self.output.warn("Proxy address is required.");
+            return;
+        }
+        let outbound_proxy = match self.form_outbound_proxy() {
+            Ok(outbound_proxy) => outbound_proxy,
+            Err(err) => {
+                self.output.warn(err);
+                return;
+            }
+        };
+        self.connection_form.loading_servers = true;
+        self.connection_form.servers.clear();
+        self.connection_form.servers_error = None;
+        spawn_server_listing(
+            proxy_addr,
+            self.connection_form.proxy_username.trim().to_string(),
+            self.connection_form.proxy_password.clone(),
+            outbound_proxy,
+            self.event_tx.clone(),
+        );
+    }
+
+    /// Scans the LAN for proxies; results arrive as [`ConnectionEvent::ProxiesDiscovered`].
+    pub fn discover_proxies(&mut self) {
+        if !self.connection_form.discovering {
+            self.connection_form.discovering = true;
+            spawn_discovery(self.event_tx.clone());
+        }
+    }
+
+    /// Runs the proxy handshake with the form's settings; the outcome is shown in the
+    /// dialog once [`ConnectionEvent::ConnectionTested`] arrives.
+    pub fn test_connection_form(&mut self) {
+        let form = &self.connection_form;
+        if form.proxy_addr.trim().is_empty() || form.server_name.trim().is_empty() {
+            self.output.warn("Proxy address and server name are required.");
+            return;
+        }
+        let (outbound_proxy, ice_servers) = match self
+            .form_outbound_proxy()
+            .and_then(|outbound_proxy| Ok((outbound_proxy, self.form_ice_servers()?)))
+        {
+            Ok(settings) => settings,
+            Err(err) => {
+                self.output.warn(err);
+                return;
+            }
+        };
+        let form = &self.connection_form;
+        let connection = ProjectConnection {
+            name: form.name.trim().to_string(),
+            proxy_addr: form.proxy_addr.trim().to_string(),
+            proxy_username: form.proxy_username.trim().to_string(),
+            proxy_password: form.proxy_password.clone(),
+            server_name: form.server_name.trim().to_string(),
+            server_password: form.server_password.clone(),
+            prefer_p2p: form.prefer_p2p,
+            require_e2e: form.require_e2e,
+            credential_ref: None,
+            server_fingerprint: None,
+            terminal_profiles: Vec::new(),
+            outbound_proxy,
+            ice_servers,
+            env: Vec::new(),
+        };
+        self.connection_form.testing = true;
+        self.connection_form.test_result = None;
+        spawn_connection_test(connection, self.event_tx.clone());
+    }
+
+    fn form_outbound_proxy(&self) -> Result<Option<OutboundProxy>, String> {
+        let form = &self.connection_form;
+        let Some(kind) = form.outbound_kind else {
+            return Ok(None);
+        };
+        let addr = form.outbound_addr.trim();
+        if addr.is_empty() {
+            return Err("Outbound proxy address is required.".to_string());
+        }
+        let mut proxy = OutboundProxy::parse(&format!("{}://{addr}", kind.scheme()))
+            .map_err(|err| format!("Invalid outbound proxy: {err}"))?;
+        proxy.username = form.outbound_username.trim().to_string();
+        proxy.password = form.outbound_password.clone();
+        Ok(Some(proxy))
+    }
+
+    /// ICE servers in the form, without the rows whose URL was left empty.
+    fn form_ice_servers(&self) -> Result<Vec<IceServer>, String> {
+        self.connection_form
+            .ice_servers
+            .iter()
+            .filter(|server| !server.url.trim().is_empty())
+            .map(|server| {
+                let server = IceServer {
+                    url: server.url.trim().to_string(),
+                    username: server.username.trim().to_string(),
+                    password: server.password.clone(),
+                };
+                server
+                    .validate()
+                    .map(|()| server)
+                    .map_err(|err| format!("Invalid ICE server: {err}"))
+            })
+            .collect()
+    }
+
+    pub fn send_broadcast(&mut self) {
+        let connection_name = self.broadcast_form.connection_name.clone();
+        let message = self.broadcast_form.message.trim().to_string();
+        if connection_name.is_empty() || message.is_empty() {
+            self.output
+                .warn("Broadcast requires a connection and a message.");
+            return;
+        }
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, PendingAction::SendBroadcast);
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::Broadcast { message },
+            },
+        );
+        self.broadcast_form.message.clear();
+    }
+
     pub fn add_folder(&mut self) {
         let folder = if self.folder_form.is_remote {
             if self.folder_form.remote_connection_name.trim().is_empty()
                 || self.folder_form.remote_path.trim().is_empty()
             {
-                self.output_lines
-                    .push("Remote folder requires connection and path.".to_string());
+                self.output
+                    .warn("Remote folder requires connection and path.");
                 return;
             }
             ProjectFolder {
@@ -95,10 +395,12 @@ impl WorkspaceApp {
                     connection_name: self.folder_form.remote_connection_name.clone(),
                     path: self.folder_form.remote_path.clone(),
                 },
+                terminal_profile: Some(self.folder_form.terminal_profile.clone())
+                    .filter(|name| !name.is_empty()),
             }
         } else {
             if self.folder_form.local_path.trim().is_empty() {
-                self.output_lines.push("Local folder path is required.".to_string());
+                self.output.warn("Local folder path is required.");
                 return;
             }
             ProjectFolder {
@@ -110,6 +412,7 @@ impl WorkspaceApp {
                 source: FolderSource::Local {
                     path: self.folder_form.local_path.clone(),
                 },
+                terminal_profile: None,
             }
         };
 
@@ -121,44 +424,213 @@ impl WorkspaceApp {
 
     pub fn create_terminal(&mut self) {
         if self.terminal_form.connection_name.is_empty() {
-            self.output_lines
-                .push("Select a connection for the terminal.".to_string());
+            self.output.warn("Select a connection for the terminal.");
             return;
         }
 
+        let profile = self.find_terminal_profile(
+            &self.terminal_form.connection_name,
+            &self.terminal_form.profile_name,
+        );
+        let container = Some(self.terminal_form.container.clone()).filter(|name| !name.is_empty());
+        let mut title = match &profile {
+            Some(profile) => format!("Terminal {} ({})", self.terminals.len() + 1, profile.name),
+            None => format!("Terminal {}", self.terminals.len() + 1),
+        };
+        if let Some(container) = &container {
+            title.push_str(&format!(" [{container}]"));
+        }
+
         self.terminals.push(TerminalTab {
             id: Uuid::new_v4(),
             connection_name: self.terminal_form.connection_name.clone(),
-            title: format!("Terminal {}", self.terminals.len() + 1),
+            title,
             input: String::new(),
-            output: String::new(),
+            blocks: Vec::new(),
+            profile,
+            container,
+            history: CommandHistory::default(),
         });
         self.selected_terminal = Some(self.terminals.len() - 1);
         self.active_bottom_tab = BottomTab::Terminal;
         self.terminal_form = TerminalForm::default();
     }
 
-    pub fn run_terminal(&mut self, terminal_index: usize, command: String) {
-        let Some(terminal) = self.terminals.get_mut(terminal_index) else {
+    /// Opens a terminal in `path` using the folder's default profile, if it has one.
+    pub fn open_folder_terminal(&mut self, folder: &ProjectFolder, path: &str) {
+        let FolderSource::Remote { connection_name, .. } = &folder.source else {
+            return;
+        };
+        let mut profile = folder
+            .terminal_profile
+            .as_ref()
+            .and_then(|name| self.find_terminal_profile(connection_name, name))
+            .unwrap_or_default();
+        profile.cwd = Some(path.to_string());
+        let title = if profile.name.is_empty() {
+            format!("Terminal {} @ {}", self.terminals.len() + 1, display_name_for_path(path))
+        } else {
+            format!(
+                "Terminal {} ({}) @ {}",
+                self.terminals.len() + 1,
+                profile.name,
+                display_name_for_path(path)
+            )
+        };
+
+        self.terminals.push(TerminalTab {
+            id: Uuid::new_v4(),
+            connection_name: connection_name.clone(),
+            title,
+            input: String::new(),
+            blocks: Vec::new(),
+            profile: Some(profile),
+            container: None,
+            history: CommandHistory::default(),
+        });
+        self.selected_terminal = Some(self.terminals.len() - 1);
+        self.active_bottom_tab = BottomTab::Terminal;
+    }
+
+    pub fn set_folder_profile(&mut self, root_path: &str, profile: Option<String>) {
+        if let Some(folder) = self.project.folders.iter_mut().find(|folder| {
+            matches!(&folder.source, FolderSource::Remote { path, .. } if path == root_path)
+        }) {
+            folder.terminal_profile = profile;
+        }
+    }
+
+    pub fn find_terminal_profile(&self, connection_name: &str, name: &str) -> Option<TerminalProfile> {
+        self.project
+            .connections
+            .iter()
+            .find(|connection| connection.name == connection_name)?
+            .terminal_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+    }
+
+    pub fn load_terminal_containers(&mut self) {
+        let connection_name = self.terminal_form.connection_name.clone();
+        if connection_name.is_empty() {
+            return;
+        }
+        self.terminal_form.loading_containers = true;
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadTerminalContainers {
+                connection_name: connection_name.clone(),
+            },
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListContainers,
+            },
+        );
+    }
+
+    pub fn load_container_logs(&mut self, terminal_index: usize) {
+        let Some(terminal) = self.terminals.get(terminal_index) else {
+            return;
+        };
+        let Some(container) = terminal.container.clone() else {
             return;
         };
-        terminal.output.push_str(&format!("> {command}\n"));
         let connection_name = terminal.connection_name.clone();
         let terminal_id = terminal.id;
+        let mut block = TerminalBlock::new(format!("logs {container}"));
+        block.rerunnable = false;
+        let block_id = block.id;
+        if let Some(terminal) = self.terminals.get_mut(terminal_index) {
+            terminal.blocks.push(block);
+        }
         let request_id = Uuid::new_v4();
         self.pending.insert(
             request_id,
-            PendingAction::RunTerminal {
+            PendingAction::LoadContainerLogs {
                 terminal_id,
+                block_id,
             },
         );
         self.send_rpc(
             &connection_name,
             RpcRequest {
                 request_id,
-                action: RpcAction::RunCommand { command },
+                action: RpcAction::ContainerLogs {
+                    container,
+                    lines: CONTAINER_LOG_LINES,
+                },
+            },
+        );
+    }
+
+    fn terminal_block_mut(&mut self, terminal_id: Uuid, block_id: Uuid) -> Option<&mut TerminalBlock> {
+        self.terminals
+            .iter_mut()
+            .find(|term| term.id == terminal_id)?
+            .blocks
+            .iter_mut()
+            .find(|block| block.id == block_id)
+    }
+
+    pub fn run_terminal(&mut self, terminal_index: usize, command: String) {
+        let Some(terminal) = self.terminals.get(terminal_index) else {
+            return;
+        };
+        if terminal.container.is_none()
+            && let Some(tool) = self.missing_tool(&terminal.connection_name, &command)
+        {
+            self.task_manager.note(format!(
+                "[{}] warning: {tool} was not detected on this server",
+                terminal.connection_name
+            ));
+        }
+        let Some(terminal) = self.terminals.get_mut(terminal_index) else {
+            return;
+        };
+        terminal.history.push(&command);
+        let block = TerminalBlock::new(command.clone());
+        let block_id = block.id;
+        terminal.blocks.push(block);
+        let connection_name = terminal.connection_name.clone();
+        let terminal_id = terminal.id;
+        let container = terminal.container.clone();
+        let profile = terminal.profile.clone().unwrap_or_default();
+        let command = if profile.startup_commands.is_empty() {
+            command
+        } else {
+            format!("{}\n{command}", profile.startup_commands.join("\n"))
+        };
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::RunTerminal {
+                terminal_id,
+                block_id,
+                started: Instant::now(),
             },
         );
+        let env = self.command_env(&connection_name, &profile.env);
+        let action = match container {
+            Some(container) => RpcAction::ExecInContainer {
+                container,
+                command,
+                shell: profile.shell,
+                cwd: profile.cwd,
+                env,
+            },
+            None => RpcAction::RunCommand {
+                command,
+                shell: profile.shell,
+                cwd: profile.cwd,
+                env,
+            },
+        };
+        self.send_rpc(&connection_name, RpcRequest { request_id, action });
         self.active_bottom_tab = BottomTab::Tasks;
     }
 
@@ -168,31 +640,57 @@ impl WorkspaceApp {
             .and_then(|text| ron::from_str::<ProjectFile>(&text).ok());
 
         match loaded {
+            Some(project) if project.encrypted_credentials.is_some() => {
+                self.passphrase_form = PassphraseForm {
+                    open: true,
+                    locked_project: Some((path, project)),
+                    ..Default::default()
+                };
+            }
             Some(project) => {
-                self.reset_project();
-                self.project = project;
-                self.project_path = Some(path.clone());
-                for connection in self.project.connections.clone() {
-                    let command_tx = spawn_connection(connection.clone(), self.event_tx.clone());
-                    self.connections.insert(
-                        connection.name.clone(),
-                        ConnectionState {
-                            command_tx,
-                            connected: false,
-                            transport: "Connecting".to_string(),
-                        },
+                let plaintext = project.connections.iter().any(|connection| {
+                    !connection.proxy_password.is_empty()
+                        || !connection.server_password.is_empty()
+                        || connection.env.iter().any(|var| var.secret && !var.value.is_empty())
+                }) || project
+                    .run_configs
+                    .iter()
+                    .any(|config| config.env.iter().any(|var| var.secret && !var.value.is_empty()));
+                self.load_project(path, project, None);
+                if plaintext {
+                    self.output.warn(
+                        "Project stores passwords in plaintext; use File -> Encrypt Credentials to protect them.",
                     );
                 }
-                self.output_lines
-                    .push(format!("Opened project {}", path.display()));
             }
             None => {
-                self.output_lines
-                    .push(format!("Failed to open project {}", path.display()));
+                self.output
+                    .error(format!("Failed to open project {}", path.display()));
             }
         }
     }
 
+    pub fn load_project(&mut self, path: PathBuf, project: ProjectFile, passphrase: Option<String>) {
+        self.reset_project();
+        self.project = project;
+        self.project_path = Some(path.clone());
+        self.project_passphrase = passphrase;
+        for connection in &mut self.project.connections {
+            if let Err(err) = credentials::load(connection) {
+                self.output.connection_error(
+                    &connection.name,
+                    format!("failed to read credentials from system keyring: {err}"),
+                );
+            }
+        }
+        for connection in self.project.connections.clone() {
+            self.start_connection(connection);
+        }
+        self.restore_session();
+        self.output
+            .info(format!("Opened project {}", path.display()));
+    }
+
     pub fn save_project(&mut self) {
         if self.project_path.is_none() {
             if let Some(path) = rfd::FileDialog::new()
@@ -206,49 +704,119 @@ impl WorkspaceApp {
             }
         }
 
+        self.project.session = self.capture_session();
+        let project = credentials::strip(&self.project);
+        let project = match &self.project_passphrase {
+            Some(passphrase) => match seal_credentials(&project, passphrase) {
+                Ok(project) => project,
+                Err(err) => {
+                    self.output
+                        .error(format!("Failed to encrypt credentials: {err}"));
+                    return;
+                }
+            },
+            None => project,
+        };
+
         if let Some(path) = &self.project_path {
-            match ron::ser::to_string_pretty(&self.project, ron::ser::PrettyConfig::default()) {
+            match ron::ser::to_string_pretty(&project, ron::ser::PrettyConfig::default()) {
                 Ok(content) => match fs::write(path, content) {
                     Ok(()) => self
-                        .output_lines
-                        .push(format!("Saved project {}", path.display())),
-                    Err(err) => self
-                        .output_lines
-                        .push(format!("Failed to save project: {err}")),
+                        .output
+                        .info(format!("Saved project {}", path.display())),
+                    Err(err) => self.output.error(format!("Failed to save project: {err}")),
                 },
                 Err(err) => self
-                    .output_lines
-                    .push(format!("Failed to serialize project: {err}")),
+                    .output
+                    .error(format!("Failed to serialize project: {err}")),
             }
         }
     }
 
     pub fn save_active_editor(&mut self) {
-        let Some(idx) = self.selected_editor else {
+        if let Some(idx) = self.active_editor() {
+            self.save_editor(idx);
+        }
+    }
+
+    pub fn save_all_editors(&mut self) {
+        let dirty: Vec<usize> = (0..self.open_files.len())
+            .filter(|idx| self.open_files[*idx].dirty)
+            .collect();
+        self.save_editors(dirty);
+    }
+
+    /// Saves several editors, tracked as one task when there is more than one.
+    pub fn save_editors(&mut self, indices: Vec<usize>) {
+        let task = (indices.len() > 1).then(Uuid::new_v4);
+        if let Some(task) = task {
+            self.task_manager.start_steps(
+                task,
+                TaskKind::Save,
+                format!("Saving {} files", indices.len()),
+                indices.len(),
+            );
+        }
+        for idx in indices {
+            self.save_editor_step(idx, task);
+        }
+    }
+
+    pub fn save_editor(&mut self, idx: usize) {
+        self.save_editor_step(idx, None);
+    }
+
+    /// Saves one editor and reports the outcome as a step of `task`.
+    fn save_editor_step(&mut self, idx: usize, task: Option<Uuid>) {
+        let Some(tab) = self.open_files.get(idx) else {
             return;
         };
+        if tab.media.is_some() {
+            if let Some(task) = task {
+                self.task_manager.step(task, None);
+            }
+            return;
+        }
+        if self.project.formatting.format_on_save && self.format_before_save(idx, task) {
+            return;
+        }
+        self.write_editor(idx, task);
+    }
+
+    /// Writes an editor's content to its file.
+    pub fn write_editor(&mut self, idx: usize, task: Option<Uuid>) {
         let Some(tab) = self.open_files.get(idx).cloned() else {
             return;
         };
-
         match tab.source {
-            EditorSource::Local => match fs::write(&tab.path, &tab.content) {
-                Ok(()) => {
-                    if let Some(open_tab) = self.open_files.get_mut(idx) {
-                        open_tab.dirty = false;
+            EditorSource::Local => {
+                let error = match fs::write(&tab.path, &tab.content) {
+                    Ok(()) => {
+                        if let Some(open_tab) = self.open_files.get_mut(idx) {
+                            open_tab.dirty = false;
+                        }
+                        self.output.info(format!("Saved {}", tab.path));
+                        self.refresh_git_for(None, &tab.path);
+                        None
+                    }
+                    Err(err) => {
+                        let message = format!("Failed to save {}: {err}", tab.path);
+                        self.output.error(message.clone());
+                        Some(message)
                     }
-                    self.output_lines.push(format!("Saved {}", tab.path));
+                };
+                if let Some(task) = task {
+                    self.task_manager.step(task, error);
                 }
-                Err(err) => self
-                    .output_lines
-                    .push(format!("Failed to save {}: {err}", tab.path)),
-            },
+            }
             EditorSource::Remote { connection_name } => {
                 let request_id = Uuid::new_v4();
                 self.pending.insert(
                     request_id,
                     PendingAction::SaveRemoteFile {
                         path: tab.path.clone(),
+                        content: tab.content.clone(),
+                        task,
                     },
                 );
                 self.send_rpc(
@@ -258,6 +826,7 @@ impl WorkspaceApp {
                         action: RpcAction::WriteFile {
                             path: tab.path.clone(),
                             content: tab.content.clone(),
+                            expected_hash: tab.remote_hash.clone(),
                         },
                     },
                 );
@@ -267,8 +836,8 @@ impl WorkspaceApp {
 
     pub fn send_rpc(&mut self, connection_name: &str, request: RpcRequest) {
         let Some(connection) = self.connections.get(connection_name) else {
-            self.output_lines
-                .push(format!("Unknown connection {connection_name}"));
+            self.output
+                .error(format!("Unknown connection {connection_name}"));
             return;
         };
         let _ = connection.command_tx.send(ConnectionCommand::SendRpc(request));
@@ -280,9 +849,7 @@ impl WorkspaceApp {
                 ConnectionEvent::Status {
                     connection_name,
                     message,
-                } => self
-                    .output_lines
-                    .push(format!("[{connection_name}] {message}")),
+                } => self.output.connection_info(&connection_name, message),
                 ConnectionEvent::Transport {
                     connection_name,
                     message,
@@ -290,81 +857,406 @@ impl WorkspaceApp {
                     if let Some(connection) = self.connections.get_mut(&connection_name) {
                         connection.transport = message.clone();
                     }
-                    self.output_lines
-                        .push(format!("[{connection_name}] transport: {message}"));
+                    self.output
+                        .connection_info(&connection_name, format!("transport: {message}"));
+                }
+                ConnectionEvent::ServerIdentity {
+                    connection_name,
+                    fingerprint,
+                } => {
+                    if let Some(connection) = self
+                        .project
+                        .connections
+                        .iter_mut()
+                        .find(|connection| connection.name == connection_name)
+                        && connection.server_fingerprint.is_none()
+                    {
+                        connection.server_fingerprint = Some(fingerprint.clone());
+                        self.output.connection_warn(
+                            &connection_name,
+                            format!(
+                                "trusting server identity {fingerprint} on first use; save the project to pin it"
+                            ),
+                        );
+                    }
                 }
                 ConnectionEvent::Connected { connection_name } => {
                     if let Some(connection) = self.connections.get_mut(&connection_name) {
                         connection.connected = true;
+                        self.task_manager.complete(connection.connect_task, None);
                     }
-                    self.task_lines.push(format!("[{connection_name}] connected"));
+                    self.detect_tools(&connection_name);
+                    self.send_access_request(&connection_name, RpcAction::SessionAccess);
+                    self.restore_remote_editors(&connection_name);
+                    self.resume_transfers(&connection_name);
+                    self.start_forwards(&connection_name);
                 }
                 ConnectionEvent::RpcResponse {
                     connection_name,
                     response,
                 } => {
-                    if let Some(action) = self.pending.remove(&response.request_id) {
+                    if self.log_views.iter().any(|view| view.id == response.request_id) {
+                        self.handle_tail_response(response);
+                    } else if self.connections.get(&connection_name).is_some_and(|state| {
+                        state.system.as_ref().is_some_and(|monitor| monitor.id == response.request_id)
+                    }) {
+                        self.handle_system_response(&connection_name, response);
+                    } else if self
+                        .processes
+                        .watch
+                        .as_ref()
+                        .is_some_and(|watch| watch.id == response.request_id)
+                    {
+                        self.handle_watch_response(response);
+                    } else if self.is_language_server(response.request_id) {
+                        self.handle_lsp_response(response);
+                    } else if let Some(action) = self.pending.remove(&response.request_id) {
                         self.handle_rpc_response(&connection_name, action, response);
                     }
                 }
                 ConnectionEvent::Error {
                     connection_name,
                     message,
-                } => self
-                    .output_lines
-                    .push(format!("[{connection_name}] error: {message}")),
+                } => {
+                    self.output
+                        .connection_error(&connection_name, format!("error: {message}"));
+                    self.interrupt_transfers(&connection_name, &message);
+                    self.fail_copies(&connection_name, &message);
+                }
                 ConnectionEvent::Closed {
                     connection_name,
                     reason,
                 } => {
-                    self.output_lines
-                        .push(format!("[{connection_name}] closed: {reason}"));
+                    self.output
+                        .connection_warn(&connection_name, format!("closed: {reason}"));
+                    self.connection_lost(&connection_name, &reason);
+                }
+                ConnectionEvent::Restarted {
+                    connection_name,
+                    reason,
+                } => {
+                    self.output
+                        .connection_warn(&connection_name, format!("{reason}; reconnecting"));
+                    self.active_bottom_tab = BottomTab::Output;
+                    self.connection_lost(&connection_name, &reason);
+                    if let Some(connection) = self.connections.get_mut(&connection_name) {
+                        connection.connect_task = Uuid::new_v4();
+                        self.task_manager.start(
+                            connection.connect_task,
+                            TaskKind::Connect,
+                            format!("[{connection_name}] reconnecting"),
+                        );
+                    }
+                }
+                ConnectionEvent::Broadcast {
+                    connection_name,
+                    message,
+                } => {
+                    self.output
+                        .connection_info(&connection_name, format!("broadcast: {message}"));
+                    self.banners.push(Banner {
+                        connection_name,
+                        message,
+                    });
+                }
+                ConnectionEvent::ServersListed { proxy_addr, result } => {
+                    if self.connection_form.proxy_addr.trim() != proxy_addr {
+                        continue;
+                    }
+                    self.connection_form.loading_servers = false;
+                    match result {
+                        Ok(servers) => self.connection_form.servers = servers,
+                        Err(message) => {
+                            self.output
+                                .error(format!("Failed to list servers on {proxy_addr}: {message}"));
+                            self.connection_form.servers_error = Some(message);
+                        }
+                    }
+                }
+                ConnectionEvent::ProxiesDiscovered { proxies } => {
+                    self.connection_form.discovering = false;
+                    self.connection_form.discovered = proxies;
+                }
+                ConnectionEvent::ConnectionTested {
+                    proxy_addr,
+                    server_name,
+                    result,
+                } => {
+                    let form = &mut self.connection_form;
+                    if form.proxy_addr.trim() == proxy_addr && form.server_name.trim() == server_name {
+                        form.testing = false;
+                        form.test_result = Some(result);
+                    }
+                }
+                ConnectionEvent::TransportStats {
+                    connection_name,
+                    stats,
+                } => {
                     if let Some(connection) = self.connections.get_mut(&connection_name) {
-                        connection.connected = false;
-                        connection.transport = "Disconnected".to_string();
+                        connection.stats = Some(stats);
                     }
                 }
+                ConnectionEvent::Forwards {
+                    connection_name,
+                    forwards,
+                } => {
+                    self.forward_states.insert(connection_name, forwards);
+                }
             }
         }
     }
 
+    /// Marks the connection as disconnected and fails the work that was running on it.
+    fn connection_lost(&mut self, connection_name: &str, reason: &str) {
+        if let Some(connection) = self.connections.get_mut(connection_name) {
+            connection.connected = false;
+            connection.transport = "Disconnected".to_string();
+            connection.system = None;
+            self.task_manager.fail(connection.connect_task, reason.to_string());
+        }
+        self.directory_loads.forget_connection(connection_name);
+        self.read_ahead.forget_connection(connection_name);
+        self.forward_states.remove(connection_name);
+        self.forget_git_status(connection_name);
+        self.forget_language_servers(connection_name);
+        if self.processes.connection_name == connection_name {
+            self.processes.loading = false;
+            self.processes.watch = None;
+        }
+        if self.notebook.connection_name == connection_name {
+            self.notebook.kernel = None;
+            self.notebook.starting = false;
+        }
+        for view in &mut self.log_views {
+            if view.connection_name == connection_name {
+                view.following = false;
+            }
+        }
+        self.interrupt_transfers(connection_name, reason);
+        self.fail_copies(connection_name, reason);
+        self.fail_pipeline_steps(connection_name, reason);
+        self.fail_runs(connection_name, reason);
+    }
+
     pub fn handle_rpc_response(
         &mut self,
         connection_name: &str,
         action: PendingAction,
         response: RpcResponse,
     ) {
+        let request_id = response.request_id;
         match (action, response.result) {
             (
                 PendingAction::OpenRemoteFile {
                     path,
                     title,
                     connection_name,
+                    restore_cursor,
+                    preview,
                 },
-                RpcResult::FileContent { content, .. },
+                RpcResult::FileContent { content, hash, .. },
             ) => {
-                self.open_files.push(rs_peer_workspace_shared::project::EditorTab {
+                if let Some(hash) = &hash {
+                    self.cache_content(&connection_name, &path, hash, &content);
+                }
+                let tab = rs_peer_workspace_shared::project::EditorTab {
                     title,
-                    path,
-                    source: EditorSource::Remote { connection_name },
+                    path: path.clone(),
+                    source: EditorSource::Remote {
+                        connection_name: connection_name.clone(),
+                    },
+                    base: Some(content.clone()),
                     content,
                     dirty: false,
-                });
-                self.selected_editor = Some(self.open_files.len() - 1);
+                    remote_hash: hash,
+                    cursor: restore_cursor.unwrap_or_default(),
+                    restore_cursor: restore_cursor.is_some(),
+                    preview,
+                    media: None,
+                };
+                if restore_cursor.is_some() {
+                    self.open_files.push(tab);
+                    self.select_restored_editor();
+                } else {
+                    self.open_editor(tab);
+                }
+                self.refresh_file_lock(&connection_name, &path);
+            }
+            (action @ PendingAction::OpenRemoteFile { .. }, RpcResult::NotModified { path, hash }) => {
+                match self.content_cache.get(connection_name, &path) {
+                    Some((cached, content)) if cached == hash => {
+                        let response = RpcResponse {
+                            request_id,
+                            result: RpcResult::FileContent {
+                                path,
+                                content,
+                                hash: Some(hash),
+                            },
+                        };
+                        self.handle_rpc_response(connection_name, action, response);
+                    }
+                    _ => {
+                        // The entry was evicted or cleared since the request was sent.
+                        let request_id = Uuid::new_v4();
+                        self.pending.insert(request_id, action);
+                        self.send_rpc(
+                            connection_name,
+                            RpcRequest {
+                                request_id,
+                                action: RpcAction::ReadFile {
+                                    path,
+                                    known_hash: None,
+                                },
+                            },
+                        );
+                    }
+                }
+            }
+            (
+                PendingAction::OpenRemoteFile {
+                    path,
+                    connection_name,
+                    ..
+                },
+                RpcResult::BinaryContent { size, preview, .. },
+            ) => self.open_viewer(
+                &path,
+                Some(connection_name),
+                ViewerContent::Binary { size, preview },
+            ),
+            (PendingAction::ViewRemoteFile { path }, RpcResult::FileContent { content, hash, .. }) => {
+                self.open_viewer(
+                    &path,
+                    Some(connection_name.to_string()),
+                    ViewerContent::Text { content, hash },
+                );
+            }
+            (
+                PendingAction::OpenRemoteFile { path, .. } | PendingAction::ViewRemoteFile { path },
+                RpcResult::TooLarge { size, .. },
+            ) => self.open_viewer(
+                &path,
+                Some(connection_name.to_string()),
+                FileData::TooLarge { size }.into(),
+            ),
+            (PendingAction::LoadViewerPage { path, offset }, result) => {
+                self.show_viewer_page(&path, offset, result);
             }
-            (PendingAction::SaveRemoteFile { path }, RpcResult::WriteComplete { .. }) => {
+            (PendingAction::ViewRemoteFile { path }, RpcResult::BinaryContent { size, preview, .. }) => {
+                self.open_viewer(
+                    &path,
+                    Some(connection_name.to_string()),
+                    ViewerContent::Binary { size, preview },
+                );
+            }
+            (
+                PendingAction::LoadMedia {
+                    path,
+                    connection_name,
+                    kind,
+                    preview,
+                    data,
+                },
+                result,
+            ) => self.handle_media_chunk(connection_name, path, kind, preview, data, result),
+            (PendingAction::LoadAttachment { path, mime, data }, result) => {
+                self.handle_attachment_chunk(connection_name, path, mime, data, result);
+            }
+            (
+                PendingAction::SaveRemoteFile { path, content, task },
+                RpcResult::WriteComplete { hash, .. },
+            ) => {
+                if let Some(task) = task {
+                    self.task_manager.step(task, None);
+                }
+                if let Some(hash) = &hash {
+                    self.cache_content(connection_name, &path, hash, &content);
+                }
                 if let Some(tab) = self.open_files.iter_mut().find(|tab| tab.path == path) {
                     tab.dirty = false;
+                    tab.remote_hash = hash;
+                    tab.base = Some(content);
+                }
+                self.output
+                    .connection_info(connection_name, format!("saved {path}"));
+                self.refresh_file_lock(connection_name, &path);
+                self.refresh_git_for(Some(connection_name), &path);
+                self.lsp_saved(connection_name, &path);
+            }
+            (PendingAction::CreateEntry { folder, parent }, RpcResult::Created { path, is_dir }) => {
+                self.output
+                    .connection_info(connection_name, format!("created {path}"));
+                self.refresh_remote_directory(connection_name, &parent);
+                if !is_dir {
+                    self.open_path(&folder, &path, false);
                 }
-                self.output_lines
-                    .push(format!("[{connection_name}] saved {path}"));
+            }
+            (
+                PendingAction::JournaledOperation,
+                RpcResult::Deleted {
+                    path,
+                    trash_path,
+                    hash,
+                },
+            ) => {
+                self.record_operation(
+                    connection_name,
+                    JournalOperation::Delete {
+                        path,
+                        trash_path,
+                        hash,
+                    },
+                );
+            }
+            (PendingAction::JournaledOperation, RpcResult::Renamed { from, to, hash }) => {
+                self.record_operation(connection_name, JournalOperation::Rename { from, to, hash });
+            }
+            (
+                PendingAction::UndoOperation { entry },
+                RpcResult::Restored { .. } | RpcResult::Renamed { .. },
+            ) => self.finish_undo(entry, Ok(())),
+            (PendingAction::UndoOperation { entry }, RpcResult::Error { message }) => {
+                self.finish_undo(entry, Err(message));
             }
             (
                 PendingAction::LoadRemoteDirectory { path },
                 RpcResult::DirectoryEntries { entries, .. },
             ) => {
+                let expanded: Vec<String> = entries
+                    .iter()
+                    .filter(|entry| {
+                        entry.is_dir
+                            && self.explorer_expanded.contains(&format!("dir:{}", entry.path))
+                            && !self.explorer_cache.contains_key(&entry.path)
+                    })
+                    .map(|entry| entry.path.clone())
+                    .collect();
                 self.explorer_cache
-                    .insert(path, entries.into_iter().map(tree_from_entry).collect());
+                    .insert(path.clone(), entries.into_iter().map(tree_from_entry).collect());
+                self.finish_directory_load(connection_name, &path);
+                for child in expanded {
+                    self.request_remote_directory(connection_name, &child);
+                }
+            }
+            (
+                PendingAction::ReadAheadDirectory { path, opened },
+                RpcResult::DirectoryEntries { entries, .. },
+            ) => {
+                let entries: Vec<_> = entries.into_iter().map(tree_from_entry).collect();
+                self.read_ahead_siblings(connection_name, &opened, &entries);
+                self.explorer_cache.insert(path, entries);
+            }
+            (PendingAction::ReadAheadDirectory { .. }, _) => {}
+            (PendingAction::ReadAhead { path, modified }, result) => {
+                self.finish_read_ahead(connection_name, path, modified, result);
+            }
+            (PendingAction::LoadRemoteDirectory { path }, RpcResult::Error { message }) => {
+                self.output
+                    .connection_error(connection_name, format!("failed to list {path}: {message}"));
+                self.finish_directory_load(connection_name, &path);
+            }
+            (PendingAction::CreatePickerFolder { parent }, RpcResult::Created { path, .. }) => {
+                self.picker_folder_created(&parent, path);
             }
             (PendingAction::LoadPickerRoots, RpcResult::Roots { roots }) => {
                 self.remote_picker.roots = roots;
@@ -377,19 +1269,402 @@ impl WorkspaceApp {
                     .cache
                     .insert(path, entries.into_iter().map(tree_from_entry).collect());
             }
-            (PendingAction::RunTerminal { terminal_id }, RpcResult::CommandOutput { output }) => {
-                if let Some(term) = self.terminals.iter_mut().find(|term| term.id == terminal_id)
+            (
+                PendingAction::LoadPathCompletions {
+                    connection_name,
+                    parent,
+                },
+                RpcResult::DirectoryEntries { entries, .. },
+            ) => {
+                self.path_completions.entries.insert(
+                    (connection_name, parent),
+                    entries.into_iter().map(tree_from_entry).collect(),
+                );
+            }
+            (
+                PendingAction::RunTerminal {
+                    terminal_id,
+                    block_id,
+                    started,
+                },
+                RpcResult::CommandOutput {
+                    output,
+                    attachments,
+                    ..
+                },
+            ) => {
+                let (output, outcome) = take_bell(&output);
+                self.load_attachments(connection_name, &attachments);
+                if let Some(block) = self.terminal_block_mut(terminal_id, block_id) {
+                    block.finish(&output, attachments);
+                }
+                self.active_bottom_tab = BottomTab::Terminal;
+                self.announce_command(connection_name, terminal_id, block_id, started.elapsed(), outcome);
+            }
+            (PendingAction::LoadServices, RpcResult::Services { services }) => {
+                self.services.entries = services;
+                self.services.loading = false;
+            }
+            (PendingAction::ControlService, RpcResult::ServiceUpdated { name }) => {
+                self.output
+                    .connection_info(connection_name, format!("service {name} updated"));
+                self.refresh_services();
+            }
+            (PendingAction::LoadProcesses, RpcResult::Processes { processes }) => {
+                self.processes.entries = processes;
+                self.processes.loading = false;
+            }
+            (PendingAction::KillProcess, RpcResult::ProcessKilled { pid }) => {
+                self.output
+                    .connection_info(connection_name, format!("signalled process {pid}"));
+                self.refresh_processes();
+            }
+            (PendingAction::LoadServiceLogs, RpcResult::ServiceLogs { name, logs }) => {
+                self.services.logs_for = Some(name);
+                self.services.logs = logs;
+            }
+            (PendingAction::SessionAccess, RpcResult::Access { access }) => {
+                self.access_received(connection_name, access);
+            }
+            (PendingAction::DetectTools, RpcResult::Tools { tools }) => {
+                let missing: Vec<&str> = tools
+                    .iter()
+                    .filter(|tool| tool.version.is_none())
+                    .map(|tool| tool.name.as_str())
+                    .collect();
+                if !missing.is_empty() {
+                    self.task_manager.note(format!(
+                        "[{connection_name}] tools not found: {}",
+                        missing.join(", ")
+                    ));
+                }
+                if let Some(connection) = self.connections.get_mut(connection_name) {
+                    connection.tools = Some(tools);
+                }
+            }
+            (
+                PendingAction::LoadTerminalContainers {
+                    connection_name: requested,
+                },
+                RpcResult::Containers { containers },
+            ) if requested == self.terminal_form.connection_name => {
+                self.terminal_form.containers = containers;
+                self.terminal_form.loading_containers = false;
+            }
+            (
+                PendingAction::LoadContainerLogs {
+                    terminal_id,
+                    block_id,
+                },
+                RpcResult::ContainerLogs { logs, .. },
+            ) => {
+                if let Some(block) = self.terminal_block_mut(terminal_id, block_id) {
+                    block.finish(&logs, Vec::new());
+                }
+            }
+            (
+                PendingAction::RunTerminal {
+                    terminal_id,
+                    block_id,
+                    started,
+                },
+                RpcResult::Error { message },
+            ) => {
+                if let Some(block) = self.terminal_block_mut(terminal_id, block_id) {
+                    block.finish(&format!("error: {message}"), Vec::new());
+                }
+                self.announce_command(connection_name, terminal_id, block_id, started.elapsed(), CommandOutcome::Failed);
+            }
+            (PendingAction::LoadContainerLogs { terminal_id, block_id }, RpcResult::Error { message }) => {
+                if let Some(block) = self.terminal_block_mut(terminal_id, block_id) {
+                    block.finish(&format!("error: {message}"), Vec::new());
+                }
+            }
+            (PendingAction::LoadDiff { path }, RpcResult::FileContent { content, hash, .. }) => {
+                self.open_diff(connection_name, path, &content, hash);
+            }
+            (PendingAction::SaveRemoteFile { path, task, .. }, RpcResult::Conflict { content, hash, .. }) => {
+                if let Some(prompt) = &mut self.unsaved_prompt {
+                    prompt.saving = false;
+                }
+                if let Some(task) = task {
+                    self.task_manager.step(task, Some(format!("{path} changed on the server")));
+                }
+                self.output.connection_warn(
+                    connection_name,
+                    format!("{path} changed on the server; merge the changes before saving"),
+                );
+                self.open_save_conflict(connection_name, path, content, hash);
+            }
+            (PendingAction::FileLock { acquiring }, RpcResult::FileLock { path, lock }) => {
+                self.update_file_lock(connection_name, path, lock, acquiring);
+            }
+            (
+                PendingAction::FormatFile {
+                    path,
+                    content,
+                    save,
+                    task,
+                },
+                result,
+            ) => self.finish_format(connection_name, path, content, save, task, result),
+            (PendingAction::SaveRemoteFile { path, task, .. }, RpcResult::Error { message }) => {
+                if let Some(prompt) = &mut self.unsaved_prompt {
+                    prompt.saving = false;
+                }
+                if let Some(task) = task {
+                    self.task_manager.step(task, Some(format!("{path}: {message}")));
+                }
+                self.output
+                    .connection_error(connection_name, format!("failed to save {path}: {message}"));
+            }
+            (PendingAction::LoadTerminalContainers { .. }, RpcResult::Error { message }) => {
+                self.terminal_form.loading_containers = false;
+                self.output.connection_error(connection_name, message);
+            }
+            (PendingAction::SendBroadcast, RpcResult::BroadcastSent { recipients }) => {
+                self.output.connection_info(
+                    connection_name,
+                    format!("broadcast delivered to {recipients} session(s)"),
+                );
+            }
+            (PendingAction::StartKernel, RpcResult::KernelStarted { kernel }) => {
+                self.notebook.starting = false;
+                self.output.connection_info(
+                    connection_name,
+                    format!("kernel {} started", kernel.kernel_name),
+                );
+                self.notebook.kernel = Some(kernel);
+                if self.notebook.cells.is_empty() {
+                    self.notebook.cells.push(NotebookCell::new());
+                }
+            }
+            (PendingAction::ExecuteCell { cell_id }, RpcResult::CellExecuted { cell }) => {
+                self.load_attachments(connection_name, &cell.attachments);
+                if let Some(target) = self.notebook.cells.iter_mut().find(|item| item.id == cell_id) {
+                    target.running = false;
+                    target.output = cell.output;
+                    target.attachments = cell.attachments;
+                    target.error = cell.error;
+                    target.execution_count = cell.execution_count;
+                }
+            }
+            (PendingAction::KernelControl, RpcResult::KernelStopped { .. }) => {
+                self.notebook.kernel = None;
+                for cell in &mut self.notebook.cells {
+                    cell.running = false;
+                }
+            }
+            (PendingAction::KernelControl, RpcResult::KernelInterrupted { .. }) => {}
+            (PendingAction::StartKernel, RpcResult::Error { message }) => {
+                self.notebook.starting = false;
+                self.output.connection_error(connection_name, message);
+            }
+            (PendingAction::ExecuteCell { cell_id }, RpcResult::Error { message }) => {
+                if let Some(target) = self.notebook.cells.iter_mut().find(|item| item.id == cell_id) {
+                    target.running = false;
+                    target.error = Some(message);
+                }
+            }
+            (
+                PendingAction::LoadPathCompletions {
+                    connection_name,
+                    parent,
+                },
+                RpcResult::Error { .. },
+            ) => {
+                self.path_completions
+                    .entries
+                    .insert((connection_name, parent), Vec::new());
+            }
+            (
+                PendingAction::TransferChunk {
+                    transfer_id,
+                    offset,
+                    length,
+                    sent_at,
+                    checksum,
+                },
+                result,
+            ) => self.handle_transfer_chunk(transfer_id, offset, length, sent_at, checksum, result),
+            (
+                PendingAction::ResumeTransfer {
+                    transfer_id,
+                    local,
+                    local_size,
+                },
+                result,
+            ) => self.handle_transfer_resume(transfer_id, local, local_size, result),
+            (PendingAction::StatEntry { path }, result) => {
+                if let Some(dialog) = &mut self.properties
+                    && dialog.path == path
+                    && dialog.connection_name.as_deref() == Some(connection_name)
                 {
-                    term.output.push_str(&output);
-                    if !output.ends_with('\n') {
-                        term.output.push('\n');
+                    match result {
+                        RpcResult::Stat { stat } => dialog.stat = Some(*stat),
+                        RpcResult::Error { message } => dialog.error = Some(message),
+                        _ => {}
                     }
                 }
-                self.active_bottom_tab = BottomTab::Terminal;
+            }
+            (PendingAction::SetPermissions { path }, result) => {
+                let dialog = self.properties.as_mut().filter(|dialog| {
+                    dialog.path == path
+                        && dialog.connection_name.as_deref() == Some(connection_name)
+                });
+                match result {
+                    RpcResult::Stat { stat } => {
+                        self.task_manager.note(format!(
+                            "[{connection_name}] set permissions of {path} to {}",
+                            stat.permissions
+                        ));
+                        if let Some(dialog) = dialog {
+                            dialog.stat = Some(*stat);
+                            dialog.mode = None;
+                            dialog.error = None;
+                            dialog.applying = false;
+                        }
+                    }
+                    RpcResult::Error { message } => {
+                        self.output.connection_error(
+                            connection_name,
+                            format!("failed to set permissions of {path}: {message}"),
+                        );
+                        if let Some(dialog) = dialog {
+                            dialog.error = Some(message);
+                            dialog.applying = false;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (
+                PendingAction::RunPipelineStep { run_id, step },
+                RpcResult::CommandOutput { output, exit_code, .. },
+            ) => self.finish_pipeline_step(run_id, step, Ok((output, exit_code))),
+            (PendingAction::RunPipelineStep { run_id, step }, RpcResult::Error { message }) => {
+                self.finish_pipeline_step(run_id, step, Err(message));
+            }
+            (PendingAction::Run, RpcResult::CommandOutput { output, exit_code, .. }) => {
+                self.finish_run(request_id, Ok((output, exit_code)));
+            }
+            (PendingAction::Run, RpcResult::Error { message }) => self.finish_run(request_id, Err(message)),
+            (PendingAction::CollectDiagnostics, RpcResult::DiagnosticsCollected { path, size }) => {
+                self.task_manager.complete(request_id, Some(format_bytes(size)));
+                self.prompt_download(connection_name, &path);
+            }
+            (PendingAction::SearchFiles, RpcResult::SearchResults { files, truncated }) => {
+                self.search_finished(request_id, files, truncated);
+            }
+            (PendingAction::IndexTree, RpcResult::TreeIndex { root, paths, truncated }) => {
+                self.index_received(connection_name, root, paths, truncated);
+            }
+            (PendingAction::IndexTree, RpcResult::Error { message }) => {
+                self.index_failed(connection_name);
+                self.output.connection_error(
+                    connection_name,
+                    format!("indexing for quick open failed: {message}"),
+                );
+            }
+            (PendingAction::WorkspaceSearch { search, group }, result) => {
+                self.workspace_search_received(search, group, result);
+            }
+            (PendingAction::SearchFiles, RpcResult::Error { message }) => {
+                self.search.searching = false;
+                self.task_manager.fail(request_id, message.clone());
+                self.output
+                    .connection_error(connection_name, format!("search failed: {message}"));
+                self.active_bottom_tab = BottomTab::Output;
+            }
+            (PendingAction::ReplaceInFiles, RpcResult::Replaced { files }) => {
+                self.replace_finished(request_id, connection_name, files);
+            }
+            (PendingAction::ReplaceInFiles, RpcResult::Error { message }) => {
+                self.search.replacing = false;
+                self.task_manager.fail(request_id, message.clone());
+                self.output.connection_error(
+                    connection_name,
+                    format!("replace failed, no files were changed: {message}"),
+                );
+                self.active_bottom_tab = BottomTab::Output;
+            }
+            (PendingAction::ReloadRemoteFile { path }, RpcResult::FileContent { content, hash, .. }) => {
+                if let Some(tab) = self
+                    .open_files
+                    .iter_mut()
+                    .find(|tab| tab.path == path && !tab.dirty)
+                {
+                    tab.cursor = tab.cursor.min(content.chars().count());
+                    tab.content = content;
+                    tab.remote_hash = hash;
+                }
+            }
+            (PendingAction::CreateCopyTarget { copy_id }, RpcResult::Created { .. }) => {
+                self.copy_target_created(copy_id);
+            }
+            (PendingAction::ListCopySource { copy_id }, RpcResult::DirectoryEntries { entries, .. }) => {
+                self.copy_source_listed(copy_id, entries);
+            }
+            (
+                PendingAction::CreateCopyTarget { copy_id } | PendingAction::ListCopySource { copy_id },
+                RpcResult::Error { message },
+            ) => self.fail_copy(copy_id, message),
+            (
+                PendingAction::ReadCopyChunk {
+                    copy_id,
+                    offset,
+                    length,
+                    sent_at,
+                },
+                result,
+            ) => self.handle_copy_read(copy_id, offset, length, sent_at, result),
+            (PendingAction::WriteCopyChunk { copy_id, sent_at }, result) => {
+                self.handle_copy_write(copy_id, sent_at, result);
+            }
+            (PendingAction::GitStatus { root }, RpcResult::GitStatus { status, .. }) => {
+                self.finish_git_status(connection_name, root, status);
+            }
+            (PendingAction::GitStatus { root }, RpcResult::Error { message }) => {
+                self.output
+                    .connection_warn(connection_name, format!("git status of {root}: {message}"));
+            }
+            (PendingAction::GitDiff { file }, RpcResult::GitDiff { diff, .. }) => {
+                self.finish_git_diff(file, diff);
+            }
+            (PendingAction::GitDiff { file }, RpcResult::Error { message }) => {
+                self.finish_git_diff(file, message);
+            }
+            (PendingAction::GitGutter { path }, RpcResult::GitDiff { diff, .. }) => {
+                self.finish_git_gutter(connection_name, path, &diff);
+            }
+            (PendingAction::GitGutter { .. }, RpcResult::Error { .. }) => {}
+            (PendingAction::GitCommit { root }, RpcResult::GitCommitted { commit, .. }) => {
+                self.finish_git_commit(connection_name, root, Ok(commit));
+            }
+            (PendingAction::GitCommit { root }, RpcResult::Error { message }) => {
+                self.finish_git_commit(connection_name, root, Err(message));
+            }
+            (PendingAction::LoadServices, RpcResult::Error { message }) => {
+                self.services.loading = false;
+                self.output.connection_error(connection_name, message);
+            }
+            (PendingAction::LoadProcesses, RpcResult::Error { message }) => {
+                self.processes.loading = false;
+                self.output.connection_error(connection_name, message);
+            }
+            (_, RpcResult::TooLarge { path, size, limit }) => {
+                self.output.connection_error(
+                    connection_name,
+                    format!(
+                        "{path} is {}, over the server's {} read limit",
+                        format_bytes(size),
+                        format_bytes(limit)
+                    ),
+                );
             }
             (_, RpcResult::Error { message }) => {
-                self.output_lines
-                    .push(format!("[{connection_name}] {message}"));
+                self.task_manager.fail(request_id, message.clone());
+                self.output.connection_error(connection_name, message);
                 self.active_bottom_tab = BottomTab::Output;
             }
             _ => {}
@@ -0,0 +1,181 @@
+use eframe::egui;
+use rs_peer_workspace_shared::project::{display_name_for_path, is_absolute_path, path_crumbs, FolderSource};
+
+use super::state::WorkspaceApp;
+
+impl WorkspaceApp {
+    /// Connection picker and path field above the explorer tree, with the
+    /// breadcrumbs of the directory being browsed.
+    pub fn draw_address_bar(&mut self, ui: &mut egui::Ui) {
+        let connected: Vec<String> = self
+            .project
+            .connections
+            .iter()
+            .map(|connection| connection.name.clone())
+            .filter(|name| self.connections.get(name).is_some_and(|state| state.connected))
+            .collect();
+        let bar = &mut self.address_bar;
+        if !connected.contains(&bar.connection_name) {
+            bar.path = None;
+            match connected.first() {
+                Some(name) => bar.connection_name = name.clone(),
+                None => return,
+            }
+        }
+
+        let mut go = None;
+        let mut close = false;
+        let previous = bar.connection_name.clone();
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("address-connection")
+                .selected_text(bar.connection_name.clone())
+                .width(96.0)
+                .show_ui(ui, |ui| {
+                    for name in &connected {
+                        ui.selectable_value(&mut bar.connection_name, name.clone(), name);
+                    }
+                });
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut bar.input)
+                    .hint_text(self.i18n.tr("address-hint"))
+                    .desired_width(f32::INFINITY),
+            );
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                go = Some(bar.input.clone());
+            }
+        });
+        if bar.connection_name != previous {
+            bar.path = None;
+        }
+        if let Some(path) = bar.path.clone() {
+            ui.horizontal_wrapped(|ui| {
+                if let Some(crumb) = breadcrumbs(ui, &path) {
+                    go = Some(crumb);
+                }
+                if ui
+                    .small_button("x")
+                    .on_hover_text(self.i18n.tr("address-close"))
+                    .clicked()
+                {
+                    close = true;
+                }
+            });
+        }
+        ui.separator();
+
+        if close {
+            self.address_bar.path = None;
+        }
+        if let Some(path) = go {
+            self.browse_remote_path(&path);
+        }
+    }
+
+    /// Lists `path` on the address bar's connection, fetched afresh.
+    fn browse_remote_path(&mut self, path: &str) {
+        let Some(path) = normalized_absolute(path) else {
+            self.output
+                .warn(format!("{} is not an absolute path.", path.trim()));
+            return;
+        };
+        let connection_name = self.address_bar.connection_name.clone();
+        self.address_bar.input = path.clone();
+        self.address_bar.path = Some(path.clone());
+        // The cache is keyed by path alone, so a listing from another connection
+        // must not be shown here.
+        self.explorer_cache.remove(&path);
+        self.request_remote_directory(&connection_name, &path);
+    }
+
+    /// The browsed directory, listed at the top of the explorer.
+    pub fn draw_browsed(&mut self, ui: &mut egui::Ui) {
+        let Some(path) = self.address_bar.path.clone() else {
+            return;
+        };
+        let connection_name = self.address_bar.connection_name.clone();
+        let folder = self.containing_folder(&FolderSource::Remote {
+            connection_name: connection_name.clone(),
+            path: path.clone(),
+        });
+        egui::CollapsingHeader::new(format!("[{connection_name}] {}", display_name_for_path(&path)))
+            .id_salt("explorer-browsed")
+            .default_open(true)
+            .show(ui, |ui| {
+                for child in self.sorted_children(&path) {
+                    self.render_tree_entry(ui, &folder, &child, 0);
+                }
+            });
+    }
+
+    /// Path field and breadcrumbs of the remote folder picker.
+    pub fn draw_picker_address(&mut self, ui: &mut egui::Ui) {
+        let mut go = None;
+        let picker = &mut self.remote_picker;
+        ui.horizontal(|ui| {
+            let label = ui.label(self.i18n.tr("remote-picker-go-to"));
+            let response = ui
+                .add(
+                    egui::TextEdit::singleline(&mut picker.location_input)
+                        .hint_text(self.i18n.tr("address-hint")),
+                )
+                .labelled_by(label.id);
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                go = Some(picker.location_input.clone());
+            }
+            if picker.location.is_some() && ui.button(self.i18n.tr("remote-picker-roots")).clicked() {
+                picker.location = None;
+            }
+        });
+        if let Some(location) = &picker.location
+            && let Some(crumb) = ui.horizontal_wrapped(|ui| breadcrumbs(ui, location)).inner
+        {
+            go = Some(crumb);
+        }
+
+        if let Some(path) = go {
+            self.picker_go_to(&path);
+        }
+    }
+
+    /// Lists `path` on its own in the remote picker and selects it.
+    pub fn picker_go_to(&mut self, path: &str) {
+        let Some(path) = normalized_absolute(path) else {
+            self.output
+                .warn(format!("{} is not an absolute path.", path.trim()));
+            return;
+        };
+        let picker = &mut self.remote_picker;
+        picker.location_input = path.clone();
+        picker.selected_path = path.clone();
+        picker.selected_is_file = false;
+        picker.expanded.insert(format!("picker:{path}"));
+        picker.location = Some(path.clone());
+        self.request_picker_children(&path);
+    }
+}
+
+/// `path` without repeated or trailing separators, when it is absolute.
+fn normalized_absolute(path: &str) -> Option<String> {
+    let path = path.trim();
+    if !is_absolute_path(path) {
+        return None;
+    }
+    path_crumbs(path).pop().map(|(_, path)| path)
+}
+
+/// One button per directory from the root down to `path`; returns the one
+/// clicked.
+pub fn breadcrumbs(ui: &mut egui::Ui, path: &str) -> Option<String> {
+    let crumbs = path_crumbs(path);
+    let last = crumbs.len().saturating_sub(1);
+    let mut clicked = None;
+    for (index, (name, crumb)) in crumbs.into_iter().enumerate() {
+        if index > 1 {
+            ui.weak(">");
+        }
+        if ui.selectable_label(index == last, name).clicked() {
+            clicked = Some(crumb);
+        }
+    }
+    clicked
+}
@@ -0,0 +1,498 @@
+use eframe::egui;
+use rfd::FileDialog;
+use rs_peer_workspace_shared::format::Formatter;
+use rs_peer_workspace_shared::index::fuzzy_match;
+use serde::{Deserialize, Serialize};
+
+use super::i18n::Language;
+use super::quickopen::highlight_matches;
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, CommandPalette, GuardedAction, PassphraseForm, SplitOrientation};
+
+/// An action the user can run from the menus or the command palette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AppCommand {
+    CreateProject,
+    OpenProject,
+    SaveProject,
+    Settings,
+    EncryptCredentials,
+    RemoveEncryption,
+    Save,
+    SaveAll,
+    FormatDocument,
+    QuickOpen,
+    SearchWorkspace,
+    UndoOperation,
+    AddConnection,
+    AddFolder,
+    Broadcast,
+    RecordMacro,
+    Macros,
+    CommandPalette,
+    SplitVertical,
+    SplitHorizontal,
+    Unsplit,
+    ToggleBottomPanel,
+    ShowBottomTab(BottomTab),
+    ExplorerOptions,
+    SetLanguage(Language),
+    NewTerminal,
+    TerminalProfiles,
+    Notebook,
+    Tasks,
+    RunTask(String),
+    RunConfigs,
+    Run(String),
+    Reconnect(String),
+    CollectDiagnostics(String),
+    RunMacro(String),
+}
+
+const BOTTOM_TABS: [BottomTab; 10] = [
+    BottomTab::Output,
+    BottomTab::Tasks,
+    BottomTab::Terminal,
+    BottomTab::Services,
+    BottomTab::Transfers,
+    BottomTab::Search,
+    BottomTab::WorkspaceSearch,
+    BottomTab::Logs,
+    BottomTab::Ports,
+    BottomTab::Processes,
+];
+
+impl BottomTab {
+    pub fn label_id(&self) -> &'static str {
+        match self {
+            BottomTab::Output => "bottom-output",
+            BottomTab::Tasks => "bottom-tasks",
+            BottomTab::Terminal => "bottom-terminal",
+            BottomTab::Services => "bottom-services",
+            BottomTab::Transfers => "bottom-transfers",
+            BottomTab::Search => "bottom-search",
+            BottomTab::WorkspaceSearch => "bottom-workspace-search",
+            BottomTab::Logs => "bottom-logs",
+            BottomTab::Ports => "bottom-ports",
+            BottomTab::Processes => "bottom-processes",
+        }
+    }
+}
+
+impl AppCommand {
+    /// Menu the command belongs to, shown before its name in the palette.
+    fn category_id(&self) -> &'static str {
+        match self {
+            AppCommand::CreateProject
+            | AppCommand::OpenProject
+            | AppCommand::SaveProject
+            | AppCommand::Settings
+            | AppCommand::EncryptCredentials
+            | AppCommand::RemoveEncryption => "menu-file",
+            AppCommand::Save
+            | AppCommand::SaveAll
+            | AppCommand::FormatDocument
+            | AppCommand::QuickOpen
+            | AppCommand::SearchWorkspace
+            | AppCommand::UndoOperation
+            | AppCommand::AddConnection
+            | AppCommand::AddFolder
+            | AppCommand::Broadcast
+            | AppCommand::RecordMacro
+            | AppCommand::Macros => "menu-edit",
+            AppCommand::CommandPalette
+            | AppCommand::SplitVertical
+            | AppCommand::SplitHorizontal
+            | AppCommand::Unsplit
+            | AppCommand::ToggleBottomPanel
+            | AppCommand::ShowBottomTab(_)
+            | AppCommand::ExplorerOptions
+            | AppCommand::SetLanguage(_) => "menu-view",
+            AppCommand::NewTerminal
+            | AppCommand::TerminalProfiles
+            | AppCommand::Notebook
+            | AppCommand::Tasks
+            | AppCommand::RunTask(_)
+            | AppCommand::RunConfigs
+            | AppCommand::Run(_) => "menu-terminal",
+            AppCommand::Reconnect(_) | AppCommand::CollectDiagnostics(_) => {
+                "command-category-connection"
+            }
+            AppCommand::RunMacro(_) => "command-category-macro",
+        }
+    }
+
+    pub fn shortcut(&self) -> Option<&'static str> {
+        match self {
+            AppCommand::Save => Some("Ctrl+S"),
+            AppCommand::FormatDocument => Some("Shift+Alt+F"),
+            AppCommand::QuickOpen => Some("Ctrl+P"),
+            AppCommand::SearchWorkspace => Some("Ctrl+Shift+F"),
+            AppCommand::CommandPalette => Some("Ctrl+Shift+P"),
+            _ => None,
+        }
+    }
+}
+
+impl WorkspaceApp {
+    /// Every command in menu order, including the ones that are currently disabled.
+    pub fn commands(&self) -> Vec<AppCommand> {
+        let mut commands = vec![
+            AppCommand::CreateProject,
+            AppCommand::OpenProject,
+            AppCommand::SaveProject,
+            AppCommand::Settings,
+            AppCommand::EncryptCredentials,
+            AppCommand::RemoveEncryption,
+            AppCommand::Save,
+            AppCommand::SaveAll,
+            AppCommand::FormatDocument,
+            AppCommand::QuickOpen,
+            AppCommand::SearchWorkspace,
+            AppCommand::UndoOperation,
+            AppCommand::AddConnection,
+            AppCommand::AddFolder,
+            AppCommand::Broadcast,
+            AppCommand::RecordMacro,
+            AppCommand::Macros,
+            AppCommand::CommandPalette,
+            AppCommand::SplitVertical,
+            AppCommand::SplitHorizontal,
+            AppCommand::Unsplit,
+            AppCommand::ToggleBottomPanel,
+        ];
+        commands.extend(BOTTOM_TABS.into_iter().map(AppCommand::ShowBottomTab));
+        commands.push(AppCommand::ExplorerOptions);
+        commands.extend(Language::ALL.into_iter().map(AppCommand::SetLanguage));
+        commands.extend([
+            AppCommand::NewTerminal,
+            AppCommand::TerminalProfiles,
+            AppCommand::Notebook,
+            AppCommand::Tasks,
+        ]);
+        commands.extend(
+            self.project
+                .tasks
+                .iter()
+                .map(|task| AppCommand::RunTask(task.name.clone())),
+        );
+        commands.push(AppCommand::RunConfigs);
+        commands.extend(
+            self.project
+                .run_configs
+                .iter()
+                .map(|config| AppCommand::Run(config.name.clone())),
+        );
+        commands.extend(
+            self.project
+                .connections
+                .iter()
+                .map(|connection| AppCommand::Reconnect(connection.name.clone())),
+        );
+        commands.extend(
+            self.project
+                .connections
+                .iter()
+                .map(|connection| AppCommand::CollectDiagnostics(connection.name.clone())),
+        );
+        commands.extend(
+            self.settings
+                .macros
+                .iter()
+                .map(|item| AppCommand::RunMacro(item.name.clone())),
+        );
+        commands
+    }
+
+    pub fn command_label(&self, command: &AppCommand) -> String {
+        let id = match command {
+            AppCommand::CreateProject => "menu-create-project",
+            AppCommand::OpenProject => "menu-open-project",
+            AppCommand::SaveProject => "menu-save-project",
+            AppCommand::Settings => "menu-settings",
+            AppCommand::EncryptCredentials if self.project_passphrase.is_some() => {
+                "menu-change-passphrase"
+            }
+            AppCommand::EncryptCredentials => "menu-encrypt-credentials",
+            AppCommand::RemoveEncryption => "menu-remove-encryption",
+            AppCommand::Save => "menu-save",
+            AppCommand::SaveAll => "menu-save-all",
+            AppCommand::FormatDocument => "menu-format-document",
+            AppCommand::QuickOpen => "menu-quick-open",
+            AppCommand::SearchWorkspace => "menu-search-workspace",
+            AppCommand::UndoOperation => "menu-undo-operation",
+            AppCommand::AddConnection => "menu-add-connection",
+            AppCommand::AddFolder => "menu-add-folder",
+            AppCommand::Broadcast => "menu-broadcast",
+            AppCommand::RecordMacro => {
+                return match &self.macros.recording {
+                    Some(commands) => self
+                        .i18n
+                        .tr_args("menu-stop-recording", &[("count", commands.len().into())]),
+                    None => self.i18n.tr("menu-record-macro"),
+                };
+            }
+            AppCommand::Macros => "menu-macros",
+            AppCommand::CommandPalette => "menu-command-palette",
+            AppCommand::SplitVertical => "menu-split-vertical",
+            AppCommand::SplitHorizontal => "menu-split-horizontal",
+            AppCommand::Unsplit => "menu-unsplit",
+            AppCommand::ToggleBottomPanel => "menu-toggle-bottom-panel",
+            AppCommand::ShowBottomTab(tab) => {
+                return self
+                    .i18n
+                    .tr_args("command-show-panel", &[("panel", self.i18n.tr(tab.label_id()).into())]);
+            }
+            AppCommand::ExplorerOptions => "menu-explorer-options",
+            AppCommand::SetLanguage(language) => {
+                return self
+                    .i18n
+                    .tr_args("command-language", &[("language", language.native_name().into())]);
+            }
+            AppCommand::NewTerminal => "menu-new-terminal",
+            AppCommand::TerminalProfiles => "menu-terminal-profiles",
+            AppCommand::Notebook => "menu-notebook",
+            AppCommand::Tasks => "menu-tasks",
+            AppCommand::RunTask(name) => {
+                return self
+                    .i18n
+                    .tr_args("command-run-task", &[("name", name.clone().into())]);
+            }
+            AppCommand::RunConfigs => "menu-run-configs",
+            AppCommand::Run(name) => {
+                return self
+                    .i18n
+                    .tr_args("command-run-config", &[("name", name.clone().into())]);
+            }
+            AppCommand::Reconnect(name) => {
+                return self
+                    .i18n
+                    .tr_args("command-reconnect", &[("name", name.clone().into())]);
+            }
+            AppCommand::CollectDiagnostics(name) => {
+                return self
+                    .i18n
+                    .tr_args("command-collect-diagnostics", &[("name", name.clone().into())]);
+            }
+            AppCommand::RunMacro(name) => {
+                return self
+                    .i18n
+                    .tr_args("command-run-macro", &[("name", name.clone().into())]);
+            }
+        };
+        self.i18n.tr(id)
+    }
+
+    pub fn command_enabled(&self, command: &AppCommand) -> bool {
+        match command {
+            AppCommand::RemoveEncryption => self.project_passphrase.is_some(),
+            AppCommand::Save => self.active_editor().is_some(),
+            AppCommand::SaveAll => self.open_files.iter().any(|tab| tab.dirty),
+            AppCommand::FormatDocument => self
+                .active_editor()
+                .is_some_and(|idx| Formatter::for_path(&self.open_files[idx].path).is_some()),
+            AppCommand::UndoOperation => !self.journal.is_empty(),
+            AppCommand::Unsplit => self.editor_split.is_some(),
+            AppCommand::Reconnect(name) => self.connection_closed(name),
+            AppCommand::CollectDiagnostics(name) => self
+                .connections
+                .get(name)
+                .is_some_and(|connection| connection.connected),
+            AppCommand::SetLanguage(language) => self.i18n.language() != *language,
+            AppCommand::Run(name) => !self.run_active(name),
+            _ => true,
+        }
+    }
+
+    pub fn run_command(&mut self, command: AppCommand) {
+        if !self.command_enabled(&command) {
+            return;
+        }
+        self.record_command(&command);
+        match command {
+            AppCommand::CreateProject => self.guard_unsaved(GuardedAction::ResetProject),
+            AppCommand::OpenProject => {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("RS Peer Workspace", &["rpw"])
+                    .pick_file()
+                {
+                    self.guard_unsaved(GuardedAction::OpenProject(path));
+                }
+            }
+            AppCommand::SaveProject => self.save_project(),
+            AppCommand::Settings => self.show_settings = true,
+            AppCommand::EncryptCredentials => {
+                self.passphrase_form = PassphraseForm {
+                    open: true,
+                    ..Default::default()
+                };
+            }
+            AppCommand::RemoveEncryption => {
+                self.project_passphrase = None;
+                self.save_project();
+            }
+            AppCommand::Save => self.save_active_editor(),
+            AppCommand::SaveAll => self.save_all_editors(),
+            AppCommand::FormatDocument => self.format_active_editor(),
+            AppCommand::QuickOpen => self.open_quick_open(),
+            AppCommand::SearchWorkspace => self.open_workspace_search(),
+            AppCommand::UndoOperation => self.undo_last_operation(),
+            AppCommand::AddConnection => self.open_add_connection(),
+            AppCommand::AddFolder => self.show_add_folder = true,
+            AppCommand::Broadcast => self.show_broadcast = true,
+            AppCommand::RecordMacro => self.toggle_macro_recording(),
+            AppCommand::Macros => self.macros.show = true,
+            AppCommand::CommandPalette => self.open_command_palette(),
+            AppCommand::SplitVertical => self.set_editor_split(Some(SplitOrientation::Vertical)),
+            AppCommand::SplitHorizontal => self.set_editor_split(Some(SplitOrientation::Horizontal)),
+            AppCommand::Unsplit => self.set_editor_split(None),
+            AppCommand::ToggleBottomPanel => self.show_bottom_panel = !self.show_bottom_panel,
+            AppCommand::ShowBottomTab(tab) => {
+                self.show_bottom_panel = true;
+                self.active_bottom_tab = tab;
+            }
+            AppCommand::ExplorerOptions => self.open_explorer_options(),
+            AppCommand::SetLanguage(language) => self.i18n.set_language(language),
+            AppCommand::NewTerminal => self.show_new_terminal = true,
+            AppCommand::TerminalProfiles => self.show_terminal_profiles = true,
+            AppCommand::Notebook => self.show_notebook = true,
+            AppCommand::Tasks => self.show_tasks = true,
+            AppCommand::RunTask(name) => self.run_task(&name),
+            AppCommand::RunConfigs => self.show_run_configs = true,
+            AppCommand::Run(name) => self.start_run(&name),
+            AppCommand::Reconnect(name) => self.reconnect(&name),
+            AppCommand::CollectDiagnostics(name) => self.collect_diagnostics(&name),
+            AppCommand::RunMacro(name) => self.run_macro(&name),
+        }
+    }
+
+    /// Menu entry for `command`; clicking it closes the menu and runs the command.
+    pub fn command_button(&mut self, ui: &mut egui::Ui, command: AppCommand) -> egui::Response {
+        let mut button = egui::Button::new(self.command_label(&command));
+        if let Some(shortcut) = command.shortcut() {
+            button = button.shortcut_text(shortcut);
+        }
+        let response = ui.add_enabled(self.command_enabled(&command), button);
+        if response.clicked() {
+            ui.close_menu();
+            self.run_command(command);
+        }
+        response
+    }
+
+    pub fn open_command_palette(&mut self) {
+        self.command_palette = CommandPalette {
+            open: true,
+            focus: true,
+            ..Default::default()
+        };
+    }
+
+    pub fn draw_command_palette(&mut self, ctx: &egui::Context) {
+        if !self.command_palette.open {
+            return;
+        }
+
+        let query = self.command_palette.query.clone();
+        let mut matches: Vec<(AppCommand, String, i32, Vec<usize>)> = self
+            .commands()
+            .into_iter()
+            .filter(|command| self.command_enabled(command))
+            .filter_map(|command| {
+                let text = self.i18n.tr_args(
+                    "command-palette-entry",
+                    &[
+                        ("category", self.i18n.tr(command.category_id()).into()),
+                        ("command", self.command_label(&command).into()),
+                    ],
+                );
+                let found = fuzzy_match(&query, &text)?;
+                Some((command, text, found.score, found.positions))
+            })
+            .collect();
+        if !query.trim().is_empty() {
+            matches.sort_by_key(|found| std::cmp::Reverse(found.2));
+        }
+
+        let count = matches.len();
+        let palette = &mut self.command_palette;
+        palette.selected = palette.selected.min(count.saturating_sub(1));
+        ctx.input_mut(|input| {
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) && count > 0 {
+                palette.selected = (palette.selected + 1) % count;
+            }
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) && count > 0 {
+                palette.selected = (palette.selected + count - 1) % count;
+            }
+        });
+
+        let mut open = true;
+        let mut chosen = None;
+        egui::Window::new(self.i18n.tr("command-palette-title"))
+            .id(egui::Id::new("command-palette"))
+            .open(&mut open)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+            .collapsible(false)
+            .resizable(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let palette = &mut self.command_palette;
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut palette.query)
+                        .hint_text(self.i18n.tr("command-palette-hint"))
+                        .desired_width(f32::INFINITY),
+                );
+                if response.changed() {
+                    palette.selected = 0;
+                }
+                if palette.focus {
+                    response.request_focus();
+                    palette.focus = false;
+                }
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    chosen = Some(palette.selected);
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_salt("command-palette-results")
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        if matches.is_empty() {
+                            ui.label(self.i18n.tr("command-palette-no-matches"));
+                        }
+                        for (idx, (command, text, _, positions)) in matches.iter().enumerate() {
+                            let selected = idx == palette.selected;
+                            let response = ui
+                                .horizontal(|ui| {
+                                    let response = ui.selectable_label(
+                                        selected,
+                                        highlight_matches(ui, "", text, positions),
+                                    );
+                                    if let Some(shortcut) = command.shortcut() {
+                                        ui.with_layout(
+                                            egui::Layout::right_to_left(egui::Align::Center),
+                                            |ui| ui.weak(shortcut),
+                                        );
+                                    }
+                                    response
+                                })
+                                .inner;
+                            if selected {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                chosen = Some(idx);
+                            }
+                        }
+                    });
+            });
+
+        let chosen = chosen.and_then(|idx| matches.into_iter().nth(idx));
+        if chosen.is_some() || !open {
+            self.command_palette = CommandPalette::default();
+        }
+        if let Some((command, ..)) = chosen {
+            self.run_command(command);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{ListFilter, RpcAction, RpcRequest};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::PendingAction;
+
+const MAX_SUGGESTIONS: usize = 8;
+
+fn split_path(path: &str) -> Option<(&str, &str)> {
+    let index = path.rfind(['/', '\\'])?;
+    Some((&path[..=index], &path[index + 1..]))
+}
+
+impl WorkspaceApp {
+    pub fn draw_path_suggestions(
+        &mut self,
+        ui: &mut egui::Ui,
+        connection_name: &str,
+        path: &mut String,
+    ) {
+        let Some((parent, partial)) = split_path(path) else {
+            return;
+        };
+        let key = (connection_name.to_string(), parent.to_string());
+        let Some(entries) = self.path_completions.entries.get(&key) else {
+            self.request_path_completions(key);
+            return;
+        };
+
+        let separator = if parent.ends_with('\\') { '\\' } else { '/' };
+        let partial = partial.to_lowercase();
+        let suggestions: Vec<&str> = entries
+            .iter()
+            .filter(|entry| {
+                let name = entry.name.to_lowercase();
+                entry.is_dir
+                    && name.starts_with(&partial)
+                    && (partial.starts_with('.') || !name.starts_with('.'))
+            })
+            .map(|entry| entry.name.as_str())
+            .take(MAX_SUGGESTIONS)
+            .collect();
+        if suggestions.is_empty() {
+            return;
+        }
+
+        let mut chosen = None;
+        ui.horizontal_wrapped(|ui| {
+            for name in suggestions {
+                if ui.small_button(format!("{name}{separator}")).clicked() {
+                    chosen = Some(format!("{parent}{name}{separator}"));
+                }
+            }
+        });
+        if let Some(chosen) = chosen {
+            *path = chosen;
+        }
+    }
+
+    fn request_path_completions(&mut self, key: (String, String)) {
+        let connected = self
+            .connections
+            .get(&key.0)
+            .is_some_and(|connection| connection.connected);
+        if !connected || !self.path_completions.requested.insert(key.clone()) {
+            return;
+        }
+
+        let (connection_name, parent) = key;
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadPathCompletions {
+                connection_name: connection_name.clone(),
+                parent: parent.clone(),
+            },
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListDirectory {
+                    path: parent,
+                    filter: ListFilter::default(),
+                },
+            },
+        );
+    }
+}
@@ -0,0 +1,627 @@
+use std::collections::HashSet;
+
+use eframe::egui;
+use rs_peer_workspace_core::ConnectionTest;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, SessionAccess};
+use rs_peer_workspace_shared::project::default_connection_form_addr;
+use rs_peer_workspace_shared::relay::{unix_now, ServerInfo};
+use uuid::Uuid;
+
+use super::i18n::Localizer;
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, ConnectionForm, ElevateForm, PendingAction, TaskKind};
+
+pub const CONNECTED: egui::Color32 = egui::Color32::from_rgb(0x4c, 0xaf, 0x50);
+
+enum ConnectionAction {
+    Reconnect(String),
+    Edit(String),
+    Disconnect(String),
+    Remove(String),
+}
+
+impl WorkspaceApp {
+    pub fn draw_connections(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(self.i18n.tr("connections-title"))
+            .id_salt("connections")
+            .default_open(true)
+            .show(ui, |ui| {
+                if self.project.connections.is_empty() {
+                    ui.label(self.i18n.tr("connection-none"));
+                }
+                let mut action = None;
+                for connection in &self.project.connections {
+                    let name = &connection.name;
+                    let state = self.connections.get(name);
+                    let closed = self.connection_closed(name);
+                    let (color, status) = match state {
+                        Some(state) if state.connected => (CONNECTED, "connections-connected"),
+                        _ if closed => (ui.visuals().weak_text_color(), "connections-disconnected"),
+                        _ => (ui.visuals().warn_fg_color, "connections-connecting"),
+                    };
+                    ui.horizontal(|ui| {
+                        let status = self.i18n.tr(status);
+                        let (rect, response) =
+                            ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                        ui.painter().circle_filled(rect.center(), 4.0, color);
+                        response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, true, &status));
+                        response.on_hover_text(&status);
+                        ui.strong(name);
+                        if let Some(state) = state.filter(|state| state.connected) {
+                            let latency = state
+                                .stats
+                                .as_ref()
+                                .and_then(|stats| stats.rtt_ms)
+                                .map(|rtt| format!(" ({rtt:.0} ms)"))
+                                .unwrap_or_default();
+                            ui.label(egui::RichText::new(format!("{}{latency}", state.transport)).weak());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add_space(14.0);
+                        if closed {
+                            if ui.small_button(self.i18n.tr("connections-reconnect")).clicked() {
+                                action = Some(ConnectionAction::Reconnect(name.clone()));
+                            }
+                        } else if ui.small_button(self.i18n.tr("connections-disconnect")).clicked() {
+                            action = Some(ConnectionAction::Disconnect(name.clone()));
+                        }
+                        if ui.small_button(self.i18n.tr("connections-edit")).clicked() {
+                            action = Some(ConnectionAction::Edit(name.clone()));
+                        }
+                        if ui.small_button(self.i18n.tr("connections-remove")).clicked() {
+                            action = Some(ConnectionAction::Remove(name.clone()));
+                        }
+                    });
+                }
+                if ui.button(self.i18n.tr("connections-add")).clicked() {
+                    self.open_add_connection();
+                }
+
+                match action {
+                    Some(ConnectionAction::Reconnect(name)) => self.reconnect(&name),
+                    Some(ConnectionAction::Edit(name)) => self.edit_connection(&name),
+                    Some(ConnectionAction::Disconnect(name)) => self.disconnect(&name),
+                    Some(ConnectionAction::Remove(name)) => self.remove_connection(&name),
+                    None => {}
+                }
+            });
+    }
+
+    /// Opens the connection dialog empty, unless it already holds a new connection.
+    pub fn open_add_connection(&mut self) {
+        if self.connection_form.editing.is_some() {
+            self.connection_form = ConnectionForm {
+                proxy_addr: default_connection_form_addr(),
+                prefer_p2p: true,
+                ..Default::default()
+            };
+        }
+        self.show_add_connection = true;
+        self.discover_proxies();
+    }
+
+    /// Opens the connection dialog filled in with the settings of `name`.
+    pub fn edit_connection(&mut self, name: &str) {
+        let Some(connection) = self
+            .project
+            .connections
+            .iter()
+            .find(|connection| connection.name == name)
+        else {
+            return;
+        };
+        let outbound = connection.outbound_proxy.as_ref();
+        self.connection_form = ConnectionForm {
+            editing: Some(connection.name.clone()),
+            name: connection.name.clone(),
+            proxy_addr: connection.proxy_addr.clone(),
+            proxy_username: connection.proxy_username.clone(),
+            proxy_password: connection.proxy_password.clone(),
+            server_name: connection.server_name.clone(),
+            server_password: connection.server_password.clone(),
+            prefer_p2p: connection.prefer_p2p,
+            require_e2e: connection.require_e2e,
+            use_keyring: connection.credential_ref.is_some(),
+            outbound_kind: outbound.map(|proxy| proxy.kind),
+            outbound_addr: outbound.map(|proxy| proxy.addr.clone()).unwrap_or_default(),
+            outbound_username: outbound.map(|proxy| proxy.username.clone()).unwrap_or_default(),
+            outbound_password: outbound.map(|proxy| proxy.password.clone()).unwrap_or_default(),
+            ice_servers: connection.ice_servers.clone(),
+            env: connection.env.clone(),
+            ..Default::default()
+        };
+        self.show_add_connection = true;
+    }
+
+    /// Proxies found on the LAN, each filling in the proxy address when clicked.
+    /// Nothing is shown when the scan found none.
+    pub fn draw_discovered_proxies(&mut self, ui: &mut egui::Ui) {
+        let form = &mut self.connection_form;
+        if form.discovering {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(self.i18n.tr("add-connection-discovering"));
+            });
+            return;
+        }
+        if form.discovered.is_empty() {
+            return;
+        }
+        let mut rescan = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.weak(self.i18n.tr("add-connection-discovered"));
+            for proxy in &form.discovered {
+                let selected = form.proxy_addr.trim() == proxy.url;
+                if ui
+                    .selectable_label(selected, &proxy.name)
+                    .on_hover_text(&proxy.url)
+                    .clicked()
+                {
+                    form.proxy_addr = proxy.url.clone();
+                }
+            }
+            rescan = ui.small_button(self.i18n.tr("add-connection-rescan")).clicked();
+        });
+        if rescan {
+            self.discover_proxies();
+        }
+    }
+
+    /// Lists the servers registered with the form's proxy, so one can be picked
+    /// instead of typing its name.
+    pub fn draw_server_browser(&mut self, ctx: &egui::Context) {
+        if !self.connection_form.browsing || !self.show_add_connection {
+            return;
+        }
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut chosen = None;
+        egui::Window::new(self.i18n.tr("server-browser-title"))
+            .id(egui::Id::new("server-browser"))
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let form = &mut self.connection_form;
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.tr_args(
+                        "server-browser-proxy",
+                        &[("proxy", form.proxy_addr.trim().to_string().into())],
+                    ));
+                    refresh = ui
+                        .add_enabled(!form.loading_servers, egui::Button::new(self.i18n.tr("server-browser-refresh")))
+                        .clicked();
+                    if form.loading_servers {
+                        ui.spinner();
+                    }
+                });
+                ui.add(
+                    egui::TextEdit::singleline(&mut form.server_filter)
+                        .hint_text(self.i18n.tr("server-browser-filter"))
+                        .desired_width(f32::INFINITY),
+                );
+                if let Some(err) = &form.servers_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                let filter = form.server_filter.trim().to_lowercase();
+                let servers: Vec<&ServerInfo> = form
+                    .servers
+                    .iter()
+                    .filter(|server| filter.is_empty() || server_matches(server, &filter))
+                    .collect();
+                if servers.is_empty() && !form.loading_servers && form.servers_error.is_none() {
+                    ui.label(self.i18n.tr("server-browser-empty"));
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("server-browser-list")
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("server-browser-grid").num_columns(5).striped(true).show(ui, |ui| {
+                            ui.strong(self.i18n.tr("server-browser-name"));
+                            ui.strong(self.i18n.tr("server-browser-host"));
+                            ui.strong(self.i18n.tr("server-browser-platform"));
+                            ui.strong(self.i18n.tr("server-browser-agent"));
+                            ui.strong(self.i18n.tr("server-browser-tags"));
+                            ui.end_row();
+                            for server in servers {
+                                let metadata = &server.metadata;
+                                let label = format!("{} {}", os_icon(&metadata.os), server.name);
+                                if ui
+                                    .selectable_label(server.name == form.server_name, label)
+                                    .on_hover_text(self.i18n.tr("server-browser-choose"))
+                                    .clicked()
+                                {
+                                    chosen = Some(server.name.clone());
+                                }
+                                ui.label(&metadata.hostname);
+                                if metadata.os.is_empty() {
+                                    ui.weak(self.i18n.tr("server-no-metadata"));
+                                } else {
+                                    ui.label(format!("{}/{}", metadata.os, metadata.arch));
+                                }
+                                ui.label(&metadata.agent_version);
+                                ui.label(metadata.tags.join(", "));
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+
+        if let Some(name) = chosen {
+            let form = &mut self.connection_form;
+            if form.name.trim().is_empty() {
+                form.name = name.clone();
+            }
+            form.server_name = name;
+            open = false;
+        }
+        self.connection_form.browsing = open;
+        if refresh {
+            self.list_proxy_servers();
+        }
+    }
+
+    pub fn draw_connection_details(&mut self, ui: &mut egui::Ui) {
+        let mut visible = HashSet::new();
+        egui::CollapsingHeader::new(self.i18n.tr("connection-details"))
+            .id_salt("connection-details")
+            .default_open(false)
+            .show(ui, |ui| {
+                if self.project.connections.is_empty() {
+                    ui.label(self.i18n.tr("connection-none"));
+                    return;
+                }
+
+                let mut elevate = None;
+                let mut end_elevation = None;
+                let mut collect = None;
+                for connection in &self.project.connections {
+                    let Some(state) = self.connections.get(&connection.name) else {
+                        continue;
+                    };
+                    egui::CollapsingHeader::new(&connection.name)
+                        .id_salt(format!("connection-details:{}", connection.name))
+                        .show(ui, |ui| {
+                            egui::Grid::new(format!("connection-stats:{}", connection.name))
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label(self.i18n.tr("connection-transport"));
+                                    ui.label(&state.transport);
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-identity"));
+                                    ui.label(connection.server_fingerprint.as_deref().unwrap_or("-"));
+                                    ui.end_row();
+                                    if let Some(access) = &state.access {
+                                        ui.label(self.i18n.tr("connection-access"));
+                                        ui.horizontal(|ui| match access.elevated_until {
+                                            _ if !access.read_only => {
+                                                ui.label(self.i18n.tr("connection-access-full"));
+                                            }
+                                            Some(until) if until > unix_now() => {
+                                                ui.colored_label(
+                                                    ui.visuals().warn_fg_color,
+                                                    self.i18n.tr_args(
+                                                        "connection-access-elevated",
+                                                        &[("time", self.i18n.clock(until).into())],
+                                                    ),
+                                                );
+                                                if ui.small_button(self.i18n.tr("connection-end-elevation")).clicked() {
+                                                    end_elevation = Some(connection.name.clone());
+                                                }
+                                            }
+                                            _ => {
+                                                ui.label(self.i18n.tr("connection-access-read-only"));
+                                                if ui.small_button(self.i18n.tr("connection-elevate")).clicked() {
+                                                    elevate = Some(connection.name.clone());
+                                                }
+                                            }
+                                        });
+                                        ui.end_row();
+                                    }
+
+                                    let Some(stats) = &state.stats else {
+                                        ui.label(self.i18n.tr("connection-stats"));
+                                        ui.label(self.i18n.tr("connection-waiting"));
+                                        ui.end_row();
+                                        return;
+                                    };
+
+                                    ui.label(self.i18n.tr("connection-candidate-pair"));
+                                    ui.label(stats.candidate_pair.as_deref().unwrap_or("-"));
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-rtt"));
+                                    ui.label(
+                                        stats
+                                            .rtt_ms
+                                            .map(|rtt| format!("{rtt:.1} ms"))
+                                            .unwrap_or_else(|| "-".to_string()),
+                                    );
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-p2p-bytes"));
+                                    ui.label(format!(
+                                        "{} / {}",
+                                        format_bytes(stats.p2p_bytes_sent),
+                                        format_bytes(stats.p2p_bytes_received)
+                                    ));
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-retransmits"));
+                                    ui.label(stats.retransmits.to_string());
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-relay-bytes"));
+                                    ui.label(format!(
+                                        "{} / {}",
+                                        format_bytes(stats.relay_bytes_sent),
+                                        format_bytes(stats.relay_bytes_received)
+                                    ));
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-throughput"));
+                                    ui.label(self.i18n.tr_args(
+                                        "connection-throughput-value",
+                                        &[
+                                            ("up", format_bytes(stats.relay_send_rate as u64).into()),
+                                            (
+                                                "down",
+                                                format_bytes(stats.relay_receive_rate as u64).into(),
+                                            ),
+                                        ],
+                                    ));
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-outgoing-queue"));
+                                    ui.label(stats.outgoing_queue_depth.to_string());
+                                    ui.end_row();
+                                    ui.label(self.i18n.tr("connection-relay-encryption"));
+                                    ui.label(self.i18n.tr(if stats.relay_encrypted {
+                                        "connection-relay-encrypted"
+                                    } else {
+                                        "connection-relay-plain"
+                                    }));
+                                    ui.end_row();
+                                });
+
+                            if state.connected
+                                && ui
+                                    .button(self.i18n.tr("connection-collect-diagnostics"))
+                                    .on_hover_text(self.i18n.tr("connection-collect-diagnostics-hint"))
+                                    .clicked()
+                            {
+                                collect = Some(connection.name.clone());
+                            }
+
+                            if state.connected {
+                                visible.insert(connection.name.clone());
+                                ui.separator();
+                                ui.strong(self.i18n.tr("connection-system"));
+                                self.draw_system_info(ui, &connection.name, state.system.as_ref());
+                            }
+
+                            ui.separator();
+                            ui.strong(self.i18n.tr("connection-environment"));
+                            let Some(tools) = &state.tools else {
+                                ui.label(self.i18n.tr("connection-detecting-tools"));
+                                return;
+                            };
+                            egui::Grid::new(format!("connection-tools:{}", connection.name))
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for tool in tools {
+                                        ui.label(&tool.name);
+                                        match &tool.version {
+                                            Some(version) => ui.label(version),
+                                            None => ui.colored_label(
+                                                ui.visuals().warn_fg_color,
+                                                self.i18n.tr("connection-tool-missing"),
+                                            ),
+                                        };
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+                if let Some(connection_name) = elevate {
+                    self.elevate_form = ElevateForm {
+                        open: true,
+                        connection_name,
+                        credential: String::new(),
+                    };
+                }
+                if let Some(connection_name) = end_elevation {
+                    self.send_access_request(&connection_name, RpcAction::EndElevation);
+                }
+                if let Some(connection_name) = collect {
+                    self.collect_diagnostics(&connection_name);
+                }
+            });
+        self.update_system_monitors(ui.ctx(), &visible);
+    }
+
+    pub fn send_access_request(&mut self, connection_name: &str, action: RpcAction) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, PendingAction::SessionAccess);
+        self.send_rpc(connection_name, RpcRequest { request_id, action });
+    }
+
+    pub fn access_received(&mut self, connection_name: &str, access: SessionAccess) {
+        let Some(state) = self.connections.get_mut(connection_name) else {
+            return;
+        };
+        let previous = state.access.replace(access);
+        if access.elevated_until != previous.and_then(|previous| previous.elevated_until) {
+            let line = match access.elevated_until {
+                Some(until) => format!("[{connection_name}] elevated until {}", self.i18n.clock(until)),
+                None => format!("[{connection_name}] elevation ended"),
+            };
+            self.task_manager.note(line);
+        }
+    }
+
+    pub fn draw_elevate(&mut self, ctx: &egui::Context) {
+        if !self.elevate_form.open {
+            return;
+        }
+
+        let mut open = self.elevate_form.open;
+        let mut submit = false;
+        egui::Window::new(self.i18n.tr("elevate-title"))
+            .id(egui::Id::new("elevate"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr_args(
+                    "elevate-prompt",
+                    &[("name", self.elevate_form.connection_name.clone().into())],
+                ));
+                let label = ui.label(self.i18n.tr("elevate-credential"));
+                let response = ui
+                    .add(egui::TextEdit::singleline(&mut self.elevate_form.credential).password(true))
+                    .labelled_by(label.id);
+                let enter = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if ui.button(self.i18n.tr("elevate-submit")).clicked() || enter {
+                    submit = true;
+                }
+            });
+        self.elevate_form.open = open && !submit;
+        if submit {
+            let form = std::mem::take(&mut self.elevate_form);
+            self.send_access_request(
+                &form.connection_name,
+                RpcAction::Elevate {
+                    credential: form.credential,
+                },
+            );
+        }
+    }
+
+    /// Has the server bundle its logs and system information, then downloads the
+    /// bundle once it is ready.
+    pub fn collect_diagnostics(&mut self, connection_name: &str) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, PendingAction::CollectDiagnostics);
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::CollectDiagnostics,
+            },
+        );
+        self.task_manager.start(
+            request_id,
+            TaskKind::Diagnostics,
+            format!("[{connection_name}] collecting diagnostics"),
+        );
+        self.active_bottom_tab = BottomTab::Tasks;
+    }
+
+    pub fn detect_tools(&mut self, connection_name: &str) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, PendingAction::DetectTools);
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::DetectTools,
+            },
+        );
+    }
+
+    pub fn missing_tool(&self, connection_name: &str, command: &str) -> Option<String> {
+        let tools = self.connections.get(connection_name)?.tools.as_ref()?;
+        let program = command.split_whitespace().next()?;
+        let program = program
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(program)
+            .trim_end_matches(".exe")
+            .to_ascii_lowercase();
+        let name = match program.as_str() {
+            "python3" | "pip" | "pip3" => "python",
+            "npm" | "npx" => "node",
+            "rustc" => "cargo",
+            other => other,
+        };
+        tools
+            .iter()
+            .find(|tool| tool.name == name && tool.version.is_none())
+            .map(|tool| tool.name.clone())
+    }
+}
+
+pub fn os_icon(os: &str) -> &'static str {
+    match os {
+        "linux" => "🐧",
+        "windows" => "⊞",
+        "macos" => "🍎",
+        "freebsd" | "openbsd" | "netbsd" => "😈",
+        _ => "🖥",
+    }
+}
+
+pub fn server_details(i18n: &Localizer, server: &ServerInfo) -> String {
+    let metadata = &server.metadata;
+    if metadata.os.is_empty() {
+        return i18n.tr("server-no-metadata");
+    }
+    i18n.tr_args(
+        "server-details",
+        &[
+            ("hostname", metadata.hostname.clone().into()),
+            ("os", metadata.os.clone().into()),
+            ("arch", metadata.arch.clone().into()),
+            ("version", metadata.agent_version.clone().into()),
+        ],
+    )
+}
+
+/// Whether `filter`, already lowercased, occurs in the server's name, host name or tags.
+fn server_matches(server: &ServerInfo, filter: &str) -> bool {
+    std::iter::once(&server.name)
+        .chain([&server.metadata.hostname])
+        .chain(&server.metadata.tags)
+        .any(|text| text.to_lowercase().contains(filter))
+}
+
+/// Text for the outcome of a connection test, and whether the settings worked.
+pub fn connection_test_message(
+    i18n: &Localizer,
+    result: &Result<ConnectionTest, String>,
+    custom_ice: bool,
+) -> (bool, String) {
+    match result {
+        Ok(ConnectionTest::Reachable { .. }) if custom_ice => (true, i18n.tr("connection-test-ok-ice")),
+        Ok(ConnectionTest::Reachable { turn: true }) => (true, i18n.tr("connection-test-ok-turn")),
+        Ok(ConnectionTest::Reachable { turn: false }) => (true, i18n.tr("connection-test-ok-relay")),
+        Ok(ConnectionTest::ProxyAuthFailed(reason)) => (
+            false,
+            i18n.tr_args("connection-test-proxy-auth", &[("reason", reason.clone().into())]),
+        ),
+        Ok(ConnectionTest::UnknownServer) => (false, i18n.tr("connection-test-unknown-server")),
+        Ok(ConnectionTest::Rejected(reason)) => (
+            false,
+            i18n.tr_args("connection-test-rejected", &[("reason", reason.clone().into())]),
+        ),
+        Err(err) => (
+            false,
+            i18n.tr_args("connection-test-failed", &[("error", err.clone().into())]),
+        ),
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
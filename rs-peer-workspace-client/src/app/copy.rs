@@ -0,0 +1,416 @@
+use std::path::Path;
+use std::time::Instant;
+
+use rs_peer_workspace_core::transfer::ChunkTuner;
+use rs_peer_workspace_shared::app::{DirectoryEntry, ListFilter, RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::project::display_name_for_path;
+use rs_peer_workspace_shared::stat::ensure_space;
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::{join_remote_path, progress, remote_parent, write_local_chunk};
+use super::tree::{create_local_entry, list_local_directory};
+use super::types::{BottomTab, CopySource, CopyTask, PendingAction, TaskKind, TaskStatus};
+
+const MAX_PARALLEL_COPIES: usize = 2;
+
+impl WorkspaceApp {
+    /// Queues a copy of the copied entry into `parent` on `target_connection`, or into
+    /// a local directory when it is `None`. Bytes flow through the client: chunks are
+    /// read from the source connection and written to the target as they arrive.
+    pub fn paste_entry(&mut self, target_connection: Option<&str>, parent: &str) {
+        let Some(source) = self.copy_source.clone() else {
+            return;
+        };
+        if source.is_dir
+            && target_connection == Some(source.connection_name.as_str())
+            && is_within(parent, &source.path)
+        {
+            self.output
+                .error(format!("Cannot copy {} into itself", source.path));
+            return;
+        }
+        let name = display_name_for_path(&source.path);
+        let target_path = join_target_path(target_connection, parent, &name);
+        self.queue_copy(source, target_connection.map(str::to_string), target_path);
+        self.active_bottom_tab = BottomTab::Transfers;
+        self.pump_copy_queue();
+    }
+
+    fn queue_copy(&mut self, source: CopySource, target_connection: Option<String>, target_path: String) {
+        let id = Uuid::new_v4();
+        self.task_manager.queue(
+            id,
+            TaskKind::Copy,
+            format!("⇄ [{}] {} to {target_path}", source.connection_name, source.path),
+        );
+        self.copies.push(CopyTask {
+            id,
+            source,
+            target_connection,
+            target_path,
+            total_size: None,
+            transferred: 0,
+            next_offset: 0,
+            in_flight: 0,
+            tuner: ChunkTuner::default(),
+            started: None,
+            status: TaskStatus::Queued,
+        });
+    }
+
+    fn pump_copy_queue(&mut self) {
+        let running = self
+            .copies
+            .iter()
+            .filter(|task| task.status == TaskStatus::Running)
+            .count();
+        let next: Vec<Uuid> = self
+            .copies
+            .iter()
+            .filter(|task| task.status == TaskStatus::Queued)
+            .take(MAX_PARALLEL_COPIES.saturating_sub(running))
+            .map(|task| task.id)
+            .collect();
+        for copy_id in next {
+            self.start_copy(copy_id);
+        }
+    }
+
+    /// Creates the target first so an existing file or folder is never overwritten.
+    fn start_copy(&mut self, copy_id: Uuid) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        task.status = TaskStatus::Running;
+        task.started = Some(Instant::now());
+        self.task_manager.run(copy_id);
+        let is_dir = task.source.is_dir;
+        let path = task.target_path.clone();
+        match task.target_connection.clone() {
+            None => match create_local_entry(Path::new(&path), is_dir) {
+                Ok(()) => self.copy_target_created(copy_id),
+                Err(err) => self.fail_copy(copy_id, format!("failed to create {path}: {err}")),
+            },
+            Some(connection_name) => {
+                let request_id = Uuid::new_v4();
+                self.pending
+                    .insert(request_id, PendingAction::CreateCopyTarget { copy_id });
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::CreateFile { path, is_dir },
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn copy_target_created(&mut self, copy_id: Uuid) {
+        let Some(task) = self.copies.iter().find(|task| task.id == copy_id) else {
+            return;
+        };
+        if task.status != TaskStatus::Running {
+            return;
+        }
+        if !task.source.is_dir {
+            self.pump_copy(copy_id);
+            return;
+        }
+        let connection_name = task.source.connection_name.clone();
+        let path = task.source.path.clone();
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::ListCopySource { copy_id });
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListDirectory {
+                    path,
+                    filter: ListFilter::default(),
+                },
+            },
+        );
+    }
+
+    /// A folder copy finishes once its target exists and its children are queued.
+    pub fn copy_source_listed(&mut self, copy_id: Uuid, entries: Vec<DirectoryEntry>) {
+        let Some(task) = self.copies.iter().find(|task| task.id == copy_id) else {
+            return;
+        };
+        if task.status != TaskStatus::Running {
+            return;
+        }
+        let connection_name = task.source.connection_name.clone();
+        let target_connection = task.target_connection.clone();
+        let target_path = task.target_path.clone();
+        for entry in entries {
+            let child_target = join_target_path(target_connection.as_deref(), &target_path, &entry.name);
+            self.queue_copy(
+                CopySource {
+                    connection_name: connection_name.clone(),
+                    path: entry.path,
+                    is_dir: entry.is_dir,
+                },
+                target_connection.clone(),
+                child_target,
+            );
+        }
+        self.complete_copy(copy_id);
+    }
+
+    fn pump_copy(&mut self, copy_id: Uuid) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        if task.status != TaskStatus::Running {
+            return;
+        }
+
+        let mut requests = Vec::new();
+        loop {
+            let awaiting_first = task.next_offset > 0 && task.transferred == 0;
+            let remaining = task
+                .total_size
+                .map(|total| total.saturating_sub(task.next_offset));
+            let started = task.next_offset > 0 || task.in_flight > 0;
+            if task.in_flight >= task.tuner.window()
+                || awaiting_first
+                || (started && remaining.is_none_or(|remaining| remaining == 0))
+            {
+                break;
+            }
+
+            let offset = task.next_offset;
+            let length = remaining.map_or(task.tuner.chunk_size(), |remaining| {
+                remaining.min(task.tuner.chunk_size())
+            });
+            task.next_offset += length;
+            task.in_flight += 1;
+            requests.push((offset, length));
+            if task.total_size.is_none() {
+                break;
+            }
+        }
+
+        let connection_name = task.source.connection_name.clone();
+        let path = task.source.path.clone();
+        for (offset, length) in requests {
+            let request_id = Uuid::new_v4();
+            self.pending.insert(
+                request_id,
+                PendingAction::ReadCopyChunk {
+                    copy_id,
+                    offset,
+                    length,
+                    sent_at: Instant::now(),
+                },
+            );
+            self.send_rpc(
+                &connection_name,
+                RpcRequest {
+                    request_id,
+                    action: RpcAction::ReadFileChunk {
+                        path: path.clone(),
+                        offset,
+                        length,
+                    },
+                },
+            );
+        }
+    }
+
+    /// Forwards a chunk read from the source to the target. The chunk stays in flight
+    /// until the target has written it, so the window bounds both legs.
+    pub fn handle_copy_read(
+        &mut self,
+        copy_id: Uuid,
+        offset: u64,
+        length: u64,
+        sent_at: Instant,
+        result: RpcResult,
+    ) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        let data = match result {
+            RpcResult::FileChunk {
+                data,
+                total_size,
+                checksum,
+                ..
+            } => {
+                task.total_size = Some(total_size);
+                let received = data.len() as u64;
+                if received < length && offset + received < total_size {
+                    Err("source file ended early".to_string())
+                } else if checksum.as_ref().is_some_and(|checksum| *checksum != content_hash(&data)) {
+                    Err(format!("chunk at offset {offset} failed its checksum"))
+                } else {
+                    Ok((data, checksum))
+                }
+            }
+            RpcResult::Error { message } => Err(message),
+            _ => Err("unexpected response to copy chunk".to_string()),
+        };
+        if task.status != TaskStatus::Running {
+            task.in_flight = task.in_flight.saturating_sub(1);
+            return;
+        }
+
+        let (data, checksum) = match data {
+            Ok(chunk) => chunk,
+            Err(message) => {
+                self.finish_copy_chunk(copy_id, sent_at, Err(message));
+                return;
+            }
+        };
+        let path = task.target_path.clone();
+        let total_size = (offset == 0).then_some(task.total_size).flatten();
+        match task.target_connection.clone() {
+            None => {
+                let outcome = total_size
+                    .map_or(Ok(()), |total| ensure_space(Path::new(&path), total))
+                    .and_then(|()| write_local_chunk(&path, offset, &data))
+                    .map(|()| data.len() as u64)
+                    .map_err(|err| err.to_string());
+                self.finish_copy_chunk(copy_id, sent_at, outcome);
+            }
+            Some(_) if data.is_empty() => self.finish_copy_chunk(copy_id, sent_at, Ok(0)),
+            Some(connection_name) => {
+                let request_id = Uuid::new_v4();
+                self.pending
+                    .insert(request_id, PendingAction::WriteCopyChunk { copy_id, sent_at });
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::WriteFileChunk {
+                            path,
+                            offset,
+                            data,
+                            truncate: offset == 0,
+                            total_size,
+                            checksum,
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn handle_copy_write(&mut self, copy_id: Uuid, sent_at: Instant, result: RpcResult) {
+        let outcome = match result {
+            RpcResult::ChunkWritten { length, .. } => Ok(length),
+            RpcResult::Error { message } => Err(message),
+            _ => Err("unexpected response to copy chunk".to_string()),
+        };
+        self.finish_copy_chunk(copy_id, sent_at, outcome);
+    }
+
+    fn finish_copy_chunk(&mut self, copy_id: Uuid, sent_at: Instant, outcome: Result<u64, String>) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        task.in_flight = task.in_flight.saturating_sub(1);
+        if task.status != TaskStatus::Running {
+            return;
+        }
+        match outcome {
+            Ok(bytes) => {
+                task.transferred += bytes;
+                task.tuner.record(bytes, sent_at.elapsed());
+                self.task_manager
+                    .progress(copy_id, progress(task.total_size, task.transferred));
+                if task.total_size.is_some_and(|total| task.transferred >= total) {
+                    self.complete_copy(copy_id);
+                } else {
+                    self.pump_copy(copy_id);
+                }
+            }
+            Err(message) => {
+                task.tuner.record_failure();
+                self.fail_copy(copy_id, message);
+            }
+        }
+    }
+
+    fn complete_copy(&mut self, copy_id: Uuid) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        task.status = TaskStatus::Completed;
+        let message = if task.source.is_dir {
+            format!("Created folder {}", task.target_path)
+        } else {
+            format!("Copied {} to {} ({} bytes)", task.source.path, task.target_path, task.transferred)
+        };
+        let target_connection = task.target_connection.clone();
+        let target_path = task.target_path.clone();
+        self.task_manager.complete(copy_id, Some(message));
+        match target_connection {
+            Some(connection_name) => {
+                self.refresh_remote_directory(&connection_name, &remote_parent(&target_path));
+            }
+            None => {
+                if let Some(parent) = Path::new(&target_path).parent() {
+                    let parent = parent.to_string_lossy().to_string();
+                    if self.explorer_cache.contains_key(&parent) {
+                        let entries = list_local_directory(&parent, &self.project.explorer.list_filter())
+                            .unwrap_or_default();
+                        self.explorer_cache.insert(parent, entries);
+                    }
+                }
+            }
+        }
+        self.pump_copy_queue();
+    }
+
+    pub fn fail_copy(&mut self, copy_id: Uuid, message: String) {
+        let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) else {
+            return;
+        };
+        self.task_manager.fail(copy_id, message.clone());
+        task.status = TaskStatus::Failed(message);
+        self.pump_copy_queue();
+    }
+
+    pub fn cancel_copy(&mut self, copy_id: Uuid) {
+        if let Some(task) = self.copies.iter_mut().find(|task| task.id == copy_id) {
+            task.status = TaskStatus::Cancelled;
+        }
+        self.task_manager.cancelled(copy_id);
+        self.pump_copy_queue();
+    }
+
+    /// Fails queued and running copies that read from or write to `connection_name`.
+    pub fn fail_copies(&mut self, connection_name: &str, reason: &str) {
+        for task in &mut self.copies {
+            let involved = task.source.connection_name == connection_name
+                || task.target_connection.as_deref() == Some(connection_name);
+            if involved
+                && matches!(task.status, TaskStatus::Queued | TaskStatus::Running)
+            {
+                task.status = TaskStatus::Failed(reason.to_string());
+                self.task_manager.fail(task.id, reason.to_string());
+            }
+        }
+        self.pump_copy_queue();
+    }
+}
+
+fn join_target_path(target_connection: Option<&str>, parent: &str, name: &str) -> String {
+    match target_connection {
+        Some(_) => join_remote_path(parent, name),
+        None => Path::new(parent).join(name).to_string_lossy().to_string(),
+    }
+}
+
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    let ancestor = ancestor.trim_end_matches(['/', '\\']);
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::PathBuf;
+
+use eframe::egui;
+use rs_peer_workspace_shared::crash;
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource, EditorTab};
+
+use super::state::WorkspaceApp;
+
+enum CrashAction {
+    Open,
+    Copy,
+    Dismiss,
+}
+
+impl WorkspaceApp {
+    /// Offers the crash reports left by earlier runs until the user opens or
+    /// dismisses them.
+    pub fn draw_crash_reports(&mut self, ctx: &egui::Context) {
+        let Some(newest) = self.crash_reports.first().cloned() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new(self.i18n.tr("crash-title"))
+            .id(egui::Id::new("crash-reports"))
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr("crash-message"));
+                ui.monospace(newest.display().to_string());
+                if self.crash_reports.len() > 1 {
+                    ui.weak(self.i18n.tr_args(
+                        "crash-older",
+                        &[("count", (self.crash_reports.len() - 1).into())],
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(self.i18n.tr("crash-open")).clicked() {
+                        action = Some(CrashAction::Open);
+                    }
+                    if ui.button(self.i18n.tr("crash-copy")).clicked() {
+                        action = Some(CrashAction::Copy);
+                    }
+                    if ui.button(self.i18n.tr("crash-dismiss")).clicked() {
+                        action = Some(CrashAction::Dismiss);
+                    }
+                });
+            });
+        if !open {
+            action = Some(CrashAction::Dismiss);
+        }
+
+        match action {
+            Some(CrashAction::Open) => {
+                let Some(report) = self.acknowledge_crash_reports() else {
+                    return;
+                };
+                let path = report.to_string_lossy().to_string();
+                match fs::read_to_string(&report) {
+                    Ok(content) => {
+                        self.open_files.push(EditorTab {
+                            title: display_name_for_path(&path),
+                            path,
+                            source: EditorSource::Local,
+                            content,
+                            dirty: false,
+                            remote_hash: None,
+                            base: None,
+                            cursor: 0,
+                            restore_cursor: false,
+                            preview: false,
+                            media: None,
+                        });
+                        self.show_editor(self.open_files.len() - 1);
+                    }
+                    Err(err) => self.output.error(format!("Failed to read {path}: {err}")),
+                }
+            }
+            Some(CrashAction::Copy) => match fs::read_to_string(&newest) {
+                Ok(content) => {
+                    ctx.copy_text(content);
+                    self.output.info(format!(
+                        "Copied crash report {} to the clipboard.",
+                        newest.display()
+                    ));
+                }
+                Err(err) => self
+                    .output
+                    .error(format!("Failed to read {}: {err}", newest.display())),
+            },
+            Some(CrashAction::Dismiss) => {
+                self.acknowledge_crash_reports();
+            }
+            None => {}
+        }
+    }
+
+    /// Moves every pending report aside so it is not offered again, returning the new
+    /// path of the newest one.
+    pub fn acknowledge_crash_reports(&mut self) -> Option<PathBuf> {
+        let mut newest = None;
+        for (idx, report) in std::mem::take(&mut self.crash_reports).into_iter().enumerate() {
+            match crash::acknowledge(&report) {
+                Ok(seen) if idx == 0 => newest = Some(seen),
+                Ok(_) => {}
+                Err(err) => self.output.error(format!(
+                    "Failed to move crash report {}: {err}",
+                    report.display()
+                )),
+            }
+        }
+        newest
+    }
+}
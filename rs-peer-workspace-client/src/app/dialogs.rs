@@ -1,59 +1,196 @@
 use eframe::egui;
 use rfd::FileDialog;
+use rs_peer_workspace_core::credentials;
+use rs_peer_workspace_shared::project::{IceServer, OutboundProxyKind};
+use rs_peer_workspace_shared::secrets::unseal_credentials;
 
+use super::commands::AppCommand;
+use super::connections::{connection_test_message, server_details, CONNECTED};
+use super::env::draw_env_editor;
+use super::i18n::Language;
 use super::state::WorkspaceApp;
+use super::types::{
+    CommandPalette, ElevateForm, ExplorerOptionsForm, GitCommitForm, JournalOperation, NewEntryForm,
+    PassphraseForm, QuickOpen, RenameForm,
+};
 
 impl WorkspaceApp {
     pub fn handle_shortcuts(&mut self, ctx: &egui::Context) {
         if ctx.input(|input| input.modifiers.ctrl && input.key_pressed(egui::Key::S)) {
             self.save_active_editor();
         }
+        if ctx.input(|input| {
+            input.modifiers.ctrl && !input.modifiers.shift && input.key_pressed(egui::Key::P)
+        }) {
+            self.open_quick_open();
+        }
+        if ctx.input(|input| {
+            input.modifiers.ctrl && input.modifiers.shift && input.key_pressed(egui::Key::P)
+        }) {
+            self.open_command_palette();
+        }
+        if ctx.input_mut(|input| {
+            input.consume_key(egui::Modifiers::SHIFT | egui::Modifiers::ALT, egui::Key::F)
+        }) {
+            self.run_command(AppCommand::FormatDocument);
+        }
+        if ctx.input_mut(|input| {
+            input.consume_key(egui::Modifiers::CTRL | egui::Modifiers::SHIFT, egui::Key::F)
+        }) {
+            self.run_command(AppCommand::SearchWorkspace);
+        }
+        self.handle_macro_shortcuts(ctx);
+        if ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+            self.close_top_dialog();
+        }
+    }
+
+    /// Escape closes the most recently layered dialog, so every dialog can be left
+    /// without a pointer.
+    fn close_top_dialog(&mut self) {
+        if self.unsaved_prompt.is_some() {
+            if !self.unsaved_prompt.as_ref().is_some_and(|prompt| prompt.saving) {
+                self.unsaved_prompt = None;
+            }
+        } else if self.command_palette.open {
+            self.command_palette = CommandPalette::default();
+        } else if self.quick_open.open {
+            self.quick_open = QuickOpen::default();
+        } else if self.history_search.is_some() {
+            self.history_search = None;
+        } else if self.diff_view.is_some() {
+            self.diff_view = None;
+        } else if self.merge_view.is_some() {
+            self.merge_view = None;
+        } else if self.file_viewer.is_some() {
+            self.file_viewer = None;
+        } else if self.passphrase_form.open {
+            self.passphrase_form = PassphraseForm::default();
+        } else if self.elevate_form.open {
+            self.elevate_form = ElevateForm::default();
+        } else if self.remote_picker.open {
+            self.remote_picker.open = false;
+        } else if self.connection_form.browsing {
+            self.connection_form.browsing = false;
+        } else if self.show_add_connection {
+            self.show_add_connection = false;
+        } else if self.show_add_folder {
+            self.show_add_folder = false;
+        } else if self.show_new_terminal {
+            self.show_new_terminal = false;
+        } else if self.show_terminal_profiles {
+            self.show_terminal_profiles = false;
+        } else if self.show_notebook {
+            self.show_notebook = false;
+        } else if self.show_tasks {
+            self.show_tasks = false;
+        } else if self.show_run_configs {
+            self.show_run_configs = false;
+        } else if self.show_broadcast {
+            self.show_broadcast = false;
+        } else if self.macros.show {
+            self.macros.show = false;
+        } else if self.show_settings {
+            self.show_settings = false;
+        } else if self.new_entry_form.folder.is_some() {
+            self.new_entry_form = NewEntryForm::default();
+        } else if !self.rename_form.from.is_empty() {
+            self.rename_form = RenameForm::default();
+        } else if self.git_commit.folder.is_some() {
+            self.git_commit = GitCommitForm::default();
+        } else if self.properties.is_some() {
+            self.properties = None;
+        } else if self.explorer_options.open {
+            self.explorer_options = ExplorerOptionsForm::default();
+        } else if !self.crash_reports.is_empty() {
+            self.acknowledge_crash_reports();
+        }
     }
 
     pub fn draw_menu(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("menu").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Create Project").clicked() {
-                        self.reset_project();
-                        ui.close_menu();
+                ui.menu_button(self.i18n.tr("menu-file"), |ui| {
+                    self.command_button(ui, AppCommand::CreateProject);
+                    self.command_button(ui, AppCommand::OpenProject);
+                    self.command_button(ui, AppCommand::SaveProject);
+                    ui.separator();
+                    self.command_button(ui, AppCommand::Settings);
+                    ui.separator();
+                    self.command_button(ui, AppCommand::EncryptCredentials);
+                    if self.project_passphrase.is_some() {
+                        self.command_button(ui, AppCommand::RemoveEncryption);
                     }
-                    if ui.button("Open Project").clicked() {
-                        if let Some(path) = FileDialog::new()
-                            .add_filter("RS Peer Workspace", &["rpw"])
-                            .pick_file()
-                        {
-                            self.open_project(path);
+                });
+
+                ui.menu_button(self.i18n.tr("menu-edit"), |ui| {
+                    self.command_button(ui, AppCommand::Save);
+                    self.command_button(ui, AppCommand::SaveAll);
+                    self.command_button(ui, AppCommand::FormatDocument);
+                    ui.menu_button(self.i18n.tr("menu-formatting"), |ui| {
+                        self.draw_format_menu(ui);
+                    });
+                    self.command_button(ui, AppCommand::QuickOpen);
+                    self.command_button(ui, AppCommand::SearchWorkspace);
+                    let description = self.journal.last().map(|entry| match &entry.operation {
+                        JournalOperation::Delete { path, .. } => {
+                            self.i18n.tr_args("undo-delete", &[("path", path.clone().into())])
                         }
-                        ui.close_menu();
-                    }
-                    if ui.button("Save Project").clicked() {
-                        self.save_project();
-                        ui.close_menu();
+                        JournalOperation::Rename { from, to, .. } => self.i18n.tr_args(
+                            "undo-rename",
+                            &[("from", from.clone().into()), ("to", to.clone().into())],
+                        ),
+                    });
+                    let undo = self.command_button(ui, AppCommand::UndoOperation);
+                    if let Some(description) = description {
+                        undo.on_hover_text(description);
                     }
+                    self.command_button(ui, AppCommand::AddConnection);
+                    self.command_button(ui, AppCommand::AddFolder);
+                    self.command_button(ui, AppCommand::Broadcast);
+                    ui.separator();
+                    self.command_button(ui, AppCommand::RecordMacro);
+                    self.command_button(ui, AppCommand::Macros);
                 });
 
-                ui.menu_button("Edit", |ui| {
-                    if ui.button("Save").clicked() {
-                        self.save_active_editor();
-                        ui.close_menu();
-                    }
-                    if ui.button("Add Connection").clicked() {
-                        self.show_add_connection = true;
-                        ui.close_menu();
-                    }
-                    if ui.button("Add Folder").clicked() {
-                        self.show_add_folder = true;
-                        ui.close_menu();
-                    }
+                ui.menu_button(self.i18n.tr("menu-view"), |ui| {
+                    self.command_button(ui, AppCommand::CommandPalette);
+                    ui.separator();
+                    self.command_button(ui, AppCommand::SplitVertical);
+                    self.command_button(ui, AppCommand::SplitHorizontal);
+                    self.command_button(ui, AppCommand::Unsplit);
+                    ui.separator();
+                    self.command_button(ui, AppCommand::ToggleBottomPanel);
+                    self.command_button(ui, AppCommand::ExplorerOptions);
+                    ui.menu_button(self.i18n.tr("menu-notifications"), |ui| {
+                        self.draw_notification_menu(ui);
+                    });
+                    ui.menu_button(self.i18n.tr("menu-language"), |ui| {
+                        for language in Language::ALL {
+                            if ui
+                                .selectable_label(
+                                    self.i18n.language() == language,
+                                    language.native_name(),
+                                )
+                                .clicked()
+                            {
+                                self.run_command(AppCommand::SetLanguage(language));
+                                ui.close_menu();
+                            }
+                        }
+                    });
                 });
 
-                ui.menu_button("Terminal", |ui| {
-                    if ui.button("New Terminal").clicked() {
-                        self.show_new_terminal = true;
-                        ui.close_menu();
-                    }
+                ui.menu_button(self.i18n.tr("menu-terminal"), |ui| {
+                    self.command_button(ui, AppCommand::NewTerminal);
+                    self.command_button(ui, AppCommand::TerminalProfiles);
+                    self.command_button(ui, AppCommand::Notebook);
+                    self.command_button(ui, AppCommand::Tasks);
+                    self.command_button(ui, AppCommand::RunConfigs);
                 });
+
+                ui.separator();
+                self.draw_run_toolbar(ui);
             });
         });
     }
@@ -64,33 +201,302 @@ impl WorkspaceApp {
         }
 
         let mut open = self.show_add_connection;
-        egui::Window::new("Add Connection")
+        let mut fetch_servers = false;
+        let mut test_connection = false;
+        let editing = self.connection_form.editing.is_some();
+        let title = if editing {
+            self.i18n.tr("edit-connection-title")
+        } else {
+            self.i18n.tr("add-connection-title")
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("add-connection"))
             .open(&mut open)
             .resizable(false)
             .show(ctx, |ui| {
-                ui.label("Custom Name");
-                ui.text_edit_singleline(&mut self.connection_form.name);
-                ui.label("Proxy Address");
-                ui.text_edit_singleline(&mut self.connection_form.proxy_addr);
-                ui.label("Proxy Password");
+                let label = ui.label(self.i18n.tr("add-connection-name"));
+                ui.add_enabled(!editing, egui::TextEdit::singleline(&mut self.connection_form.name))
+                    .labelled_by(label.id)
+                    .on_disabled_hover_text(self.i18n.tr("edit-connection-name-fixed"));
+                let label = ui.label(self.i18n.tr("add-connection-proxy-address"));
+                ui.text_edit_singleline(&mut self.connection_form.proxy_addr).labelled_by(label.id);
+                if !editing {
+                    self.draw_discovered_proxies(ui);
+                }
+                let label = ui.label(self.i18n.tr("add-connection-proxy-username"));
+                ui.text_edit_singleline(&mut self.connection_form.proxy_username)
+                    .labelled_by(label.id)
+                    .on_hover_text(self.i18n.tr("add-connection-proxy-username-hint"));
+                let password_label = if self.connection_form.proxy_username.trim().is_empty() {
+                    "add-connection-proxy-password"
+                } else {
+                    "add-connection-user-password"
+                };
+                let label = ui.label(self.i18n.tr(password_label));
                 ui.add(
                     egui::TextEdit::singleline(&mut self.connection_form.proxy_password)
                         .password(true),
-                );
-                ui.label("Server Name");
-                ui.text_edit_singleline(&mut self.connection_form.server_name);
-                ui.label("Server Password");
+                )
+                .labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("add-connection-server-name"));
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.connection_form.server_name)
+                        .labelled_by(label.id);
+                    if ui.button(self.i18n.tr("add-connection-browse")).clicked() {
+                        self.connection_form.browsing = true;
+                        fetch_servers = true;
+                    }
+                });
+                if let Some(server) = self
+                    .connection_form
+                    .servers
+                    .iter()
+                    .find(|server| server.name == self.connection_form.server_name)
+                {
+                    ui.weak(server_details(&self.i18n, server));
+                }
+                let label = ui.label(self.i18n.tr("add-connection-server-password"));
                 ui.add(
                     egui::TextEdit::singleline(&mut self.connection_form.server_password)
                         .password(true),
-                );
-                ui.checkbox(&mut self.connection_form.prefer_p2p, "Try P2P first");
-                if ui.button("Add").clicked() {
-                    self.add_connection();
-                    self.show_add_connection = false;
+                )
+                .labelled_by(label.id);
+                ui.checkbox(&mut self.connection_form.prefer_p2p, self.i18n.tr("add-connection-prefer-p2p"));
+                ui.checkbox(&mut self.connection_form.require_e2e, self.i18n.tr("add-connection-require-e2e"))
+                    .on_hover_text(self.i18n.tr("add-connection-require-e2e-hint"));
+                egui::CollapsingHeader::new(self.i18n.tr("add-connection-outbound"))
+                    .default_open(self.connection_form.outbound_kind.is_some())
+                    .show(ui, |ui| {
+                        let label = ui.label(self.i18n.tr("add-connection-outbound-type"));
+                        let kind_label = |kind: Option<OutboundProxyKind>| match kind {
+                            None => self.i18n.tr("add-connection-outbound-none"),
+                            Some(OutboundProxyKind::Http) => "HTTP".to_string(),
+                            Some(OutboundProxyKind::Socks5) => "SOCKS5".to_string(),
+                        };
+                        egui::ComboBox::from_id_salt("connection-outbound-kind")
+                            .selected_text(kind_label(self.connection_form.outbound_kind))
+                            .show_ui(ui, |ui| {
+                                for kind in std::iter::once(None).chain(OutboundProxyKind::ALL.map(Some)) {
+                                    ui.selectable_value(
+                                        &mut self.connection_form.outbound_kind,
+                                        kind,
+                                        kind_label(kind),
+                                    );
+                                }
+                            })
+                            .response
+                            .labelled_by(label.id);
+                        ui.add_enabled_ui(self.connection_form.outbound_kind.is_some(), |ui| {
+                            let label = ui.label(self.i18n.tr("add-connection-outbound-address"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.connection_form.outbound_addr)
+                                    .hint_text("proxy.example.com:3128"),
+                            )
+                            .labelled_by(label.id);
+                            let label = ui.label(self.i18n.tr("add-connection-outbound-username"));
+                            ui.text_edit_singleline(&mut self.connection_form.outbound_username)
+                                .labelled_by(label.id);
+                            let label = ui.label(self.i18n.tr("add-connection-outbound-password"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.connection_form.outbound_password)
+                                    .password(true),
+                            )
+                            .labelled_by(label.id);
+                        });
+                    });
+                egui::CollapsingHeader::new(self.i18n.tr("add-connection-ice"))
+                    .default_open(!self.connection_form.ice_servers.is_empty())
+                    .show(ui, |ui| {
+                        ui.weak(self.i18n.tr("add-connection-ice-hint"));
+                        let mut remove = None;
+                        egui::Grid::new("connection-ice-servers").show(ui, |ui| {
+                            for (idx, server) in self.connection_form.ice_servers.iter_mut().enumerate() {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut server.url)
+                                        .hint_text("turn:relay.example.com:3478")
+                                        .desired_width(200.0),
+                                );
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut server.username)
+                                        .hint_text(self.i18n.tr("add-connection-ice-username"))
+                                        .desired_width(100.0),
+                                );
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut server.password)
+                                        .hint_text(self.i18n.tr("add-connection-ice-password"))
+                                        .password(true)
+                                        .desired_width(100.0),
+                                );
+                                if ui.small_button(self.i18n.tr("add-connection-ice-remove")).clicked() {
+                                    remove = Some(idx);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if let Some(idx) = remove {
+                            self.connection_form.ice_servers.remove(idx);
+                        }
+                        if ui.button(self.i18n.tr("add-connection-ice-add")).clicked() {
+                            self.connection_form.ice_servers.push(IceServer::default());
+                        }
+                    });
+                egui::CollapsingHeader::new(self.i18n.tr("add-connection-env"))
+                    .default_open(!self.connection_form.env.is_empty())
+                    .show(ui, |ui| {
+                        ui.weak(self.i18n.tr("add-connection-env-hint"));
+                        draw_env_editor(ui, &self.i18n, "connection-env", &mut self.connection_form.env);
+                    });
+                ui.add_enabled(
+                    credentials::AVAILABLE,
+                    egui::Checkbox::new(&mut self.connection_form.use_keyring, self.i18n.tr("add-connection-keyring")),
+                )
+                .on_hover_text(self.i18n.tr("add-connection-keyring-hint"))
+                .on_disabled_hover_text(self.i18n.tr("add-connection-keyring-unavailable"));
+                let submit = if editing {
+                    self.i18n.tr("edit-connection-submit")
+                } else {
+                    self.i18n.tr("add-connection-submit")
+                };
+                ui.horizontal(|ui| {
+                    if ui.button(submit).clicked() {
+                        self.add_connection();
+                        self.show_add_connection = false;
+                    }
+                    let test = ui
+                        .add_enabled(!self.connection_form.testing, egui::Button::new(self.i18n.tr("add-connection-test")))
+                        .on_hover_text(self.i18n.tr("add-connection-test-hint"));
+                    test_connection = test.clicked();
+                    if self.connection_form.testing {
+                        ui.spinner();
+                    }
+                });
+                if let Some(result) = &self.connection_form.test_result {
+                    let custom_ice = !self.connection_form.ice_servers.is_empty();
+                    let (ok, message) = connection_test_message(&self.i18n, result, custom_ice);
+                    let color = if ok {
+                        CONNECTED
+                    } else {
+                        ui.visuals().error_fg_color
+                    };
+                    ui.colored_label(color, message);
                 }
             });
         self.show_add_connection = open;
+        if fetch_servers {
+            self.list_proxy_servers();
+        }
+        if test_connection {
+            self.test_connection_form();
+        }
+    }
+
+    pub fn draw_banners(&mut self, ctx: &egui::Context) {
+        if self.banners.is_empty() {
+            return;
+        }
+
+        let mut dismiss = None;
+        egui::TopBottomPanel::top("banners").show(ctx, |ui| {
+            for (idx, banner) in self.banners.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(ui.visuals().warn_fg_color, "📢");
+                    ui.strong(format!("[{}]", banner.connection_name));
+                    ui.label(&banner.message);
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button(self.i18n.tr("banner-dismiss")).clicked() {
+                            dismiss = Some(idx);
+                        }
+                    });
+                });
+            }
+        });
+        if let Some(idx) = dismiss {
+            self.banners.remove(idx);
+        }
+    }
+
+    pub fn draw_broadcast(&mut self, ctx: &egui::Context) {
+        if !self.show_broadcast {
+            return;
+        }
+
+        let mut open = self.show_broadcast;
+        egui::Window::new(self.i18n.tr("broadcast-title"))
+            .id(egui::Id::new("broadcast"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("broadcast-connection")
+                    .selected_text(if self.broadcast_form.connection_name.is_empty() {
+                        self.i18n.tr("select-connection")
+                    } else {
+                        self.broadcast_form.connection_name.clone()
+                    })
+                    .show_ui(ui, |ui| {
+                        for connection in &self.project.connections {
+                            ui.selectable_value(
+                                &mut self.broadcast_form.connection_name,
+                                connection.name.clone(),
+                                &connection.name,
+                            );
+                        }
+                    });
+                let label = ui.label(self.i18n.tr("broadcast-message"));
+                ui.text_edit_multiline(&mut self.broadcast_form.message).labelled_by(label.id);
+                if ui.button(self.i18n.tr("broadcast-send")).clicked() {
+                    self.send_broadcast();
+                    self.show_broadcast = false;
+                }
+            });
+        self.show_broadcast = open;
+    }
+
+    pub fn draw_new_entry(&mut self, ctx: &egui::Context) {
+        if self.new_entry_form.folder.is_none() {
+            return;
+        }
+
+        let mut open = true;
+        let mut create = false;
+        let title = if self.new_entry_form.is_dir {
+            self.i18n.tr("new-entry-folder-title")
+        } else {
+            self.i18n.tr("new-entry-file-title")
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("new-entry"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr_args(
+                    "new-entry-parent",
+                    &[("path", self.new_entry_form.parent.clone().into())],
+                ));
+                let label = ui.label(self.i18n.tr("new-entry-name"));
+                let response = ui
+                    .text_edit_singleline(&mut self.new_entry_form.name)
+                    .labelled_by(label.id);
+                if self.new_entry_form.name.is_empty() {
+                    response.request_focus();
+                }
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    create = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(self.i18n.tr("new-entry-create")).clicked() {
+                        create = true;
+                    }
+                    if ui.button(self.i18n.tr("cancel")).clicked() {
+                        self.new_entry_form = NewEntryForm::default();
+                    }
+                });
+            });
+        if create {
+            self.create_entry();
+        }
+        if !open {
+            self.new_entry_form = NewEntryForm::default();
+        }
     }
 
     pub fn draw_add_folder(&mut self, ctx: &egui::Context) {
@@ -99,23 +505,24 @@ impl WorkspaceApp {
         }
 
         let mut open = self.show_add_folder;
-        egui::Window::new("Add Folder")
+        egui::Window::new(self.i18n.tr("add-folder-title"))
+            .id(egui::Id::new("add-folder"))
             .open(&mut open)
             .resizable(false)
             .show(ctx, |ui| {
-                ui.label("Display Name");
-                ui.text_edit_singleline(&mut self.folder_form.name);
+                let label = ui.label(self.i18n.tr("add-folder-name"));
+                ui.text_edit_singleline(&mut self.folder_form.name).labelled_by(label.id);
                 ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.folder_form.is_remote, false, "Local");
-                    ui.radio_value(&mut self.folder_form.is_remote, true, "Remote");
+                    ui.radio_value(&mut self.folder_form.is_remote, false, self.i18n.tr("folder-local"));
+                    ui.radio_value(&mut self.folder_form.is_remote, true, self.i18n.tr("folder-remote"));
                 });
 
                 if self.folder_form.is_remote {
                     egui::ComboBox::from_id_salt("remote-connection")
                         .selected_text(if self.folder_form.remote_connection_name.is_empty() {
-                            "Select connection"
+                            self.i18n.tr("select-connection")
                         } else {
-                            &self.folder_form.remote_connection_name
+                            self.folder_form.remote_connection_name.clone()
                         })
                         .show_ui(ui, |ui| {
                             for connection in &self.project.connections {
@@ -133,22 +540,55 @@ impl WorkspaceApp {
                         });
                     ui.horizontal(|ui| {
                         ui.text_edit_singleline(&mut self.folder_form.remote_path);
-                        if ui.button("Browse").clicked() {
+                        if ui.button(self.i18n.tr("browse")).clicked() {
                             self.open_remote_picker();
                         }
                     });
+                    let connection_name = self.folder_form.remote_connection_name.clone();
+                    let mut remote_path = self.folder_form.remote_path.clone();
+                    self.draw_path_suggestions(ui, &connection_name, &mut remote_path);
+                    self.folder_form.remote_path = remote_path;
+
+                    ui.label(self.i18n.tr("default-terminal-profile"));
+                    egui::ComboBox::from_id_salt("folder-terminal-profile")
+                        .selected_text(if self.folder_form.terminal_profile.is_empty() {
+                            self.i18n.tr("none")
+                        } else {
+                            self.folder_form.terminal_profile.clone()
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.folder_form.terminal_profile,
+                                String::new(),
+                                self.i18n.tr("none"),
+                            );
+                            if let Some(connection) = self
+                                .project
+                                .connections
+                                .iter()
+                                .find(|connection| connection.name == connection_name)
+                            {
+                                for profile in &connection.terminal_profiles {
+                                    ui.selectable_value(
+                                        &mut self.folder_form.terminal_profile,
+                                        profile.name.clone(),
+                                        &profile.name,
+                                    );
+                                }
+                            }
+                        });
                 } else {
                     ui.horizontal(|ui| {
                         ui.text_edit_singleline(&mut self.folder_form.local_path);
-                        if ui.button("Browse").clicked() {
-                            if let Some(path) = FileDialog::new().pick_folder() {
-                                self.folder_form.local_path = path.to_string_lossy().to_string();
-                            }
+                        if ui.button(self.i18n.tr("browse")).clicked()
+                            && let Some(path) = FileDialog::new().pick_folder()
+                        {
+                            self.folder_form.local_path = path.to_string_lossy().to_string();
                         }
                     });
                 }
 
-                if ui.button("Add Folder").clicked() {
+                if ui.button(self.i18n.tr("add-folder-submit")).clicked() {
                     self.add_folder();
                     self.show_add_folder = false;
                 }
@@ -162,15 +602,17 @@ impl WorkspaceApp {
         }
 
         let mut open = self.show_new_terminal;
-        egui::Window::new("New Terminal")
+        let mut load_containers = false;
+        egui::Window::new(self.i18n.tr("new-terminal-title"))
+            .id(egui::Id::new("new-terminal"))
             .open(&mut open)
             .resizable(false)
             .show(ctx, |ui| {
                 egui::ComboBox::from_id_salt("terminal-connection")
                     .selected_text(if self.terminal_form.connection_name.is_empty() {
-                        "Select connection"
+                        self.i18n.tr("select-connection")
                     } else {
-                        &self.terminal_form.connection_name
+                        self.terminal_form.connection_name.clone()
                     })
                     .show_ui(ui, |ui| {
                         for connection in &self.project.connections {
@@ -182,15 +624,82 @@ impl WorkspaceApp {
                                 .clicked()
                             {
                                 self.terminal_form.connection_name = connection.name.clone();
+                                self.terminal_form.profile_name.clear();
+                                self.terminal_form.container.clear();
+                                self.terminal_form.containers.clear();
+                                load_containers = true;
+                            }
+                        }
+                    });
+                let profiles = self
+                    .project
+                    .connections
+                    .iter()
+                    .find(|connection| connection.name == self.terminal_form.connection_name)
+                    .map(|connection| connection.terminal_profiles.clone())
+                    .unwrap_or_default();
+                if !profiles.is_empty() {
+                    egui::ComboBox::from_id_salt("terminal-profile")
+                        .selected_text(if self.terminal_form.profile_name.is_empty() {
+                            self.i18n.tr("default-shell")
+                        } else {
+                            self.terminal_form.profile_name.clone()
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.terminal_form.profile_name,
+                                String::new(),
+                                self.i18n.tr("default-shell"),
+                            );
+                            for profile in &profiles {
+                                ui.selectable_value(
+                                    &mut self.terminal_form.profile_name,
+                                    profile.name.clone(),
+                                    &profile.name,
+                                );
                             }
+                        });
+                }
+                if !self.terminal_form.connection_name.is_empty() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("terminal-container")
+                            .selected_text(if self.terminal_form.container.is_empty() {
+                                self.i18n.tr("terminal-host")
+                            } else {
+                                self.terminal_form.container.clone()
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut self.terminal_form.container,
+                                    String::new(),
+                                    self.i18n.tr("terminal-host"),
+                                );
+                                for container in &self.terminal_form.containers {
+                                    ui.selectable_value(
+                                        &mut self.terminal_form.container,
+                                        container.name.clone(),
+                                        format!("{} ({})", container.name, container.image),
+                                    )
+                                    .on_hover_text(&container.status);
+                                }
+                            });
+                        if ui.button(self.i18n.tr("refresh")).clicked() {
+                            load_containers = true;
+                        }
+                        if self.terminal_form.loading_containers {
+                            ui.spinner();
                         }
                     });
-                if ui.button("Open Terminal").clicked() {
+                }
+                if ui.button(self.i18n.tr("new-terminal-open")).clicked() {
                     self.create_terminal();
                     self.show_new_terminal = false;
                 }
             });
         self.show_new_terminal = open;
+        if load_containers {
+            self.load_terminal_containers();
+        }
     }
 
     pub fn draw_remote_picker(&mut self, ctx: &egui::Context) {
@@ -199,24 +708,157 @@ impl WorkspaceApp {
         }
 
         let mut open = self.remote_picker.open;
-        egui::Window::new("Remote Folder Picker")
+        egui::Window::new(self.i18n.tr("remote-picker-title"))
+            .id(egui::Id::new("remote-picker"))
             .open(&mut open)
             .default_size([520.0, 420.0])
             .show(ctx, |ui| {
-                ui.label(format!("Connection: {}", self.remote_picker.connection_name));
-                let roots = self.remote_picker.roots.clone();
+                ui.label(self.i18n.tr_args(
+                    "connection-name",
+                    &[("name", self.remote_picker.connection_name.clone().into())],
+                ));
+                self.draw_picker_address(ui);
+                let roots = match &self.remote_picker.location {
+                    Some(location) => vec![location.clone()],
+                    None => self.remote_picker.roots.clone(),
+                };
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     for root in roots {
                         self.render_picker_node(ui, &root, 0);
                     }
                 });
                 ui.separator();
-                ui.label(format!("Selected: {}", self.remote_picker.selected_path));
-                if ui.button("Use Folder").clicked() {
-                    self.folder_form.remote_path = self.remote_picker.selected_path.clone();
-                    self.remote_picker.open = false;
+                ui.horizontal(|ui| {
+                    let label = ui.label(self.i18n.tr("remote-picker-selected"));
+                    ui.text_edit_singleline(&mut self.remote_picker.selected_path).labelled_by(label.id);
+                });
+                let connection_name = self.remote_picker.connection_name.clone();
+                let mut selected_path = self.remote_picker.selected_path.clone();
+                self.draw_path_suggestions(ui, &connection_name, &mut selected_path);
+                if selected_path != self.remote_picker.selected_path {
+                    self.remote_picker.selected_path = selected_path;
+                    self.remote_picker.selected_is_file = false;
+                }
+
+                let mut create = false;
+                let picker = &mut self.remote_picker;
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut picker.show_files, self.i18n.tr("remote-picker-show-files"));
+                    let can_create = !picker.selected_path.trim().is_empty() && !picker.selected_is_file;
+                    if ui
+                        .add_enabled(can_create, egui::Button::new(self.i18n.tr("explorer-new-folder")))
+                        .clicked()
+                    {
+                        picker.new_folder = Some(String::new());
+                    }
+                });
+                if let Some(name) = &mut picker.new_folder {
+                    let mut cancel = false;
+                    ui.horizontal(|ui| {
+                        let label = ui.label(self.i18n.tr("remote-picker-folder-name"));
+                        let response = ui.text_edit_singleline(name).labelled_by(label.id);
+                        if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                            create = true;
+                        }
+                        if ui.button(self.i18n.tr("new-entry-create")).clicked() {
+                            create = true;
+                        }
+                        if ui.button(self.i18n.tr("cancel")).clicked() {
+                            cancel = true;
+                        }
+                    });
+                    if cancel {
+                        picker.new_folder = None;
+                    }
                 }
+                if create {
+                    self.create_picker_folder();
+                }
+
+                ui.horizontal(|ui| {
+                    let picker = &self.remote_picker;
+                    if picker.selected_is_file {
+                        if ui.button(self.i18n.tr("remote-picker-open-file")).clicked() {
+                            self.open_picked_file();
+                        }
+                    } else if ui.button(self.i18n.tr("remote-picker-use")).clicked() {
+                        self.folder_form.remote_path = picker.selected_path.clone();
+                        self.remote_picker.open = false;
+                    }
+                });
             });
         self.remote_picker.open = open;
     }
+
+    pub fn draw_passphrase(&mut self, ctx: &egui::Context) {
+        if !self.passphrase_form.open {
+            return;
+        }
+
+        let unlocking = self.passphrase_form.locked_project.is_some();
+        let mut open = self.passphrase_form.open;
+        let mut submit = false;
+        egui::Window::new(self.i18n.tr(if unlocking {
+            "passphrase-unlock-title"
+        } else {
+            "passphrase-encrypt-title"
+        }))
+            .id(egui::Id::new("passphrase"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if unlocking {
+                    ui.label(self.i18n.tr("passphrase-unlock-prompt"));
+                } else {
+                    ui.label(self.i18n.tr("passphrase-encrypt-prompt"));
+                }
+                let label = ui.label(self.i18n.tr("passphrase"));
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.passphrase_form.passphrase).password(true),
+                )
+                .labelled_by(label.id);
+                if !unlocking {
+                    let label = ui.label(self.i18n.tr("passphrase-confirm"));
+                    ui.add(egui::TextEdit::singleline(&mut self.passphrase_form.confirm).password(true)).labelled_by(label.id);
+                }
+                if let Some(error) = &self.passphrase_form.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                let enter = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if ui.button(self.i18n.tr(if unlocking {
+                    "passphrase-unlock"
+                } else {
+                    "passphrase-encrypt-and-save"
+                })).clicked() || enter {
+                    submit = true;
+                }
+            });
+        self.passphrase_form.open = open;
+        if !submit {
+            return;
+        }
+
+        let passphrase = self.passphrase_form.passphrase.clone();
+        if let Some((path, mut project)) = self.passphrase_form.locked_project.take() {
+            match unseal_credentials(&mut project, &passphrase) {
+                Ok(()) => {
+                    self.passphrase_form = PassphraseForm::default();
+                    self.load_project(path, project, Some(passphrase));
+                }
+                Err(err) => {
+                    self.passphrase_form.error = Some(err.to_string());
+                    self.passphrase_form.locked_project = Some((path, project));
+                }
+            }
+        } else if passphrase.is_empty() {
+            self.passphrase_form.error = Some(self.i18n.tr("passphrase-empty"));
+        } else if passphrase != self.passphrase_form.confirm {
+            self.passphrase_form.error = Some(self.i18n.tr("passphrase-mismatch"));
+        } else {
+            self.passphrase_form = PassphraseForm::default();
+            self.project_passphrase = Some(passphrase);
+            self.save_project();
+        }
+    }
 }
@@ -0,0 +1,210 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::diff::{DiffOp, TextDiff};
+use uuid::Uuid;
+
+use super::i18n::Localizer;
+use super::state::WorkspaceApp;
+use super::types::{DiffView, PendingAction};
+
+/// Unchanged runs longer than this are folded to their first and last lines.
+const CONTEXT_LINES: usize = 3;
+
+impl WorkspaceApp {
+    pub fn request_diff(&mut self, connection_name: &str, path: &str) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadDiff {
+                path: path.to_string(),
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ReadFile {
+                    path: path.to_string(),
+                    known_hash: None,
+                },
+            },
+        );
+    }
+
+    pub fn open_diff(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        remote: &str,
+        remote_hash: Option<String>,
+    ) {
+        let Some(tab) = self.open_files.iter_mut().find(|tab| tab.path == path) else {
+            return;
+        };
+        let diff = TextDiff::new(&tab.content, remote);
+        if diff.is_identical() {
+            tab.remote_hash = remote_hash;
+            tab.base = Some(remote.to_string());
+            self.output
+                .connection_info(connection_name, format!("{path} matches the remote file"));
+            return;
+        }
+        self.diff_view = Some(DiffView {
+            connection_name: connection_name.to_string(),
+            path,
+            remote_hash,
+            take_remote: vec![false; diff.hunk_count()],
+            diff,
+            inline: false,
+        });
+    }
+
+    pub fn draw_diff_view(&mut self, ctx: &egui::Context) {
+        let Some(view) = &mut self.diff_view else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = None;
+        egui::Window::new(self.i18n.tr_args("diff-title", &[("path", view.path.clone().into())]))
+            .id(egui::Id::new("diff-view"))
+            .open(&mut open)
+            .default_size([900.0, 560.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut view.inline, false, self.i18n.tr("diff-side-by-side"));
+                    ui.selectable_value(&mut view.inline, true, self.i18n.tr("diff-inline"));
+                    ui.separator();
+                    if ui.button(self.i18n.tr("diff-accept-all")).clicked() {
+                        view.take_remote.fill(true);
+                    }
+                    if ui.button(self.i18n.tr("diff-keep-all")).clicked() {
+                        view.take_remote.fill(false);
+                    }
+                });
+                ui.label(self.i18n.tr_args(
+                    "diff-summary",
+                    &[
+                        ("count", view.take_remote.len().into()),
+                        ("path", view.path.clone().into()),
+                        ("connection", view.connection_name.clone().into()),
+                    ],
+                ));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() - 40.0)
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        let mut hunk = 0;
+                        for op in &view.diff.ops {
+                            match op {
+                                DiffOp::Equal(lines) => draw_context(ui, &self.i18n, lines),
+                                DiffOp::Change { local, remote } => {
+                                    draw_change(ui, local, remote, view.inline);
+                                    ui.horizontal(|ui| {
+                                        ui.selectable_value(
+                                            &mut view.take_remote[hunk],
+                                            false,
+                                            self.i18n.tr("diff-keep-local"),
+                                        );
+                                        ui.selectable_value(
+                                            &mut view.take_remote[hunk],
+                                            true,
+                                            self.i18n.tr("diff-accept-remote"),
+                                        );
+                                    });
+                                    hunk += 1;
+                                }
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button(self.i18n.tr("diff-apply")).clicked() {
+                        apply = Some(false);
+                    }
+                    if ui.button(self.i18n.tr("diff-apply-save")).clicked() {
+                        apply = Some(true);
+                    }
+                });
+            });
+
+        if let Some(save) = apply {
+            self.apply_diff(save);
+        } else if !open {
+            self.diff_view = None;
+        }
+    }
+
+    fn apply_diff(&mut self, save: bool) {
+        let Some(view) = self.diff_view.take() else {
+            return;
+        };
+        let Some(idx) = self.open_files.iter().position(|tab| tab.path == view.path) else {
+            self.output
+                .error(format!("{} is no longer open", view.path));
+            return;
+        };
+        let merged = view.diff.merge(|hunk| view.take_remote[hunk]);
+        let tab = &mut self.open_files[idx];
+        tab.remote_hash = view.remote_hash;
+        tab.base = Some(view.diff.merge(|_| true));
+        if tab.content != merged {
+            tab.content = merged;
+            tab.dirty = true;
+        }
+        if save {
+            self.save_editor(idx);
+        }
+    }
+}
+
+fn draw_context(ui: &mut egui::Ui, i18n: &Localizer, lines: &[String]) {
+    let show = |ui: &mut egui::Ui, lines: &[String]| {
+        for line in lines {
+            ui.monospace(line.trim_end_matches(['\r', '\n']));
+        }
+    };
+    if lines.len() <= CONTEXT_LINES * 2 + 1 {
+        show(ui, lines);
+    } else {
+        show(ui, &lines[..CONTEXT_LINES]);
+        ui.weak(i18n.tr_args(
+            "diff-unchanged-lines",
+            &[("count", (lines.len() - CONTEXT_LINES * 2).into())],
+        ));
+        show(ui, &lines[lines.len() - CONTEXT_LINES..]);
+    }
+}
+
+fn draw_change(ui: &mut egui::Ui, local: &[String], remote: &[String], inline: bool) {
+    let removed = egui::Color32::from_rgba_unmultiplied(200, 60, 60, 60);
+    let added = egui::Color32::from_rgba_unmultiplied(60, 160, 60, 60);
+    let line = |ui: &mut egui::Ui, prefix: &str, text: &str, color: egui::Color32| {
+        ui.label(
+            egui::RichText::new(format!("{prefix}{}", text.trim_end_matches(['\r', '\n'])))
+                .monospace()
+                .background_color(color),
+        );
+    };
+
+    if inline {
+        for text in local {
+            line(ui, "- ", text, removed);
+        }
+        for text in remote {
+            line(ui, "+ ", text, added);
+        }
+    } else {
+        ui.columns(2, |columns| {
+            for text in local {
+                line(&mut columns[0], "", text, removed);
+            }
+            for text in remote {
+                line(&mut columns[1], "", text, added);
+            }
+        });
+    }
+}
@@ -1,42 +1,77 @@
 use eframe::egui;
+use rs_peer_workspace_shared::lsp::Severity;
 use rs_peer_workspace_shared::project::EditorSource;
 
+use super::a11y::icon_button;
+use super::git::{editor_connection, paint_git_gutter};
+use super::lsp::{diagnostic_line, diagnostics_at, paint_diagnostics, severity_color};
+use super::rich::render_attachment;
 use super::state::WorkspaceApp;
-use super::types::BottomTab;
+use super::types::{BottomTab, EditorPane, GuardedAction, HistorySearch, SplitOrientation};
 
 impl WorkspaceApp {
     pub fn draw_bottom(&mut self, ctx: &egui::Context) {
+        if !self.show_bottom_panel {
+            return;
+        }
         egui::TopBottomPanel::bottom("bottom")
             .resizable(true)
             .default_height(220.0)
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
-                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Output, "Output");
-                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Tasks, "Tasks");
+                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Output, self.i18n.tr("bottom-output"));
+                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Tasks, self.i18n.tr("bottom-tasks"));
                     ui.selectable_value(
                         &mut self.active_bottom_tab,
                         BottomTab::Terminal,
-                        "Terminal",
+                        self.i18n.tr("bottom-terminal"),
+                    );
+                    ui.selectable_value(
+                        &mut self.active_bottom_tab,
+                        BottomTab::Services,
+                        self.i18n.tr("bottom-services"),
+                    );
+                    ui.selectable_value(
+                        &mut self.active_bottom_tab,
+                        BottomTab::Transfers,
+                        self.i18n.tr("bottom-transfers"),
+                    );
+                    ui.selectable_value(
+                        &mut self.active_bottom_tab,
+                        BottomTab::Search,
+                        self.i18n.tr("bottom-search"),
+                    );
+                    ui.selectable_value(
+                        &mut self.active_bottom_tab,
+                        BottomTab::WorkspaceSearch,
+                        self.i18n.tr("bottom-workspace-search"),
+                    );
+                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Logs, self.i18n.tr("bottom-logs"));
+                    ui.selectable_value(&mut self.active_bottom_tab, BottomTab::Ports, self.i18n.tr("bottom-ports"));
+                    ui.selectable_value(
+                        &mut self.active_bottom_tab,
+                        BottomTab::Processes,
+                        self.i18n.tr("bottom-processes"),
                     );
                 });
                 ui.separator();
 
                 match self.active_bottom_tab {
-                    BottomTab::Output => {
-                        egui::ScrollArea::vertical().show(ui, |ui| {
-                            for line in &self.output_lines {
-                                ui.label(line);
-                            }
-                        });
-                    }
+                    BottomTab::Output => self.draw_output(ui),
                     BottomTab::Tasks => {
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            for line in &self.task_lines {
-                                ui.label(line);
-                            }
+                            self.draw_pipelines(ui);
+                            self.draw_task_list(ui);
                         });
                     }
                     BottomTab::Terminal => self.draw_terminal_tabs(ui),
+                    BottomTab::Services => self.draw_services(ui),
+                    BottomTab::Transfers => self.draw_transfers(ui),
+                    BottomTab::Search => self.draw_search(ui),
+                    BottomTab::WorkspaceSearch => self.draw_workspace_search(ui),
+                    BottomTab::Logs => self.draw_log_viewer(ui),
+                    BottomTab::Ports => self.draw_ports(ui),
+                    BottomTab::Processes => self.draw_processes(ui),
                 }
             });
     }
@@ -44,67 +79,390 @@ impl WorkspaceApp {
     pub fn draw_editor(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.open_files.is_empty() {
-                ui.heading("No file open");
-                ui.label("Open a .txt, .py, or .m file from the explorer.");
+                ui.heading(self.i18n.tr("editor-empty-title"));
+                ui.label(self.i18n.tr("editor-empty-hint"));
                 return;
             }
 
-            ui.horizontal_wrapped(|ui| {
-                for (idx, tab) in self.open_files.iter().enumerate() {
-                    let title = if tab.dirty {
-                        format!("{}*", tab.title)
-                    } else {
-                        tab.title.clone()
-                    };
-                    if ui
-                        .selectable_label(self.selected_editor == Some(idx), title)
-                        .clicked()
+            match self.editor_split {
+                None => self.draw_editor_pane(ui, EditorPane::Primary),
+                Some(SplitOrientation::Vertical) => {
+                    ui.columns(2, |columns| {
+                        self.draw_editor_pane(&mut columns[0], EditorPane::Primary);
+                        self.draw_editor_pane(&mut columns[1], EditorPane::Secondary);
+                    });
+                }
+                Some(SplitOrientation::Horizontal) => {
+                    let size = egui::vec2(ui.available_width(), (ui.available_height() - 12.0) / 2.0);
+                    ui.allocate_ui(size, |ui| self.draw_editor_pane(ui, EditorPane::Primary));
+                    ui.separator();
+                    ui.allocate_ui(size, |ui| self.draw_editor_pane(ui, EditorPane::Secondary));
+                }
+            }
+        });
+    }
+
+    fn draw_editor_pane(&mut self, ui: &mut egui::Ui, pane: EditorPane) {
+        let selected = self.pane_editor(pane);
+        let split = self.editor_split.is_some();
+        let mut tab_action = None;
+        ui.horizontal_wrapped(|ui| {
+            for (idx, tab) in self.open_files.iter().enumerate() {
+                let title = if tab.dirty {
+                    format!("{}*", tab.title)
+                } else {
+                    tab.title.clone()
+                };
+                let title = if tab.preview {
+                    egui::RichText::new(title).italics()
+                } else {
+                    egui::RichText::new(title)
+                };
+                let response = ui
+                    .selectable_label(selected == Some(idx), title)
+                    .interact(egui::Sense::drag())
+                    .on_hover_text(&tab.path);
+                response.dnd_set_drag_payload(idx);
+                if let Some(from) = response.dnd_release_payload::<usize>() {
+                    tab_action = Some(TabAction::Move(*from, idx));
+                } else if response.double_clicked() {
+                    tab_action = Some(TabAction::Keep(idx));
+                } else if response.clicked() {
+                    tab_action = Some(TabAction::Select(idx));
+                } else if response.middle_clicked() {
+                    tab_action = Some(TabAction::Close(vec![tab.path.clone()]));
+                }
+                response.context_menu(|ui| {
+                    if split && ui.button(self.i18n.tr("tab-show-in-other-pane")).clicked() {
+                        tab_action = Some(TabAction::ShowInOtherPane(idx));
+                        ui.close_menu();
+                    }
+                    if ui.button(self.i18n.tr("tab-close")).clicked() {
+                        tab_action = Some(TabAction::Close(vec![tab.path.clone()]));
+                        ui.close_menu();
+                    }
+                    if ui.button(self.i18n.tr("tab-close-others")).clicked() {
+                        tab_action = Some(TabAction::Close(
+                            self.open_files
+                                .iter()
+                                .filter(|other| other.path != tab.path)
+                                .map(|other| other.path.clone())
+                                .collect(),
+                        ));
+                        ui.close_menu();
+                    }
+                    if ui.button(self.i18n.tr("tab-close-all")).clicked() {
+                        tab_action = Some(TabAction::Close(
+                            self.open_files.iter().map(|other| other.path.clone()).collect(),
+                        ));
+                        ui.close_menu();
+                    }
+                });
+                if icon_button(
+                    ui,
+                    "x",
+                    &self.i18n.tr_args("tab-close-named", &[("title", tab.title.clone().into())]),
+                ).clicked() {
+                    tab_action = Some(TabAction::Close(vec![tab.path.clone()]));
+                }
+                ui.add_space(6.0);
+            }
+        });
+        match tab_action {
+            Some(TabAction::Select(idx)) => {
+                *self.pane_editor_mut(pane) = Some(idx);
+                self.focused_pane = pane;
+            }
+            Some(TabAction::Keep(idx)) => {
+                if let Some(tab) = self.open_files.get_mut(idx) {
+                    tab.preview = false;
+                }
+                *self.pane_editor_mut(pane) = Some(idx);
+                self.focused_pane = pane;
+            }
+            Some(TabAction::ShowInOtherPane(idx)) => {
+                *self.pane_editor_mut(pane.other()) = Some(idx);
+            }
+            Some(TabAction::Move(from, to)) => self.move_editor(from, to),
+            Some(TabAction::Close(paths)) => {
+                self.guard_unsaved(GuardedAction::CloseTabs(paths));
+            }
+            None => {}
+        }
+        ui.separator();
+
+        let mut save_clicked = false;
+        let mut lock_action = None;
+        let mut compare = None;
+        let mut resolve = None;
+        let mut focus = false;
+        let mut lsp_target = None;
+        let mut pointer_index = None;
+        let mut definition = false;
+        if let Some(idx) = self.pane_editor(pane)
+            && let Some(tab) = self.open_files.get(idx)
+            && let Some(media) = tab.media.clone()
+        {
+            let path = tab.path.clone();
+            self.draw_media_preview(ui, &path, &media);
+        } else if let Some(idx) = self.pane_editor(pane)
+            && let Some(tab) = self.open_files.get_mut(idx)
+        {
+            ui.horizontal(|ui| {
+                if split && self.focused_pane == pane {
+                    ui.strong(&tab.path);
+                } else {
+                    ui.label(&tab.path);
+                }
+                if let EditorSource::Remote { connection_name } = &tab.source {
+                    let transport = self
+                        .connections
+                        .get(connection_name)
+                        .map(|state| state.transport.clone())
+                        .unwrap_or_else(|| self.i18n.tr("transport-disconnected"));
+                    ui.separator();
+                    ui.label(self.i18n.tr_args("editor-transport", &[("transport", transport.into())]));
+                    ui.separator();
+                    match self
+                        .file_locks
+                        .get(&(connection_name.clone(), tab.path.clone()))
                     {
-                        self.selected_editor = Some(idx);
+                        Some(lock) if lock.mine => {
+                            ui.label(self.i18n.tr_args(
+                                "lock-held-by-me",
+                                &[("until", self.i18n.clock(lock.expires_at).into())],
+                            ));
+                            if ui.button(self.i18n.tr("lock-release")).clicked() {
+                                lock_action = Some((connection_name.clone(), tab.path.clone(), false));
+                            }
+                        }
+                        Some(lock) => {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                self.i18n.tr_args(
+                                    "lock-held-by-other",
+                                    &[
+                                        ("owner", lock.owner.clone().into()),
+                                        ("since", self.i18n.clock(lock.acquired_at).into()),
+                                    ],
+                                ),
+                            );
+                        }
+                        None => {
+                            if ui.button(self.i18n.tr("lock-acquire")).clicked() {
+                                lock_action = Some((connection_name.clone(), tab.path.clone(), true));
+                            }
+                        }
                     }
+                    if let Some(diagnostics) = self
+                        .lsp_diagnostics
+                        .get(&(connection_name.clone(), tab.path.clone()))
+                        && !diagnostics.is_empty()
+                    {
+                        let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+                        let summary = diagnostics
+                            .iter()
+                            .take(20)
+                            .map(diagnostic_line)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        ui.separator();
+                        ui.label(self.i18n.tr_args(
+                            "lsp-problems",
+                            &[
+                                ("errors", count(Severity::Error).into()),
+                                ("warnings", count(Severity::Warning).into()),
+                            ],
+                        ))
+                        .on_hover_text(summary);
+                    }
+                }
+                if let EditorSource::Remote { connection_name } = &tab.source
+                    && ui.button(self.i18n.tr("editor-compare")).clicked()
+                {
+                    compare = Some((connection_name.clone(), tab.path.clone()));
+                }
+                if tab.content.contains("\n=======")
+                    && tab.content.contains("<<<<<<<")
+                    && ui.button(self.i18n.tr("editor-resolve-conflicts")).clicked()
+                {
+                    resolve = Some(idx);
+                }
+                if ui.button(self.i18n.tr("editor-save")).clicked() {
+                    save_clicked = true;
                 }
             });
-            ui.separator();
 
-            let mut save_clicked = false;
-            if let Some(idx) = self.selected_editor {
-                if let Some(tab) = self.open_files.get_mut(idx) {
-                    ui.horizontal(|ui| {
-                        ui.label(&tab.path);
-                        if let EditorSource::Remote { connection_name } = &tab.source {
-                            let transport = self
-                                .connections
-                                .get(connection_name)
-                                .map(|state| state.transport.clone())
-                                .unwrap_or_else(|| "Disconnected".to_string());
-                            ui.separator();
-                            ui.label(format!("Transport: {transport}"));
+            let editor_id = egui::Id::new(("editor", pane, &tab.path));
+            if tab.restore_cursor {
+                let mut state = egui::text_edit::TextEditState::load(ui.ctx(), editor_id)
+                    .unwrap_or_default();
+                let cursor = egui::text::CCursor::new(tab.cursor);
+                state
+                    .cursor
+                    .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+                state.store(ui.ctx(), editor_id);
+                tab.restore_cursor = false;
+            }
+            let font_id = self.settings.editor_font_id();
+            let word_wrap = self.settings.word_wrap;
+            let indent = ui.memory(|memory| memory.has_focus(editor_id))
+                && ui.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Tab));
+            let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                let job = egui::text::LayoutJob::simple(
+                    text.to_owned(),
+                    font_id.clone(),
+                    ui.visuals().widgets.inactive.text_color(),
+                    if word_wrap { wrap_width } else { f32::INFINITY },
+                );
+                ui.fonts(|fonts| fonts.layout_job(job))
+            };
+            let gutter = self
+                .git_gutters
+                .get(&(editor_connection(&tab.source), tab.path.clone()));
+            let remote = editor_connection(&tab.source);
+            let diagnostics = remote
+                .clone()
+                .and_then(|connection_name| self.lsp_diagnostics.get(&(connection_name, tab.path.clone())));
+            let hover = self
+                .lsp_hover
+                .as_ref()
+                .filter(|hover| remote.as_deref() == Some(hover.connection_name.as_str()) && hover.path == tab.path)
+                .and_then(|hover| Some((hover.index, hover.text.as_deref()?)));
+            lsp_target = remote.clone().map(|connection_name| (connection_name, tab.path.clone()));
+            let scroll = if word_wrap {
+                egui::ScrollArea::vertical()
+            } else {
+                egui::ScrollArea::both()
+            };
+            scroll
+                .id_salt(("editor-scroll", pane))
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    let output = egui::TextEdit::multiline(&mut tab.content)
+                        .id(editor_id)
+                        .desired_rows(32)
+                        .desired_width(f32::INFINITY)
+                        .code_editor()
+                        .margin(egui::Margin {
+                            left: 10,
+                            right: 4,
+                            top: 2,
+                            bottom: 2,
+                        })
+                        .layouter(&mut layouter)
+                        .show(ui);
+                    if let Some(changes) = gutter {
+                        paint_git_gutter(
+                            ui,
+                            output.response.rect,
+                            &output.galley,
+                            output.galley_pos,
+                            changes,
+                        );
+                    }
+                    if let Some(diagnostics) = diagnostics {
+                        paint_diagnostics(
+                            ui,
+                            output.response.rect,
+                            &output.galley,
+                            output.galley_pos,
+                            &tab.content,
+                            diagnostics,
+                        );
+                    }
+                    if remote.is_some()
+                        && let Some(pointer) = output.response.hover_pos()
+                    {
+                        let index = output.galley.cursor_from_pos(pointer - output.galley_pos).ccursor.index;
+                        pointer_index = Some(index);
+                        let problems = diagnostics
+                            .map(|diagnostics| diagnostics_at(&tab.content, diagnostics, index))
+                            .unwrap_or_default();
+                        let text = hover.filter(|(at, _)| *at == index).map(|(_, text)| text);
+                        if !problems.is_empty() || text.is_some() {
+                            output.response.clone().on_hover_ui_at_pointer(|ui| {
+                                ui.set_max_width(480.0);
+                                for diagnostic in &problems {
+                                    ui.colored_label(severity_color(ui, diagnostic.severity), diagnostic_line(diagnostic));
+                                }
+                                if let Some(text) = text {
+                                    if !problems.is_empty() {
+                                        ui.separator();
+                                    }
+                                    ui.label(egui::RichText::new(text).monospace());
+                                }
+                            });
                         }
-                        if ui.button("Save").clicked() {
-                            save_clicked = true;
+                        if output.response.clicked() && ui.input(|input| input.modifiers.command) {
+                            definition = true;
                         }
-                    });
-
-                    let response = ui.add(
-                        egui::TextEdit::multiline(&mut tab.content)
-                            .desired_rows(32)
-                            .code_editor(),
-                    );
-                    if response.changed() {
+                    }
+                    if output.response.has_focus() && ui.input(|input| input.key_pressed(egui::Key::F12)) {
+                        definition = true;
+                    }
+                    if output.response.changed() {
                         tab.dirty = true;
+                        tab.preview = false;
                     }
-                }
+                    if output.response.has_focus() {
+                        focus = true;
+                    }
+                    if let Some(range) = output.cursor_range {
+                        tab.cursor = range.primary.ccursor.index;
+                    }
+                });
+            if indent {
+                // Tab inserts the configured number of spaces instead of a tab character.
+                let byte = tab
+                    .content
+                    .char_indices()
+                    .nth(tab.cursor)
+                    .map_or(tab.content.len(), |(byte, _)| byte);
+                let width = self.settings.tab_width;
+                tab.content.insert_str(byte, &" ".repeat(width));
+                tab.cursor += width;
+                tab.dirty = true;
+                tab.preview = false;
+                let mut state = egui::text_edit::TextEditState::load(ui.ctx(), editor_id)
+                    .unwrap_or_default();
+                let cursor = egui::text::CCursor::new(tab.cursor);
+                state
+                    .cursor
+                    .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+                state.store(ui.ctx(), editor_id);
             }
+        } else {
+            ui.label(self.i18n.tr("editor-pane-empty"));
+        }
 
-            if save_clicked {
-                self.save_active_editor();
+        if focus {
+            self.focused_pane = pane;
+        }
+        if let Some((connection_name, path)) = lsp_target {
+            self.lsp_pointer(&connection_name, &path, pointer_index);
+            if definition {
+                self.go_to_definition(&connection_name, &path);
             }
-        });
+        }
+        if save_clicked {
+            self.focused_pane = pane;
+            self.save_active_editor();
+        }
+        if let Some((connection_name, path, lock)) = lock_action {
+            self.send_lock_rpc(&connection_name, &path, lock);
+        }
+        if let Some((connection_name, path)) = compare {
+            self.request_diff(&connection_name, &path);
+        }
+        if let Some(idx) = resolve {
+            self.open_marker_merge(idx);
+        }
     }
 
     pub fn draw_terminal_tabs(&mut self, ui: &mut egui::Ui) {
         if self.terminals.is_empty() {
-            ui.label("No terminal open.");
+            ui.label(self.i18n.tr("terminal-empty"));
             return;
         }
 
@@ -121,30 +479,186 @@ impl WorkspaceApp {
         ui.separator();
 
         let mut run = None;
-        if let Some(idx) = self.selected_terminal {
-            if let Some(term) = self.terminals.get_mut(idx) {
-                ui.label(format!("Connection: {}", term.connection_name));
-                ui.add(
-                    egui::TextEdit::multiline(&mut term.output)
-                        .desired_rows(10)
-                        .interactive(false),
-                );
-                ui.horizontal(|ui| {
-                    let input_width = (ui.available_width() - 80.0).clamp(140.0, 720.0);
-                    ui.add(egui::TextEdit::singleline(&mut term.input).desired_width(input_width));
-                    if ui.button("Run").clicked() {
-                        let command = term.input.trim().to_string();
-                        if !command.is_empty() {
-                            run = Some((idx, command));
-                            term.input.clear();
+        let mut logs = None;
+        let mut history = None;
+        if let Some(idx) = self.selected_terminal
+            && let Some(term) = self.terminals.get_mut(idx)
+        {
+            ui.horizontal(|ui| {
+                ui.label(self.i18n.tr_args(
+                    "connection-name",
+                    &[("name", term.connection_name.clone().into())],
+                ));
+                if let Some(cwd) = term.profile.as_ref().and_then(|profile| profile.cwd.as_ref()) {
+                    ui.separator();
+                    ui.label(self.i18n.tr_args("terminal-cwd", &[("path", cwd.clone().into())]));
+                }
+                if let Some(container) = &term.container {
+                    ui.separator();
+                    ui.label(self.i18n.tr_args(
+                        "terminal-container",
+                        &[("name", container.clone().into())],
+                    ));
+                    if ui.button(self.i18n.tr("logs")).clicked() {
+                        logs = Some(idx);
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.small_button(self.i18n.tr("terminal-expand-all")).clicked() {
+                    term.blocks.iter_mut().for_each(|block| block.collapsed = false);
+                }
+                if ui.small_button(self.i18n.tr("terminal-collapse-all")).clicked() {
+                    term.blocks.iter_mut().for_each(|block| block.collapsed = true);
+                }
+                if ui.small_button(self.i18n.tr("terminal-clear")).clicked() {
+                    term.blocks.retain(|block| block.running);
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt(format!("terminal-blocks:{}", term.id))
+                .max_height(ui.available_height() - 32.0)
+                .auto_shrink([false, true])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for block in &mut term.blocks {
+                        let header = egui::RichText::new(format!("> {}", block.command)).monospace();
+                        let response = egui::CollapsingHeader::new(header)
+                            .id_salt(block.id)
+                            .open(Some(!block.collapsed))
+                            .show(ui, |ui| {
+                                if block.running {
+                                    ui.spinner();
+                                }
+                                if !block.output.is_empty() {
+                                    ui.add(
+                                        egui::Label::new(
+                                            egui::RichText::new(&block.output).monospace(),
+                                        )
+                                        .wrap(),
+                                    );
+                                }
+                                for (attachment_idx, attachment) in
+                                    block.attachments.iter().enumerate()
+                                {
+                                    render_attachment(
+                                        ui,
+                                        &format!("terminal-{}-{attachment_idx}", block.id),
+                                        attachment,
+                                    );
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.small_button(self.i18n.tr("terminal-copy")).clicked() {
+                                        ui.ctx().copy_text(block.output.clone());
+                                    }
+                                    if block.rerunnable
+                                        && ui
+                                            .add_enabled(
+                                                !block.running,
+                                                egui::Button::new(self.i18n.tr("terminal-rerun")).small(),
+                                            )
+                                            .clicked()
+                                    {
+                                        run = Some((idx, block.command.clone()));
+                                    }
+                                });
+                            });
+                        if response.header_response.clicked() {
+                            block.collapsed = !block.collapsed;
                         }
                     }
                 });
-            }
+            ui.horizontal(|ui| {
+                let input_width = (ui.available_width() - 120.0).clamp(140.0, 720.0);
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut term.input)
+                        .id(egui::Id::new(("terminal-input", term.id)))
+                        .hint_text(self.i18n.tr("terminal-command"))
+                        .desired_width(input_width),
+                );
+                input.widget_info(|| {
+                    egui::WidgetInfo::labeled(
+                        egui::WidgetType::TextEdit,
+                        true,
+                        self.i18n
+                            .tr_args("terminal-command-for", &[("title", term.title.clone().into())]),
+                    )
+                });
+                if input.changed() {
+                    term.history.reset();
+                }
+                if input.has_focus() {
+                    let (up, down, search) = ui.input(|input| {
+                        (
+                            input.key_pressed(egui::Key::ArrowUp),
+                            input.key_pressed(egui::Key::ArrowDown),
+                            input.modifiers.ctrl && input.key_pressed(egui::Key::R),
+                        )
+                    });
+                    let recalled = if up {
+                        term.history.older(&term.input)
+                    } else if down {
+                        term.history.newer()
+                    } else {
+                        None
+                    };
+                    if let Some(command) = recalled {
+                        term.input = command.to_string();
+                        let mut state = egui::text_edit::TextEditState::load(ui.ctx(), input.id)
+                            .unwrap_or_default();
+                        let end = egui::text::CCursor::new(term.input.chars().count());
+                        state
+                            .cursor
+                            .set_char_range(Some(egui::text::CCursorRange::one(end)));
+                        state.store(ui.ctx(), input.id);
+                    }
+                    if search {
+                        history = Some(term.id);
+                    }
+                }
+                let submitted =
+                    input.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if ui
+                    .button("⟲")
+                    .on_hover_text(self.i18n.tr("terminal-history"))
+                    .clicked()
+                {
+                    history = Some(term.id);
+                }
+                if ui.button(self.i18n.tr("run")).clicked() || submitted {
+                    let command = term.input.trim().to_string();
+                    if !command.is_empty() {
+                        run = Some((idx, command));
+                        term.input.clear();
+                    }
+                    if submitted {
+                        input.request_focus();
+                    }
+                }
+            });
         }
 
         if let Some((idx, command)) = run {
             self.run_terminal(idx, command);
         }
+        if let Some(idx) = logs {
+            self.load_container_logs(idx);
+        }
+        if let Some(terminal_id) = history {
+            self.history_search = Some(HistorySearch {
+                terminal_id,
+                focus: true,
+                ..Default::default()
+            });
+        }
     }
 }
+
+enum TabAction {
+    Select(usize),
+    /// Selects the tab and turns it from a preview into a regular tab.
+    Keep(usize),
+    ShowInOtherPane(usize),
+    Move(usize, usize),
+    Close(Vec<String>),
+}
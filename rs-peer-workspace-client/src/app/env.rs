@@ -0,0 +1,64 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{merge_env, EnvVar};
+
+use super::i18n::Localizer;
+use super::state::WorkspaceApp;
+
+impl WorkspaceApp {
+    /// Environment for a command on `connection_name`: the connection's variables
+    /// with `overrides` set over them.
+    pub fn command_env(&self, connection_name: &str, overrides: &[EnvVar]) -> Vec<EnvVar> {
+        let base = self
+            .project
+            .connections
+            .iter()
+            .find(|connection| connection.name == connection_name)
+            .map(|connection| connection.env.as_slice())
+            .unwrap_or_default();
+        merge_env(base, overrides)
+    }
+}
+
+/// Editable rows of environment variables. Values marked secret are masked like
+/// passwords.
+pub fn draw_env_editor(ui: &mut egui::Ui, i18n: &Localizer, id_salt: &str, env: &mut Vec<EnvVar>) {
+    let mut remove = None;
+    egui::Grid::new(id_salt).show(ui, |ui| {
+        for (idx, var) in env.iter_mut().enumerate() {
+            ui.add(
+                egui::TextEdit::singleline(&mut var.name)
+                    .hint_text(i18n.tr("env-name"))
+                    .desired_width(120.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut var.value)
+                    .hint_text(i18n.tr("env-value"))
+                    .password(var.secret)
+                    .desired_width(180.0),
+            );
+            ui.checkbox(&mut var.secret, i18n.tr("env-secret"))
+                .on_hover_text(i18n.tr("env-secret-hint"));
+            if ui.small_button(i18n.tr("remove")).clicked() {
+                remove = Some(idx);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(idx) = remove {
+        env.remove(idx);
+    }
+    if ui.button(i18n.tr("env-add")).clicked() {
+        env.push(EnvVar::default());
+    }
+}
+
+/// `env` without the rows left without a name.
+pub fn clean_env(env: &[EnvVar]) -> Vec<EnvVar> {
+    env.iter()
+        .filter(|var| !var.name.trim().is_empty())
+        .map(|var| EnvVar {
+            name: var.name.trim().to_string(),
+            ..var.clone()
+        })
+        .collect()
+}
@@ -1,38 +1,52 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fs;
+use std::collections::BTreeMap;
+use std::path::Path;
 
 use eframe::egui;
-use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::app::{ListFilter, RpcAction, RpcRequest, RpcResponse, RpcResult};
+use rs_peer_workspace_shared::content::{read_file_data, FileData, MAX_TEXT_LEN};
+use rs_peer_workspace_shared::media::MediaKind;
 use rs_peer_workspace_shared::project::{
-    display_name_for_path, is_text_file, EditorSource, EditorTab, FolderSource, ProjectFolder,
+    display_name_for_path, is_text_file, EditorSource, EditorTab, ExplorerSettings, ExplorerSort,
+    FolderSource, OpenOn, ProjectFolder,
 };
 use uuid::Uuid;
 
+use super::a11y::{expander, tree_item};
 use super::state::WorkspaceApp;
-use super::tree::list_local_directory;
-use super::types::{PendingAction, RemoteFolderPicker, TreeEntry};
+use super::transfers::{format_bytes, join_remote_path, remote_parent};
+use super::copy::is_within;
+use super::git::{git_color, git_letter};
+use super::icons::icon_label;
+use super::tree::{create_local_entry, list_local_directory, sort_entries};
+use super::types::{BottomTab, CopySource, ExplorerOptionsForm, NewEntryForm, PendingAction, RemoteFolderPicker, RenameForm, TreeEntry};
 
 impl WorkspaceApp {
     pub fn draw_explorer(&mut self, ctx: &egui::Context) {
+        self.drop_target = None;
         egui::SidePanel::left("explorer")
             .resizable(true)
             .default_width(280.0)
             .show(ctx, |ui| {
-                ui.heading("Explorer");
+                ui.heading(self.i18n.tr("explorer-title"));
                 ui.separator();
+                self.draw_address_bar(ui);
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.collapsing("Local", |ui| {
-                        let locals: Vec<_> = self
-                            .project
-                            .folders
-                            .iter()
-                            .filter(|folder| matches!(folder.source, FolderSource::Local { .. }))
-                            .cloned()
-                            .collect();
-                        for folder in locals {
-                            self.render_folder_root(ui, &folder);
-                        }
-                    });
+                    self.draw_browsed(ui);
+                    self.draw_pinned(ui);
+                    egui::CollapsingHeader::new(self.i18n.tr("folder-local"))
+                        .id_salt("explorer-local")
+                        .show(ui, |ui| {
+                            let locals: Vec<_> = self
+                                .project
+                                .folders
+                                .iter()
+                                .filter(|folder| matches!(folder.source, FolderSource::Local { .. }))
+                                .cloned()
+                                .collect();
+                            for folder in locals {
+                                self.render_folder_root(ui, &folder);
+                            }
+                        });
 
                     let mut groups: BTreeMap<String, Vec<ProjectFolder>> = BTreeMap::new();
                     for folder in &self.project.folders {
@@ -51,8 +65,77 @@ impl WorkspaceApp {
                             }
                         });
                     }
+
+                    ui.separator();
+                    self.draw_connections(ui);
+                    self.draw_connection_details(ui);
                 });
             });
+        self.upload_dropped_files(ctx);
+    }
+
+    fn track_drop_target(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        folder: &ProjectFolder,
+        path: &str,
+    ) {
+        let FolderSource::Remote { connection_name, .. } = &folder.source else {
+            return;
+        };
+        let dragging = ui.input(|input| {
+            !input.raw.hovered_files.is_empty() || !input.raw.dropped_files.is_empty()
+        });
+        if !dragging || !ui.rect_contains_pointer(response.rect) {
+            return;
+        }
+        ui.painter().rect_stroke(
+            response.rect,
+            2.0,
+            ui.visuals().selection.stroke,
+            egui::StrokeKind::Outside,
+        );
+        self.drop_target = Some((connection_name.clone(), path.to_string()));
+    }
+
+    fn upload_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|input| input.raw.dropped_files.clone());
+        if dropped.is_empty() {
+            return;
+        }
+        let Some((connection_name, remote_dir)) = self.drop_target.take() else {
+            self.output
+                .warn("Drop files onto a remote folder in the explorer to upload them.");
+            return;
+        };
+
+        let mut uploads = 0;
+        for local_path in dropped.into_iter().filter_map(|file| file.path) {
+            if local_path.is_dir() {
+                self.output.warn(format!(
+                    "Skipping folder {}; only files can be dropped for upload",
+                    local_path.display()
+                ));
+                continue;
+            }
+            let name = local_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.start_upload(
+                &connection_name,
+                local_path.to_string_lossy().to_string(),
+                join_remote_path(&remote_dir, &name),
+            );
+            uploads += 1;
+        }
+        if uploads > 0 {
+            self.task_manager.note(format!(
+                "[{connection_name}] uploading {uploads} dropped file(s) to {remote_dir}"
+            ));
+            self.active_bottom_tab = BottomTab::Tasks;
+        }
     }
 
     pub fn render_folder_root(&mut self, ui: &mut egui::Ui, folder: &ProjectFolder) {
@@ -63,27 +146,30 @@ impl WorkspaceApp {
         let is_open = self.explorer_expanded.contains(&id);
 
         ui.horizontal(|ui| {
-            if ui.small_button(if is_open { "v" } else { ">" }).clicked() {
+            if expander(ui, &self.i18n, is_open, &folder.name).clicked() {
                 if is_open {
                     self.explorer_expanded.remove(&id);
                 } else {
                     self.explorer_expanded.insert(id.clone());
                     self.load_children(folder, &root_path);
+                    self.refresh_git_status(folder);
                 }
             }
-            if ui.selectable_label(false, &folder.name).clicked() {
+            icon_label(ui, self.icons.folder(is_open));
+            let response = ui.selectable_label(false, &folder.name);
+            tree_item(&response, Some(is_open));
+            if self.project.explorer.expand_on_label_click && self.activated(&response) {
                 self.explorer_expanded.insert(id.clone());
                 self.load_children(folder, &root_path);
             }
+            self.ensure_git_status(folder);
+            self.draw_git_summary(ui, folder);
+            self.track_drop_target(ui, &response, folder, &root_path);
+            self.entry_menu(&response, folder, &root_path, true);
         });
 
         if self.explorer_expanded.contains(&id) {
-            let children = self
-                .explorer_cache
-                .get(&root_path)
-                .cloned()
-                .unwrap_or_default();
-            for child in children {
+            for child in self.sorted_children(&root_path) {
                 self.render_tree_entry(ui, folder, &child, 1);
             }
         }
@@ -101,7 +187,7 @@ impl WorkspaceApp {
             if entry.is_dir {
                 let id = format!("dir:{}", entry.path);
                 let is_open = self.explorer_expanded.contains(&id);
-                if ui.small_button(if is_open { "v" } else { ">" }).clicked() {
+                if expander(ui, &self.i18n, is_open, &entry.name).clicked() {
                     if is_open {
                         self.explorer_expanded.remove(&id);
                     } else {
@@ -109,31 +195,478 @@ impl WorkspaceApp {
                         self.load_children(folder, &entry.path);
                     }
                 }
-                if ui.selectable_label(false, &entry.name).clicked() {
+                icon_label(ui, self.icons.for_entry(&entry.name, true, entry.is_symlink, is_open));
+                let response = ui.selectable_label(false, self.entry_name(folder, entry));
+                tree_item(&response, Some(is_open));
+                if self.project.explorer.expand_on_label_click && self.activated(&response) {
                     self.explorer_expanded.insert(id.clone());
                     self.load_children(folder, &entry.path);
                 }
+                self.track_drop_target(ui, &response, folder, &entry.path);
+                self.entry_menu(&response, folder, &entry.path, true);
+                self.entry_columns(ui, entry);
             } else {
                 ui.label(" ");
-                if ui.selectable_label(false, &entry.name).clicked() {
-                    self.open_path(folder, &entry.path);
+                icon_label(ui, self.icons.for_entry(&entry.name, false, entry.is_symlink, false));
+                let response = ui.selectable_label(false, self.entry_name(folder, entry));
+                tree_item(&response, None);
+                if self.activated(&response) {
+                    let settings = &self.project.explorer;
+                    // In single-click mode the second click of a double-click keeps the preview.
+                    let keep = settings.open_on == OpenOn::SingleClick && response.double_clicked();
+                    let preview = settings.preview_tabs && !keep;
+                    self.open_path(folder, &entry.path, preview);
                 }
+                self.entry_menu(&response, folder, &entry.path, false);
+                if let Some(state) = self.entry_git_state(folder, &entry.path, false) {
+                    ui.label(egui::RichText::new(git_letter(state)).small().color(git_color(state)));
+                }
+                self.entry_columns(ui, entry);
             }
         });
 
         let id = format!("dir:{}", entry.path);
         if entry.is_dir && self.explorer_expanded.contains(&id) {
-            let children = self
-                .explorer_cache
-                .get(&entry.path)
-                .cloned()
-                .unwrap_or_default();
-            for child in children {
+            for child in self.sorted_children(&entry.path) {
                 self.render_tree_entry(ui, folder, &child, depth + 1);
             }
         }
     }
 
+    fn entry_menu(
+        &mut self,
+        response: &egui::Response,
+        folder: &ProjectFolder,
+        path: &str,
+        is_dir: bool,
+    ) {
+        let FolderSource::Remote { connection_name, .. } = &folder.source else {
+            response.context_menu(|ui| {
+                self.git_menu_buttons(ui, folder, path);
+                if is_dir {
+                    self.new_entry_buttons(ui, folder, path);
+                    self.paste_button(ui, None, path);
+                    ui.separator();
+                }
+                self.pin_button(ui, folder, path, is_dir);
+                self.properties_button(ui, None, path);
+            });
+            return;
+        };
+        response.context_menu(|ui| {
+            self.git_menu_buttons(ui, folder, path);
+            if is_dir {
+                self.new_entry_buttons(ui, folder, path);
+                self.paste_button(ui, Some(connection_name), path);
+                ui.separator();
+                if ui.button(self.i18n.tr("explorer-open-terminal")).clicked() {
+                    ui.close_menu();
+                    self.open_folder_terminal(folder, path);
+                }
+                if ui.button(self.i18n.tr("explorer-upload")).clicked() {
+                    ui.close_menu();
+                    self.prompt_upload(connection_name, path);
+                }
+                if ui.button(self.i18n.tr("explorer-search")).clicked() {
+                    ui.close_menu();
+                    self.open_search(connection_name, path);
+                }
+                if matches!(&folder.source, FolderSource::Remote { path: root, .. } if root == path) {
+                    let profiles: Vec<String> = self
+                        .project
+                        .connections
+                        .iter()
+                        .find(|connection| &connection.name == connection_name)
+                        .map(|connection| {
+                            connection
+                                .terminal_profiles
+                                .iter()
+                                .map(|profile| profile.name.clone())
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    ui.menu_button(self.i18n.tr("default-terminal-profile"), |ui| {
+                        if ui
+                            .selectable_label(folder.terminal_profile.is_none(), self.i18n.tr("none"))
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            self.set_folder_profile(path, None);
+                        }
+                        for name in profiles {
+                            let selected = folder.terminal_profile.as_ref() == Some(&name);
+                            if ui.selectable_label(selected, &name).clicked() {
+                                ui.close_menu();
+                                self.set_folder_profile(path, Some(name));
+                            }
+                        }
+                    });
+                }
+            } else {
+                if ui.button(self.i18n.tr("explorer-download")).clicked() {
+                    ui.close_menu();
+                    self.prompt_download(connection_name, path);
+                }
+                if ui.button(self.i18n.tr("explorer-tail")).clicked() {
+                    ui.close_menu();
+                    self.open_log_view(connection_name, path);
+                }
+            }
+            if ui.button(self.i18n.tr("explorer-copy")).clicked() {
+                ui.close_menu();
+                self.copy_source = Some(CopySource {
+                    connection_name: connection_name.clone(),
+                    path: path.to_string(),
+                    is_dir,
+                });
+            }
+            if !matches!(&folder.source, FolderSource::Remote { path: root, .. } if root == path) {
+                ui.separator();
+                if ui.button(self.i18n.tr("explorer-rename")).clicked() {
+                    ui.close_menu();
+                    self.rename_form = RenameForm {
+                        connection_name: connection_name.clone(),
+                        from: path.to_string(),
+                        to: path.to_string(),
+                    };
+                }
+                if ui.button(self.i18n.tr("explorer-delete")).clicked() {
+                    ui.close_menu();
+                    self.delete_remote_path(connection_name, path);
+                }
+            }
+            ui.separator();
+            self.pin_button(ui, folder, path, is_dir);
+            self.properties_button(ui, Some(connection_name), path);
+        });
+    }
+
+    fn properties_button(&mut self, ui: &mut egui::Ui, connection_name: Option<&str>, path: &str) {
+        if ui.button(self.i18n.tr("explorer-properties")).clicked() {
+            ui.close_menu();
+            self.show_properties(connection_name, path);
+        }
+    }
+
+    /// Whether `response` is the click that opens or expands an entry.
+    fn activated(&self, response: &egui::Response) -> bool {
+        match self.project.explorer.open_on {
+            OpenOn::SingleClick => response.clicked(),
+            OpenOn::DoubleClick => response.double_clicked(),
+        }
+    }
+
+    /// Entry name, coloured by its git state when it has changes.
+    fn entry_name(&self, folder: &ProjectFolder, entry: &TreeEntry) -> egui::RichText {
+        let name = egui::RichText::new(&entry.name);
+        match self.entry_git_state(folder, &entry.path, entry.is_dir) {
+            Some(state) => name.color(git_color(state)),
+            None => name,
+        }
+    }
+
+    pub fn sorted_children(&self, path: &str) -> Vec<TreeEntry> {
+        let mut children = self.explorer_cache.get(path).cloned().unwrap_or_default();
+        sort_entries(&mut children, self.project.explorer.sort);
+        children
+    }
+
+    /// Size and modification time, right-aligned after the entry name.
+    fn entry_columns(&self, ui: &mut egui::Ui, entry: &TreeEntry) {
+        let settings = &self.project.explorer;
+        let show_modified = settings.show_modified && entry.modified.is_some();
+        let show_size = settings.show_size && entry.size.is_some();
+        if !show_modified && !show_size {
+            return;
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let height = ui.spacing().interact_size.y;
+            if settings.show_modified {
+                let modified = entry
+                    .modified
+                    .map(|modified| self.i18n.relative(modified))
+                    .unwrap_or_default();
+                let response = ui.add_sized(
+                    [104.0, height],
+                    egui::Label::new(egui::RichText::new(modified).small().weak()),
+                );
+                if let Some(modified) = entry.modified {
+                    response.on_hover_text(self.i18n.datetime(modified));
+                }
+            }
+            if settings.show_size {
+                let size = entry.size.map(format_bytes).unwrap_or_default();
+                ui.add_sized(
+                    [64.0, height],
+                    egui::Label::new(egui::RichText::new(size).small().weak()),
+                );
+            }
+        });
+    }
+
+    pub fn open_explorer_options(&mut self) {
+        let settings = self.project.explorer.clone();
+        self.explorer_options = ExplorerOptionsForm {
+            open: true,
+            excludes: settings.excludes.join("\n"),
+            text_extensions: settings.text_extensions.join(" "),
+            settings,
+        };
+    }
+
+    pub fn draw_explorer_options(&mut self, ctx: &egui::Context) {
+        if !self.explorer_options.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut apply = false;
+        let form = &mut self.explorer_options;
+        egui::Window::new(self.i18n.tr("explorer-options-title"))
+            .id(egui::Id::new("explorer-options"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let settings = &mut form.settings;
+                ui.checkbox(&mut settings.hide_hidden, self.i18n.tr("explorer-options-hide-hidden"));
+                ui.checkbox(&mut settings.show_size, self.i18n.tr("explorer-options-show-size"));
+                ui.checkbox(&mut settings.show_modified, self.i18n.tr("explorer-options-show-modified"));
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.tr("explorer-options-sort"));
+                    for (sort, id) in [
+                        (ExplorerSort::Name, "explorer-options-sort-name"),
+                        (ExplorerSort::Size, "explorer-options-sort-size"),
+                        (ExplorerSort::Modified, "explorer-options-sort-modified"),
+                    ] {
+                        ui.radio_value(&mut settings.sort, sort, self.i18n.tr(id));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.tr("explorer-options-open-on"));
+                    ui.radio_value(
+                        &mut settings.open_on,
+                        OpenOn::SingleClick,
+                        self.i18n.tr("explorer-options-single-click"),
+                    );
+                    ui.radio_value(
+                        &mut settings.open_on,
+                        OpenOn::DoubleClick,
+                        self.i18n.tr("explorer-options-double-click"),
+                    );
+                });
+                ui.checkbox(
+                    &mut settings.expand_on_label_click,
+                    self.i18n.tr("explorer-options-expand-on-label"),
+                );
+                ui.checkbox(&mut settings.preview_tabs, self.i18n.tr("explorer-options-preview-tabs"));
+                ui.checkbox(&mut settings.read_ahead, self.i18n.tr("explorer-options-read-ahead"));
+                let label = ui.label(self.i18n.tr("explorer-options-excludes"));
+                ui.add(
+                    egui::TextEdit::multiline(&mut form.excludes)
+                        .hint_text("node_modules\ntarget\n*.pyc")
+                        .desired_rows(4),
+                )
+                .labelled_by(label.id);
+                ui.weak(self.i18n.tr("explorer-options-excludes-hint"));
+                let label = ui.label(self.i18n.tr("explorer-options-text-extensions"));
+                ui.add(egui::TextEdit::multiline(&mut form.text_extensions).desired_rows(3))
+                    .labelled_by(label.id);
+                ui.weak(self.i18n.tr("explorer-options-text-extensions-hint"));
+                if ui.button(self.i18n.tr("explorer-options-apply")).clicked() {
+                    apply = true;
+                }
+            });
+        if apply {
+            let mut settings = self.explorer_options.settings.clone();
+            settings.excludes = self
+                .explorer_options
+                .excludes
+                .lines()
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect();
+            settings.text_extensions = self
+                .explorer_options
+                .text_extensions
+                .split(|ch: char| ch.is_whitespace() || ch == ',')
+                .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+            self.apply_explorer_settings(settings);
+        }
+        if apply || !open {
+            self.explorer_options = ExplorerOptionsForm::default();
+        }
+    }
+
+    fn apply_explorer_settings(&mut self, settings: ExplorerSettings) {
+        let refilter = settings.list_filter() != self.project.explorer.list_filter();
+        if !settings.read_ahead {
+            self.read_ahead.clear();
+        }
+        self.project.explorer = settings;
+        if refilter {
+            self.reload_explorer();
+        }
+    }
+
+    /// Lists every expanded folder again, for example after the listing filter changed.
+    fn reload_explorer(&mut self) {
+        self.explorer_cache.clear();
+        let folders = self.project.folders.clone();
+        for folder in &folders {
+            let root = match &folder.source {
+                FolderSource::Local { path } | FolderSource::Remote { path, .. } => path.clone(),
+            };
+            let mut expanded: Vec<String> = self
+                .explorer_expanded
+                .iter()
+                .filter_map(|id| id.strip_prefix("dir:"))
+                .filter(|path| is_within(path, &root))
+                .map(str::to_string)
+                .collect();
+            if self.explorer_expanded.contains(&format!("folder:{root}")) {
+                expanded.insert(0, root);
+            }
+            for path in expanded {
+                match &folder.source {
+                    FolderSource::Local { .. } => self.load_children(folder, &path),
+                    FolderSource::Remote { connection_name, .. } => {
+                        if self.directory_loads.request(connection_name, &path, true) {
+                            self.send_directory_listing(connection_name, &path);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn new_entry_buttons(&mut self, ui: &mut egui::Ui, folder: &ProjectFolder, parent: &str) {
+        for (id, is_dir) in [("explorer-new-file", false), ("explorer-new-folder", true)] {
+            if ui.button(self.i18n.tr(id)).clicked() {
+                ui.close_menu();
+                self.new_entry_form = NewEntryForm {
+                    folder: Some(folder.clone()),
+                    parent: parent.to_string(),
+                    is_dir,
+                    name: String::new(),
+                };
+            }
+        }
+    }
+
+    fn paste_button(&mut self, ui: &mut egui::Ui, target_connection: Option<&str>, parent: &str) {
+        let Some(source) = &self.copy_source else {
+            return;
+        };
+        let label = self.i18n.tr_args(
+            "explorer-paste",
+            &[("name", display_name_for_path(&source.path).into())],
+        );
+        if ui
+            .button(label)
+            .on_hover_text(format!("[{}] {}", source.connection_name, source.path))
+            .clicked()
+        {
+            ui.close_menu();
+            self.paste_entry(target_connection, parent);
+        }
+    }
+
+    pub fn create_entry(&mut self) {
+        let form = std::mem::take(&mut self.new_entry_form);
+        let Some(folder) = form.folder else {
+            return;
+        };
+        let name = form.name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            self.output.error(format!("Invalid name {:?}", form.name));
+            return;
+        }
+
+        match &folder.source {
+            FolderSource::Local { .. } => {
+                let path = Path::new(&form.parent).join(name);
+                let result = create_local_entry(&path, form.is_dir);
+                let path = path.to_string_lossy().to_string();
+                if let Err(err) = result {
+                    self.output.error(format!("Failed to create {path}: {err}"));
+                    return;
+                }
+                self.output.info(format!("Created {path}"));
+                if self.explorer_cache.remove(&form.parent).is_some() {
+                    self.load_children(&folder, &form.parent);
+                }
+                if !form.is_dir {
+                    self.open_path(&folder, &path, false);
+                }
+            }
+            FolderSource::Remote { connection_name, .. } => {
+                let path = join_remote_path(&form.parent, name);
+                let request_id = Uuid::new_v4();
+                self.pending.insert(
+                    request_id,
+                    PendingAction::CreateEntry {
+                        folder: folder.clone(),
+                        parent: form.parent.clone(),
+                    },
+                );
+                self.send_rpc(
+                    connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::CreateFile {
+                            path,
+                            is_dir: form.is_dir,
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn refresh_remote_directory(&mut self, connection_name: &str, path: &str) {
+        if self.explorer_cache.remove(path).is_none() {
+            return;
+        }
+        if self.directory_loads.request(connection_name, path, true) {
+            self.send_directory_listing(connection_name, path);
+        }
+    }
+
+    pub fn request_remote_directory(&mut self, connection_name: &str, path: &str) {
+        if self.directory_loads.request(connection_name, path, false) {
+            self.send_directory_listing(connection_name, path);
+        }
+    }
+
+    pub fn finish_directory_load(&mut self, connection_name: &str, path: &str) {
+        if let Some(next) = self.directory_loads.finish(connection_name, path) {
+            self.send_directory_listing(connection_name, &next);
+        }
+    }
+
+    fn send_directory_listing(&mut self, connection_name: &str, path: &str) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadRemoteDirectory {
+                path: path.to_string(),
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListDirectory {
+                    path: path.to_string(),
+                    filter: self.project.explorer.list_filter(),
+                },
+            },
+        );
+    }
+
     pub fn render_picker_node(&mut self, ui: &mut egui::Ui, path: &str, depth: usize) {
         let label = display_name_for_path(path);
         let id = format!("picker:{path}");
@@ -141,7 +674,7 @@ impl WorkspaceApp {
 
         ui.horizontal(|ui| {
             ui.add_space((depth as f32) * 16.0);
-            if ui.small_button(if is_open { "v" } else { ">" }).clicked() {
+            if expander(ui, &self.i18n, is_open, &label).clicked() {
                 if is_open {
                     self.remote_picker.expanded.remove(&id);
                 } else {
@@ -149,11 +682,15 @@ impl WorkspaceApp {
                     self.request_picker_children(path);
                 }
             }
-            if ui
-                .selectable_label(self.remote_picker.selected_path == path, label)
-                .clicked()
-            {
+            let response = ui.selectable_label(self.remote_picker.selected_path == path, label);
+            tree_item(&response, Some(is_open));
+            if response.clicked() {
                 self.remote_picker.selected_path = path.to_string();
+                self.remote_picker.selected_is_file = false;
+            }
+            // Descending lists the directory on its own, like typing it in.
+            if response.double_clicked() {
+                self.picker_go_to(path);
             }
         });
 
@@ -164,10 +701,86 @@ impl WorkspaceApp {
                 .get(path)
                 .cloned()
                 .unwrap_or_default();
-            for child in children.into_iter().filter(|entry| entry.is_dir) {
-                self.render_picker_node(ui, &child.path, depth + 1);
+            for child in children {
+                if child.is_dir {
+                    self.render_picker_node(ui, &child.path, depth + 1);
+                } else if self.remote_picker.show_files {
+                    self.render_picker_file(ui, &child, depth + 1);
+                }
+            }
+        }
+    }
+
+    fn render_picker_file(&mut self, ui: &mut egui::Ui, entry: &TreeEntry, depth: usize) {
+        ui.horizontal(|ui| {
+            ui.add_space((depth as f32) * 16.0);
+            ui.label(" ");
+            icon_label(ui, self.icons.for_entry(&entry.name, false, entry.is_symlink, false));
+            let picker = &mut self.remote_picker;
+            let response = ui.selectable_label(picker.selected_path == entry.path, &entry.name);
+            tree_item(&response, None);
+            if response.clicked() {
+                picker.selected_path = entry.path.clone();
+                picker.selected_is_file = true;
             }
+            if response.double_clicked() {
+                self.open_picked_file();
+            }
+        });
+    }
+
+    /// Opens the file selected in the remote picker and closes it.
+    pub fn open_picked_file(&mut self) {
+        self.remote_picker.open = false;
+        let path = self.remote_picker.selected_path.clone();
+        let folder = self.containing_folder(&FolderSource::Remote {
+            connection_name: self.remote_picker.connection_name.clone(),
+            path: path.clone(),
+        });
+        self.open_path(&folder, &path, false);
+    }
+
+    /// Asks the server to create the folder named in the picker inside the selected
+    /// directory.
+    pub fn create_picker_folder(&mut self) {
+        let picker = &mut self.remote_picker;
+        let Some(name) = picker.new_folder.take() else {
+            return;
+        };
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            self.output.error(format!("Invalid name {name:?}"));
+            return;
         }
+        let parent = picker.selected_path.clone();
+        let connection_name = picker.connection_name.clone();
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::CreatePickerFolder {
+                parent: parent.clone(),
+            },
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::CreateFile {
+                    path: join_remote_path(&parent, name),
+                    is_dir: true,
+                },
+            },
+        );
+    }
+
+    /// Lists the parent again with the new folder in it, and selects the folder.
+    pub fn picker_folder_created(&mut self, parent: &str, path: String) {
+        let picker = &mut self.remote_picker;
+        picker.cache.remove(parent);
+        picker.expanded.insert(format!("picker:{parent}"));
+        picker.selected_path = path;
+        picker.selected_is_file = false;
+        self.request_picker_children(parent);
     }
 
     pub fn load_children(&mut self, folder: &ProjectFolder, path: &str) {
@@ -177,65 +790,82 @@ impl WorkspaceApp {
 
         match &folder.source {
             FolderSource::Local { .. } => {
-                let entries = list_local_directory(path).unwrap_or_default();
+                let entries = list_local_directory(path, &self.project.explorer.list_filter())
+                    .unwrap_or_default();
                 self.explorer_cache.insert(path.to_string(), entries);
             }
             FolderSource::Remote { connection_name, .. } => {
-                let request_id = Uuid::new_v4();
-                self.pending.insert(
-                    request_id,
-                    PendingAction::LoadRemoteDirectory {
-                        path: path.to_string(),
-                    },
-                );
-                self.send_rpc(
-                    connection_name,
-                    RpcRequest {
-                        request_id,
-                        action: RpcAction::ListDirectory {
-                            path: path.to_string(),
-                        },
-                    },
-                );
+                self.request_remote_directory(connection_name, path);
             }
         }
     }
 
-    pub fn open_path(&mut self, folder: &ProjectFolder, path: &str) {
-        if !is_text_file(path) {
-            self.output_lines
-                .push(format!("Skipping non-text file {path}"));
+    /// The project folder a path lies in, for its terminal profile and git status,
+    /// or a folder of its own when it is outside all of them.
+    pub fn containing_folder(&self, source: &FolderSource) -> ProjectFolder {
+        self.project
+            .folders
+            .iter()
+            .filter(|folder| {
+                folder.source.connection_name() == source.connection_name()
+                    && is_within(source.path(), folder.source.path())
+            })
+            .max_by_key(|folder| folder.source.path().len())
+            .cloned()
+            .unwrap_or_else(|| ProjectFolder {
+                name: display_name_for_path(source.path()),
+                source: source.clone(),
+                terminal_profile: None,
+            })
+    }
+
+    /// Opens `path` in an editor, as the preview tab when `preview` is set. Opening
+    /// an already open preview without `preview` keeps it.
+    pub fn open_path(&mut self, folder: &ProjectFolder, path: &str, preview: bool) {
+        if let Some(existing) = self.open_files.iter().position(|tab| tab.path == path) {
+            if !preview {
+                self.open_files[existing].preview = false;
+            }
+            self.show_editor(existing);
             return;
         }
-        if let Some(existing) = self.open_files.iter().position(|tab| tab.path == path) {
-            self.selected_editor = Some(existing);
+
+        if let Some(kind) = MediaKind::from_path(path) {
+            match &folder.source {
+                FolderSource::Local { .. } => self.open_local_media(path, kind, preview),
+                FolderSource::Remote { connection_name, .. } => {
+                    let connection_name = connection_name.clone();
+                    self.load_remote_media(&connection_name, path, kind, preview, Vec::new());
+                }
+            }
             return;
         }
 
+        let as_text = is_text_file(path, &self.project.explorer.text_extensions);
         match &folder.source {
-            FolderSource::Local { .. } => match fs::read_to_string(path) {
-                Ok(content) => {
-                    self.open_files.push(EditorTab {
-                        title: display_name_for_path(path),
-                        path: path.to_string(),
-                        source: EditorSource::Local,
-                        content,
-                        dirty: false,
-                    });
-                    self.selected_editor = Some(self.open_files.len() - 1);
-                }
-                Err(err) => self
-                    .output_lines
-                    .push(format!("Failed to read {path}: {err}")),
+            FolderSource::Local { .. } => match read_file_data(Path::new(path), MAX_TEXT_LEN) {
+                Ok(FileData::Text(content)) if as_text => self.open_editor(EditorTab {
+                    title: display_name_for_path(path),
+                    path: path.to_string(),
+                    source: EditorSource::Local,
+                    content,
+                    dirty: false,
+                    remote_hash: None,
+                    base: None,
+                    cursor: 0,
+                    restore_cursor: false,
+                    preview,
+                    media: None,
+                }),
+                Ok(data) => self.open_viewer(path, None, data.into()),
+                Err(err) => self.output.error(format!("Failed to read {path}: {err}")),
             },
-            FolderSource::Remote { connection_name, .. } => {
+            FolderSource::Remote { connection_name, .. } if !as_text => {
                 let request_id = Uuid::new_v4();
                 self.pending.insert(
                     request_id,
-                    PendingAction::OpenRemoteFile {
+                    PendingAction::ViewRemoteFile {
                         path: path.to_string(),
-                        title: display_name_for_path(path),
-                        connection_name: connection_name.clone(),
                     },
                 );
                 self.send_rpc(
@@ -244,27 +874,80 @@ impl WorkspaceApp {
                         request_id,
                         action: RpcAction::ReadFile {
                             path: path.to_string(),
+                            known_hash: None,
                         },
                     },
                 );
             }
+            FolderSource::Remote { connection_name, .. } => {
+                let action = PendingAction::OpenRemoteFile {
+                    path: path.to_string(),
+                    title: display_name_for_path(path),
+                    connection_name: connection_name.clone(),
+                    restore_cursor: None,
+                    preview,
+                };
+                let modified = self
+                    .explorer_cache
+                    .get(&remote_parent(path))
+                    .and_then(|entries| entries.iter().find(|entry| entry.path == path))
+                    .and_then(|entry| entry.modified);
+                if let Some((content, hash)) = self.read_ahead.take(connection_name, path, modified) {
+                    let response = RpcResponse {
+                        request_id: Uuid::nil(),
+                        result: RpcResult::FileContent {
+                            path: path.to_string(),
+                            content,
+                            hash,
+                        },
+                    };
+                    self.handle_rpc_response(connection_name, action, response);
+                    return;
+                }
+
+                let request_id = Uuid::new_v4();
+                self.pending.insert(request_id, action);
+                self.send_rpc(
+                    connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: self.cached_read(connection_name, path),
+                    },
+                );
+                self.start_read_ahead(connection_name, path);
+            }
+        }
+    }
+
+    /// Reads `path`, letting the server answer `NotModified` when the copy in the
+    /// content cache is still current.
+    pub fn cached_read(&self, connection_name: &str, path: &str) -> RpcAction {
+        RpcAction::ReadFile {
+            path: path.to_string(),
+            known_hash: self
+                .content_cache
+                .get(connection_name, path)
+                .map(|(hash, _)| hash),
+        }
+    }
+
+    pub fn cache_content(&self, connection_name: &str, path: &str, hash: &str, content: &str) {
+        if let Err(err) = self.content_cache.put(connection_name, path, hash, content) {
+            tracing::warn!(path, error = %err, "failed to cache file content");
         }
     }
 
     pub fn open_remote_picker(&mut self) {
         if self.folder_form.remote_connection_name.is_empty() {
-            self.output_lines
-                .push("Select a connection before browsing remote folders.".to_string());
+            self.output
+                .warn("Select a connection before browsing remote folders.");
             return;
         }
 
         self.remote_picker = RemoteFolderPicker {
             open: true,
             connection_name: self.folder_form.remote_connection_name.clone(),
-            selected_path: String::new(),
-            roots: Vec::new(),
-            cache: HashMap::new(),
-            expanded: HashSet::new(),
+            ..Default::default()
         };
 
         let request_id = Uuid::new_v4();
@@ -299,6 +982,7 @@ impl WorkspaceApp {
                 request_id,
                 action: RpcAction::ListDirectory {
                     path: path.to_string(),
+                    filter: ListFilter::default(),
                 },
             },
         );
@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::format::{self, Formatter};
+use rs_peer_workspace_shared::project::EditorSource;
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::PendingAction;
+
+impl WorkspaceApp {
+    /// Formats the active editor; the result is left unsaved.
+    pub fn format_active_editor(&mut self) {
+        let Some(idx) = self.active_editor() else {
+            return;
+        };
+        let tab = &self.open_files[idx];
+        let Some(formatter) = Formatter::for_path(&tab.path).filter(|_| tab.media.is_none()) else {
+            self.output.warn(format!("No formatter for {}", tab.path));
+            return;
+        };
+        match tab.source.clone() {
+            EditorSource::Local => self.format_local(idx, formatter),
+            EditorSource::Remote { connection_name } => {
+                self.request_format(&connection_name, idx, formatter, false, None);
+            }
+        }
+    }
+
+    /// Formats an editor that is about to be written, when it has a formatter.
+    /// Returns true when the server was asked to format it, which writes it once
+    /// answered.
+    pub fn format_before_save(&mut self, idx: usize, task: Option<Uuid>) -> bool {
+        let tab = &self.open_files[idx];
+        let Some(formatter) = Formatter::for_path(&tab.path) else {
+            return false;
+        };
+        match tab.source.clone() {
+            EditorSource::Local => {
+                self.format_local(idx, formatter);
+                false
+            }
+            EditorSource::Remote { connection_name } => {
+                self.request_format(&connection_name, idx, formatter, true, task);
+                true
+            }
+        }
+    }
+
+    fn format_local(&mut self, idx: usize, formatter: Formatter) {
+        let tab = &self.open_files[idx];
+        let program = self.project.formatting.local_program(formatter);
+        match format::format(formatter, program, Path::new(&tab.path), &tab.content) {
+            Ok(content) => self.apply_format(idx, content),
+            Err(err) => {
+                let message = format!("Failed to format {}: {err}", tab.path);
+                self.output.warn(message);
+            }
+        }
+    }
+
+    fn request_format(
+        &mut self,
+        connection_name: &str,
+        idx: usize,
+        formatter: Formatter,
+        save: bool,
+        task: Option<Uuid>,
+    ) {
+        let tab = &self.open_files[idx];
+        let (path, content) = (tab.path.clone(), tab.content.clone());
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::FormatFile {
+                path: path.clone(),
+                content: content.clone(),
+                save,
+                task,
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::FormatFile {
+                    path,
+                    formatter,
+                    content: Some(content),
+                },
+            },
+        );
+    }
+
+    /// Applies a formatted remote file unless it was edited in the meantime, then
+    /// writes it when it was formatted for saving, formatted or not.
+    pub fn finish_format(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        sent: String,
+        save: bool,
+        task: Option<Uuid>,
+        result: RpcResult,
+    ) {
+        let idx = self.open_files.iter().position(|tab| {
+            tab.path == path
+                && matches!(&tab.source, EditorSource::Remote { connection_name: name } if name == connection_name)
+        });
+        match result {
+            RpcResult::FileFormatted { content, .. } => match idx {
+                Some(idx) if self.open_files[idx].content == sent => self.apply_format(idx, content),
+                Some(_) => self
+                    .output
+                    .connection_warn(connection_name, format!("{path} was edited while formatting; not applied")),
+                None => {}
+            },
+            RpcResult::Error { message } => self
+                .output
+                .connection_warn(connection_name, format!("failed to format {path}: {message}")),
+            _ => {}
+        }
+        if save {
+            match idx {
+                Some(idx) => self.write_editor(idx, task),
+                None => {
+                    if let Some(task) = task {
+                        self.task_manager.step(task, Some(format!("{path} was closed")));
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply_format(&mut self, idx: usize, content: String) {
+        let tab = &mut self.open_files[idx];
+        if tab.content == content {
+            return;
+        }
+        tab.content = content;
+        tab.cursor = tab.cursor.min(tab.content.chars().count());
+        tab.restore_cursor = true;
+        tab.dirty = true;
+        tab.preview = false;
+    }
+
+    pub fn draw_format_menu(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.project.formatting;
+        ui.checkbox(&mut settings.format_on_save, self.i18n.tr("format-menu-on-save"));
+        ui.separator();
+        ui.weak(self.i18n.tr("format-menu-local-paths"));
+        for formatter in Formatter::ALL {
+            ui.horizontal(|ui| {
+                let label = ui.label(formatter.program());
+                let mut path = settings.local_paths.get(&formatter).cloned().unwrap_or_default();
+                if ui
+                    .add(
+                        egui::TextEdit::singleline(&mut path)
+                            .hint_text(formatter.program())
+                            .desired_width(220.0),
+                    )
+                    .labelled_by(label.id)
+                    .changed()
+                {
+                    if path.trim().is_empty() {
+                        settings.local_paths.remove(&formatter);
+                    } else {
+                        settings.local_paths.insert(formatter, path);
+                    }
+                }
+            });
+        }
+    }
+}
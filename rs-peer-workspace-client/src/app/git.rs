@@ -0,0 +1,548 @@
+use std::path::Path;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{GitFileState, GitStatus, RpcAction, RpcRequest};
+use rs_peer_workspace_shared::git::{self, LineChange, LineChangeKind};
+use rs_peer_workspace_shared::project::{EditorSource, FolderSource, ProjectFolder};
+use uuid::Uuid;
+
+use super::copy::is_within;
+use super::state::WorkspaceApp;
+use super::types::{GitCommitForm, PendingAction};
+
+impl WorkspaceApp {
+    /// Git status of `folder`, `None` while it loads or when it is not in a
+    /// repository.
+    pub fn folder_git_status(&self, folder: &ProjectFolder) -> Option<&GitStatus> {
+        self.git_status.get(&folder_key(folder))?.as_ref()
+    }
+
+    /// Loads the git status of a folder the first time it is shown.
+    pub fn ensure_git_status(&mut self, folder: &ProjectFolder) {
+        if !self.git_status.contains_key(&folder_key(folder)) {
+            self.refresh_git_status(folder);
+        }
+    }
+
+    /// Reads the status of a local folder right away, or requests it from the
+    /// server of a connected remote one. The previous status is kept until the new
+    /// one arrives.
+    pub fn refresh_git_status(&mut self, folder: &ProjectFolder) {
+        let key = folder_key(folder);
+        let Some(connection_name) = key.0.clone() else {
+            let status = git::status(Path::new(&key.1)).unwrap_or_else(|err| {
+                self.output.warn(format!("git status of {}: {err}", key.1));
+                None
+            });
+            self.git_status.insert(key.clone(), status);
+            self.refresh_git_gutters(&key);
+            return;
+        };
+        if !self
+            .connections
+            .get(&connection_name)
+            .is_some_and(|connection| connection.connected)
+        {
+            return;
+        }
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::GitStatus { root: key.1.clone() });
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::GitStatus { path: key.1.clone() },
+            },
+        );
+        self.git_status.entry(key).or_insert(None);
+    }
+
+    /// Refreshes the folders containing `path`, after it was saved.
+    pub fn refresh_git_for(&mut self, connection_name: Option<&str>, path: &str) {
+        let folders: Vec<_> = self
+            .project
+            .folders
+            .iter()
+            .filter(|folder| {
+                let (connection, root) = folder_key(folder);
+                connection.as_deref() == connection_name
+                    && is_within(path, &root)
+                    && self.git_status.get(&(connection.clone(), root)).is_some_and(Option::is_some)
+            })
+            .cloned()
+            .collect();
+        for folder in folders {
+            self.refresh_git_status(&folder);
+        }
+    }
+
+    pub fn finish_git_status(&mut self, connection_name: &str, root: String, status: Option<GitStatus>) {
+        let key = (Some(connection_name.to_string()), root);
+        self.git_status.insert(key.clone(), status);
+        self.refresh_git_gutters(&key);
+    }
+
+    /// Reloads the gutter markers of the open files in the folder `key`, whose
+    /// status just changed.
+    fn refresh_git_gutters(&mut self, key: &(Option<String>, String)) {
+        let tabs: Vec<_> = self
+            .open_files
+            .iter()
+            .filter(|tab| editor_connection(&tab.source) == key.0 && is_within(&tab.path, &key.1))
+            .map(|tab| (tab.source.clone(), tab.path.clone()))
+            .collect();
+        for (source, path) in tabs {
+            self.refresh_git_gutter(&source, &path);
+        }
+    }
+
+    /// Loads which lines of a file differ from `HEAD`, when it is in a folder whose
+    /// repository is known: right away for a local file, with `git_diff` for a
+    /// remote one.
+    pub fn refresh_git_gutter(&mut self, source: &EditorSource, path: &str) {
+        let connection_name = editor_connection(source);
+        let key = (connection_name.clone(), path.to_string());
+        let Some((root, file)) = self.project.folders.iter().find_map(|folder| {
+            let status = self.folder_git_status(folder)?;
+            if folder_key(folder).0 != connection_name {
+                return None;
+            }
+            let file = relative_path(&status.root, path).filter(|file| !file.is_empty())?;
+            Some((status.root.clone(), file))
+        }) else {
+            self.git_gutters.remove(&key);
+            return;
+        };
+        let Some(connection_name) = connection_name else {
+            let changes = git::diff(Path::new(&root), Some(&file))
+                .map(|diff| git::line_changes(&diff))
+                .unwrap_or_default();
+            self.git_gutters.insert(key, changes);
+            return;
+        };
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::GitGutter { path: path.to_string() });
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::GitDiff {
+                    path: root,
+                    file: Some(file),
+                },
+            },
+        );
+    }
+
+    pub fn finish_git_gutter(&mut self, connection_name: &str, path: String, diff: &str) {
+        self.git_gutters
+            .insert((Some(connection_name.to_string()), path), git::line_changes(diff));
+    }
+
+    /// Statuses of a closed connection are reloaded once it reconnects.
+    pub fn forget_git_status(&mut self, connection_name: &str) {
+        self.git_status
+            .retain(|(connection, _), _| connection.as_deref() != Some(connection_name));
+    }
+
+    /// How the entry at `path` differs from `HEAD`. A directory takes the state
+    /// shared by the changes inside it, or modified when they differ.
+    pub fn entry_git_state(&self, folder: &ProjectFolder, path: &str, is_dir: bool) -> Option<GitFileState> {
+        let status = self.folder_git_status(folder)?;
+        let relative = relative_path(&status.root, path)?;
+        if !is_dir {
+            return status
+                .files
+                .iter()
+                .find(|file| file.path == relative)
+                .map(|file| file.state);
+        }
+        let mut states = status
+            .files
+            .iter()
+            .filter(|file| relative.is_empty() || is_within(&file.path, &relative))
+            .map(|file| file.state);
+        let first = states.next()?;
+        Some(if states.all(|state| state == first) {
+            first
+        } else {
+            GitFileState::Modified
+        })
+    }
+
+    /// Branch and a marker for uncommitted changes, after a folder's name.
+    pub fn draw_git_summary(&self, ui: &mut egui::Ui, folder: &ProjectFolder) {
+        let Some(status) = self.folder_git_status(folder) else {
+            return;
+        };
+        let branch = status
+            .branch
+            .clone()
+            .unwrap_or_else(|| self.i18n.tr("git-detached"));
+        ui.label(egui::RichText::new(format!("⎇ {branch}")).small().weak());
+        if !status.files.is_empty() {
+            ui.label(egui::RichText::new("●").color(git_color(GitFileState::Modified)))
+                .on_hover_text(self.i18n.tr_args(
+                    "git-changes",
+                    &[("count", status.files.len().into())],
+                ));
+        }
+    }
+
+    /// Commit and refresh entries for the context menu of a folder root in a
+    /// repository.
+    pub fn git_menu_buttons(&mut self, ui: &mut egui::Ui, folder: &ProjectFolder, path: &str) {
+        let (_, root) = folder_key(folder);
+        if root != path || self.folder_git_status(folder).is_none() {
+            return;
+        }
+        if ui.button(self.i18n.tr("git-commit")).clicked() {
+            ui.close_menu();
+            self.open_git_commit(folder);
+        }
+        if ui.button(self.i18n.tr("git-refresh")).clicked() {
+            ui.close_menu();
+            self.refresh_git_status(folder);
+        }
+        ui.separator();
+    }
+
+    pub fn open_git_commit(&mut self, folder: &ProjectFolder) {
+        let Some(status) = self.folder_git_status(folder).cloned() else {
+            return;
+        };
+        self.git_commit = GitCommitForm {
+            folder: Some(folder.clone()),
+            branch: status.branch,
+            files: status.files.into_iter().map(|file| (file, true)).collect(),
+            ..Default::default()
+        };
+    }
+
+    fn load_git_diff(&mut self, file: String) {
+        let Some(folder) = self.git_commit.folder.clone() else {
+            return;
+        };
+        self.git_commit.diff_file = Some(file.clone());
+        self.git_commit.diff = None;
+        match folder.source {
+            FolderSource::Local { path } => {
+                self.git_commit.diff =
+                    Some(git::diff(Path::new(&path), Some(&file)).unwrap_or_else(|err| err.to_string()));
+            }
+            FolderSource::Remote { connection_name, path } => {
+                let request_id = Uuid::new_v4();
+                self.pending
+                    .insert(request_id, PendingAction::GitDiff { file: file.clone() });
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::GitDiff {
+                            path,
+                            file: Some(file),
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn finish_git_diff(&mut self, file: String, diff: String) {
+        if self.git_commit.diff_file.as_ref() == Some(&file) {
+            self.git_commit.diff = Some(diff);
+        }
+    }
+
+    fn commit_git_changes(&mut self) {
+        let Some(folder) = self.git_commit.folder.clone() else {
+            return;
+        };
+        let message = self.git_commit.message.trim().to_string();
+        let files: Vec<String> = self
+            .git_commit
+            .files
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(file, _)| file.path.clone())
+            .collect();
+        self.git_commit.error = None;
+        match folder.source.clone() {
+            FolderSource::Local { path } => match git::commit(Path::new(&path), &message, &files) {
+                Ok(commit) => {
+                    self.output.info(format!("Committed {commit} in {path}"));
+                    self.git_commit = GitCommitForm::default();
+                    self.refresh_git_status(&folder);
+                }
+                Err(err) => self.git_commit.error = Some(err.to_string()),
+            },
+            FolderSource::Remote { connection_name, path } => {
+                self.git_commit.committing = true;
+                let request_id = Uuid::new_v4();
+                self.pending
+                    .insert(request_id, PendingAction::GitCommit { root: path.clone() });
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::GitCommit {
+                            path,
+                            message,
+                            files,
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    /// Records a commit made on `connection_name`, or why it failed, and refreshes
+    /// the folder's status.
+    pub fn finish_git_commit(&mut self, connection_name: &str, root: String, result: Result<String, String>) {
+        let open = self.git_commit.folder.as_ref().is_some_and(|folder| {
+            matches!(&folder.source, FolderSource::Remote { connection_name: name, path }
+                if name == connection_name && *path == root)
+        });
+        match result {
+            Ok(commit) => {
+                self.output
+                    .connection_info(connection_name, format!("committed {commit} in {root}"));
+                if open {
+                    self.git_commit = GitCommitForm::default();
+                }
+                self.refresh_git_for(Some(connection_name), &root);
+            }
+            Err(message) if open => {
+                self.git_commit.committing = false;
+                self.git_commit.error = Some(message);
+            }
+            Err(message) => self.output.connection_error(connection_name, message),
+        }
+    }
+
+    pub fn draw_git_commit(&mut self, ctx: &egui::Context) {
+        let Some(folder) = &self.git_commit.folder else {
+            return;
+        };
+        let mut open = true;
+        let mut show_diff = None;
+        let mut commit = false;
+        let mut close = false;
+        egui::Window::new(self.i18n.tr_args(
+            "git-commit-title",
+            &[("folder", folder.name.clone().into())],
+        ))
+        .id(egui::Id::new("git-commit"))
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(640.0)
+        .show(ctx, |ui| {
+            let form = &mut self.git_commit;
+            let branch = form
+                .branch
+                .clone()
+                .unwrap_or_else(|| self.i18n.tr("git-detached"));
+            ui.label(self.i18n.tr_args("git-on-branch", &[("branch", branch.into())]));
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_salt("git-commit-files")
+                .max_height(180.0)
+                .show(ui, |ui| {
+                    if form.files.is_empty() {
+                        ui.label(self.i18n.tr("git-no-changes"));
+                    }
+                    for (file, selected) in &mut form.files {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(selected, "");
+                            ui.label(
+                                egui::RichText::new(git_letter(file.state))
+                                    .monospace()
+                                    .color(git_color(file.state)),
+                            );
+                            let shown = form.diff_file.as_ref() == Some(&file.path);
+                            if ui.selectable_label(shown, &file.path).clicked() {
+                                show_diff = Some(file.path.clone());
+                            }
+                        });
+                    }
+                });
+            if let Some(file) = &form.diff_file {
+                ui.separator();
+                ui.label(egui::RichText::new(file).strong());
+                egui::ScrollArea::both()
+                    .id_salt("git-commit-diff")
+                    .max_height(260.0)
+                    .show(ui, |ui| match &form.diff {
+                        Some(diff) => draw_patch(ui, diff),
+                        None => {
+                            ui.spinner();
+                        }
+                    });
+            }
+            ui.separator();
+            ui.add(
+                egui::TextEdit::multiline(&mut form.message)
+                    .hint_text(self.i18n.tr("git-message"))
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+            if let Some(error) = &form.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            ui.horizontal(|ui| {
+                let ready = !form.committing
+                    && !form.message.trim().is_empty()
+                    && form.files.iter().any(|(_, selected)| *selected);
+                if ui
+                    .add_enabled(ready, egui::Button::new(self.i18n.tr("git-commit-button")))
+                    .clicked()
+                {
+                    commit = true;
+                }
+                if ui.button(self.i18n.tr("cancel")).clicked() {
+                    close = true;
+                }
+                if form.committing {
+                    ui.spinner();
+                }
+            });
+        });
+
+        if let Some(file) = show_diff {
+            self.load_git_diff(file);
+        }
+        if commit {
+            self.commit_git_changes();
+        }
+        if close || !open {
+            self.git_commit = GitCommitForm::default();
+        }
+    }
+}
+
+pub fn editor_connection(source: &EditorSource) -> Option<String> {
+    match source {
+        EditorSource::Local => None,
+        EditorSource::Remote { connection_name } => Some(connection_name.clone()),
+    }
+}
+
+/// Key of a folder's git status: its connection, `None` when local, and root.
+fn folder_key(folder: &ProjectFolder) -> (Option<String>, String) {
+    match &folder.source {
+        FolderSource::Local { path } => (None, path.clone()),
+        FolderSource::Remote { connection_name, path } => (Some(connection_name.clone()), path.clone()),
+    }
+}
+
+/// `path` relative to the repository `root`, `/`-separated like the paths git
+/// reports; empty for the root itself.
+fn relative_path(root: &str, path: &str) -> Option<String> {
+    let root = root.replace('\\', "/");
+    let path = path.replace('\\', "/");
+    let rest = path.strip_prefix(root.trim_end_matches('/'))?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    Some(rest.trim_start_matches('/').to_string())
+}
+
+pub fn git_color(state: GitFileState) -> egui::Color32 {
+    match state {
+        GitFileState::Modified => egui::Color32::from_rgb(0xe2, 0xb9, 0x3b),
+        GitFileState::Added | GitFileState::Untracked => egui::Color32::from_rgb(0x73, 0xc9, 0x91),
+        GitFileState::Deleted => egui::Color32::from_rgb(0xe0, 0x6c, 0x75),
+        GitFileState::Renamed => egui::Color32::from_rgb(0x4f, 0x9d, 0xde),
+        GitFileState::Conflicted => egui::Color32::from_rgb(0xc6, 0x78, 0xdd),
+    }
+}
+
+/// One-letter badge for a changed file, as `git status --short` shows it.
+pub fn git_letter(state: GitFileState) -> &'static str {
+    match state {
+        GitFileState::Modified => "M",
+        GitFileState::Added => "A",
+        GitFileState::Deleted => "D",
+        GitFileState::Renamed => "R",
+        GitFileState::Untracked => "U",
+        GitFileState::Conflicted => "!",
+    }
+}
+
+/// Paints the changes of `changes` in the left margin of an editor, next to the
+/// rows of `galley` drawn at `galley_pos`.
+pub fn paint_git_gutter(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    galley: &egui::Galley,
+    galley_pos: egui::Pos2,
+    changes: &[LineChange],
+) {
+    if changes.is_empty() {
+        return;
+    }
+    // Top and bottom of each line, which can wrap over several rows.
+    let mut lines = Vec::new();
+    let mut start = None;
+    for row in &galley.rows {
+        let top = *start.get_or_insert(row.rect.top());
+        if row.ends_with_newline {
+            lines.push((top, row.rect.bottom()));
+            start = None;
+        }
+    }
+    if let Some(top) = start {
+        lines.push((top, galley.rows.last().map_or(top, |row| row.rect.bottom())));
+    }
+    let end = lines.last().map_or(0.0, |(_, bottom)| *bottom);
+    let x = rect.left() + 2.0;
+    let painter = ui.painter_at(rect);
+    for change in changes {
+        match change.kind {
+            LineChangeKind::Added | LineChangeKind::Modified => {
+                let Some((top, bottom)) = lines.get(change.line) else {
+                    continue;
+                };
+                let state = if change.kind == LineChangeKind::Added {
+                    GitFileState::Added
+                } else {
+                    GitFileState::Modified
+                };
+                painter.rect_filled(
+                    egui::Rect::from_x_y_ranges(x..=x + 3.0, galley_pos.y + top..=galley_pos.y + bottom),
+                    0.0,
+                    git_color(state),
+                );
+            }
+            LineChangeKind::Deleted => {
+                let y = galley_pos.y + lines.get(change.line).map_or(end, |(top, _)| *top);
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        egui::pos2(x, y - 3.0),
+                        egui::pos2(x + 5.0, y),
+                        egui::pos2(x, y + 3.0),
+                    ],
+                    git_color(GitFileState::Deleted),
+                    egui::Stroke::NONE,
+                ));
+            }
+        }
+    }
+}
+
+fn draw_patch(ui: &mut egui::Ui, patch: &str) {
+    for line in patch.lines() {
+        let color = match line.chars().next() {
+            Some('+') if !line.starts_with("+++") => Some(git_color(GitFileState::Added)),
+            Some('-') if !line.starts_with("---") => Some(git_color(GitFileState::Deleted)),
+            _ => None,
+        };
+        let text = egui::RichText::new(line).monospace();
+        ui.label(match color {
+            Some(color) => text.color(color),
+            None => text,
+        });
+    }
+}
@@ -0,0 +1,98 @@
+use eframe::egui;
+
+use super::state::WorkspaceApp;
+
+impl WorkspaceApp {
+    /// Fuzzy search over a terminal's command history. The chosen command replaces the
+    /// terminal's input so it can be edited before it runs.
+    pub fn draw_history_search(&mut self, ctx: &egui::Context) {
+        let Some(search) = &mut self.history_search else {
+            return;
+        };
+        let Some(terminal) = self
+            .terminals
+            .iter_mut()
+            .find(|terminal| terminal.id == search.terminal_id)
+        else {
+            self.history_search = None;
+            return;
+        };
+        let matches: Vec<String> = terminal
+            .history
+            .search(&search.query)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let count = matches.len();
+        search.selected = search.selected.min(count.saturating_sub(1));
+        ctx.input_mut(|input| {
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) && count > 0 {
+                search.selected = (search.selected + 1) % count;
+            }
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) && count > 0 {
+                search.selected = (search.selected + count - 1) % count;
+            }
+        });
+
+        let mut open = true;
+        let mut chosen = None;
+        egui::Window::new(self.i18n.tr_args(
+            "terminal-history-title",
+            &[("title", terminal.title.clone().into())],
+        ))
+        .id(egui::Id::new("terminal-history"))
+        .open(&mut open)
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+        .collapsible(false)
+        .resizable(false)
+        .default_width(520.0)
+        .show(ctx, |ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut search.query)
+                    .hint_text(self.i18n.tr("terminal-history-hint"))
+                    .desired_width(f32::INFINITY),
+            );
+            if search.focus {
+                response.request_focus();
+                search.focus = false;
+            }
+            if response.changed() {
+                search.selected = 0;
+            }
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                chosen = matches.get(search.selected).cloned();
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_salt("terminal-history-results")
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.label(self.i18n.tr("terminal-history-empty"));
+                    }
+                    for (idx, command) in matches.iter().enumerate() {
+                        let selected = idx == search.selected;
+                        let response = ui.selectable_label(
+                            selected,
+                            egui::RichText::new(command).monospace(),
+                        );
+                        if selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(command.clone());
+                        }
+                    }
+                });
+        });
+
+        if let Some(command) = chosen {
+            terminal.input = command;
+            terminal.history.reset();
+            ctx.memory_mut(|memory| memory.request_focus(egui::Id::new(("terminal-input", terminal.id))));
+            self.history_search = None;
+        } else if !open {
+            self.history_search = None;
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en-US",
+            Language::German => "de",
+        }
+    }
+
+    /// Name shown in the language menu, written in the language itself.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../locales/en-US/client.ftl"),
+            Language::German => include_str!("../../locales/de/client.ftl"),
+        }
+    }
+
+    /// Picks the supported language matching a locale such as `de-DE` or `de_AT.UTF-8`.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let primary = locale
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code().split('-').next() == Some(primary.as_str()))
+    }
+}
+
+/// Fluent bundles for the active language, falling back to English for messages the
+/// active locale does not translate yet.
+pub struct Localizer {
+    language: Language,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localizer {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            bundle: load_bundle(language),
+            fallback: load_bundle(Language::English),
+        }
+    }
+
+    /// Uses the operating system's locale, or English when it is not supported.
+    pub fn detect() -> Self {
+        let language = sys_locale::get_locale()
+            .and_then(|locale| Language::from_locale(&locale))
+            .unwrap_or(Language::English);
+        Self::new(language)
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        if language != self.language {
+            self.language = language;
+            self.bundle = load_bundle(language);
+        }
+    }
+
+    pub fn tr(&self, id: &str) -> String {
+        self.format(id, None)
+    }
+
+    pub fn tr_args<'a>(&self, id: &str, args: &[(&'a str, FluentValue<'a>)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        self.format(id, Some(&fluent_args))
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in [&self.bundle, &self.fallback] {
+            if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+                let mut errors = Vec::new();
+                let text = bundle.format_pattern(pattern, args, &mut errors);
+                if !errors.is_empty() {
+                    tracing::warn!(id, ?errors, "failed to format localized message");
+                }
+                return text.into_owned();
+            }
+        }
+        tracing::warn!(id, "missing localized message");
+        id.to_string()
+    }
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+fn load_bundle(language: Language) -> FluentBundle<FluentResource> {
+    let langid: LanguageIdentifier = language.code().parse().expect("valid language code");
+    let mut bundle = FluentBundle::new(vec![langid]);
+    // egui renders the Unicode isolation marks Fluent inserts around arguments.
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(language.source().to_string()).unwrap_or_else(
+        |(resource, errors)| {
+            tracing::warn!(language = language.code(), ?errors, "invalid localization file");
+            resource
+        },
+    );
+    if let Err(errors) = bundle.add_resource(resource) {
+        tracing::warn!(language = language.code(), ?errors, "duplicate localized messages");
+    }
+    bundle
+}
@@ -0,0 +1,97 @@
+use eframe::egui::{self, Color32};
+
+/// Glyph drawn before an explorer entry's name.
+#[derive(Debug, Clone, Copy)]
+pub struct Icon {
+    pub glyph: &'static str,
+    /// `None` keeps the regular text color.
+    pub color: Option<Color32>,
+}
+
+impl Icon {
+    const fn new(glyph: &'static str, color: Option<Color32>) -> Self {
+        Self { glyph, color }
+    }
+}
+
+/// Icon pack for the explorer. Another pack is just another `IconTheme` value
+/// assigned to `WorkspaceApp::icons`.
+pub struct IconTheme {
+    pub folder: Icon,
+    pub folder_open: Icon,
+    pub file: Icon,
+    pub symlink: Icon,
+    /// Lowercase extensions and the icon for their files; the first match wins.
+    pub extensions: &'static [(&'static [&'static str], Icon)],
+}
+
+const CODE: Color32 = Color32::from_rgb(0x4f, 0x9d, 0xde);
+const DATA: Color32 = Color32::from_rgb(0xd7, 0xa9, 0x3b);
+const MEDIA: Color32 = Color32::from_rgb(0xb0, 0x7c, 0xd8);
+const ARCHIVE: Color32 = Color32::from_rgb(0xc0, 0x7a, 0x4a);
+const SCRIPT: Color32 = Color32::from_rgb(0x6a, 0xb8, 0x5c);
+const FOLDER: Color32 = Color32::from_rgb(0xd9, 0xb3, 0x5f);
+const LINK: Color32 = Color32::from_rgb(0x3f, 0xb5, 0xb0);
+
+pub const DEFAULT_ICONS: IconTheme = IconTheme {
+    folder: Icon::new("🗀", Some(FOLDER)),
+    folder_open: Icon::new("🗁", Some(FOLDER)),
+    file: Icon::new("🗋", None),
+    symlink: Icon::new("🔗", Some(LINK)),
+    extensions: &[
+        (&["txt", "md", "rst", "log"], Icon::new("🖹", None)),
+        (&["py", "pyi"], Icon::new("🐍", Some(CODE))),
+        (&["ipynb"], Icon::new("📓", Some(CODE))),
+        (
+            &["m", "rs", "c", "h", "cpp", "hpp", "go", "java", "js", "ts", "jsx", "tsx"],
+            Icon::new("🖹", Some(CODE)),
+        ),
+        (&["sh", "bash", "zsh", "ps1", "bat", "cmd"], Icon::new("🖥", Some(SCRIPT))),
+        (
+            &["json", "toml", "yaml", "yml", "ini", "cfg", "conf", "ron", "xml"],
+            Icon::new("⚙", Some(DATA)),
+        ),
+        (&["csv", "tsv", "mat", "parquet"], Icon::new("📊", Some(DATA))),
+        (
+            &["png", "jpg", "jpeg", "gif", "bmp", "svg", "webp", "ico"],
+            Icon::new("🖼", Some(MEDIA)),
+        ),
+        (&["mp3", "wav", "flac", "ogg"], Icon::new("🎵", Some(MEDIA))),
+        (&["mp4", "mkv", "mov", "webm"], Icon::new("🎞", Some(MEDIA))),
+        (&["zip", "tar", "gz", "tgz", "xz", "bz2", "7z", "rar"], Icon::new("📦", Some(ARCHIVE))),
+        (&["pem", "key", "crt", "pub"], Icon::new("🔑", Some(DATA))),
+    ],
+};
+
+impl IconTheme {
+    pub fn for_entry(&self, name: &str, is_dir: bool, is_symlink: bool, expanded: bool) -> Icon {
+        if is_symlink {
+            return self.symlink;
+        }
+        if is_dir {
+            return self.folder(expanded);
+        }
+        let Some((_, extension)) = name.rsplit_once('.') else {
+            return self.file;
+        };
+        let extension = extension.to_ascii_lowercase();
+        self.extensions
+            .iter()
+            .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+            .map_or(self.file, |(_, icon)| *icon)
+    }
+
+    pub fn folder(&self, expanded: bool) -> Icon {
+        if expanded { self.folder_open } else { self.folder }
+    }
+}
+
+/// Draws `icon`; it is decorative, so assistive technologies skip it.
+pub fn icon_label(ui: &mut egui::Ui, icon: Icon) {
+    let mut text = egui::RichText::new(icon.glyph);
+    if let Some(color) = icon.color {
+        text = text.color(color);
+    }
+    let response = ui.add(egui::Label::new(text).selectable(false));
+    response.widget_info(|| egui::WidgetInfo::new(egui::WidgetType::Other));
+}
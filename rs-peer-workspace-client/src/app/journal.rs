@@ -0,0 +1,188 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::remote_parent;
+use super::types::{JournalEntry, JournalOperation, PendingAction, RenameForm};
+
+impl WorkspaceApp {
+    pub fn delete_remote_path(&mut self, connection_name: &str, path: &str) {
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::JournaledOperation);
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::DeletePath {
+                    path: path.to_string(),
+                },
+            },
+        );
+    }
+
+    pub fn rename_remote_path(&mut self) {
+        let form = std::mem::take(&mut self.rename_form);
+        let to = form.to.trim().to_string();
+        if to.is_empty() || to == form.from {
+            return;
+        }
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::JournaledOperation);
+        self.send_rpc(
+            &form.connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::RenamePath {
+                    from: form.from,
+                    to,
+                    expected_hash: None,
+                },
+            },
+        );
+    }
+
+    pub fn record_operation(&mut self, connection_name: &str, operation: JournalOperation) {
+        match &operation {
+            JournalOperation::Delete { path, .. } => {
+                self.output.connection_info(
+                    connection_name,
+                    format!(
+                        "moved {path} to the server trash; Edit -> Undo Last Operation restores it"
+                    ),
+                );
+                self.refresh_remote_directory(connection_name, &remote_parent(path));
+            }
+            JournalOperation::Rename { from, to, .. } => {
+                self.output
+                    .connection_info(connection_name, format!("moved {from} to {to}"));
+                self.retarget_remote_tabs(connection_name, from, to);
+                self.refresh_remote_directory(connection_name, &remote_parent(from));
+                self.refresh_remote_directory(connection_name, &remote_parent(to));
+            }
+        }
+        self.journal.push(JournalEntry {
+            connection_name: connection_name.to_string(),
+            operation,
+        });
+    }
+
+    pub fn undo_last_operation(&mut self) {
+        let Some(entry) = self.journal.pop() else {
+            return;
+        };
+        let action = match &entry.operation {
+            JournalOperation::Delete {
+                path,
+                trash_path,
+                hash,
+            } => RpcAction::RestorePath {
+                trash_path: trash_path.clone(),
+                path: path.clone(),
+                expected_hash: hash.clone(),
+            },
+            JournalOperation::Rename { from, to, hash } => RpcAction::RenamePath {
+                from: to.clone(),
+                to: from.clone(),
+                expected_hash: hash.clone(),
+            },
+        };
+        let request_id = Uuid::new_v4();
+        let connection_name = entry.connection_name.clone();
+        self.pending
+            .insert(request_id, PendingAction::UndoOperation { entry });
+        self.send_rpc(&connection_name, RpcRequest { request_id, action });
+    }
+
+    pub fn finish_undo(&mut self, entry: JournalEntry, result: Result<(), String>) {
+        let connection_name = entry.connection_name.clone();
+        if let Err(message) = result {
+            self.output
+                .connection_error(&connection_name, format!("undo failed: {message}"));
+            self.journal.push(entry);
+            return;
+        }
+        match &entry.operation {
+            JournalOperation::Delete { path, .. } => {
+                self.output
+                    .connection_info(&connection_name, format!("restored {path}"));
+                self.refresh_remote_directory(&connection_name, &remote_parent(path));
+            }
+            JournalOperation::Rename { from, to, .. } => {
+                self.output
+                    .connection_info(&connection_name, format!("moved {to} back to {from}"));
+                self.retarget_remote_tabs(&connection_name, to, from);
+                self.refresh_remote_directory(&connection_name, &remote_parent(from));
+                self.refresh_remote_directory(&connection_name, &remote_parent(to));
+            }
+        }
+    }
+
+    fn retarget_remote_tabs(&mut self, connection_name: &str, from: &str, to: &str) {
+        for tab in &mut self.open_files {
+            let EditorSource::Remote {
+                connection_name: tab_connection,
+            } = &tab.source
+            else {
+                continue;
+            };
+            if tab_connection != connection_name {
+                continue;
+            }
+            let path = if tab.path == from {
+                to.to_string()
+            } else if let Some(rest) = tab.path.strip_prefix(from)
+                && rest.starts_with(['/', '\\'])
+            {
+                format!("{to}{rest}")
+            } else {
+                continue;
+            };
+            tab.title = display_name_for_path(&path);
+            tab.path = path;
+        }
+    }
+
+    pub fn draw_rename(&mut self, ctx: &egui::Context) {
+        if self.rename_form.from.is_empty() {
+            return;
+        }
+
+        let mut open = true;
+        let mut rename = false;
+        egui::Window::new(self.i18n.tr("rename-title"))
+            .id(egui::Id::new("rename"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr_args(
+                    "rename-from",
+                    &[("path", self.rename_form.from.clone().into())],
+                ));
+                let label = ui.label(self.i18n.tr("rename-target"));
+                let response = ui
+                    .text_edit_singleline(&mut self.rename_form.to)
+                    .labelled_by(label.id);
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    rename = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(self.i18n.tr("rename-submit")).clicked() {
+                        rename = true;
+                    }
+                    if ui.button(self.i18n.tr("cancel")).clicked() {
+                        self.rename_form = RenameForm::default();
+                    }
+                });
+            });
+        if rename {
+            self.rename_remote_path();
+        }
+        if !open {
+            self.rename_form = RenameForm::default();
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use rs_peer_workspace_shared::app::{FileLock, RpcAction, RpcRequest};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::PendingAction;
+
+impl WorkspaceApp {
+    pub fn refresh_file_lock(&mut self, connection_name: &str, path: &str) {
+        self.send_file_lock_request(
+            connection_name,
+            false,
+            RpcAction::FileLockStatus {
+                path: path.to_string(),
+            },
+        );
+    }
+
+    /// Takes (`lock == true`) or releases the advisory lock on a remote file.
+    pub fn send_lock_rpc(&mut self, connection_name: &str, path: &str, lock: bool) {
+        let action = if lock {
+            RpcAction::LockFile {
+                path: path.to_string(),
+                owner: lock_owner(),
+                ttl_secs: None,
+            }
+        } else {
+            RpcAction::UnlockFile {
+                path: path.to_string(),
+            }
+        };
+        self.send_file_lock_request(connection_name, lock, action);
+    }
+
+    fn send_file_lock_request(&mut self, connection_name: &str, acquiring: bool, action: RpcAction) {
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::FileLock { acquiring });
+        self.send_rpc(connection_name, RpcRequest { request_id, action });
+    }
+
+    pub fn update_file_lock(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        lock: Option<FileLock>,
+        acquiring: bool,
+    ) {
+        let key = (connection_name.to_string(), path);
+        match lock {
+            Some(lock) => {
+                if acquiring && !lock.mine {
+                    self.output.connection_warn(
+                        connection_name,
+                        format!(
+                            "{} is locked by {} since {}",
+                            key.1,
+                            lock.owner,
+                            self.i18n.clock(lock.acquired_at)
+                        ),
+                    );
+                }
+                self.file_locks.insert(key, lock);
+            }
+            None => {
+                self.file_locks.remove(&key);
+            }
+        }
+    }
+}
+
+/// Name shown to other clients as the holder of a lock.
+fn lock_owner() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, LogView};
+
+/// Lines kept per log view; older lines are dropped as new ones arrive.
+const MAX_LOG_LINES: usize = 10_000;
+
+impl WorkspaceApp {
+    pub fn open_log_view(&mut self, connection_name: &str, path: &str) {
+        self.show_bottom_panel = true;
+        self.active_bottom_tab = BottomTab::Logs;
+        if let Some(idx) = self
+            .log_views
+            .iter()
+            .position(|view| view.connection_name == connection_name && view.path == path)
+        {
+            self.selected_log = idx;
+            if !self.log_views[idx].following {
+                self.restart_tail(idx);
+            }
+            return;
+        }
+        self.log_views.push(LogView {
+            id: Uuid::nil(),
+            connection_name: connection_name.to_string(),
+            path: path.to_string(),
+            lines: VecDeque::new(),
+            held: Vec::new(),
+            paused: false,
+            filter: String::new(),
+            following: false,
+        });
+        self.selected_log = self.log_views.len() - 1;
+        self.restart_tail(self.selected_log);
+    }
+
+    /// Starts following the view's file again under a new id, so lines still in
+    /// flight for the previous tail are ignored.
+    fn restart_tail(&mut self, idx: usize) {
+        let request_id = Uuid::new_v4();
+        let view = &mut self.log_views[idx];
+        view.id = request_id;
+        view.following = true;
+        let connection_name = view.connection_name.clone();
+        let path = view.path.clone();
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::TailFile { path, follow: true },
+            },
+        );
+    }
+
+    fn stop_tail(&mut self, idx: usize) {
+        let view = &mut self.log_views[idx];
+        if !view.following {
+            return;
+        }
+        view.following = false;
+        let connection_name = view.connection_name.clone();
+        let tail_id = view.id;
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id: Uuid::new_v4(),
+                action: RpcAction::StopTail { tail_id },
+            },
+        );
+    }
+
+    pub fn handle_tail_response(&mut self, response: RpcResponse) {
+        let Some(view) = self
+            .log_views
+            .iter_mut()
+            .find(|view| view.id == response.request_id)
+        else {
+            return;
+        };
+        match response.result {
+            RpcResult::TailLines { lines, rotated, .. } => {
+                let mut lines = lines;
+                if rotated {
+                    lines.insert(0, format!("--- {} was truncated or rotated ---", view.path));
+                }
+                if view.paused {
+                    view.held.extend(lines);
+                } else {
+                    push_lines(&mut view.lines, lines);
+                }
+            }
+            RpcResult::TailStopped { .. } => view.following = false,
+            RpcResult::Error { message } => {
+                view.following = false;
+                self.output.connection_error(
+                    &view.connection_name,
+                    format!("failed to tail {}: {message}", view.path),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    pub fn draw_log_viewer(&mut self, ui: &mut egui::Ui) {
+        if self.log_views.is_empty() {
+            ui.label(self.i18n.tr("logs-empty"));
+            return;
+        }
+        self.selected_log = self.selected_log.min(self.log_views.len() - 1);
+
+        let mut stop = false;
+        let mut restart = false;
+        let mut close = false;
+        ui.horizontal(|ui| {
+            let selected = &self.log_views[self.selected_log];
+            egui::ComboBox::from_id_salt("log-view")
+                .selected_text(format!("{}: {}", selected.connection_name, selected.path))
+                .show_ui(ui, |ui| {
+                    for (idx, view) in self.log_views.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut self.selected_log,
+                            idx,
+                            format!("{}: {}", view.connection_name, view.path),
+                        );
+                    }
+                });
+
+            let view = &mut self.log_views[self.selected_log];
+            let pause = if view.paused { "logs-resume" } else { "logs-pause" };
+            if ui.button(self.i18n.tr(pause)).clicked() {
+                view.paused = !view.paused;
+                if !view.paused {
+                    let held = std::mem::take(&mut view.held);
+                    push_lines(&mut view.lines, held);
+                }
+            }
+            if ui.button(self.i18n.tr("logs-clear")).clicked() {
+                view.lines.clear();
+                view.held.clear();
+            }
+            let label = ui.label(self.i18n.tr("logs-filter"));
+            ui.text_edit_singleline(&mut view.filter).labelled_by(label.id);
+            if view.following {
+                stop = ui.button(self.i18n.tr("logs-stop")).clicked();
+            } else {
+                restart = ui.button(self.i18n.tr("logs-restart")).clicked();
+            }
+            close = ui.button(self.i18n.tr("logs-close")).clicked();
+            if view.paused && !view.held.is_empty() {
+                ui.label(self.i18n.tr_args("logs-held", &[("count", view.held.len().into())]));
+            }
+        });
+        ui.separator();
+
+        let view = &self.log_views[self.selected_log];
+        let filter = view.filter.to_lowercase();
+        egui::ScrollArea::vertical()
+            .id_salt(("log-lines", view.id))
+            .auto_shrink(false)
+            .stick_to_bottom(!view.paused)
+            .show(ui, |ui| {
+                for line in view
+                    .lines
+                    .iter()
+                    .filter(|line| filter.is_empty() || line.to_lowercase().contains(&filter))
+                {
+                    ui.monospace(line);
+                }
+            });
+
+        if stop || close {
+            self.stop_tail(self.selected_log);
+        }
+        if restart {
+            self.restart_tail(self.selected_log);
+        }
+        if close {
+            self.log_views.remove(self.selected_log);
+            self.selected_log = self.selected_log.saturating_sub(1);
+        }
+    }
+}
+
+fn push_lines(buffer: &mut VecDeque<String>, lines: Vec<String>) {
+    buffer.extend(lines);
+    let excess = buffer.len().saturating_sub(MAX_LOG_LINES);
+    buffer.drain(..excess);
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use eframe::egui::text::CCursor;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use rs_peer_workspace_shared::lsp::{
+    self, Diagnostic, Incoming, LanguageServerSpec, LineIndex, Position, Severity,
+};
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource, FolderSource};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::copy::is_within;
+use super::state::WorkspaceApp;
+use super::transfers::remote_parent;
+use super::types::{LanguageClient, LspHover, LspRequest, PendingAction};
+
+/// How long the pointer rests on a position before its hover is requested.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+impl WorkspaceApp {
+    /// Starts language servers for open remote files and keeps the documents they
+    /// know in step with the editors. Called every frame.
+    pub fn sync_language_servers(&mut self, ctx: &egui::Context) {
+        let documents: Vec<(String, String, &'static LanguageServerSpec)> = self
+            .open_files
+            .iter()
+            .filter(|tab| tab.media.is_none())
+            .filter_map(|tab| {
+                let EditorSource::Remote { connection_name } = &tab.source else {
+                    return None;
+                };
+                Some((connection_name.clone(), tab.path.clone(), lsp::server_for_path(&tab.path)?))
+            })
+            .collect();
+
+        for (connection_name, path, spec) in &documents {
+            let root = self.language_root(connection_name, path);
+            let Some(server) = self.language_servers.iter().position(|server| {
+                server.connection_name == *connection_name && server.root == root && server.spec.name == spec.name
+            }) else {
+                if self
+                    .connections
+                    .get(connection_name)
+                    .is_some_and(|connection| connection.connected)
+                {
+                    self.start_language_server(connection_name, root, spec);
+                }
+                continue;
+            };
+            let Some(tab) = self.open_files.iter().find(|tab| tab.path == *path) else {
+                continue;
+            };
+            let client = &mut self.language_servers[server];
+            if !client.ready {
+                continue;
+            }
+            let message = match client.documents.get_mut(path) {
+                None => {
+                    client.documents.insert(path.clone(), (1, tab.content.clone()));
+                    lsp::did_open(path, spec.language_id, 1, &tab.content)
+                }
+                Some((version, sent)) if *sent != tab.content => {
+                    *version += 1;
+                    sent.clone_from(&tab.content);
+                    lsp::did_change(path, *version, &tab.content)
+                }
+                Some(_) => continue,
+            };
+            self.send_lsp(server, message);
+        }
+
+        for server in 0..self.language_servers.len() {
+            let client = &mut self.language_servers[server];
+            let closed: Vec<String> = client
+                .documents
+                .keys()
+                .filter(|path| {
+                    !documents
+                        .iter()
+                        .any(|(connection_name, open, _)| *connection_name == client.connection_name && open == *path)
+                })
+                .cloned()
+                .collect();
+            for path in closed {
+                let client = &mut self.language_servers[server];
+                client.documents.remove(&path);
+                self.lsp_diagnostics.remove(&(client.connection_name.clone(), path.clone()));
+                self.send_lsp(server, lsp::did_close(&path));
+            }
+        }
+
+        if let Some((connection_name, path, position)) = &self.lsp_goto
+            && let Some(idx) = self.open_files.iter().position(|tab| {
+                matches!(&tab.source, EditorSource::Remote { connection_name: name } if name == connection_name)
+                    && tab.path == *path
+            })
+        {
+            let tab = &mut self.open_files[idx];
+            tab.cursor = position.to_index(&tab.content);
+            tab.restore_cursor = true;
+            self.lsp_goto = None;
+            self.show_editor(idx);
+        }
+
+        if let Some(hover) = &mut self.lsp_hover
+            && !hover.requested
+        {
+            if hover.since.elapsed() >= HOVER_DELAY {
+                hover.requested = true;
+                let (connection_name, path, index) = (hover.connection_name.clone(), hover.path.clone(), hover.index);
+                self.request_at(
+                    &connection_name,
+                    &path,
+                    index,
+                    "textDocument/hover",
+                    LspRequest::Hover { path: path.clone(), index },
+                );
+            } else {
+                ctx.request_repaint_after(HOVER_DELAY);
+            }
+        }
+    }
+
+    /// Follows the pointer over a remote editor: `index` is the character under it,
+    /// `None` once it left the editor of `path`.
+    pub fn lsp_pointer(&mut self, connection_name: &str, path: &str, index: Option<usize>) {
+        let Some(index) = index else {
+            if self
+                .lsp_hover
+                .as_ref()
+                .is_some_and(|hover| hover.connection_name == connection_name && hover.path == path)
+            {
+                self.lsp_hover = None;
+            }
+            return;
+        };
+        if self.lsp_hover.as_ref().is_some_and(|hover| {
+            hover.connection_name == connection_name && hover.path == path && hover.index == index
+        }) {
+            return;
+        }
+        self.lsp_hover = self.document_server(connection_name, path).map(|_| LspHover {
+            connection_name: connection_name.to_string(),
+            path: path.to_string(),
+            index,
+            since: Instant::now(),
+            requested: false,
+            text: None,
+        });
+    }
+
+    /// Asks the language server where the symbol at the cursor of `path` is
+    /// defined and opens it there.
+    pub fn go_to_definition(&mut self, connection_name: &str, path: &str) {
+        let Some(cursor) = self
+            .open_files
+            .iter()
+            .find(|tab| tab.path == path)
+            .map(|tab| tab.cursor)
+        else {
+            return;
+        };
+        if self.document_server(connection_name, path).is_none() {
+            self.output
+                .connection_info(connection_name, format!("no language server for {path}"));
+            return;
+        }
+        self.request_at(connection_name, path, cursor, "textDocument/definition", LspRequest::Definition);
+    }
+
+    /// Tells the language server of `path` that it was saved.
+    pub fn lsp_saved(&mut self, connection_name: &str, path: &str) {
+        if let Some(server) = self.document_server(connection_name, path) {
+            self.send_lsp(server, lsp::did_save(path));
+        }
+    }
+
+    /// Drops the language servers of a connection that went away, so they start
+    /// again once it is back.
+    pub fn forget_language_servers(&mut self, connection_name: &str) {
+        self.language_servers
+            .retain(|server| server.connection_name != connection_name);
+        self.lsp_diagnostics
+            .retain(|(connection, _), _| connection != connection_name);
+        if self
+            .lsp_hover
+            .as_ref()
+            .is_some_and(|hover| hover.connection_name == connection_name)
+        {
+            self.lsp_hover = None;
+        }
+    }
+
+    pub fn is_language_server(&self, request_id: Uuid) -> bool {
+        self.language_servers.iter().any(|server| server.id == request_id)
+    }
+
+    pub fn handle_lsp_response(&mut self, response: RpcResponse) {
+        let Some(server) = self
+            .language_servers
+            .iter()
+            .position(|server| server.id == response.request_id)
+        else {
+            return;
+        };
+        match response.result {
+            RpcResult::LanguageServerStarted { .. } => {
+                let root = self.language_servers[server].root.clone();
+                self.lsp_request(server, "initialize", lsp::initialize_params(&root), LspRequest::Initialize);
+            }
+            RpcResult::LspMessage { seq, message, .. } => {
+                let messages = self.language_servers[server].incoming.push(seq, message);
+                for message in messages {
+                    match lsp::parse(&message) {
+                        Ok(incoming) => self.handle_lsp_message(server, incoming),
+                        Err(err) => {
+                            let client = &self.language_servers[server];
+                            self.output
+                                .connection_warn(&client.connection_name, format!("{}: {err}", client.spec.name));
+                        }
+                    }
+                }
+            }
+            RpcResult::LanguageServerStopped { error, .. } => {
+                let reason = error.unwrap_or_else(|| "stopped".to_string());
+                self.fail_language_server(server, &reason);
+            }
+            RpcResult::Error { message } => self.fail_language_server(server, &message),
+            _ => {}
+        }
+    }
+
+    fn handle_lsp_message(&mut self, server: usize, incoming: Incoming) {
+        let connection_name = self.language_servers[server].connection_name.clone();
+        match incoming {
+            Incoming::Response { id, result } => {
+                let Some(request) = self.language_servers[server].requests.remove(&id) else {
+                    return;
+                };
+                match (request, result) {
+                    (LspRequest::Initialize, Ok(_)) => {
+                        self.send_lsp(server, lsp::notification("initialized", json!({})));
+                        self.language_servers[server].ready = true;
+                    }
+                    (LspRequest::Initialize, Err(message)) => self.fail_language_server(server, &message),
+                    (LspRequest::Hover { path, index }, Ok(result)) => {
+                        if let Some(hover) = &mut self.lsp_hover
+                            && hover.connection_name == connection_name
+                            && hover.path == path
+                            && hover.index == index
+                        {
+                            hover.text = lsp::hover_text(&result);
+                        }
+                    }
+                    (LspRequest::Definition, Ok(result)) => match lsp::definition_target(&result) {
+                        Some((path, position)) => {
+                            let path = server_path(&self.language_servers[server].root, path);
+                            self.open_definition(&connection_name, path, position);
+                        }
+                        None => self
+                            .output
+                            .connection_info(&connection_name, "no definition found"),
+                    },
+                    (_, Err(message)) => {
+                        let name = self.language_servers[server].spec.name;
+                        self.output
+                            .connection_warn(&connection_name, format!("{name}: {message}"));
+                    }
+                }
+            }
+            Incoming::Notification { method, params } => match method.as_str() {
+                "textDocument/publishDiagnostics" => {
+                    if let Some((path, diagnostics)) = lsp::parse_diagnostics(&params) {
+                        let path = server_path(&self.language_servers[server].root, path);
+                        if self.language_servers[server].documents.contains_key(&path) {
+                            self.lsp_diagnostics.insert((connection_name, path), diagnostics);
+                        }
+                    }
+                }
+                "window/showMessage" => {
+                    if let Some(message) = params.get("message").and_then(Value::as_str) {
+                        let name = self.language_servers[server].spec.name;
+                        self.output
+                            .connection_info(&connection_name, format!("{name}: {message}"));
+                    }
+                }
+                _ => {}
+            },
+            Incoming::Request { id, method, params } => {
+                self.send_lsp(server, lsp::reply(&id, &method, &params));
+            }
+        }
+    }
+
+    /// Keeps a server that failed so it is not started again for every frame, and
+    /// drops what it reported.
+    fn fail_language_server(&mut self, server: usize, reason: &str) {
+        let client = &mut self.language_servers[server];
+        if client.failed.is_some() {
+            return;
+        }
+        client.failed = Some(reason.to_string());
+        client.ready = false;
+        client.requests.clear();
+        let connection_name = client.connection_name.clone();
+        let message = format!("{} in {}: {reason}", client.spec.name, client.root);
+        for path in std::mem::take(&mut client.documents).into_keys() {
+            self.lsp_diagnostics.remove(&(connection_name.clone(), path));
+        }
+        self.output.connection_warn(&connection_name, message);
+    }
+
+    fn start_language_server(&mut self, connection_name: &str, root: String, spec: &'static LanguageServerSpec) {
+        let id = Uuid::new_v4();
+        self.output
+            .connection_info(connection_name, format!("starting {} in {root}", spec.name));
+        self.language_servers.push(LanguageClient {
+            id,
+            connection_name: connection_name.to_string(),
+            root: root.clone(),
+            spec,
+            ready: false,
+            failed: None,
+            next_seq: 0,
+            incoming: Default::default(),
+            next_request: 1,
+            requests: HashMap::new(),
+            documents: HashMap::new(),
+        });
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id: id,
+                action: RpcAction::StartLanguageServer {
+                    command: spec.command.to_string(),
+                    args: spec.args.iter().map(|arg| arg.to_string()).collect(),
+                    root,
+                },
+            },
+        );
+    }
+
+    /// The remote project folder containing `path`, the innermost when they nest,
+    /// or else its directory.
+    fn language_root(&self, connection_name: &str, path: &str) -> String {
+        self.project
+            .folders
+            .iter()
+            .filter_map(|folder| match &folder.source {
+                FolderSource::Remote {
+                    connection_name: name,
+                    path: root,
+                } if name == connection_name && is_within(path, root) => Some(root),
+                _ => None,
+            })
+            .max_by_key(|root| root.len())
+            .cloned()
+            .unwrap_or_else(|| remote_parent(path))
+    }
+
+    /// Ready server that has `path` open.
+    fn document_server(&self, connection_name: &str, path: &str) -> Option<usize> {
+        self.language_servers.iter().position(|server| {
+            server.connection_name == connection_name && server.ready && server.documents.contains_key(path)
+        })
+    }
+
+    fn request_at(&mut self, connection_name: &str, path: &str, index: usize, method: &str, request: LspRequest) {
+        let Some(server) = self.document_server(connection_name, path) else {
+            return;
+        };
+        let Some(text) = self.language_servers[server].documents.get(path).map(|(_, text)| text) else {
+            return;
+        };
+        let position = Position::from_index(text, index);
+        self.lsp_request(server, method, lsp::position_params(path, position), request);
+    }
+
+    fn lsp_request(&mut self, server: usize, method: &str, params: Value, request: LspRequest) {
+        let client = &mut self.language_servers[server];
+        let id = client.next_request;
+        client.next_request += 1;
+        client.requests.insert(id, request);
+        self.send_lsp(server, lsp::request(id, method, params));
+    }
+
+    fn send_lsp(&mut self, server: usize, message: String) {
+        let client = &mut self.language_servers[server];
+        let seq = client.next_seq;
+        client.next_seq += 1;
+        let (connection_name, server_id) = (client.connection_name.clone(), client.id);
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id: Uuid::new_v4(),
+                action: RpcAction::LspSend {
+                    server_id,
+                    seq,
+                    message,
+                },
+            },
+        );
+    }
+
+    fn open_definition(&mut self, connection_name: &str, path: String, position: Position) {
+        self.lsp_goto = Some((connection_name.to_string(), path.clone(), position));
+        let open = self.open_files.iter().any(|tab| {
+            tab.path == path && matches!(&tab.source, EditorSource::Remote { connection_name: name } if name == connection_name)
+        });
+        if open {
+            return;
+        }
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::OpenRemoteFile {
+                path: path.clone(),
+                title: display_name_for_path(&path),
+                connection_name: connection_name.to_string(),
+                restore_cursor: None,
+                preview: false,
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: self.cached_read(connection_name, &path),
+            },
+        );
+    }
+}
+
+/// `path` from a language server with the separators of `root`, so Windows paths
+/// match the editor's.
+fn server_path(root: &str, path: String) -> String {
+    if root.contains('\\') {
+        path.replace('/', "\\")
+    } else {
+        path
+    }
+}
+
+pub fn severity_color(ui: &egui::Ui, severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Error => ui.visuals().error_fg_color,
+        Severity::Warning => ui.visuals().warn_fg_color,
+        Severity::Information | Severity::Hint => ui.visuals().hyperlink_color,
+    }
+}
+
+/// Underlines the text each diagnostic covers; multi-line ranges are underlined
+/// to the end of their first line.
+pub fn paint_diagnostics(
+    ui: &egui::Ui,
+    clip: egui::Rect,
+    galley: &egui::Galley,
+    galley_pos: egui::Pos2,
+    text: &str,
+    diagnostics: &[Diagnostic],
+) {
+    let painter = ui.painter_at(clip);
+    let lines = LineIndex::new(text);
+    for diagnostic in diagnostics {
+        let start = lines.index(diagnostic.range.start);
+        let end = lines.index(diagnostic.range.end).max(start + 1);
+        let from = galley.pos_from_ccursor(CCursor::new(start));
+        let to = galley.pos_from_ccursor(CCursor::new(end));
+        let right = if (to.top() - from.top()).abs() < 1.0 {
+            to.left()
+        } else {
+            galley.rect.right()
+        };
+        let y = galley_pos.y + from.bottom() - 1.0;
+        let left = galley_pos.x + from.left();
+        painter.line_segment(
+            [egui::pos2(left, y), egui::pos2((galley_pos.x + right).max(left + 4.0), y)],
+            egui::Stroke::new(1.5, severity_color(ui, diagnostic.severity)),
+        );
+    }
+}
+
+/// "line: message (source)" of a diagnostic, for tooltips.
+pub fn diagnostic_line(diagnostic: &Diagnostic) -> String {
+    let line = diagnostic.range.start.line + 1;
+    match &diagnostic.source {
+        Some(source) => format!("{line}: {} ({source})", diagnostic.message),
+        None => format!("{line}: {}", diagnostic.message),
+    }
+}
+
+/// Diagnostics whose range contains character `index`.
+pub fn diagnostics_at<'a>(text: &str, diagnostics: &'a [Diagnostic], index: usize) -> Vec<&'a Diagnostic> {
+    let lines = LineIndex::new(text);
+    diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            let start = lines.index(diagnostic.range.start);
+            let end = lines.index(diagnostic.range.end).max(start + 1);
+            (start..end).contains(&index)
+        })
+        .collect()
+}
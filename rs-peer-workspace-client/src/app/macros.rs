@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+
+use super::commands::AppCommand;
+use super::state::WorkspaceApp;
+
+/// How long a macro waits for its next command to become available, for example
+/// for a connection it just started.
+const STEP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A recorded sequence of commands, run from the palette or with its shortcut.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub commands: Vec<AppCommand>,
+    /// Shortcut such as `Ctrl+Alt+1`.
+    #[serde(default)]
+    pub shortcut: String,
+}
+
+#[derive(Default)]
+pub struct MacroState {
+    pub show: bool,
+    /// Commands run since recording started.
+    pub recording: Option<Vec<AppCommand>>,
+    queue: VecDeque<AppCommand>,
+    waiting_since: Option<Instant>,
+}
+
+/// Parses `Ctrl+Shift+Alt+Key`; at least one modifier is required.
+pub fn parse_shortcut(text: &str) -> Option<egui::KeyboardShortcut> {
+    let mut parts = text.split('+').map(str::trim);
+    let key = egui::Key::from_name(parts.next_back()?)?;
+    let mut modifiers = egui::Modifiers::NONE;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" => modifiers.alt = true,
+            _ => return None,
+        }
+    }
+    (!modifiers.is_none()).then(|| egui::KeyboardShortcut::new(modifiers, key))
+}
+
+impl WorkspaceApp {
+    pub fn toggle_macro_recording(&mut self) {
+        match self.macros.recording.take() {
+            None => self.macros.recording = Some(Vec::new()),
+            Some(commands) if commands.is_empty() => {}
+            Some(commands) => {
+                let mut number = self.settings.macros.len() + 1;
+                while self.find_macro(&format!("Macro {number}")).is_some() {
+                    number += 1;
+                }
+                self.settings.macros.push(Macro {
+                    name: format!("Macro {number}"),
+                    commands,
+                    shortcut: String::new(),
+                });
+                self.save_settings();
+                self.macros.show = true;
+            }
+        }
+    }
+
+    pub fn record_command(&mut self, command: &AppCommand) {
+        if let Some(recording) = &mut self.macros.recording
+            && !matches!(
+                command,
+                AppCommand::RecordMacro
+                    | AppCommand::Macros
+                    | AppCommand::RunMacro(_)
+                    | AppCommand::CommandPalette
+            )
+        {
+            recording.push(command.clone());
+        }
+    }
+
+    fn find_macro(&self, name: &str) -> Option<&Macro> {
+        self.settings.macros.iter().find(|item| item.name == name)
+    }
+
+    pub fn run_macro(&mut self, name: &str) {
+        if let Some(commands) = self.find_macro(name).map(|item| item.commands.clone()) {
+            self.macros.queue.extend(commands);
+        }
+    }
+
+    /// Runs queued macro commands in order. A command that is not available yet,
+    /// such as a task whose connection is still being established, holds up the
+    /// rest until it is or [`STEP_TIMEOUT`] passes.
+    pub fn advance_macro(&mut self, ctx: &egui::Context) {
+        while let Some(command) = self.macros.queue.front().cloned() {
+            if let AppCommand::Reconnect(name) = &command
+                && self
+                    .connections
+                    .get(name)
+                    .is_some_and(|state| state.connected)
+            {
+                self.macros.queue.pop_front();
+                continue;
+            }
+            if self.command_enabled(&command) && self.macro_step_ready(&command) {
+                self.macros.queue.pop_front();
+                self.macros.waiting_since = None;
+                self.run_command(command);
+                continue;
+            }
+            let waiting_since = *self.macros.waiting_since.get_or_insert_with(Instant::now);
+            if waiting_since.elapsed() > STEP_TIMEOUT {
+                let label = self.command_label(&command);
+                self.output
+                    .error(format!("Macro stopped: {label} did not become available"));
+                self.macros.queue.clear();
+                self.macros.waiting_since = None;
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+            break;
+        }
+    }
+
+    fn macro_step_ready(&self, command: &AppCommand) -> bool {
+        match command {
+            AppCommand::RunTask(name) => self
+                .project
+                .tasks
+                .iter()
+                .find(|task| &task.name == name)
+                .and_then(|task| self.connections.get(&task.connection_name))
+                .is_some_and(|state| state.connected),
+            _ => true,
+        }
+    }
+
+    pub fn handle_macro_shortcuts(&mut self, ctx: &egui::Context) {
+        let triggered: Vec<String> = self
+            .settings
+            .macros
+            .iter()
+            .filter(|item| {
+                parse_shortcut(&item.shortcut).is_some_and(|shortcut| {
+                    ctx.input_mut(|input| input.consume_shortcut(&shortcut))
+                })
+            })
+            .map(|item| item.name.clone())
+            .collect();
+        for name in triggered {
+            self.run_macro(&name);
+        }
+    }
+
+    pub fn draw_macros(&mut self, ctx: &egui::Context) {
+        if !self.macros.show {
+            return;
+        }
+
+        let mut open = true;
+        let mut macros = self.settings.macros.clone();
+        let mut run = None;
+        let mut remove = None;
+        let mut record = false;
+        let mut import = false;
+        let mut export = false;
+        egui::Window::new(self.i18n.tr("macros-title"))
+            .id(egui::Id::new("macros"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if macros.is_empty() {
+                    ui.label(self.i18n.tr("macros-empty"));
+                }
+                egui::Grid::new("macros-grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (idx, item) in macros.iter_mut().enumerate() {
+                            ui.add(egui::TextEdit::singleline(&mut item.name).desired_width(140.0));
+                            let steps: Vec<String> = item
+                                .commands
+                                .iter()
+                                .map(|command| self.command_label(command))
+                                .collect();
+                            ui.label(
+                                self.i18n
+                                    .tr_args("macros-steps", &[("count", steps.len().into())]),
+                            )
+                            .on_hover_text(steps.join("\n"));
+                            let valid = item.shortcut.trim().is_empty()
+                                || parse_shortcut(&item.shortcut).is_some();
+                            let mut shortcut = egui::TextEdit::singleline(&mut item.shortcut)
+                                .hint_text(self.i18n.tr("macros-shortcut-hint"))
+                                .desired_width(120.0);
+                            if !valid {
+                                shortcut = shortcut.text_color(ui.visuals().error_fg_color);
+                            }
+                            ui.add(shortcut);
+                            if ui.button(self.i18n.tr("macros-run")).clicked() {
+                                run = Some(item.name.clone());
+                            }
+                            if ui.button(self.i18n.tr("macros-delete")).clicked() {
+                                remove = Some(idx);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let id = if self.macros.recording.is_some() {
+                        "macros-stop-recording"
+                    } else {
+                        "macros-record"
+                    };
+                    record = ui.button(self.i18n.tr(id)).clicked();
+                    import = ui.button(self.i18n.tr("macros-import")).clicked();
+                    export = ui
+                        .add_enabled(
+                            !macros.is_empty(),
+                            egui::Button::new(self.i18n.tr("macros-export")),
+                        )
+                        .clicked();
+                });
+            });
+        self.macros.show = open;
+
+        if let Some(idx) = remove {
+            macros.remove(idx);
+        }
+        if macros != self.settings.macros {
+            self.settings.macros = macros;
+            self.save_settings();
+        }
+        if let Some(name) = run {
+            self.run_macro(&name);
+        }
+        if record {
+            self.toggle_macro_recording();
+        }
+        if export {
+            self.export_macros();
+        }
+        if import {
+            self.import_macros();
+        }
+    }
+
+    fn export_macros(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("macros.json")
+            .save_file()
+        else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(&self.settings.macros)
+            .map_err(std::io::Error::other)
+            .and_then(|text| std::fs::write(&path, text));
+        match result {
+            Ok(()) => self
+                .output
+                .info(format!("Exported macros to {}", path.display())),
+            Err(err) => self.output.error(format!("Failed to export macros: {err}")),
+        }
+    }
+
+    /// Adds the macros of an exported file, replacing the ones with the same name.
+    fn import_macros(&mut self) {
+        let Some(path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+        let imported: Vec<Macro> = match std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+        {
+            Ok(imported) => imported,
+            Err(err) => {
+                self.output.error(format!(
+                    "Failed to import macros from {}: {err}",
+                    path.display()
+                ));
+                return;
+            }
+        };
+        let count = imported.len();
+        for item in imported {
+            self.settings
+                .macros
+                .retain(|existing| existing.name != item.name);
+            self.settings.macros.push(item);
+        }
+        self.save_settings();
+        self.output
+            .info(format!("Imported {count} macro(s) from {}", path.display()));
+    }
+}
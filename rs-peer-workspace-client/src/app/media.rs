@@ -0,0 +1,231 @@
+use std::fs;
+use std::process::Command;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::media::{
+    pdf_page_count, pdf_version, MediaKind, MediaPreview, MAX_PREVIEW_BYTES,
+};
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource, EditorTab};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::PendingAction;
+
+const MEDIA_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+impl WorkspaceApp {
+    pub fn open_media(
+        &mut self,
+        path: &str,
+        source: EditorSource,
+        kind: MediaKind,
+        data: Vec<u8>,
+        preview: bool,
+    ) {
+        self.open_editor(EditorTab {
+            title: display_name_for_path(path),
+            path: path.to_string(),
+            source,
+            content: String::new(),
+            dirty: false,
+            remote_hash: None,
+            base: None,
+            cursor: 0,
+            restore_cursor: false,
+            preview,
+            media: Some(MediaPreview::new(kind, data)),
+        });
+    }
+
+    pub fn open_local_media(&mut self, path: &str, kind: MediaKind, preview: bool) {
+        let data = fs::metadata(path).and_then(|metadata| {
+            if metadata.len() > MAX_PREVIEW_BYTES {
+                Err(std::io::Error::other(too_large(metadata.len())))
+            } else {
+                fs::read(path)
+            }
+        });
+        match data {
+            Ok(data) => self.open_media(path, EditorSource::Local, kind, data, preview),
+            Err(err) => self.output.error(format!("Failed to read {path}: {err}")),
+        }
+    }
+
+    /// Reads the next chunk of a remote preview, starting after the bytes in `data`.
+    pub fn load_remote_media(
+        &mut self,
+        connection_name: &str,
+        path: &str,
+        kind: MediaKind,
+        preview: bool,
+        data: Vec<u8>,
+    ) {
+        let request_id = Uuid::new_v4();
+        let offset = data.len() as u64;
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadMedia {
+                path: path.to_string(),
+                connection_name: connection_name.to_string(),
+                kind,
+                preview,
+                data,
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ReadFileChunk {
+                    path: path.to_string(),
+                    offset,
+                    length: MEDIA_CHUNK_BYTES,
+                },
+            },
+        );
+    }
+
+    pub fn handle_media_chunk(
+        &mut self,
+        connection_name: String,
+        path: String,
+        kind: MediaKind,
+        preview: bool,
+        mut data: Vec<u8>,
+        result: RpcResult,
+    ) {
+        match result {
+            RpcResult::FileChunk {
+                data: chunk,
+                total_size,
+                ..
+            } => {
+                if total_size > MAX_PREVIEW_BYTES {
+                    self.output.connection_error(
+                        &connection_name,
+                        format!("cannot preview {path}: {}", too_large(total_size)),
+                    );
+                    return;
+                }
+                let done = chunk.is_empty() || data.len() + chunk.len() >= total_size as usize;
+                data.extend_from_slice(&chunk);
+                if done {
+                    self.open_media(
+                        &path,
+                        EditorSource::Remote { connection_name },
+                        kind,
+                        data,
+                        preview,
+                    );
+                } else {
+                    self.load_remote_media(&connection_name, &path, kind, preview, data);
+                }
+            }
+            RpcResult::Error { message } => self.output.connection_error(
+                &connection_name,
+                format!("failed to preview {path}: {message}"),
+            ),
+            _ => self.output.connection_error(
+                &connection_name,
+                format!("unexpected response previewing {path}"),
+            ),
+        }
+    }
+
+    /// Read-only content of an image or PDF tab. Images are drawn scaled to fit; SVG
+    /// and PDF files show their details and can be handed to the system viewer.
+    pub fn draw_media_preview(&mut self, ui: &mut egui::Ui, path: &str, media: &MediaPreview) {
+        let mut open_external = false;
+        ui.horizontal(|ui| {
+            ui.label(path);
+            ui.separator();
+            ui.label(format_bytes(media.data.len() as u64));
+            if media.kind == MediaKind::Pdf {
+                ui.separator();
+                ui.label(self.i18n.tr_args(
+                    "media-pdf-info",
+                    &[
+                        ("version", pdf_version(&media.data).unwrap_or_default().into()),
+                        ("pages", pdf_page_count(&media.data).into()),
+                    ],
+                ));
+            }
+            ui.separator();
+            open_external = ui.button(self.i18n.tr("media-open-external")).clicked();
+        });
+        ui.separator();
+
+        match media.kind {
+            MediaKind::Image => {
+                let extension = path.rsplit('.').next().unwrap_or("png").to_ascii_lowercase();
+                let uri = format!("bytes://media/{}.{extension}", media.id);
+                if ui.ctx().try_load_bytes(&uri).is_err() {
+                    ui.ctx().include_bytes(uri.clone(), media.data.clone());
+                }
+                egui::ScrollArea::both()
+                    .id_salt(("media", media.id))
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        ui.add(egui::Image::new(uri).shrink_to_fit());
+                    });
+            }
+            MediaKind::Svg => {
+                ui.label(self.i18n.tr("media-svg-source"));
+                egui::ScrollArea::both()
+                    .id_salt(("media", media.id))
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::Label::new(
+                                egui::RichText::new(String::from_utf8_lossy(&media.data))
+                                    .monospace(),
+                            )
+                            .extend(),
+                        );
+                    });
+            }
+            MediaKind::Pdf => {
+                ui.label(self.i18n.tr("media-pdf-external"));
+            }
+        }
+
+        if open_external && let Err(err) = open_in_system_viewer(path, media) {
+            self.output
+                .error(format!("Failed to open {path} in the system viewer: {err}"));
+        }
+    }
+}
+
+fn too_large(size: u64) -> String {
+    format!(
+        "{} is larger than the {} preview limit, download it instead",
+        format_bytes(size),
+        format_bytes(MAX_PREVIEW_BYTES)
+    )
+}
+
+/// Writes the preview to the temp directory and opens it with the platform's default
+/// application.
+fn open_in_system_viewer(path: &str, media: &MediaPreview) -> std::io::Result<()> {
+    let target = std::env::temp_dir().join(format!(
+        "rs-peer-preview-{}-{}",
+        media.id,
+        display_name_for_path(path)
+    ));
+    if !target.exists() {
+        fs::write(&target, &media.data)?;
+    }
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(&target).spawn()?;
+    Ok(())
+}
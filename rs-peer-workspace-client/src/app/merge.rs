@@ -0,0 +1,199 @@
+use eframe::egui;
+use rs_peer_workspace_shared::merge::{MergeChunk, ThreeWayMerge};
+
+use super::i18n::Localizer;
+use super::state::WorkspaceApp;
+use super::types::MergeView;
+
+impl WorkspaceApp {
+    /// Merges the remote content that refused a save with the tab's edits, using the
+    /// content last read from or written to the server as the base. Tabs without a
+    /// base fall back to the two-way compare view.
+    pub fn open_save_conflict(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        theirs: String,
+        hash: String,
+    ) {
+        let Some(tab) = self.open_files.iter().find(|tab| tab.path == path) else {
+            return;
+        };
+        let Some(base) = &tab.base else {
+            self.open_diff(connection_name, path, &theirs, Some(hash));
+            return;
+        };
+        let merge = ThreeWayMerge::new(base, &tab.content, &theirs);
+        self.merge_view = Some(MergeView {
+            path,
+            resolutions: vec![None; merge.conflict_count()],
+            merge,
+            theirs: Some((theirs, hash)),
+        });
+    }
+
+    /// Opens the conflicts git left in the tab's content for resolution.
+    pub fn open_marker_merge(&mut self, idx: usize) {
+        let Some(tab) = self.open_files.get(idx) else {
+            return;
+        };
+        let Some(merge) = ThreeWayMerge::from_conflict_markers(&tab.content) else {
+            self.output
+                .error(format!("{} has no complete conflict markers", tab.path));
+            return;
+        };
+        self.merge_view = Some(MergeView {
+            path: tab.path.clone(),
+            resolutions: vec![None; merge.conflict_count()],
+            merge,
+            theirs: None,
+        });
+    }
+
+    pub fn draw_merge_view(&mut self, ctx: &egui::Context) {
+        let Some(view) = &mut self.merge_view else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = None;
+        let unresolved = view.resolutions.iter().filter(|resolution| resolution.is_none()).count();
+        egui::Window::new(self.i18n.tr_args("merge-title", &[("path", view.path.clone().into())]))
+            .id(egui::Id::new("merge-view"))
+            .open(&mut open)
+            .default_size([1000.0, 600.0])
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr_args(
+                    "merge-summary",
+                    &[
+                        ("count", view.resolutions.len().into()),
+                        ("unresolved", unresolved.into()),
+                    ],
+                ));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() - 40.0)
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        let mut conflict = 0;
+                        for chunk in &view.merge.chunks {
+                            match chunk {
+                                MergeChunk::Resolved(lines) => {
+                                    for line in lines {
+                                        ui.monospace(line.trim_end_matches(['\r', '\n']));
+                                    }
+                                }
+                                MergeChunk::Conflict { base, mine, theirs } => {
+                                    let resolution = &mut view.resolutions[conflict];
+                                    ui.push_id(("merge-conflict", conflict), |ui| {
+                                        draw_conflict(ui, &self.i18n, base, mine, theirs, resolution);
+                                    });
+                                    conflict += 1;
+                                }
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(unresolved == 0, egui::Button::new(self.i18n.tr("merge-apply")))
+                        .clicked()
+                    {
+                        apply = Some(false);
+                    }
+                    if ui
+                        .add_enabled(unresolved == 0, egui::Button::new(self.i18n.tr("merge-apply-save")))
+                        .clicked()
+                    {
+                        apply = Some(true);
+                    }
+                });
+            });
+
+        if let Some(save) = apply {
+            self.apply_merge(save);
+        } else if !open {
+            self.merge_view = None;
+        }
+    }
+
+    fn apply_merge(&mut self, save: bool) {
+        let Some(view) = self.merge_view.take() else {
+            return;
+        };
+        let Some(idx) = self.open_files.iter().position(|tab| tab.path == view.path) else {
+            self.output
+                .error(format!("{} is no longer open", view.path));
+            return;
+        };
+        let resolutions: Vec<String> = view.resolutions.into_iter().flatten().collect();
+        let merged = view.merge.result(&resolutions);
+        let tab = &mut self.open_files[idx];
+        if let Some((theirs, hash)) = view.theirs {
+            tab.base = Some(theirs);
+            tab.remote_hash = Some(hash);
+        }
+        if tab.content != merged {
+            tab.content = merged;
+            tab.dirty = true;
+            tab.preview = false;
+        }
+        if save {
+            self.save_editor(idx);
+        }
+    }
+}
+
+fn draw_conflict(
+    ui: &mut egui::Ui,
+    i18n: &Localizer,
+    base: &[String],
+    mine: &[String],
+    theirs: &[String],
+    resolution: &mut Option<String>,
+) {
+    let tint = egui::Color32::from_rgba_unmultiplied(200, 160, 40, 40);
+    egui::Frame::group(ui.style()).fill(tint).show(ui, |ui| {
+        ui.columns(3, |columns| {
+            for (column, (label, lines)) in columns.iter_mut().zip([
+                (i18n.tr("merge-base"), base),
+                (i18n.tr("merge-mine"), mine),
+                (i18n.tr("merge-theirs"), theirs),
+            ]) {
+                column.strong(label);
+                for line in lines {
+                    column.monospace(line.trim_end_matches(['\r', '\n']));
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            let choices = [
+                ("merge-take-mine", mine.concat()),
+                ("merge-take-theirs", theirs.concat()),
+                ("merge-take-both", [mine, theirs].concat().concat()),
+                ("merge-take-base", base.concat()),
+            ];
+            for (label, text) in choices {
+                if ui.button(i18n.tr(label)).clicked() {
+                    *resolution = Some(text);
+                }
+            }
+        });
+        match resolution {
+            Some(text) => {
+                ui.label(i18n.tr("merge-result"));
+                ui.add(
+                    egui::TextEdit::multiline(text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(2),
+                );
+            }
+            None => {
+                ui.colored_label(ui.visuals().warn_fg_color, i18n.tr("merge-unresolved"));
+            }
+        }
+    });
+}
@@ -1,9 +1,55 @@
+mod a11y;
 mod actions;
+mod address;
+mod commands;
+mod completion;
+mod connections;
+mod copy;
+mod crash;
 mod dialogs;
+mod diff;
 mod editor;
+mod env;
 mod explorer;
+mod format;
+mod git;
+mod history;
+mod i18n;
+mod icons;
+mod journal;
+mod locks;
+mod logs;
+mod lsp;
+mod macros;
+mod media;
+mod merge;
+mod notebook;
+mod notify;
+mod output;
+mod panes;
+mod pinned;
+mod ports;
+mod processes;
+mod profiles;
+mod properties;
+mod quickopen;
+mod readahead;
+mod rich;
+mod runs;
+mod search;
+mod services;
+mod session;
+mod settings;
 mod state;
+mod system;
+mod taskmanager;
+mod tasks;
+mod time;
+mod transfers;
 pub mod tree;
 pub mod types;
+mod unsaved;
+mod viewer;
+mod workspace_search;
 
 pub use state::WorkspaceApp;
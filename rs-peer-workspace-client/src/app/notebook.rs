@@ -0,0 +1,222 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use uuid::Uuid;
+
+use super::rich::render_attachment;
+use super::state::WorkspaceApp;
+use super::types::{NotebookCell, PendingAction};
+
+enum NotebookCommand {
+    Run(usize),
+    Remove(usize),
+    RunAll,
+}
+
+impl WorkspaceApp {
+    pub fn draw_notebook(&mut self, ctx: &egui::Context) {
+        if !self.show_notebook {
+            return;
+        }
+
+        let mut open = self.show_notebook;
+        let mut command = None;
+        let mut kernel_action = None;
+        egui::Window::new(self.i18n.tr("notebook-title"))
+            .id(egui::Id::new("notebook"))
+            .open(&mut open)
+            .default_size([640.0, 560.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let previous = self.notebook.connection_name.clone();
+                    ui.add_enabled_ui(self.notebook.kernel.is_none(), |ui| {
+                        egui::ComboBox::from_id_salt("notebook-connection")
+                            .selected_text(if self.notebook.connection_name.is_empty() {
+                                self.i18n.tr("select-connection")
+                            } else {
+                                self.notebook.connection_name.clone()
+                            })
+                            .show_ui(ui, |ui| {
+                                for connection in &self.project.connections {
+                                    ui.selectable_value(
+                                        &mut self.notebook.connection_name,
+                                        connection.name.clone(),
+                                        &connection.name,
+                                    );
+                                }
+                            });
+                    });
+                    if previous != self.notebook.connection_name {
+                        self.notebook.kernel = None;
+                    }
+
+                    match &self.notebook.kernel {
+                        Some(kernel) => {
+                            ui.label(self.i18n.tr_args(
+                                "notebook-kernel-name",
+                                &[("name", kernel.kernel_name.clone().into())],
+                            ));
+                            if ui.button(self.i18n.tr("notebook-interrupt")).clicked() {
+                                kernel_action = Some(RpcAction::InterruptKernel {
+                                    kernel_id: kernel.kernel_id,
+                                });
+                            }
+                            if ui.button(self.i18n.tr("notebook-shutdown")).clicked() {
+                                kernel_action = Some(RpcAction::ShutdownKernel {
+                                    kernel_id: kernel.kernel_id,
+                                });
+                            }
+                        }
+                        None => {
+                            let label = ui.label(self.i18n.tr("notebook-kernel"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.notebook.kernel_name)
+                                    .hint_text("python3")
+                                    .desired_width(100.0),
+                            )
+                            .labelled_by(label.id);
+                            let can_start = !self.notebook.connection_name.is_empty()
+                                && !self.notebook.starting;
+                            if ui.add_enabled(can_start, egui::Button::new(self.i18n.tr("notebook-start"))).clicked() {
+                                kernel_action = Some(RpcAction::StartKernel {
+                                    kernel_name: Some(self.notebook.kernel_name.trim().to_string())
+                                        .filter(|name| !name.is_empty()),
+                                });
+                            }
+                            if self.notebook.starting {
+                                ui.spinner();
+                            }
+                        }
+                    }
+                });
+                ui.separator();
+
+                let has_kernel = self.notebook.kernel.is_some();
+                egui::ScrollArea::vertical()
+                    .id_salt("notebook-cells")
+                    .auto_shrink([false, false])
+                    .max_height(ui.available_height() - 32.0)
+                    .show(ui, |ui| {
+                        for (idx, cell) in self.notebook.cells.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                let label = match cell.execution_count {
+                                    Some(count) => format!("[{count}]"),
+                                    None => "[ ]".to_string(),
+                                };
+                                ui.monospace(label);
+                                if ui
+                                    .add_enabled(has_kernel && !cell.running, egui::Button::new(self.i18n.tr("run")))
+                                    .clicked()
+                                {
+                                    command = Some(NotebookCommand::Run(idx));
+                                }
+                                if ui.small_button(self.i18n.tr("remove")).clicked() {
+                                    command = Some(NotebookCommand::Remove(idx));
+                                }
+                                if cell.running {
+                                    ui.spinner();
+                                }
+                            });
+                            let response = ui.add(
+                                egui::TextEdit::multiline(&mut cell.code)
+                                    .code_editor()
+                                    .desired_rows(3)
+                                    .desired_width(f32::INFINITY),
+                            );
+                            if has_kernel
+                                && response.has_focus()
+                                && ui.input(|input| {
+                                    input.modifiers.shift && input.key_pressed(egui::Key::Enter)
+                                })
+                            {
+                                command = Some(NotebookCommand::Run(idx));
+                            }
+                            if !cell.output.is_empty() {
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(&cell.output).monospace())
+                                        .wrap(),
+                                );
+                            }
+                            for (attachment_idx, attachment) in cell.attachments.iter().enumerate() {
+                                render_attachment(
+                                    ui,
+                                    &format!("notebook-{}-{attachment_idx}", cell.id),
+                                    attachment,
+                                );
+                            }
+                            if let Some(error) = &cell.error {
+                                ui.colored_label(
+                                    ui.visuals().error_fg_color,
+                                    egui::RichText::new(error).monospace(),
+                                );
+                            }
+                            ui.separator();
+                        }
+                    });
+
+                ui.horizontal(|ui| {
+                    if ui.button(self.i18n.tr("notebook-add-cell")).clicked() {
+                        self.notebook.cells.push(NotebookCell::new());
+                    }
+                    if ui
+                        .add_enabled(has_kernel, egui::Button::new(self.i18n.tr("notebook-run-all")))
+                        .clicked()
+                    {
+                        command = Some(NotebookCommand::RunAll);
+                    }
+                });
+            });
+        self.show_notebook = open;
+
+        if let Some(action) = kernel_action {
+            let pending = match action {
+                RpcAction::StartKernel { .. } => {
+                    self.notebook.starting = true;
+                    PendingAction::StartKernel
+                }
+                _ => PendingAction::KernelControl,
+            };
+            self.send_notebook_rpc(pending, action);
+        }
+        match command {
+            Some(NotebookCommand::Run(idx)) => self.run_notebook_cell(idx),
+            Some(NotebookCommand::Remove(idx)) => {
+                self.notebook.cells.remove(idx);
+            }
+            Some(NotebookCommand::RunAll) => {
+                for idx in 0..self.notebook.cells.len() {
+                    self.run_notebook_cell(idx);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn run_notebook_cell(&mut self, idx: usize) {
+        let Some(kernel_id) = self.notebook.kernel.as_ref().map(|kernel| kernel.kernel_id) else {
+            return;
+        };
+        let Some(cell) = self.notebook.cells.get_mut(idx) else {
+            return;
+        };
+        if cell.running || cell.code.trim().is_empty() {
+            return;
+        }
+        cell.running = true;
+        cell.output.clear();
+        cell.attachments.clear();
+        cell.error = None;
+        let pending = PendingAction::ExecuteCell { cell_id: cell.id };
+        let action = RpcAction::ExecuteCell {
+            kernel_id,
+            code: cell.code.clone(),
+        };
+        self.send_notebook_rpc(pending, action);
+    }
+
+    fn send_notebook_rpc(&mut self, pending: PendingAction, action: RpcAction) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, pending);
+        let connection_name = self.notebook.connection_name.clone();
+        self.send_rpc(&connection_name, RpcRequest { request_id, action });
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use eframe::egui;
+use notify_rust::Notification;
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::CommandNotice;
+
+const BELL: char = '\u{7}';
+
+impl WorkspaceApp {
+    /// Queues a notification for a terminal command that finished while the window
+    /// was in the background, if it ran long enough or its output rang the bell.
+    pub fn announce_command(
+        &mut self,
+        connection_name: &str,
+        terminal_id: Uuid,
+        block_id: Uuid,
+        elapsed: Duration,
+        outcome: CommandOutcome,
+    ) {
+        if self.window_focused {
+            return;
+        }
+        let Some(block) = self
+            .terminals
+            .iter()
+            .find(|term| term.id == terminal_id)
+            .and_then(|term| term.blocks.iter().find(|block| block.id == block_id))
+        else {
+            return;
+        };
+        let settings = &self.project.notifications;
+        let slow = settings.command_finished && elapsed.as_secs() >= settings.min_duration_secs;
+        let bell = settings.bell && outcome == CommandOutcome::Bell;
+        if !slow && !bell {
+            return;
+        }
+
+        let title = self.i18n.tr_args(
+            if outcome == CommandOutcome::Failed {
+                "notify-command-failed"
+            } else {
+                "notify-command-finished"
+            },
+            &[("connection", connection_name.into())],
+        );
+        let body = self.i18n.tr_args(
+            "notify-command-body",
+            &[
+                ("command", block.command.lines().next().unwrap_or_default().into()),
+                ("duration", format_duration(elapsed).into()),
+            ],
+        );
+        self.notices.push(CommandNotice { title, body });
+    }
+
+    /// Flashes the taskbar entry and shows the queued notifications.
+    pub fn flush_notices(&mut self, ctx: &egui::Context) {
+        if self.notices.is_empty() {
+            return;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+            egui::UserAttentionType::Informational,
+        ));
+        for notice in self.notices.drain(..) {
+            // Showing a notification can block on the desktop's notification service.
+            std::thread::spawn(move || {
+                if let Err(err) = Notification::new()
+                    .appname("rs-peer-workspace")
+                    .summary(&notice.title)
+                    .body(&notice.body)
+                    .show()
+                {
+                    tracing::warn!(error = %err, "failed to show notification");
+                }
+            });
+        }
+    }
+
+    pub fn draw_notification_menu(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.project.notifications;
+        ui.checkbox(
+            &mut settings.command_finished,
+            self.i18n.tr("notify-menu-command-finished"),
+        );
+        ui.add_enabled_ui(settings.command_finished, |ui| {
+            ui.horizontal(|ui| {
+                let label = ui.label(self.i18n.tr("notify-menu-min-duration"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.min_duration_secs)
+                        .range(0..=86_400)
+                        .suffix(" s"),
+                )
+                .labelled_by(label.id);
+            });
+        });
+        ui.checkbox(&mut settings.bell, self.i18n.tr("notify-menu-bell"));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandOutcome {
+    Finished,
+    /// Finished with a BEL in its output.
+    Bell,
+    Failed,
+}
+
+/// Removes BEL characters, which the output view cannot show, and reports whether
+/// there were any.
+pub fn take_bell(output: &str) -> (String, CommandOutcome) {
+    if output.contains(BELL) {
+        (output.replace(BELL, ""), CommandOutcome::Bell)
+    } else {
+        (output.to_string(), CommandOutcome::Finished)
+    }
+}
+
+pub fn format_duration(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::Write as _;
+
+use chrono::{DateTime, SecondsFormat};
+use eframe::egui;
+use rfd::FileDialog;
+use rs_peer_workspace_shared::relay::unix_now;
+
+use super::state::WorkspaceApp;
+
+/// Entries kept in the Output tab; older ones are dropped as new ones arrive.
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+pub struct LogEntry {
+    pub at: u64,
+    /// `None` for messages that are not about one connection.
+    pub connection: Option<String>,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// Messages shown in the Output tab, with the filters the tab applies to them.
+pub struct OutputLog {
+    entries: VecDeque<LogEntry>,
+    connections: BTreeSet<String>,
+    /// Connections whose entries are hidden; the empty name stands for general messages.
+    hidden: HashSet<String>,
+    min_level: LogLevel,
+    search: String,
+}
+
+impl Default for OutputLog {
+    fn default() -> Self {
+        let mut log = Self {
+            entries: VecDeque::new(),
+            connections: BTreeSet::new(),
+            hidden: HashSet::new(),
+            min_level: LogLevel::Info,
+            search: String::new(),
+        };
+        log.info("Ready.");
+        log
+    }
+}
+
+impl OutputLog {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(None, LogLevel::Info, message.into());
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(None, LogLevel::Warn, message.into());
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(None, LogLevel::Error, message.into());
+    }
+
+    pub fn connection_info(&mut self, connection_name: &str, message: impl Into<String>) {
+        self.push(Some(connection_name), LogLevel::Info, message.into());
+    }
+
+    pub fn connection_warn(&mut self, connection_name: &str, message: impl Into<String>) {
+        self.push(Some(connection_name), LogLevel::Warn, message.into());
+    }
+
+    pub fn connection_error(&mut self, connection_name: &str, message: impl Into<String>) {
+        self.push(Some(connection_name), LogLevel::Error, message.into());
+    }
+
+    fn push(&mut self, connection: Option<&str>, level: LogLevel, message: String) {
+        if let Some(connection) = connection
+            && !self.connections.contains(connection)
+        {
+            self.connections.insert(connection.to_string());
+        }
+        self.entries.push_back(LogEntry {
+            at: unix_now(),
+            connection: connection.map(str::to_string),
+            level,
+            message,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    fn visible(&self) -> impl Iterator<Item = &LogEntry> {
+        let search = self.search.to_lowercase();
+        self.entries.iter().filter(move |entry| {
+            entry.level >= self.min_level
+                && !self.hidden.contains(entry.connection.as_deref().unwrap_or_default())
+                && (search.is_empty() || entry.message.to_lowercase().contains(&search))
+        })
+    }
+
+    /// The entries passing the current filters, one per line with a UTC timestamp.
+    fn export(&self) -> String {
+        self.visible().fold(String::new(), |mut text, entry| {
+            let at = DateTime::from_timestamp(entry.at as i64, 0)
+                .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default();
+            let _ = match &entry.connection {
+                Some(connection) => {
+                    writeln!(text, "{at} {} [{connection}] {}", entry.level.label(), entry.message)
+                }
+                None => writeln!(text, "{at} {} {}", entry.level.label(), entry.message),
+            };
+            text
+        })
+    }
+}
+
+impl WorkspaceApp {
+    pub fn draw_output(&mut self, ui: &mut egui::Ui) {
+        let mut copy = false;
+        let mut save = false;
+        ui.horizontal_wrapped(|ui| {
+            let output = &mut self.output;
+            ui.label(self.i18n.tr("output-level"));
+            egui::ComboBox::from_id_salt("output-level")
+                .selected_text(self.i18n.tr(filter_id(output.min_level)))
+                .show_ui(ui, |ui| {
+                    for level in [LogLevel::Info, LogLevel::Warn, LogLevel::Error] {
+                        ui.selectable_value(&mut output.min_level, level, self.i18n.tr(filter_id(level)));
+                    }
+                });
+            let label = ui.label(self.i18n.tr("output-search"));
+            ui.text_edit_singleline(&mut output.search).labelled_by(label.id);
+            copy = ui.button(self.i18n.tr("output-copy")).clicked();
+            save = ui.button(self.i18n.tr("output-save")).clicked();
+            if ui.button(self.i18n.tr("output-clear")).clicked() {
+                output.entries.clear();
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            let output = &mut self.output;
+            let general = self.i18n.tr("output-general");
+            let chips = std::iter::once((String::new(), general))
+                .chain(output.connections.iter().map(|name| (name.clone(), name.clone())));
+            for (key, label) in chips {
+                let shown = !output.hidden.contains(&key);
+                if ui.selectable_label(shown, label).clicked() {
+                    if shown {
+                        output.hidden.insert(key);
+                    } else {
+                        output.hidden.remove(&key);
+                    }
+                }
+            }
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("output-entries")
+            .auto_shrink(false)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("output-grid").num_columns(4).show(ui, |ui| {
+                    for entry in self.output.visible() {
+                        ui.label(egui::RichText::new(self.i18n.clock(entry.at)).weak())
+                            .on_hover_text(self.i18n.datetime(entry.at));
+                        let color = match entry.level {
+                            LogLevel::Info => ui.visuals().weak_text_color(),
+                            LogLevel::Warn => ui.visuals().warn_fg_color,
+                            LogLevel::Error => ui.visuals().error_fg_color,
+                        };
+                        ui.colored_label(color, self.i18n.tr(level_id(entry.level)));
+                        ui.label(entry.connection.as_deref().unwrap_or_default());
+                        ui.label(&entry.message);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if copy {
+            ui.ctx().copy_text(self.output.export());
+        }
+        if save
+            && let Some(path) = FileDialog::new()
+                .set_file_name("output.log")
+                .add_filter("Log", &["log", "txt"])
+                .save_file()
+            && let Err(err) = std::fs::write(&path, self.output.export())
+        {
+            self.output
+                .error(format!("Failed to save {}: {err}", path.display()));
+        }
+    }
+}
+
+fn level_id(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Info => "output-info",
+        LogLevel::Warn => "output-warn",
+        LogLevel::Error => "output-error",
+    }
+}
+
+/// Label of the level filter that shows `level` and everything more severe.
+fn filter_id(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Info => "output-level-info",
+        LogLevel::Warn => "output-level-warn",
+        LogLevel::Error => "output-level-error",
+    }
+}
@@ -0,0 +1,64 @@
+use rs_peer_workspace_shared::project::EditorTab;
+
+use super::state::WorkspaceApp;
+use super::types::{EditorPane, SplitOrientation};
+
+impl WorkspaceApp {
+    pub fn pane_editor(&self, pane: EditorPane) -> Option<usize> {
+        match pane {
+            EditorPane::Primary => self.selected_editor,
+            EditorPane::Secondary => self.secondary_editor,
+        }
+    }
+
+    pub fn pane_editor_mut(&mut self, pane: EditorPane) -> &mut Option<usize> {
+        match pane {
+            EditorPane::Primary => &mut self.selected_editor,
+            EditorPane::Secondary => &mut self.secondary_editor,
+        }
+    }
+
+    /// Editor shown in the focused pane; the target of Ctrl+S.
+    pub fn active_editor(&self) -> Option<usize> {
+        self.pane_editor(self.focused_pane)
+    }
+
+    /// Shows editor `idx` in the focused pane.
+    pub fn show_editor(&mut self, idx: usize) {
+        *self.pane_editor_mut(self.focused_pane) = Some(idx);
+    }
+
+    /// Adds `tab` and shows it. A preview tab takes the place of the current preview
+    /// unless that one has unsaved edits.
+    pub fn open_editor(&mut self, tab: EditorTab) {
+        self.refresh_git_gutter(&tab.source, &tab.path);
+        let replaced = tab
+            .preview
+            .then(|| self.open_files.iter().position(|open| open.preview && !open.dirty))
+            .flatten();
+        let idx = match replaced {
+            Some(idx) => {
+                let old = std::mem::replace(&mut self.open_files[idx], tab);
+                self.release_editor_lock(&old);
+                idx
+            }
+            None => {
+                self.open_files.push(tab);
+                self.open_files.len() - 1
+            }
+        };
+        self.show_editor(idx);
+    }
+
+    pub fn set_editor_split(&mut self, split: Option<SplitOrientation>) {
+        self.editor_split = split;
+        if split.is_none() {
+            if self.focused_pane == EditorPane::Secondary {
+                self.selected_editor = self.secondary_editor.or(self.selected_editor);
+            }
+            self.focused_pane = EditorPane::Primary;
+        } else if self.secondary_editor.is_none() {
+            self.secondary_editor = self.selected_editor;
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use eframe::egui;
+use rs_peer_workspace_shared::project::{display_name_for_path, FolderSource, PinnedPath, ProjectFolder};
+
+use super::a11y::{expander, tree_item};
+use super::icons::icon_label;
+use super::state::WorkspaceApp;
+use super::transfers::remote_parent;
+
+impl WorkspaceApp {
+    /// The "Pinned" section at the top of the explorer, shown once something is
+    /// pinned.
+    pub fn draw_pinned(&mut self, ui: &mut egui::Ui) {
+        if self.project.pinned.is_empty() {
+            return;
+        }
+        egui::CollapsingHeader::new(self.i18n.tr("explorer-pinned"))
+            .id_salt("explorer-pinned")
+            .default_open(true)
+            .show(ui, |ui| {
+                for pin in self.project.pinned.clone() {
+                    self.render_pinned(ui, &pin);
+                }
+            });
+    }
+
+    /// One pin: a click opens a file or expands a directory in place, a middle
+    /// click opens a terminal in the directory.
+    fn render_pinned(&mut self, ui: &mut egui::Ui, pin: &PinnedPath) {
+        let folder = self.containing_folder(&pin.source);
+        let path = pin.source.path();
+        let name = display_name_for_path(path);
+        let id = format!("dir:{path}");
+        let is_open = pin.is_dir && self.explorer_expanded.contains(&id);
+
+        ui.horizontal(|ui| {
+            let mut toggle = false;
+            if pin.is_dir {
+                toggle = expander(ui, &self.i18n, is_open, &name).clicked();
+            } else {
+                ui.label(" ");
+            }
+            icon_label(ui, self.icons.for_entry(&name, pin.is_dir, false, is_open));
+            let response = ui.selectable_label(false, &name);
+            tree_item(&response, pin.is_dir.then_some(is_open));
+            let location = match &pin.source {
+                FolderSource::Local { path } => path.clone(),
+                FolderSource::Remote { connection_name, path } => format!("[{connection_name}] {path}"),
+            };
+            let response = response.on_hover_text(location);
+            if response.clicked() {
+                if pin.is_dir {
+                    toggle = true;
+                } else {
+                    self.open_path(&folder, path, false);
+                }
+            }
+            if toggle {
+                if is_open {
+                    self.explorer_expanded.remove(&id);
+                } else {
+                    self.explorer_expanded.insert(id.clone());
+                    self.load_children(&folder, path);
+                }
+            }
+            if response.middle_clicked() {
+                self.open_pinned_terminal(&folder, pin);
+            }
+            response.context_menu(|ui| {
+                if matches!(pin.source, FolderSource::Remote { .. })
+                    && ui.button(self.i18n.tr("explorer-open-terminal")).clicked()
+                {
+                    ui.close_menu();
+                    self.open_pinned_terminal(&folder, pin);
+                }
+                if ui.button(self.i18n.tr("explorer-unpin")).clicked() {
+                    ui.close_menu();
+                    self.project.toggle_pin(pin.clone());
+                }
+            });
+        });
+
+        if is_open {
+            for child in self.sorted_children(path) {
+                self.render_tree_entry(ui, &folder, &child, 1);
+            }
+        }
+    }
+
+    /// Opens a terminal in a pinned directory, or in the directory of a pinned file.
+    fn open_pinned_terminal(&mut self, folder: &ProjectFolder, pin: &PinnedPath) {
+        let path = pin.source.path();
+        if matches!(pin.source, FolderSource::Local { .. }) {
+            self.output
+                .warn(format!("Terminals run on a connection; {path} is a local path."));
+            return;
+        }
+        let dir = if pin.is_dir { path.to_string() } else { remote_parent(path) };
+        self.open_folder_terminal(folder, &dir);
+    }
+
+    /// "Pin" or "Unpin" in the context menu of an explorer entry.
+    pub fn pin_button(&mut self, ui: &mut egui::Ui, folder: &ProjectFolder, path: &str, is_dir: bool) {
+        let source = match &folder.source {
+            FolderSource::Local { .. } => FolderSource::Local {
+                path: path.to_string(),
+            },
+            FolderSource::Remote { connection_name, .. } => FolderSource::Remote {
+                connection_name: connection_name.clone(),
+                path: path.to_string(),
+            },
+        };
+        let label = if self.project.is_pinned(&source) {
+            "explorer-unpin"
+        } else {
+            "explorer-pin"
+        };
+        if ui.button(self.i18n.tr(label)).clicked() {
+            ui.close_menu();
+            self.project.toggle_pin(PinnedPath { source, is_dir });
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use eframe::egui;
+use rs_peer_workspace_core::{ConnectionCommand, ForwardStatus};
+use rs_peer_workspace_shared::project::{ForwardDirection, PortForward};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+
+enum PortOperation {
+    Toggle(Uuid),
+    Remove(Uuid),
+}
+
+impl WorkspaceApp {
+    pub fn draw_ports(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let form = &mut self.forward_form;
+            egui::ComboBox::from_id_salt("ports-connection")
+                .selected_text(if form.connection_name.is_empty() {
+                    self.i18n.tr("select-connection")
+                } else {
+                    form.connection_name.clone()
+                })
+                .show_ui(ui, |ui| {
+                    for connection in &self.project.connections {
+                        ui.selectable_value(&mut form.connection_name, connection.name.clone(), &connection.name);
+                    }
+                });
+            egui::ComboBox::from_id_salt("ports-direction")
+                .selected_text(direction_label(&self.i18n, form.direction))
+                .show_ui(ui, |ui| {
+                    for direction in [ForwardDirection::Local, ForwardDirection::Remote] {
+                        ui.selectable_value(&mut form.direction, direction, direction_label(&self.i18n, direction));
+                    }
+                });
+            let label = ui.label(self.i18n.tr("ports-listen-port"));
+            ui.add(egui::TextEdit::singleline(&mut form.listen_port).desired_width(60.0))
+                .labelled_by(label.id);
+            let label = ui.label(self.i18n.tr("ports-target"));
+            ui.add(
+                egui::TextEdit::singleline(&mut form.host)
+                    .hint_text("127.0.0.1")
+                    .desired_width(140.0),
+            )
+            .labelled_by(label.id);
+            ui.label(":");
+            ui.add(egui::TextEdit::singleline(&mut form.port).desired_width(60.0));
+            if ui.button(self.i18n.tr("ports-add")).clicked() {
+                self.add_forward();
+            }
+        });
+        ui.separator();
+
+        if self.project.port_forwards.is_empty() {
+            ui.label(self.i18n.tr("ports-empty"));
+            return;
+        }
+
+        let mut operation = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("ports-grid").striped(true).show(ui, |ui| {
+                for forward in &self.project.port_forwards {
+                    let state = self
+                        .forward_states
+                        .get(&forward.connection_name)
+                        .and_then(|forwards| forwards.get(&forward.id));
+                    let listening = match state.map(|state| &state.status) {
+                        Some(ForwardStatus::Listening { port }) => *port,
+                        _ => forward.listen_port,
+                    };
+                    ui.label(format!("[{}]", forward.connection_name));
+                    ui.label(match forward.direction {
+                        ForwardDirection::Local => format!("localhost:{listening} → {}:{}", forward.host, forward.port),
+                        ForwardDirection::Remote => {
+                            format!("{}:{listening} → {}:{}", forward.connection_name, forward.host, forward.port)
+                        }
+                    })
+                    .on_hover_text(direction_label(&self.i18n, forward.direction));
+                    if !forward.enabled {
+                        ui.label(self.i18n.tr("ports-stopped"));
+                    } else if !self.is_connected(&forward.connection_name) {
+                        ui.label(self.i18n.tr("ports-waiting"));
+                    } else {
+                        match state.map(|state| (&state.status, state.active)) {
+                            None | Some((ForwardStatus::Starting, _)) => {
+                                ui.spinner();
+                            }
+                            Some((ForwardStatus::Listening { .. }, active)) => {
+                                ui.label(self.i18n.tr_args("ports-active", &[("count", active.into())]));
+                            }
+                            Some((ForwardStatus::Failed(error), _)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, self.i18n.tr("ports-failed"))
+                                    .on_hover_text(error);
+                            }
+                        }
+                    }
+                    match state {
+                        Some(state) => {
+                            let traffic = ui.label(format!(
+                                "↑ {} ↓ {}",
+                                format_bytes(state.bytes_sent),
+                                format_bytes(state.bytes_received)
+                            ));
+                            if let Some(error) = &state.last_error {
+                                traffic.on_hover_text(self.i18n.tr_args(
+                                    "ports-last-error",
+                                    &[("error", error.clone().into())],
+                                ));
+                            }
+                        }
+                        None => {
+                            ui.label("");
+                        }
+                    }
+                    let toggle = if forward.enabled { "ports-stop" } else { "ports-start" };
+                    if ui.small_button(self.i18n.tr(toggle)).clicked() {
+                        operation = Some(PortOperation::Toggle(forward.id));
+                    }
+                    if ui.small_button(self.i18n.tr("remove")).clicked() {
+                        operation = Some(PortOperation::Remove(forward.id));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        match operation {
+            Some(PortOperation::Toggle(id)) => {
+                if let Some(forward) = self.project.port_forwards.iter_mut().find(|forward| forward.id == id) {
+                    forward.enabled = !forward.enabled;
+                    let forward = forward.clone();
+                    let command = if forward.enabled {
+                        ConnectionCommand::StartForward(forward.clone())
+                    } else {
+                        ConnectionCommand::StopForward(forward.id)
+                    };
+                    self.send_forward_command(&forward.connection_name, command);
+                }
+            }
+            Some(PortOperation::Remove(id)) => {
+                if let Some(index) = self.project.port_forwards.iter().position(|forward| forward.id == id) {
+                    let forward = self.project.port_forwards.remove(index);
+                    self.send_forward_command(&forward.connection_name, ConnectionCommand::StopForward(id));
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn add_forward(&mut self) {
+        let form = &self.forward_form;
+        if form.connection_name.is_empty() {
+            self.output.error("Select a connection to forward a port through");
+            return;
+        }
+        let listen_port = match form.listen_port.trim() {
+            "" => Ok(0),
+            port => port.parse::<u16>(),
+        };
+        let (Ok(listen_port), Ok(port)) = (listen_port, form.port.trim().parse::<u16>()) else {
+            self.output.error("Port forwards need valid port numbers");
+            return;
+        };
+        let host = match form.host.trim() {
+            "" => "127.0.0.1".to_string(),
+            host => host.to_string(),
+        };
+        let forward = PortForward {
+            id: Uuid::new_v4(),
+            connection_name: form.connection_name.clone(),
+            direction: form.direction,
+            listen_port,
+            host,
+            port,
+            enabled: true,
+        };
+        self.forward_form.listen_port.clear();
+        self.forward_form.port.clear();
+        self.project.port_forwards.push(forward.clone());
+        let connection_name = forward.connection_name.clone();
+        self.send_forward_command(&connection_name, ConnectionCommand::StartForward(forward));
+    }
+
+    /// Starts the enabled forwards of a connection that has just connected.
+    pub fn start_forwards(&mut self, connection_name: &str) {
+        let forwards: Vec<PortForward> = self
+            .project
+            .port_forwards
+            .iter()
+            .filter(|forward| forward.enabled && forward.connection_name == connection_name)
+            .cloned()
+            .collect();
+        for forward in forwards {
+            self.send_forward_command(connection_name, ConnectionCommand::StartForward(forward));
+        }
+    }
+
+    fn is_connected(&self, connection_name: &str) -> bool {
+        self.connections
+            .get(connection_name)
+            .is_some_and(|connection| connection.connected)
+    }
+
+    /// Sends a forward command to a connection that is up; the others start their
+    /// forwards when they connect.
+    fn send_forward_command(&mut self, connection_name: &str, command: ConnectionCommand) {
+        if let Some(connection) = self.connections.get(connection_name)
+            && connection.connected
+        {
+            let _ = connection.command_tx.send(command);
+        }
+    }
+}
+
+fn direction_label(i18n: &super::i18n::Localizer, direction: ForwardDirection) -> String {
+    match direction {
+        ForwardDirection::Local => i18n.tr("ports-local"),
+        ForwardDirection::Remote => i18n.tr("ports-remote"),
+    }
+}
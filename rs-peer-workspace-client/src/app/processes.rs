@@ -0,0 +1,261 @@
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::{PendingAction, ProcessSort, ProcessWatch};
+
+/// How often the list refreshes while the tab is shown.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+enum ProcessOperation {
+    Kill { pid: u32, force: bool },
+    Watch { pid: u32, name: String },
+}
+
+impl WorkspaceApp {
+    pub fn draw_processes(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let previous = self.processes.connection_name.clone();
+            egui::ComboBox::from_id_salt("processes-connection")
+                .selected_text(if self.processes.connection_name.is_empty() {
+                    self.i18n.tr("select-connection")
+                } else {
+                    self.processes.connection_name.clone()
+                })
+                .show_ui(ui, |ui| {
+                    for connection in &self.project.connections {
+                        ui.selectable_value(
+                            &mut self.processes.connection_name,
+                            connection.name.clone(),
+                            &connection.name,
+                        );
+                    }
+                });
+            if previous != self.processes.connection_name {
+                if !previous.is_empty() {
+                    self.stop_process_watch(&previous);
+                }
+                self.processes.entries.clear();
+                self.processes.loading = false;
+                self.refresh_processes();
+            }
+            if ui.button(self.i18n.tr("refresh")).clicked() {
+                self.refresh_processes();
+            }
+            let label = ui.label(self.i18n.tr("processes-filter"));
+            ui.text_edit_singleline(&mut self.processes.filter).labelled_by(label.id);
+            if self.processes.loading {
+                ui.spinner();
+            }
+        });
+
+        if self.processes.connection_name.is_empty() {
+            ui.separator();
+            ui.label(self.i18n.tr("processes-empty"));
+            return;
+        }
+        if self
+            .processes
+            .refreshed_at
+            .is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_INTERVAL)
+        {
+            self.refresh_processes();
+        }
+        ui.ctx().request_repaint_after(REFRESH_INTERVAL);
+
+        let mut stop_watch = false;
+        if let Some(watch) = &self.processes.watch {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong(format!("{} ({})", watch.name, watch.pid));
+                match &watch.process {
+                    Some(process) => {
+                        ui.label(self.i18n.tr_args(
+                            "processes-watch-status",
+                            &[
+                                ("cpu", format!("{:.1}", process.cpu_usage).into()),
+                                ("memory", format_bytes(process.memory).into()),
+                                ("status", process.status.clone().into()),
+                            ],
+                        ));
+                    }
+                    None => {
+                        ui.colored_label(ui.visuals().warn_fg_color, self.i18n.tr("processes-exited"));
+                    }
+                }
+                if ui.small_button(self.i18n.tr("processes-unwatch")).clicked() {
+                    stop_watch = true;
+                }
+            });
+        }
+        ui.separator();
+
+        let filter = self.processes.filter.to_lowercase();
+        let mut entries: Vec<_> = self
+            .processes
+            .entries
+            .iter()
+            .filter(|process| {
+                filter.is_empty()
+                    || process.name.to_lowercase().contains(&filter)
+                    || process.command.to_lowercase().contains(&filter)
+                    || process.pid.to_string() == filter
+            })
+            .collect();
+        match self.processes.sort {
+            ProcessSort::Cpu => entries.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage)),
+            ProcessSort::Memory => entries.sort_by_key(|process| Reverse(process.memory)),
+        }
+
+        let mut operation = None;
+        egui::ScrollArea::vertical().id_salt("processes-list").show(ui, |ui| {
+            egui::Grid::new("processes-grid").striped(true).show(ui, |ui| {
+                ui.strong(self.i18n.tr("processes-pid"));
+                ui.strong(self.i18n.tr("processes-name"));
+                ui.selectable_value(&mut self.processes.sort, ProcessSort::Cpu, self.i18n.tr("processes-cpu"));
+                ui.selectable_value(
+                    &mut self.processes.sort,
+                    ProcessSort::Memory,
+                    self.i18n.tr("processes-memory"),
+                );
+                ui.strong(self.i18n.tr("processes-status"));
+                ui.end_row();
+                for process in entries {
+                    ui.label(process.pid.to_string());
+                    let name = ui.label(&process.name);
+                    if !process.command.is_empty() {
+                        name.on_hover_text(&process.command);
+                    }
+                    ui.label(format!("{:.1}%", process.cpu_usage));
+                    ui.label(format_bytes(process.memory));
+                    ui.label(&process.status);
+                    if ui.small_button(self.i18n.tr("processes-watch")).clicked() {
+                        operation = Some(ProcessOperation::Watch {
+                            pid: process.pid,
+                            name: process.name.clone(),
+                        });
+                    }
+                    if ui
+                        .small_button(self.i18n.tr("processes-end"))
+                        .on_hover_text(self.i18n.tr("processes-end-hint"))
+                        .clicked()
+                    {
+                        operation = Some(ProcessOperation::Kill {
+                            pid: process.pid,
+                            force: false,
+                        });
+                    }
+                    if ui
+                        .small_button(self.i18n.tr("processes-kill"))
+                        .on_hover_text(self.i18n.tr("processes-kill-hint"))
+                        .clicked()
+                    {
+                        operation = Some(ProcessOperation::Kill {
+                            pid: process.pid,
+                            force: true,
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        let connection_name = self.processes.connection_name.clone();
+        if stop_watch {
+            self.stop_process_watch(&connection_name);
+        }
+        match operation {
+            Some(ProcessOperation::Kill { pid, force }) => {
+                let request_id = Uuid::new_v4();
+                self.pending.insert(request_id, PendingAction::KillProcess);
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::KillProcess { pid, force },
+                    },
+                );
+            }
+            Some(ProcessOperation::Watch { pid, name }) => {
+                self.stop_process_watch(&connection_name);
+                let id = Uuid::new_v4();
+                self.processes.watch = Some(ProcessWatch {
+                    id,
+                    pid,
+                    name,
+                    process: None,
+                });
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id: id,
+                        action: RpcAction::WatchProcess {
+                            pid,
+                            interval_ms: WATCH_INTERVAL.as_millis() as u64,
+                        },
+                    },
+                );
+            }
+            None => {}
+        }
+    }
+
+    pub fn refresh_processes(&mut self) {
+        self.processes.refreshed_at = Some(Instant::now());
+        if self.processes.connection_name.is_empty() || self.processes.loading {
+            return;
+        }
+        let connection_name = self.processes.connection_name.clone();
+        if !self
+            .connections
+            .get(&connection_name)
+            .is_some_and(|connection| connection.connected)
+        {
+            return;
+        }
+        self.processes.loading = true;
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, PendingAction::LoadProcesses);
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListProcesses,
+            },
+        );
+    }
+
+    pub fn handle_watch_response(&mut self, response: RpcResponse) {
+        let Some(watch) = &mut self.processes.watch else {
+            return;
+        };
+        match response.result {
+            RpcResult::ProcessUpdate { process, .. } => watch.process = process,
+            RpcResult::Error { message } => {
+                let connection_name = self.processes.connection_name.clone();
+                self.output.connection_error(&connection_name, message);
+                self.processes.watch = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn stop_process_watch(&mut self, connection_name: &str) {
+        let Some(watch) = self.processes.watch.take() else {
+            return;
+        };
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id: Uuid::new_v4(),
+                action: RpcAction::StopWatchProcess { watch_id: watch.id },
+            },
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::EnvVar;
+use rs_peer_workspace_shared::project::TerminalProfile;
+
+use super::state::WorkspaceApp;
+use super::types::TerminalProfileForm;
+
+impl WorkspaceApp {
+    pub fn draw_terminal_profiles(&mut self, ctx: &egui::Context) {
+        if !self.show_terminal_profiles {
+            return;
+        }
+
+        let mut open = self.show_terminal_profiles;
+        egui::Window::new(self.i18n.tr("profiles-title"))
+            .id(egui::Id::new("terminal-profiles"))
+            .open(&mut open)
+            .default_size([420.0, 480.0])
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("profile-connection")
+                    .selected_text(if self.profile_form.connection_name.is_empty() {
+                        self.i18n.tr("select-connection")
+                    } else {
+                        self.profile_form.connection_name.clone()
+                    })
+                    .show_ui(ui, |ui| {
+                        for connection in &self.project.connections {
+                            if ui
+                                .selectable_label(
+                                    self.profile_form.connection_name == connection.name,
+                                    &connection.name,
+                                )
+                                .clicked()
+                            {
+                                self.profile_form = TerminalProfileForm {
+                                    connection_name: connection.name.clone(),
+                                    ..Default::default()
+                                };
+                            }
+                        }
+                    });
+
+                let profiles = self
+                    .project
+                    .connections
+                    .iter()
+                    .find(|connection| connection.name == self.profile_form.connection_name)
+                    .map(|connection| connection.terminal_profiles.clone())
+                    .unwrap_or_default();
+                if self.profile_form.connection_name.is_empty() {
+                    return;
+                }
+
+                ui.separator();
+                let mut remove = None;
+                for profile in &profiles {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(
+                                self.profile_form.editing.as_deref() == Some(profile.name.as_str()),
+                                &profile.name,
+                            )
+                            .clicked()
+                        {
+                            self.edit_terminal_profile(profile);
+                        }
+                        if ui.small_button(self.i18n.tr("remove")).clicked() {
+                            remove = Some(profile.name.clone());
+                        }
+                    });
+                }
+                if profiles.is_empty() {
+                    ui.label(self.i18n.tr("profiles-empty"));
+                }
+                if ui.button(self.i18n.tr("profiles-new")).clicked() {
+                    self.profile_form = TerminalProfileForm {
+                        connection_name: self.profile_form.connection_name.clone(),
+                        ..Default::default()
+                    };
+                }
+                ui.separator();
+
+                let label = ui.label(self.i18n.tr("profiles-name"));
+                ui.text_edit_singleline(&mut self.profile_form.name).labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("profiles-shell"));
+                ui.text_edit_singleline(&mut self.profile_form.shell).labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("profiles-cwd"));
+                ui.text_edit_singleline(&mut self.profile_form.cwd).labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("profiles-env"));
+                ui.add(egui::TextEdit::multiline(&mut self.profile_form.env).desired_rows(3)).labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("profiles-startup"));
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.profile_form.startup_commands)
+                        .desired_rows(3),
+                )
+                .labelled_by(label.id);
+                if ui.button(self.i18n.tr("profiles-save")).clicked() {
+                    self.save_terminal_profile();
+                }
+
+                if let Some(name) = remove {
+                    self.remove_terminal_profile(&name);
+                }
+            });
+        self.show_terminal_profiles = open;
+    }
+
+    fn edit_terminal_profile(&mut self, profile: &TerminalProfile) {
+        self.profile_form = TerminalProfileForm {
+            connection_name: self.profile_form.connection_name.clone(),
+            editing: Some(profile.name.clone()),
+            name: profile.name.clone(),
+            shell: profile.shell.clone().unwrap_or_default(),
+            cwd: profile.cwd.clone().unwrap_or_default(),
+            env: profile
+                .env
+                .iter()
+                .map(|var| format!("{}={}", var.name, var.value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            startup_commands: profile.startup_commands.join("\n"),
+        };
+    }
+
+    pub fn save_terminal_profile(&mut self) {
+        let name = self.profile_form.name.trim().to_string();
+        if name.is_empty() {
+            self.output.warn("Profile name is required.");
+            return;
+        }
+
+        let profile = TerminalProfile {
+            name: name.clone(),
+            shell: non_empty(&self.profile_form.shell),
+            cwd: non_empty(&self.profile_form.cwd),
+            env: parse_env_lines(&self.profile_form.env),
+            startup_commands: self
+                .profile_form
+                .startup_commands
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+
+        let Some(connection) = self
+            .project
+            .connections
+            .iter_mut()
+            .find(|connection| connection.name == self.profile_form.connection_name)
+        else {
+            return;
+        };
+        let replaced = self.profile_form.editing.clone().unwrap_or_else(|| name.clone());
+        connection
+            .terminal_profiles
+            .retain(|item| item.name != replaced && item.name != name);
+        connection.terminal_profiles.push(profile);
+        self.profile_form.editing = Some(name.clone());
+        self.output.connection_info(
+            &self.profile_form.connection_name,
+            format!("saved terminal profile {name}"),
+        );
+    }
+
+    fn remove_terminal_profile(&mut self, name: &str) {
+        if let Some(connection) = self
+            .project
+            .connections
+            .iter_mut()
+            .find(|connection| connection.name == self.profile_form.connection_name)
+        {
+            connection.terminal_profiles.retain(|item| item.name != name);
+        }
+        if self.profile_form.editing.as_deref() == Some(name) {
+            self.profile_form = TerminalProfileForm {
+                connection_name: self.profile_form.connection_name.clone(),
+                ..Default::default()
+            };
+        }
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+pub fn parse_env_lines(text: &str) -> Vec<EnvVar> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| EnvVar {
+                name: name.to_string(),
+                value: value.trim().to_string(),
+                secret: false,
+            })
+        })
+        .collect()
+}
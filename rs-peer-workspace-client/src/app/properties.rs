@@ -0,0 +1,238 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::project::display_name_for_path;
+use rs_peer_workspace_shared::stat::{set_permissions, stat_entry};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::{PendingAction, PropertiesDialog};
+
+impl WorkspaceApp {
+    /// Opens the properties dialog for `path`, which lives on `connection_name` or,
+    /// when that is `None`, on this machine.
+    pub fn show_properties(&mut self, connection_name: Option<&str>, path: &str) {
+        let mut dialog = PropertiesDialog {
+            connection_name: connection_name.map(str::to_string),
+            path: path.to_string(),
+            stat: None,
+            error: None,
+            mode: None,
+            applying: false,
+        };
+        match connection_name {
+            None => match stat_entry(path) {
+                Ok(stat) => dialog.stat = Some(stat),
+                Err(err) => dialog.error = Some(err.to_string()),
+            },
+            Some(connection_name) => {
+                let request_id = Uuid::new_v4();
+                self.pending.insert(
+                    request_id,
+                    PendingAction::StatEntry {
+                        path: path.to_string(),
+                    },
+                );
+                self.send_rpc(
+                    connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::StatEntry {
+                            path: path.to_string(),
+                        },
+                    },
+                );
+            }
+        }
+        self.properties = Some(dialog);
+    }
+
+    fn apply_permissions(&mut self, mode: u32) {
+        let Some(dialog) = &mut self.properties else {
+            return;
+        };
+        let Some(connection_name) = dialog.connection_name.clone() else {
+            match set_permissions(&dialog.path, mode) {
+                Ok(stat) => {
+                    dialog.stat = Some(stat);
+                    dialog.mode = None;
+                    dialog.error = None;
+                }
+                Err(err) => dialog.error = Some(err.to_string()),
+            }
+            return;
+        };
+        dialog.applying = true;
+        let path = dialog.path.clone();
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::SetPermissions { path: path.clone() },
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::SetPermissions { path, mode },
+            },
+        );
+    }
+
+    pub fn draw_properties(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.properties else {
+            return;
+        };
+
+        let mut open = true;
+        let mut close = false;
+        let mut apply = None;
+        egui::Window::new(self.i18n.tr("properties-title"))
+            .id(egui::Id::new("properties"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("properties-grid").num_columns(2).show(ui, |ui| {
+                    let unknown = self.i18n.tr("properties-unknown");
+                    ui.label(self.i18n.tr("properties-name"));
+                    ui.label(display_name_for_path(&dialog.path));
+                    ui.end_row();
+                    ui.label(self.i18n.tr("properties-location"));
+                    ui.label(match &dialog.connection_name {
+                        Some(connection_name) => connection_name.clone(),
+                        None => self.i18n.tr("folder-local"),
+                    });
+                    ui.end_row();
+                    ui.label(self.i18n.tr("properties-path"));
+                    ui.label(&dialog.path);
+                    ui.end_row();
+
+                    let Some(stat) = &dialog.stat else {
+                        return;
+                    };
+                    ui.label(self.i18n.tr("properties-type"));
+                    ui.label(self.i18n.tr(if stat.is_dir {
+                        "properties-type-folder"
+                    } else {
+                        "properties-type-file"
+                    }));
+                    ui.end_row();
+                    if let Some(target) = &stat.symlink_target {
+                        ui.label(self.i18n.tr("properties-link-target"));
+                        ui.label(target);
+                        ui.end_row();
+                    }
+                    if !stat.is_dir {
+                        ui.label(self.i18n.tr("properties-size"));
+                        ui.label(self.i18n.tr_args(
+                            "properties-size-value",
+                            &[
+                                ("size", format_bytes(stat.size).into()),
+                                ("bytes", stat.size.into()),
+                            ],
+                        ));
+                        ui.end_row();
+                    }
+                    ui.label(self.i18n.tr("properties-modified"));
+                    ui.label(stat.modified.map_or(unknown.clone(), |modified| {
+                        format!("{} ({})", self.i18n.datetime(modified), self.i18n.relative(modified))
+                    }));
+                    ui.end_row();
+                    ui.label(self.i18n.tr("properties-permissions"));
+                    ui.label(match stat.mode {
+                        Some(mode) => format!("{} ({mode:o})", stat.permissions),
+                        None => stat.permissions.clone(),
+                    });
+                    ui.end_row();
+                    ui.label(self.i18n.tr("properties-owner"));
+                    ui.label(stat.owner.clone().unwrap_or(unknown));
+                    ui.end_row();
+                    if let Some(disk) = stat.disk {
+                        ui.label(self.i18n.tr("properties-free-space"));
+                        ui.label(self.i18n.tr_args(
+                            "properties-free-space-value",
+                            &[
+                                ("available", format_bytes(disk.available).into()),
+                                ("total", format_bytes(disk.total).into()),
+                            ],
+                        ));
+                        ui.end_row();
+                    }
+                });
+                if let Some(stat) = &dialog.stat {
+                    ui.separator();
+                    let current = stat.mode.unwrap_or(if stat.readonly { 0o444 } else { 0o644 });
+                    let mode = dialog.mode.get_or_insert(current);
+                    if stat.mode.is_some() {
+                        egui::Grid::new("properties-mode").num_columns(4).show(ui, |ui| {
+                            ui.label("");
+                            for id in ["properties-read", "properties-write", "properties-execute"] {
+                                ui.label(self.i18n.tr(id));
+                            }
+                            ui.end_row();
+                            for (id, shift) in [
+                                ("properties-class-owner", 6),
+                                ("properties-class-group", 3),
+                                ("properties-class-others", 0),
+                            ] {
+                                let class = self.i18n.tr(id);
+                                ui.label(&class);
+                                for (bit, name) in [
+                                    (0o4, "properties-read"),
+                                    (0o2, "properties-write"),
+                                    (0o1, "properties-execute"),
+                                ] {
+                                    let mask = bit << shift;
+                                    let mut set = *mode & mask != 0;
+                                    if ui
+                                        .checkbox(&mut set, "")
+                                        .on_hover_text(format!("{class}: {}", self.i18n.tr(name)))
+                                        .changed()
+                                    {
+                                        *mode ^= mask;
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    } else {
+                        let mut readonly = *mode & 0o222 == 0;
+                        if ui
+                            .checkbox(&mut readonly, self.i18n.tr("properties-read-only"))
+                            .changed()
+                        {
+                            *mode = if readonly { *mode & !0o222 } else { *mode | 0o200 };
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        if stat.mode.is_some() {
+                            ui.monospace(format!("{:04o}", *mode));
+                        }
+                        let button = egui::Button::new(self.i18n.tr("properties-apply"));
+                        if ui
+                            .add_enabled(*mode != current && !dialog.applying, button)
+                            .clicked()
+                        {
+                            apply = Some(*mode);
+                        }
+                        if dialog.applying {
+                            ui.spinner();
+                        }
+                    });
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                } else if dialog.stat.is_none() {
+                    ui.spinner();
+                }
+                if ui.button(self.i18n.tr("properties-close")).clicked() {
+                    close = true;
+                }
+            });
+        if let Some(mode) = apply {
+            self.apply_permissions(mode);
+        }
+        if close || !open {
+            self.properties = None;
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::path::Path;
+
+use eframe::egui;
+use eframe::egui::text::{LayoutJob, TextFormat};
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::index::{fuzzy_match, index_tree};
+use rs_peer_workspace_shared::project::{FolderSource, ProjectFolder};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::join_remote_path;
+use super::types::{FileIndex, PendingAction, QuickOpen, QuickOpenMatch};
+
+const MAX_RESULTS: usize = 100;
+
+/// Index key of a folder: its connection, `None` for local folders, and its root.
+pub fn index_key(folder: &ProjectFolder) -> (Option<String>, String) {
+    match &folder.source {
+        FolderSource::Local { path } => (None, path.clone()),
+        FolderSource::Remote {
+            connection_name,
+            path,
+        } => (Some(connection_name.clone()), path.clone()),
+    }
+}
+
+impl WorkspaceApp {
+    /// Shows the quick open palette and indexes every project folder again; the
+    /// previous index stays searchable until the new one arrives.
+    pub fn open_quick_open(&mut self) {
+        self.quick_open = QuickOpen {
+            open: true,
+            focus: true,
+            ..Default::default()
+        };
+        let filter = self.project.explorer.list_filter();
+        for folder in self.project.folders.clone() {
+            let key = index_key(&folder);
+            match &folder.source {
+                FolderSource::Local { path } => {
+                    let index = match index_tree(Path::new(path), &filter) {
+                        Ok((paths, truncated)) => FileIndex {
+                            paths,
+                            truncated,
+                            loading: false,
+                        },
+                        Err(err) => {
+                            self.output.error(format!("Failed to index {path}: {err}"));
+                            continue;
+                        }
+                    };
+                    self.file_indexes.insert(key, index);
+                }
+                FolderSource::Remote {
+                    connection_name,
+                    path,
+                } => {
+                    if !self.connections.contains_key(connection_name) {
+                        continue;
+                    }
+                    self.file_indexes.entry(key).or_default().loading = true;
+                    let request_id = Uuid::new_v4();
+                    self.pending.insert(request_id, PendingAction::IndexTree);
+                    self.send_rpc(
+                        connection_name,
+                        RpcRequest {
+                            request_id,
+                            action: RpcAction::IndexTree {
+                                root: path.clone(),
+                                filter: filter.clone(),
+                            },
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn index_received(&mut self, connection_name: &str, root: String, paths: Vec<String>, truncated: bool) {
+        self.file_indexes.insert(
+            (Some(connection_name.to_string()), root),
+            FileIndex {
+                paths,
+                truncated,
+                loading: false,
+            },
+        );
+        self.quick_open.matched_query = None;
+    }
+
+    pub fn index_failed(&mut self, connection_name: &str) {
+        for ((connection, _), index) in &mut self.file_indexes {
+            if connection.as_deref() == Some(connection_name) {
+                index.loading = false;
+            }
+        }
+    }
+
+    fn refresh_quick_open_matches(&mut self) {
+        if self.quick_open.matched_query.as_deref() == Some(self.quick_open.query.as_str()) {
+            return;
+        }
+        let query = self.quick_open.query.clone();
+        let mut matches = Vec::new();
+        for (folder_index, folder) in self.project.folders.iter().enumerate() {
+            let Some(index) = self.file_indexes.get(&index_key(folder)) else {
+                continue;
+            };
+            matches.extend(index.paths.iter().filter_map(|path| {
+                fuzzy_match(&query, path).map(|found| QuickOpenMatch {
+                    folder_index,
+                    path: path.clone(),
+                    score: found.score,
+                    positions: found.positions,
+                })
+            }));
+        }
+        matches.sort_by(|left, right| {
+            right
+                .score
+                .cmp(&left.score)
+                .then_with(|| left.path.cmp(&right.path))
+        });
+        matches.truncate(MAX_RESULTS);
+        self.quick_open.selected = 0;
+        self.quick_open.matches = matches;
+        self.quick_open.matched_query = Some(query);
+    }
+
+    fn open_quick_open_match(&mut self, found: &QuickOpenMatch) {
+        let Some(folder) = self.project.folders.get(found.folder_index).cloned() else {
+            return;
+        };
+        let path = match &folder.source {
+            FolderSource::Local { path } => Path::new(path).join(&found.path).to_string_lossy().to_string(),
+            FolderSource::Remote { path, .. } => join_remote_path(path, &found.path),
+        };
+        self.quick_open = QuickOpen::default();
+        self.open_path(&folder, &path, false);
+    }
+
+    pub fn draw_quick_open(&mut self, ctx: &egui::Context) {
+        if !self.quick_open.open {
+            return;
+        }
+        self.refresh_quick_open_matches();
+
+        let count = self.quick_open.matches.len();
+        ctx.input_mut(|input| {
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) && count > 0 {
+                self.quick_open.selected = (self.quick_open.selected + 1) % count;
+            }
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) && count > 0 {
+                self.quick_open.selected = (self.quick_open.selected + count - 1) % count;
+            }
+        });
+
+        let mut open = true;
+        let mut chosen = None;
+        let loading = self.file_indexes.values().any(|index| index.loading);
+        let truncated = self.file_indexes.values().any(|index| index.truncated);
+        egui::Window::new(self.i18n.tr("quick-open-title"))
+            .id(egui::Id::new("quick-open"))
+            .open(&mut open)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+            .collapsible(false)
+            .resizable(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.quick_open.query)
+                        .hint_text(self.i18n.tr("quick-open-hint"))
+                        .desired_width(f32::INFINITY),
+                );
+                if self.quick_open.focus {
+                    response.request_focus();
+                    self.quick_open.focus = false;
+                }
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    chosen = Some(self.quick_open.selected);
+                }
+                ui.horizontal(|ui| {
+                    if loading {
+                        ui.spinner();
+                        ui.weak(self.i18n.tr("quick-open-indexing"));
+                    } else if self.project.folders.is_empty() {
+                        ui.weak(self.i18n.tr("quick-open-no-folders"));
+                    } else if truncated {
+                        ui.weak(self.i18n.tr("quick-open-truncated"));
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_salt("quick-open-results")
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        if self.quick_open.matches.is_empty() && !loading {
+                            ui.label(self.i18n.tr("quick-open-no-matches"));
+                        }
+                        for (idx, found) in self.quick_open.matches.iter().enumerate() {
+                            let folder = self
+                                .project
+                                .folders
+                                .get(found.folder_index)
+                                .map_or("", |folder| folder.name.as_str());
+                            let selected = idx == self.quick_open.selected;
+                            let response = ui.selectable_label(
+                                selected,
+                                highlight_matches(ui, folder, &found.path, &found.positions),
+                            );
+                            if selected {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                chosen = Some(idx);
+                            }
+                        }
+                    });
+            });
+
+        if let Some(found) = chosen.and_then(|idx| self.quick_open.matches.get(idx).cloned()) {
+            self.open_quick_open_match(&found);
+        } else if !open {
+            self.quick_open = QuickOpen::default();
+        }
+    }
+}
+
+/// `prefix` in weak text followed by `text` with the bytes at `positions` marked.
+pub fn highlight_matches(ui: &egui::Ui, prefix: &str, text: &str, positions: &[usize]) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let weak = TextFormat::simple(font_id.clone(), ui.visuals().weak_text_color());
+    let normal = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let marked = TextFormat {
+        underline: egui::Stroke::new(1.0, ui.visuals().strong_text_color()),
+        ..TextFormat::simple(font_id, ui.visuals().strong_text_color())
+    };
+    let mut job = LayoutJob::default();
+    let mut leading = 0.0;
+    if !prefix.is_empty() {
+        job.append(prefix, 0.0, weak);
+        leading = 8.0;
+    }
+    let mut start = 0;
+    while start < text.len() {
+        let is_marked = positions.contains(&start);
+        let end = text[start..]
+            .char_indices()
+            .skip(1)
+            .map(|(offset, _)| start + offset)
+            .find(|offset| positions.contains(offset) != is_marked)
+            .unwrap_or(text.len());
+        let format = if is_marked { marked.clone() } else { normal.clone() };
+        job.append(&text[start..end], leading, format);
+        leading = 0.0;
+        start = end;
+    }
+    job
+}
@@ -0,0 +1,167 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::project::is_text_file;
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::remote_parent;
+use super::types::{PendingAction, TreeEntry};
+
+/// Siblings prefetched per opened file.
+const MAX_FILES: usize = 16;
+const MAX_FILE_BYTES: u64 = 64 * 1024;
+/// Bytes prefetched per opened file, across all siblings.
+const BUDGET_BYTES: u64 = 512 * 1024;
+/// How long a prefetched file is used instead of reading it again.
+const FRESH_FOR: Duration = Duration::from_secs(60);
+
+struct CachedFile {
+    content: String,
+    hash: Option<String>,
+    modified: Option<u64>,
+    fetched: Instant,
+}
+
+/// Remote files read before they were opened, keyed by connection and path.
+#[derive(Default)]
+pub struct ReadAheadCache {
+    files: HashMap<(String, String), CachedFile>,
+    in_flight: HashSet<(String, String)>,
+}
+
+impl ReadAheadCache {
+    /// Takes the prefetched content and hash of `path`. It is only used while fresh
+    /// and when `modified`, from the latest listing, matches the time it was read at.
+    pub fn take(
+        &mut self,
+        connection_name: &str,
+        path: &str,
+        modified: Option<u64>,
+    ) -> Option<(String, Option<String>)> {
+        let file = self
+            .files
+            .remove(&(connection_name.to_string(), path.to_string()))?;
+        (file.fetched.elapsed() < FRESH_FOR && modified.is_some() && file.modified == modified)
+            .then_some((file.content, file.hash))
+    }
+
+    fn wants(&self, connection_name: &str, path: &str) -> bool {
+        let key = (connection_name.to_string(), path.to_string());
+        !self.in_flight.contains(&key)
+            && self
+                .files
+                .get(&key)
+                .is_none_or(|file| file.fetched.elapsed() >= FRESH_FOR)
+    }
+
+    pub fn forget_connection(&mut self, connection_name: &str) {
+        self.files.retain(|(connection, _), _| connection != connection_name);
+        self.in_flight.retain(|(connection, _)| connection != connection_name);
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.in_flight.clear();
+    }
+}
+
+impl WorkspaceApp {
+    /// Lists the folder of a remote file that is being opened, then prefetches its
+    /// small text siblings so opening them next needs no round trip.
+    pub fn start_read_ahead(&mut self, connection_name: &str, opened: &str) {
+        if !self.project.explorer.read_ahead {
+            return;
+        }
+        let path = remote_parent(opened);
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::ReadAheadDirectory {
+                path: path.clone(),
+                opened: opened.to_string(),
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ListDirectory {
+                    path,
+                    filter: self.project.explorer.list_filter(),
+                },
+            },
+        );
+    }
+
+    pub fn read_ahead_siblings(&mut self, connection_name: &str, opened: &str, entries: &[TreeEntry]) {
+        let mut budget = BUDGET_BYTES;
+        let mut files = Vec::new();
+        for entry in entries {
+            if files.len() == MAX_FILES {
+                break;
+            }
+            let Some(size) = entry.size else {
+                continue;
+            };
+            if entry.is_dir
+                || entry.path == opened
+                || size > MAX_FILE_BYTES.min(budget)
+                || !is_text_file(&entry.path, &self.project.explorer.text_extensions)
+                || self.open_files.iter().any(|tab| tab.path == entry.path)
+                || !self.read_ahead.wants(connection_name, &entry.path)
+            {
+                continue;
+            }
+            budget -= size;
+            files.push((entry.path.clone(), entry.modified));
+        }
+
+        for (path, modified) in files {
+            self.read_ahead
+                .in_flight
+                .insert((connection_name.to_string(), path.clone()));
+            let request_id = Uuid::new_v4();
+            self.pending.insert(
+                request_id,
+                PendingAction::ReadAhead {
+                    path: path.clone(),
+                    modified,
+                },
+            );
+            self.send_rpc(
+                connection_name,
+                RpcRequest {
+                    request_id,
+                    action: RpcAction::ReadFile {
+                        path,
+                        known_hash: None,
+                    },
+                },
+            );
+        }
+    }
+
+    pub fn finish_read_ahead(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        modified: Option<u64>,
+        result: RpcResult,
+    ) {
+        let key = (connection_name.to_string(), path);
+        self.read_ahead.in_flight.remove(&key);
+        if let RpcResult::FileContent { content, hash, .. } = result {
+            self.read_ahead.files.insert(
+                key,
+                CachedFile {
+                    content,
+                    hash,
+                    modified,
+                    fetched: Instant::now(),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{OutputAttachment, RpcAction, RpcRequest, RpcResult};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::PendingAction;
+
+const MAX_IMAGE_WIDTH: f32 = 480.0;
+const ATTACHMENT_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+impl WorkspaceApp {
+    /// Starts fetching the images in `attachments` that the server left in files.
+    pub fn load_attachments(&mut self, connection_name: &str, attachments: &[OutputAttachment]) {
+        for attachment in attachments {
+            if let OutputAttachment::ImageFile { mime, path, .. } = attachment {
+                self.load_attachment(connection_name, path, mime, Vec::new());
+            }
+        }
+    }
+
+    /// Reads the next chunk of an image attachment, starting after the bytes in `data`.
+    fn load_attachment(&mut self, connection_name: &str, path: &str, mime: &str, data: Vec<u8>) {
+        let request_id = Uuid::new_v4();
+        let offset = data.len() as u64;
+        self.pending.insert(
+            request_id,
+            PendingAction::LoadAttachment {
+                path: path.to_string(),
+                mime: mime.to_string(),
+                data,
+            },
+        );
+        self.send_rpc(
+            connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ReadFileChunk {
+                    path: path.to_string(),
+                    offset,
+                    length: ATTACHMENT_CHUNK_BYTES,
+                },
+            },
+        );
+    }
+
+    pub fn handle_attachment_chunk(
+        &mut self,
+        connection_name: &str,
+        path: String,
+        mime: String,
+        mut data: Vec<u8>,
+        result: RpcResult,
+    ) {
+        let loaded = match result {
+            RpcResult::FileChunk {
+                data: chunk,
+                total_size,
+                ..
+            } => {
+                let done = chunk.is_empty() || data.len() + chunk.len() >= total_size as usize;
+                data.extend_from_slice(&chunk);
+                if !done {
+                    self.load_attachment(connection_name, &path, &mime, data);
+                    return;
+                }
+                Some(OutputAttachment::Image { mime, data })
+            }
+            RpcResult::Error { message } => {
+                self.output.connection_error(
+                    connection_name,
+                    format!("failed to load image {path}: {message}"),
+                );
+                None
+            }
+            _ => {
+                self.output.connection_error(
+                    connection_name,
+                    format!("unexpected response loading image {path}"),
+                );
+                None
+            }
+        };
+        let blocks = self
+            .terminals
+            .iter_mut()
+            .flat_map(|terminal| terminal.blocks.iter_mut())
+            .map(|block| &mut block.attachments);
+        let cells = self.notebook.cells.iter_mut().map(|cell| &mut cell.attachments);
+        for attachments in blocks.chain(cells) {
+            attachments.retain_mut(|attachment| {
+                if !matches!(attachment, OutputAttachment::ImageFile { path: pending, .. } if *pending == path) {
+                    return true;
+                }
+                match &loaded {
+                    Some(loaded) => {
+                        *attachment = loaded.clone();
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+    }
+}
+
+pub fn render_attachment(ui: &mut egui::Ui, id: &str, attachment: &OutputAttachment) {
+    match attachment {
+        OutputAttachment::Image { mime, data } => {
+            let extension = match mime.as_str() {
+                "image/png" => "png",
+                "image/jpeg" => "jpg",
+                "image/gif" => "gif",
+                _ => {
+                    ui.weak(format!("[{mime} images are not supported]"));
+                    return;
+                }
+            };
+            let uri = format!("bytes://{id}.{extension}");
+            if ui.ctx().try_load_bytes(&uri).is_err() {
+                ui.ctx().include_bytes(uri.clone(), data.clone());
+            }
+            ui.add(egui::Image::new(uri).max_width(MAX_IMAGE_WIDTH).shrink_to_fit());
+        }
+        OutputAttachment::ImageFile { size, .. } => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(format!("loading image ({})", format_bytes(*size)));
+            });
+        }
+        OutputAttachment::Table { columns, rows } => {
+            egui::ScrollArea::horizontal().id_salt(id).show(ui, |ui| {
+                egui::Grid::new(id).striped(true).show(ui, |ui| {
+                    for column in columns {
+                        ui.strong(column);
+                    }
+                    ui.end_row();
+                    for row in rows {
+                        for cell in row {
+                            ui.label(cell);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        OutputAttachment::Json { value } => {
+            egui::CollapsingHeader::new("JSON")
+                .id_salt(id)
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.add(egui::Label::new(egui::RichText::new(value).monospace()).wrap());
+                });
+        }
+    }
+}
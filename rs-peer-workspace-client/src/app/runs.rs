@@ -0,0 +1,303 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::project::RunConfig;
+use uuid::Uuid;
+
+use super::env::{clean_env, draw_env_editor};
+use super::state::WorkspaceApp;
+use super::types::{ActiveRun, BottomTab, PendingAction, RunConfigForm, TaskKind};
+
+impl WorkspaceApp {
+    pub fn run_active(&self, name: &str) -> bool {
+        self.runs.iter().any(|run| run.name == name)
+    }
+
+    /// Runs the configuration `name` on its connection as a cancellable task.
+    pub fn start_run(&mut self, name: &str) {
+        let Some(config) = self
+            .project
+            .run_configs
+            .iter()
+            .find(|config| config.name == name)
+            .cloned()
+        else {
+            return;
+        };
+        if !self
+            .connections
+            .get(&config.connection_name)
+            .is_some_and(|connection| connection.connected)
+        {
+            self.output.error(format!(
+                "Cannot run {name}: {} is not connected",
+                config.connection_name
+            ));
+            self.active_bottom_tab = BottomTab::Output;
+            return;
+        }
+        let id = Uuid::new_v4();
+        self.runs.push(ActiveRun {
+            id,
+            name: config.name.clone(),
+            connection_name: config.connection_name.clone(),
+        });
+        self.task_manager.start(id, TaskKind::Run, format!("run {name}"));
+        self.output
+            .connection_info(&config.connection_name, format!("Running {name}: {}", config.command));
+        let env = self.command_env(&config.connection_name, &config.env);
+        self.pending.insert(id, PendingAction::Run);
+        self.send_rpc(
+            &config.connection_name,
+            RpcRequest {
+                request_id: id,
+                action: RpcAction::RunCommand {
+                    command: config.command,
+                    shell: None,
+                    cwd: config.cwd,
+                    env,
+                },
+            },
+        );
+    }
+
+    /// Kills the command of a run; its task is cancelled by the caller.
+    pub fn cancel_run(&mut self, id: Uuid) {
+        let Some(run) = self.runs.iter().find(|run| run.id == id) else {
+            return;
+        };
+        let connection_name = run.connection_name.clone();
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id: Uuid::new_v4(),
+                action: RpcAction::CancelCommand { command_id: id },
+            },
+        );
+    }
+
+    /// Records how a run ended, `Ok` holding its output and exit code, in the Output
+    /// log and its task.
+    pub fn finish_run(&mut self, id: Uuid, result: Result<(String, Option<i32>), String>) {
+        let Some(index) = self.runs.iter().position(|run| run.id == id) else {
+            return;
+        };
+        let run = self.runs.remove(index);
+        match result {
+            Ok((output, exit_code)) => {
+                let output = output.trim_end();
+                if !output.is_empty() {
+                    self.output.connection_info(&run.connection_name, output);
+                }
+                match exit_code {
+                    Some(0) => {
+                        self.output
+                            .connection_info(&run.connection_name, format!("{} finished", run.name));
+                        self.task_manager.complete(id, None);
+                    }
+                    code => {
+                        let reason = code.map_or("no exit code".to_string(), |code| format!("exit code {code}"));
+                        self.output
+                            .connection_warn(&run.connection_name, format!("{} failed: {reason}", run.name));
+                        self.task_manager.fail(id, reason);
+                    }
+                }
+            }
+            Err(message) => {
+                self.output
+                    .connection_warn(&run.connection_name, format!("{} stopped: {message}", run.name));
+                self.task_manager.fail(id, message);
+            }
+        }
+    }
+
+    /// Ends the runs on a connection that closed.
+    pub fn fail_runs(&mut self, connection_name: &str, reason: &str) {
+        let ids: Vec<_> = self
+            .runs
+            .iter()
+            .filter(|run| run.connection_name == connection_name)
+            .map(|run| run.id)
+            .collect();
+        for id in ids {
+            self.pending.remove(&id);
+            self.finish_run(id, Err(reason.to_string()));
+        }
+    }
+
+    /// Run configuration picker with Run and Stop buttons for the menu bar.
+    pub fn draw_run_toolbar(&mut self, ui: &mut egui::Ui) {
+        if self
+            .selected_run_config
+            .as_ref()
+            .is_some_and(|name| !self.project.run_configs.iter().any(|config| &config.name == name))
+        {
+            self.selected_run_config = None;
+        }
+        if self.selected_run_config.is_none() {
+            self.selected_run_config = self.project.run_configs.first().map(|config| config.name.clone());
+        }
+
+        let mut edit = false;
+        egui::ComboBox::from_id_salt("run-config")
+            .selected_text(
+                self.selected_run_config
+                    .clone()
+                    .unwrap_or_else(|| self.i18n.tr("runs-none")),
+            )
+            .show_ui(ui, |ui| {
+                for config in &self.project.run_configs {
+                    ui.selectable_value(
+                        &mut self.selected_run_config,
+                        Some(config.name.clone()),
+                        &config.name,
+                    )
+                    .on_hover_text(&config.command);
+                }
+                if !self.project.run_configs.is_empty() {
+                    ui.separator();
+                }
+                if ui.button(self.i18n.tr("runs-edit")).clicked() {
+                    edit = true;
+                }
+            });
+        if edit {
+            self.show_run_configs = true;
+        }
+        let Some(name) = self.selected_run_config.clone() else {
+            return;
+        };
+        let active = self.runs.iter().find(|run| run.name == name).map(|run| run.id);
+        if ui
+            .add_enabled(active.is_none(), egui::Button::new("▶"))
+            .on_hover_text(self.i18n.tr("runs-start"))
+            .clicked()
+        {
+            self.start_run(&name);
+        }
+        if ui
+            .add_enabled(active.is_some(), egui::Button::new("■"))
+            .on_hover_text(self.i18n.tr("runs-stop"))
+            .clicked()
+            && let Some(id) = active
+        {
+            self.cancel_task(id);
+        }
+        if active.is_some() {
+            ui.spinner();
+        }
+    }
+
+    pub fn draw_run_configs(&mut self, ctx: &egui::Context) {
+        if !self.show_run_configs {
+            return;
+        }
+
+        let mut open = self.show_run_configs;
+        let mut edit = None;
+        let mut remove = None;
+        egui::Window::new(self.i18n.tr("runs-title"))
+            .id(egui::Id::new("run-configs"))
+            .open(&mut open)
+            .default_size([420.0, 480.0])
+            .show(ctx, |ui| {
+                for config in &self.project.run_configs {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(
+                                self.run_config_form.editing.as_deref() == Some(config.name.as_str()),
+                                &config.name,
+                            )
+                            .clicked()
+                        {
+                            edit = Some(config.clone());
+                        }
+                        if ui.small_button(self.i18n.tr("remove")).clicked() {
+                            remove = Some(config.name.clone());
+                        }
+                    });
+                }
+                if self.project.run_configs.is_empty() {
+                    ui.label(self.i18n.tr("runs-none"));
+                }
+                if ui.button(self.i18n.tr("runs-new")).clicked() {
+                    self.run_config_form = RunConfigForm::default();
+                }
+                ui.separator();
+
+                let label = ui.label(self.i18n.tr("runs-name"));
+                ui.text_edit_singleline(&mut self.run_config_form.name).labelled_by(label.id);
+                egui::ComboBox::from_id_salt("run-config-connection")
+                    .selected_text(if self.run_config_form.connection_name.is_empty() {
+                        self.i18n.tr("select-connection")
+                    } else {
+                        self.run_config_form.connection_name.clone()
+                    })
+                    .show_ui(ui, |ui| {
+                        for connection in &self.project.connections {
+                            ui.selectable_value(
+                                &mut self.run_config_form.connection_name,
+                                connection.name.clone(),
+                                &connection.name,
+                            );
+                        }
+                    });
+                let label = ui.label(self.i18n.tr("runs-command"));
+                ui.add(egui::TextEdit::multiline(&mut self.run_config_form.command).desired_rows(3))
+                    .labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("runs-cwd"));
+                ui.text_edit_singleline(&mut self.run_config_form.cwd).labelled_by(label.id);
+                ui.label(self.i18n.tr("runs-env"));
+                draw_env_editor(ui, &self.i18n, "run-config-env", &mut self.run_config_form.env);
+                if ui.button(self.i18n.tr("runs-save")).clicked() {
+                    self.save_run_config();
+                }
+            });
+        self.show_run_configs = open;
+
+        if let Some(config) = edit {
+            self.run_config_form = RunConfigForm {
+                editing: Some(config.name.clone()),
+                name: config.name,
+                connection_name: config.connection_name,
+                command: config.command,
+                cwd: config.cwd.unwrap_or_default(),
+                env: config.env,
+            };
+        }
+        if let Some(name) = remove {
+            self.project.run_configs.retain(|config| config.name != name);
+            if self.run_config_form.editing.as_deref() == Some(name.as_str()) {
+                self.run_config_form = RunConfigForm::default();
+            }
+        }
+    }
+
+    fn save_run_config(&mut self) {
+        let name = self.run_config_form.name.trim().to_string();
+        if name.is_empty()
+            || self.run_config_form.connection_name.is_empty()
+            || self.run_config_form.command.trim().is_empty()
+        {
+            self.output.warn("Run configuration name, connection and command are required.");
+            return;
+        }
+        let cwd = self.run_config_form.cwd.trim();
+        let config = RunConfig {
+            name: name.clone(),
+            connection_name: self.run_config_form.connection_name.clone(),
+            command: self.run_config_form.command.clone(),
+            cwd: (!cwd.is_empty()).then(|| cwd.to_string()),
+            env: clean_env(&self.run_config_form.env),
+        };
+        let replaced = self.run_config_form.editing.clone().unwrap_or_else(|| name.clone());
+        self.project
+            .run_configs
+            .retain(|item| item.name != replaced && item.name != name);
+        self.project.run_configs.push(config);
+        if self.selected_run_config.as_deref() == Some(replaced.as_str()) {
+            self.selected_run_config = Some(name.clone());
+        }
+        self.run_config_form.editing = Some(name.clone());
+        self.output.info(format!("Saved run configuration {name}"));
+    }
+}
@@ -0,0 +1,375 @@
+use eframe::egui;
+use eframe::egui::text::{LayoutJob, TextFormat};
+use rs_peer_workspace_shared::app::{
+    ReplaceFile, ReplacedFile, RpcAction, RpcRequest, SearchFileMatches, SearchMatch,
+};
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, PendingAction, TaskKind};
+
+impl WorkspaceApp {
+    pub fn open_search(&mut self, connection_name: &str, root: &str) {
+        if self.search.connection_name != connection_name || self.search.root != root {
+            self.search.results.clear();
+            self.search.selected.clear();
+            self.search.searched = None;
+        }
+        self.search.connection_name = connection_name.to_string();
+        self.search.root = root.to_string();
+        self.active_bottom_tab = BottomTab::Search;
+    }
+
+    fn run_search(&mut self) {
+        let search = &mut self.search;
+        if search.connection_name.is_empty()
+            || search.root.trim().is_empty()
+            || search.query.pattern.is_empty()
+        {
+            return;
+        }
+        let replacement = search.replace_mode.then(|| search.replacement.clone());
+        for previous in self.task_manager.running(TaskKind::Search) {
+            self.cancel_task(previous);
+        }
+        let search = &mut self.search;
+        search.searching = true;
+        search.summary.clear();
+        search.searched = Some((search.query.clone(), replacement.clone()));
+        let request = RpcRequest {
+            request_id: Uuid::new_v4(),
+            action: RpcAction::SearchFiles {
+                root: search.root.trim().to_string(),
+                query: search.query.clone(),
+                replacement,
+            },
+        };
+        let connection_name = search.connection_name.clone();
+        self.task_manager.start(
+            request.request_id,
+            TaskKind::Search,
+            format!(
+                "[{connection_name}] search for {} in {}",
+                search.query.pattern,
+                search.root.trim()
+            ),
+        );
+        self.pending
+            .insert(request.request_id, PendingAction::SearchFiles);
+        self.send_rpc(&connection_name, request);
+    }
+
+    pub fn search_finished(&mut self, request_id: Uuid, files: Vec<SearchFileMatches>, truncated: bool) {
+        let matches: usize = files.iter().map(|file| file.matches.len()).sum();
+        let mut detail = format!("{matches} matches in {} files", files.len());
+        if truncated {
+            detail.push_str(", truncated");
+        }
+        self.task_manager.complete(request_id, Some(detail));
+        let search = &mut self.search;
+        search.searching = false;
+        search.truncated = truncated;
+        search.selected = files
+            .iter()
+            .flat_map(|file| {
+                file.matches
+                    .iter()
+                    .map(|found| (file.path.clone(), found.line, found.start))
+            })
+            .collect();
+        search.results = files;
+    }
+
+    fn replace_selected(&mut self) {
+        let Some((query, Some(replacement))) = self.search.searched.clone() else {
+            return;
+        };
+        let files: Vec<ReplaceFile> = self
+            .search
+            .results
+            .iter()
+            .filter_map(|file| {
+                let matches: Vec<(usize, usize)> = file
+                    .matches
+                    .iter()
+                    .filter(|found| {
+                        self.search
+                            .selected
+                            .contains(&(file.path.clone(), found.line, found.start))
+                    })
+                    .map(|found| (found.line, found.start))
+                    .collect();
+                (!matches.is_empty()).then(|| ReplaceFile {
+                    path: file.path.clone(),
+                    hash: file.hash.clone(),
+                    matches,
+                })
+            })
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+        self.search.replacing = true;
+        let request_id = Uuid::new_v4();
+        self.pending
+            .insert(request_id, PendingAction::ReplaceInFiles);
+        let connection_name = self.search.connection_name.clone();
+        self.task_manager.start(
+            request_id,
+            TaskKind::Replace,
+            format!("[{connection_name}] replace in {} files", files.len()),
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: RpcAction::ReplaceInFiles {
+                    query,
+                    replacement,
+                    files,
+                },
+            },
+        );
+    }
+
+    /// Shows the summary and reloads editors of the modified files that have no
+    /// unsaved edits; tabs with edits keep them and get a conflict on save.
+    pub fn replace_finished(
+        &mut self,
+        request_id: Uuid,
+        connection_name: &str,
+        files: Vec<ReplacedFile>,
+    ) {
+        let count: usize = files.iter().map(|file| file.replacements).sum();
+        self.task_manager.complete(
+            request_id,
+            Some(format!("replaced {count} matches in {} files", files.len())),
+        );
+        for file in &files {
+            let open = self.open_files.iter().any(|tab| {
+                tab.path == file.path
+                    && !tab.dirty
+                    && matches!(&tab.source, EditorSource::Remote { connection_name: name } if name == connection_name)
+            });
+            if !open {
+                continue;
+            }
+            let request_id = Uuid::new_v4();
+            self.pending.insert(
+                request_id,
+                PendingAction::ReloadRemoteFile {
+                    path: file.path.clone(),
+                },
+            );
+            self.send_rpc(
+                connection_name,
+                RpcRequest {
+                    request_id,
+                    action: RpcAction::ReadFile {
+                        path: file.path.clone(),
+                        known_hash: None,
+                    },
+                },
+            );
+        }
+        let search = &mut self.search;
+        search.replacing = false;
+        search.results.clear();
+        search.selected.clear();
+        search.searched = None;
+        search.summary = files;
+    }
+
+    fn open_search_result(&mut self, path: &str) {
+        if let Some(existing) = self.open_files.iter().position(|tab| tab.path == path) {
+            self.show_editor(existing);
+            return;
+        }
+        let connection_name = self.search.connection_name.clone();
+        let request_id = Uuid::new_v4();
+        self.pending.insert(
+            request_id,
+            PendingAction::OpenRemoteFile {
+                path: path.to_string(),
+                title: display_name_for_path(path),
+                connection_name: connection_name.clone(),
+                restore_cursor: None,
+                preview: false,
+            },
+        );
+        self.send_rpc(
+            &connection_name,
+            RpcRequest {
+                request_id,
+                action: self.cached_read(&connection_name, path),
+            },
+        );
+    }
+
+    pub fn draw_search(&mut self, ui: &mut egui::Ui) {
+        let mut run = false;
+        let mut replace = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("search-connection")
+                .selected_text(if self.search.connection_name.is_empty() {
+                    self.i18n.tr("select-connection")
+                } else {
+                    self.search.connection_name.clone()
+                })
+                .show_ui(ui, |ui| {
+                    for connection in &self.project.connections {
+                        ui.selectable_value(
+                            &mut self.search.connection_name,
+                            connection.name.clone(),
+                            &connection.name,
+                        );
+                    }
+                });
+            let label = ui.label(self.i18n.tr("search-folder"));
+            ui.add(egui::TextEdit::singleline(&mut self.search.root).desired_width(200.0))
+                .labelled_by(label.id);
+            let label = ui.label(self.i18n.tr("search-pattern"));
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut self.search.query.pattern).desired_width(200.0))
+                .labelled_by(label.id);
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                run = true;
+            }
+            ui.checkbox(&mut self.search.query.regex, self.i18n.tr("search-regex"));
+            ui.checkbox(&mut self.search.query.case_sensitive, self.i18n.tr("search-match-case"));
+            ui.checkbox(&mut self.search.replace_mode, self.i18n.tr("search-replace-mode"));
+            if ui
+                .add_enabled(!self.search.searching, egui::Button::new(self.i18n.tr("search-run")))
+                .clicked()
+            {
+                run = true;
+            }
+            if self.search.searching || self.search.replacing {
+                ui.spinner();
+            }
+        });
+
+        if self.search.replace_mode {
+            ui.horizontal(|ui| {
+                let label = ui.label(self.i18n.tr("search-replacement"));
+                ui.add(egui::TextEdit::singleline(&mut self.search.replacement).desired_width(200.0))
+                    .labelled_by(label.id);
+                let current = (self.search.query.clone(), Some(self.search.replacement.clone()));
+                let previewed = self.search.searched.as_ref() == Some(&current);
+                let count = self.search.selected.len();
+                let button = egui::Button::new(
+                    self.i18n
+                        .tr_args("search-replace-selected", &[("count", count.into())]),
+                );
+                if ui
+                    .add_enabled(previewed && count > 0 && !self.search.replacing, button)
+                    .clicked()
+                {
+                    replace = true;
+                }
+                if !previewed && !self.search.results.is_empty() {
+                    ui.label(self.i18n.tr("search-preview-stale"));
+                }
+            });
+        }
+        ui.separator();
+
+        if !self.search.summary.is_empty() {
+            let matches: usize = self.search.summary.iter().map(|file| file.replacements).sum();
+            ui.label(self.i18n.tr_args(
+                "search-summary",
+                &[
+                    ("matches", matches.into()),
+                    ("files", self.search.summary.len().into()),
+                ],
+            ));
+            for file in &self.search.summary {
+                ui.label(format!("{} ({})", file.path, file.replacements));
+            }
+            ui.separator();
+        }
+
+        let mut open = None;
+        egui::ScrollArea::vertical()
+            .id_salt("search-results")
+            .show(ui, |ui| {
+                if self.search.searched.is_none() && self.search.summary.is_empty() {
+                    ui.label(self.i18n.tr("search-empty"));
+                    return;
+                }
+                if self.search.searched.is_some()
+                    && !self.search.searching
+                    && self.search.results.is_empty()
+                {
+                    ui.label(self.i18n.tr("search-no-results"));
+                }
+                if self.search.truncated {
+                    let count: usize = self.search.results.iter().map(|file| file.matches.len()).sum();
+                    ui.label(self.i18n.tr_args("search-truncated", &[("count", count.into())]));
+                }
+                for file in &self.search.results {
+                    egui::CollapsingHeader::new(format!("{} ({})", file.path, file.matches.len()))
+                        .id_salt(("search-file", &file.path))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for found in &file.matches {
+                                ui.horizontal(|ui| {
+                                    if self.search.replace_mode {
+                                        let key = (file.path.clone(), found.line, found.start);
+                                        let mut checked = self.search.selected.contains(&key);
+                                        if ui.checkbox(&mut checked, "").changed() {
+                                            if checked {
+                                                self.search.selected.insert(key);
+                                            } else {
+                                                self.search.selected.remove(&key);
+                                            }
+                                        }
+                                    }
+                                    if ui.link(format!("{}:", found.line)).clicked() {
+                                        open = Some(file.path.clone());
+                                    }
+                                    ui.label(highlighted(ui, found));
+                                    if self.search.replace_mode
+                                        && let Some(preview) = &found.preview
+                                    {
+                                        ui.label("→");
+                                        ui.label(egui::RichText::new(preview).monospace());
+                                    }
+                                });
+                            }
+                        });
+                }
+            });
+
+        if let Some(path) = open {
+            self.open_search_result(&path);
+        }
+        if run {
+            self.run_search();
+        }
+        if replace {
+            self.replace_selected();
+        }
+    }
+}
+
+pub fn highlighted(ui: &egui::Ui, found: &SearchMatch) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let normal = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let marked = TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        ..TextFormat::simple(font_id, ui.visuals().strong_text_color())
+    };
+    let text = &found.text;
+    let mut job = LayoutJob::default();
+    match (text.get(..found.start), text.get(found.start..found.end), text.get(found.end..)) {
+        (Some(before), Some(matched), Some(after)) => {
+            job.append(before, 0.0, normal.clone());
+            job.append(matched, 0.0, marked);
+            job.append(after, 0.0, normal);
+        }
+        _ => job.append(text, 0.0, normal),
+    }
+    job
+}
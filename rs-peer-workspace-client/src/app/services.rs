@@ -0,0 +1,130 @@
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::PendingAction;
+
+const SERVICE_LOG_LINES: usize = 200;
+
+impl WorkspaceApp {
+    pub fn draw_services(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let previous = self.services.connection_name.clone();
+            egui::ComboBox::from_id_salt("services-connection")
+                .selected_text(if self.services.connection_name.is_empty() {
+                    self.i18n.tr("select-connection")
+                } else {
+                    self.services.connection_name.clone()
+                })
+                .show_ui(ui, |ui| {
+                    for connection in &self.project.connections {
+                        ui.selectable_value(
+                            &mut self.services.connection_name,
+                            connection.name.clone(),
+                            &connection.name,
+                        );
+                    }
+                });
+            if previous != self.services.connection_name {
+                self.services.entries.clear();
+                self.services.logs_for = None;
+                self.services.logs.clear();
+                self.refresh_services();
+            }
+            if ui.button(self.i18n.tr("refresh")).clicked() {
+                self.refresh_services();
+            }
+            let label = ui.label(self.i18n.tr("services-filter"));
+            ui.text_edit_singleline(&mut self.services.filter).labelled_by(label.id);
+            if self.services.loading {
+                ui.spinner();
+            }
+        });
+        ui.separator();
+
+        if self.services.connection_name.is_empty() {
+            ui.label(self.i18n.tr("services-empty"));
+            return;
+        }
+
+        let filter = self.services.filter.to_lowercase();
+        let mut operation = None;
+        ui.columns(2, |columns| {
+            egui::ScrollArea::vertical()
+                .id_salt("services-list")
+                .show(&mut columns[0], |ui| {
+                    egui::Grid::new("services-grid").striped(true).show(ui, |ui| {
+                        for service in self.services.entries.iter().filter(|service| {
+                            filter.is_empty()
+                                || service.name.to_lowercase().contains(&filter)
+                                || service.description.to_lowercase().contains(&filter)
+                        }) {
+                            ui.label(&service.name).on_hover_text(&service.description);
+                            ui.label(&service.status);
+                            if ui.small_button(self.i18n.tr("service-start")).clicked() {
+                                operation = Some(RpcAction::StartService {
+                                    name: service.name.clone(),
+                                });
+                            }
+                            if ui.small_button(self.i18n.tr("service-stop")).clicked() {
+                                operation = Some(RpcAction::StopService {
+                                    name: service.name.clone(),
+                                });
+                            }
+                            if ui.small_button(self.i18n.tr("service-restart")).clicked() {
+                                operation = Some(RpcAction::RestartService {
+                                    name: service.name.clone(),
+                                });
+                            }
+                            if ui.small_button(self.i18n.tr("logs")).clicked() {
+                                operation = Some(RpcAction::ServiceLogs {
+                                    name: service.name.clone(),
+                                    lines: SERVICE_LOG_LINES,
+                                });
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            let ui = &mut columns[1];
+            match &self.services.logs_for {
+                Some(name) => ui.strong(self.i18n.tr_args("logs-for", &[("name", name.clone().into())])),
+                None => ui.strong(self.i18n.tr("logs")),
+            };
+            egui::ScrollArea::vertical()
+                .id_salt("services-logs")
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Label::new(egui::RichText::new(&self.services.logs).monospace())
+                            .wrap(),
+                    );
+                });
+        });
+
+        if let Some(action) = operation {
+            let pending = match action {
+                RpcAction::ServiceLogs { .. } => PendingAction::LoadServiceLogs,
+                _ => PendingAction::ControlService,
+            };
+            self.send_service_rpc(pending, action);
+        }
+    }
+
+    pub fn refresh_services(&mut self) {
+        if self.services.connection_name.is_empty() {
+            return;
+        }
+        self.services.loading = true;
+        self.send_service_rpc(PendingAction::LoadServices, RpcAction::ListServices);
+    }
+
+    fn send_service_rpc(&mut self, pending: PendingAction, action: RpcAction) {
+        let request_id = Uuid::new_v4();
+        self.pending.insert(request_id, pending);
+        let connection_name = self.services.connection_name.clone();
+        self.send_rpc(&connection_name, RpcRequest { request_id, action });
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+
+use rs_peer_workspace_shared::app::RpcRequest;
+use rs_peer_workspace_shared::history::CommandHistory;
+use rs_peer_workspace_shared::project::{
+    display_name_for_path, EditorSource, EditorTab, SavedEditor, SavedTerminal, SessionState,
+    TerminalTab,
+};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::PendingAction;
+
+impl WorkspaceApp {
+    pub fn capture_session(&self) -> SessionState {
+        SessionState {
+            editors: self
+                .open_files
+                .iter()
+                .filter(|tab| tab.media.is_none())
+                .map(|tab| SavedEditor {
+                    path: tab.path.clone(),
+                    source: tab.source.clone(),
+                    cursor: tab.cursor,
+                })
+                .collect(),
+            selected_editor: self
+                .selected_editor
+                .and_then(|idx| self.open_files.get(idx))
+                .map(|tab| tab.path.clone()),
+            terminals: self
+                .terminals
+                .iter()
+                .map(|terminal| SavedTerminal {
+                    connection_name: terminal.connection_name.clone(),
+                    title: terminal.title.clone(),
+                    profile: terminal
+                        .profile
+                        .as_ref()
+                        .map(|profile| profile.name.clone())
+                        .filter(|name| !name.is_empty()),
+                    container: terminal.container.clone(),
+                    cwd: terminal.profile.as_ref().and_then(|profile| profile.cwd.clone()),
+                    history: terminal.history.entries().to_vec(),
+                })
+                .collect(),
+            selected_terminal: self.selected_terminal,
+            transfers: self.saved_transfers(),
+        }
+    }
+
+    /// Reopens the editors, terminals and unfinished transfers saved with the
+    /// project. Remote editors and transfers wait until their connection comes up.
+    pub fn restore_session(&mut self) {
+        let session = std::mem::take(&mut self.project.session);
+        self.restore_selected_editor = session.selected_editor;
+
+        for editor in session.editors {
+            match &editor.source {
+                EditorSource::Local => match fs::read_to_string(&editor.path) {
+                    Ok(content) => self.open_files.push(EditorTab {
+                        title: display_name_for_path(&editor.path),
+                        path: editor.path,
+                        source: EditorSource::Local,
+                        content,
+                        dirty: false,
+                        remote_hash: None,
+                        base: None,
+                        cursor: editor.cursor,
+                        restore_cursor: true,
+                        preview: false,
+                        media: None,
+                    }),
+                    Err(err) => self
+                        .output
+                        .error(format!("Failed to reopen {}: {err}", editor.path)),
+                },
+                EditorSource::Remote { connection_name } => self
+                    .session_restore
+                    .entry(connection_name.clone())
+                    .or_default()
+                    .push(editor),
+            }
+        }
+        self.select_restored_editor();
+
+        for terminal in session.terminals {
+            let mut profile = terminal
+                .profile
+                .as_ref()
+                .and_then(|name| self.find_terminal_profile(&terminal.connection_name, name));
+            if let Some(cwd) = terminal.cwd {
+                profile.get_or_insert_with(Default::default).cwd = Some(cwd);
+            }
+            self.terminals.push(TerminalTab {
+                id: Uuid::new_v4(),
+                connection_name: terminal.connection_name,
+                title: terminal.title,
+                input: String::new(),
+                blocks: Vec::new(),
+                profile,
+                container: terminal.container,
+                history: CommandHistory::from_entries(terminal.history),
+            });
+        }
+        self.selected_terminal = session
+            .selected_terminal
+            .filter(|idx| *idx < self.terminals.len());
+        for transfer in session.transfers {
+            self.restore_transfer(transfer);
+        }
+    }
+
+    pub fn restore_remote_editors(&mut self, connection_name: &str) {
+        let Some(editors) = self.session_restore.remove(connection_name) else {
+            return;
+        };
+        for editor in editors {
+            let request_id = Uuid::new_v4();
+            let action = self.cached_read(connection_name, &editor.path);
+            self.pending.insert(
+                request_id,
+                PendingAction::OpenRemoteFile {
+                    path: editor.path.clone(),
+                    title: display_name_for_path(&editor.path),
+                    connection_name: connection_name.to_string(),
+                    restore_cursor: Some(editor.cursor),
+                    preview: false,
+                },
+            );
+            self.send_rpc(
+                connection_name,
+                RpcRequest {
+                    request_id,
+                    action,
+                },
+            );
+        }
+    }
+
+    pub fn select_restored_editor(&mut self) {
+        let restored = self.restore_selected_editor.as_ref().and_then(|path| {
+            self.open_files.iter().position(|tab| &tab.path == path)
+        });
+        if let Some(idx) = restored {
+            self.selected_editor = Some(idx);
+            self.restore_selected_editor = None;
+        } else if self.selected_editor.is_none() && !self.open_files.is_empty() {
+            self.selected_editor = Some(0);
+        }
+    }
+}
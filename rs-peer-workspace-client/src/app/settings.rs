@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::PathBuf;
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use super::macros::Macro;
+use super::state::WorkspaceApp;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follows the operating system.
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl From<Theme> for egui::ThemePreference {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorFont {
+    #[default]
+    Monospace,
+    Proportional,
+}
+
+/// Per-user appearance and editor settings, kept apart from project files so they
+/// follow the user rather than the project.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct UserSettings {
+    pub theme: Theme,
+    pub editor_font: EditorFont,
+    pub font_size: f32,
+    /// Spaces the Tab key inserts in the editor.
+    pub tab_width: usize,
+    pub word_wrap: bool,
+    /// Disk space for cached remote file contents, in MiB; 0 turns the cache off.
+    pub content_cache_mb: u64,
+    pub macros: Vec<Macro>,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::System,
+            editor_font: EditorFont::Monospace,
+            font_size: 14.0,
+            tab_width: 4,
+            word_wrap: true,
+            content_cache_mb: 256,
+            macros: Vec::new(),
+        }
+    }
+}
+
+impl UserSettings {
+    pub fn load() -> Self {
+        fs::read_to_string(settings_path())
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = settings_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn content_cache_bytes(&self) -> u64 {
+        self.content_cache_mb * 1024 * 1024
+    }
+
+    pub fn editor_font_id(&self) -> egui::FontId {
+        let family = match self.editor_font {
+            EditorFont::Monospace => egui::FontFamily::Monospace,
+            EditorFont::Proportional => egui::FontFamily::Proportional,
+        };
+        egui::FontId::new(self.font_size, family)
+    }
+}
+
+/// `settings.json` in the per-user configuration directory.
+pub fn settings_path() -> PathBuf {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    };
+    base.unwrap_or_else(std::env::temp_dir)
+        .join("rs-peer-workspace-client")
+        .join("settings.json")
+}
+
+impl WorkspaceApp {
+    pub fn apply_theme(&self, ctx: &egui::Context) {
+        let theme = self.settings.theme.into();
+        if ctx.options(|options| options.theme_preference) != theme {
+            ctx.set_theme(theme);
+        }
+    }
+
+    pub fn draw_settings(&mut self, ctx: &egui::Context) {
+        if !self.show_settings {
+            return;
+        }
+
+        let mut open = true;
+        let mut clear_cache = false;
+        let mut settings = self.settings.clone();
+        egui::Window::new(self.i18n.tr("settings-title"))
+            .id(egui::Id::new("settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings-grid").num_columns(2).show(ui, |ui| {
+                    ui.label(self.i18n.tr("settings-theme"));
+                    ui.horizontal(|ui| {
+                        for (theme, id) in [
+                            (Theme::System, "settings-theme-system"),
+                            (Theme::Dark, "settings-theme-dark"),
+                            (Theme::Light, "settings-theme-light"),
+                        ] {
+                            ui.radio_value(&mut settings.theme, theme, self.i18n.tr(id));
+                        }
+                    });
+                    ui.end_row();
+                    ui.label(self.i18n.tr("settings-editor-font"));
+                    ui.horizontal(|ui| {
+                        ui.radio_value(
+                            &mut settings.editor_font,
+                            EditorFont::Monospace,
+                            self.i18n.tr("settings-font-monospace"),
+                        );
+                        ui.radio_value(
+                            &mut settings.editor_font,
+                            EditorFont::Proportional,
+                            self.i18n.tr("settings-font-proportional"),
+                        );
+                    });
+                    ui.end_row();
+                    let label = ui.label(self.i18n.tr("settings-font-size"));
+                    ui.add(egui::Slider::new(&mut settings.font_size, 8.0..=32.0).step_by(1.0))
+                        .labelled_by(label.id);
+                    ui.end_row();
+                    let label = ui.label(self.i18n.tr("settings-tab-width"));
+                    ui.add(egui::Slider::new(&mut settings.tab_width, 1..=8))
+                        .labelled_by(label.id);
+                    ui.end_row();
+                    ui.label("");
+                    ui.checkbox(&mut settings.word_wrap, self.i18n.tr("settings-word-wrap"));
+                    ui.end_row();
+                    let label = ui.label(self.i18n.tr("settings-content-cache"));
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::Slider::new(&mut settings.content_cache_mb, 0..=4096)
+                                .suffix(" MiB")
+                                .logarithmic(true),
+                        )
+                        .labelled_by(label.id);
+                        if ui.button(self.i18n.tr("settings-clear-cache")).clicked() {
+                            clear_cache = true;
+                        }
+                    });
+                    ui.end_row();
+                });
+                ui.separator();
+                ui.label(
+                    egui::RichText::new(self.i18n.tr("settings-preview"))
+                        .font(settings.editor_font_id()),
+                );
+                if ui.button(self.i18n.tr("settings-reset")).clicked() {
+                    settings = UserSettings::default();
+                }
+            });
+        self.show_settings = open;
+
+        if clear_cache {
+            match self.content_cache.clear() {
+                Ok(()) => self.output.info("Cleared the file cache."),
+                Err(err) => self
+                    .output
+                    .error(format!("Failed to clear the file cache: {err}")),
+            }
+        }
+
+        if settings != self.settings {
+            self.settings = settings;
+            self.content_cache.set_max_bytes(self.settings.content_cache_bytes());
+            self.apply_theme(ctx);
+            self.save_settings();
+        }
+    }
+
+    pub fn save_settings(&mut self) {
+        if let Err(err) = self.settings.save() {
+            self.output.error(format!("Failed to save settings: {err}"));
+        }
+    }
+}
@@ -1,23 +1,38 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
 
 use eframe::egui;
+use rs_peer_workspace_core::{ConnectionEvent, ForwardState};
+use rs_peer_workspace_shared::app::{FileLock, GitStatus};
+use rs_peer_workspace_shared::cache::{self, ContentCache};
+use rs_peer_workspace_shared::crash;
+use rs_peer_workspace_shared::git::LineChange;
+use rs_peer_workspace_shared::lsp::{Diagnostic, Position};
 use rs_peer_workspace_shared::project::{
-    default_connection_form_addr, EditorTab, ProjectFile, TerminalTab,
+    default_connection_form_addr, EditorTab, ProjectFile, SavedEditor, TerminalTab,
 };
 use uuid::Uuid;
 
-use crate::net::ConnectionEvent;
-
+use super::i18n::Localizer;
+use super::icons::{IconTheme, DEFAULT_ICONS};
+use super::macros::MacroState;
+use super::output::OutputLog;
+use super::readahead::ReadAheadCache;
+use super::settings::UserSettings;
+use super::taskmanager::TaskManager;
+use super::tree::DirectoryLoads;
 use super::types::{
-    BottomTab, ConnectionForm, ConnectionState, FolderForm, PendingAction, RemoteFolderPicker,
-    TerminalForm, TreeEntry,
+    ActiveRun, AddressBar, Banner, BottomTab, BroadcastForm, CommandNotice, CommandPalette, ConnectionForm, ConnectionState, CopySource, CopyTask, DiffView, EditorPane, ElevateForm, ExplorerOptionsForm, FileIndex, FileViewer, FolderForm, ForwardForm, GitCommitForm, HistorySearch, JournalEntry, LanguageClient, LspHover, LogView, MergeView, NewEntryForm, NotebookPanel, PassphraseForm, PipelineRun, PathCompletions, ProcessesPanel, PropertiesDialog, QuickOpen, RenameForm, RunConfigForm,
+    PendingAction, RemoteFolderPicker, SearchPanel, ServicesPanel, WorkspaceSearchPanel, TaskForm, TerminalForm, TerminalProfileForm, Transfer, TreeEntry,
+    SplitOrientation, UnsavedPrompt,
 };
 
 pub struct WorkspaceApp {
     pub project: ProjectFile,
     pub project_path: Option<PathBuf>,
+    pub project_passphrase: Option<String>,
     pub event_rx: Receiver<ConnectionEvent>,
     pub event_tx: Sender<ConnectionEvent>,
     pub connections: HashMap<String, ConnectionState>,
@@ -25,27 +40,114 @@ pub struct WorkspaceApp {
     pub show_add_connection: bool,
     pub show_add_folder: bool,
     pub show_new_terminal: bool,
+    pub show_terminal_profiles: bool,
+    pub show_notebook: bool,
+    pub show_tasks: bool,
+    pub show_run_configs: bool,
+    pub show_settings: bool,
+    pub settings: UserSettings,
+    pub show_broadcast: bool,
+    pub macros: MacroState,
+    pub explorer_options: ExplorerOptionsForm,
+    pub window_focused: bool,
+    pub quick_open: QuickOpen,
+    pub history_search: Option<HistorySearch>,
+    pub command_palette: CommandPalette,
+    /// Quick open indexes by connection (`None` when local) and folder root.
+    pub file_indexes: HashMap<(Option<String>, String), FileIndex>,
+    /// Git status by connection (`None` when local) and folder root; `None` while
+    /// loading or when the folder is not in a repository.
+    pub git_status: HashMap<(Option<String>, String), Option<GitStatus>>,
+    pub git_commit: GitCommitForm,
+    /// Lines changed against `HEAD` in saved files, by connection (`None` when
+    /// local) and path.
+    pub git_gutters: HashMap<(Option<String>, String), Vec<LineChange>>,
+    pub language_servers: Vec<LanguageClient>,
+    /// Diagnostics reported by language servers, by connection and path.
+    pub lsp_diagnostics: HashMap<(String, String), Vec<Diagnostic>>,
+    pub lsp_hover: Option<LspHover>,
+    /// Definition to show once its file has opened.
+    pub lsp_goto: Option<(String, String, Position)>,
+    pub notices: Vec<CommandNotice>,
     pub connection_form: ConnectionForm,
     pub folder_form: FolderForm,
     pub terminal_form: TerminalForm,
+    pub profile_form: TerminalProfileForm,
     pub remote_picker: RemoteFolderPicker,
-    pub output_lines: Vec<String>,
-    pub task_lines: Vec<String>,
+    pub address_bar: AddressBar,
+    pub passphrase_form: PassphraseForm,
+    pub elevate_form: ElevateForm,
+    pub path_completions: PathCompletions,
+    pub output: OutputLog,
+    pub task_manager: TaskManager,
+    pub task_form: TaskForm,
+    pub pipelines: Vec<PipelineRun>,
+    pub run_config_form: RunConfigForm,
+    /// Run configuration picked in the toolbar.
+    pub selected_run_config: Option<String>,
+    pub runs: Vec<ActiveRun>,
     pub explorer_cache: HashMap<String, Vec<TreeEntry>>,
+    pub read_ahead: ReadAheadCache,
+    pub content_cache: ContentCache,
     pub explorer_expanded: HashSet<String>,
     pub open_files: Vec<EditorTab>,
     pub selected_editor: Option<usize>,
+    pub secondary_editor: Option<usize>,
+    pub editor_split: Option<SplitOrientation>,
+    pub focused_pane: EditorPane,
     pub terminals: Vec<TerminalTab>,
     pub selected_terminal: Option<usize>,
+    pub session_restore: HashMap<String, Vec<SavedEditor>>,
+    pub restore_selected_editor: Option<String>,
+    pub unsaved_prompt: Option<UnsavedPrompt>,
+    pub file_locks: HashMap<(String, String), FileLock>,
+    pub diff_view: Option<DiffView>,
+    pub merge_view: Option<MergeView>,
+    pub log_views: Vec<LogView>,
+    pub selected_log: usize,
+    pub file_viewer: Option<FileViewer>,
+    pub exit_confirmed: bool,
     pub active_bottom_tab: BottomTab,
+    pub show_bottom_panel: bool,
+    pub services: ServicesPanel,
+    pub processes: ProcessesPanel,
+    pub search: SearchPanel,
+    pub workspace_search: WorkspaceSearchPanel,
+    pub notebook: NotebookPanel,
+    pub broadcast_form: BroadcastForm,
+    pub new_entry_form: NewEntryForm,
+    pub rename_form: RenameForm,
+    pub properties: Option<PropertiesDialog>,
+    /// Crash reports from earlier runs not yet opened or dismissed, newest first.
+    pub crash_reports: Vec<PathBuf>,
+    /// Deletes and renames made this session, most recent last.
+    pub journal: Vec<JournalEntry>,
+    /// Remote connection and directory under the pointer while files are dragged in.
+    pub drop_target: Option<(String, String)>,
+    pub directory_loads: DirectoryLoads,
+    pub banners: Vec<Banner>,
+    pub transfers: Vec<Transfer>,
+    pub forward_form: ForwardForm,
+    /// Reported state of the port forwards by connection and forward id.
+    pub forward_states: HashMap<String, HashMap<Uuid, ForwardState>>,
+    pub copy_source: Option<CopySource>,
+    pub copies: Vec<CopyTask>,
+    pub i18n: Localizer,
+    pub icons: &'static IconTheme,
 }
 
 impl Default for WorkspaceApp {
     fn default() -> Self {
         let (event_tx, event_rx) = mpsc::channel();
+        let settings = UserSettings::load();
+        let content_cache = ContentCache::new(
+            cache::default_dir("rs-peer-workspace-client"),
+            settings.content_cache_bytes(),
+        );
         Self {
             project: ProjectFile::default(),
             project_path: None,
+            project_passphrase: None,
             event_rx,
             event_tx,
             connections: HashMap::new(),
@@ -53,6 +155,28 @@ impl Default for WorkspaceApp {
             show_add_connection: false,
             show_add_folder: false,
             show_new_terminal: false,
+            show_terminal_profiles: false,
+            show_notebook: false,
+            show_tasks: false,
+            show_run_configs: false,
+            show_settings: false,
+            settings,
+            show_broadcast: false,
+            macros: MacroState::default(),
+            explorer_options: ExplorerOptionsForm::default(),
+            window_focused: true,
+            quick_open: QuickOpen::default(),
+            history_search: None,
+            command_palette: CommandPalette::default(),
+            file_indexes: HashMap::new(),
+            git_status: HashMap::new(),
+            git_commit: GitCommitForm::default(),
+            git_gutters: HashMap::new(),
+            language_servers: Vec::new(),
+            lsp_diagnostics: HashMap::new(),
+            lsp_hover: None,
+            lsp_goto: None,
+            notices: Vec::new(),
             connection_form: ConnectionForm {
                 proxy_addr: default_connection_form_addr(),
                 prefer_p2p: true,
@@ -60,29 +184,108 @@ impl Default for WorkspaceApp {
             },
             folder_form: FolderForm::default(),
             terminal_form: TerminalForm::default(),
+            profile_form: TerminalProfileForm::default(),
             remote_picker: RemoteFolderPicker::default(),
-            output_lines: vec!["Ready.".to_string()],
-            task_lines: Vec::new(),
+            address_bar: AddressBar::default(),
+            passphrase_form: PassphraseForm::default(),
+            elevate_form: ElevateForm::default(),
+            path_completions: PathCompletions::default(),
+            output: OutputLog::default(),
+            task_manager: TaskManager::default(),
+            task_form: TaskForm::default(),
+            pipelines: Vec::new(),
+            run_config_form: RunConfigForm::default(),
+            selected_run_config: None,
+            runs: Vec::new(),
             explorer_cache: HashMap::new(),
+            read_ahead: ReadAheadCache::default(),
+            content_cache,
             explorer_expanded: HashSet::new(),
             open_files: Vec::new(),
             selected_editor: None,
+            secondary_editor: None,
+            editor_split: None,
+            focused_pane: EditorPane::Primary,
             terminals: Vec::new(),
             selected_terminal: None,
+            session_restore: HashMap::new(),
+            restore_selected_editor: None,
+            unsaved_prompt: None,
+            file_locks: HashMap::new(),
+            diff_view: None,
+            merge_view: None,
+            log_views: Vec::new(),
+            selected_log: 0,
+            file_viewer: None,
+            exit_confirmed: false,
             active_bottom_tab: BottomTab::Output,
+            show_bottom_panel: true,
+            services: ServicesPanel::default(),
+            processes: ProcessesPanel::default(),
+            search: SearchPanel::default(),
+            workspace_search: WorkspaceSearchPanel::default(),
+            notebook: NotebookPanel::default(),
+            broadcast_form: BroadcastForm::default(),
+            new_entry_form: NewEntryForm::default(),
+            rename_form: RenameForm::default(),
+            properties: None,
+            crash_reports: crash::pending_reports(&crate::crash_dir()),
+            journal: Vec::new(),
+            drop_target: None,
+            directory_loads: DirectoryLoads::default(),
+            banners: Vec::new(),
+            transfers: Vec::new(),
+            forward_form: ForwardForm::default(),
+            forward_states: HashMap::new(),
+            copy_source: None,
+            copies: Vec::new(),
+            i18n: Localizer::default(),
+            icons: &DEFAULT_ICONS,
         }
     }
 }
 
 impl eframe::App for WorkspaceApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.window_focused = ctx.input(|input| input.viewport().focused.unwrap_or(true));
+        self.apply_theme(ctx);
         self.poll_events();
+        self.sync_language_servers(ctx);
+        self.advance_macro(ctx);
+        self.flush_notices(ctx);
+        // Keeps relative times such as "3 min ago" current while the window is idle.
+        ctx.request_repaint_after(Duration::from_secs(30));
+        self.handle_close_request(ctx);
         self.handle_shortcuts(ctx);
         self.draw_menu(ctx);
+        self.draw_banners(ctx);
         self.draw_add_connection(ctx);
+        self.draw_server_browser(ctx);
         self.draw_add_folder(ctx);
         self.draw_new_terminal(ctx);
+        self.draw_terminal_profiles(ctx);
+        self.draw_notebook(ctx);
+        self.draw_tasks(ctx);
+        self.draw_run_configs(ctx);
+        self.draw_broadcast(ctx);
+        self.draw_macros(ctx);
+        self.draw_new_entry(ctx);
+        self.draw_rename(ctx);
+        self.draw_git_commit(ctx);
+        self.draw_properties(ctx);
+        self.draw_explorer_options(ctx);
+        self.draw_settings(ctx);
+        self.draw_crash_reports(ctx);
+        self.draw_quick_open(ctx);
+        self.draw_history_search(ctx);
+        self.draw_command_palette(ctx);
         self.draw_remote_picker(ctx);
+        self.draw_passphrase(ctx);
+        self.draw_elevate(ctx);
+        self.draw_unsaved_prompt(ctx);
+        self.draw_diff_view(ctx);
+        self.draw_merge_view(ctx);
+        self.draw_file_viewer(ctx);
         self.draw_explorer(ctx);
         self.draw_bottom(ctx);
         self.draw_editor(ctx);
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::SystemMonitor;
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+const GAUGE_WIDTH: f32 = 180.0;
+
+impl WorkspaceApp {
+    pub fn draw_system_info(&self, ui: &mut egui::Ui, connection_name: &str, monitor: Option<&SystemMonitor>) {
+        if let Some(error) = monitor.and_then(|monitor| monitor.error.as_ref()) {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+            return;
+        }
+        let Some(info) = monitor.and_then(|monitor| monitor.info.as_ref()) else {
+            ui.label(self.i18n.tr("system-loading"));
+            return;
+        };
+        egui::Grid::new(format!("connection-system:{connection_name}"))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(self.i18n.tr("system-os"));
+                ui.label(format!("{} ({})", info.os, info.arch))
+                    .on_hover_text(self.i18n.tr_args(
+                        "system-kernel",
+                        &[
+                            ("kernel", info.kernel.clone().into()),
+                            ("host", info.host_name.clone().into()),
+                        ],
+                    ));
+                ui.end_row();
+                ui.label(self.i18n.tr("system-uptime"));
+                ui.label(format_uptime(info.uptime_secs));
+                ui.end_row();
+                ui.label(self.i18n.tr("system-cpu"));
+                gauge(
+                    ui,
+                    info.cpu_usage,
+                    self.i18n.tr_args(
+                        "system-cpu-value",
+                        &[
+                            ("usage", format!("{:.0}", info.cpu_usage).into()),
+                            ("cores", info.cpu_count.into()),
+                        ],
+                    ),
+                )
+                .on_hover_text(self.i18n.tr_args(
+                    "system-load",
+                    &[(
+                        "load",
+                        format!(
+                            "{:.2} {:.2} {:.2}",
+                            info.load_average[0], info.load_average[1], info.load_average[2]
+                        )
+                        .into(),
+                    )],
+                ));
+                ui.end_row();
+                ui.label(self.i18n.tr("system-memory"));
+                gauge(ui, info.memory_usage(), used_of(info.memory_used, info.memory_total));
+                ui.end_row();
+                if info.swap_total > 0 {
+                    ui.label(self.i18n.tr("system-swap"));
+                    gauge(
+                        ui,
+                        info.swap_usage(),
+                        used_of(info.swap_used, info.swap_total),
+                    );
+                    ui.end_row();
+                }
+                for disk in &info.disks {
+                    ui.label(&disk.mount_point).on_hover_text(&disk.file_system);
+                    gauge(
+                        ui,
+                        disk.usage(),
+                        used_of(disk.total.saturating_sub(disk.available), disk.total),
+                    );
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Subscribes to the system information of the connections in `visible` and
+    /// ends the subscriptions of the rest.
+    pub fn update_system_monitors(&mut self, ctx: &egui::Context, visible: &HashSet<String>) {
+        let mut requests = Vec::new();
+        for (connection_name, state) in &mut self.connections {
+            let wanted = state.connected && visible.contains(connection_name);
+            match &state.system {
+                None if wanted => {
+                    let id = Uuid::new_v4();
+                    state.system = Some(SystemMonitor {
+                        id,
+                        info: None,
+                        error: None,
+                    });
+                    let action = RpcAction::SystemInfo {
+                        interval_ms: Some(MONITOR_INTERVAL.as_millis() as u64),
+                    };
+                    requests.push((connection_name.clone(), id, action));
+                }
+                Some(monitor) if !wanted => {
+                    let action = RpcAction::StopSystemInfo { monitor_id: monitor.id };
+                    requests.push((connection_name.clone(), Uuid::new_v4(), action));
+                    state.system = None;
+                }
+                _ => {}
+            }
+        }
+        for (connection_name, request_id, action) in requests {
+            self.send_rpc(&connection_name, RpcRequest { request_id, action });
+        }
+        if self.connections.values().any(|state| state.system.is_some()) {
+            ctx.request_repaint_after(MONITOR_INTERVAL);
+        }
+    }
+
+    pub fn handle_system_response(&mut self, connection_name: &str, response: RpcResponse) {
+        let Some(monitor) = self
+            .connections
+            .get_mut(connection_name)
+            .and_then(|state| state.system.as_mut())
+        else {
+            return;
+        };
+        match response.result {
+            RpcResult::SystemInfo { info } => {
+                monitor.info = Some(*info);
+                monitor.error = None;
+            }
+            RpcResult::Error { message } => monitor.error = Some(message),
+            _ => {}
+        }
+    }
+}
+
+fn gauge(ui: &mut egui::Ui, percent: f32, text: String) -> egui::Response {
+    ui.add(
+        egui::ProgressBar::new(percent.clamp(0.0, 100.0) / 100.0)
+            .desired_width(GAUGE_WIDTH)
+            .text(text),
+    )
+}
+
+fn used_of(used: u64, total: u64) -> String {
+    format!("{} / {}", format_bytes(used), format_bytes(total))
+}
+
+fn format_uptime(secs: u64) -> String {
+    let days = secs / 86_400;
+    let hours = secs % 86_400 / 3600;
+    let minutes = secs % 3600 / 60;
+    if days > 0 {
+        format!("{days}d {hours}h {minutes:02}m")
+    } else {
+        format!("{hours}h {minutes:02}m")
+    }
+}
+
@@ -0,0 +1,294 @@
+use std::time::Instant;
+
+use eframe::egui;
+use rs_peer_workspace_shared::relay::unix_now;
+use uuid::Uuid;
+
+use super::notify::format_duration;
+use super::state::WorkspaceApp;
+use super::types::{PipelineStepStatus, Task, TaskKind, TaskStatus};
+
+/// Finished tasks kept in the Tasks tab; older ones are dropped.
+const MAX_FINISHED: usize = 500;
+
+/// Long-running operations and events shown in the Tasks tab. Updates to a task
+/// that has already finished are ignored, so a late response cannot revive it.
+#[derive(Default)]
+pub struct TaskManager {
+    tasks: Vec<Task>,
+}
+
+impl TaskManager {
+    pub fn start(&mut self, id: Uuid, kind: TaskKind, title: String) {
+        self.push(id, kind, title, TaskStatus::Running);
+    }
+
+    /// Adds a task that waits for a free slot; [`TaskManager::run`] starts it.
+    pub fn queue(&mut self, id: Uuid, kind: TaskKind, title: String) {
+        self.push(id, kind, title, TaskStatus::Queued);
+    }
+
+    /// Adds a task that finishes once `total` steps have been reported.
+    pub fn start_steps(&mut self, id: Uuid, kind: TaskKind, title: String, total: usize) {
+        self.push(id, kind, title, TaskStatus::Running);
+        if let Some(task) = self.active(id) {
+            task.steps = Some((0, total));
+            task.progress = Some(0.0);
+        }
+    }
+
+    /// Records an event as an already completed task.
+    pub fn note(&mut self, title: String) {
+        self.push(Uuid::new_v4(), TaskKind::Note, title, TaskStatus::Completed);
+    }
+
+    fn push(&mut self, id: Uuid, kind: TaskKind, title: String, status: TaskStatus) {
+        let finished = matches!(status, TaskStatus::Completed).then(Instant::now);
+        self.tasks.push(Task {
+            id,
+            kind,
+            title,
+            status,
+            progress: None,
+            steps: None,
+            detail: None,
+            at: unix_now(),
+            started: Instant::now(),
+            finished,
+        });
+        let finished = self.tasks.iter().filter(|task| task.finished.is_some()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        self.tasks.retain(|task| {
+            let drop = excess > 0 && task.finished.is_some();
+            excess -= usize::from(drop);
+            !drop
+        });
+    }
+
+    fn active(&mut self, id: Uuid) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| {
+            task.id == id && matches!(task.status, TaskStatus::Queued | TaskStatus::Running)
+        })
+    }
+
+    pub fn run(&mut self, id: Uuid) {
+        if let Some(task) = self.active(id) {
+            task.status = TaskStatus::Running;
+            task.started = Instant::now();
+        }
+    }
+
+    /// Puts a running task back in the queue with the reason it waits, such as a
+    /// transfer whose connection dropped. [`TaskManager::run`] picks it up again.
+    pub fn wait(&mut self, id: Uuid, detail: String) {
+        if let Some(task) = self.active(id) {
+            task.status = TaskStatus::Queued;
+            task.detail = Some(detail);
+        }
+    }
+
+    pub fn progress(&mut self, id: Uuid, progress: f32) {
+        if let Some(task) = self.active(id) {
+            task.progress = Some(progress.clamp(0.0, 1.0));
+        }
+    }
+
+    pub fn detail(&mut self, id: Uuid, detail: String) {
+        if let Some(task) = self.active(id) {
+            task.detail = Some(detail);
+        }
+    }
+
+    /// Counts one step of a task started with [`TaskManager::start_steps`]. The task
+    /// completes after its last step, or fails with the first error reported.
+    pub fn step(&mut self, id: Uuid, error: Option<String>) {
+        let Some(task) = self.active(id) else {
+            return;
+        };
+        let Some((done, total)) = &mut task.steps else {
+            return;
+        };
+        *done += 1;
+        let finished = *done >= *total;
+        task.progress = Some(*done as f32 / (*total).max(1) as f32);
+        if let Some(error) = error
+            && task.detail.is_none()
+        {
+            task.detail = Some(error);
+        }
+        if finished {
+            match task.detail.clone() {
+                Some(error) => self.fail(id, error),
+                None => self.complete(id, None),
+            }
+        }
+    }
+
+    pub fn complete(&mut self, id: Uuid, detail: Option<String>) {
+        self.finish(id, TaskStatus::Completed, detail);
+    }
+
+    pub fn fail(&mut self, id: Uuid, message: String) {
+        self.finish(id, TaskStatus::Failed(message), None);
+    }
+
+    pub fn cancelled(&mut self, id: Uuid) {
+        self.finish(id, TaskStatus::Cancelled, None);
+    }
+
+    fn finish(&mut self, id: Uuid, status: TaskStatus, detail: Option<String>) {
+        if let Some(task) = self.active(id) {
+            if status == TaskStatus::Completed {
+                task.progress = task.progress.map(|_| 1.0);
+            }
+            task.status = status;
+            task.finished = Some(Instant::now());
+            if detail.is_some() {
+                task.detail = detail;
+            }
+        }
+    }
+
+    pub fn kind(&self, id: Uuid) -> Option<TaskKind> {
+        self.tasks.iter().find(|task| task.id == id).map(|task| task.kind)
+    }
+
+    /// Ids of the queued and running tasks of `kind`.
+    pub fn running(&self, kind: TaskKind) -> Vec<Uuid> {
+        self.tasks
+            .iter()
+            .filter(|task| {
+                task.kind == kind && matches!(task.status, TaskStatus::Queued | TaskStatus::Running)
+            })
+            .map(|task| task.id)
+            .collect()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.tasks.retain(|task| task.finished.is_none());
+    }
+}
+
+impl WorkspaceApp {
+    /// Stops the work behind a task. Requests already sent cannot be recalled, so
+    /// their answers are dropped instead.
+    pub fn cancel_task(&mut self, id: Uuid) {
+        match self.task_manager.kind(id) {
+            Some(TaskKind::Transfer) => {
+                if let Some(transfer) = self.transfers.iter_mut().find(|item| item.id == id) {
+                    transfer.status = TaskStatus::Cancelled;
+                }
+            }
+            Some(TaskKind::Copy) => self.cancel_copy(id),
+            Some(TaskKind::Search) => {
+                if self.pending.remove(&id).is_some() {
+                    self.search.searching = false;
+                }
+            }
+            Some(TaskKind::Pipeline) => {
+                let steps = self
+                    .pipelines
+                    .iter_mut()
+                    .filter(|run| run.id == id)
+                    .flat_map(|run| run.steps.iter_mut());
+                for step in steps {
+                    if step.status == PipelineStepStatus::Waiting {
+                        step.status = PipelineStepStatus::Skipped;
+                    }
+                }
+            }
+            Some(TaskKind::Run) => self.cancel_run(id),
+            _ => return,
+        }
+        self.task_manager.cancelled(id);
+    }
+
+    pub fn draw_task_list(&mut self, ui: &mut egui::Ui) {
+        if self.task_manager.tasks.is_empty() {
+            ui.label(self.i18n.tr("operations-empty"));
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.strong(self.i18n.tr("operations-title"));
+            if ui.button(self.i18n.tr("operations-clear-finished")).clicked() {
+                self.task_manager.clear_finished();
+            }
+        });
+
+        let mut cancel = None;
+        egui::Grid::new("task-list").striped(true).show(ui, |ui| {
+            for task in self.task_manager.tasks.iter().rev() {
+                ui.label(egui::RichText::new(self.i18n.relative(task.at)).weak())
+                    .on_hover_text(self.i18n.datetime(task.at));
+                ui.label(&task.title);
+                match (&task.status, task.progress) {
+                    (TaskStatus::Running, None) => {
+                        ui.spinner();
+                    }
+                    (_, Some(progress)) => {
+                        let bar = egui::ProgressBar::new(progress).desired_width(160.0);
+                        let bar = match task.steps {
+                            Some((done, total)) => bar.text(format!("{done}/{total}")),
+                            None => bar.show_percentage(),
+                        };
+                        ui.add(bar);
+                    }
+                    _ => {
+                        ui.label("");
+                    }
+                }
+                if task.kind == TaskKind::Note {
+                    ui.label("");
+                } else {
+                    let elapsed = task.finished.unwrap_or_else(Instant::now) - task.started;
+                    ui.label(format_duration(elapsed));
+                }
+                match &task.status {
+                    TaskStatus::Queued => {
+                        ui.label(self.i18n.tr("operations-queued"));
+                    }
+                    TaskStatus::Running => {
+                        ui.label(self.i18n.tr("operations-running"));
+                    }
+                    TaskStatus::Completed if task.kind == TaskKind::Note => {
+                        ui.label("");
+                    }
+                    TaskStatus::Completed => {
+                        ui.label(self.i18n.tr("operations-done"));
+                    }
+                    TaskStatus::Cancelled => {
+                        ui.label(self.i18n.tr("operations-cancelled"));
+                    }
+                    TaskStatus::Failed(message) => {
+                        ui.colored_label(ui.visuals().error_fg_color, self.i18n.tr("operations-failed"))
+                            .on_hover_text(message);
+                    }
+                }
+                let active = matches!(task.status, TaskStatus::Queued | TaskStatus::Running);
+                if active && task.kind.cancellable() {
+                    if ui.small_button(self.i18n.tr("cancel")).clicked() {
+                        cancel = Some(task.id);
+                    }
+                } else {
+                    ui.label("");
+                }
+                match (&task.status, &task.detail) {
+                    (TaskStatus::Failed(message), _) => {
+                        ui.label(message);
+                    }
+                    (_, Some(detail)) => {
+                        ui.label(detail);
+                    }
+                    _ => {
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(id) = cancel {
+            self.cancel_task(id);
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use std::time::Instant;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest};
+use rs_peer_workspace_shared::pipeline;
+use rs_peer_workspace_shared::project::ProjectTask;
+use uuid::Uuid;
+
+use super::notify::format_duration;
+use super::state::WorkspaceApp;
+use super::types::{
+    BottomTab, PendingAction, PipelineRun, PipelineStep, PipelineStepStatus, TaskForm, TaskKind,
+};
+
+/// Lines of a step's output shown when hovering over it.
+const OUTPUT_PREVIEW_LINES: usize = 20;
+
+impl WorkspaceApp {
+    /// Runs `name` after the tasks it depends on. Tasks whose dependencies have all
+    /// succeeded start at once, so independent tasks run in parallel.
+    pub fn run_task(&mut self, name: &str) {
+        let planned = match pipeline::plan(&self.project.tasks, name) {
+            Ok(planned) => planned,
+            Err(err) => {
+                self.output.error(format!("Cannot run task {name}: {err}"));
+                self.active_bottom_tab = BottomTab::Output;
+                return;
+            }
+        };
+        let steps = planned
+            .into_iter()
+            .filter_map(|planned| {
+                let task = self
+                    .project
+                    .tasks
+                    .iter()
+                    .find(|task| task.name == planned.name)?;
+                Some(PipelineStep {
+                    task: task.clone(),
+                    stage: planned.stage,
+                    status: PipelineStepStatus::Waiting,
+                    started: None,
+                    elapsed: None,
+                    output: String::new(),
+                })
+            })
+            .collect();
+        let id = Uuid::new_v4();
+        self.pipelines.push(PipelineRun {
+            id,
+            target: name.to_string(),
+            steps,
+        });
+        self.task_manager.start(id, TaskKind::Pipeline, format!("task {name}"));
+        self.show_bottom_panel = true;
+        self.active_bottom_tab = BottomTab::Tasks;
+        self.advance_pipeline(id);
+    }
+
+    /// Starts the steps whose dependencies have succeeded and skips the ones whose
+    /// dependencies failed.
+    fn advance_pipeline(&mut self, run_id: Uuid) {
+        let Some(run) = self.pipelines.iter_mut().find(|run| run.id == run_id) else {
+            return;
+        };
+        let mut ready = Vec::new();
+        loop {
+            let mut changed = false;
+            for idx in 0..run.steps.len() {
+                if run.steps[idx].status != PipelineStepStatus::Waiting {
+                    continue;
+                }
+                let statuses: Vec<_> = run.steps[idx]
+                    .task
+                    .depends_on
+                    .iter()
+                    .filter_map(|name| run.steps.iter().find(|step| &step.task.name == name))
+                    .map(|step| &step.status)
+                    .collect();
+                if statuses.iter().any(|status| {
+                    matches!(status, PipelineStepStatus::Failed(_) | PipelineStepStatus::Skipped)
+                }) {
+                    run.steps[idx].status = PipelineStepStatus::Skipped;
+                    changed = true;
+                } else if statuses
+                    .iter()
+                    .all(|status| **status == PipelineStepStatus::Succeeded)
+                {
+                    let step = &mut run.steps[idx];
+                    let connection_name = &step.task.connection_name;
+                    if self
+                        .connections
+                        .get(connection_name)
+                        .is_some_and(|connection| connection.connected)
+                    {
+                        step.status = PipelineStepStatus::Running;
+                        step.started = Some(Instant::now());
+                        ready.push((idx, step.task.clone()));
+                    } else {
+                        let reason = format!("{connection_name} is not connected");
+                        self.task_manager.detail(
+                            run_id,
+                            format!("[{connection_name}] task {} failed: {reason}", step.task.name),
+                        );
+                        step.status = PipelineStepStatus::Failed(reason);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (idx, task) in ready {
+            let env = self.command_env(&task.connection_name, &[]);
+            let request_id = Uuid::new_v4();
+            self.pending
+                .insert(request_id, PendingAction::RunPipelineStep { run_id, step: idx });
+            self.send_rpc(
+                &task.connection_name,
+                RpcRequest {
+                    request_id,
+                    action: RpcAction::RunCommand {
+                        command: task.command,
+                        shell: None,
+                        cwd: task.cwd,
+                        env,
+                    },
+                },
+            );
+        }
+        self.report_finished_pipeline(run_id);
+    }
+
+    /// Records the outcome of a step, `Ok` holding its output and exit code, and
+    /// moves the pipeline on.
+    pub fn finish_pipeline_step(
+        &mut self,
+        run_id: Uuid,
+        idx: usize,
+        result: Result<(String, Option<i32>), String>,
+    ) {
+        let Some(step) = self
+            .pipelines
+            .iter_mut()
+            .find(|run| run.id == run_id)
+            .and_then(|run| run.steps.get_mut(idx))
+            .filter(|step| step.status == PipelineStepStatus::Running)
+        else {
+            return;
+        };
+        step.elapsed = step.started.map(|started| started.elapsed());
+        step.status = match result {
+            Ok((output, exit_code)) => {
+                step.output = output;
+                match exit_code {
+                    Some(0) => PipelineStepStatus::Succeeded,
+                    Some(code) => PipelineStepStatus::Failed(format!("exit code {code}")),
+                    None => PipelineStepStatus::Failed("no exit code".to_string()),
+                }
+            }
+            Err(message) => PipelineStepStatus::Failed(message),
+        };
+        let line = match &step.status {
+            PipelineStepStatus::Failed(reason) => format!(
+                "[{}] task {} failed: {reason}",
+                step.task.connection_name, step.task.name
+            ),
+            _ => format!(
+                "[{}] task {} succeeded",
+                step.task.connection_name, step.task.name
+            ),
+        };
+        self.task_manager.detail(run_id, line);
+        self.advance_pipeline(run_id);
+    }
+
+    /// Fails the running steps on a connection that closed.
+    pub fn fail_pipeline_steps(&mut self, connection_name: &str, reason: &str) {
+        let running: Vec<_> = self
+            .pipelines
+            .iter()
+            .flat_map(|run| {
+                run.steps.iter().enumerate().filter_map(move |(idx, step)| {
+                    (step.status == PipelineStepStatus::Running
+                        && step.task.connection_name == connection_name)
+                        .then_some((run.id, idx))
+                })
+            })
+            .collect();
+        for (run_id, idx) in running {
+            self.finish_pipeline_step(run_id, idx, Err(reason.to_string()));
+        }
+    }
+
+    fn report_finished_pipeline(&mut self, run_id: Uuid) {
+        let Some(run) = self.pipelines.iter().find(|run| run.id == run_id) else {
+            return;
+        };
+        let done = run
+            .steps
+            .iter()
+            .filter(|step| {
+                !matches!(step.status, PipelineStepStatus::Waiting | PipelineStepStatus::Running)
+            })
+            .count();
+        self.task_manager
+            .progress(run_id, done as f32 / run.steps.len().max(1) as f32);
+        if !run.finished() {
+            return;
+        }
+        let failed = run
+            .steps
+            .iter()
+            .any(|step| step.status != PipelineStepStatus::Succeeded);
+        if failed {
+            self.task_manager
+                .fail(run_id, format!("task {} failed", run.target));
+        } else {
+            self.task_manager.complete(run_id, None);
+        }
+    }
+
+    /// Project tasks with Run buttons and the progress of every pipeline, one row per
+    /// stage, for the Tasks tab.
+    pub fn draw_pipelines(&mut self, ui: &mut egui::Ui) {
+        let mut run = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(self.i18n.tr("tasks-run"));
+            for task in &self.project.tasks {
+                if ui
+                    .small_button(&task.name)
+                    .on_hover_text(&task.command)
+                    .clicked()
+                {
+                    run = Some(task.name.clone());
+                }
+            }
+            if self.project.tasks.is_empty() {
+                ui.weak(self.i18n.tr("tasks-none"));
+            }
+            if ui.small_button(self.i18n.tr("tasks-edit")).clicked() {
+                self.show_tasks = true;
+            }
+            if self.pipelines.iter().any(PipelineRun::finished)
+                && ui.small_button(self.i18n.tr("tasks-clear-finished")).clicked()
+            {
+                self.pipelines.retain(|run| !run.finished());
+            }
+        });
+
+        for pipeline in self.pipelines.iter().rev() {
+            let done = pipeline
+                .steps
+                .iter()
+                .filter(|step| {
+                    !matches!(step.status, PipelineStepStatus::Waiting | PipelineStepStatus::Running)
+                })
+                .count();
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong(&pipeline.target);
+                ui.add(
+                    egui::ProgressBar::new(done as f32 / pipeline.steps.len().max(1) as f32)
+                        .desired_width(160.0)
+                        .text(format!("{done}/{}", pipeline.steps.len())),
+                );
+            });
+            let stages = pipeline.steps.iter().map(|step| step.stage).max().unwrap_or_default();
+            for stage in 0..=stages {
+                ui.horizontal_wrapped(|ui| {
+                    ui.weak(self.i18n.tr_args("tasks-stage", &[("stage", (stage + 1).into())]));
+                    for step in pipeline.steps.iter().filter(|step| step.stage == stage) {
+                        self.draw_pipeline_step(ui, step);
+                    }
+                });
+            }
+        }
+        if self.pipelines.iter().any(|run| !run.finished()) {
+            ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
+        if let Some(name) = run {
+            self.run_task(&name);
+        }
+    }
+
+    fn draw_pipeline_step(&self, ui: &mut egui::Ui, step: &PipelineStep) {
+        let visuals = ui.visuals();
+        let (symbol, status, color) = match &step.status {
+            PipelineStepStatus::Waiting => ("○", self.i18n.tr("tasks-waiting"), visuals.weak_text_color()),
+            PipelineStepStatus::Running => ("▶", self.i18n.tr("tasks-running"), visuals.text_color()),
+            PipelineStepStatus::Succeeded => ("✔", self.i18n.tr("tasks-succeeded"), visuals.text_color()),
+            PipelineStepStatus::Failed(reason) => (
+                "✖",
+                self.i18n.tr_args("tasks-failed", &[("reason", reason.clone().into())]),
+                visuals.error_fg_color,
+            ),
+            PipelineStepStatus::Skipped => ("–", self.i18n.tr("tasks-skipped"), visuals.weak_text_color()),
+        };
+        let elapsed = step
+            .elapsed
+            .or_else(|| step.started.map(|started| started.elapsed()))
+            .map(|elapsed| format!(" {}", format_duration(elapsed)))
+            .unwrap_or_default();
+        let preview: Vec<_> = step.output.lines().rev().take(OUTPUT_PREVIEW_LINES).collect();
+        let mut hover = format!("{status}\n{}", step.task.command);
+        if !preview.is_empty() {
+            hover.push_str("\n\n");
+            hover.push_str(&preview.into_iter().rev().collect::<Vec<_>>().join("\n"));
+        }
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.colored_label(
+                color,
+                format!("{symbol} {} [{}]{elapsed}", step.task.name, step.task.connection_name),
+            )
+            .on_hover_text(hover);
+        });
+    }
+
+    pub fn draw_tasks(&mut self, ctx: &egui::Context) {
+        if !self.show_tasks {
+            return;
+        }
+
+        let mut open = self.show_tasks;
+        let mut edit = None;
+        let mut remove = None;
+        egui::Window::new(self.i18n.tr("tasks-title"))
+            .id(egui::Id::new("tasks"))
+            .open(&mut open)
+            .default_size([420.0, 480.0])
+            .show(ctx, |ui| {
+                for task in &self.project.tasks {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(
+                                self.task_form.editing.as_deref() == Some(task.name.as_str()),
+                                &task.name,
+                            )
+                            .clicked()
+                        {
+                            edit = Some(task.clone());
+                        }
+                        if ui.small_button(self.i18n.tr("remove")).clicked() {
+                            remove = Some(task.name.clone());
+                        }
+                    });
+                }
+                if self.project.tasks.is_empty() {
+                    ui.label(self.i18n.tr("tasks-none"));
+                }
+                if ui.button(self.i18n.tr("tasks-new")).clicked() {
+                    self.task_form = TaskForm::default();
+                }
+                ui.separator();
+
+                let label = ui.label(self.i18n.tr("tasks-name"));
+                ui.text_edit_singleline(&mut self.task_form.name).labelled_by(label.id);
+                egui::ComboBox::from_id_salt("task-connection")
+                    .selected_text(if self.task_form.connection_name.is_empty() {
+                        self.i18n.tr("select-connection")
+                    } else {
+                        self.task_form.connection_name.clone()
+                    })
+                    .show_ui(ui, |ui| {
+                        for connection in &self.project.connections {
+                            ui.selectable_value(
+                                &mut self.task_form.connection_name,
+                                connection.name.clone(),
+                                &connection.name,
+                            );
+                        }
+                    });
+                let label = ui.label(self.i18n.tr("tasks-command"));
+                ui.add(egui::TextEdit::multiline(&mut self.task_form.command).desired_rows(3))
+                    .labelled_by(label.id);
+                let label = ui.label(self.i18n.tr("tasks-cwd"));
+                ui.text_edit_singleline(&mut self.task_form.cwd).labelled_by(label.id);
+                ui.label(self.i18n.tr("tasks-depends-on"));
+                let editing = self.task_form.editing.clone();
+                for task in &self.project.tasks {
+                    if editing.as_deref() == Some(task.name.as_str()) {
+                        continue;
+                    }
+                    let mut checked = self.task_form.depends_on.contains(&task.name);
+                    if ui.checkbox(&mut checked, &task.name).changed() {
+                        if checked {
+                            self.task_form.depends_on.push(task.name.clone());
+                        } else {
+                            self.task_form.depends_on.retain(|name| name != &task.name);
+                        }
+                    }
+                }
+                if ui.button(self.i18n.tr("tasks-save")).clicked() {
+                    self.save_task();
+                }
+            });
+        self.show_tasks = open;
+
+        if let Some(task) = edit {
+            self.task_form = TaskForm {
+                editing: Some(task.name.clone()),
+                name: task.name,
+                connection_name: task.connection_name,
+                command: task.command,
+                cwd: task.cwd.unwrap_or_default(),
+                depends_on: task.depends_on,
+            };
+        }
+        if let Some(name) = remove {
+            self.remove_task(&name);
+        }
+    }
+
+    /// Saves the task form, renaming the task in other tasks' dependencies. A task
+    /// that would complete a dependency cycle is refused.
+    fn save_task(&mut self) {
+        let name = self.task_form.name.trim().to_string();
+        if name.is_empty() || self.task_form.connection_name.is_empty() {
+            self.output.warn("Task name and connection are required.");
+            return;
+        }
+        let cwd = self.task_form.cwd.trim();
+        let task = ProjectTask {
+            name: name.clone(),
+            connection_name: self.task_form.connection_name.clone(),
+            command: self.task_form.command.clone(),
+            cwd: (!cwd.is_empty()).then(|| cwd.to_string()),
+            depends_on: self.task_form.depends_on.clone(),
+        };
+
+        let replaced = self.task_form.editing.clone().unwrap_or_else(|| name.clone());
+        let mut tasks = self.project.tasks.clone();
+        tasks.retain(|item| item.name != replaced && item.name != name);
+        for item in &mut tasks {
+            for dependency in &mut item.depends_on {
+                if *dependency == replaced {
+                    *dependency = name.clone();
+                }
+            }
+        }
+        tasks.push(task);
+        for item in &tasks {
+            if let Err(err) = pipeline::plan(&tasks, &item.name) {
+                self.output.error(format!("Task {name} not saved: {err}"));
+                self.active_bottom_tab = BottomTab::Output;
+                return;
+            }
+        }
+        self.project.tasks = tasks;
+        self.task_form.editing = Some(name.clone());
+        self.output.info(format!("Saved task {name}"));
+    }
+
+    fn remove_task(&mut self, name: &str) {
+        self.project.tasks.retain(|task| task.name != name);
+        for task in &mut self.project.tasks {
+            task.depends_on.retain(|dependency| dependency != name);
+        }
+        if self.task_form.editing.as_deref() == Some(name) {
+            self.task_form = TaskForm::default();
+        }
+    }
+}
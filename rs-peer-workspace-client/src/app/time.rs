@@ -0,0 +1,44 @@
+use chrono::{DateTime, Local};
+use rs_peer_workspace_shared::relay::unix_now;
+
+use super::i18n::{Language, Localizer};
+
+/// Timestamps travel as Unix seconds in UTC and are only converted to the local
+/// time zone here, when they are displayed.
+fn local(unix_secs: u64) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(unix_secs as i64, 0).map(|time| time.with_timezone(&Local))
+}
+
+impl Localizer {
+    /// Local date and time in the active language's usual notation.
+    pub fn datetime(&self, unix_secs: u64) -> String {
+        let format = match self.language() {
+            Language::English => "%Y-%m-%d %H:%M",
+            Language::German => "%d.%m.%Y %H:%M",
+        };
+        local(unix_secs).map_or_else(|| "?".to_string(), |time| time.format(format).to_string())
+    }
+
+    /// Local time of day, for timestamps that are expected to be recent.
+    pub fn clock(&self, unix_secs: u64) -> String {
+        local(unix_secs).map_or_else(|| "?".to_string(), |time| time.format("%H:%M").to_string())
+    }
+
+    /// "3 min ago" or "in 2 hours"; a week or more away falls back to the date.
+    pub fn relative(&self, unix_secs: u64) -> String {
+        let now = unix_now();
+        let (id, secs) = if unix_secs <= now {
+            ("time-ago", now - unix_secs)
+        } else {
+            ("time-in", unix_secs - now)
+        };
+        let (unit, count) = match secs {
+            0..60 => ("seconds", 0),
+            60..3600 => ("minutes", secs / 60),
+            3600..86_400 => ("hours", secs / 3600),
+            86_400..604_800 => ("days", secs / 86_400),
+            _ => return self.datetime(unix_secs),
+        };
+        self.tr_args(id, &[("unit", unit.into()), ("count", count.into())])
+    }
+}
@@ -0,0 +1,660 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use eframe::egui;
+use rfd::FileDialog;
+use rs_peer_workspace_core::transfer::ChunkTuner;
+use rs_peer_workspace_shared::app::{ChunkRange, RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::project::{display_name_for_path, SavedTransfer, TransferDirection};
+use rs_peer_workspace_shared::stat::ensure_space;
+use rs_peer_workspace_shared::transfer::{range_checksums, ChunkLog, VerifiedChunk};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, PendingAction, TaskKind, TaskStatus, Transfer};
+
+impl WorkspaceApp {
+    pub fn prompt_download(&mut self, connection_name: &str, remote_path: &str) {
+        let Some(local_path) = FileDialog::new()
+            .set_file_name(display_name_for_path(remote_path))
+            .save_file()
+        else {
+            return;
+        };
+        self.start_download(connection_name, remote_path, local_path.to_string_lossy().to_string());
+    }
+
+    pub fn prompt_upload(&mut self, connection_name: &str, remote_dir: &str) {
+        let Some(local_path) = FileDialog::new().pick_file() else {
+            return;
+        };
+        let name = local_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.start_upload(
+            connection_name,
+            local_path.to_string_lossy().to_string(),
+            join_remote_path(remote_dir, &name),
+        );
+    }
+
+    pub fn start_download(&mut self, connection_name: &str, remote_path: &str, local_path: String) {
+        if let Err(err) = File::create(&local_path) {
+            self.output
+                .error(format!("Failed to create {local_path}: {err}"));
+            return;
+        }
+        self.start_transfer(
+            connection_name,
+            TransferDirection::Download,
+            local_path,
+            remote_path.to_string(),
+            None,
+        );
+    }
+
+    pub fn start_upload(&mut self, connection_name: &str, local_path: String, remote_path: String) {
+        let total_size = match std::fs::metadata(&local_path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) => {
+                self.output
+                    .error(format!("Failed to read {local_path}: {err}"));
+                return;
+            }
+        };
+        self.start_transfer(
+            connection_name,
+            TransferDirection::Upload,
+            local_path,
+            remote_path,
+            Some(total_size),
+        );
+    }
+
+    fn start_transfer(
+        &mut self,
+        connection_name: &str,
+        direction: TransferDirection,
+        local_path: String,
+        remote_path: String,
+        total_size: Option<u64>,
+    ) {
+        let id = Uuid::new_v4();
+        self.task_manager.start(
+            id,
+            TaskKind::Transfer,
+            transfer_title(connection_name, direction, &remote_path),
+        );
+        self.transfers.push(Transfer {
+            id,
+            connection_name: connection_name.to_string(),
+            direction,
+            local_path,
+            remote_path,
+            total_size,
+            transferred: 0,
+            next_offset: 0,
+            in_flight: 0,
+            tuner: ChunkTuner::default(),
+            chunks: ChunkLog::default(),
+            started: Instant::now(),
+            status: TaskStatus::Running,
+        });
+        self.active_bottom_tab = BottomTab::Transfers;
+        self.pump_transfer(id);
+    }
+
+    /// Queues a transfer saved with the project; it resumes once its connection is up.
+    pub fn restore_transfer(&mut self, saved: SavedTransfer) {
+        let id = Uuid::new_v4();
+        self.task_manager.queue(
+            id,
+            TaskKind::Transfer,
+            transfer_title(&saved.connection_name, saved.direction, &saved.remote_path),
+        );
+        self.transfers.push(Transfer {
+            id,
+            connection_name: saved.connection_name,
+            direction: saved.direction,
+            local_path: saved.local_path,
+            remote_path: saved.remote_path,
+            total_size: saved.total_size,
+            transferred: saved.chunks.verified_len(),
+            next_offset: saved.chunks.verified_len(),
+            in_flight: 0,
+            tuner: ChunkTuner::default(),
+            chunks: saved.chunks,
+            started: Instant::now(),
+            status: TaskStatus::Queued,
+        });
+    }
+
+    /// Unfinished transfers, to be saved with the project.
+    pub fn saved_transfers(&self) -> Vec<SavedTransfer> {
+        self.transfers
+            .iter()
+            .filter(|transfer| matches!(transfer.status, TaskStatus::Queued | TaskStatus::Running))
+            .map(|transfer| SavedTransfer {
+                connection_name: transfer.connection_name.clone(),
+                direction: transfer.direction,
+                local_path: transfer.local_path.clone(),
+                remote_path: transfer.remote_path.clone(),
+                total_size: transfer.total_size,
+                chunks: transfer.chunks.clone(),
+            })
+            .collect()
+    }
+
+    fn pump_transfer(&mut self, transfer_id: Uuid) {
+        let mut requests = Vec::new();
+        let mut failure = None;
+        let Some(transfer) = self.transfers.iter_mut().find(|item| item.id == transfer_id) else {
+            return;
+        };
+        if transfer.status != TaskStatus::Running {
+            return;
+        }
+
+        loop {
+            let awaiting_first = transfer.next_offset > 0 && transfer.transferred == 0;
+            let remaining = transfer
+                .total_size
+                .map(|total| total.saturating_sub(transfer.next_offset));
+            let started = transfer.next_offset > 0 || transfer.in_flight > 0;
+            if transfer.in_flight >= transfer.tuner.window()
+                || awaiting_first
+                || (started && remaining.is_none_or(|remaining| remaining == 0))
+            {
+                break;
+            }
+
+            let offset = transfer.next_offset;
+            let length = remaining.map_or(transfer.tuner.chunk_size(), |remaining| {
+                remaining.min(transfer.tuner.chunk_size())
+            });
+            let (action, checksum) = match transfer.direction {
+                TransferDirection::Download => (
+                    RpcAction::ReadFileChunk {
+                        path: transfer.remote_path.clone(),
+                        offset,
+                        length,
+                    },
+                    None,
+                ),
+                TransferDirection::Upload => match read_local_chunk(&transfer.local_path, offset, length) {
+                    Ok(data) => {
+                        let checksum = content_hash(&data);
+                        let action = RpcAction::WriteFileChunk {
+                            path: transfer.remote_path.clone(),
+                            offset,
+                            data,
+                            truncate: offset == 0,
+                            total_size: (offset == 0).then_some(transfer.total_size).flatten(),
+                            checksum: Some(checksum.clone()),
+                        };
+                        (action, Some(checksum))
+                    }
+                    Err(err) => {
+                        failure = Some(err.to_string());
+                        break;
+                    }
+                },
+            };
+            transfer.next_offset += length;
+            transfer.in_flight += 1;
+            requests.push((offset, length, checksum, action));
+            if transfer.total_size.is_none() {
+                break;
+            }
+        }
+
+        let connection_name = transfer.connection_name.clone();
+        if let Some(message) = failure {
+            transfer.status = TaskStatus::Failed(message.clone());
+            self.task_manager.fail(transfer_id, message);
+        }
+        for (offset, length, checksum, action) in requests {
+            let request_id = Uuid::new_v4();
+            self.pending.insert(
+                request_id,
+                PendingAction::TransferChunk {
+                    transfer_id,
+                    offset,
+                    length,
+                    sent_at: Instant::now(),
+                    checksum,
+                },
+            );
+            self.send_rpc(&connection_name, RpcRequest { request_id, action });
+        }
+    }
+
+    pub fn handle_transfer_chunk(
+        &mut self,
+        transfer_id: Uuid,
+        offset: u64,
+        length: u64,
+        sent_at: Instant,
+        checksum: Option<String>,
+        result: RpcResult,
+    ) {
+        let Some(transfer) = self.transfers.iter_mut().find(|item| item.id == transfer_id) else {
+            return;
+        };
+        transfer.in_flight = transfer.in_flight.saturating_sub(1);
+        if transfer.status != TaskStatus::Running {
+            return;
+        }
+
+        let outcome = match result {
+            RpcResult::FileChunk {
+                data,
+                total_size,
+                checksum: expected,
+                ..
+            } => {
+                transfer.total_size = Some(total_size);
+                let received = data.len() as u64;
+                let checksum = content_hash(&data);
+                if received < length && offset + received < total_size {
+                    Err("remote file ended early".to_string())
+                } else if expected.is_some_and(|expected| expected != checksum) {
+                    Err(format!("chunk at offset {offset} failed its checksum"))
+                } else if offset == 0
+                    && let Err(err) = ensure_space(Path::new(&transfer.local_path), total_size)
+                {
+                    Err(err.to_string())
+                } else {
+                    write_local_chunk(&transfer.local_path, offset, &data)
+                        .map(|()| (received, checksum))
+                        .map_err(|err| err.to_string())
+                }
+            }
+            RpcResult::ChunkWritten { length, .. } => checksum
+                .map(|checksum| (length, checksum))
+                .ok_or_else(|| "unexpected response to transfer chunk".to_string()),
+            RpcResult::Error { message } => Err(message),
+            _ => Err("unexpected response to transfer chunk".to_string()),
+        };
+
+        match outcome {
+            Ok((bytes, checksum)) => {
+                transfer.chunks.record(VerifiedChunk {
+                    offset,
+                    length: bytes,
+                    checksum,
+                });
+                transfer.transferred += bytes;
+                transfer.tuner.record(bytes, sent_at.elapsed());
+                self.task_manager
+                    .progress(transfer_id, progress(transfer.total_size, transfer.transferred));
+                if transfer
+                    .total_size
+                    .is_some_and(|total| transfer.transferred >= total)
+                {
+                    transfer.status = TaskStatus::Completed;
+                    let message = format!(
+                        "Transfer of {} complete ({} bytes)",
+                        display_name_for_path(&transfer.remote_path),
+                        transfer.transferred
+                    );
+                    let uploaded = (transfer.direction == TransferDirection::Upload).then(|| {
+                        (transfer.connection_name.clone(), remote_parent(&transfer.remote_path))
+                    });
+                    self.task_manager.complete(transfer_id, Some(message));
+                    if let Some((connection_name, parent)) = uploaded {
+                        self.refresh_remote_directory(&connection_name, &parent);
+                    }
+                } else {
+                    self.pump_transfer(transfer_id);
+                }
+            }
+            Err(message) => {
+                transfer.tuner.record_failure();
+                self.task_manager.fail(transfer_id, message.clone());
+                transfer.status = TaskStatus::Failed(message);
+            }
+        }
+    }
+
+    /// Fails the unfinished transfers of `connection_name`, or of every connection.
+    pub fn fail_transfers(&mut self, connection_name: Option<&str>, reason: &str) {
+        for transfer in &mut self.transfers {
+            if connection_name.is_none_or(|name| transfer.connection_name == name)
+                && matches!(transfer.status, TaskStatus::Queued | TaskStatus::Running)
+            {
+                transfer.status = TaskStatus::Failed(reason.to_string());
+                self.task_manager.fail(transfer.id, reason.to_string());
+            }
+        }
+    }
+
+    /// Holds the running transfers of a connection that dropped. They resume from
+    /// their verified chunks when it connects again.
+    pub fn interrupt_transfers(&mut self, connection_name: &str, reason: &str) {
+        for transfer in &mut self.transfers {
+            if transfer.connection_name == connection_name && transfer.status == TaskStatus::Running {
+                transfer.status = TaskStatus::Queued;
+                transfer.in_flight = 0;
+                self.task_manager
+                    .wait(transfer.id, format!("waiting to resume: {reason}"));
+            }
+        }
+    }
+
+    /// Asks the server to checksum the last verified chunks of the transfers waiting
+    /// for `connection_name`, and checksums the local side of the same chunks.
+    pub fn resume_transfers(&mut self, connection_name: &str) {
+        let mut requests = Vec::new();
+        for transfer in &self.transfers {
+            if transfer.connection_name != connection_name || transfer.status != TaskStatus::Queued {
+                continue;
+            }
+            let chunks: Vec<ChunkRange> = transfer
+                .chunks
+                .resume_checks()
+                .iter()
+                .map(VerifiedChunk::range)
+                .collect();
+            let (local, local_size) = match range_checksums(Path::new(&transfer.local_path), &chunks) {
+                Ok((checksums, size)) => (checksums, Some(size)),
+                Err(_) => (Vec::new(), None),
+            };
+            let action = RpcAction::ChecksumChunks {
+                path: transfer.remote_path.clone(),
+                chunks,
+            };
+            requests.push((transfer.id, local, local_size, action));
+        }
+        for (transfer_id, local, local_size, action) in requests {
+            let request_id = Uuid::new_v4();
+            self.pending.insert(
+                request_id,
+                PendingAction::ResumeTransfer {
+                    transfer_id,
+                    local,
+                    local_size,
+                },
+            );
+            self.send_rpc(connection_name, RpcRequest { request_id, action });
+        }
+    }
+
+    /// Resumes a transfer after the chunks it had verified: from the first chunk
+    /// the destination no longer holds, or from the start when the source changed
+    /// or either side cannot be checksummed.
+    pub fn handle_transfer_resume(
+        &mut self,
+        transfer_id: Uuid,
+        local: Vec<Option<String>>,
+        local_size: Option<u64>,
+        result: RpcResult,
+    ) {
+        let Some(transfer) = self.transfers.iter_mut().find(|item| item.id == transfer_id) else {
+            return;
+        };
+        if transfer.status != TaskStatus::Queued {
+            return;
+        }
+
+        let remote = match result {
+            RpcResult::ChunkChecksums {
+                checksums,
+                total_size,
+                ..
+            } => Some((checksums, total_size)),
+            _ => None,
+        };
+        let offset = match (remote, local_size) {
+            (Some(remote), Some(local_size)) => {
+                let ((source, source_size), (destination, destination_size)) = match transfer.direction {
+                    TransferDirection::Download => (remote, (local, local_size)),
+                    TransferDirection::Upload => ((local, local_size), remote),
+                };
+                let offset = if transfer.total_size.is_some_and(|total| total != source_size) {
+                    transfer.chunks.reset();
+                    0
+                } else {
+                    transfer.chunks.resume(&source, &destination)
+                };
+                if destination_size < offset {
+                    transfer.chunks.reset();
+                    0
+                } else {
+                    offset
+                }
+            }
+            _ => {
+                transfer.chunks.reset();
+                0
+            }
+        };
+
+        let mut failure = None;
+        if offset == 0 {
+            match transfer.direction {
+                TransferDirection::Download => {
+                    transfer.total_size = None;
+                    if let Err(err) = File::create(&transfer.local_path) {
+                        failure = Some(format!("Failed to create {}: {err}", transfer.local_path));
+                    }
+                }
+                TransferDirection::Upload => transfer.total_size = local_size,
+            }
+        }
+        if let Some(message) = failure {
+            transfer.status = TaskStatus::Failed(message.clone());
+            self.task_manager.fail(transfer_id, message);
+            return;
+        }
+
+        transfer.next_offset = offset;
+        transfer.transferred = offset;
+        transfer.in_flight = 0;
+        transfer.tuner = ChunkTuner::default();
+        transfer.status = TaskStatus::Running;
+        let message = match offset {
+            0 => format!("restarting transfer of {}", display_name_for_path(&transfer.remote_path)),
+            _ => format!(
+                "resuming transfer of {} after {} verified",
+                display_name_for_path(&transfer.remote_path),
+                format_bytes(offset)
+            ),
+        };
+        let connection_name = transfer.connection_name.clone();
+        let done = progress(transfer.total_size, transfer.transferred);
+        self.task_manager.run(transfer_id);
+        self.task_manager.progress(transfer_id, done);
+        self.output.connection_info(&connection_name, message);
+        self.pump_transfer(transfer_id);
+    }
+
+    pub fn draw_transfers(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(self.i18n.tr("transfer-clear-finished")).clicked() {
+                self.transfers.retain(|transfer| {
+                    matches!(transfer.status, TaskStatus::Queued | TaskStatus::Running)
+                });
+                self.copies.retain(|task| {
+                    matches!(task.status, TaskStatus::Queued | TaskStatus::Running)
+                });
+            }
+        });
+        ui.separator();
+
+        if self.transfers.is_empty() && self.copies.is_empty() {
+            ui.label(self.i18n.tr("transfer-empty"));
+            return;
+        }
+
+        let mut cancel = None;
+        let mut cancel_copy = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("transfers-grid").striped(true).show(ui, |ui| {
+                for transfer in &self.transfers {
+                    let arrow = match transfer.direction {
+                        TransferDirection::Download => "↓",
+                        TransferDirection::Upload => "↑",
+                    };
+                    ui.label(format!("{arrow} [{}]", transfer.connection_name));
+                    ui.label(display_name_for_path(&transfer.remote_path))
+                        .on_hover_text(format!("{} <-> {}", transfer.remote_path, transfer.local_path));
+                    ui.add(
+                        egui::ProgressBar::new(progress(transfer.total_size, transfer.transferred))
+                            .desired_width(160.0)
+                            .text(format_bytes(transfer.transferred)),
+                    );
+                    let elapsed = transfer.started.elapsed().as_secs_f64().max(0.001);
+                    ui.label(format!("{}/s", format_bytes((transfer.transferred as f64 / elapsed) as u64)));
+                    ui.label(self.i18n.tr_args(
+                        "transfer-chunk",
+                        &[
+                            ("size", format_bytes(transfer.tuner.chunk_size()).into()),
+                            ("window", transfer.tuner.window().into()),
+                        ],
+                    ))
+                    .on_hover_text(match transfer.tuner.rtt() {
+                        Some(rtt) => self.i18n.tr_args(
+                            "transfer-rtt",
+                            &[("ms", format!("{:.0}", rtt.as_secs_f64() * 1000.0).into())],
+                        ),
+                        None => self.i18n.tr("transfer-rtt-unknown"),
+                    });
+                    if self.draw_transfer_status(ui, &transfer.status) {
+                        cancel = Some(transfer.id);
+                    }
+                    ui.end_row();
+                }
+                for task in &self.copies {
+                    let target = match &task.target_connection {
+                        Some(connection_name) => connection_name.clone(),
+                        None => self.i18n.tr("folder-local"),
+                    };
+                    ui.label(format!("⇄ [{}] → [{target}]", task.source.connection_name));
+                    ui.label(display_name_for_path(&task.source.path))
+                        .on_hover_text(format!("{} -> {}", task.source.path, task.target_path));
+                    if task.source.is_dir {
+                        ui.label("");
+                        ui.label("");
+                        ui.label("");
+                    } else {
+                        ui.add(
+                            egui::ProgressBar::new(progress(task.total_size, task.transferred))
+                                .desired_width(160.0)
+                                .text(format_bytes(task.transferred)),
+                        );
+                        let elapsed = task
+                            .started
+                            .map_or(0.001, |started| started.elapsed().as_secs_f64().max(0.001));
+                        ui.label(format!("{}/s", format_bytes((task.transferred as f64 / elapsed) as u64)));
+                        ui.label(self.i18n.tr_args(
+                            "transfer-chunk",
+                            &[
+                                ("size", format_bytes(task.tuner.chunk_size()).into()),
+                                ("window", task.tuner.window().into()),
+                            ],
+                        ));
+                    }
+                    if self.draw_transfer_status(ui, &task.status) {
+                        cancel_copy = Some(task.id);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some(id) = cancel.or(cancel_copy) {
+            self.cancel_task(id);
+        }
+    }
+
+    /// Returns whether the cancel button was clicked.
+    fn draw_transfer_status(&self, ui: &mut egui::Ui, status: &TaskStatus) -> bool {
+        match status {
+            TaskStatus::Queued => {
+                return ui
+                    .horizontal(|ui| {
+                        ui.label(self.i18n.tr("transfer-queued"));
+                        ui.small_button(self.i18n.tr("cancel")).clicked()
+                    })
+                    .inner;
+            }
+            TaskStatus::Running => {
+                return ui.small_button(self.i18n.tr("cancel")).clicked();
+            }
+            TaskStatus::Completed => {
+                ui.label(self.i18n.tr("transfer-done"));
+            }
+            TaskStatus::Cancelled => {
+                ui.label(self.i18n.tr("transfer-cancelled"));
+            }
+            TaskStatus::Failed(message) => {
+                ui.colored_label(ui.visuals().error_fg_color, self.i18n.tr("transfer-failed"))
+                    .on_hover_text(message);
+            }
+        }
+        false
+    }
+}
+
+fn transfer_title(connection_name: &str, direction: TransferDirection, remote_path: &str) -> String {
+    let arrow = match direction {
+        TransferDirection::Download => "↓",
+        TransferDirection::Upload => "↑",
+    };
+    format!("{arrow} [{connection_name}] {}", display_name_for_path(remote_path))
+}
+
+pub fn progress(total_size: Option<u64>, transferred: u64) -> f32 {
+    match total_size {
+        Some(0) => 1.0,
+        Some(total) => transferred as f32 / total as f32,
+        None => 0.0,
+    }
+}
+
+pub fn join_remote_path(parent: &str, name: &str) -> String {
+    let separator = if parent.contains('\\') && !parent.contains('/') { '\\' } else { '/' };
+    format!("{}{separator}{name}", parent.trim_end_matches(['/', '\\']))
+}
+
+pub fn remote_parent(path: &str) -> String {
+    match path.rfind(['/', '\\']) {
+        Some(0) => path[..1].to_string(),
+        Some(index) => path[..index].to_string(),
+        None => path.to_string(),
+    }
+}
+
+fn read_local_chunk(path: &str, offset: u64, length: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(length as usize);
+    file.take(length).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+pub fn write_local_chunk(path: &str, offset: u64, data: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(data)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -1,26 +1,106 @@
+use std::collections::{HashSet, VecDeque};
 use std::fs;
+use std::path::Path;
 
-use rs_peer_workspace_shared::app::DirectoryEntry;
+use rs_peer_workspace_shared::app::{DirectoryEntry, ListFilter};
+use rs_peer_workspace_shared::project::ExplorerSort;
+use rs_peer_workspace_shared::stat::modified_secs;
 
 use super::types::TreeEntry;
 
+const MAX_PARALLEL_LISTINGS: usize = 4;
+
+/// Explorer directory listings that are in flight or waiting for a free slot, keyed
+/// by connection and path.
+#[derive(Default)]
+pub struct DirectoryLoads {
+    in_flight: HashSet<(String, String)>,
+    queued: VecDeque<(String, String)>,
+    stale: HashSet<(String, String)>,
+}
+
+impl DirectoryLoads {
+    /// Returns whether a listing of `path` should be sent now. Requests for a path
+    /// already pending are coalesced; with `fresh`, an in-flight listing is repeated
+    /// once it completes because it may predate the change being refreshed.
+    pub fn request(&mut self, connection_name: &str, path: &str, fresh: bool) -> bool {
+        let key = (connection_name.to_string(), path.to_string());
+        if self.in_flight.contains(&key) {
+            if fresh {
+                self.stale.insert(key);
+            }
+            return false;
+        }
+        if self.queued.contains(&key) {
+            return false;
+        }
+        let running = self
+            .in_flight
+            .iter()
+            .filter(|(connection, _)| connection == connection_name)
+            .count();
+        if running >= MAX_PARALLEL_LISTINGS {
+            self.queued.push_back(key);
+            return false;
+        }
+        self.in_flight.insert(key);
+        true
+    }
+
+    /// Marks a listing as answered and returns the next path to list on that
+    /// connection, if any.
+    pub fn finish(&mut self, connection_name: &str, path: &str) -> Option<String> {
+        let key = (connection_name.to_string(), path.to_string());
+        if self.stale.remove(&key) {
+            return Some(key.1);
+        }
+        self.in_flight.remove(&key);
+        let index = self
+            .queued
+            .iter()
+            .position(|(connection, _)| connection == connection_name)?;
+        let next = self.queued.remove(index)?;
+        self.in_flight.insert(next.clone());
+        Some(next.1)
+    }
+
+    pub fn forget_connection(&mut self, connection_name: &str) {
+        self.in_flight
+            .retain(|(connection, _)| connection != connection_name);
+        self.queued
+            .retain(|(connection, _)| connection != connection_name);
+        self.stale
+            .retain(|(connection, _)| connection != connection_name);
+    }
+}
+
 pub fn tree_from_entry(entry: DirectoryEntry) -> TreeEntry {
     TreeEntry {
         name: entry.name,
         path: entry.path,
         is_dir: entry.is_dir,
+        size: entry.size,
+        modified: entry.modified,
+        is_symlink: entry.is_symlink,
     }
 }
 
-pub fn list_local_directory(path: &str) -> anyhow::Result<Vec<TreeEntry>> {
+pub fn list_local_directory(path: &str, filter: &ListFilter) -> anyhow::Result<Vec<TreeEntry>> {
     let mut entries = Vec::new();
     for entry in fs::read_dir(path)? {
         let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if filter.hides(&name) {
+            continue;
+        }
         let metadata = entry.metadata()?;
         entries.push(TreeEntry {
-            name: entry.file_name().to_string_lossy().to_string(),
+            name,
             path: entry.path().to_string_lossy().to_string(),
             is_dir: metadata.is_dir(),
+            size: (!metadata.is_dir()).then_some(metadata.len()),
+            modified: modified_secs(&metadata),
+            is_symlink: entry.file_type()?.is_symlink(),
         });
     }
     entries.sort_by(|left, right| {
@@ -31,3 +111,32 @@ pub fn list_local_directory(path: &str) -> anyhow::Result<Vec<TreeEntry>> {
     });
     Ok(entries)
 }
+
+/// Orders `entries` for display, folders first.
+pub fn sort_entries(entries: &mut [TreeEntry], sort: ExplorerSort) {
+    entries.sort_by(|left, right| {
+        let order = match sort {
+            ExplorerSort::Name => std::cmp::Ordering::Equal,
+            ExplorerSort::Size => right.size.cmp(&left.size),
+            ExplorerSort::Modified => right.modified.cmp(&left.modified),
+        };
+        right
+            .is_dir
+            .cmp(&left.is_dir)
+            .then(order)
+            .then_with(|| left.name.to_lowercase().cmp(&right.name.to_lowercase()))
+    });
+}
+
+/// Creates an empty file or directory, failing if `path` already exists.
+pub fn create_local_entry(path: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        fs::create_dir(path)
+    } else {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    }
+}
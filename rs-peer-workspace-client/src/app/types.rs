@@ -1,14 +1,42 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
+use rs_peer_workspace_core::transfer::ChunkTuner;
+use rs_peer_workspace_core::{ConnectionCommand, ConnectionTest, TransportStats};
+use rs_peer_workspace_shared::app::{
+    ContainerEntry, EntryStat, EnvVar, GitFileStatus, KernelEntry, OutputAttachment, ProcessEntry, ReplacedFile, SearchFileMatches, SearchQuery,
+    ServiceEntry, SessionAccess, SystemInfo, ToolInfo,
+};
+use rs_peer_workspace_shared::diff::TextDiff;
+use rs_peer_workspace_shared::lsp::LanguageServerSpec;
+use rs_peer_workspace_shared::discovery::Announcement;
+use rs_peer_workspace_shared::media::MediaKind;
+use rs_peer_workspace_shared::merge::ThreeWayMerge;
+use rs_peer_workspace_shared::project::{
+    ExplorerSettings, ForwardDirection, IceServer, OutboundProxyKind, ProjectFile, ProjectFolder, ProjectTask,
+    TransferDirection,
+};
+use rs_peer_workspace_shared::relay::ServerInfo;
+use rs_peer_workspace_shared::transfer::ChunkLog;
+use rs_peer_workspace_shared::tunnel::InOrder;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::net::ConnectionCommand;
-
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BottomTab {
     Output,
     Tasks,
     Terminal,
+    Services,
+    Transfers,
+    Search,
+    WorkspaceSearch,
+    Logs,
+    Ports,
+    Processes,
 }
 
 #[derive(Debug, Clone)]
@@ -16,6 +44,20 @@ pub struct ConnectionState {
     pub command_tx: tokio::sync::mpsc::UnboundedSender<ConnectionCommand>,
     pub connected: bool,
     pub transport: String,
+    pub stats: Option<TransportStats>,
+    pub tools: Option<Vec<ToolInfo>>,
+    pub access: Option<SessionAccess>,
+    pub connect_task: Uuid,
+    /// Live system information, while the connection's details are open.
+    pub system: Option<SystemMonitor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemMonitor {
+    /// Request id of the `system_info` subscription.
+    pub id: Uuid,
+    pub info: Option<SystemInfo>,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,16 +65,51 @@ pub struct TreeEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    pub size: Option<u64>,
+    pub modified: Option<u64>,
+    pub is_symlink: bool,
 }
 
 #[derive(Default)]
 pub struct ConnectionForm {
+    /// Name of the connection being edited; `None` while adding one.
+    pub editing: Option<String>,
     pub name: String,
     pub proxy_addr: String,
+    pub proxy_username: String,
     pub proxy_password: String,
     pub server_name: String,
     pub server_password: String,
     pub prefer_p2p: bool,
+    pub require_e2e: bool,
+    pub use_keyring: bool,
+    pub outbound_kind: Option<OutboundProxyKind>,
+    pub outbound_addr: String,
+    pub outbound_username: String,
+    pub outbound_password: String,
+    pub ice_servers: Vec<IceServer>,
+    pub env: Vec<EnvVar>,
+    /// Whether the server browser is open.
+    pub browsing: bool,
+    pub servers: Vec<ServerInfo>,
+    pub servers_error: Option<String>,
+    pub server_filter: String,
+    pub loading_servers: bool,
+    pub testing: bool,
+    pub test_result: Option<Result<ConnectionTest, String>>,
+    /// Proxies found on the LAN by the last discovery scan.
+    pub discovered: Vec<Announcement>,
+    pub discovering: bool,
+}
+
+#[derive(Default)]
+pub struct ExplorerOptionsForm {
+    pub open: bool,
+    pub settings: ExplorerSettings,
+    /// Exclude patterns being edited, one per line.
+    pub excludes: String,
+    /// Text file extensions being edited, separated by spaces or commas.
+    pub text_extensions: String,
 }
 
 #[derive(Default)]
@@ -42,11 +119,110 @@ pub struct FolderForm {
     pub local_path: String,
     pub remote_connection_name: String,
     pub remote_path: String,
+    pub terminal_profile: String,
 }
 
 #[derive(Default)]
 pub struct TerminalForm {
     pub connection_name: String,
+    pub profile_name: String,
+    pub container: String,
+    pub containers: Vec<ContainerEntry>,
+    pub loading_containers: bool,
+}
+
+#[derive(Default)]
+pub struct TerminalProfileForm {
+    pub connection_name: String,
+    pub editing: Option<String>,
+    pub name: String,
+    pub shell: String,
+    pub cwd: String,
+    pub env: String,
+    pub startup_commands: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditorPane {
+    Primary,
+    Secondary,
+}
+
+impl EditorPane {
+    pub fn other(self) -> Self {
+        match self {
+            EditorPane::Primary => EditorPane::Secondary,
+            EditorPane::Secondary => EditorPane::Primary,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitOrientation {
+    Vertical,
+    Horizontal,
+}
+
+/// Action that would discard unsaved editor changes.
+#[derive(Debug, Clone)]
+pub enum GuardedAction {
+    CloseTabs(Vec<String>),
+    ResetProject,
+    OpenProject(PathBuf),
+    Exit,
+}
+
+pub struct UnsavedPrompt {
+    pub action: GuardedAction,
+    pub saving: bool,
+}
+
+/// Local buffer of a remote editor compared against the file's current remote content.
+pub struct DiffView {
+    pub connection_name: String,
+    pub path: String,
+    pub diff: TextDiff,
+    pub remote_hash: Option<String>,
+    pub take_remote: Vec<bool>,
+    pub inline: bool,
+}
+
+/// Three-way merge of an editor tab's content. `theirs` is set when merging a save
+/// conflict, and becomes the tab's remote state once the result is applied.
+pub struct MergeView {
+    pub path: String,
+    pub merge: ThreeWayMerge,
+    pub resolutions: Vec<Option<String>>,
+    pub theirs: Option<(String, String)>,
+}
+
+/// A remote file followed with `tail_file`. `id` is the request id the server pushes
+/// new lines under; lines that arrive while paused wait in `held`.
+pub struct LogView {
+    pub id: Uuid,
+    pub connection_name: String,
+    pub path: String,
+    pub lines: VecDeque<String>,
+    pub held: Vec<String>,
+    pub paused: bool,
+    pub filter: String,
+    pub following: bool,
+}
+
+#[derive(Default)]
+pub struct ElevateForm {
+    pub open: bool,
+    pub connection_name: String,
+    pub credential: String,
+}
+
+#[derive(Default)]
+pub struct PassphraseForm {
+    pub open: bool,
+    pub locked_project: Option<(PathBuf, ProjectFile)>,
+    pub passphrase: String,
+    pub confirm: String,
+    pub error: Option<String>,
 }
 
 #[derive(Default)]
@@ -57,6 +233,498 @@ pub struct RemoteFolderPicker {
     pub roots: Vec<String>,
     pub cache: HashMap<String, Vec<TreeEntry>>,
     pub expanded: HashSet<String>,
+    /// Directory typed into the address bar, listed instead of the roots.
+    pub location: Option<String>,
+    pub location_input: String,
+    /// Lists files as well, so the picker can open a remote file.
+    pub show_files: bool,
+    pub selected_is_file: bool,
+    /// Name typed for a new folder in the selected directory.
+    pub new_folder: Option<String>,
+}
+
+/// The explorer's address bar, which lists a typed remote directory without
+/// expanding the tree down to it.
+#[derive(Default)]
+pub struct AddressBar {
+    pub connection_name: String,
+    pub input: String,
+    /// Directory being browsed, shown as breadcrumbs and listed under the bar.
+    pub path: Option<String>,
+}
+
+#[derive(Default)]
+pub struct PathCompletions {
+    pub entries: HashMap<(String, String), Vec<TreeEntry>>,
+    pub requested: HashSet<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct ServicesPanel {
+    pub connection_name: String,
+    pub entries: Vec<ServiceEntry>,
+    pub filter: String,
+    pub loading: bool,
+    pub logs_for: Option<String>,
+    pub logs: String,
+}
+
+pub struct PropertiesDialog {
+    /// Connection the entry lives on, or `None` for a local path.
+    pub connection_name: Option<String>,
+    pub path: String,
+    pub stat: Option<EntryStat>,
+    pub error: Option<String>,
+    /// Permission bits being edited, starting from the entry's current mode.
+    pub mode: Option<u32>,
+    pub applying: bool,
+}
+
+/// The add form of the forwarded ports panel.
+#[derive(Default)]
+pub struct ForwardForm {
+    pub connection_name: String,
+    pub direction: ForwardDirection,
+    pub listen_port: String,
+    pub host: String,
+    pub port: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+#[derive(Default)]
+pub struct ProcessesPanel {
+    pub connection_name: String,
+    pub entries: Vec<ProcessEntry>,
+    pub filter: String,
+    pub sort: ProcessSort,
+    pub loading: bool,
+    pub refreshed_at: Option<Instant>,
+    pub watch: Option<ProcessWatch>,
+}
+
+pub struct ProcessWatch {
+    /// Request id of the `watch_process` call.
+    pub id: Uuid,
+    pub pid: u32,
+    pub name: String,
+    /// Latest update, `None` once the process has exited.
+    pub process: Option<ProcessEntry>,
+}
+
+#[derive(Default)]
+pub struct SearchPanel {
+    pub connection_name: String,
+    pub root: String,
+    pub query: SearchQuery,
+    pub replace_mode: bool,
+    pub replacement: String,
+    pub searching: bool,
+    /// Query and replacement the results were produced with. Replacing is offered
+    /// only while the inputs still match what was previewed.
+    pub searched: Option<(SearchQuery, Option<String>)>,
+    pub results: Vec<SearchFileMatches>,
+    pub truncated: bool,
+    /// `(path, line, start)` of the matches chosen for replacement.
+    pub selected: HashSet<(String, usize, usize)>,
+    pub replacing: bool,
+    pub summary: Vec<ReplacedFile>,
+}
+
+/// The "Search in Workspace" panel, which runs one query over every project
+/// folder: local ones on a background thread, remote ones through their server.
+#[derive(Default)]
+pub struct WorkspaceSearchPanel {
+    pub query: SearchQuery,
+    /// Identifies the current search; results of earlier ones are dropped.
+    pub id: Uuid,
+    pub groups: Vec<SearchGroup>,
+    /// Stops the local search thread.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Results of local folders by group index.
+    pub local_results: Option<mpsc::Receiver<(usize, SearchOutcome)>>,
+}
+
+/// Matches of a folder and whether they were truncated, or why it failed.
+pub type SearchOutcome = Result<(Vec<SearchFileMatches>, bool), String>;
+
+/// Results of one project folder.
+pub struct SearchGroup {
+    pub folder: ProjectFolder,
+    pub state: SearchGroupState,
+}
+
+pub enum SearchGroupState {
+    Searching,
+    Done {
+        files: Vec<SearchFileMatches>,
+        truncated: bool,
+    },
+    Failed(String),
+    Cancelled,
+}
+
+/// What a task tracks, which decides whether it can be cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    /// A finished event, such as a connection's tools being detected.
+    Note,
+    Connect,
+    Transfer,
+    Copy,
+    Search,
+    Replace,
+    Save,
+    Diagnostics,
+    Pipeline,
+    Run,
+}
+
+impl TaskKind {
+    pub fn cancellable(self) -> bool {
+        matches!(
+            self,
+            TaskKind::Transfer | TaskKind::Copy | TaskKind::Search | TaskKind::Pipeline | TaskKind::Run
+        )
+    }
+}
+
+/// Entry of the Tasks tab. `at` is the Unix time it was created.
+pub struct Task {
+    pub id: Uuid,
+    pub kind: TaskKind,
+    pub title: String,
+    pub status: TaskStatus,
+    /// Fraction done, when the operation knows its size.
+    pub progress: Option<f32>,
+    /// Completed and total steps of a task made of several operations.
+    pub steps: Option<(usize, usize)>,
+    pub detail: Option<String>,
+    pub at: u64,
+    pub started: Instant,
+    pub finished: Option<Instant>,
+}
+
+pub struct FileViewer {
+    pub path: String,
+    /// `None` for local files.
+    pub connection_name: Option<String>,
+    pub content: ViewerContent,
+}
+
+pub enum ViewerContent {
+    Text { content: String, hash: Option<String> },
+    /// The first bytes of a binary file of `size` bytes.
+    Binary { size: u64, preview: Vec<u8> },
+    /// One page of a text file too large to read whole, starting at byte `offset`.
+    Paged {
+        size: u64,
+        offset: u64,
+        text: String,
+        loading: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct TaskForm {
+    pub editing: Option<String>,
+    pub name: String,
+    pub connection_name: String,
+    pub command: String,
+    pub cwd: String,
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct RunConfigForm {
+    pub editing: Option<String>,
+    pub name: String,
+    pub connection_name: String,
+    pub command: String,
+    pub cwd: String,
+    pub env: Vec<EnvVar>,
+}
+
+/// A run configuration started from the toolbar, tracked as the task with the id
+/// of its `run_command` request until the command finishes.
+pub struct ActiveRun {
+    pub id: Uuid,
+    pub name: String,
+    pub connection_name: String,
+}
+
+/// A language server running on a connection for the files under `root`, known by
+/// the id of its `start_language_server` request.
+pub struct LanguageClient {
+    pub id: Uuid,
+    pub connection_name: String,
+    pub root: String,
+    pub spec: &'static LanguageServerSpec,
+    /// Set once the server answered `initialize`; documents are opened after that.
+    pub ready: bool,
+    /// Why the server could not start or stopped; it is not started again until
+    /// the connection reconnects.
+    pub failed: Option<String>,
+    pub next_seq: u64,
+    pub incoming: InOrder<String>,
+    pub next_request: i64,
+    pub requests: HashMap<i64, LspRequest>,
+    /// Open documents with the version and text last sent.
+    pub documents: HashMap<String, (i32, String)>,
+}
+
+pub enum LspRequest {
+    Initialize,
+    Hover { path: String, index: usize },
+    Definition,
+}
+
+/// Position under the pointer in a remote editor and what the language server
+/// says about it.
+pub struct LspHover {
+    pub connection_name: String,
+    pub path: String,
+    pub index: usize,
+    pub since: Instant,
+    pub requested: bool,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineStepStatus {
+    Waiting,
+    Running,
+    Succeeded,
+    Failed(String),
+    /// Not run because a task it depends on failed or was skipped.
+    Skipped,
+}
+
+pub struct PipelineStep {
+    pub task: ProjectTask,
+    pub stage: usize,
+    pub status: PipelineStepStatus,
+    pub started: Option<Instant>,
+    pub elapsed: Option<Duration>,
+    pub output: String,
+}
+
+/// One run of a task and the tasks it depends on, shown in the Tasks tab.
+pub struct PipelineRun {
+    pub id: Uuid,
+    pub target: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+impl PipelineRun {
+    pub fn finished(&self) -> bool {
+        self.steps.iter().all(|step| {
+            !matches!(step.status, PipelineStepStatus::Waiting | PipelineStepStatus::Running)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    pub focus: bool,
+    pub selected: usize,
+}
+
+#[derive(Default)]
+pub struct QuickOpen {
+    pub open: bool,
+    pub query: String,
+    pub focus: bool,
+    pub selected: usize,
+    pub matches: Vec<QuickOpenMatch>,
+    /// Query `matches` were computed for; `None` forces a new match.
+    pub matched_query: Option<String>,
+}
+
+/// Search over the command history of a terminal.
+#[derive(Default)]
+pub struct HistorySearch {
+    pub terminal_id: Uuid,
+    pub query: String,
+    pub focus: bool,
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuickOpenMatch {
+    pub folder_index: usize,
+    /// Path relative to the folder root.
+    pub path: String,
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+/// Files of one project folder as last indexed for quick open.
+#[derive(Default)]
+pub struct FileIndex {
+    pub paths: Vec<String>,
+    pub truncated: bool,
+    pub loading: bool,
+}
+
+/// OS notification waiting for the next frame, which has the egui context.
+pub struct CommandNotice {
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Banner {
+    pub connection_name: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct NewEntryForm {
+    pub folder: Option<ProjectFolder>,
+    pub parent: String,
+    pub is_dir: bool,
+    pub name: String,
+}
+
+#[derive(Default)]
+pub struct GitCommitForm {
+    /// Folder whose repository is committed; the dialog is open while set.
+    pub folder: Option<ProjectFolder>,
+    pub branch: Option<String>,
+    /// Changed files, relative to the repository root, and whether to commit them.
+    pub files: Vec<(GitFileStatus, bool)>,
+    pub message: String,
+    /// File whose diff is shown, and the diff once loaded.
+    pub diff_file: Option<String>,
+    pub diff: Option<String>,
+    pub committing: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct RenameForm {
+    pub connection_name: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// A destructive remote operation with what is needed to reverse it.
+#[derive(Debug, Clone)]
+pub enum JournalOperation {
+    Delete {
+        path: String,
+        trash_path: String,
+        hash: Option<String>,
+    },
+    Rename {
+        from: String,
+        to: String,
+        hash: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub connection_name: String,
+    pub operation: JournalOperation,
+}
+
+#[derive(Default)]
+pub struct BroadcastForm {
+    pub connection_name: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct NotebookPanel {
+    pub connection_name: String,
+    pub kernel_name: String,
+    pub kernel: Option<KernelEntry>,
+    pub starting: bool,
+    pub cells: Vec<NotebookCell>,
+}
+
+pub struct NotebookCell {
+    pub id: Uuid,
+    pub code: String,
+    pub output: String,
+    pub attachments: Vec<OutputAttachment>,
+    pub error: Option<String>,
+    pub execution_count: Option<u32>,
+    pub running: bool,
+}
+
+impl NotebookCell {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            code: String::new(),
+            output: String::new(),
+            attachments: Vec::new(),
+            error: None,
+            execution_count: None,
+            running: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+pub struct Transfer {
+    pub id: Uuid,
+    pub connection_name: String,
+    pub direction: TransferDirection,
+    pub local_path: String,
+    pub remote_path: String,
+    pub total_size: Option<u64>,
+    pub transferred: u64,
+    pub next_offset: u64,
+    pub in_flight: usize,
+    pub tuner: ChunkTuner,
+    /// Chunks confirmed by their checksums; the transfer resumes after them when
+    /// its connection comes back.
+    pub chunks: ChunkLog,
+    pub started: Instant,
+    /// `Queued` while the transfer waits for its connection.
+    pub status: TaskStatus,
+}
+
+/// A remote file or folder picked with Copy, waiting to be pasted.
+#[derive(Debug, Clone)]
+pub struct CopySource {
+    pub connection_name: String,
+    pub path: String,
+    pub is_dir: bool,
+}
+
+pub struct CopyTask {
+    pub id: Uuid,
+    pub source: CopySource,
+    /// Destination connection, or `None` for the local file system.
+    pub target_connection: Option<String>,
+    pub target_path: String,
+    pub total_size: Option<u64>,
+    pub transferred: u64,
+    pub next_offset: u64,
+    pub in_flight: usize,
+    pub tuner: ChunkTuner,
+    pub started: Option<Instant>,
+    pub status: TaskStatus,
 }
 
 #[derive(Debug, Clone)]
@@ -65,18 +733,166 @@ pub enum PendingAction {
         path: String,
         title: String,
         connection_name: String,
+        restore_cursor: Option<usize>,
+        preview: bool,
+    },
+    ViewRemoteFile {
+        path: String,
+    },
+    LoadViewerPage {
+        path: String,
+        offset: u64,
+    },
+    LoadMedia {
+        path: String,
+        connection_name: String,
+        kind: MediaKind,
+        preview: bool,
+        data: Vec<u8>,
+    },
+    LoadAttachment {
+        path: String,
+        mime: String,
+        data: Vec<u8>,
     },
     SaveRemoteFile {
         path: String,
+        content: String,
+        task: Option<Uuid>,
+    },
+    /// Group `group` of workspace search `search`.
+    WorkspaceSearch {
+        search: Uuid,
+        group: usize,
+    },
+    /// `content` is the text sent; `save` writes the editor once answered, as a
+    /// step of `task`.
+    FormatFile {
+        path: String,
+        content: String,
+        save: bool,
+        task: Option<Uuid>,
+    },
+    CreateEntry {
+        folder: ProjectFolder,
+        parent: String,
+    },
+    JournaledOperation,
+    UndoOperation {
+        entry: JournalEntry,
     },
     LoadRemoteDirectory {
         path: String,
     },
+    ReadAheadDirectory {
+        path: String,
+        opened: String,
+    },
+    ReadAhead {
+        path: String,
+        modified: Option<u64>,
+    },
     LoadPickerRoots,
     LoadPickerDirectory {
         path: String,
     },
+    CreatePickerFolder {
+        parent: String,
+    },
+    LoadPathCompletions {
+        connection_name: String,
+        parent: String,
+    },
     RunTerminal {
         terminal_id: Uuid,
+        block_id: Uuid,
+        started: Instant,
+    },
+    LoadServices,
+    LoadProcesses,
+    KillProcess,
+    ControlService,
+    LoadServiceLogs,
+    DetectTools,
+    CollectDiagnostics,
+    SessionAccess,
+    LoadTerminalContainers {
+        connection_name: String,
+    },
+    FileLock {
+        acquiring: bool,
+    },
+    LoadDiff {
+        path: String,
+    },
+    LoadContainerLogs {
+        terminal_id: Uuid,
+        block_id: Uuid,
+    },
+    SendBroadcast,
+    StartKernel,
+    ExecuteCell {
+        cell_id: Uuid,
+    },
+    KernelControl,
+    TransferChunk {
+        transfer_id: Uuid,
+        offset: u64,
+        length: u64,
+        sent_at: Instant,
+        /// Checksum of an uploaded chunk; downloads take theirs from the response.
+        checksum: Option<String>,
+    },
+    /// Server-side checksums of a transfer's last verified chunks, compared with
+    /// the local ones taken when the request was sent.
+    ResumeTransfer {
+        transfer_id: Uuid,
+        local: Vec<Option<String>>,
+        local_size: Option<u64>,
+    },
+    StatEntry {
+        path: String,
+    },
+    SetPermissions {
+        path: String,
+    },
+    SearchFiles,
+    IndexTree,
+    ReplaceInFiles,
+    ReloadRemoteFile {
+        path: String,
+    },
+    CreateCopyTarget {
+        copy_id: Uuid,
+    },
+    ListCopySource {
+        copy_id: Uuid,
+    },
+    ReadCopyChunk {
+        copy_id: Uuid,
+        offset: u64,
+        length: u64,
+        sent_at: Instant,
+    },
+    WriteCopyChunk {
+        copy_id: Uuid,
+        sent_at: Instant,
+    },
+    RunPipelineStep {
+        run_id: Uuid,
+        step: usize,
+    },
+    Run,
+    GitStatus {
+        root: String,
+    },
+    GitDiff {
+        file: String,
+    },
+    GitCommit {
+        root: String,
+    },
+    GitGutter {
+        path: String,
     },
 }
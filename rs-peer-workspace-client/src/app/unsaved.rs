@@ -0,0 +1,172 @@
+use eframe::egui;
+use rs_peer_workspace_shared::project::{EditorSource, EditorTab};
+
+use super::state::WorkspaceApp;
+use super::types::{EditorPane, GuardedAction, UnsavedPrompt};
+
+impl WorkspaceApp {
+    /// Runs `action` right away, or asks first when it would discard unsaved edits.
+    pub fn guard_unsaved(&mut self, action: GuardedAction) {
+        if self.unsaved_editors(&action).is_empty() {
+            self.perform_guarded(action);
+        } else {
+            self.unsaved_prompt = Some(UnsavedPrompt {
+                action,
+                saving: false,
+            });
+        }
+    }
+
+    /// Indices of the dirty editors that `action` would discard.
+    pub fn unsaved_editors(&self, action: &GuardedAction) -> Vec<usize> {
+        self.open_files
+            .iter()
+            .enumerate()
+            .filter(|(_, tab)| tab.dirty)
+            .filter(|(_, tab)| match action {
+                GuardedAction::CloseTabs(paths) => paths.contains(&tab.path),
+                _ => true,
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub fn move_editor(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.open_files.len() || to >= self.open_files.len() {
+            return;
+        }
+        let shown = [EditorPane::Primary, EditorPane::Secondary].map(|pane| {
+            self.pane_editor(pane)
+                .and_then(|idx| self.open_files.get(idx))
+                .map(|tab| tab.path.clone())
+        });
+        let tab = self.open_files.remove(from);
+        self.open_files.insert(to, tab);
+        for (pane, path) in [EditorPane::Primary, EditorPane::Secondary].into_iter().zip(shown) {
+            *self.pane_editor_mut(pane) =
+                path.and_then(|path| self.open_files.iter().position(|tab| tab.path == path));
+        }
+    }
+
+    pub fn close_editor(&mut self, path: &str) {
+        let Some(idx) = self.open_files.iter().position(|tab| tab.path == path) else {
+            return;
+        };
+        let tab = self.open_files.remove(idx);
+        self.release_editor_lock(&tab);
+        let remaining = self.open_files.len();
+        for pane in [EditorPane::Primary, EditorPane::Secondary] {
+            let selected = self.pane_editor_mut(pane);
+            *selected = match *selected {
+                _ if remaining == 0 => None,
+                Some(selected) if selected > idx => Some(selected - 1),
+                Some(selected) => Some(selected.min(remaining - 1)),
+                None => None,
+            };
+        }
+    }
+
+    /// Gives up the lock this session holds on the file of a closing editor.
+    pub fn release_editor_lock(&mut self, tab: &EditorTab) {
+        if let EditorSource::Remote { connection_name } = &tab.source
+            && self
+                .file_locks
+                .get(&(connection_name.clone(), tab.path.clone()))
+                .is_some_and(|lock| lock.mine)
+        {
+            self.send_lock_rpc(connection_name, &tab.path, false);
+        }
+    }
+
+    fn perform_guarded(&mut self, action: GuardedAction) {
+        match action {
+            GuardedAction::CloseTabs(paths) => {
+                for path in paths {
+                    self.close_editor(&path);
+                }
+            }
+            GuardedAction::ResetProject => self.reset_project(),
+            GuardedAction::OpenProject(path) => self.open_project(path),
+            GuardedAction::Exit => self.exit_confirmed = true,
+        }
+    }
+
+    pub fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if self.exit_confirmed {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+        if ctx.input(|input| input.viewport().close_requested())
+            && !self.unsaved_editors(&GuardedAction::Exit).is_empty()
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.guard_unsaved(GuardedAction::Exit);
+        }
+    }
+
+    pub fn draw_unsaved_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = &self.unsaved_prompt else {
+            return;
+        };
+        let action = prompt.action.clone();
+        let dirty = self.unsaved_editors(&action);
+        if prompt.saving && dirty.is_empty() {
+            self.unsaved_prompt = None;
+            self.perform_guarded(action);
+            ctx.request_repaint();
+            return;
+        }
+
+        let saving = prompt.saving;
+        let mut choice = None;
+        egui::Window::new(self.i18n.tr("unsaved-title"))
+            .id(egui::Id::new("unsaved-changes"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.tr_args("unsaved-message", &[("count", dirty.len().into())]));
+                for idx in &dirty {
+                    if let Some(tab) = self.open_files.get(*idx) {
+                        ui.monospace(&tab.path);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(!saving, |ui| {
+                        if ui.button(self.i18n.tr("unsaved-save")).clicked() {
+                            choice = Some(true);
+                        }
+                        if ui.button(self.i18n.tr("unsaved-discard")).clicked() {
+                            choice = Some(false);
+                        }
+                    });
+                    if ui.button(self.i18n.tr("cancel")).clicked() {
+                        self.unsaved_prompt = None;
+                    }
+                    if saving {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        match choice {
+            Some(true) => {
+                self.save_editors(dirty);
+                // Local saves finish immediately; one that is still dirty failed.
+                let local_failed = self
+                    .unsaved_editors(&action)
+                    .into_iter()
+                    .any(|idx| matches!(self.open_files[idx].source, EditorSource::Local));
+                if let Some(prompt) = &mut self.unsaved_prompt {
+                    prompt.saving = !local_failed;
+                }
+                ctx.request_repaint();
+            }
+            Some(false) => {
+                self.unsaved_prompt = None;
+                self.perform_guarded(action);
+            }
+            None => {}
+        }
+    }
+}
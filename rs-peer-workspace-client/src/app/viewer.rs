@@ -0,0 +1,262 @@
+use std::path::Path;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::content::{hex_dump, read_range, FileData};
+use rs_peer_workspace_shared::project::{display_name_for_path, EditorSource, EditorTab};
+use uuid::Uuid;
+
+use super::state::WorkspaceApp;
+use super::transfers::format_bytes;
+use super::types::{FileViewer, PendingAction, ViewerContent};
+
+/// Bytes shown per page of a file too large to open whole.
+const PAGE_LEN: u64 = 256 * 1024;
+
+impl From<FileData> for ViewerContent {
+    fn from(data: FileData) -> Self {
+        match data {
+            FileData::Text(content) => ViewerContent::Text {
+                content,
+                hash: None,
+            },
+            FileData::Binary { size, preview } => ViewerContent::Binary { size, preview },
+            FileData::TooLarge { size } => ViewerContent::Paged {
+                size,
+                offset: 0,
+                text: String::new(),
+                loading: false,
+            },
+        }
+    }
+}
+
+impl WorkspaceApp {
+    pub fn open_viewer(&mut self, path: &str, connection_name: Option<String>, content: ViewerContent) {
+        let paged = matches!(content, ViewerContent::Paged { .. });
+        self.file_viewer = Some(FileViewer {
+            path: path.to_string(),
+            connection_name,
+            content,
+        });
+        if paged {
+            self.load_viewer_page(0);
+        }
+    }
+
+    /// Reads the page of a paged viewer starting at `offset`, directly for local
+    /// files and with `read_file_chunk` for remote ones.
+    fn load_viewer_page(&mut self, offset: u64) {
+        let Some(viewer) = &mut self.file_viewer else {
+            return;
+        };
+        let ViewerContent::Paged { loading, .. } = &mut viewer.content else {
+            return;
+        };
+        let path = viewer.path.clone();
+        match viewer.connection_name.clone() {
+            Some(connection_name) => {
+                *loading = true;
+                let request_id = Uuid::new_v4();
+                self.pending.insert(
+                    request_id,
+                    PendingAction::LoadViewerPage {
+                        path: path.clone(),
+                        offset,
+                    },
+                );
+                self.send_rpc(
+                    &connection_name,
+                    RpcRequest {
+                        request_id,
+                        action: RpcAction::ReadFileChunk {
+                            path,
+                            offset,
+                            length: PAGE_LEN,
+                        },
+                    },
+                );
+            }
+            None => {
+                let result = match read_range(Path::new(&path), offset, PAGE_LEN) {
+                    Ok((data, total_size)) => RpcResult::FileChunk {
+                        path: path.clone(),
+                        offset,
+                        data,
+                        total_size,
+                        checksum: None,
+                    },
+                    Err(err) => RpcResult::Error {
+                        message: err.to_string(),
+                    },
+                };
+                self.show_viewer_page(&path, offset, result);
+            }
+        }
+    }
+
+    pub fn show_viewer_page(&mut self, path: &str, offset: u64, result: RpcResult) {
+        let Some(viewer) = self.file_viewer.as_mut().filter(|viewer| viewer.path == path) else {
+            return;
+        };
+        let ViewerContent::Paged {
+            size,
+            offset: shown,
+            text,
+            loading,
+        } = &mut viewer.content
+        else {
+            return;
+        };
+        *loading = false;
+        match result {
+            RpcResult::FileChunk {
+                data, total_size, ..
+            } => {
+                *size = total_size;
+                *shown = offset;
+                *text = String::from_utf8_lossy(&data).into_owned();
+            }
+            RpcResult::Error { message } => self
+                .output
+                .error(format!("Failed to read {path}: {message}")),
+            _ => {}
+        }
+    }
+
+    /// Read-only view of a file that is not opened in the editor: a hex dump when it
+    /// is binary, otherwise its text with a button to edit it anyway.
+    pub fn draw_file_viewer(&mut self, ctx: &egui::Context) {
+        let Some(viewer) = &self.file_viewer else {
+            return;
+        };
+
+        let mut open = true;
+        let mut edit = false;
+        let mut page = None;
+        egui::Window::new(display_name_for_path(&viewer.path))
+            .id(egui::Id::new("file-viewer"))
+            .open(&mut open)
+            .default_size([640.0, 420.0])
+            .show(ctx, |ui| {
+                ui.weak(&viewer.path);
+                match &viewer.content {
+                    ViewerContent::Text { content, .. } => {
+                        ui.horizontal(|ui| {
+                            ui.label(self.i18n.tr("viewer-not-listed"));
+                            if ui.button(self.i18n.tr("viewer-open-in-editor")).clicked() {
+                                edit = true;
+                            }
+                        });
+                        ui.separator();
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(egui::Label::new(egui::RichText::new(content).monospace()).extend());
+                        });
+                    }
+                    ViewerContent::Binary { size, preview } => {
+                        ui.label(self.i18n.tr_args(
+                            "viewer-binary",
+                            &[
+                                ("shown", preview.len().into()),
+                                ("size", (*size as f64).into()),
+                            ],
+                        ));
+                        ui.separator();
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(
+                                egui::Label::new(egui::RichText::new(hex_dump(preview)).monospace())
+                                    .extend(),
+                            );
+                        });
+                    }
+                    ViewerContent::Paged {
+                        size,
+                        offset,
+                        text,
+                        loading,
+                    } => {
+                        let end = (*offset + text.len() as u64).min(*size);
+                        ui.label(self.i18n.tr_args(
+                            "viewer-too-large",
+                            &[("size", format_bytes(*size).into())],
+                        ));
+                        ui.horizontal(|ui| {
+                            ui.add_enabled_ui(!*loading, |ui| {
+                                let last = size.saturating_sub(PAGE_LEN);
+                                let pages = [
+                                    ("viewer-head", 0, *offset > 0),
+                                    (
+                                        "viewer-previous",
+                                        offset.saturating_sub(PAGE_LEN),
+                                        *offset > 0,
+                                    ),
+                                    ("viewer-next", (*offset + PAGE_LEN).min(last), end < *size),
+                                    ("viewer-tail", last, end < *size),
+                                ];
+                                for (label, target, enabled) in pages {
+                                    if ui
+                                        .add_enabled(enabled, egui::Button::new(self.i18n.tr(label)))
+                                        .clicked()
+                                    {
+                                        page = Some(target);
+                                    }
+                                }
+                            });
+                            ui.separator();
+                            ui.label(self.i18n.tr_args(
+                                "viewer-range",
+                                &[
+                                    ("from", format_bytes(*offset).into()),
+                                    ("to", format_bytes(end).into()),
+                                ],
+                            ));
+                            if *loading {
+                                ui.spinner();
+                            }
+                        });
+                        ui.separator();
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(egui::Label::new(egui::RichText::new(text.as_str()).monospace()).extend());
+                        });
+                    }
+                }
+            });
+
+        if let Some(offset) = page {
+            self.load_viewer_page(offset);
+        } else if edit {
+            let viewer = self.file_viewer.take();
+            if let Some(FileViewer {
+                path,
+                connection_name,
+                content: ViewerContent::Text { content, hash },
+            }) = viewer
+            {
+                let source = match &connection_name {
+                    Some(connection_name) => EditorSource::Remote {
+                        connection_name: connection_name.clone(),
+                    },
+                    None => EditorSource::Local,
+                };
+                self.open_editor(EditorTab {
+                    title: display_name_for_path(&path),
+                    path: path.clone(),
+                    source,
+                    base: connection_name.is_some().then(|| content.clone()),
+                    content,
+                    dirty: false,
+                    remote_hash: hash,
+                    cursor: 0,
+                    restore_cursor: false,
+                    preview: false,
+                    media: None,
+                });
+                if let Some(connection_name) = connection_name {
+                    self.refresh_file_lock(&connection_name, &path);
+                }
+            }
+        } else if !open {
+            self.file_viewer = None;
+        }
+    }
+}
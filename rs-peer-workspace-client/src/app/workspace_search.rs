@@ -0,0 +1,285 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use eframe::egui;
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::project::FolderSource;
+use rs_peer_workspace_shared::search::search_tree;
+use uuid::Uuid;
+
+use super::search::highlighted;
+use super::state::WorkspaceApp;
+use super::types::{BottomTab, PendingAction, SearchGroup, SearchGroupState, SearchOutcome};
+
+impl WorkspaceApp {
+    pub fn open_workspace_search(&mut self) {
+        self.show_bottom_panel = true;
+        self.active_bottom_tab = BottomTab::WorkspaceSearch;
+    }
+
+    /// Sends the query to every project folder, replacing a search that is still
+    /// running.
+    fn run_workspace_search(&mut self) {
+        if self.workspace_search.query.pattern.is_empty() {
+            return;
+        }
+        self.cancel_workspace_search();
+        let id = Uuid::new_v4();
+        let query = self.workspace_search.query.clone();
+        let mut groups = Vec::new();
+        let mut local = Vec::new();
+        for folder in self.project.folders.clone() {
+            let group = groups.len();
+            let state = match &folder.source {
+                FolderSource::Local { path } => {
+                    local.push((group, path.clone()));
+                    SearchGroupState::Searching
+                }
+                FolderSource::Remote { connection_name, path } => {
+                    if self
+                        .connections
+                        .get(connection_name)
+                        .is_some_and(|connection| connection.connected)
+                    {
+                        let request_id = Uuid::new_v4();
+                        self.pending
+                            .insert(request_id, PendingAction::WorkspaceSearch { search: id, group });
+                        self.send_rpc(
+                            connection_name,
+                            RpcRequest {
+                                request_id,
+                                action: RpcAction::SearchFiles {
+                                    root: path.clone(),
+                                    query: query.clone(),
+                                    replacement: None,
+                                },
+                            },
+                        );
+                        SearchGroupState::Searching
+                    } else {
+                        SearchGroupState::Failed(format!("{connection_name} is not connected"))
+                    }
+                }
+            };
+            groups.push(SearchGroup { folder, state });
+        }
+
+        let search = &mut self.workspace_search;
+        search.id = id;
+        search.groups = groups;
+        if !local.is_empty() {
+            let cancel = Arc::new(AtomicBool::new(false));
+            let (results, receiver) = mpsc::channel();
+            let flag = cancel.clone();
+            std::thread::spawn(move || {
+                for (group, root) in local {
+                    let result = search_tree(&root, &query, None, Some(&flag)).map_err(|err| err.to_string());
+                    if flag.load(Ordering::Relaxed) || results.send((group, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+            search.cancel = Some(cancel);
+            search.local_results = Some(receiver);
+        }
+    }
+
+    /// Stops the local search and drops the remote results still to come.
+    fn cancel_workspace_search(&mut self) {
+        let search = &mut self.workspace_search;
+        if let Some(cancel) = search.cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        search.local_results = None;
+        search.id = Uuid::new_v4();
+        for group in &mut search.groups {
+            if matches!(group.state, SearchGroupState::Searching) {
+                group.state = SearchGroupState::Cancelled;
+            }
+        }
+    }
+
+    pub fn workspace_search_received(&mut self, search: Uuid, group: usize, result: RpcResult) {
+        if search != self.workspace_search.id {
+            return;
+        }
+        let result = match result {
+            RpcResult::SearchResults { files, truncated } => Ok((files, truncated)),
+            RpcResult::Error { message } => Err(message),
+            _ => return,
+        };
+        self.finish_search_group(group, result);
+    }
+
+    fn finish_search_group(&mut self, group: usize, result: SearchOutcome) {
+        let Some(group) = self.workspace_search.groups.get_mut(group) else {
+            return;
+        };
+        if !matches!(group.state, SearchGroupState::Searching) {
+            return;
+        }
+        group.state = match result {
+            Ok((files, truncated)) => SearchGroupState::Done { files, truncated },
+            Err(message) => SearchGroupState::Failed(message),
+        };
+    }
+
+    fn poll_workspace_search(&mut self) {
+        let Some(receiver) = &self.workspace_search.local_results else {
+            return;
+        };
+        let mut received = Vec::new();
+        let mut finished = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(result) => received.push(result),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+        for (group, result) in received {
+            self.finish_search_group(group, result);
+        }
+        if finished {
+            self.workspace_search.local_results = None;
+            self.workspace_search.cancel = None;
+        }
+    }
+
+    fn workspace_searching(&self) -> bool {
+        self.workspace_search
+            .groups
+            .iter()
+            .any(|group| matches!(group.state, SearchGroupState::Searching))
+    }
+
+    pub fn draw_workspace_search(&mut self, ui: &mut egui::Ui) {
+        self.poll_workspace_search();
+        let searching = self.workspace_searching();
+        if searching {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+
+        let mut run = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            let search = &mut self.workspace_search;
+            let label = ui.label(self.i18n.tr("search-pattern"));
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut search.query.pattern).desired_width(260.0))
+                .labelled_by(label.id);
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                run = true;
+            }
+            ui.checkbox(&mut search.query.regex, self.i18n.tr("search-regex"));
+            ui.checkbox(&mut search.query.case_sensitive, self.i18n.tr("search-match-case"));
+            if ui.button(self.i18n.tr("search-run")).clicked() {
+                run = true;
+            }
+            if searching {
+                if ui.button(self.i18n.tr("cancel")).clicked() {
+                    cancel = true;
+                }
+                ui.spinner();
+            }
+        });
+        ui.separator();
+
+        let mut open = None;
+        egui::ScrollArea::vertical()
+            .id_salt("workspace-search-results")
+            .show(ui, |ui| {
+                let groups = &self.workspace_search.groups;
+                if groups.is_empty() {
+                    ui.label(self.i18n.tr("workspace-search-empty"));
+                    return;
+                }
+                let (matches, files) = groups
+                    .iter()
+                    .filter_map(|group| match &group.state {
+                        SearchGroupState::Done { files, .. } => Some(files),
+                        _ => None,
+                    })
+                    .flatten()
+                    .fold((0, 0), |(matches, files), file| (matches + file.matches.len(), files + 1));
+                ui.label(self.i18n.tr_args(
+                    "workspace-search-summary",
+                    &[
+                        ("matches", matches.into()),
+                        ("files", files.into()),
+                        ("folders", groups.len().into()),
+                    ],
+                ));
+                for (index, group) in groups.iter().enumerate() {
+                    let location = match &group.folder.source {
+                        FolderSource::Local { .. } => self.i18n.tr("workspace-search-local"),
+                        FolderSource::Remote { connection_name, .. } => connection_name.clone(),
+                    };
+                    let status = match &group.state {
+                        SearchGroupState::Searching => self.i18n.tr("workspace-search-searching"),
+                        SearchGroupState::Done { files, .. } => {
+                            let count: usize = files.iter().map(|file| file.matches.len()).sum();
+                            self.i18n
+                                .tr_args("workspace-search-matches", &[("count", count.into())])
+                        }
+                        SearchGroupState::Failed(_) => self.i18n.tr("workspace-search-failed"),
+                        SearchGroupState::Cancelled => self.i18n.tr("workspace-search-cancelled"),
+                    };
+                    egui::CollapsingHeader::new(format!("{} [{location}] - {status}", group.folder.name))
+                        .id_salt(("workspace-search-group", index))
+                        .default_open(true)
+                        .show(ui, |ui| match &group.state {
+                            SearchGroupState::Done { files, truncated } => {
+                                if *truncated {
+                                    ui.weak(self.i18n.tr("workspace-search-truncated"));
+                                }
+                                let root = match &group.folder.source {
+                                    FolderSource::Local { path } | FolderSource::Remote { path, .. } => path,
+                                };
+                                for file in files {
+                                    let relative = file
+                                        .path
+                                        .strip_prefix(root.as_str())
+                                        .map(|rest| rest.trim_start_matches(['/', '\\']))
+                                        .unwrap_or(&file.path);
+                                    egui::CollapsingHeader::new(format!("{relative} ({})", file.matches.len()))
+                                        .id_salt(("workspace-search-file", index, &file.path))
+                                        .default_open(true)
+                                        .show(ui, |ui| {
+                                            for found in &file.matches {
+                                                ui.horizontal(|ui| {
+                                                    if ui.link(format!("{}:", found.line)).clicked() {
+                                                        open = Some((index, file.path.clone()));
+                                                    }
+                                                    ui.label(highlighted(ui, found));
+                                                });
+                                            }
+                                        });
+                                }
+                            }
+                            SearchGroupState::Failed(message) => {
+                                ui.colored_label(ui.visuals().error_fg_color, message);
+                            }
+                            SearchGroupState::Searching | SearchGroupState::Cancelled => {}
+                        });
+                }
+            });
+
+        if let Some((index, path)) = open
+            && let Some(group) = self.workspace_search.groups.get(index)
+        {
+            let folder = group.folder.clone();
+            self.open_path(&folder, &path, false);
+        }
+        if cancel {
+            self.cancel_workspace_search();
+        }
+        if run {
+            self.run_workspace_search();
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use rs_peer_workspace_core::{spawn_connection, ConnectionCommand, ConnectionEvent, Workspace};
+use rs_peer_workspace_shared::app::{EnvVar, ListFilter, RpcAction, RpcRequest, RpcResult};
+use rs_peer_workspace_shared::content::hex_dump;
+use rs_peer_workspace_shared::project::{
+    default_connection_form_addr, IceServer, OutboundProxy, ProjectConnection,
+};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+#[derive(Args, Debug)]
+pub struct HeadlessArgs {
+    #[arg(long)]
+    project: Option<PathBuf>,
+    #[arg(long)]
+    connection: Option<String>,
+    #[arg(long, default_value_t = default_connection_form_addr())]
+    proxy_addr: String,
+    /// Proxy account to log in with; `--proxy-password` is then its password.
+    #[arg(long, default_value = "")]
+    proxy_username: String,
+    #[arg(long, default_value = "")]
+    proxy_password: String,
+    #[arg(long)]
+    server_name: Option<String>,
+    #[arg(long, default_value = "")]
+    server_password: String,
+    #[arg(long)]
+    relay_only: bool,
+    /// Refuse servers that do not end-to-end encrypt relayed traffic.
+    #[arg(long)]
+    require_e2e: bool,
+    #[arg(long)]
+    server_fingerprint: Option<String>,
+    /// `http://host:port` or `socks5://host:port`, optionally with `user:password@`.
+    #[arg(long)]
+    outbound_proxy: Option<String>,
+    /// `stun:host:port` or `turn:user:password@host:port`, used for P2P instead of the
+    /// proxy's TURN server. Repeat for several servers.
+    #[arg(long = "ice-server")]
+    ice_servers: Vec<String>,
+    #[arg(long, short = 'c')]
+    command: Option<String>,
+    #[arg(long, default_value_t = 300)]
+    timeout_secs: u64,
+}
+
+struct Session {
+    command_tx: UnboundedSender<ConnectionCommand>,
+    event_rx: Receiver<ConnectionEvent>,
+    timeout: Duration,
+    /// Variables of the project connection, set for every command.
+    env: Vec<EnvVar>,
+}
+
+pub fn run(args: HeadlessArgs) -> ExitCode {
+    match run_inner(args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_inner(args: HeadlessArgs) -> anyhow::Result<ExitCode> {
+    let connection = resolve_connection(&args)?;
+    let env = connection.env.clone();
+    let (event_tx, event_rx) = mpsc::channel();
+    let command_tx = spawn_connection(connection, event_tx);
+    let session = Session {
+        command_tx,
+        event_rx,
+        timeout: Duration::from_secs(args.timeout_secs),
+        env,
+    };
+    session.wait_connected()?;
+
+    let code = match &args.command {
+        Some(command) => {
+            let ok = session.execute(RpcAction::RunCommand {
+                command: command.clone(),
+                shell: None,
+                cwd: None,
+                env: session.env.clone(),
+            })?;
+            if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        }
+        None => {
+            session.repl()?;
+            ExitCode::SUCCESS
+        }
+    };
+    let _ = session.command_tx.send(ConnectionCommand::Disconnect);
+    Ok(code)
+}
+
+fn resolve_connection(args: &HeadlessArgs) -> anyhow::Result<ProjectConnection> {
+    let outbound_proxy = args
+        .outbound_proxy
+        .as_deref()
+        .map(OutboundProxy::parse)
+        .transpose()?;
+    let ice_servers = args
+        .ice_servers
+        .iter()
+        .map(|url| IceServer::parse(url))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if let Some(path) = &args.project {
+        let workspace = Workspace::open(path, None)?;
+        let name = args
+            .connection
+            .as_deref()
+            .or_else(|| workspace.connections().first().map(|connection| connection.name.as_str()))
+            .ok_or_else(|| anyhow::anyhow!("project has no connections"))?;
+        let mut connection = workspace
+            .connection(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("connection '{name}' not found in project"))?;
+        if outbound_proxy.is_some() {
+            connection.outbound_proxy = outbound_proxy;
+        }
+        if !ice_servers.is_empty() {
+            connection.ice_servers = ice_servers;
+        }
+        connection.require_e2e |= args.require_e2e;
+        return Ok(connection);
+    }
+
+    let server_name = args
+        .server_name
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("--server-name or --project is required"))?;
+    Ok(ProjectConnection {
+        name: server_name.clone(),
+        proxy_addr: args.proxy_addr.clone(),
+        proxy_username: args.proxy_username.clone(),
+        proxy_password: args.proxy_password.clone(),
+        server_name,
+        server_password: args.server_password.clone(),
+        prefer_p2p: !args.relay_only,
+        require_e2e: args.require_e2e,
+        credential_ref: None,
+        server_fingerprint: args.server_fingerprint.clone(),
+        terminal_profiles: Vec::new(),
+        outbound_proxy,
+        ice_servers,
+        env: Vec::new(),
+    })
+}
+
+impl Session {
+    fn wait_connected(&self) -> anyhow::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match self.next_event(deadline)? {
+                ConnectionEvent::Connected { .. } => return Ok(()),
+                ConnectionEvent::ServerIdentity { fingerprint, .. } => {
+                    eprintln!("server identity {fingerprint}");
+                }
+                ConnectionEvent::Status { message, .. } => eprintln!("{message}"),
+                ConnectionEvent::Error { message, .. } => anyhow::bail!(message),
+                ConnectionEvent::Closed { reason, .. } => anyhow::bail!("connection closed: {reason}"),
+                _ => {}
+            }
+        }
+    }
+
+    fn execute(&self, action: RpcAction) -> anyhow::Result<bool> {
+        let request_id = Uuid::new_v4();
+        self.command_tx
+            .send(ConnectionCommand::SendRpc(RpcRequest { request_id, action }))
+            .map_err(|_| anyhow::anyhow!("connection is closed"))?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match self.next_event(deadline)? {
+                ConnectionEvent::RpcResponse { response, .. } if response.request_id == request_id => {
+                    return Ok(print_result(response.result));
+                }
+                ConnectionEvent::Broadcast { message, .. } => eprintln!("broadcast: {message}"),
+                ConnectionEvent::Error { message, .. } => anyhow::bail!(message),
+                ConnectionEvent::Closed { reason, .. } => anyhow::bail!("connection closed: {reason}"),
+                _ => {}
+            }
+        }
+    }
+
+    fn repl(&self) -> anyhow::Result<()> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+        loop {
+            write!(stdout, "> ")?;
+            stdout.flush()?;
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let line = line.trim();
+            let action = match line.split_once(' ').unwrap_or((line, "")) {
+                ("", _) => continue,
+                (":quit" | ":exit", _) => return Ok(()),
+                (":help", _) => {
+                    println!(":ls <path>    list a remote directory");
+                    println!(":cat <path>   print a remote file");
+                    println!(":tail <path>  print the last lines of a remote file");
+                    println!(":quit         exit");
+                    println!("anything else runs as a shell command on the server");
+                    continue;
+                }
+                (":ls", path) => RpcAction::ListDirectory {
+                    path: path.trim().to_string(),
+                    filter: ListFilter::default(),
+                },
+                (":cat", path) => RpcAction::ReadFile {
+                    path: path.trim().to_string(),
+                    known_hash: None,
+                },
+                (":tail", path) => RpcAction::TailFile {
+                    path: path.trim().to_string(),
+                    follow: false,
+                },
+                _ => RpcAction::RunCommand {
+                    command: line.to_string(),
+                    shell: None,
+                    cwd: None,
+                    env: self.env.clone(),
+                },
+            };
+            self.execute(action)?;
+        }
+    }
+
+    fn next_event(&self, deadline: Instant) -> anyhow::Result<ConnectionEvent> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match self.event_rx.recv_timeout(remaining) {
+            Ok(event) => Ok(event),
+            Err(RecvTimeoutError::Timeout) => anyhow::bail!("timed out waiting for the server"),
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("connection task exited"),
+        }
+    }
+}
+
+fn print_result(result: RpcResult) -> bool {
+    match result {
+        RpcResult::CommandOutput {
+            output,
+            attachments,
+            ..
+        } => {
+            print!("{output}");
+            if !output.ends_with('\n') {
+                println!();
+            }
+            if !attachments.is_empty() {
+                eprintln!("({} attachment(s) not shown)", attachments.len());
+            }
+        }
+        RpcResult::DirectoryEntries { entries, .. } => {
+            for entry in entries {
+                if entry.is_dir {
+                    println!("{}/", entry.name);
+                } else {
+                    println!("{}", entry.name);
+                }
+            }
+        }
+        RpcResult::FileContent { content, .. } => print!("{content}"),
+        RpcResult::BinaryContent { size, preview, .. } => {
+            print!("{}", hex_dump(&preview));
+            if size > preview.len() as u64 {
+                eprintln!("(first {} of {size} bytes shown)", preview.len());
+            }
+        }
+        RpcResult::TailLines { lines, .. } => {
+            for line in lines {
+                println!("{line}");
+            }
+        }
+        RpcResult::TooLarge { path, size, limit } => {
+            eprintln!("error: {path} is {size} bytes, over the server's {limit} byte read limit");
+            return false;
+        }
+        RpcResult::Error { message } => {
+            eprintln!("error: {message}");
+            return false;
+        }
+        other => println!("{other:?}"),
+    }
+    true
+}
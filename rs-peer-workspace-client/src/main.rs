@@ -1,14 +1,46 @@
 mod app;
-mod net;
+mod headless;
 
-fn main() {
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use rs_peer_workspace_shared::{crash, telemetry};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    #[arg(long)]
+    headless: bool,
+    #[command(flatten)]
+    headless_args: headless::HeadlessArgs,
+}
+
+pub fn crash_dir() -> PathBuf {
+    crash::default_dir("rs-peer-workspace-client")
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    crash::install("rs-peer-workspace-client", env!("CARGO_PKG_VERSION"), crash_dir());
+    if args.headless {
+        telemetry::init("warn", false);
+        return headless::run(args.headless_args);
+    }
+
+    telemetry::init("info", false);
     let _runmat_installed_marker = "runmat-runtime";
     let options = eframe::NativeOptions::default();
     if let Err(err) = eframe::run_native(
         "RS Peer Workspace Client",
         options,
-        Box::new(|_cc| Ok(Box::<app::WorkspaceApp>::default())),
+        Box::new(|cc| {
+            egui_extras::install_image_loaders(&cc.egui_ctx);
+            Ok(Box::<app::WorkspaceApp>::default())
+        }),
     ) {
-        eprintln!("failed to launch egui client: {err}");
+        tracing::error!(error = %err, "failed to launch egui client");
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,237 @@
+use rs_peer_workspace_shared::app::{
+    merge_env, DirectoryEntry, EnvVar, ListFilter, OutputAttachment, RpcAction, RpcRequest, RpcResult,
+};
+use rs_peer_workspace_shared::project::{PortForward, ProjectConnection, TerminalProfile};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::engine::{run_connection, ConnectionCommand, ConnectionEvent, Heartbeat};
+
+const EVENT_CAPACITY: usize = 256;
+
+/// Output of a command run on the server.
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    pub output: String,
+    pub attachments: Vec<OutputAttachment>,
+    /// `None` when the command could not be started or was ended by a signal.
+    pub exit_code: Option<i32>,
+}
+
+/// Handle to an established session with a workspace server.
+///
+/// The connection runs as a task on the current tokio runtime and is closed when
+/// the handle is dropped or [`Connection::disconnect`] is called.
+pub struct Connection {
+    name: String,
+    server_fingerprint: String,
+    env: Vec<EnvVar>,
+    command_tx: mpsc::UnboundedSender<ConnectionCommand>,
+    events: broadcast::Receiver<ConnectionEvent>,
+}
+
+impl Connection {
+    /// Connects through the proxy and waits until the server accepts the session and
+    /// proves its identity. Fails if `connection.server_fingerprint` is set and the
+    /// server presents a different key.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub async fn connect(connection: ProjectConnection) -> anyhow::Result<Self> {
+        let (event_tx, mut events) = broadcast::channel(EVENT_CAPACITY);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let name = connection.name.clone();
+        let env = connection.env.clone();
+        tokio::spawn(run_connection(
+            connection,
+            command_rx,
+            event_tx,
+            Heartbeat::default(),
+        ));
+
+        let mut server_fingerprint = String::new();
+        loop {
+            match events.recv().await {
+                Ok(ConnectionEvent::ServerIdentity { fingerprint, .. }) => {
+                    server_fingerprint = fingerprint;
+                }
+                Ok(ConnectionEvent::Connected { .. }) => break,
+                Ok(ConnectionEvent::Error { message, .. }) => anyhow::bail!(message),
+                Ok(ConnectionEvent::Closed { reason, .. }) => anyhow::bail!(reason),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => anyhow::bail!("connection task exited"),
+            }
+        }
+
+        Ok(Self {
+            name,
+            server_fingerprint,
+            env,
+            command_tx,
+            events,
+        })
+    }
+
+    /// Name of the project connection this session was opened for.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fingerprint of the identity key the server proved it holds, to pin in
+    /// [`ProjectConnection::server_fingerprint`].
+    pub fn server_fingerprint(&self) -> &str {
+        &self.server_fingerprint
+    }
+
+    /// Queues a request without waiting for its response; the matching
+    /// [`ConnectionEvent::RpcResponse`] arrives on [`Connection::subscribe_events`].
+    pub fn send_rpc(&self, request: RpcRequest) -> anyhow::Result<()> {
+        self.command_tx
+            .send(ConnectionCommand::SendRpc(request))
+            .map_err(|_| anyhow::anyhow!("connection closed"))
+    }
+
+    /// Starts forwarding a port through the session; its state is reported as
+    /// [`ConnectionEvent::Forwards`].
+    pub fn start_forward(&self, forward: PortForward) -> anyhow::Result<()> {
+        self.command_tx
+            .send(ConnectionCommand::StartForward(forward))
+            .map_err(|_| anyhow::anyhow!("connection closed"))
+    }
+
+    /// Stops a forward and closes the connections open through it.
+    pub fn stop_forward(&self, id: Uuid) -> anyhow::Result<()> {
+        self.command_tx
+            .send(ConnectionCommand::StopForward(id))
+            .map_err(|_| anyhow::anyhow!("connection closed"))
+    }
+
+    /// Sends `action` and waits for the server's result.
+    pub async fn request(&self, action: RpcAction) -> anyhow::Result<RpcResult> {
+        let mut events = self.subscribe_events();
+        let request_id = Uuid::new_v4();
+        self.send_rpc(RpcRequest { request_id, action })?;
+
+        loop {
+            match events.recv().await {
+                Ok(ConnectionEvent::RpcResponse { response, .. })
+                    if response.request_id == request_id =>
+                {
+                    return Ok(response.result);
+                }
+                Ok(ConnectionEvent::Error { message, .. }) => anyhow::bail!(message),
+                Ok(ConnectionEvent::Closed { reason, .. }) => anyhow::bail!(reason),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => anyhow::bail!("connection closed"),
+            }
+        }
+    }
+
+    /// Runs `command` in the server's default shell.
+    pub async fn run_command(&self, command: &str) -> anyhow::Result<CommandOutput> {
+        self.run_in_profile(&TerminalProfile::default(), command).await
+    }
+
+    /// Runs `command` with the shell, working directory, environment and startup
+    /// commands of a terminal profile, as the client's terminals do. The profile's
+    /// variables are set over the connection's.
+    pub async fn run_in_profile(
+        &self,
+        profile: &TerminalProfile,
+        command: &str,
+    ) -> anyhow::Result<CommandOutput> {
+        let command = if profile.startup_commands.is_empty() {
+            command.to_string()
+        } else {
+            format!("{}\n{command}", profile.startup_commands.join("\n"))
+        };
+        match self
+            .request(RpcAction::RunCommand {
+                command,
+                shell: profile.shell.clone(),
+                cwd: profile.cwd.clone(),
+                env: merge_env(&self.env, &profile.env),
+            })
+            .await?
+        {
+            RpcResult::CommandOutput {
+                output,
+                attachments,
+                exit_code,
+            } => Ok(CommandOutput {
+                output,
+                attachments,
+                exit_code,
+            }),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn list_directory(&self, path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
+        match self
+            .request(RpcAction::ListDirectory {
+                path: path.to_string(),
+                filter: ListFilter::default(),
+            })
+            .await?
+        {
+            RpcResult::DirectoryEntries { entries, .. } => Ok(entries),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn read_file(&self, path: &str) -> anyhow::Result<String> {
+        match self
+            .request(RpcAction::ReadFile {
+                path: path.to_string(),
+                known_hash: None,
+            })
+            .await?
+        {
+            RpcResult::FileContent { content, .. } => Ok(content),
+            RpcResult::BinaryContent { path, .. } => anyhow::bail!("{path} is not a text file"),
+            RpcResult::TooLarge { path, size, limit } => {
+                anyhow::bail!("{path} is {size} bytes, over the server's {limit} byte read limit")
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn write_file(&self, path: &str, content: &str) -> anyhow::Result<()> {
+        match self
+            .request(RpcAction::WriteFile {
+                path: path.to_string(),
+                content: content.to_string(),
+                expected_hash: None,
+            })
+            .await?
+        {
+            RpcResult::WriteComplete { .. } => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Returns a receiver for every event emitted after this call, including RPC
+    /// responses, broadcasts, transport changes and the final close.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.resubscribe()
+    }
+
+    /// Closes the session and stops the connection task.
+    pub fn disconnect(&self) {
+        let _ = self.command_tx.send(ConnectionCommand::Disconnect);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+fn unexpected(result: RpcResult) -> anyhow::Error {
+    match result {
+        RpcResult::Error { message } => anyhow::anyhow!(message),
+        other => anyhow::anyhow!("unexpected response: {other:?}"),
+    }
+}
@@ -0,0 +1,95 @@
+use keyring::Entry;
+use rs_peer_workspace_shared::project::{ProjectConnection, ProjectFile};
+use uuid::Uuid;
+
+const SERVICE: &str = "rs-peer-workspace";
+
+/// Whether this build has a persistent platform secret store. Other platforms fall
+/// back to keyring's in-memory store, which forgets secrets on exit.
+pub const AVAILABLE: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Fresh value for [`ProjectConnection::credential_ref`].
+pub fn new_reference() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Writes the connection's passwords under its `credential_ref`, if it has one.
+pub fn store(connection: &ProjectConnection) -> keyring::Result<()> {
+    let Some(reference) = &connection.credential_ref else {
+        return Ok(());
+    };
+    entry(reference, "proxy")?.set_password(&connection.proxy_password)?;
+    entry(reference, "server")?.set_password(&connection.server_password)?;
+    if let Some(proxy) = &connection.outbound_proxy {
+        entry(reference, "outbound-proxy")?.set_password(&proxy.password)?;
+    }
+    if !connection.ice_servers.is_empty() {
+        let passwords: Vec<&str> = connection
+            .ice_servers
+            .iter()
+            .map(|server| server.password.as_str())
+            .collect();
+        let passwords = serde_json::to_string(&passwords)
+            .map_err(|err| keyring::Error::PlatformFailure(err.into()))?;
+        entry(reference, "ice-servers")?.set_password(&passwords)?;
+    }
+    Ok(())
+}
+
+/// Fills in the passwords of a keyring-backed connection. Missing entries load as empty.
+pub fn load(connection: &mut ProjectConnection) -> keyring::Result<()> {
+    let Some(reference) = &connection.credential_ref else {
+        return Ok(());
+    };
+    connection.proxy_password = read(reference, "proxy")?;
+    connection.server_password = read(reference, "server")?;
+    if let Some(proxy) = &mut connection.outbound_proxy {
+        proxy.password = read(reference, "outbound-proxy")?;
+    }
+    if !connection.ice_servers.is_empty() {
+        let passwords: Vec<String> =
+            serde_json::from_str(&read(reference, "ice-servers")?).unwrap_or_default();
+        for (server, password) in connection.ice_servers.iter_mut().zip(passwords) {
+            server.password = password;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the keyring entries for `reference`, ignoring entries that do not exist.
+pub fn delete(reference: &str) {
+    for field in ["proxy", "server", "outbound-proxy", "ice-servers"] {
+        if let Ok(entry) = entry(reference, field) {
+            let _ = entry.delete_credential();
+        }
+    }
+}
+
+/// Copy of `project` with keyring-backed passwords blanked out for writing to disk.
+pub fn strip(project: &ProjectFile) -> ProjectFile {
+    let mut stripped = project.clone();
+    for connection in &mut stripped.connections {
+        if connection.credential_ref.is_some() {
+            connection.proxy_password.clear();
+            connection.server_password.clear();
+            if let Some(proxy) = &mut connection.outbound_proxy {
+                proxy.password.clear();
+            }
+            for server in &mut connection.ice_servers {
+                server.password.clear();
+            }
+        }
+    }
+    stripped
+}
+
+fn entry(reference: &str, field: &str) -> keyring::Result<Entry> {
+    Entry::new(SERVICE, &format!("{reference}:{field}"))
+}
+
+fn read(reference: &str, field: &str) -> keyring::Result<String> {
+    match entry(reference, field)?.get_password() {
+        Err(keyring::Error::NoEntry) => Ok(String::new()),
+        result => result,
+    }
+}
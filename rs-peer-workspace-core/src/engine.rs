@@ -0,0 +1,1049 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures_util::{SinkExt, StreamExt};
+use rs_peer_workspace_shared::app::{AppEnvelope, AppPayload, Capabilities, RpcRequest, RpcResponse};
+use rs_peer_workspace_shared::compression::{self, Codec};
+use rs_peer_workspace_shared::discovery::{self, Announcement};
+use rs_peer_workspace_shared::e2e::{KeyPair, SessionCipher};
+use rs_peer_workspace_shared::identity::{self, IdentityChallenge};
+use rs_peer_workspace_shared::outbox::{self, Outbox};
+use rs_peer_workspace_shared::project::{OutboundProxy, PortForward, ProjectConnection};
+use rs_peer_workspace_shared::relay::{
+    auth_proof, AuthRole, Freshness, PeerToProxy, ProxyToPeer, ServerInfo, SignalPayload,
+    TurnCredentials,
+};
+use tokio::sync::{broadcast, mpsc as tokio_mpsc, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, info_span, warn, Instrument};
+use uuid::Uuid;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::stats::StatsReportType;
+
+use crate::forward::{ForwardState, Forwards};
+use crate::tunnel::connect_websocket;
+
+const STATS_INTERVAL: Duration = Duration::from_secs(2);
+const SERVER_LIST_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// A connection task whose event loop has not come round for this long is wedged.
+const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Commands accepted by a running connection task.
+#[derive(Debug)]
+pub enum ConnectionCommand {
+    SendRpc(RpcRequest),
+    /// Starts forwarding a port, replacing a forward with the same id.
+    StartForward(PortForward),
+    StopForward(Uuid),
+    Disconnect,
+}
+
+/// Events reported by a connection task, tagged with the connection name.
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    Status {
+        connection_name: String,
+        message: String,
+    },
+    Transport {
+        connection_name: String,
+        message: String,
+    },
+    /// The server proved it holds the key with this fingerprint; sent before
+    /// [`ConnectionEvent::Connected`].
+    ServerIdentity {
+        connection_name: String,
+        fingerprint: String,
+    },
+    Connected {
+        connection_name: String,
+    },
+    RpcResponse {
+        connection_name: String,
+        response: RpcResponse,
+    },
+    Error {
+        connection_name: String,
+        message: String,
+    },
+    Closed {
+        connection_name: String,
+        reason: String,
+    },
+    TransportStats {
+        connection_name: String,
+        stats: TransportStats,
+    },
+    Broadcast {
+        connection_name: String,
+        message: String,
+    },
+    ServersListed {
+        proxy_addr: String,
+        result: Result<Vec<ServerInfo>, String>,
+    },
+    ConnectionTested {
+        proxy_addr: String,
+        server_name: String,
+        result: Result<ConnectionTest, String>,
+    },
+    /// State of every port forward of the connection, sent when it changes and with
+    /// the transport stats.
+    Forwards {
+        connection_name: String,
+        forwards: HashMap<Uuid, ForwardState>,
+    },
+    /// Proxies that answered a LAN discovery scan; empty when none did or multicast
+    /// is unavailable.
+    ProxiesDiscovered {
+        proxies: Vec<Announcement>,
+    },
+    /// The watchdog abandoned a wedged connection task and started a new one, which
+    /// reconnects and reports [`ConnectionEvent::Connected`] again.
+    Restarted {
+        connection_name: String,
+        reason: String,
+    },
+}
+
+/// What [`test_connection`] found out about a connection's settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionTest {
+    /// The proxy accepted both passwords and opened a session to the server.
+    Reachable {
+        /// Whether the proxy hands out a TURN server for P2P.
+        turn: bool,
+    },
+    /// The proxy refused the proxy password.
+    ProxyAuthFailed(String),
+    /// No server with this name is registered with the proxy.
+    UnknownServer,
+    /// The proxy refused the session, for example for a wrong server password.
+    Rejected(String),
+}
+
+/// Periodic transport counters for the active session.
+#[derive(Debug, Clone, Default)]
+pub struct TransportStats {
+    pub candidate_pair: Option<String>,
+    pub rtt_ms: Option<f64>,
+    pub p2p_bytes_sent: u64,
+    pub p2p_bytes_received: u64,
+    pub retransmits: u64,
+    pub relay_bytes_sent: u64,
+    pub relay_bytes_received: u64,
+    pub relay_send_rate: f64,
+    pub relay_receive_rate: f64,
+    /// Messages waiting to be written to the proxy socket.
+    pub outgoing_queue_depth: usize,
+    /// Whether relayed payloads are end-to-end encrypted, so the proxy cannot read them.
+    pub relay_encrypted: bool,
+}
+
+/// Destination for events produced by a connection task.
+pub trait EventSink: Clone + Send + Sync + 'static {
+    fn emit(&self, event: ConnectionEvent);
+}
+
+impl EventSink for std::sync::mpsc::Sender<ConnectionEvent> {
+    fn emit(&self, event: ConnectionEvent) {
+        let _ = self.send(event);
+    }
+}
+
+impl EventSink for broadcast::Sender<ConnectionEvent> {
+    fn emit(&self, event: ConnectionEvent) {
+        let _ = self.send(event);
+    }
+}
+
+/// Counts turns of a connection task's event loop, so a watchdog can tell a task
+/// that is waiting for work from one stuck on an await.
+#[derive(Clone, Default)]
+pub(crate) struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    fn beat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs a connection on a dedicated thread with its own tokio runtime.
+///
+/// Intended for callers without a runtime, such as the GUI; async code should use
+/// [`Connection::connect`](crate::Connection::connect) instead. A watchdog replaces
+/// the connection task when it stops responding, see [`ConnectionEvent::Restarted`].
+pub fn spawn_connection(
+    connection: ProjectConnection,
+    event_tx: Sender<ConnectionEvent>,
+) -> tokio_mpsc::UnboundedSender<ConnectionCommand> {
+    let (command_tx, command_rx) = tokio_mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let supervisor = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build();
+        let Ok(supervisor) = supervisor else {
+            event_tx.emit(ConnectionEvent::Error {
+                connection_name: connection.name.clone(),
+                message: "failed to start tokio runtime".to_string(),
+            });
+            return;
+        };
+        supervisor.block_on(supervise_connection(connection, command_rx, event_tx));
+    });
+    command_tx
+}
+
+/// Runs the connection task on a runtime of its own and forwards commands to it. Once
+/// the task's heartbeat has stalled for [`WATCHDOG_TIMEOUT`], its runtime is abandoned
+/// without waiting for it and a fresh task connects again.
+async fn supervise_connection(
+    connection: ProjectConnection,
+    mut command_rx: tokio_mpsc::UnboundedReceiver<ConnectionCommand>,
+    event_tx: Sender<ConnectionEvent>,
+) {
+    let mut commands_open = true;
+    loop {
+        let Ok(runtime) = tokio::runtime::Runtime::new() else {
+            event_tx.emit(ConnectionEvent::Error {
+                connection_name: connection.name.clone(),
+                message: "failed to start tokio runtime".to_string(),
+            });
+            return;
+        };
+        let heartbeat = Heartbeat::default();
+        let (task_tx, task_rx) = tokio_mpsc::unbounded_channel();
+        let mut task = runtime.spawn(run_connection(
+            connection.clone(),
+            task_rx,
+            event_tx.clone(),
+            heartbeat.clone(),
+        ));
+        let mut task_tx = commands_open.then_some(task_tx);
+        let mut check = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut last_count = 0;
+        let mut last_progress = Instant::now();
+        loop {
+            tokio::select! {
+                _ = &mut task => {
+                    runtime.shutdown_background();
+                    return;
+                }
+                command = command_rx.recv(), if commands_open => match command {
+                    Some(command) => {
+                        if let Some(task_tx) = &task_tx {
+                            let _ = task_tx.send(command);
+                        }
+                    }
+                    None => {
+                        commands_open = false;
+                        task_tx = None;
+                    }
+                },
+                _ = check.tick() => {
+                    let count = heartbeat.count();
+                    if count != last_count {
+                        last_count = count;
+                        last_progress = Instant::now();
+                    } else if count > 0 && last_progress.elapsed() >= WATCHDOG_TIMEOUT {
+                        break;
+                    }
+                }
+            }
+        }
+
+        task.abort();
+        runtime.shutdown_background();
+        let reason = format!(
+            "connection task stopped responding for {}s and was restarted",
+            WATCHDOG_TIMEOUT.as_secs()
+        );
+        warn!(connection = %connection.name, "{reason}");
+        event_tx.emit(ConnectionEvent::Restarted {
+            connection_name: connection.name.clone(),
+            reason,
+        });
+        if !commands_open {
+            return;
+        }
+    }
+}
+
+pub(crate) async fn run_connection<E: EventSink>(
+    connection: ProjectConnection,
+    command_rx: tokio_mpsc::UnboundedReceiver<ConnectionCommand>,
+    event_tx: E,
+    heartbeat: Heartbeat,
+) {
+    let span = info_span!("connection", connection = %connection.name, session_id = tracing::field::Empty);
+    if let Err(err) = connection_task(connection.clone(), command_rx, event_tx.clone(), heartbeat)
+        .instrument(span)
+        .await
+    {
+        warn!(connection = %connection.name, error = %err, "connection task failed");
+        event_tx.emit(ConnectionEvent::Error {
+            connection_name: connection.name.clone(),
+            message: err.to_string(),
+        });
+    }
+}
+
+/// Asks the proxy for its registered servers and reports the result as
+/// [`ConnectionEvent::ServersListed`].
+pub fn spawn_server_listing(
+    proxy_addr: String,
+    proxy_username: String,
+    proxy_password: String,
+    outbound_proxy: Option<OutboundProxy>,
+    event_tx: Sender<ConnectionEvent>,
+) {
+    std::thread::spawn(move || {
+        let result = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime.block_on(async {
+                tokio::time::timeout(
+                    SERVER_LIST_TIMEOUT,
+                    list_servers(&proxy_addr, &proxy_username, &proxy_password, outbound_proxy.as_ref()),
+                )
+                .await
+                .map_err(|_| "timed out listing servers".to_string())?
+                .map_err(|err| err.to_string())
+            }),
+            Err(_) => Err("failed to start tokio runtime".to_string()),
+        };
+        event_tx.emit(ConnectionEvent::ServersListed { proxy_addr, result });
+    });
+}
+
+/// Fetches the servers registered with the proxy and their metadata. With a
+/// `proxy_username`, `proxy_password` is that account's password.
+pub async fn list_servers(
+    proxy_addr: &str,
+    proxy_username: &str,
+    proxy_password: &str,
+    outbound_proxy: Option<&OutboundProxy>,
+) -> anyhow::Result<Vec<ServerInfo>> {
+    let mut ws_stream = connect_websocket(proxy_addr, outbound_proxy).await?;
+    for message in [
+        proxy_auth(proxy_username, proxy_password),
+        PeerToProxy::ListServers,
+    ] {
+        ws_stream
+            .send(Message::Text(serde_json::to_string(&message)?.into()))
+            .await?;
+    }
+
+    while let Some(message) = ws_stream.next().await {
+        let Message::Text(text) = message? else { continue; };
+        match serde_json::from_str::<ProxyToPeer>(&text) {
+            Ok(ProxyToPeer::ServersList { servers }) => {
+                let _ = ws_stream.close(None).await;
+                return Ok(servers);
+            }
+            Ok(ProxyToPeer::AuthError { reason } | ProxyToPeer::ConnectionError { reason }) => {
+                anyhow::bail!(reason)
+            }
+            _ => {}
+        }
+    }
+    anyhow::bail!("proxy closed the connection")
+}
+
+/// Looks for proxies announcing themselves on the LAN and reports them as
+/// [`ConnectionEvent::ProxiesDiscovered`].
+pub fn spawn_discovery(event_tx: Sender<ConnectionEvent>) {
+    std::thread::spawn(move || {
+        let proxies = discovery::scan(DISCOVERY_TIMEOUT).unwrap_or_else(|err| {
+            debug!(error = %err, "LAN discovery unavailable");
+            Vec::new()
+        });
+        event_tx.emit(ConnectionEvent::ProxiesDiscovered { proxies });
+    });
+}
+
+/// Tests `connection` with [`test_connection`] and reports the outcome as
+/// [`ConnectionEvent::ConnectionTested`].
+pub fn spawn_connection_test(connection: ProjectConnection, event_tx: Sender<ConnectionEvent>) {
+    std::thread::spawn(move || {
+        let result = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime.block_on(async {
+                tokio::time::timeout(CONNECTION_TEST_TIMEOUT, test_connection(&connection))
+                    .await
+                    .map_err(|_| "timed out testing the connection".to_string())?
+                    .map_err(|err| err.to_string())
+            }),
+            Err(_) => Err("failed to start tokio runtime".to_string()),
+        };
+        event_tx.emit(ConnectionEvent::ConnectionTested {
+            proxy_addr: connection.proxy_addr,
+            server_name: connection.server_name,
+            result,
+        });
+    });
+}
+
+/// Authenticates to the proxy and asks it for a session to the server, then closes
+/// that session straight away. Network failures are errors; refusals by the proxy
+/// are reported in the returned [`ConnectionTest`].
+pub async fn test_connection(connection: &ProjectConnection) -> anyhow::Result<ConnectionTest> {
+    let mut ws_stream =
+        connect_websocket(&connection.proxy_addr, connection.outbound_proxy.as_ref()).await?;
+    let freshness = Freshness::new();
+    for message in [
+        proxy_auth(&connection.proxy_username, &connection.proxy_password),
+        PeerToProxy::ConnectServer {
+            server_name: connection.server_name.clone(),
+            proof: auth_proof(&connection.server_password, &freshness),
+            use_p2p: true,
+            freshness,
+        },
+    ] {
+        ws_stream
+            .send(Message::Text(serde_json::to_string(&message)?.into()))
+            .await?;
+    }
+
+    while let Some(message) = ws_stream.next().await {
+        let Message::Text(text) = message? else { continue; };
+        let outcome = match serde_json::from_str::<ProxyToPeer>(&text) {
+            Ok(ProxyToPeer::Connected { session_id, turn, .. }) => {
+                let disconnect = PeerToProxy::DisconnectSession {
+                    session_id,
+                    reason: Some("connection test".to_string()),
+                };
+                ws_stream
+                    .send(Message::Text(serde_json::to_string(&disconnect)?.into()))
+                    .await?;
+                ConnectionTest::Reachable { turn: turn.is_some() }
+            }
+            Ok(ProxyToPeer::AuthError { reason }) => ConnectionTest::ProxyAuthFailed(reason),
+            Ok(ProxyToPeer::ConnectionError { reason }) if reason == "unknown server name" => {
+                ConnectionTest::UnknownServer
+            }
+            Ok(ProxyToPeer::ConnectionError { reason }) => ConnectionTest::Rejected(reason),
+            _ => continue,
+        };
+        let _ = ws_stream.close(None).await;
+        return Ok(outcome);
+    }
+    anyhow::bail!("proxy closed the connection")
+}
+
+async fn connection_task<E: EventSink>(
+    connection: ProjectConnection,
+    mut command_rx: tokio_mpsc::UnboundedReceiver<ConnectionCommand>,
+    event_tx: E,
+    heartbeat: Heartbeat,
+) -> anyhow::Result<()> {
+    let ws_stream =
+        connect_websocket(&connection.proxy_addr, connection.outbound_proxy.as_ref()).await?;
+    info!(
+        proxy_addr = %connection.proxy_addr,
+        outbound_proxy = connection.outbound_proxy.as_ref().map(|proxy| proxy.addr.as_str()),
+        "connected to proxy"
+    );
+    let (mut write, mut read) = ws_stream.split();
+    let (ws_send_tx, mut ws_send_rx) = outbox::channel::<String>(outbox::DEFAULT_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(text) = ws_send_rx.recv().await {
+            if write.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    send_ws(
+        &ws_send_tx,
+        &proxy_auth(&connection.proxy_username, &connection.proxy_password),
+    )?;
+    let freshness = Freshness::new();
+    send_ws(
+        &ws_send_tx,
+        &PeerToProxy::ConnectServer {
+            server_name: connection.server_name.clone(),
+            proof: auth_proof(&connection.server_password, &freshness),
+            use_p2p: connection.prefer_p2p,
+            freshness,
+        },
+    )?;
+
+    let (forward_tx, mut forward_rx) = tokio_mpsc::unbounded_channel::<RpcRequest>();
+    let forwards = Forwards::new(connection.name.clone(), event_tx, forward_tx);
+    let _stop_forwards = forwards.stop_on_drop();
+    let event_tx = forwards.clone();
+
+    let mut active_session: Option<Uuid> = None;
+    let mut identity_challenge: Option<IdentityChallenge> = None;
+    let mut verified = false;
+    // Offered in the capabilities and turned into session keys once the server answers;
+    // the keys are only used after the identity proof has vouched for them.
+    let mut e2e_key: Option<KeyPair> = None;
+    let mut pending_cipher: Option<SessionCipher> = None;
+    let mut encoding = PeerEncoding::default();
+    let mut queued: Vec<RpcRequest> = Vec::new();
+    let mut peer_connection: Option<Arc<RTCPeerConnection>> = None;
+    let mut early_candidates: Vec<RTCIceCandidateInit> = Vec::new();
+    let data_channel = Arc::new(Mutex::new(None::<Arc<RTCDataChannel>>));
+    let p2p_ready = Arc::new(AtomicBool::new(false));
+    let mut stats = TransportStats::default();
+    let mut stats_interval = tokio::time::interval(STATS_INTERVAL);
+    let mut last_stats_at = Instant::now();
+    let mut last_relay_totals = (0u64, 0u64);
+    let mut heartbeat_interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = heartbeat_interval.tick() => heartbeat.beat(),
+            inbound = read.next() => {
+                let Some(message) = inbound else {
+                    event_tx.emit(ConnectionEvent::Closed {
+                        connection_name: connection.name.clone(),
+                        reason: "proxy socket closed".to_string(),
+                    });
+                    break;
+                };
+                let message = message?;
+                let Message::Text(text) = message else { continue; };
+                let Ok(parsed) = serde_json::from_str::<ProxyToPeer>(&text) else { continue; };
+
+                match parsed {
+                    ProxyToPeer::AuthOk { username, .. } => {
+                        let message = match username {
+                            Some(username) => format!("logged in to proxy as {username}"),
+                            None => "authenticated to proxy".to_string(),
+                        };
+                        event_tx.emit(ConnectionEvent::Status {
+                            connection_name: connection.name.clone(),
+                            message,
+                        });
+                    }
+                    ProxyToPeer::AuthError { reason } | ProxyToPeer::ConnectionError { reason } => {
+                        warn!(%reason, "proxy rejected connection");
+                        event_tx.emit(ConnectionEvent::Error {
+                            connection_name: connection.name.clone(),
+                            message: reason,
+                        });
+                        break;
+                    }
+                    ProxyToPeer::Connected { session_id, via_p2p, turn, .. } => {
+                        active_session = Some(session_id);
+                        tracing::Span::current().record("session_id", tracing::field::display(session_id));
+                        info!(via_p2p, "session established");
+                        let key_pair = KeyPair::generate()?;
+                        let capabilities = serde_json::to_vec(&AppEnvelope {
+                            message_id: Uuid::new_v4(),
+                            payload: AppPayload::Capabilities(Capabilities {
+                                e2e_key: Some(key_pair.public_key()),
+                                ..Capabilities::supported()
+                            }),
+                        })?;
+                        e2e_key = Some(key_pair);
+                        send_ws(&ws_send_tx, &PeerToProxy::RelayData {
+                            session_id,
+                            payload: capabilities,
+                            droppable: false,
+                        })?;
+                        let challenge = IdentityChallenge {
+                            nonce: Uuid::new_v4().to_string(),
+                        };
+                        let payload = serde_json::to_vec(&AppEnvelope {
+                            message_id: Uuid::new_v4(),
+                            payload: AppPayload::IdentityChallenge(challenge.clone()),
+                        })?;
+                        send_ws(&ws_send_tx, &PeerToProxy::RelayData { session_id, payload, droppable: false })?;
+                        identity_challenge = Some(challenge);
+                        if via_p2p {
+                            let ice_servers = ice_servers(&connection, turn);
+                            if !ice_servers.is_empty() {
+                                let message = if connection.ice_servers.is_empty() {
+                                    "Attempting P2P via TURN"
+                                } else {
+                                    "Attempting P2P via custom ICE servers"
+                                };
+                                event_tx.emit(ConnectionEvent::Transport {
+                                    connection_name: connection.name.clone(),
+                                    message: message.to_string(),
+                                });
+                                let (pc, dc) = create_client_peer_connection(
+                                    session_id,
+                                    ice_servers,
+                                    ws_send_tx.clone(),
+                                    event_tx.clone(),
+                                    connection.name.clone(),
+                                    p2p_ready.clone(),
+                                )
+                                .await?;
+                                *data_channel.lock().await = Some(dc);
+                                let offer = pc.create_offer(None).await?;
+                                pc.set_local_description(offer).await?;
+                                if let Some(local) = pc.local_description().await {
+                                    send_ws(&ws_send_tx, &PeerToProxy::Signal {
+                                        session_id,
+                                        signal: SignalPayload::SdpOffer { sdp: local.sdp },
+                                        freshness: Freshness::new(),
+                                    })?;
+                                }
+                                peer_connection = Some(pc);
+                            } else {
+                                event_tx.emit(ConnectionEvent::Transport {
+                                    connection_name: connection.name.clone(),
+                                    message: "WebSocket relay".to_string(),
+                                });
+                            }
+                        } else {
+                            event_tx.emit(ConnectionEvent::Transport {
+                                connection_name: connection.name.clone(),
+                                message: "WebSocket relay".to_string(),
+                            });
+                        }
+                    }
+                    ProxyToPeer::PeerSignal { session_id, from, signal } => {
+                        if Some(session_id) != active_session || from != AuthRole::Server {
+                            continue;
+                        }
+                        if let Some(pc) = &peer_connection {
+                            match signal {
+                                SignalPayload::SdpAnswer { sdp } => {
+                                    let answer = RTCSessionDescription::answer(sdp)?;
+                                    pc.set_remote_description(answer).await?;
+                                    for candidate in early_candidates.drain(..) {
+                                        pc.add_ice_candidate(candidate).await?;
+                                    }
+                                }
+                                SignalPayload::IceCandidate { candidate, sdp_mid, sdp_mline_index } => {
+                                    let candidate = RTCIceCandidateInit {
+                                        candidate,
+                                        sdp_mid,
+                                        sdp_mline_index,
+                                        username_fragment: None,
+                                    };
+                                    // The server may gather candidates before its answer reaches us.
+                                    if pc.remote_description().await.is_none() {
+                                        early_candidates.push(candidate);
+                                    } else {
+                                        pc.add_ice_candidate(candidate).await?;
+                                    }
+                                }
+                                SignalPayload::SdpOffer { .. } => {}
+                            }
+                        }
+                    }
+                    ProxyToPeer::RelayData { session_id, payload, .. } => {
+                        if Some(session_id) != active_session {
+                            continue;
+                        }
+                        stats.relay_bytes_received += payload.len() as u64;
+                        let payload = match &mut encoding.cipher {
+                            Some(cipher) => match cipher.open(&payload) {
+                                Ok(payload) => payload,
+                                Err(err) => {
+                                    warn!(error = %err, "dropped relayed payload");
+                                    continue;
+                                }
+                            },
+                            None => payload,
+                        };
+                        let payload = match compression::decompress(payload) {
+                            Ok(payload) => payload,
+                            Err(err) => {
+                                warn!(error = %err, "failed to decompress relay message");
+                                continue;
+                            }
+                        };
+                        let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&payload) else {
+                            continue;
+                        };
+                        debug!("relay message received");
+                        let server_identity = match envelope.payload {
+                            AppPayload::Identity(server_identity) => server_identity,
+                            AppPayload::Capabilities(capabilities) => {
+                                encoding.compression = Codec::for_peer(&capabilities);
+                                if let Some(server_key) = capabilities.e2e_key
+                                    && let Some(key_pair) = e2e_key.take()
+                                {
+                                    pending_cipher = Some(key_pair.agree(&server_key, session_id, &AuthRole::Client)?);
+                                }
+                                continue;
+                            }
+                            payload => {
+                                dispatch_envelope(&event_tx, &connection.name, payload);
+                                continue;
+                            }
+                        };
+                        let Some(challenge) = identity_challenge.take() else { continue; };
+                        let exchange = pending_cipher.as_ref().map(|cipher| cipher.exchange());
+                        let fingerprint = identity::verify(&server_identity, session_id, &challenge, exchange)?;
+                        if let Some(pinned) = &connection.server_fingerprint
+                            && *pinned != fingerprint
+                        {
+                            warn!(%pinned, %fingerprint, "server identity mismatch");
+                            let _ = send_ws(&ws_send_tx, &PeerToProxy::DisconnectSession {
+                                session_id,
+                                reason: Some("server identity mismatch".to_string()),
+                            });
+                            anyhow::bail!(
+                                "server identity changed: expected {pinned}, got {fingerprint}. The server may be an imposter; remove the pinned fingerprint only if its key was deliberately replaced"
+                            );
+                        }
+                        if pending_cipher.is_none() && connection.require_e2e {
+                            let _ = send_ws(&ws_send_tx, &PeerToProxy::DisconnectSession {
+                                session_id,
+                                reason: Some("end-to-end encryption unavailable".to_string()),
+                            });
+                            anyhow::bail!(
+                                "the server did not agree to end-to-end encryption, which this connection requires"
+                            );
+                        }
+                        info!(%fingerprint, e2e = pending_cipher.is_some(), "server identity verified");
+                        verified = true;
+                        encoding.cipher = pending_cipher.take();
+                        stats.relay_encrypted = encoding.cipher.is_some();
+                        event_tx.emit(ConnectionEvent::ServerIdentity {
+                            connection_name: connection.name.clone(),
+                            fingerprint,
+                        });
+                        event_tx.emit(ConnectionEvent::Connected {
+                            connection_name: connection.name.clone(),
+                        });
+                        for request in queued.drain(..) {
+                            send_request(request, session_id, &p2p_ready, &data_channel, &ws_send_tx, &mut encoding, &mut stats).await?;
+                        }
+                    }
+                    ProxyToPeer::SessionClosed { session_id, reason } => {
+                        if Some(session_id) == active_session {
+                            info!(%reason, "session closed");
+                            event_tx.emit(ConnectionEvent::Closed {
+                                connection_name: connection.name.clone(),
+                                reason,
+                            });
+                            break;
+                        }
+                    }
+                    ProxyToPeer::Registered { .. }
+                    | ProxyToPeer::PeerJoined { .. }
+                    | ProxyToPeer::ServersList { .. } => {}
+                }
+            }
+            command = command_rx.recv() => {
+                let Some(command) = command else { break; };
+                match command {
+                    ConnectionCommand::SendRpc(request) => {
+                        debug!(request_id = %request.request_id, action = request.action.name(), "sending request");
+                        match active_session {
+                            Some(session_id) if verified => {
+                                send_request(request, session_id, &p2p_ready, &data_channel, &ws_send_tx, &mut encoding, &mut stats).await?;
+                            }
+                            Some(_) => queued.push(request),
+                            None => {}
+                        }
+                    }
+                    ConnectionCommand::StartForward(forward) => forwards.start(forward),
+                    ConnectionCommand::StopForward(id) => forwards.stop(id),
+                    ConnectionCommand::Disconnect => {
+                        if let Some(session_id) = active_session {
+                            let _ = send_ws(&ws_send_tx, &PeerToProxy::DisconnectSession {
+                                session_id,
+                                reason: None,
+                            });
+                        }
+                        if let Some(pc) = &peer_connection {
+                            let _ = pc.close().await;
+                        }
+                        break;
+                    }
+                }
+            }
+            request = forward_rx.recv() => {
+                let Some(request) = request else { continue; };
+                match active_session {
+                    Some(session_id) if verified => {
+                        send_request(request, session_id, &p2p_ready, &data_channel, &ws_send_tx, &mut encoding, &mut stats).await?;
+                    }
+                    _ => queued.push(request),
+                }
+            }
+            _ = stats_interval.tick() => {
+                if active_session.is_none() {
+                    continue;
+                }
+                let elapsed = last_stats_at.elapsed().as_secs_f64().max(0.001);
+                stats.relay_send_rate = (stats.relay_bytes_sent - last_relay_totals.0) as f64 / elapsed;
+                stats.relay_receive_rate = (stats.relay_bytes_received - last_relay_totals.1) as f64 / elapsed;
+                last_relay_totals = (stats.relay_bytes_sent, stats.relay_bytes_received);
+                last_stats_at = Instant::now();
+                stats.outgoing_queue_depth = ws_send_tx.depth();
+                if let Some(pc) = &peer_connection {
+                    collect_peer_stats(pc, &mut stats).await;
+                }
+                event_tx.emit(ConnectionEvent::TransportStats {
+                    connection_name: connection.name.clone(),
+                    stats: stats.clone(),
+                });
+                forwards.report();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn collect_peer_stats(pc: &RTCPeerConnection, stats: &mut TransportStats) {
+    let report = pc.get_stats().await;
+    let selected = report
+        .reports
+        .values()
+        .filter_map(|entry| match entry {
+            StatsReportType::CandidatePair(pair) => Some(pair),
+            _ => None,
+        })
+        .max_by_key(|pair| (pair.nominated, pair.bytes_sent + pair.bytes_received));
+    let Some(pair) = selected else {
+        return;
+    };
+
+    let describe = |id: &str| {
+        report.reports.get(id).and_then(|entry| match entry {
+            StatsReportType::LocalCandidate(candidate) | StatsReportType::RemoteCandidate(candidate) => {
+                Some(format!(
+                    "{} {}:{} ({})",
+                    candidate.candidate_type, candidate.ip, candidate.port, candidate.network_type
+                ))
+            }
+            _ => None,
+        })
+    };
+    stats.candidate_pair = match (describe(&pair.local_candidate_id), describe(&pair.remote_candidate_id)) {
+        (Some(local), Some(remote)) => Some(format!("{local} <-> {remote} [{}]", pair.state)),
+        _ => Some(format!("{} [{}]", pair.id, pair.state)),
+    };
+    stats.rtt_ms = (pair.current_round_trip_time > 0.0).then_some(pair.current_round_trip_time * 1000.0);
+    stats.p2p_bytes_sent = pair.bytes_sent;
+    stats.p2p_bytes_received = pair.bytes_received;
+    stats.retransmits = pair.retransmissions_sent;
+}
+
+/// How requests to the server are encoded, as agreed in the capability handshake.
+#[derive(Default)]
+struct PeerEncoding {
+    compression: Option<Codec>,
+    /// Seals relayed requests once the server's identity proof has vouched for the keys.
+    cipher: Option<SessionCipher>,
+}
+
+async fn send_request(
+    request: RpcRequest,
+    session_id: Uuid,
+    p2p_ready: &AtomicBool,
+    data_channel: &Mutex<Option<Arc<RTCDataChannel>>>,
+    ws_send_tx: &Outbox<String>,
+    encoding: &mut PeerEncoding,
+    stats: &mut TransportStats,
+) -> anyhow::Result<()> {
+    let envelope = AppEnvelope {
+        message_id: Uuid::new_v4(),
+        payload: AppPayload::RpcRequest(request),
+    };
+    let payload = serde_json::to_vec(&envelope)?;
+    let payload = match encoding.compression {
+        Some(codec) => compression::compress(payload, codec),
+        None => payload,
+    };
+    if p2p_ready.load(Ordering::SeqCst)
+        && let Some(dc) = data_channel.lock().await.clone()
+    {
+        let _ = dc.send(&Bytes::from(payload)).await;
+        return Ok(());
+    }
+    let payload = match &mut encoding.cipher {
+        Some(cipher) => cipher.seal(&payload)?,
+        None => payload,
+    };
+    stats.relay_bytes_sent += payload.len() as u64;
+    send_ws(ws_send_tx, &PeerToProxy::RelayData { session_id, payload, droppable: false })
+}
+
+fn dispatch_envelope<E: EventSink>(event_tx: &E, connection_name: &str, payload: AppPayload) {
+    let event = match payload {
+        AppPayload::RpcResponse(response) => ConnectionEvent::RpcResponse {
+            connection_name: connection_name.to_string(),
+            response,
+        },
+        AppPayload::Broadcast(broadcast) => ConnectionEvent::Broadcast {
+            connection_name: connection_name.to_string(),
+            message: broadcast.message,
+        },
+        AppPayload::RpcRequest(_)
+        | AppPayload::Capabilities(_)
+        | AppPayload::IdentityChallenge(_)
+        | AppPayload::Identity(_) => return,
+    };
+    event_tx.emit(event);
+}
+
+/// Logs in with the proxy account when one is configured, otherwise authenticates
+/// with the shared proxy password.
+fn proxy_auth(proxy_username: &str, proxy_password: &str) -> PeerToProxy {
+    let freshness = Freshness::new();
+    if !proxy_username.is_empty() {
+        return PeerToProxy::Login {
+            username: proxy_username.to_string(),
+            password: proxy_password.to_string(),
+            freshness,
+        };
+    }
+    PeerToProxy::AuthProxy {
+        proof: auth_proof(proxy_password, &freshness),
+        role: AuthRole::Client,
+        freshness,
+    }
+}
+
+fn send_ws(tx: &Outbox<String>, payload: &impl serde::Serialize) -> anyhow::Result<()> {
+    let text = serde_json::to_string(payload)?;
+    tx.send_control(text)?;
+    Ok(())
+}
+
+/// The connection's own ICE servers, or else the TURN server the proxy handed out.
+fn ice_servers(connection: &ProjectConnection, turn: Option<TurnCredentials>) -> Vec<RTCIceServer> {
+    if !connection.ice_servers.is_empty() {
+        return connection
+            .ice_servers
+            .iter()
+            .map(|server| RTCIceServer {
+                urls: vec![server.url.clone()],
+                username: server.username.clone(),
+                credential: server.password.clone(),
+            })
+            .collect();
+    }
+    turn.map(|turn| RTCIceServer {
+        urls: vec![turn.url],
+        username: turn.username,
+        credential: turn.password,
+    })
+    .into_iter()
+    .collect()
+}
+
+async fn create_client_peer_connection<E: EventSink>(
+    session_id: Uuid,
+    ice_servers: Vec<RTCIceServer>,
+    ws_tx: Outbox<String>,
+    event_tx: E,
+    connection_name: String,
+    p2p_ready: Arc<AtomicBool>,
+) -> anyhow::Result<(Arc<RTCPeerConnection>, Arc<RTCDataChannel>)> {
+    let mut media_engine = MediaEngine::default();
+    media_engine.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(media_engine).build();
+    let config = RTCConfiguration {
+        ice_servers,
+        ..Default::default()
+    };
+    let pc = Arc::new(api.new_peer_connection(config).await?);
+
+    let ws_tx_ice = ws_tx.clone();
+    pc.on_ice_candidate(Box::new(move |candidate| {
+        let ws_tx_inner = ws_tx_ice.clone();
+        Box::pin(async move {
+            if let Some(candidate) = candidate
+                && let Ok(json) = candidate.to_json()
+            {
+                let _ = send_ws(&ws_tx_inner, &PeerToProxy::Signal {
+                    session_id,
+                    signal: SignalPayload::IceCandidate {
+                        candidate: json.candidate,
+                        sdp_mid: json.sdp_mid,
+                        sdp_mline_index: json.sdp_mline_index,
+                    },
+                    freshness: Freshness::new(),
+                });
+            }
+        })
+    }));
+
+    let dc = pc.create_data_channel(
+        "workspace",
+        Some(RTCDataChannelInit {
+            ordered: Some(true),
+            ..Default::default()
+        }),
+    ).await?;
+
+    let ready_flag = p2p_ready.clone();
+    let event_tx_open = event_tx.clone();
+    let name_open = connection_name.clone();
+    dc.on_open(Box::new(move || {
+        let ready_flag = ready_flag.clone();
+        let event_tx_open = event_tx_open.clone();
+        let name_open = name_open.clone();
+        Box::pin(async move {
+            ready_flag.store(true, Ordering::SeqCst);
+            event_tx_open.emit(ConnectionEvent::Transport {
+                connection_name: name_open.clone(),
+                message: "P2P data channel".to_string(),
+            });
+            event_tx_open.emit(ConnectionEvent::Status {
+                connection_name: name_open,
+                message: "P2P channel established".to_string(),
+            });
+        })
+    }));
+
+    let ready_flag_close = p2p_ready.clone();
+    let event_tx_close = event_tx.clone();
+    let name_close = connection_name.clone();
+    dc.on_close(Box::new(move || {
+        let ready_flag_close = ready_flag_close.clone();
+        let event_tx_close = event_tx_close.clone();
+        let name_close = name_close.clone();
+        Box::pin(async move {
+            ready_flag_close.store(false, Ordering::SeqCst);
+            event_tx_close.emit(ConnectionEvent::Transport {
+                connection_name: name_close.clone(),
+                message: "WebSocket relay".to_string(),
+            });
+            event_tx_close.emit(ConnectionEvent::Status {
+                connection_name: name_close,
+                message: "P2P channel closed; using WebSocket relay".to_string(),
+            });
+        })
+    }));
+
+    let event_tx_msg = event_tx.clone();
+    let name_msg = connection_name.clone();
+    dc.on_message(Box::new(move |msg| {
+        let event_tx_msg = event_tx_msg.clone();
+        let name_msg = name_msg.clone();
+        Box::pin(async move {
+            let Ok(data) = compression::decompress(msg.data.to_vec()) else {
+                return;
+            };
+            let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&data) else {
+                return;
+            };
+            dispatch_envelope(&event_tx_msg, &name_msg, envelope.payload);
+        })
+    }));
+
+    Ok((pc, dc))
+}
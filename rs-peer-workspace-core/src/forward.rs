@@ -0,0 +1,539 @@
+//! Port forwarding through a connection's session.
+//!
+//! [`Forwards`] sits between a connection task and its event sink. It handles the
+//! tunnel results in the server's responses itself, so tunnel traffic never reaches
+//! the sink, and reports the state of each forward as [`ConnectionEvent::Forwards`].
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rs_peer_workspace_shared::app::{RpcAction, RpcRequest, RpcResponse, RpcResult};
+use rs_peer_workspace_shared::project::{ForwardDirection, PortForward};
+use rs_peer_workspace_shared::tunnel::{InOrder, CHUNK_SIZE};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::AbortHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::engine::{ConnectionEvent, EventSink};
+
+/// Chunks a tunnel may have in flight before waiting for the server to write them.
+const WINDOW: usize = 8;
+
+/// Where a port forward stands, with its traffic since it was started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardState {
+    pub status: ForwardStatus,
+    /// Connections currently open through the forward.
+    pub active: usize,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Why the last connection through the forward failed, if one did.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardStatus {
+    Starting,
+    /// Accepting connections on `port`, on the client for local forwards and on the
+    /// server for remote ones.
+    Listening { port: u16 },
+    Failed(String),
+}
+
+struct Forward {
+    spec: PortForward,
+    state: ForwardState,
+    task: Option<AbortHandle>,
+    /// Request id of the `listen_tunnel` call of a remote forward.
+    listener_id: Option<Uuid>,
+}
+
+struct Tunnel {
+    forward_id: Uuid,
+    chunks: mpsc::UnboundedSender<(u64, Vec<u8>)>,
+    window: Arc<Semaphore>,
+    task: Option<AbortHandle>,
+}
+
+#[derive(Default)]
+struct Table {
+    forwards: HashMap<Uuid, Forward>,
+    tunnels: HashMap<Uuid, Tunnel>,
+    /// `tunnel_send` and `close_tunnel` calls still waiting for their answer.
+    pending: HashSet<Uuid>,
+}
+
+impl Table {
+    fn forward_of_listener(&mut self, listener_id: Uuid) -> Option<&mut Forward> {
+        self.forwards
+            .values_mut()
+            .find(|forward| forward.listener_id == Some(listener_id))
+    }
+}
+
+struct Inner<E> {
+    connection_name: String,
+    event_tx: E,
+    requests: mpsc::UnboundedSender<RpcRequest>,
+    runtime: Handle,
+    table: Mutex<Table>,
+}
+
+/// The port forwards of one connection task. Must be created on the runtime the
+/// task runs on.
+pub(crate) struct Forwards<E> {
+    inner: Arc<Inner<E>>,
+}
+
+impl<E> Clone for Forwards<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Stops every forward when the connection task that owns it ends.
+pub(crate) struct StopOnDrop<E: EventSink>(Forwards<E>);
+
+impl<E: EventSink> Drop for StopOnDrop<E> {
+    fn drop(&mut self) {
+        self.0.stop_all();
+    }
+}
+
+impl<E: EventSink> EventSink for Forwards<E> {
+    fn emit(&self, event: ConnectionEvent) {
+        let event = match event {
+            ConnectionEvent::RpcResponse {
+                connection_name,
+                response,
+            } => match self.handle(response) {
+                Some(response) => ConnectionEvent::RpcResponse {
+                    connection_name,
+                    response,
+                },
+                None => return,
+            },
+            event => event,
+        };
+        self.inner.event_tx.emit(event);
+    }
+}
+
+impl<E: EventSink> Forwards<E> {
+    /// Forwards for `connection_name`, sending their requests to the server through
+    /// `requests` and reporting to `event_tx`.
+    pub fn new(
+        connection_name: String,
+        event_tx: E,
+        requests: mpsc::UnboundedSender<RpcRequest>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                connection_name,
+                event_tx,
+                requests,
+                runtime: Handle::current(),
+                table: Mutex::new(Table::default()),
+            }),
+        }
+    }
+
+    pub fn stop_on_drop(&self) -> StopOnDrop<E> {
+        StopOnDrop(self.clone())
+    }
+
+    fn table(&self) -> MutexGuard<'_, Table> {
+        self.inner.table.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn request(&self, request_id: Uuid, action: RpcAction) {
+        let _ = self.inner.requests.send(RpcRequest { request_id, action });
+    }
+
+    /// Sends `action` under a fresh id whose answer is swallowed.
+    fn request_quietly(&self, action: RpcAction) {
+        let request_id = Uuid::new_v4();
+        self.table().pending.insert(request_id);
+        self.request(request_id, action);
+    }
+
+    /// Starts `spec`, replacing a running forward with the same id.
+    pub fn start(&self, spec: PortForward) {
+        self.stop_quietly(spec.id);
+        let id = spec.id;
+        let direction = spec.direction;
+        let listen_port = spec.listen_port;
+        let listener_id = (direction == ForwardDirection::Remote).then(Uuid::new_v4);
+        self.table().forwards.insert(
+            id,
+            Forward {
+                spec,
+                state: ForwardState {
+                    status: ForwardStatus::Starting,
+                    active: 0,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    last_error: None,
+                },
+                task: None,
+                listener_id,
+            },
+        );
+        match listener_id {
+            Some(listener_id) => {
+                self.request(listener_id, RpcAction::ListenTunnel { port: listen_port });
+            }
+            None => {
+                let task = self.inner.runtime.spawn(self.clone().accept_local(id, listen_port));
+                if let Some(forward) = self.table().forwards.get_mut(&id) {
+                    forward.task = Some(task.abort_handle());
+                }
+            }
+        }
+        self.report();
+    }
+
+    /// Stops a forward and closes the connections open through it.
+    pub fn stop(&self, id: Uuid) {
+        self.stop_quietly(id);
+        self.report_always();
+    }
+
+    fn stop_quietly(&self, id: Uuid) {
+        let (listener_id, tunnels) = {
+            let mut table = self.table();
+            let Some(forward) = table.forwards.remove(&id) else {
+                return;
+            };
+            if let Some(task) = forward.task {
+                task.abort();
+            }
+            let tunnels: Vec<Uuid> = table
+                .tunnels
+                .iter()
+                .filter(|(_, tunnel)| tunnel.forward_id == id)
+                .map(|(tunnel_id, _)| *tunnel_id)
+                .collect();
+            for tunnel_id in &tunnels {
+                if let Some(task) = table.tunnels.remove(tunnel_id).and_then(|tunnel| tunnel.task) {
+                    task.abort();
+                }
+            }
+            (forward.listener_id, tunnels)
+        };
+        for tunnel_id in listener_id.into_iter().chain(tunnels) {
+            self.request_quietly(RpcAction::CloseTunnel { tunnel_id });
+        }
+    }
+
+    /// Stops every forward without telling the server, whose session is ending.
+    pub fn stop_all(&self) {
+        let mut table = self.table();
+        for forward in table.forwards.values() {
+            if let Some(task) = &forward.task {
+                task.abort();
+            }
+        }
+        for tunnel in table.tunnels.values() {
+            if let Some(task) = &tunnel.task {
+                task.abort();
+            }
+        }
+        table.forwards.clear();
+        table.tunnels.clear();
+    }
+
+    /// Emits the state of every forward, if there are any.
+    pub fn report(&self) {
+        if !self.table().forwards.is_empty() {
+            self.report_always();
+        }
+    }
+
+    fn report_always(&self) {
+        let forwards = {
+            let table = self.table();
+            table
+                .forwards
+                .iter()
+                .map(|(id, forward)| {
+                    let mut state = forward.state.clone();
+                    state.active = table
+                        .tunnels
+                        .values()
+                        .filter(|tunnel| tunnel.forward_id == *id)
+                        .count();
+                    (*id, state)
+                })
+                .collect()
+        };
+        self.inner.event_tx.emit(ConnectionEvent::Forwards {
+            connection_name: self.inner.connection_name.clone(),
+            forwards,
+        });
+    }
+
+    fn set_status(&self, id: Uuid, status: ForwardStatus) {
+        if let Some(forward) = self.table().forwards.get_mut(&id) {
+            forward.state.status = status;
+        }
+        self.report();
+    }
+
+    async fn accept_local(self, id: Uuid, port: u16) {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!(%id, port, error = %err, "failed to listen for port forward");
+                self.set_status(id, ForwardStatus::Failed(err.to_string()));
+                return;
+            }
+        };
+        let port = listener.local_addr().map_or(port, |addr| addr.port());
+        info!(%id, port, "forwarding local port");
+        self.set_status(id, ForwardStatus::Listening { port });
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    self.set_status(id, ForwardStatus::Failed(err.to_string()));
+                    return;
+                }
+            };
+            let Some((host, port)) = self
+                .table()
+                .forwards
+                .get(&id)
+                .map(|forward| (forward.spec.host.clone(), forward.spec.port))
+            else {
+                return;
+            };
+            let tunnel_id = Uuid::new_v4();
+            let chunks = self.add_tunnel(id, tunnel_id);
+            self.request(tunnel_id, RpcAction::OpenTunnel { host, port });
+            self.spawn_tunnel(tunnel_id, chunks, async { Ok(stream) });
+        }
+    }
+
+    /// Registers a tunnel, returning the receiver for the data the server sends it.
+    fn add_tunnel(&self, forward_id: Uuid, tunnel_id: Uuid) -> mpsc::UnboundedReceiver<(u64, Vec<u8>)> {
+        let (chunks, chunk_rx) = mpsc::unbounded_channel();
+        self.table().tunnels.insert(
+            tunnel_id,
+            Tunnel {
+                forward_id,
+                chunks,
+                window: Arc::new(Semaphore::new(WINDOW)),
+                task: None,
+            },
+        );
+        self.report();
+        chunk_rx
+    }
+
+    fn spawn_tunnel(
+        &self,
+        tunnel_id: Uuid,
+        chunk_rx: mpsc::UnboundedReceiver<(u64, Vec<u8>)>,
+        stream: impl Future<Output = std::io::Result<TcpStream>> + Send + 'static,
+    ) {
+        let forwards = self.clone();
+        let task = self.inner.runtime.spawn(async move {
+            let ended_by_server = match stream.await {
+                Ok(stream) => forwards.relay(tunnel_id, stream, chunk_rx).await,
+                Err(err) => {
+                    forwards.record_error(tunnel_id, err.to_string());
+                    false
+                }
+            };
+            let removed = forwards.table().tunnels.remove(&tunnel_id).is_some();
+            if removed && !ended_by_server {
+                forwards.request_quietly(RpcAction::CloseTunnel { tunnel_id });
+            }
+            forwards.report();
+        });
+        if let Some(tunnel) = self.table().tunnels.get_mut(&tunnel_id) {
+            tunnel.task = Some(task.abort_handle());
+        }
+    }
+
+    /// Copies between `stream` and the server until both directions have ended, one
+    /// fails or the server closes the tunnel; returns whether the server closed it.
+    async fn relay(
+        &self,
+        tunnel_id: Uuid,
+        stream: TcpStream,
+        mut chunk_rx: mpsc::UnboundedReceiver<(u64, Vec<u8>)>,
+    ) -> bool {
+        let Some(window) = self.table().tunnels.get(&tunnel_id).map(|tunnel| tunnel.window.clone()) else {
+            return true;
+        };
+        let (mut reader, mut writer) = stream.into_split();
+        let upload = async {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            for seq in 0.. {
+                window.acquire().await?.forget();
+                let len = reader.read(&mut buf).await?;
+                self.count(tunnel_id, len as u64, 0);
+                self.request(
+                    Uuid::new_v4(),
+                    RpcAction::TunnelSend {
+                        tunnel_id,
+                        seq,
+                        data: buf[..len].to_vec(),
+                    },
+                );
+                if len == 0 {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        // Ends with `true` once the server's socket reached its end, or `false` when
+        // the server closed the tunnel.
+        let download = async {
+            let mut order = InOrder::default();
+            while let Some((seq, data)) = chunk_rx.recv().await {
+                for data in order.push(seq, data) {
+                    if data.is_empty() {
+                        writer.shutdown().await?;
+                        return Ok(true);
+                    }
+                    self.count(tunnel_id, 0, data.len() as u64);
+                    writer.write_all(&data).await?;
+                }
+            }
+            Ok::<_, anyhow::Error>(false)
+        };
+        tokio::pin!(upload, download);
+        let (mut uploaded, mut downloaded) = (false, false);
+        while !(uploaded && downloaded) {
+            tokio::select! {
+                result = &mut upload, if !uploaded => match result {
+                    Ok(()) => uploaded = true,
+                    Err(err) => {
+                        debug!(%tunnel_id, error = %err, "tunnel upload failed");
+                        return false;
+                    }
+                },
+                result = &mut download, if !downloaded => match result {
+                    Ok(true) => downloaded = true,
+                    Ok(false) => return true,
+                    Err(err) => {
+                        debug!(%tunnel_id, error = %err, "tunnel download failed");
+                        return false;
+                    }
+                },
+            }
+        }
+        // Both ends are done; the server reports the tunnel closed in a moment.
+        true
+    }
+
+    fn count(&self, tunnel_id: Uuid, sent: u64, received: u64) {
+        let mut table = self.table();
+        let Some(forward_id) = table.tunnels.get(&tunnel_id).map(|tunnel| tunnel.forward_id) else {
+            return;
+        };
+        if let Some(forward) = table.forwards.get_mut(&forward_id) {
+            forward.state.bytes_sent += sent;
+            forward.state.bytes_received += received;
+        }
+    }
+
+    fn record_error(&self, tunnel_id: Uuid, message: String) {
+        let mut table = self.table();
+        let Some(forward_id) = table.tunnels.get(&tunnel_id).map(|tunnel| tunnel.forward_id) else {
+            return;
+        };
+        if let Some(forward) = table.forwards.get_mut(&forward_id) {
+            warn!(%tunnel_id, %message, "forwarded connection failed");
+            forward.state.last_error = Some(message);
+        }
+    }
+
+    /// Handles `response` if it belongs to a tunnel, otherwise hands it back.
+    fn handle(&self, response: RpcResponse) -> Option<RpcResponse> {
+        let request_id = response.request_id;
+        let mut table = self.table();
+        let quiet = table.pending.remove(&request_id);
+        match response.result {
+            RpcResult::TunnelData { tunnel_id, seq, data } => {
+                if let Some(tunnel) = table.tunnels.get(&tunnel_id) {
+                    let _ = tunnel.chunks.send((seq, data));
+                }
+            }
+            RpcResult::TunnelSent { tunnel_id, .. } => {
+                if let Some(tunnel) = table.tunnels.get(&tunnel_id) {
+                    tunnel.window.add_permits(1);
+                }
+            }
+            RpcResult::TunnelOpened { .. } => {}
+            RpcResult::TunnelListening { listener_id, port } => {
+                if let Some(forward) = table.forward_of_listener(listener_id) {
+                    info!(id = %forward.spec.id, port, "forwarding remote port");
+                    forward.state.status = ForwardStatus::Listening { port };
+                }
+                drop(table);
+                self.report();
+            }
+            RpcResult::TunnelAccepted {
+                listener_id,
+                tunnel_id,
+            } => {
+                let Some(forward) = table.forward_of_listener(listener_id) else {
+                    drop(table);
+                    self.request_quietly(RpcAction::CloseTunnel { tunnel_id });
+                    return None;
+                };
+                let forward_id = forward.spec.id;
+                let target = (forward.spec.host.clone(), forward.spec.port);
+                drop(table);
+                let chunks = self.add_tunnel(forward_id, tunnel_id);
+                self.spawn_tunnel(tunnel_id, chunks, TcpStream::connect(target));
+            }
+            RpcResult::TunnelClosed { tunnel_id, error } => {
+                // Dropping the tunnel's sender lets it write out what already arrived.
+                let tunnel = table.tunnels.remove(&tunnel_id);
+                if let Some(forward) = table.forward_of_listener(tunnel_id)
+                    && let Some(error) = &error
+                {
+                    forward.state.status = ForwardStatus::Failed(error.clone());
+                } else if let Some(error) = error
+                    && let Some(forward) = tunnel.and_then(|tunnel| table.forwards.get_mut(&tunnel.forward_id))
+                {
+                    forward.state.last_error = Some(error);
+                }
+                drop(table);
+                self.report();
+            }
+            RpcResult::Error { message } if !quiet => {
+                if let Some(tunnel) = table.tunnels.remove(&request_id) {
+                    if let Some(forward) = table.forwards.get_mut(&tunnel.forward_id) {
+                        warn!(%message, "failed to open forwarded connection");
+                        forward.state.last_error = Some(message);
+                    }
+                } else if let Some(forward) = table.forward_of_listener(request_id) {
+                    warn!(%message, "failed to start remote port forward");
+                    forward.state.status = ForwardStatus::Failed(message);
+                } else {
+                    return Some(RpcResponse {
+                        request_id,
+                        result: RpcResult::Error { message },
+                    });
+                }
+                drop(table);
+                self.report();
+            }
+            result if !quiet => return Some(RpcResponse { request_id, result }),
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,51 @@
+//! Connection engine shared by the workspace client and third-party tools.
+//!
+//! [`Connection`] is the async entry point: it authenticates with the proxy, joins a
+//! server session (upgrading to a WebRTC data channel when allowed) and exchanges
+//! [`RpcRequest`](rs_peer_workspace_shared::app::RpcRequest)s with the server.
+//!
+//! ```no_run
+//! use rs_peer_workspace_core::Connection;
+//! use rs_peer_workspace_shared::app::RpcAction;
+//! use rs_peer_workspace_shared::project::ProjectConnection;
+//!
+//! # async fn example(connection: ProjectConnection) -> anyhow::Result<()> {
+//! let session = Connection::connect(connection).await?;
+//! let result = session.request(RpcAction::ListRoots).await?;
+//! println!("{result:?}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Workspace`] loads a saved `.rpw` project, resolving encrypted and keyring-backed
+//! credentials, so automation can reuse the connections and terminal profiles set up
+//! in the client:
+//!
+//! ```no_run
+//! use rs_peer_workspace_core::Workspace;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let workspace = Workspace::open("workspace.rpw", None)?;
+//! let session = workspace.connect("build-box").await?;
+//! let profile = workspace.terminal_profile("build-box", "tests").cloned().unwrap_or_default();
+//! let result = session.run_in_profile(&profile, "cargo test").await?;
+//! println!("{}", result.output);
+//! # Ok(())
+//! # }
+//! ```
+
+mod connection;
+pub mod credentials;
+mod engine;
+mod forward;
+pub mod transfer;
+mod tunnel;
+mod workspace;
+
+pub use connection::{CommandOutput, Connection};
+pub use engine::{
+    list_servers, spawn_connection, spawn_connection_test, spawn_discovery, spawn_server_listing,
+    test_connection, ConnectionCommand, ConnectionEvent, ConnectionTest, EventSink, TransportStats,
+};
+pub use forward::{ForwardState, ForwardStatus};
+pub use workspace::Workspace;
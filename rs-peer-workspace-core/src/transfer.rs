@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+pub const MIN_CHUNK_SIZE: u64 = 16 * 1024;
+pub const MAX_CHUNK_SIZE: u64 = 1024 * 1024;
+pub const MAX_WINDOW: usize = 8;
+
+const INITIAL_CHUNK_SIZE: u64 = 64 * 1024;
+const JITTER_RATIO: f64 = 0.5;
+const CHANGE_THRESHOLD: f64 = 1.1;
+
+/// Adapts chunk size and the number of chunks in flight to the measured path.
+///
+/// Chunk size is hill-climbed on throughput: once a full window of chunks has
+/// completed, the size keeps moving in the same direction while throughput improves
+/// and reverses when it drops. Heavy RTT jitter, typical of lossy TURN relays,
+/// always shrinks chunks. The window tracks the bandwidth-delay product.
+#[derive(Debug, Clone)]
+pub struct ChunkTuner {
+    chunk_size: u64,
+    window: usize,
+    growing: bool,
+    srtt: Option<Duration>,
+    rttvar: Duration,
+    round_started: Instant,
+    round_bytes: u64,
+    round_chunks: usize,
+    throughput: f64,
+}
+
+impl Default for ChunkTuner {
+    fn default() -> Self {
+        Self {
+            chunk_size: INITIAL_CHUNK_SIZE,
+            window: 2,
+            growing: true,
+            srtt: None,
+            rttvar: Duration::ZERO,
+            round_started: Instant::now(),
+            round_bytes: 0,
+            round_chunks: 0,
+            throughput: 0.0,
+        }
+    }
+}
+
+impl ChunkTuner {
+    /// Size to request for the next chunk, in bytes.
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    /// Number of chunks that may be outstanding at once.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Smoothed round-trip time of completed chunks.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// Throughput of the last completed round, in bytes per second.
+    pub fn throughput(&self) -> f64 {
+        self.throughput
+    }
+
+    /// Records a completed chunk of `bytes` that took `rtt` from request to response.
+    pub fn record(&mut self, bytes: u64, rtt: Duration) {
+        match self.srtt {
+            Some(srtt) => {
+                self.rttvar = self.rttvar.mul_f64(0.75) + srtt.abs_diff(rtt).mul_f64(0.25);
+                self.srtt = Some(srtt.mul_f64(0.875) + rtt.mul_f64(0.125));
+            }
+            None => {
+                self.srtt = Some(rtt);
+                self.rttvar = rtt / 2;
+            }
+        }
+        self.round_bytes += bytes;
+        self.round_chunks += 1;
+        if self.round_chunks >= self.window {
+            self.finish_round();
+        }
+    }
+
+    /// Backs off after a failed chunk.
+    pub fn record_failure(&mut self) {
+        self.resize(false);
+        self.window = 1;
+        self.reset_round();
+    }
+
+    fn finish_round(&mut self) {
+        let elapsed = self.round_started.elapsed().as_secs_f64().max(0.001);
+        let throughput = self.round_bytes as f64 / elapsed;
+        let Some(srtt) = self.srtt else {
+            return;
+        };
+
+        let jittery = self.rttvar.as_secs_f64() > srtt.as_secs_f64() * JITTER_RATIO;
+        if jittery {
+            self.resize(false);
+        } else if throughput > self.throughput * CHANGE_THRESHOLD {
+            self.resize(self.growing);
+        } else if throughput * CHANGE_THRESHOLD < self.throughput {
+            self.resize(!self.growing);
+        }
+
+        let bdp = throughput * srtt.as_secs_f64() / self.chunk_size as f64;
+        self.window = (bdp.ceil() as usize + 1).clamp(1, MAX_WINDOW);
+        self.throughput = throughput;
+        self.reset_round();
+    }
+
+    fn resize(&mut self, grow: bool) {
+        self.growing = grow;
+        let resized = if grow {
+            (self.chunk_size * 2).min(MAX_CHUNK_SIZE)
+        } else {
+            (self.chunk_size / 2).max(MIN_CHUNK_SIZE)
+        };
+        if resized != self.chunk_size {
+            self.chunk_size = resized;
+            self.srtt = None;
+        }
+    }
+
+    fn reset_round(&mut self) {
+        self.round_started = Instant::now();
+        self.round_bytes = 0;
+        self.round_chunks = 0;
+    }
+}
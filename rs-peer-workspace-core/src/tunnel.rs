@@ -0,0 +1,180 @@
+use std::net::IpAddr;
+
+use anyhow::Context;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rs_peer_workspace_shared::project::{OutboundProxy, OutboundProxyKind};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{client_async_tls, connect_async, MaybeTlsStream, WebSocketStream};
+
+const MAX_RESPONSE_HEAD: usize = 16 * 1024;
+
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Opens the WebSocket to `url`, tunnelled through `outbound` when it is set. TLS for
+/// `wss://` addresses runs inside the tunnel, so the outbound proxy never sees it.
+pub(crate) async fn connect_websocket(
+    url: &str,
+    outbound: Option<&OutboundProxy>,
+) -> anyhow::Result<WsStream> {
+    let Some(outbound) = outbound else {
+        let (stream, _) = connect_async(url).await?;
+        return Ok(stream);
+    };
+
+    let request = url.into_client_request()?;
+    let uri = request.uri();
+    let host = uri
+        .host()
+        .ok_or_else(|| anyhow::anyhow!("proxy address {url} has no host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+
+    let mut stream = TcpStream::connect(&outbound.addr)
+        .await
+        .with_context(|| format!("failed to reach outbound proxy {}", outbound.addr))?;
+    match outbound.kind {
+        OutboundProxyKind::Http => http_connect(&mut stream, &host, port, outbound).await?,
+        OutboundProxyKind::Socks5 => socks5_connect(&mut stream, &host, port, outbound).await?,
+    }
+    let (stream, _) = client_async_tls(request, stream).await?;
+    Ok(stream)
+}
+
+async fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    proxy: &OutboundProxy,
+) -> anyhow::Result<()> {
+    let authority = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if !proxy.username.is_empty() {
+        let credentials = STANDARD.encode(format!("{}:{}", proxy.username, proxy.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {credentials}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Byte by byte, so nothing the target sends after the response head is consumed.
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_RESPONSE_HEAD {
+            anyhow::bail!("outbound proxy sent an oversized response");
+        }
+        head.push(
+            stream
+                .read_u8()
+                .await
+                .context("outbound proxy closed the connection")?,
+        );
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        anyhow::bail!("outbound proxy refused the tunnel: {status}");
+    }
+    Ok(())
+}
+
+/// SOCKS5 `CONNECT` (RFC 1928) with optional username/password authentication
+/// (RFC 1929). Host names are resolved by the proxy.
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    proxy: &OutboundProxy,
+) -> anyhow::Result<()> {
+    let authenticate = !proxy.username.is_empty();
+    let method = if authenticate { 0x02 } else { 0x00 };
+    stream.write_all(&[0x05, 0x01, method]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply != [0x05, method] {
+        anyhow::bail!(if authenticate {
+            "SOCKS proxy does not accept username/password authentication"
+        } else {
+            "SOCKS proxy requires authentication"
+        });
+    }
+
+    if authenticate {
+        let (username, password) = (proxy.username.as_bytes(), proxy.password.as_bytes());
+        if username.len() > 255 || password.len() > 255 {
+            anyhow::bail!("SOCKS username and password are limited to 255 bytes");
+        }
+        let mut message = vec![0x01, username.len() as u8];
+        message.extend_from_slice(username);
+        message.push(password.len() as u8);
+        message.extend_from_slice(password);
+        stream.write_all(&message).await?;
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            anyhow::bail!("SOCKS proxy rejected the credentials");
+        }
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                anyhow::bail!("host name {host} is too long for SOCKS");
+            }
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        anyhow::bail!(
+            "SOCKS proxy could not connect to {host}:{port}: {}",
+            socks_error(reply[1])
+        );
+    }
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => anyhow::bail!("SOCKS proxy replied with unknown address type {other}"),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+fn socks_error(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
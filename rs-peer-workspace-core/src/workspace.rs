@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use anyhow::Context;
+use rs_peer_workspace_shared::project::{ProjectConnection, ProjectFile, TerminalProfile};
+use rs_peer_workspace_shared::secrets::{unseal_credentials, PASSPHRASE_ENV};
+
+use crate::connection::Connection;
+use crate::credentials;
+
+/// A loaded project with every connection's credentials resolved.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    project: ProjectFile,
+}
+
+impl Workspace {
+    /// Reads a `.rpw` project file. Encrypted credentials are unsealed with `passphrase`,
+    /// or with `RS_PEER_PROJECT_PASSPHRASE` when it is `None`; keyring-backed passwords
+    /// are fetched from the system keyring.
+    pub fn open(path: impl AsRef<Path>, passphrase: Option<&str>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read project {}", path.display()))?;
+        let project: ProjectFile = ron::from_str(&text)
+            .with_context(|| format!("failed to parse project {}", path.display()))?;
+        Self::from_project(project, passphrase)
+    }
+
+    /// Same as [`Workspace::open`] for a project that is already in memory.
+    pub fn from_project(mut project: ProjectFile, passphrase: Option<&str>) -> anyhow::Result<Self> {
+        if project.encrypted_credentials.is_some() {
+            let passphrase = match passphrase {
+                Some(passphrase) => passphrase.to_string(),
+                None => std::env::var(PASSPHRASE_ENV).map_err(|_| {
+                    anyhow::anyhow!("project credentials are encrypted; set {PASSPHRASE_ENV}")
+                })?,
+            };
+            unseal_credentials(&mut project, &passphrase)?;
+        }
+        for connection in &mut project.connections {
+            credentials::load(connection).with_context(|| {
+                format!("failed to read credentials for '{}' from system keyring", connection.name)
+            })?;
+        }
+        Ok(Self { project })
+    }
+
+    pub fn project(&self) -> &ProjectFile {
+        &self.project
+    }
+
+    pub fn connections(&self) -> &[ProjectConnection] {
+        &self.project.connections
+    }
+
+    pub fn connection(&self, name: &str) -> Option<&ProjectConnection> {
+        self.project
+            .connections
+            .iter()
+            .find(|connection| connection.name == name)
+    }
+
+    /// Terminal profile `profile` of connection `connection_name`.
+    pub fn terminal_profile(&self, connection_name: &str, profile: &str) -> Option<&TerminalProfile> {
+        self.connection(connection_name)?
+            .terminal_profiles
+            .iter()
+            .find(|item| item.name == profile)
+    }
+
+    /// Opens a session to the named connection.
+    pub async fn connect(&self, name: &str) -> anyhow::Result<Connection> {
+        let connection = self
+            .connection(name)
+            .with_context(|| format!("connection '{name}' not found in project"))?;
+        Connection::connect(connection.clone()).await
+    }
+}
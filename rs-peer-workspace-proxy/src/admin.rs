@@ -0,0 +1,251 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rs_peer_workspace_shared::relay::{constant_time_eq, AuthRole, ProxyToPeer, ServerMetadata};
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::snapshot::ProxySnapshot;
+use crate::throttle::Ban;
+use crate::users::UserRole;
+use crate::{accepts_passwords, client_ip, send_to_connection, AppState};
+
+#[derive(Debug, Serialize)]
+struct ServerSummary {
+    server_name: String,
+    conn_id: Uuid,
+    session_count: usize,
+    busy: bool,
+    metadata: ServerMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionSummary {
+    session_id: Uuid,
+    server_name: Option<String>,
+    server_conn_id: Uuid,
+    client_conn_id: Uuid,
+    username: Option<String>,
+    via_p2p: bool,
+    idle_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ConnectionSummary {
+    conn_id: Uuid,
+    role: Option<AuthRole>,
+    username: Option<String>,
+    user_role: Option<UserRole>,
+    server_name: Option<String>,
+    queue_depth: usize,
+    dropped_messages: u64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/servers", get(list_servers))
+        .route("/admin/sessions", get(list_sessions))
+        .route("/admin/sessions/{session_id}", delete(close_session))
+        .route("/admin/connections", get(list_connections))
+        .route("/admin/state", get(export_state).put(import_state))
+        .route("/admin/bans", get(list_bans))
+        .route("/admin/bans/{ip}", delete(lift_ban))
+}
+
+/// Admits requests carrying the admin token as a bearer token, or the username and
+/// password of an `admin` account from the users file with basic authentication,
+/// which is only accepted over TLS, like `login`.
+/// Wrong credentials count toward the same per-address lockout as failed logins.
+async fn authorize(app: &AppState, peer_addr: SocketAddr, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let admins = app.users.clone().filter(|users| users.has_admin());
+    if app.admin_token.is_none() && admins.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let ip = client_ip(app, peer_addr, headers);
+    let throttled = app.state.lock().await.throttle.check(ip);
+    if let Err(reason) = throttled {
+        warn!(%ip, %reason, "admin request refused");
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let Some(provided) = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    if let Some(expected) = &app.admin_token
+        && provided
+            .strip_prefix("Bearer ")
+            .is_some_and(|token| constant_time_eq(expected, token))
+    {
+        return Ok(());
+    }
+    if let Some(users) = admins
+        && let Some((username, password)) = basic_credentials(provided)
+    {
+        // Same rule as `login`: an admin password only travels over TLS.
+        if !accepts_passwords(app, peer_addr, headers) {
+            warn!(%ip, "refused admin basic authentication without TLS");
+            return Err(StatusCode::FORBIDDEN);
+        }
+        // Argon2 is deliberately slow, so keep it off the async workers.
+        let role = tokio::task::spawn_blocking(move || users.verify(&username, &password))
+            .await
+            .ok()
+            .flatten();
+        if role == Some(UserRole::Admin) {
+            return Ok(());
+        }
+    }
+    app.state.lock().await.throttle.record_failure(ip, "invalid admin credentials");
+    Err(StatusCode::UNAUTHORIZED)
+}
+
+fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let decoded = STANDARD.decode(header.strip_prefix("Basic ")?).ok()?;
+    let (username, password) = std::str::from_utf8(&decoded).ok()?.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+async fn list_servers(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ServerSummary>>, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    let state = app.state.lock().await;
+    let mut servers: Vec<ServerSummary> = state
+        .servers
+        .iter()
+        .map(|(name, registration)| ServerSummary {
+            server_name: name.clone(),
+            conn_id: registration.conn_id,
+            session_count: state
+                .sessions
+                .values()
+                .filter(|session| session.server_conn_id == registration.conn_id)
+                .count(),
+            busy: registration.busy,
+            metadata: registration.metadata.clone(),
+        })
+        .collect();
+    servers.sort_by(|a, b| a.server_name.cmp(&b.server_name));
+    Ok(Json(servers))
+}
+
+async fn list_sessions(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SessionSummary>>, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    let state = app.state.lock().await;
+    let sessions = state
+        .sessions
+        .values()
+        .map(|session| SessionSummary {
+            session_id: session.session_id,
+            server_name: state.server_name_for(session.server_conn_id),
+            server_conn_id: session.server_conn_id,
+            client_conn_id: session.client_conn_id,
+            username: session.username.clone(),
+            via_p2p: session.via_p2p,
+            idle_secs: session.last_activity.elapsed().as_secs(),
+        })
+        .collect();
+    Ok(Json(sessions))
+}
+
+async fn list_connections(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ConnectionSummary>>, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    let state = app.state.lock().await;
+    let connections = state
+        .connections
+        .iter()
+        .map(|(conn_id, outgoing)| ConnectionSummary {
+            conn_id: *conn_id,
+            role: state.conn_roles.get(conn_id).cloned(),
+            username: state.conn_users.get(conn_id).map(|(name, _)| name.clone()),
+            user_role: state.conn_users.get(conn_id).map(|(_, role)| *role),
+            server_name: state.server_name_for(*conn_id),
+            queue_depth: outgoing.depth(),
+            dropped_messages: outgoing.dropped(),
+        })
+        .collect();
+    Ok(Json(connections))
+}
+
+async fn close_session(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    let Some(session) = app.state.lock().await.sessions.remove(&session_id) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    for target in [session.client_conn_id, session.server_conn_id] {
+        let _ = send_to_connection(&app.state, target, &ProxyToPeer::SessionClosed {
+            session_id,
+            reason: "closed by administrator".to_string(),
+        }).await;
+    }
+    info!(%session_id, "admin closed session");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn export_state(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<ProxySnapshot>, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    Ok(Json(app.state.lock().await.export()))
+}
+
+async fn import_state(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(snapshot): Json<ProxySnapshot>,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    let imported = app.state.lock().await.import(snapshot, app.resume_grace);
+    info!(imported, "admin imported server registrations");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_bans(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Ban>>, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    Ok(Json(app.state.lock().await.throttle.bans().to_vec()))
+}
+
+async fn lift_ban(
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(ip): Path<IpAddr>,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&app, peer_addr, &headers).await?;
+    if app.state.lock().await.throttle.unban(ip) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
@@ -0,0 +1,93 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use rs_peer_workspace_shared::discovery::{self, MDNS_ADDR};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
+
+/// Answers mDNS queries for the proxy service so clients on the LAN can find the
+/// proxy listening on `addr`. Gives up with a warning when multicast is unavailable.
+pub fn spawn(name: String, addr: SocketAddr) {
+    tokio::spawn(async move {
+        if let Err(err) = run(&name, addr).await {
+            warn!(error = %err, "LAN announcement unavailable");
+        }
+    });
+}
+
+async fn run(name: &str, addr: SocketAddr) -> anyhow::Result<()> {
+    let socket = bind_mdns()?;
+    if let Some(local) = announced_addr(addr, SocketAddr::V4(MDNS_ADDR)) {
+        socket
+            .send_to(&discovery::response(0, false, name, local), MDNS_ADDR)
+            .await?;
+        info!(name, url = %format!("ws://{local}/ws"), "announcing proxy on the LAN");
+    }
+
+    let mut buf = [0u8; 9000];
+    loop {
+        // One bad datagram, or an ICMP error reported for an earlier reply, must not end
+        // the announcement for good.
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                debug!(error = %err, "failed to receive LAN discovery query");
+                continue;
+            }
+        };
+        let Some(query) = discovery::parse_query(&buf[..len]) else {
+            continue;
+        };
+        let Some(local) = announced_addr(addr, peer) else {
+            continue;
+        };
+        // Queriers on other ports are one-shot resolvers that only listen for a
+        // unicast reply carrying their question.
+        let legacy = peer.port() != MDNS_ADDR.port();
+        let (id, target) = if legacy {
+            (query.id, peer)
+        } else if query.unicast {
+            (0, peer)
+        } else {
+            (0, SocketAddr::V4(MDNS_ADDR))
+        };
+        debug!(%peer, "answering LAN discovery query");
+        if let Err(err) = socket
+            .send_to(&discovery::response(id, legacy, name, local), target)
+            .await
+        {
+            warn!(%target, error = %err, "failed to answer LAN discovery query");
+        }
+    }
+}
+
+fn bind_mdns() -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Other responders, such as the system's own, usually hold port 5353 as well.
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_ADDR.port()).into())?;
+    socket.join_multicast_v4(MDNS_ADDR.ip(), &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+/// Address to announce to `peer`: the bind address, or for a wildcard bind the
+/// local address the system routes towards `peer` from.
+fn announced_addr(addr: SocketAddr, peer: SocketAddr) -> Option<SocketAddrV4> {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => ip,
+        IpAddr::V4(_) => {
+            let probe = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+            probe.connect(peer).ok()?;
+            match probe.local_addr().ok()?.ip() {
+                IpAddr::V4(ip) if !ip.is_unspecified() => ip,
+                _ => return None,
+            }
+        }
+        IpAddr::V6(_) => return None,
+    };
+    Some(SocketAddrV4::new(ip, addr.port()))
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+/// Token bucket refilled at `rate` units per second and holding at most one second's
+/// worth. A take is allowed while the bucket is not empty and may drive it into debt,
+/// so a single message larger than the per-second allowance still gets through once
+/// the bucket has refilled.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// `None` when `rate` is 0, which means unlimited.
+    pub fn new(rate: u64) -> Option<Self> {
+        (rate > 0).then(|| Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            refilled: Instant::now(),
+        })
+    }
+
+    /// How long until the bucket is no longer empty; zero when a take would succeed now.
+    pub fn wait(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+        if self.tokens > 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-self.tokens / self.rate).max(Duration::from_millis(1))
+    }
+
+    pub fn take(&mut self, amount: u64) {
+        self.tokens -= amount as f64;
+    }
+}
+
+/// Per-connection message and byte rate limits.
+#[derive(Debug)]
+pub struct ConnectionLimits {
+    messages: Option<RateLimiter>,
+    bytes: Option<RateLimiter>,
+}
+
+impl ConnectionLimits {
+    pub fn new(messages_per_sec: u64, bytes_per_sec: u64) -> Self {
+        Self {
+            messages: RateLimiter::new(messages_per_sec),
+            bytes: RateLimiter::new(bytes_per_sec),
+        }
+    }
+
+    /// Charges one message of `len` bytes. When either bucket is empty nothing is
+    /// charged and the time until both have refilled is returned instead, so the caller
+    /// can hold the message back rather than drop it.
+    pub fn admit(&mut self, len: usize) -> Result<(), Duration> {
+        let wait = [&mut self.messages, &mut self.bytes]
+            .into_iter()
+            .flatten()
+            .map(RateLimiter::wait)
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        if let Some(messages) = &mut self.messages {
+            messages.take(1);
+        }
+        if let Some(bytes) = &mut self.bytes {
+            bytes.take(len as u64);
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,37 @@
+mod admin;
+mod announce;
+mod limits;
+mod replay;
+mod snapshot;
+mod throttle;
+mod users;
+
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
+use axum::extract::{ConnectInfo, State};
+use axum::http::HeaderMap;
 use axum::response::Response;
 use axum::routing::get;
 use axum::Router;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use futures::{SinkExt, StreamExt};
-use rs_peer_workspace_shared::relay::{AuthRole, PeerToProxy, ProxyToPeer, TurnCredentials};
-use tokio::sync::{mpsc, Mutex};
+use limits::ConnectionLimits;
+use replay::ReplayGuard;
+use rs_peer_workspace_shared::relay::{
+    verify_proof, AuthRole, PeerToProxy, ProxyToPeer, RegistrationOutcome, ServerInfo, ServerMetadata,
+    SignalPayload, TurnCredentials,
+};
+use rs_peer_workspace_shared::outbox::{self, Outbox, Priority};
+use rs_peer_workspace_shared::telemetry;
+use throttle::{AuthThrottle, ThrottleSettings};
+use tokio::sync::Mutex;
+use tracing::{debug, info, info_span, warn, Instrument};
+use users::{UserRole, Users};
 use uuid::Uuid;
 
 #[derive(Parser, Debug, Clone)]
@@ -20,6 +41,10 @@ struct Args {
     bind: String,
     #[arg(long)]
     proxy_password: String,
+    /// JSON list of client accounts; clients must then log in with a username and
+    /// password instead of the proxy password, which stays in use for servers.
+    #[arg(long)]
+    users_file: Option<PathBuf>,
     #[arg(long)]
     turn_url: Option<String>,
     #[arg(long, default_value = "3478")]
@@ -30,12 +55,96 @@ struct Args {
     turn_username: String,
     #[arg(long, default_value = "peer-secret")]
     turn_password: String,
+    #[arg(long)]
+    admin_token: Option<String>,
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+    #[arg(long, default_value = "300")]
+    resume_grace_secs: u64,
+    /// How far auth and signal timestamps may drift from the proxy clock.
+    #[arg(long, default_value = "120")]
+    replay_window_secs: u64,
+    #[arg(long)]
+    log_json: bool,
+    /// Answer mDNS queries so clients on the LAN can find this proxy.
+    #[arg(long)]
+    announce: bool,
+    /// Name announced on the LAN; defaults to the host name.
+    #[arg(long)]
+    announce_name: Option<String>,
+    /// How often registered servers are pinged; 0 turns liveness probing off.
+    #[arg(long, default_value = "20")]
+    ping_interval_secs: u64,
+    /// Unanswered pings in a row after which a server's registration is dropped.
+    #[arg(long, default_value = "3")]
+    ping_max_missed: u32,
+    /// What to do when a server registers a name that is already taken.
+    #[arg(long, value_enum, default_value_t = NameConflict::Reject)]
+    on_name_conflict: NameConflict,
+    /// Close relayed sessions that carry no traffic for this long; 0 keeps them open.
+    #[arg(long, default_value = "0")]
+    session_idle_timeout_secs: u64,
+    /// WebSocket messages a single connection may send per second; 0 is unlimited.
+    #[arg(long, default_value = "0")]
+    rate_limit_messages: u64,
+    /// Bytes a single connection may send per second; 0 is unlimited.
+    #[arg(long, default_value = "0")]
+    rate_limit_bytes: u64,
+    /// Largest `relay_data` payload the proxy forwards.
+    #[arg(long, default_value = "16777216")]
+    max_payload_bytes: usize,
+    /// Messages queued for a slow peer before relayed data is dropped, oldest first.
+    #[arg(long, default_value_t = outbox::DEFAULT_CAPACITY)]
+    outgoing_queue_capacity: usize,
+    /// Failed authentications from one address before it is locked out.
+    #[arg(long, default_value = "5")]
+    auth_max_failures: u32,
+    /// First lockout of an address; each further lockout doubles it.
+    #[arg(long, default_value = "30")]
+    auth_lockout_secs: u64,
+    #[arg(long, default_value = "3600")]
+    auth_lockout_max_secs: u64,
+    /// Ban addresses that keep getting locked out and keep the bans in this file.
+    #[arg(long)]
+    ban_file: Option<PathBuf>,
+    #[arg(long, default_value = "3")]
+    ban_after_lockouts: u32,
+    /// Wrong server passwords one connection may send before it is disconnected.
+    #[arg(long, default_value = "3")]
+    max_connect_failures: u32,
+    /// Take client addresses from `X-Forwarded-For`, when behind a reverse proxy.
+    #[arg(long)]
+    trust_forwarded_for: bool,
+    /// Accept `login` over plain `ws://` from other hosts, such as inside a VPN.
+    #[arg(long)]
+    allow_insecure_login: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum NameConflict {
+    /// Refuse the new registration.
+    Reject,
+    /// Disconnect the server holding the name and register the new one.
+    Replace,
+    /// Register the new server as `<name>-2`, `<name>-3`, ...
+    Suffix,
 }
 
 #[derive(Debug, Clone)]
 struct ServerRegistration {
     conn_id: Uuid,
     server_password: String,
+    metadata: ServerMetadata,
+    busy: bool,
+    resume_token: String,
+}
+
+#[derive(Debug, Clone)]
+struct Reservation {
+    server_password: String,
+    resume_token: String,
+    metadata: ServerMetadata,
+    expires: Instant,
 }
 
 #[derive(Debug, Clone)]
@@ -43,65 +152,276 @@ struct Session {
     session_id: Uuid,
     server_conn_id: Uuid,
     client_conn_id: Uuid,
+    /// Account the client logged in with, if the proxy has a users file.
+    username: Option<String>,
+    via_p2p: bool,
+    last_activity: Instant,
+}
+
+impl Session {
+    /// The other side of the session, when `conn_id` is its `role` side.
+    fn peer_of(&self, conn_id: Uuid, role: &AuthRole) -> Option<Uuid> {
+        match role {
+            AuthRole::Client if self.client_conn_id == conn_id => Some(self.server_conn_id),
+            AuthRole::Server if self.server_conn_id == conn_id => Some(self.client_conn_id),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
 struct ProxyState {
-    connections: HashMap<Uuid, mpsc::UnboundedSender<Message>>,
+    connections: HashMap<Uuid, Outbox<Message>>,
     conn_roles: HashMap<Uuid, AuthRole>,
+    conn_users: HashMap<Uuid, (String, UserRole)>,
     servers: HashMap<String, ServerRegistration>,
+    reserved: HashMap<String, Reservation>,
     sessions: HashMap<Uuid, Session>,
+    replays: ReplayGuard,
+    throttle: AuthThrottle,
 }
 
 impl ProxyState {
-    fn new() -> Self {
+    fn new(replay_window_secs: u64, throttle: AuthThrottle) -> Self {
         Self {
             connections: HashMap::new(),
             conn_roles: HashMap::new(),
+            conn_users: HashMap::new(),
             servers: HashMap::new(),
+            reserved: HashMap::new(),
             sessions: HashMap::new(),
+            replays: ReplayGuard::new(replay_window_secs),
+            throttle,
         }
     }
+
+    fn server_name_for(&self, conn_id: Uuid) -> Option<String> {
+        self.servers
+            .iter()
+            .find(|(_, registration)| registration.conn_id == conn_id)
+            .map(|(name, _)| name.clone())
+    }
+
+    fn name_taken(&self, name: &str) -> bool {
+        self.servers.contains_key(name) || self.reserved.contains_key(name)
+    }
+
+    /// Looks up the peer on the other side of `session_id` from `conn_id`, speaking as
+    /// `role`, and marks the session active. Returns `None` unless `conn_id` is that side
+    /// of the session, so nobody can inject data into a session they are not part of.
+    fn relay_target(&mut self, session_id: Uuid, conn_id: Uuid, role: &AuthRole) -> Option<Uuid> {
+        let session = self.sessions.get_mut(&session_id)?;
+        let target = session.peer_of(conn_id, role)?;
+        session.last_activity = Instant::now();
+        Some(target)
+    }
+
+    /// Like [`ProxyState::relay_target`] for WebRTC signaling, which only P2P sessions
+    /// carry: offers come from the client and answers from the server.
+    fn signal_target(
+        &mut self,
+        session_id: Uuid,
+        conn_id: Uuid,
+        role: &AuthRole,
+        signal: &SignalPayload,
+    ) -> Option<Uuid> {
+        let allowed = match signal {
+            SignalPayload::SdpOffer { .. } => *role == AuthRole::Client,
+            SignalPayload::SdpAnswer { .. } => *role == AuthRole::Server,
+            SignalPayload::IceCandidate { .. } => true,
+        };
+        if !allowed || !self.sessions.get(&session_id)?.via_p2p {
+            return None;
+        }
+        self.relay_target(session_id, conn_id, role)
+    }
+
+    /// Removes `session_id` when `conn_id` is its `role` side and returns the other side.
+    fn close_session(&mut self, session_id: Uuid, conn_id: Uuid, role: &AuthRole) -> Option<Uuid> {
+        let peer = self.sessions.get(&session_id)?.peer_of(conn_id, role)?;
+        self.sessions.remove(&session_id);
+        Some(peer)
+    }
+
+    /// Removes relayed sessions that have been quiet for longer than `timeout`. P2P
+    /// sessions only pass signaling through the proxy, so they are left alone.
+    fn take_idle_sessions(&mut self, timeout: Duration) -> Vec<Session> {
+        let now = Instant::now();
+        let idle: Vec<Uuid> = self
+            .sessions
+            .values()
+            .filter(|session| !session.via_p2p && now.duration_since(session.last_activity) >= timeout)
+            .map(|session| session.session_id)
+            .collect();
+        idle.iter().filter_map(|session_id| self.sessions.remove(session_id)).collect()
+    }
+
+    fn server_infos(&self) -> Vec<ServerInfo> {
+        let mut servers: Vec<ServerInfo> = self
+            .servers
+            .iter()
+            .map(|(name, registration)| ServerInfo {
+                name: name.clone(),
+                metadata: registration.metadata.clone(),
+            })
+            .collect();
+        servers.sort_by(|a, b| a.name.cmp(&b.name));
+        servers
+    }
 }
 
 #[derive(Debug, Clone)]
 struct AppState {
     proxy_password: String,
+    users: Option<Arc<Users>>,
+    admin_token: Option<String>,
     turn: Option<TurnCredentials>,
+    resume_grace: Duration,
+    on_name_conflict: NameConflict,
+    ping_interval: Option<Duration>,
+    ping_max_missed: u32,
+    rate_limit_messages: u64,
+    rate_limit_bytes: u64,
+    max_payload_bytes: usize,
+    outgoing_queue_capacity: usize,
+    max_connect_failures: u32,
+    trust_forwarded_for: bool,
+    allow_insecure_login: bool,
     state: Arc<Mutex<ProxyState>>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    telemetry::init("info", args.log_json);
     let addr: SocketAddr = args.bind.parse()?;
     let advertised_turn_url = resolve_turn_url(&args).await;
 
+    let throttle = AuthThrottle::new(
+        ThrottleSettings {
+            max_failures: args.auth_max_failures.max(1),
+            lockout: Duration::from_secs(args.auth_lockout_secs),
+            max_lockout: Duration::from_secs(args.auth_lockout_max_secs),
+            ban_after_lockouts: args.ban_after_lockouts.max(1),
+        },
+        args.ban_file.clone(),
+    )?;
+    let mut proxy_state = ProxyState::new(args.replay_window_secs, throttle);
+    let resume_grace = Duration::from_secs(args.resume_grace_secs);
+    if let Some(path) = &args.state_file
+        && let Some(snapshot) = snapshot::load(path)?
+    {
+        let imported = proxy_state.import(snapshot, resume_grace);
+        info!(path = %path.display(), imported, "restored server registrations");
+    }
+
+    let users = match &args.users_file {
+        Some(path) => {
+            let users = Users::load(path)?;
+            info!(path = %path.display(), users = users.len(), "loaded user accounts; clients must log in");
+            Some(Arc::new(users))
+        }
+        None => None,
+    };
+
     let app_state = AppState {
         proxy_password: args.proxy_password,
+        users,
+        admin_token: args.admin_token.filter(|token| !token.is_empty()),
         turn: advertised_turn_url.map(|url| TurnCredentials {
             url,
             username: args.turn_username,
             password: args.turn_password,
         }),
-        state: Arc::new(Mutex::new(ProxyState::new())),
+        resume_grace,
+        on_name_conflict: args.on_name_conflict,
+        ping_interval: (args.ping_interval_secs > 0).then(|| Duration::from_secs(args.ping_interval_secs)),
+        ping_max_missed: args.ping_max_missed.max(1),
+        rate_limit_messages: args.rate_limit_messages,
+        rate_limit_bytes: args.rate_limit_bytes,
+        max_payload_bytes: args.max_payload_bytes,
+        outgoing_queue_capacity: args.outgoing_queue_capacity,
+        max_connect_failures: args.max_connect_failures.max(1),
+        trust_forwarded_for: args.trust_forwarded_for,
+        allow_insecure_login: args.allow_insecure_login,
+        state: Arc::new(Mutex::new(proxy_state)),
     };
+    if args.session_idle_timeout_secs > 0 {
+        tokio::spawn(expire_idle_sessions(
+            app_state.state.clone(),
+            Duration::from_secs(args.session_idle_timeout_secs),
+        ));
+    }
 
     let app = Router::new()
         .route("/ws", get(ws_handler))
+        .merge(admin::router())
         .with_state(app_state.clone());
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("proxy listening on {}", addr);
+    info!(%addr, "proxy listening");
     if let Some(turn) = &app_state.turn {
-        println!("advertising TURN endpoint {}", turn.url);
+        info!(turn_url = %turn.url, "advertising TURN endpoint");
     } else {
-        println!("TURN unavailable; P2P disabled and sessions will use WebSocket relay");
+        warn!("TURN unavailable; P2P disabled and sessions will use WebSocket relay");
+    }
+    if app_state.admin_token.is_some() || app_state.users.as_ref().is_some_and(|users| users.has_admin()) {
+        info!("admin API enabled under /admin");
     }
-    axum::serve(listener, app).await?;
+    if args.announce {
+        let name = args
+            .announce_name
+            .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().to_string());
+        announce::spawn(name, listener.local_addr()?);
+    }
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal(app_state.state.clone(), args.state_file))
+        .await?;
     Ok(())
 }
 
+async fn shutdown_signal(state: Arc<Mutex<ProxyState>>, state_file: Option<PathBuf>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+
+    info!("shutting down");
+    let Some(path) = state_file else {
+        return;
+    };
+    let snapshot = state.lock().await.export();
+    match snapshot::save(&path, &snapshot) {
+        Ok(()) => info!(path = %path.display(), servers = snapshot.servers.len(), "saved server registrations"),
+        Err(err) => warn!(path = %path.display(), error = %err, "failed to save server registrations"),
+    }
+}
+
+async fn expire_idle_sessions(state: Arc<Mutex<ProxyState>>, timeout: Duration) {
+    let mut sweep = tokio::time::interval((timeout / 4).clamp(Duration::from_secs(1), Duration::from_secs(30)));
+    sweep.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        sweep.tick().await;
+        let expired = state.lock().await.take_idle_sessions(timeout);
+        for session in expired {
+            info!(session_id = %session.session_id, "session closed after idle timeout");
+            for target in [session.client_conn_id, session.server_conn_id] {
+                let _ = send_to_connection(&state, target, &ProxyToPeer::SessionClosed {
+                    session_id: session.session_id,
+                    reason: "session idle timeout".to_string(),
+                }).await;
+            }
+        }
+    }
+}
+
 async fn resolve_turn_url(args: &Args) -> Option<String> {
     if let Some(explicit) = &args.turn_url {
         return Some(explicit.clone());
@@ -119,35 +439,73 @@ async fn resolve_turn_url(args: &Args) -> Option<String> {
         Ok(resp) => match resp.text().await {
             Ok(ip) if !ip.trim().is_empty() => Some(format!("turn:{}:{}", ip.trim(), args.turn_port)),
             _ => {
-                eprintln!("failed to parse public IP response; disabling TURN and using WebSocket relay");
+                warn!("failed to parse public IP response; disabling TURN and using WebSocket relay");
                 None
             }
         },
         Err(err) => {
-            eprintln!(
-                "failed to detect public IP from {} ({err}); disabling TURN and using WebSocket relay",
-                args.public_ip_service
+            warn!(
+                service = %args.public_ip_service,
+                error = %err,
+                "failed to detect public IP; disabling TURN and using WebSocket relay"
             );
             None
         }
     }
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(app): State<AppState>) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, app))
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(app): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = client_ip(&app, peer_addr, &headers);
+    let secure_login = accepts_passwords(&app, peer_addr, &headers);
+    // Payload bytes travel as a JSON array of numbers, at most four characters each, so
+    // this caps what the proxy buffers while still letting the payload check answer.
+    let max_frame = app.max_payload_bytes.saturating_mul(4).saturating_add(64 * 1024);
+    ws.max_message_size(max_frame).on_upgrade(move |socket| {
+        let conn_id = Uuid::new_v4();
+        handle_socket(socket, app, conn_id, ip, secure_login).instrument(info_span!("connection", %conn_id, %ip))
+    })
 }
 
-async fn handle_socket(socket: WebSocket, app: AppState) {
-    let conn_id = Uuid::new_v4();
+/// The address failures are counted against: the socket's peer, or the first
+/// `X-Forwarded-For` entry when the proxy sits behind a trusted front end.
+pub(crate) fn client_ip(app: &AppState, peer_addr: SocketAddr, headers: &HeaderMap) -> IpAddr {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .and_then(|first| first.trim().parse::<IpAddr>().ok());
+    match forwarded {
+        Some(forwarded) if app.trust_forwarded_for => forwarded,
+        _ => peer_addr.ip(),
+    }
+}
+
+/// Whether a password may be sent on this request. It needs TLS: terminated by a
+/// trusted front end, or not needed because the socket comes straight from this host.
+pub(crate) fn accepts_passwords(app: &AppState, peer_addr: SocketAddr, headers: &HeaderMap) -> bool {
+    app.allow_insecure_login
+        || match headers.get("x-forwarded-proto").and_then(|value| value.to_str().ok()) {
+            Some(proto) => app.trust_forwarded_for && proto.trim().eq_ignore_ascii_case("https"),
+            None => !headers.contains_key("x-forwarded-for") && peer_addr.ip().is_loopback(),
+        }
+}
+
+async fn handle_socket(socket: WebSocket, app: AppState, conn_id: Uuid, ip: IpAddr, secure_login: bool) {
+    info!("connection opened");
     let (mut ws_tx, mut ws_rx) = socket.split();
-    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+    let (outgoing_tx, mut outgoing_rx) = outbox::channel::<Message>(app.outgoing_queue_capacity);
 
     {
         let mut state = app.state.lock().await;
         state.connections.insert(conn_id, outgoing_tx);
     }
 
-    let writer = tokio::spawn(async move {
+    let mut writer = tokio::spawn(async move {
         while let Some(msg) = outgoing_rx.recv().await {
             if ws_tx.send(msg).await.is_err() {
                 break;
@@ -156,39 +514,119 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
     });
 
     let mut role: Option<AuthRole> = None;
+    let mut username: Option<String> = None;
     let mut server_name: Option<String> = None;
+    let mut connect_failures = 0;
+    let mut limits = ConnectionLimits::new(app.rate_limit_messages, app.rate_limit_bytes);
 
-    while let Some(msg_result) = ws_rx.next().await {
+    // Pings only go to registered servers: a server that vanished without closing its
+    // socket would otherwise keep its name until the TCP connection times out.
+    let mut ping = tokio::time::interval(app.ping_interval.unwrap_or(Duration::from_secs(60)));
+    ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut missed_pings = 0;
+
+    loop {
+        let msg_result = tokio::select! {
+            msg_result = ws_rx.next() => {
+                let Some(msg_result) = msg_result else { break; };
+                msg_result
+            }
+            _ = ping.tick(), if app.ping_interval.is_some() && server_name.is_some() => {
+                if missed_pings >= app.ping_max_missed {
+                    warn!(server_name = ?server_name, missed_pings, "server stopped answering pings; dropping registration");
+                    break;
+                }
+                missed_pings += 1;
+                send_frame(&app.state, conn_id, Message::Ping(Default::default())).await;
+                continue;
+            }
+        };
         let msg = match msg_result {
             Ok(m) => m,
             Err(_) => break,
         };
+        missed_pings = 0;
 
         let Message::Text(text) = msg else {
             continue;
         };
 
+        // A connection over its rate limit is read more slowly rather than cut off: for a
+        // server that would end every session it carries. Holding the read back lets
+        // TCP push back on the sender.
+        while let Err(wait) = limits.admit(text.len()) {
+            debug!(?wait, "connection over its rate limit; delaying reads");
+            tokio::time::sleep(wait).await;
+        }
+
         let Ok(peer_msg) = serde_json::from_str::<PeerToProxy>(&text) else {
             continue;
         };
 
-        if role.is_none() {
-            let PeerToProxy::AuthProxy { proxy_password, role: parsed_role } = peer_msg else {
-                let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthError {
-                    reason: "first message must be auth_proxy".to_string(),
-                }).await;
-                break;
+        // Only the session the oversized payload was meant for is closed; the sender may
+        // be a server with other sessions to keep.
+        if let PeerToProxy::RelayData { session_id, payload, .. } = &peer_msg
+            && payload.len() > app.max_payload_bytes
+        {
+            warn!(%session_id, len = payload.len(), max = app.max_payload_bytes, "closing session that relayed an oversized payload");
+            let peer = {
+                let mut state = app.state.lock().await;
+                role.as_ref().and_then(|role| state.close_session(*session_id, conn_id, role))
             };
+            if let Some(peer) = peer {
+                for target in [conn_id, peer] {
+                    let _ = send_to_connection(&app.state, target, &ProxyToPeer::SessionClosed {
+                        session_id: *session_id,
+                        reason: format!("relayed payload exceeds {} bytes", app.max_payload_bytes),
+                    }).await;
+                }
+            }
+            continue;
+        }
 
-            if proxy_password != app.proxy_password {
-                let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthError {
-                    reason: "invalid proxy password".to_string(),
-                }).await;
+        if role.is_none() {
+            let throttled = app.state.lock().await.throttle.check(ip);
+            if let Err(reason) = throttled {
+                warn!(%reason, "authentication refused");
+                let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthError { reason }).await;
                 break;
             }
+            let (parsed_role, user) = match authenticate(&app, peer_msg, secure_login).await {
+                Ok(authenticated) => authenticated,
+                Err(failure) => {
+                    let reason = match failure {
+                        AuthFailure::Credentials(reason) => {
+                            app.state.lock().await.throttle.record_failure(ip, &reason);
+                            reason
+                        }
+                        AuthFailure::Protocol(reason) => {
+                            warn!(%reason, "authentication refused");
+                            reason
+                        }
+                    };
+                    let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthError { reason }).await;
+                    break;
+                }
+            };
 
-            app.state.lock().await.conn_roles.insert(conn_id, parsed_role.clone());
-            let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthOk { role: parsed_role.clone() }).await;
+            info!(
+                role = ?parsed_role,
+                username = user.as_ref().map(|(name, _)| name.as_str()),
+                user_role = ?user.as_ref().map(|(_, user_role)| user_role),
+                "authenticated"
+            );
+            {
+                let mut state = app.state.lock().await;
+                state.conn_roles.insert(conn_id, parsed_role.clone());
+                if let Some(user) = &user {
+                    state.conn_users.insert(conn_id, user.clone());
+                }
+            }
+            username = user.map(|(name, _)| name);
+            let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::AuthOk {
+                role: parsed_role.clone(),
+                username: username.clone(),
+            }).await;
             role = Some(parsed_role);
             continue;
         }
@@ -196,53 +634,132 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
         match role {
             Some(AuthRole::Server) => {
                 if server_name.is_none() {
-                    let PeerToProxy::RegisterServer { server_name: name, server_password } = peer_msg else {
+                    let PeerToProxy::RegisterServer { server_name: name, server_password, metadata, resume_token } = peer_msg else {
                         let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError {
                             reason: "server must register before other actions".to_string(),
                         }).await;
                         break;
                     };
 
-                    let inserted = {
+                    let registered = {
                         let mut state = app.state.lock().await;
+                        state.prune_reservations();
+                        let reserved = state.reserved.get(&name).map(|reservation| reservation.resume_token.clone());
+                        let resumes = reserved.is_some() && reserved == resume_token;
+                        let mut name = name.clone();
+                        let mut outcome = if resumes { RegistrationOutcome::Resumed } else { RegistrationOutcome::New };
+                        let mut replaced = None;
+                        if !resumes && state.name_taken(&name) {
+                            match app.on_name_conflict {
+                                NameConflict::Reject => {}
+                                NameConflict::Replace => {
+                                    state.reserved.remove(&name);
+                                    replaced = state.servers.remove(&name).map(|registration| registration.conn_id);
+                                    outcome = RegistrationOutcome::Replaced;
+                                }
+                                NameConflict::Suffix => {
+                                    name = (2..)
+                                        .map(|n| format!("{name}-{n}"))
+                                        .find(|candidate| !state.name_taken(candidate))
+                                        .expect("some suffix is free");
+                                    outcome = RegistrationOutcome::Suffixed;
+                                }
+                            }
+                        }
+
                         if state.servers.contains_key(&name) {
-                            false
+                            Err("server name already registered")
+                        } else if state.reserved.contains_key(&name) && !resumes {
+                            Err("server name reserved for a reconnecting server")
                         } else {
-                            state.servers.insert(name.clone(), ServerRegistration { conn_id, server_password });
-                            true
+                            state.reserved.remove(&name);
+                            let token = reserved
+                                .filter(|_| resumes)
+                                .unwrap_or_else(|| Uuid::new_v4().to_string());
+                            state.servers.insert(name.clone(), ServerRegistration {
+                                conn_id,
+                                server_password,
+                                metadata: metadata.clone(),
+                                busy: false,
+                                resume_token: token.clone(),
+                            });
+                            Ok((name, token, outcome, replaced))
                         }
                     };
 
-                    if inserted {
-                        let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::Registered { server_name: name.clone() }).await;
-                        server_name = Some(name);
-                    } else {
-                        let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError {
-                            reason: "server name already registered".to_string(),
-                        }).await;
-                        break;
+                    match registered {
+                        Ok((registered_name, token, outcome, replaced)) => {
+                            info!(
+                                server_name = %registered_name,
+                                requested = %name,
+                                os = %metadata.os,
+                                hostname = %metadata.hostname,
+                                ?outcome,
+                                "server registered"
+                            );
+                            if let Some(stale_conn_id) = replaced {
+                                warn!(server_name = %name, %stale_conn_id, "disconnecting replaced server");
+                                let _ = send_to_connection(&app.state, stale_conn_id, &ProxyToPeer::ConnectionError {
+                                    reason: "server name registered by another connection".to_string(),
+                                }).await;
+                                send_frame(&app.state, stale_conn_id, Message::Close(None)).await;
+                            }
+                            let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::Registered {
+                                server_name: registered_name.clone(),
+                                resume_token: Some(token),
+                                outcome,
+                            }).await;
+                            server_name = Some(registered_name);
+                        }
+                        Err(reason) => {
+                            warn!(server_name = %name, %reason, "server registration rejected");
+                            let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError {
+                                reason: reason.to_string(),
+                            }).await;
+                            break;
+                        }
                     }
                     continue;
                 }
 
                 match peer_msg {
-                    PeerToProxy::DisconnectSession { session_id } => {
-                        let target_client = {
-                            let mut state = app.state.lock().await;
-                            state.sessions.remove(&session_id).map(|session| session.client_conn_id)
-                        };
+                    PeerToProxy::SetBusy { busy } => {
+                        if let Some(name) = &server_name
+                            && let Some(registration) = app.state.lock().await.servers.get_mut(name)
+                            && registration.conn_id == conn_id
+                        {
+                            registration.busy = busy;
+                        }
+                        info!(busy, "server availability changed");
+                    }
+                    PeerToProxy::DisconnectSession { session_id, reason } => {
+                        let target_client = app.state.lock().await.close_session(session_id, conn_id, &AuthRole::Server);
                         if let Some(client_conn_id) = target_client {
+                            let reason = reason.unwrap_or_else(|| "server closed session".to_string());
+                            info!(%session_id, %reason, "server closed session");
                             let _ = send_to_connection(&app.state, client_conn_id, &ProxyToPeer::SessionClosed {
                                 session_id,
-                                reason: "server closed session".to_string(),
+                                reason,
                             }).await;
+                        } else {
+                            warn!(%session_id, "ignored disconnect for a session this server does not serve");
                         }
                     }
-                    PeerToProxy::Signal { session_id, signal } => {
-                        if let Some(client_conn_id) = {
-                            let state = app.state.lock().await;
-                            state.sessions.get(&session_id).map(|s| s.client_conn_id)
-                        } {
+                    PeerToProxy::Signal { session_id, signal, freshness } => {
+                        let target = {
+                            let mut state = app.state.lock().await;
+                            if let Err(reason) = state.replays.check(&freshness) {
+                                warn!(%session_id, %reason, "dropped signal");
+                                None
+                            } else {
+                                let target = state.signal_target(session_id, conn_id, &AuthRole::Server, &signal);
+                                if target.is_none() {
+                                    warn!(%session_id, "dropped signal outside this server's P2P sessions");
+                                }
+                                target
+                            }
+                        };
+                        if let Some(client_conn_id) = target {
                             let _ = send_to_connection(&app.state, client_conn_id, &ProxyToPeer::PeerSignal {
                                 session_id,
                                 from: AuthRole::Server,
@@ -250,37 +767,59 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
                             }).await;
                         }
                     }
-                    PeerToProxy::RelayData { session_id, payload } => {
-                        if let Some(client_conn_id) = {
-                            let state = app.state.lock().await;
-                            state.sessions.get(&session_id).map(|s| s.client_conn_id)
-                        } {
-                            let _ = send_to_connection(&app.state, client_conn_id, &ProxyToPeer::RelayData {
-                                session_id,
-                                payload,
-                            }).await;
+                    PeerToProxy::RelayData { session_id, payload, droppable } => {
+                        let target = app.state.lock().await.relay_target(session_id, conn_id, &AuthRole::Server);
+                        if let Some(client_conn_id) = target {
+                            relay_data(&app.state, session_id, conn_id, &AuthRole::Server, client_conn_id, payload, droppable).await;
+                        } else {
+                            debug!(%session_id, "dropped relay data for a session this server does not serve");
                         }
                     }
-                    _ => {}
+                    other => {
+                        let reason = format!("{} is not allowed for a server connection", other.name());
+                        warn!(%reason, "protocol violation");
+                        let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError { reason }).await;
+                        break;
+                    }
                 }
             }
             Some(AuthRole::Client) => {
                 match peer_msg {
-                    PeerToProxy::ConnectServer { server_name, server_password, use_p2p } => {
+                    PeerToProxy::ListServers => {
+                        let servers = app.state.lock().await.server_infos();
+                        let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ServersList { servers }).await;
+                    }
+                    PeerToProxy::ConnectServer { server_name, proof, use_p2p, freshness } => {
                         let setup = {
                             let mut state = app.state.lock().await;
-                            if let Some(server) = state.servers.get(&server_name).cloned() {
-                                if server.server_password != server_password {
-                                    Some(Err("invalid server password".to_string()))
+                            if let Err(reason) = state.throttle.check(ip) {
+                                Some(Err(reason))
+                            } else if let Err(reason) = state.replays.validate(&freshness) {
+                                Some(Err(reason.to_string()))
+                            } else if let Some(server) = state.servers.get(&server_name).cloned() {
+                                if !verify_proof(&server.server_password, &freshness, &proof) {
+                                    let reason = "invalid server password".to_string();
+                                    state.throttle.record_failure(ip, &reason);
+                                    connect_failures += 1;
+                                    Some(Err(reason))
+                                } else if let Err(reason) = state.replays.record(&freshness) {
+                                    Some(Err(reason.to_string()))
+                                } else if server.busy {
+                                    Some(Err("server at capacity".to_string()))
                                 } else {
                                     let session_id = Uuid::new_v4();
                                     state.sessions.insert(session_id, Session {
                                         session_id,
                                         server_conn_id: server.conn_id,
                                         client_conn_id: conn_id,
+                                        username: username.clone(),
+                                        via_p2p: use_p2p && app.turn.is_some(),
+                                        last_activity: Instant::now(),
                                     });
                                     Some(Ok((session_id, server.conn_id)))
                                 }
+                            } else if state.reserved.contains_key(&server_name) {
+                                Some(Err("server is reconnecting to the proxy; try again shortly".to_string()))
                             } else {
                                 Some(Err("unknown server name".to_string()))
                             }
@@ -289,6 +828,7 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
                         match setup {
                             Some(Ok((session_id, server_conn_id))) => {
                                 let p2p_enabled = use_p2p && app.turn.is_some();
+                                info!(%session_id, %server_name, username = username.as_deref(), via_p2p = p2p_enabled, "session created");
                                 let turn_creds = if p2p_enabled { app.turn.clone() } else { None };
                                 let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::Connected {
                                     session_id,
@@ -301,35 +841,47 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
                                     peer_id: conn_id,
                                     via_p2p: p2p_enabled,
                                     turn: turn_creds,
+                                    username: username.clone(),
                                 }).await;
                             }
                             Some(Err(reason)) => {
+                                warn!(%server_name, %reason, "connect rejected");
                                 let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError { reason }).await;
+                                if connect_failures >= app.max_connect_failures {
+                                    warn!(connect_failures, "disconnecting client after repeated wrong server passwords");
+                                    break;
+                                }
                             }
                             None => {}
                         }
                     }
-                    PeerToProxy::DisconnectSession { session_id } => {
-                        let target_server = {
-                            let mut state = app.state.lock().await;
-                            state.sessions.remove(&session_id).and_then(|session| {
-                                if session.client_conn_id == conn_id { Some(session.server_conn_id) } else { None }
-                            })
-                        };
+                    PeerToProxy::DisconnectSession { session_id, .. } => {
+                        let target_server = app.state.lock().await.close_session(session_id, conn_id, &AuthRole::Client);
                         if let Some(server_conn_id) = target_server {
+                            info!(%session_id, "client closed session");
                             let _ = send_to_connection(&app.state, server_conn_id, &ProxyToPeer::SessionClosed {
                                 session_id,
                                 reason: "client closed session".to_string(),
                             }).await;
+                        } else {
+                            warn!(%session_id, "ignored disconnect for a session this client does not own");
                         }
                     }
-                    PeerToProxy::Signal { session_id, signal } => {
-                        if let Some(server_conn_id) = {
-                            let state = app.state.lock().await;
-                            state.sessions.get(&session_id).and_then(|session| {
-                                if session.client_conn_id == conn_id { Some(session.server_conn_id) } else { None }
-                            })
-                        } {
+                    PeerToProxy::Signal { session_id, signal, freshness } => {
+                        let target = {
+                            let mut state = app.state.lock().await;
+                            if let Err(reason) = state.replays.check(&freshness) {
+                                warn!(%session_id, %reason, "dropped signal");
+                                None
+                            } else {
+                                let target = state.signal_target(session_id, conn_id, &AuthRole::Client, &signal);
+                                if target.is_none() {
+                                    warn!(%session_id, "dropped signal outside this client's P2P sessions");
+                                }
+                                target
+                            }
+                        };
+                        if let Some(server_conn_id) = target {
                             let _ = send_to_connection(&app.state, server_conn_id, &ProxyToPeer::PeerSignal {
                                 session_id,
                                 from: AuthRole::Client,
@@ -337,20 +889,20 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
                             }).await;
                         }
                     }
-                    PeerToProxy::RelayData { session_id, payload } => {
-                        if let Some(server_conn_id) = {
-                            let state = app.state.lock().await;
-                            state.sessions.get(&session_id).and_then(|session| {
-                                if session.client_conn_id == conn_id { Some(session.server_conn_id) } else { None }
-                            })
-                        } {
-                            let _ = send_to_connection(&app.state, server_conn_id, &ProxyToPeer::RelayData {
-                                session_id,
-                                payload,
-                            }).await;
+                    PeerToProxy::RelayData { session_id, payload, droppable } => {
+                        let target = app.state.lock().await.relay_target(session_id, conn_id, &AuthRole::Client);
+                        if let Some(server_conn_id) = target {
+                            relay_data(&app.state, session_id, conn_id, &AuthRole::Client, server_conn_id, payload, droppable).await;
+                        } else {
+                            debug!(%session_id, "dropped relay data for a session this client does not own");
                         }
                     }
-                    _ => {}
+                    other => {
+                        let reason = format!("{} is not allowed for a client connection", other.name());
+                        warn!(%reason, "protocol violation");
+                        let _ = send_to_connection(&app.state, conn_id, &ProxyToPeer::ConnectionError { reason }).await;
+                        break;
+                    }
                 }
             }
             None => break,
@@ -358,7 +910,69 @@ async fn handle_socket(socket: WebSocket, app: AppState) {
     }
 
     cleanup_connection(&app.state, conn_id, server_name).await;
-    writer.abort();
+    // Let the writer flush a final error such as auth_error before the socket drops.
+    if tokio::time::timeout(Duration::from_secs(1), &mut writer).await.is_err() {
+        writer.abort();
+    }
+    info!("connection closed");
+}
+
+/// Checks the first frame of a connection: `auth_proxy` with the proxy password, or
+/// `login` with a user account. Clients must log in when the proxy has users, and may
+/// only do so over TLS unless `secure_login` says the socket is safe without it.
+async fn authenticate(
+    app: &AppState,
+    message: PeerToProxy,
+    secure_login: bool,
+) -> Result<(AuthRole, Option<(String, UserRole)>), AuthFailure> {
+    match message {
+        PeerToProxy::AuthProxy { proof, role, freshness } => {
+            app.state.lock().await.replays.validate(&freshness).map_err(AuthFailure::protocol)?;
+            if !verify_proof(&app.proxy_password, &freshness, &proof) {
+                return Err(AuthFailure::Credentials("invalid proxy password".to_string()));
+            }
+            app.state.lock().await.replays.record(&freshness).map_err(AuthFailure::protocol)?;
+            if role == AuthRole::Client && app.users.is_some() {
+                return Err(AuthFailure::protocol("this proxy requires a username and password"));
+            }
+            Ok((role, None))
+        }
+        PeerToProxy::Login { username, password, freshness } => {
+            app.state.lock().await.replays.validate(&freshness).map_err(AuthFailure::protocol)?;
+            let Some(users) = app.users.clone() else {
+                return Err(AuthFailure::protocol("this proxy has no user accounts; use the proxy password"));
+            };
+            if !secure_login {
+                return Err(AuthFailure::protocol("login is only accepted over TLS; connect with wss://"));
+            }
+            // Argon2 is deliberately slow, so keep it off the async workers.
+            let checked = username.clone();
+            let verified = tokio::task::spawn_blocking(move || users.verify(&checked, &password))
+                .await
+                .ok()
+                .flatten();
+            let Some(user_role) = verified else {
+                return Err(AuthFailure::Credentials("invalid username or password".to_string()));
+            };
+            app.state.lock().await.replays.record(&freshness).map_err(AuthFailure::protocol)?;
+            Ok((AuthRole::Client, Some((username, user_role))))
+        }
+        _ => Err(AuthFailure::protocol("first message must be auth_proxy or login")),
+    }
+}
+
+/// Why [`authenticate`] refused a connection. Only wrong credentials count toward the
+/// address's lockout; a stale, replayed or malformed frame says nothing about someone
+/// guessing passwords.
+enum AuthFailure {
+    Credentials(String),
+    Protocol(String),
+}
+
+impl AuthFailure {
+    fn protocol(reason: &str) -> Self {
+        AuthFailure::Protocol(reason.to_string())
+    }
 }
 
 async fn send_to_connection(
@@ -371,19 +985,70 @@ async fn send_to_connection(
         let state = state.lock().await;
         state.connections.get(&conn_id).cloned()
     };
-    if let Some(tx) = sender {
-        let _ = tx.send(Message::Text(payload.into()));
+    // Streamed output is the bulk of the traffic; a peer too slow to take it loses the
+    // oldest queued chunks rather than the requests and responses around them.
+    let priority = match message {
+        ProxyToPeer::RelayData { droppable: true, .. } => Priority::Stream,
+        _ => Priority::Control,
+    };
+    if let Some(tx) = sender
+        && let Err(err) = tx.send(priority, Message::Text(payload.into()))
+    {
+        warn!(%conn_id, depth = tx.depth(), "peer is not keeping up; control message not queued");
+        return Err(err.into());
     }
     Ok(())
 }
 
+/// Forwards a relayed payload from `conn_id` to `target`. Payloads the sender did not
+/// mark droppable are requests and responses the far side is waiting on, so when they
+/// do not fit in the target's outbox the session is closed rather than left to hang.
+async fn relay_data(
+    state: &Arc<Mutex<ProxyState>>,
+    session_id: Uuid,
+    conn_id: Uuid,
+    role: &AuthRole,
+    target: Uuid,
+    payload: Vec<u8>,
+    droppable: bool,
+) {
+    let message = ProxyToPeer::RelayData { session_id, payload, droppable };
+    if send_to_connection(state, target, &message).await.is_ok() || droppable {
+        return;
+    }
+    if state.lock().await.close_session(session_id, conn_id, role).is_none() {
+        return;
+    }
+    let reason = "peer is not keeping up with relayed data".to_string();
+    warn!(%session_id, %reason, "closing session");
+    for peer in [conn_id, target] {
+        let _ = send_to_connection(state, peer, &ProxyToPeer::SessionClosed {
+            session_id,
+            reason: reason.clone(),
+        }).await;
+    }
+}
+
+/// Queues a control frame such as a ping or close behind the messages already queued
+/// for `conn_id`.
+async fn send_frame(state: &Arc<Mutex<ProxyState>>, conn_id: Uuid, frame: Message) {
+    let sender = state.lock().await.connections.get(&conn_id).cloned();
+    if let Some(tx) = sender {
+        let _ = tx.send_control(frame);
+    }
+}
+
 async fn cleanup_connection(state: &Arc<Mutex<ProxyState>>, conn_id: Uuid, server_name: Option<String>) {
     let mut notifications: Vec<(Uuid, ProxyToPeer)> = Vec::new();
     {
         let mut locked = state.lock().await;
         locked.connections.remove(&conn_id);
         locked.conn_roles.remove(&conn_id);
-        if let Some(name) = server_name {
+        locked.conn_users.remove(&conn_id);
+        // A replaced server's name now belongs to the connection that replaced it.
+        if let Some(name) = server_name
+            && locked.servers.get(&name).is_some_and(|registration| registration.conn_id == conn_id)
+        {
             locked.servers.remove(&name);
         }
 
@@ -394,6 +1059,7 @@ async fn cleanup_connection(state: &Arc<Mutex<ProxyState>>, conn_id: Uuid, serve
 
         for session_id in affected_sessions {
             if let Some(session) = locked.sessions.remove(&session_id) {
+                info!(%session_id, "session closed by disconnect");
                 if session.server_conn_id == conn_id {
                     notifications.push((session.client_conn_id, ProxyToPeer::SessionClosed {
                         session_id,
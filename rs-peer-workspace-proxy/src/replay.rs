@@ -0,0 +1,78 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rs_peer_workspace_shared::relay::{unix_now, Freshness};
+
+/// Longest nonce the proxy will remember. Peers send UUIDs, so anything longer is
+/// refused before it can take up room in the guard.
+pub const MAX_NONCE_LEN: usize = 64;
+
+/// Remembers nonces seen within the acceptance window. Frames stamped outside the
+/// window are refused outright, so older nonces never need to be kept; they are
+/// indexed by timestamp so expiring them only touches the seconds that fell out.
+#[derive(Debug)]
+pub struct ReplayGuard {
+    window_secs: u64,
+    seen: HashMap<String, u64>,
+    by_timestamp: BTreeMap<u64, Vec<String>>,
+}
+
+impl ReplayGuard {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            seen: HashMap::new(),
+            by_timestamp: BTreeMap::new(),
+        }
+    }
+
+    /// Validates and records `freshness` in one step, for frames that carry no proof.
+    pub fn check(&mut self, freshness: &Freshness) -> Result<(), &'static str> {
+        self.validate(freshness)?;
+        self.record(freshness)
+    }
+
+    /// Checks `freshness` without remembering it. Frames carrying a proof call this
+    /// first and [`ReplayGuard::record`] once the proof holds, so a forged frame cannot
+    /// burn a nonce that a genuine one is about to use.
+    pub fn validate(&mut self, freshness: &Freshness) -> Result<(), &'static str> {
+        let now = unix_now();
+        if freshness.nonce.is_empty() {
+            return Err("message is missing a nonce");
+        }
+        if freshness.nonce.len() > MAX_NONCE_LEN {
+            return Err("message nonce is too long");
+        }
+        if freshness.timestamp.abs_diff(now) > self.window_secs {
+            return Err("message timestamp is outside the accepted window");
+        }
+        self.expire(now);
+        if self.seen.contains_key(&freshness.nonce) {
+            return Err("message nonce was already used");
+        }
+        Ok(())
+    }
+
+    /// Remembers a nonce that passed [`ReplayGuard::validate`]. Fails if another frame
+    /// recorded it in the meantime.
+    pub fn record(&mut self, freshness: &Freshness) -> Result<(), &'static str> {
+        if self
+            .seen
+            .insert(freshness.nonce.clone(), freshness.timestamp)
+            .is_some()
+        {
+            return Err("message nonce was already used");
+        }
+        self.by_timestamp
+            .entry(freshness.timestamp)
+            .or_default()
+            .push(freshness.nonce.clone());
+        Ok(())
+    }
+
+    fn expire(&mut self, now: u64) {
+        let kept = self.by_timestamp.split_off(&now.saturating_sub(self.window_secs));
+        for nonce in std::mem::replace(&mut self.by_timestamp, kept).into_values().flatten() {
+            self.seen.remove(&nonce);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rs_peer_workspace_shared::relay::{unix_now, ServerMetadata};
+use serde::{Deserialize, Serialize};
+
+use crate::{ProxyState, Reservation};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxySnapshot {
+    pub exported_at: u64,
+    pub servers: Vec<ServerSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSnapshot {
+    pub server_name: String,
+    pub server_password: String,
+    pub resume_token: String,
+    #[serde(default)]
+    pub metadata: ServerMetadata,
+}
+
+impl ProxyState {
+    /// Live registrations plus reservations that have not been reclaimed yet.
+    pub fn export(&mut self) -> ProxySnapshot {
+        self.prune_reservations();
+        let mut servers: Vec<ServerSnapshot> = self
+            .servers
+            .iter()
+            .map(|(name, registration)| ServerSnapshot {
+                server_name: name.clone(),
+                server_password: registration.server_password.clone(),
+                resume_token: registration.resume_token.clone(),
+                metadata: registration.metadata.clone(),
+            })
+            .chain(self.reserved.iter().map(|(name, reservation)| ServerSnapshot {
+                server_name: name.clone(),
+                server_password: reservation.server_password.clone(),
+                resume_token: reservation.resume_token.clone(),
+                metadata: reservation.metadata.clone(),
+            }))
+            .collect();
+        servers.sort_by(|a, b| a.server_name.cmp(&b.server_name));
+        ProxySnapshot {
+            exported_at: unix_now(),
+            servers,
+        }
+    }
+
+    /// Reserves each exported server name for `grace` so only the holder of its resume
+    /// token can register it again. Names that are already live are skipped.
+    pub fn import(&mut self, snapshot: ProxySnapshot, grace: Duration) -> usize {
+        let expires = Instant::now() + grace;
+        let mut imported = 0;
+        for server in snapshot.servers {
+            if self.servers.contains_key(&server.server_name) {
+                continue;
+            }
+            self.reserved.insert(
+                server.server_name,
+                Reservation {
+                    server_password: server.server_password,
+                    resume_token: server.resume_token,
+                    metadata: server.metadata,
+                    expires,
+                },
+            );
+            imported += 1;
+        }
+        imported
+    }
+
+    pub fn prune_reservations(&mut self) {
+        let now = Instant::now();
+        self.reserved.retain(|_, reservation| reservation.expires > now);
+    }
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Option<ProxySnapshot>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(serde_json::from_str(&text)?)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save(path: &Path, snapshot: &ProxySnapshot) -> anyhow::Result<()> {
+    let temp = path.with_extension("tmp");
+    // A temp file left by an interrupted save may have been created with looser permissions.
+    match std::fs::remove_file(&temp) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    write_private(&temp, &serde_json::to_vec_pretty(snapshot)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// The snapshot holds resume tokens, so only the proxy's user may read it.
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rs_peer_workspace_shared::relay::unix_now;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleSettings {
+    /// Failures from one address before it is locked out.
+    pub max_failures: u32,
+    /// First lockout; each further lockout of the same address doubles it.
+    pub lockout: Duration,
+    pub max_lockout: Duration,
+    /// Lockouts after which an address is banned; only with a ban file.
+    pub ban_after_lockouts: u32,
+}
+
+#[derive(Debug)]
+struct Record {
+    failures: u32,
+    lockouts: u32,
+    locked_until: Option<Instant>,
+    last_failure: Instant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ban {
+    pub ip: IpAddr,
+    pub banned_at: u64,
+}
+
+/// Counts failed authentications per address. Too many lock the address out for a
+/// doubling period, and with a ban file, repeated lockouts ban it until an operator
+/// lifts the ban.
+#[derive(Debug)]
+pub struct AuthThrottle {
+    settings: ThrottleSettings,
+    records: HashMap<IpAddr, Record>,
+    ban_file: Option<PathBuf>,
+    bans: Vec<Ban>,
+}
+
+impl AuthThrottle {
+    pub fn new(settings: ThrottleSettings, ban_file: Option<PathBuf>) -> anyhow::Result<Self> {
+        let bans = match &ban_file {
+            Some(path) => load_bans(path)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            settings,
+            records: HashMap::new(),
+            ban_file,
+            bans,
+        })
+    }
+
+    /// Refuses addresses that are banned or still locked out.
+    pub fn check(&mut self, ip: IpAddr) -> Result<(), String> {
+        if self.bans.iter().any(|ban| ban.ip == ip) {
+            return Err("address is banned after repeated failed authentication".to_string());
+        }
+        self.prune();
+        let now = Instant::now();
+        match self.records.get(&ip).and_then(|record| record.locked_until) {
+            Some(until) if until > now => Err(format!(
+                "too many failed attempts; try again in {} s",
+                (until - now).as_secs().max(1)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&mut self, ip: IpAddr, reason: &str) {
+        let now = Instant::now();
+        let record = self.records.entry(ip).or_insert(Record {
+            failures: 0,
+            lockouts: 0,
+            locked_until: None,
+            last_failure: now,
+        });
+        record.failures += 1;
+        record.last_failure = now;
+        warn!(%ip, failures = record.failures, %reason, "failed authentication");
+        if record.failures < self.settings.max_failures {
+            return;
+        }
+
+        record.failures = 0;
+        let lockout = self
+            .settings
+            .lockout
+            .saturating_mul(2u32.saturating_pow(record.lockouts))
+            .min(self.settings.max_lockout);
+        record.lockouts += 1;
+        record.locked_until = Some(now + lockout);
+        warn!(%ip, lockout_secs = lockout.as_secs(), lockouts = record.lockouts, "locking out address");
+
+        if self.ban_file.is_some() && record.lockouts >= self.settings.ban_after_lockouts {
+            self.records.remove(&ip);
+            self.ban(ip);
+        }
+    }
+
+    pub fn bans(&self) -> &[Ban] {
+        &self.bans
+    }
+
+    /// Lifts the ban on `ip`, returning whether it was banned.
+    pub fn unban(&mut self, ip: IpAddr) -> bool {
+        let before = self.bans.len();
+        self.bans.retain(|ban| ban.ip != ip);
+        if self.bans.len() == before {
+            return false;
+        }
+        info!(%ip, "ban lifted");
+        self.save_bans();
+        true
+    }
+
+    fn ban(&mut self, ip: IpAddr) {
+        warn!(%ip, "banning address after repeated lockouts");
+        self.bans.push(Ban {
+            ip,
+            banned_at: unix_now(),
+        });
+        self.save_bans();
+    }
+
+    fn save_bans(&self) {
+        let Some(path) = &self.ban_file else {
+            return;
+        };
+        if let Err(err) = save_bans(path, &self.bans) {
+            warn!(path = %path.display(), error = %err, "failed to save ban list");
+        }
+    }
+
+    /// Forgets addresses that have been quiet for longer than the longest lockout.
+    fn prune(&mut self) {
+        let now = Instant::now();
+        let max_lockout = self.settings.max_lockout;
+        self.records.retain(|_, record| {
+            record.locked_until.is_some_and(|until| until > now)
+                || now.duration_since(record.last_failure) < max_lockout
+        });
+    }
+}
+
+fn load_bans(path: &Path) -> anyhow::Result<Vec<Ban>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_bans(path: &Path, bans: &[Ban]) -> anyhow::Result<()> {
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, serde_json::to_vec_pretty(bans)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
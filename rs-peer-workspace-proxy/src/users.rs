@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    User,
+    /// May also use the admin API, see [`crate::admin`].
+    Admin,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UserEntry {
+    username: String,
+    /// PHC-format argon2 hash, such as `$argon2id$v=19$...`.
+    password_hash: String,
+    role: UserRole,
+}
+
+#[derive(Debug, Clone)]
+struct Account {
+    password_hash: String,
+    role: UserRole,
+}
+
+/// Client accounts loaded from `--users-file`, a JSON array of `username`,
+/// `password_hash` and `role` entries.
+#[derive(Debug, Default)]
+pub struct Users {
+    accounts: HashMap<String, Account>,
+    /// Checked against for unknown usernames, so a miss takes as long as a wrong
+    /// password and response times do not reveal which accounts exist.
+    dummy_hash: String,
+}
+
+impl Users {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let entries: Vec<UserEntry> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut accounts = HashMap::new();
+        for entry in entries {
+            PasswordHash::new(&entry.password_hash).map_err(|err| {
+                anyhow::anyhow!("invalid password hash for user {}: {err}", entry.username)
+            })?;
+            let account = Account {
+                password_hash: entry.password_hash,
+                role: entry.role,
+            };
+            if accounts.insert(entry.username.clone(), account).is_some() {
+                anyhow::bail!("user {} is listed twice", entry.username);
+            }
+        }
+        let salt = SaltString::generate(&mut OsRng);
+        let dummy_hash = Argon2::default()
+            .hash_password(b"no such user", &salt)
+            .map_err(|err| anyhow::anyhow!("failed to prepare password checks: {err}"))?
+            .to_string();
+        Ok(Self { accounts, dummy_hash })
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn has_admin(&self) -> bool {
+        self.accounts.values().any(|account| account.role == UserRole::Admin)
+    }
+
+    /// The account's role when `password` matches its hash.
+    pub fn verify(&self, username: &str, password: &str) -> Option<UserRole> {
+        let account = self.accounts.get(username);
+        let hash = account.map_or(&self.dummy_hash, |account| &account.password_hash);
+        let hash = PasswordHash::new(hash).ok()?;
+        let matched = Argon2::default().verify_password(password.as_bytes(), &hash).is_ok();
+        account.filter(|_| matched).map(|account| account.role)
+    }
+}
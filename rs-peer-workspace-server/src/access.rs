@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rs_peer_workspace_shared::relay::unix_now;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::audit::AuditLog;
+use crate::protocol::SessionAccess;
+
+struct Elevation {
+    grant: Uuid,
+    until: u64,
+}
+
+/// Write and command rights per session. On a `--read-only` server a session may
+/// only change files or run code while elevated; each elevation lasts for the
+/// configured window and is reverted automatically.
+pub struct AccessControl {
+    read_only: bool,
+    elevation_password: Option<String>,
+    window: Duration,
+    elevations: Arc<Mutex<HashMap<Uuid, Elevation>>>,
+    audit: Arc<AuditLog>,
+}
+
+impl AccessControl {
+    pub fn new(
+        read_only: bool,
+        elevation_password: Option<String>,
+        window: Duration,
+        audit: Arc<AuditLog>,
+    ) -> Self {
+        Self {
+            read_only,
+            elevation_password,
+            window,
+            elevations: Arc::default(),
+            audit,
+        }
+    }
+
+    pub async fn status(&self, session_id: Uuid) -> SessionAccess {
+        let now = unix_now();
+        SessionAccess {
+            read_only: self.read_only,
+            elevated_until: self
+                .elevations
+                .lock()
+                .await
+                .get(&session_id)
+                .map(|elevation| elevation.until)
+                .filter(|until| *until > now),
+        }
+    }
+
+    /// Checks that `session_id` may run a write action and audits it when the
+    /// session only may because it is elevated.
+    pub async fn require_write(&self, session_id: Uuid, action: &str) -> Result<(), String> {
+        if !self.read_only {
+            return Ok(());
+        }
+        if self.status(session_id).await.writable(unix_now()) {
+            self.audit
+                .record(session_id, "elevated_action", json!({ "action": action }));
+            return Ok(());
+        }
+        Err(format!(
+            "{action} is not allowed: this session is read-only, elevate it to write files or run commands"
+        ))
+    }
+
+    pub async fn elevate(&self, session_id: Uuid, credential: &str) -> Result<SessionAccess, String> {
+        if !self.read_only {
+            return Err("this session already has full access".to_string());
+        }
+        let Some(password) = &self.elevation_password else {
+            return Err(
+                "elevation is disabled on this server (start it with --elevation-password)".to_string(),
+            );
+        };
+        if Sha256::digest(credential.as_bytes()) != Sha256::digest(password.as_bytes()) {
+            self.audit.record(session_id, "elevation_refused", json!({}));
+            return Err("wrong elevation credential".to_string());
+        }
+
+        let grant = Uuid::new_v4();
+        let until = unix_now() + self.window.as_secs();
+        self.elevations
+            .lock()
+            .await
+            .insert(session_id, Elevation { grant, until });
+        self.audit.record(
+            session_id,
+            "elevation_granted",
+            json!({ "until": until, "window_secs": self.window.as_secs() }),
+        );
+
+        let elevations = self.elevations.clone();
+        let audit = self.audit.clone();
+        let window = self.window;
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            let mut elevations = elevations.lock().await;
+            if elevations
+                .get(&session_id)
+                .is_some_and(|elevation| elevation.grant == grant)
+            {
+                elevations.remove(&session_id);
+                audit.record(session_id, "elevation_ended", json!({ "reason": "expired" }));
+            }
+        });
+        Ok(self.status(session_id).await)
+    }
+
+    /// Ends the session's elevation early; `reason` goes to the audit trail.
+    pub async fn end_elevation(&self, session_id: Uuid, reason: &str) {
+        if self.elevations.lock().await.remove(&session_id).is_some() {
+            self.audit
+                .record(session_id, "elevation_ended", json!({ "reason": reason }));
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use rs_peer_workspace_shared::relay::unix_now;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Security-relevant events, logged under the `audit` target and, with
+/// `--audit-log`, appended to that file as one JSON object per line.
+pub struct AuditLog {
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    pub fn open(path: Option<&Path>) -> anyhow::Result<Self> {
+        let file = match path {
+            Some(path) => Some(Mutex::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )),
+            None => None,
+        };
+        Ok(Self { file })
+    }
+
+    pub fn record(&self, session_id: Uuid, event: &str, details: Value) {
+        info!(target: "audit", %session_id, event, %details);
+        let Some(file) = &self.file else {
+            return;
+        };
+        let line = json!({
+            "at": unix_now(),
+            "session_id": session_id,
+            "event": event,
+            "details": details,
+        });
+        let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = writeln!(file, "{line}") {
+            warn!(error = %err, "failed to write audit log");
+        }
+    }
+}
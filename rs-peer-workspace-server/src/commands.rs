@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use tokio::sync::{oneshot, Mutex};
+use tracing::info;
+use uuid::Uuid;
+
+struct Running {
+    session_id: Uuid,
+    cancel: oneshot::Sender<()>,
+}
+
+/// `run_command` calls still running, keyed by their request id, so they can be
+/// cancelled. Commands are spawned with `kill_on_drop`, so cancelling one, or
+/// ending its session, kills its process.
+#[derive(Default)]
+pub struct RunningCommands {
+    commands: Mutex<HashMap<Uuid, Running>>,
+}
+
+impl RunningCommands {
+    /// Runs `command` until it finishes, or returns `None` once it is cancelled.
+    pub async fn run<T>(&self, session_id: Uuid, command_id: Uuid, command: impl Future<Output = T>) -> Option<T> {
+        let (cancel, cancelled) = oneshot::channel();
+        self.commands
+            .lock()
+            .await
+            .insert(command_id, Running { session_id, cancel });
+        let result = tokio::select! {
+            result = command => Some(result),
+            _ = cancelled => None,
+        };
+        self.commands.lock().await.remove(&command_id);
+        result
+    }
+
+    pub async fn cancel(&self, session_id: Uuid, command_id: Uuid) -> anyhow::Result<()> {
+        let mut commands = self.commands.lock().await;
+        match commands.get(&command_id) {
+            Some(running) if running.session_id == session_id => {
+                if let Some(running) = commands.remove(&command_id) {
+                    let _ = running.cancel.send(());
+                }
+                info!(%command_id, "command cancelled");
+                Ok(())
+            }
+            _ => anyhow::bail!("no such running command"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.commands.lock().await.retain(|_, running| running.session_id != session_id);
+    }
+}
@@ -0,0 +1,89 @@
+use tokio::process::Command;
+
+use crate::protocol::{ContainerEntry, EnvVar};
+
+pub async fn list_containers() -> anyhow::Result<Vec<ContainerEntry>> {
+    let output = Command::new("docker")
+        .args(["ps", "--format", "{{.ID}}\t{{.Names}}\t{{.Image}}\t{{.Status}}"])
+        .output()
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to run docker: {err}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "docker ps failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut containers: Vec<ContainerEntry> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+            Some(ContainerEntry {
+                id: parts.next()?.to_string(),
+                name: parts.next()?.to_string(),
+                image: parts.next()?.to_string(),
+                status: parts.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect();
+    containers.sort_by_key(|container| container.name.to_lowercase());
+    Ok(containers)
+}
+
+pub fn exec_in_container(
+    container: &str,
+    command: String,
+    shell: Option<String>,
+    cwd: Option<String>,
+    env: Vec<EnvVar>,
+) -> anyhow::Result<Command> {
+    validate_container_name(container)?;
+    let shell = shell
+        .map(|shell| shell.trim().to_string())
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "sh".to_string());
+
+    let mut process = Command::new("docker");
+    process.arg("exec");
+    if let Some(cwd) = cwd.filter(|cwd| !cwd.trim().is_empty()) {
+        process.arg("--workdir").arg(cwd);
+    }
+    for var in env {
+        process.arg("--env").arg(format!("{}={}", var.name, var.value));
+    }
+    process.arg(container).arg(shell).arg("-c").arg(command);
+    Ok(process)
+}
+
+pub async fn container_logs(container: &str, lines: usize) -> anyhow::Result<String> {
+    validate_container_name(container)?;
+    let output = Command::new("docker")
+        .args(["logs", "--tail", &lines.clamp(1, 5000).to_string(), container])
+        .output()
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to run docker: {err}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "docker logs failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut logs = String::from_utf8_lossy(&output.stdout).to_string();
+    logs.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(logs)
+}
+
+fn validate_container_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        anyhow::bail!("invalid container name '{name}'")
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rs_peer_workspace_shared::crash;
+use rs_peer_workspace_shared::relay::unix_now;
+use rs_peer_workspace_shared::stat::{disk_space, modified_secs};
+use tokio::process::Command;
+use tokio::time::timeout;
+use walkdir::WalkDir;
+
+use crate::protocol::ServerMetadata;
+use crate::scratch::{create_private, ScratchFiles};
+use crate::tools::detect_tools;
+
+const JOURNAL_TIMEOUT: Duration = Duration::from_secs(15);
+const JOURNAL_ENTRIES: usize = 1000;
+/// Larger files are cut down to their last this many bytes, where logs keep the
+/// most recent entries.
+const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+/// Files stop being added once the bundle holds this many bytes.
+const MAX_BUNDLE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Collects support bundles: system information, recent server log lines, the
+/// system journal, crash reports and the configured log paths.
+pub struct Diagnostics {
+    paths: Vec<PathBuf>,
+    crash_dir: PathBuf,
+    metadata: ServerMetadata,
+    scratch: ScratchFiles,
+}
+
+impl Diagnostics {
+    pub fn new(
+        paths: Vec<PathBuf>,
+        crash_dir: PathBuf,
+        metadata: ServerMetadata,
+        scratch: ScratchFiles,
+    ) -> Self {
+        Self {
+            paths,
+            crash_dir,
+            metadata,
+            scratch,
+        }
+    }
+
+    /// Writes a `.tar.gz` bundle to a scratch file and returns its path and size.
+    pub async fn collect(&self) -> anyhow::Result<(PathBuf, u64)> {
+        let mut system = self.system_info();
+        let _ = writeln!(system, "\ntools:");
+        for tool in detect_tools().await {
+            let _ = writeln!(
+                system,
+                "  {}: {}",
+                tool.name,
+                tool.version.as_deref().unwrap_or("not found")
+            );
+        }
+        let journal = recent_journal().await;
+
+        let now = unix_now();
+        let name = format!("diagnostics-{}-{now}", sanitize(&self.metadata.hostname));
+        let archive = ScratchFiles::path(&name, "tar.gz");
+        let paths = self.paths.clone();
+        let crash_dir = self.crash_dir.clone();
+        let target = archive.clone();
+        tokio::task::spawn_blocking(move || {
+            write_bundle(&target, &name, now, system, journal, &crash_dir, &paths)
+        })
+        .await??;
+        let size = self.scratch.track(archive.clone()).await?;
+        Ok((archive, size))
+    }
+
+    fn system_info(&self) -> String {
+        let metadata = &self.metadata;
+        let mut info = String::new();
+        let _ = writeln!(info, "hostname: {}", metadata.hostname);
+        let _ = writeln!(info, "os: {}", metadata.os);
+        let _ = writeln!(info, "arch: {}", metadata.arch);
+        let _ = writeln!(info, "agent version: {}", metadata.agent_version);
+        let _ = writeln!(info, "tags: {}", metadata.tags.join(", "));
+        let _ = writeln!(info, "collected at: {}", unix_now());
+        if let Ok(cwd) = std::env::current_dir() {
+            let _ = writeln!(info, "working directory: {}", cwd.display());
+            if let Ok(disk) = disk_space(&cwd) {
+                let _ = writeln!(
+                    info,
+                    "disk: {} of {} bytes available",
+                    disk.available, disk.total
+                );
+            }
+        }
+        let _ = writeln!(
+            info,
+            "parallelism: {}",
+            std::thread::available_parallelism().map_or(0, |count| count.get())
+        );
+        info
+    }
+}
+
+async fn recent_journal() -> Option<String> {
+    let mut command = if cfg!(target_os = "linux") {
+        let mut command = Command::new("journalctl");
+        command.args(["--no-pager", "-o", "short-iso", "-n", &JOURNAL_ENTRIES.to_string()]);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "Get-WinEvent -LogName System -MaxEvents {JOURNAL_ENTRIES} | Format-List TimeCreated,Id,LevelDisplayName,ProviderName,Message"
+            ),
+        ]);
+        command
+    } else {
+        return None;
+    };
+    let output = timeout(
+        JOURNAL_TIMEOUT,
+        command
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Some(text)
+}
+
+fn write_bundle(
+    target: &Path,
+    name: &str,
+    now: u64,
+    system: String,
+    journal: Option<String>,
+    crash_dir: &Path,
+    paths: &[PathBuf],
+) -> io::Result<()> {
+    let mut tar = tar::Builder::new(GzEncoder::new(create_private(target)?, Compression::default()));
+    let mut manifest = String::new();
+    let mut total = 0;
+
+    append(&mut tar, &format!("{name}/system.txt"), system.as_bytes(), now)?;
+    let log = crash::recent_log_lines().join("\n");
+    append(&mut tar, &format!("{name}/server.log"), log.as_bytes(), now)?;
+    match journal {
+        Some(journal) => append(&mut tar, &format!("{name}/journal.txt"), journal.as_bytes(), now)?,
+        None => manifest.push_str("journal: not available\n"),
+    }
+
+    let sources = std::iter::once((crash_dir, "crash-reports"))
+        .chain(paths.iter().map(|path| (path.as_path(), "files")));
+    for (source, folder) in sources {
+        if !source.exists() {
+            let _ = writeln!(manifest, "{}: not found", source.display());
+            continue;
+        }
+        for entry in WalkDir::new(source).follow_links(false) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    let _ = writeln!(manifest, "{}: {err}", source.display());
+                    continue;
+                }
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let archive_path = match folder {
+                "crash-reports" => path.strip_prefix(source).unwrap_or(path).to_path_buf(),
+                _ => path.to_path_buf(),
+            };
+            let archive_name = format!("{name}/{folder}/{}", relative_name(&archive_path));
+            if total >= MAX_BUNDLE_BYTES {
+                let _ = writeln!(manifest, "{}: skipped, bundle size limit reached", path.display());
+                continue;
+            }
+            match read_tail(path) {
+                Ok((data, truncated)) => {
+                    if truncated {
+                        let _ = writeln!(
+                            manifest,
+                            "{}: only the last {MAX_FILE_BYTES} bytes included",
+                            path.display()
+                        );
+                    }
+                    total += data.len() as u64;
+                    let mtime = modified_secs(&entry.metadata()?).unwrap_or(now);
+                    append(&mut tar, &archive_name, &data, mtime)?;
+                }
+                Err(err) => {
+                    let _ = writeln!(manifest, "{}: {err}", path.display());
+                }
+            }
+        }
+    }
+
+    if manifest.is_empty() {
+        manifest.push_str("all sources included\n");
+    }
+    append(&mut tar, &format!("{name}/manifest.txt"), manifest.as_bytes(), now)?;
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Adds `data` as a regular file; names too long for the header get a GNU long-name entry.
+fn append<W: io::Write>(tar: &mut tar::Builder<W>, path: &str, data: &[u8], mtime: u64) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_entry_type(tar::EntryType::Regular);
+    tar.append_data(&mut header, path, data)
+}
+
+/// The file's content, or its last [`MAX_FILE_BYTES`] and `true` when it is larger.
+fn read_tail(path: &Path) -> io::Result<(Vec<u8>, bool)> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let truncated = size > MAX_FILE_BYTES;
+    if truncated {
+        file.seek(SeekFrom::Start(size - MAX_FILE_BYTES))?;
+    }
+    let mut data = Vec::new();
+    file.take(MAX_FILE_BYTES).read_to_end(&mut data)?;
+    Ok((data, truncated))
+}
+
+/// `path` as a relative archive path with `/` separators, without its root or drive.
+fn relative_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || ch == '-' { ch } else { '_' })
+        .collect();
+    if name.is_empty() { "server".to_string() } else { name }
+}
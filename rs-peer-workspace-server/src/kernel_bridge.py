@@ -0,0 +1,66 @@
+import json
+import queue
+import re
+import sys
+import threading
+
+from jupyter_client.manager import start_new_kernel
+
+ANSI = re.compile(r"\x1b\[[0-9;]*[A-Za-z]")
+
+
+def emit(message):
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+def read_requests(manager, requests):
+    for line in sys.stdin:
+        request = json.loads(line)
+        if request.get("type") == "interrupt":
+            manager.interrupt_kernel()
+        else:
+            requests.put(request)
+    requests.put(None)
+
+
+def execute(client, code):
+    result = {"execution_count": None, "stream": "", "data": [], "error": None}
+
+    def on_output(message):
+        kind = message["msg_type"]
+        content = message["content"]
+        if kind == "stream":
+            result["stream"] += content["text"]
+        elif kind in ("execute_result", "display_data"):
+            result["data"].append(content["data"])
+        elif kind == "error":
+            result["error"] = ANSI.sub("", "\n".join(content["traceback"]))
+
+    try:
+        reply = client.execute_interactive(code, output_hook=on_output)
+        result["execution_count"] = reply["content"].get("execution_count")
+    except Exception as err:
+        result["error"] = str(err)
+    return result
+
+
+def main():
+    kernel_name = sys.argv[1] if len(sys.argv) > 1 else "python3"
+    try:
+        manager, client = start_new_kernel(kernel_name=kernel_name)
+    except Exception as err:
+        emit({"error": f"failed to start kernel: {err}"})
+        return
+    emit({"ready": True})
+
+    requests = queue.Queue()
+    threading.Thread(target=read_requests, args=(manager, requests), daemon=True).start()
+    while (request := requests.get()) is not None:
+        emit(execute(client, request["code"]))
+
+    client.stop_channels()
+    manager.shutdown_kernel(now=True)
+
+
+main()
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::protocol::{CellResult, KernelEntry, OutputAttachment};
+
+const BRIDGE_SCRIPT: &str = include_str!("kernel_bridge.py");
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[cfg(target_os = "windows")]
+const PYTHON: &str = "python";
+#[cfg(not(target_os = "windows"))]
+const PYTHON: &str = "python3";
+
+struct KernelProcess {
+    session_id: Uuid,
+    kernel_name: String,
+    child: Mutex<Child>,
+    stdin: Mutex<Option<ChildStdin>>,
+    stdout: Mutex<Lines<BufReader<ChildStdout>>>,
+}
+
+#[derive(Default)]
+pub struct KernelRegistry {
+    kernels: Mutex<HashMap<Uuid, Arc<KernelProcess>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BridgeReply {
+    #[serde(default)]
+    ready: bool,
+    #[serde(default)]
+    execution_count: Option<u32>,
+    #[serde(default)]
+    stream: String,
+    #[serde(default)]
+    data: Vec<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl KernelRegistry {
+    pub async fn start(&self, session_id: Uuid, kernel_name: Option<String>) -> anyhow::Result<KernelEntry> {
+        let kernel_name = kernel_name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| "python3".to_string());
+        let mut child = Command::new(PYTHON)
+            .arg("-c")
+            .arg(BRIDGE_SCRIPT)
+            .arg(&kernel_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| anyhow::anyhow!("failed to start {PYTHON}: {err}"))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("kernel stdin unavailable"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("kernel stdout unavailable"))?;
+        let process = KernelProcess {
+            session_id,
+            kernel_name: kernel_name.clone(),
+            child: Mutex::new(child),
+            stdin: Mutex::new(Some(stdin)),
+            stdout: Mutex::new(BufReader::new(stdout).lines()),
+        };
+
+        let reply = read_reply(&process).await?;
+        if !reply.ready {
+            anyhow::bail!(reply.error.unwrap_or_else(|| "kernel failed to start".to_string()));
+        }
+
+        let kernel_id = Uuid::new_v4();
+        self.kernels.lock().await.insert(kernel_id, Arc::new(process));
+        Ok(KernelEntry { kernel_id, kernel_name })
+    }
+
+    pub async fn list(&self, session_id: Uuid) -> Vec<KernelEntry> {
+        self.kernels
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, process)| process.session_id == session_id)
+            .map(|(kernel_id, process)| KernelEntry {
+                kernel_id: *kernel_id,
+                kernel_name: process.kernel_name.clone(),
+            })
+            .collect()
+    }
+
+    pub async fn execute(&self, session_id: Uuid, kernel_id: Uuid, code: String) -> anyhow::Result<CellResult> {
+        let process = self.get(session_id, kernel_id).await?;
+        let mut stdout = process.stdout.lock().await;
+        send_request(&process, serde_json::json!({ "type": "execute", "code": code })).await?;
+        let line = stdout
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("kernel bridge exited"))?;
+        let reply: BridgeReply = serde_json::from_str(&line)?;
+
+        let mut output = reply.stream;
+        let mut attachments = Vec::new();
+        for bundle in reply.data {
+            bundle_to_output(bundle, &mut output, &mut attachments);
+        }
+        Ok(CellResult {
+            kernel_id,
+            execution_count: reply.execution_count,
+            output,
+            attachments,
+            error: reply.error,
+        })
+    }
+
+    pub async fn interrupt(&self, session_id: Uuid, kernel_id: Uuid) -> anyhow::Result<()> {
+        let process = self.get(session_id, kernel_id).await?;
+        send_request(&process, serde_json::json!({ "type": "interrupt" })).await
+    }
+
+    pub async fn shutdown(&self, session_id: Uuid, kernel_id: Uuid) -> anyhow::Result<()> {
+        self.get(session_id, kernel_id).await?;
+        if let Some(process) = self.kernels.lock().await.remove(&kernel_id) {
+            stop(process).await;
+        }
+        Ok(())
+    }
+
+    pub async fn shutdown_session(&self, session_id: Uuid) {
+        let removed: Vec<Arc<KernelProcess>> = {
+            let mut kernels = self.kernels.lock().await;
+            let owned: Vec<Uuid> = kernels
+                .iter()
+                .filter(|(_, process)| process.session_id == session_id)
+                .map(|(kernel_id, _)| *kernel_id)
+                .collect();
+            owned
+                .into_iter()
+                .filter_map(|kernel_id| kernels.remove(&kernel_id))
+                .collect()
+        };
+        for process in removed {
+            tokio::spawn(stop(process));
+        }
+    }
+
+    async fn get(&self, session_id: Uuid, kernel_id: Uuid) -> anyhow::Result<Arc<KernelProcess>> {
+        self.kernels
+            .lock()
+            .await
+            .get(&kernel_id)
+            .filter(|process| process.session_id == session_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("unknown kernel {kernel_id}"))
+    }
+}
+
+async fn stop(process: Arc<KernelProcess>) {
+    process.stdin.lock().await.take();
+    let mut child = process.child.lock().await;
+    if tokio::time::timeout(SHUTDOWN_GRACE, child.wait()).await.is_err() {
+        let _ = child.kill().await;
+    }
+}
+
+async fn send_request(process: &KernelProcess, request: serde_json::Value) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(&request)?;
+    line.push('\n');
+    let mut stdin = process.stdin.lock().await;
+    let stdin = stdin.as_mut().ok_or_else(|| anyhow::anyhow!("kernel is shutting down"))?;
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+async fn read_reply(process: &KernelProcess) -> anyhow::Result<BridgeReply> {
+    let line = process
+        .stdout
+        .lock()
+        .await
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("kernel bridge exited (is jupyter_client installed?)"))?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn bundle_to_output(
+    mut bundle: HashMap<String, serde_json::Value>,
+    output: &mut String,
+    attachments: &mut Vec<OutputAttachment>,
+) {
+    for mime in ["image/png", "image/jpeg", "image/gif"] {
+        if let Some(serde_json::Value::String(encoded)) = bundle.get(mime) {
+            let cleaned: String = encoded.chars().filter(|ch| !ch.is_whitespace()).collect();
+            if let Ok(data) = base64::engine::general_purpose::STANDARD.decode(cleaned) {
+                attachments.push(OutputAttachment::Image {
+                    mime: mime.to_string(),
+                    data,
+                });
+                return;
+            }
+        }
+    }
+    if let Some(value) = bundle.remove("application/json") {
+        attachments.push(OutputAttachment::Json {
+            value: serde_json::to_string_pretty(&value).unwrap_or_default(),
+        });
+        return;
+    }
+    if let Some(serde_json::Value::String(text)) = bundle.get("text/plain") {
+        output.push_str(text);
+        if !text.ends_with('\n') {
+            output.push('\n');
+        }
+    }
+}
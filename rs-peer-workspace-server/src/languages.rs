@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rs_peer_workspace_shared::lsp::{encode, FrameReader};
+use rs_peer_workspace_shared::tunnel::InOrder;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::AbortHandle;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::protocol::{RpcResponse, RpcResult};
+use crate::sessions::SessionRegistry;
+
+/// How long an exited language server's status is awaited after its output ends.
+const EXIT_GRACE: Duration = Duration::from_secs(5);
+
+struct LanguageServer {
+    session_id: Uuid,
+    messages: mpsc::UnboundedSender<(u64, String)>,
+    task: AbortHandle,
+}
+
+type Servers = Arc<Mutex<HashMap<Uuid, LanguageServer>>>;
+
+/// Running language servers, keyed by the request id of their
+/// `start_language_server` call.
+#[derive(Default)]
+pub struct LanguageServers {
+    servers: Servers,
+}
+
+impl LanguageServers {
+    /// Spawns `command` in `root` and relays its stdio: messages it writes are
+    /// pushed to the session under `server_id`, and those passed to `send` are
+    /// written to it in sequence order.
+    pub async fn start(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        server_id: Uuid,
+        command: &str,
+        args: &[String],
+        root: &str,
+    ) -> anyhow::Result<()> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| anyhow::anyhow!("failed to start {command}: {err}"))?;
+        let mut stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("language server stdin unavailable"))?;
+        let mut stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("language server stdout unavailable"))?;
+        info!(%server_id, %command, %root, "language server started");
+
+        let (messages, mut message_rx) = mpsc::unbounded_channel::<(u64, String)>();
+        let registry = self.servers.clone();
+        let mut servers = self.servers.lock().await;
+        let task = tokio::spawn(async move {
+            let input = async {
+                let mut order = InOrder::default();
+                while let Some((seq, message)) = message_rx.recv().await {
+                    for message in order.push(seq, message) {
+                        stdin.write_all(&encode(&message)).await?;
+                    }
+                    stdin.flush().await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            };
+            let output = async {
+                let mut reader = FrameReader::default();
+                let mut buf = vec![0u8; 64 * 1024];
+                let mut seq = 0;
+                loop {
+                    let len = stdout.read(&mut buf).await?;
+                    if len == 0 {
+                        return Ok::<_, anyhow::Error>(());
+                    }
+                    for message in reader.push(&buf[..len])? {
+                        let response = RpcResponse {
+                            request_id: server_id,
+                            result: RpcResult::LspMessage {
+                                server_id,
+                                seq,
+                                message,
+                            },
+                        };
+                        seq += 1;
+                        if sessions.push_response(session_id, response).await.is_err() {
+                            anyhow::bail!("session gone");
+                        }
+                    }
+                }
+            };
+            // The input side only ends when the server is stopped, so the relay
+            // ends with the server's output.
+            let result = tokio::select! {
+                result = input => result,
+                result = output => result,
+            };
+            let error = match result {
+                Err(err) => Some(err.to_string()),
+                Ok(()) => match tokio::time::timeout(EXIT_GRACE, child.wait()).await {
+                    Ok(Ok(status)) if status.success() => None,
+                    Ok(Ok(status)) => Some(format!("language server exited with {status}")),
+                    Ok(Err(err)) => Some(err.to_string()),
+                    Err(_) => Some("language server closed its output".to_string()),
+                },
+            };
+            debug!(%server_id, ?error, "language server stopped");
+            registry.lock().await.remove(&server_id);
+            let stopped = RpcResponse {
+                request_id: server_id,
+                result: RpcResult::LanguageServerStopped { server_id, error },
+            };
+            let _ = sessions.push_response(session_id, stopped).await;
+        });
+        servers.insert(
+            server_id,
+            LanguageServer {
+                session_id,
+                messages,
+                task: task.abort_handle(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Queues a message for the language server's stdin.
+    pub async fn send(&self, session_id: Uuid, server_id: Uuid, seq: u64, message: String) -> anyhow::Result<()> {
+        let servers = self.servers.lock().await;
+        let server = servers
+            .get(&server_id)
+            .filter(|server| server.session_id == session_id)
+            .ok_or_else(|| anyhow::anyhow!("no such language server"))?;
+        server
+            .messages
+            .send((seq, message))
+            .map_err(|_| anyhow::anyhow!("language server stopped"))
+    }
+
+    /// Kills the language server.
+    pub async fn stop(&self, session_id: Uuid, server_id: Uuid) -> anyhow::Result<()> {
+        let mut servers = self.servers.lock().await;
+        match servers.get(&server_id) {
+            Some(server) if server.session_id == session_id => {
+                server.task.abort();
+                servers.remove(&server_id);
+                Ok(())
+            }
+            _ => anyhow::bail!("no such language server"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.servers.lock().await.retain(|_, server| {
+            if server.session_id == session_id {
+                server.task.abort();
+            }
+            server.session_id != session_id
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use rs_peer_workspace_shared::relay::unix_now;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::protocol::FileLock;
+
+const DEFAULT_TTL_SECS: u64 = 15 * 60;
+const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+
+struct LockEntry {
+    session_id: Uuid,
+    owner: String,
+    acquired_at: u64,
+    expires_at: u64,
+}
+
+impl LockEntry {
+    fn describe(&self, path: &str, session_id: Uuid) -> FileLock {
+        FileLock {
+            path: path.to_string(),
+            owner: self.owner.clone(),
+            acquired_at: self.acquired_at,
+            expires_at: self.expires_at,
+            mine: self.session_id == session_id,
+        }
+    }
+}
+
+/// Advisory per-path locks. Nothing stops a session from writing a locked file; clients
+/// check the lock before saving and show who holds it.
+#[derive(Default)]
+pub struct LockRegistry {
+    locks: Mutex<HashMap<String, LockEntry>>,
+}
+
+impl LockRegistry {
+    /// Takes the lock, or renews it when `session_id` already holds it. When another
+    /// session holds it, that lock is returned unchanged.
+    pub async fn lock(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        owner: String,
+        ttl_secs: Option<u64>,
+    ) -> FileLock {
+        let now = unix_now();
+        let ttl = ttl_secs.unwrap_or(DEFAULT_TTL_SECS).clamp(1, MAX_TTL_SECS);
+        let mut locks = self.locks.lock().await;
+        locks.retain(|_, entry| entry.expires_at > now);
+        let entry = locks.entry(path.to_string()).or_insert_with(|| LockEntry {
+            session_id,
+            owner: owner.clone(),
+            acquired_at: now,
+            expires_at: now,
+        });
+        if entry.session_id == session_id {
+            entry.owner = owner;
+            entry.expires_at = now + ttl;
+        }
+        entry.describe(path, session_id)
+    }
+
+    pub async fn unlock(&self, session_id: Uuid, path: &str) -> anyhow::Result<()> {
+        let now = unix_now();
+        let mut locks = self.locks.lock().await;
+        match locks.get(path) {
+            Some(entry) if entry.expires_at > now && entry.session_id != session_id => {
+                anyhow::bail!("{path} is locked by {}", entry.owner)
+            }
+            _ => {
+                locks.remove(path);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn status(&self, session_id: Uuid, path: &str) -> Option<FileLock> {
+        let now = unix_now();
+        self.locks
+            .lock()
+            .await
+            .get(path)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.describe(path, session_id))
+    }
+
+    pub async fn list(&self, session_id: Uuid) -> Vec<FileLock> {
+        let now = unix_now();
+        let mut locks: Vec<FileLock> = self
+            .locks
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, entry)| entry.expires_at > now)
+            .map(|(path, entry)| entry.describe(path, session_id))
+            .collect();
+        locks.sort_by(|a, b| a.path.cmp(&b.path));
+        locks
+    }
+
+    pub async fn release_session(&self, session_id: Uuid) {
+        self.locks
+            .lock()
+            .await
+            .retain(|_, entry| entry.session_id != session_id);
+    }
+}
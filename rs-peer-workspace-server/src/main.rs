@@ -1,24 +1,68 @@
+mod access;
+mod audit;
+mod commands;
+mod containers;
+mod diagnostics;
+mod kernels;
+mod languages;
+mod locks;
+mod processes;
 mod protocol;
+mod rich;
 mod rpc;
+mod scratch;
+mod search;
+mod services;
+mod sessions;
+mod system;
+mod tails;
+mod tools;
+mod transfers;
+mod trash;
+mod tunnels;
 mod transport {
     pub mod webrtc;
 }
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
 use serde::Serialize;
-use tokio::sync::{mpsc, Mutex};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 use webrtc::data_channel::RTCDataChannel;
 use webrtc::peer_connection::RTCPeerConnection;
 
-use protocol::{AuthRole, PeerToProxy, ProxyToPeer, TurnCredentials};
-use rpc::handle_rpc;
+use access::AccessControl;
+use audit::AuditLog;
+use commands::RunningCommands;
+use diagnostics::Diagnostics;
+use protocol::{
+    auth_proof, AuthRole, Freshness, PeerToProxy, ProxyToPeer, RegistrationOutcome, ServerMetadata, TurnCredentials,
+};
+use kernels::KernelRegistry;
+use languages::LanguageServers;
+use locks::LockRegistry;
+use processes::ProcessTable;
+use rpc::{handle_rpc, RpcContext};
+use scratch::ScratchFiles;
+use sessions::SessionRegistry;
+use system::SystemMonitors;
+use tails::TailRegistry;
+use trash::Trash;
+use tunnels::TunnelRegistry;
 use rs_peer_workspace_shared::app::{AppEnvelope, AppPayload};
+use rs_peer_workspace_shared::content::MAX_TEXT_LEN;
+use rs_peer_workspace_shared::identity::ServerKey;
+use rs_peer_workspace_shared::outbox::{self, Outbox};
+use rs_peer_workspace_shared::{crash, telemetry};
+use tracing::{debug, info, info_span, warn, Instrument};
 use transport::webrtc::handle_client_signal;
 
 #[derive(Parser, Debug)]
@@ -32,8 +76,45 @@ struct Args {
     server_name: String,
     #[arg(long)]
     server_password: String,
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    #[arg(long)]
+    max_sessions: Option<usize>,
+    #[arg(long)]
+    enable_service_control: bool,
+    #[arg(long)]
+    log_json: bool,
+    #[arg(long, default_value = "server-identity.key")]
+    identity_key: PathBuf,
+    #[arg(long, default_value = "crash-reports")]
+    crash_dir: PathBuf,
+    #[arg(long, default_value = ".rs-peer-trash")]
+    trash_dir: PathBuf,
+    /// Only allow file changes and commands in sessions that elevated first.
+    #[arg(long)]
+    read_only: bool,
+    /// Credential a read-only session presents to elevate.
+    #[arg(long)]
+    elevation_password: Option<String>,
+    #[arg(long, default_value_t = 10)]
+    elevation_minutes: u64,
+    /// Appends audit events such as elevations to this file as JSON lines.
+    #[arg(long)]
+    audit_log: Option<PathBuf>,
+    /// Log file or directory included in diagnostics bundles (repeatable).
+    #[arg(long = "diagnostics-path")]
+    diagnostics_paths: Vec<PathBuf>,
+    /// Largest text file `read_file` returns whole, in bytes.
+    #[arg(long, default_value_t = MAX_TEXT_LEN)]
+    max_read_size: u64,
+    /// Refuse sessions whose client does not offer end-to-end encryption.
+    #[arg(long)]
+    require_e2e: bool,
 }
 
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 struct SessionState {
     turn: Option<TurnCredentials>,
@@ -42,13 +123,57 @@ struct SessionState {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    telemetry::init("info", args.log_json);
+    crash::install(
+        "rs-peer-workspace-server",
+        env!("CARGO_PKG_VERSION"),
+        args.crash_dir.clone(),
+    );
+    for report in crash::pending_reports(&args.crash_dir) {
+        match crash::acknowledge(&report) {
+            Ok(report) => warn!(report = %report.display(), "previous run crashed"),
+            Err(err) => warn!(report = %report.display(), error = %err, "failed to move crash report"),
+        }
+    }
     let _runmat_installed_marker = "runmat-runtime";
+    let identity = ServerKey::load_or_create(&args.identity_key)?;
+    info!(fingerprint = %identity.fingerprint(), "loaded server identity key");
 
-    let (ws_stream, _) = connect_async(&args.proxy_url).await?;
-    println!("connected to proxy {}", args.proxy_url);
+    // The name the proxy registered us under, which a suffix may have changed.
+    let mut server_name = args.server_name.clone();
+    let mut resume_token = None;
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        match connect_async(&args.proxy_url).await {
+            Ok((ws_stream, _)) => {
+                info!(proxy_url = %args.proxy_url, "connected to proxy");
+                serve_proxy(&args, &identity, ws_stream, &mut server_name, &mut resume_token).await?;
+                warn!("proxy connection closed");
+                delay = RECONNECT_MIN_DELAY;
+            }
+            Err(err) if resume_token.is_some() => {
+                warn!(error = %err, "failed to reconnect to proxy");
+            }
+            Err(err) => return Err(err.into()),
+        }
+        if resume_token.is_none() {
+            anyhow::bail!("proxy closed the connection before the server was registered");
+        }
+        info!(delay_secs = delay.as_secs(), "reconnecting to proxy");
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+}
 
+async fn serve_proxy(
+    args: &Args,
+    identity: &ServerKey,
+    ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    registered_name: &mut String,
+    resume_token: &mut Option<String>,
+) -> anyhow::Result<()> {
     let (mut write, mut read) = ws_stream.split();
-    let (ws_send_tx, mut ws_send_rx) = mpsc::unbounded_channel::<String>();
+    let (ws_send_tx, mut ws_send_rx) = outbox::channel::<String>(outbox::DEFAULT_CAPACITY);
     let writer = tokio::spawn(async move {
         while let Some(text) = ws_send_rx.recv().await {
             if write.send(Message::Text(text.into())).await.is_err() {
@@ -57,80 +182,270 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
+    let freshness = Freshness::new();
     send_json(&ws_send_tx, &PeerToProxy::AuthProxy {
-        proxy_password: args.proxy_password.clone(),
+        proof: auth_proof(&args.proxy_password, &freshness),
         role: AuthRole::Server,
+        freshness,
     })?;
+    let metadata = ServerMetadata {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        hostname: gethostname::gethostname().to_string_lossy().to_string(),
+        agent_version: env!("CARGO_PKG_VERSION").to_string(),
+        tags: args.tags.clone(),
+    };
     send_json(&ws_send_tx, &PeerToProxy::RegisterServer {
-        server_name: args.server_name.clone(),
+        server_name: registered_name.clone(),
         server_password: args.server_password.clone(),
+        metadata: metadata.clone(),
+        resume_token: resume_token.clone(),
     })?;
 
     let session_meta = Arc::new(Mutex::new(HashMap::<Uuid, SessionState>::new()));
+    let mut busy = false;
+    report_capacity(&ws_send_tx, args.max_sessions, 0, &mut busy)?;
     let peer_connections = Arc::new(Mutex::new(HashMap::<Uuid, Arc<RTCPeerConnection>>::new()));
     let data_channels = Arc::new(Mutex::new(HashMap::<Uuid, Arc<RTCDataChannel>>::new()));
+    let audit = Arc::new(AuditLog::open(args.audit_log.as_deref())?);
+    let scratch = ScratchFiles::default();
+    let rpc_context = Arc::new(RpcContext {
+        access: AccessControl::new(
+            args.read_only,
+            args.elevation_password.clone(),
+            Duration::from_secs(args.elevation_minutes * 60),
+            audit.clone(),
+        ),
+        allow_service_control: args.enable_service_control,
+        commands: RunningCommands::default(),
+        diagnostics: Diagnostics::new(
+            args.diagnostics_paths.clone(),
+            args.crash_dir.clone(),
+            metadata,
+            scratch.clone(),
+        ),
+        kernels: KernelRegistry::default(),
+        languages: LanguageServers::default(),
+        locks: LockRegistry::default(),
+        max_read_size: args.max_read_size,
+        processes: ProcessTable::default(),
+        scratch,
+        sessions: Arc::new(SessionRegistry::new(ws_send_tx.clone(), data_channels.clone(), args.require_e2e)),
+        system: SystemMonitors::default(),
+        tails: TailRegistry::default(),
+        trash: Trash::new(&args.trash_dir)?,
+        tunnels: TunnelRegistry::default(),
+    });
 
     while let Some(message) = read.next().await {
-        let message = message?;
+        let message = match message {
+            Ok(message) => message,
+            Err(err) => {
+                warn!(error = %err, "proxy connection failed");
+                break;
+            }
+        };
         let Message::Text(text) = message else { continue; };
         let Ok(proxy_message) = serde_json::from_str::<ProxyToPeer>(&text) else { continue; };
 
         match proxy_message {
-            ProxyToPeer::AuthOk { .. } => println!("proxy authentication succeeded"),
-            ProxyToPeer::Registered { server_name } => println!("server registered as '{server_name}'"),
+            ProxyToPeer::AuthOk { .. } => info!("proxy authentication succeeded"),
+            ProxyToPeer::Registered { server_name, resume_token: token, outcome } => {
+                match outcome {
+                    RegistrationOutcome::Replaced => {
+                        warn!(%server_name, "took over the server name from another connection");
+                    }
+                    RegistrationOutcome::Suffixed => {
+                        warn!(%server_name, requested = %args.server_name, "server name taken; registered under another name");
+                    }
+                    RegistrationOutcome::New | RegistrationOutcome::Resumed => {}
+                }
+                info!(%server_name, ?outcome, "server registered");
+                *registered_name = server_name;
+                if token.is_some() {
+                    *resume_token = token;
+                }
+            }
             ProxyToPeer::AuthError { reason } | ProxyToPeer::ConnectionError { reason } => anyhow::bail!("proxy rejected connection: {reason}"),
-            ProxyToPeer::PeerJoined { session_id, peer_id, via_p2p: _, turn } => {
-                println!("client {peer_id} joined session {session_id}");
-                session_meta.lock().await.insert(session_id, SessionState { turn });
+            ProxyToPeer::PeerJoined { session_id, peer_id, via_p2p: _, turn, username } => {
+                let mut sessions = session_meta.lock().await;
+                if args.max_sessions.is_some_and(|max| sessions.len() >= max) {
+                    warn!(%session_id, %peer_id, "rejecting session: server at capacity");
+                    send_json(&ws_send_tx, &PeerToProxy::DisconnectSession {
+                        session_id,
+                        reason: Some("server at capacity".to_string()),
+                    })?;
+                    continue;
+                }
+                info!(%session_id, %peer_id, username = username.as_deref(), "client joined session");
+                audit.record(session_id, "session_joined", serde_json::json!({
+                    "peer_id": peer_id,
+                    "username": username,
+                }));
+                sessions.insert(session_id, SessionState { turn });
+                rpc_context.sessions.add(session_id).await;
+                report_capacity(&ws_send_tx, args.max_sessions, sessions.len(), &mut busy)?;
             }
             ProxyToPeer::PeerSignal { session_id, from, signal } => {
                 if from != AuthRole::Client {
                     continue;
                 }
                 let turn = session_meta.lock().await.get(&session_id).and_then(|m| m.turn.clone());
-                handle_client_signal(
+                if let Err(err) = handle_client_signal(
                     session_id,
                     signal,
                     turn,
                     ws_send_tx.clone(),
+                    rpc_context.clone(),
                     peer_connections.clone(),
                     data_channels.clone(),
-                ).await?;
+                )
+                .instrument(info_span!("signal", %session_id))
+                .await
+                {
+                    warn!(%session_id, error = %err, "failed to handle client signal");
+                }
             }
-            ProxyToPeer::RelayData { session_id, payload } => {
+            ProxyToPeer::RelayData { session_id, payload, .. } => {
                 let maybe_dc = data_channels.lock().await.get(&session_id).cloned();
                 if let Some(dc) = maybe_dc {
                     let _ = dc.send(&bytes::Bytes::from(payload)).await;
-                } else if let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&payload) {
-                    if let AppPayload::RpcRequest(request) = envelope.payload {
-                        let response = handle_rpc(request).await;
-                        let out = AppEnvelope {
-                            message_id: Uuid::new_v4(),
-                            payload: AppPayload::RpcResponse(response),
-                        };
-                        let bytes = serde_json::to_vec(&out)?;
-                        send_json(&ws_send_tx, &PeerToProxy::RelayData { session_id, payload: bytes })?;
+                    continue;
+                }
+                let payload = match rpc_context.sessions.decode(session_id, payload).await {
+                    Ok(payload) => payload,
+                    Err(err) => {
+                        warn!(%session_id, error = %err, "dropped relayed payload");
+                        continue;
+                    }
+                };
+                let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&payload) else { continue; };
+                // With --require-e2e the session ends as soon as the client shows it will
+                // not seal its payloads: by challenging without having offered a key, or
+                // by sending a request before the session is sealed.
+                let refused = match &envelope.payload {
+                    AppPayload::IdentityChallenge(_) => {
+                        args.require_e2e && !rpc_context.sessions.offers_e2e(session_id).await
+                    }
+                    AppPayload::RpcRequest(_) => rpc_context.sessions.refuses_requests(session_id).await,
+                    _ => false,
+                };
+                if refused {
+                    warn!(%session_id, "rejecting session: client did not use end-to-end encryption");
+                    send_json(&ws_send_tx, &PeerToProxy::DisconnectSession {
+                        session_id,
+                        reason: Some("this server requires end-to-end encryption".to_string()),
+                    })?;
+                    let remaining = {
+                        let mut sessions = session_meta.lock().await;
+                        sessions.remove(&session_id);
+                        sessions.len()
+                    };
+                    rpc_context.sessions.remove(session_id).await;
+                    report_capacity(&ws_send_tx, args.max_sessions, remaining, &mut busy)?;
+                    continue;
+                }
+                match envelope.payload {
+                    AppPayload::RpcRequest(request) => {
+                        let rpc_context = rpc_context.clone();
+                        tokio::spawn(async move {
+                            let response = handle_rpc(&rpc_context, session_id, request).await;
+                            if let Err(err) = rpc_context.sessions.relay_response(session_id, response).await {
+                                warn!(%session_id, error = %err, "failed to relay response");
+                            }
+                        });
+                    }
+                    AppPayload::Capabilities(capabilities) => {
+                        debug!(
+                            %session_id,
+                            zstd = capabilities.zstd,
+                            deflate = capabilities.deflate,
+                            e2e = capabilities.e2e_key.is_some(),
+                            "client capabilities"
+                        );
+                        if let Err(err) = rpc_context.sessions.set_capabilities(session_id, capabilities).await {
+                            warn!(%session_id, error = %err, "failed to answer client capabilities");
+                        }
                     }
+                    AppPayload::IdentityChallenge(challenge) => {
+                        debug!(%session_id, "proving server identity");
+                        if let Err(err) = rpc_context.sessions.relay_identity(session_id, identity, &challenge).await {
+                            warn!(%session_id, error = %err, "failed to relay identity");
+                        }
+                    }
+                    AppPayload::RpcResponse(_) | AppPayload::Broadcast(_) | AppPayload::Identity(_) => {}
                 }
             }
             ProxyToPeer::SessionClosed { session_id, reason } => {
-                println!("session {session_id} closed: {reason}");
-                session_meta.lock().await.remove(&session_id);
+                info!(%session_id, %reason, "session closed");
+                let remaining = {
+                    let mut sessions = session_meta.lock().await;
+                    sessions.remove(&session_id);
+                    sessions.len()
+                };
+                report_capacity(&ws_send_tx, args.max_sessions, remaining, &mut busy)?;
+                rpc_context.sessions.remove(session_id).await;
+                rpc_context.kernels.shutdown_session(session_id).await;
+                rpc_context.languages.stop_session(session_id).await;
+                rpc_context.locks.release_session(session_id).await;
+                rpc_context.commands.stop_session(session_id).await;
+                rpc_context.processes.stop_session(session_id).await;
+                rpc_context.system.stop_session(session_id).await;
+                rpc_context.tails.stop_session(session_id).await;
+                rpc_context.tunnels.stop_session(session_id).await;
+                rpc_context.access.end_elevation(session_id, "session closed").await;
                 data_channels.lock().await.remove(&session_id);
                 if let Some(pc) = peer_connections.lock().await.remove(&session_id) {
                     let _ = pc.close().await;
                 }
             }
-            ProxyToPeer::Connected { .. } => {}
+            ProxyToPeer::Connected { .. } | ProxyToPeer::ServersList { .. } => {}
         }
     }
 
+    for session_id in session_meta.lock().await.keys() {
+        rpc_context.sessions.remove(*session_id).await;
+        rpc_context.kernels.shutdown_session(*session_id).await;
+        rpc_context.languages.stop_session(*session_id).await;
+        rpc_context.commands.stop_session(*session_id).await;
+        rpc_context.processes.stop_session(*session_id).await;
+        rpc_context.system.stop_session(*session_id).await;
+        rpc_context.tails.stop_session(*session_id).await;
+        rpc_context.tunnels.stop_session(*session_id).await;
+        rpc_context.access.end_elevation(*session_id, "server disconnected").await;
+    }
+    for (_, pc) in peer_connections.lock().await.drain() {
+        let _ = pc.close().await;
+    }
     writer.abort();
     Ok(())
 }
 
-pub(crate) fn send_json(tx: &mpsc::UnboundedSender<String>, payload: &impl Serialize) -> anyhow::Result<()> {
+fn report_capacity(
+    ws_tx: &Outbox<String>,
+    max_sessions: Option<usize>,
+    active_sessions: usize,
+    busy: &mut bool,
+) -> anyhow::Result<()> {
+    let now_busy = max_sessions.is_some_and(|max| active_sessions >= max);
+    if now_busy != *busy {
+        *busy = now_busy;
+        info!(active_sessions, busy = now_busy, "capacity changed");
+        send_json(ws_tx, &PeerToProxy::SetBusy { busy: now_busy })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn send_json(tx: &Outbox<String>, payload: &impl Serialize) -> anyhow::Result<()> {
+    let text = serde_json::to_string(payload)?;
+    tx.send_control(text)?;
+    Ok(())
+}
+
+/// Like [`send_json`] for output the client can afford to miss, which makes way for
+/// newer output when the proxy is not keeping up.
+pub(crate) fn stream_json(tx: &Outbox<String>, payload: &impl Serialize) -> anyhow::Result<()> {
     let text = serde_json::to_string(payload)?;
-    let _ = tx.send(text);
+    tx.send_stream(text);
     Ok(())
 }
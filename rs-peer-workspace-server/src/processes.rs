@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rs_peer_workspace_shared::app::ProcessEntry;
+use sysinfo::{
+    Pid, Process, ProcessRefreshKind, ProcessesToUpdate, Signal, System, UpdateKind, MINIMUM_CPU_UPDATE_INTERVAL,
+};
+use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::protocol::{RpcResponse, RpcResult};
+use crate::sessions::SessionRegistry;
+
+/// Shortest interval a watch may ask for.
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+struct Watch {
+    session_id: Uuid,
+    task: AbortHandle,
+}
+
+/// The server's processes. Listings share one sample, so the CPU usage of each
+/// covers the time since the one before; watches are keyed by the request id of
+/// their `watch_process` call.
+#[derive(Default)]
+pub struct ProcessTable {
+    system: Arc<std::sync::Mutex<System>>,
+    watches: Mutex<HashMap<Uuid, Watch>>,
+}
+
+impl ProcessTable {
+    pub async fn list(&self) -> anyhow::Result<Vec<ProcessEntry>> {
+        let system = self.system.clone();
+        let processes = tokio::task::spawn_blocking(move || {
+            let mut system = system.lock().unwrap_or_else(|err| err.into_inner());
+            if system.processes().is_empty() {
+                refresh(&mut system, ProcessesToUpdate::All);
+                std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            }
+            refresh(&mut system, ProcessesToUpdate::All);
+            let mut processes: Vec<ProcessEntry> = system.processes().values().map(entry).collect();
+            processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+            processes
+        })
+        .await?;
+        Ok(processes)
+    }
+
+    /// Sends the process SIGTERM, or SIGKILL with `force`. Where the OS has no
+    /// such signal, the process is killed.
+    pub async fn kill(&self, pid: u32, force: bool) -> anyhow::Result<()> {
+        if pid == std::process::id() {
+            anyhow::bail!("refusing to kill the server itself");
+        }
+        tokio::task::spawn_blocking(move || {
+            let pid = Pid::from_u32(pid);
+            let mut system = System::new();
+            refresh(&mut system, ProcessesToUpdate::Some(&[pid]));
+            let process = system
+                .process(pid)
+                .ok_or_else(|| anyhow::anyhow!("no such process {pid}"))?;
+            let signal = if force { Signal::Kill } else { Signal::Term };
+            let sent = process.kill_with(signal).unwrap_or_else(|| process.kill());
+            anyhow::ensure!(sent, "failed to signal process {pid}");
+            info!(%pid, force, "signalled process");
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Samples `pid`, then with a task pushes a `process_update` to the session
+    /// under `watch_id` every interval until the process exits.
+    pub async fn watch(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        watch_id: Uuid,
+        pid: u32,
+        interval: Duration,
+    ) -> anyhow::Result<ProcessEntry> {
+        let pid = Pid::from_u32(pid);
+        let (system, process) = tokio::task::spawn_blocking(move || {
+            let mut system = System::new();
+            refresh(&mut system, ProcessesToUpdate::Some(&[pid]));
+            std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            refresh(&mut system, ProcessesToUpdate::Some(&[pid]));
+            let process = system.process(pid).map(entry);
+            (system, process)
+        })
+        .await?;
+        let process = process.ok_or_else(|| anyhow::anyhow!("no such process {pid}"))?;
+
+        let task = tokio::spawn(follow_process(sessions, session_id, watch_id, system, pid, interval.max(MIN_INTERVAL)));
+        self.watches.lock().await.insert(
+            watch_id,
+            Watch {
+                session_id,
+                task: task.abort_handle(),
+            },
+        );
+        Ok(process)
+    }
+
+    pub async fn stop_watch(&self, session_id: Uuid, watch_id: Uuid) -> anyhow::Result<()> {
+        let mut watches = self.watches.lock().await;
+        match watches.get(&watch_id) {
+            Some(watch) if watch.session_id == session_id => {
+                watch.task.abort();
+                watches.remove(&watch_id);
+                Ok(())
+            }
+            _ => anyhow::bail!("no such process watch"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.watches.lock().await.retain(|_, watch| {
+            if watch.session_id == session_id {
+                watch.task.abort();
+            }
+            watch.session_id != session_id
+        });
+    }
+}
+
+async fn follow_process(
+    sessions: Arc<SessionRegistry>,
+    session_id: Uuid,
+    watch_id: Uuid,
+    mut system: System,
+    pid: Pid,
+    interval: Duration,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.tick().await;
+    loop {
+        ticks.tick().await;
+        let Ok((returned, process)) = tokio::task::spawn_blocking(move || {
+            refresh(&mut system, ProcessesToUpdate::Some(&[pid]));
+            let process = system.process(pid).map(entry);
+            (system, process)
+        })
+        .await
+        else {
+            return;
+        };
+        system = returned;
+        let exited = process.is_none();
+        let response = RpcResponse {
+            request_id: watch_id,
+            result: RpcResult::ProcessUpdate {
+                pid: pid.as_u32(),
+                process,
+            },
+        };
+        if sessions.push_output(session_id, response).await.is_err() {
+            debug!(%watch_id, "process watch session gone");
+            return;
+        }
+        if exited {
+            return;
+        }
+    }
+}
+
+fn refresh(system: &mut System, processes: ProcessesToUpdate<'_>) {
+    system.refresh_processes_specifics(
+        processes,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_cpu()
+            .with_memory()
+            .with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+}
+
+fn entry(process: &Process) -> ProcessEntry {
+    ProcessEntry {
+        pid: process.pid().as_u32(),
+        parent: process.parent().map(Pid::as_u32),
+        name: process.name().to_string_lossy().into_owned(),
+        command: process
+            .cmd()
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" "),
+        status: process.status().to_string(),
+        cpu_usage: process.cpu_usage(),
+        memory: process.memory(),
+        run_time_secs: process.run_time(),
+    }
+}
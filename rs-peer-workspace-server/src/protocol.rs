@@ -1,2 +1,2 @@
-pub use rs_peer_workspace_shared::relay::{AuthRole, PeerToProxy, ProxyToPeer, SignalPayload, TurnCredentials};
-pub use rs_peer_workspace_shared::app::{DirectoryEntry, RpcAction, RpcRequest, RpcResponse, RpcResult};
+pub use rs_peer_workspace_shared::relay::{auth_proof, AuthRole, Freshness, PeerToProxy, ProxyToPeer, RegistrationOutcome, ServerMetadata, SignalPayload, TurnCredentials};
+pub use rs_peer_workspace_shared::app::{CellResult, ContainerEntry, DirectoryEntry, EnvVar, FileLock, KernelEntry, ListFilter, OutputAttachment, ReplaceFile, ReplacedFile, RpcAction, RpcRequest, RpcResponse, RpcResult, SearchFileMatches, SearchQuery, ServiceEntry, SessionAccess, ToolInfo};
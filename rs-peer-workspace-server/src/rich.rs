@@ -0,0 +1,152 @@
+use base64::Engine;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::protocol::OutputAttachment;
+use crate::scratch::ScratchFiles;
+
+const INLINE_IMAGE_PREFIX: &str = "\x1b]1337;File=";
+const MAX_TABLE_ROWS: usize = 500;
+/// Larger images are sent as [`OutputAttachment::ImageFile`] and fetched in chunks.
+const MAX_INLINE_IMAGE_BYTES: usize = 64 * 1024;
+
+pub fn extract_attachments(output: String) -> (String, Vec<OutputAttachment>) {
+    let (text, mut attachments) = extract_inline_images(output);
+    if let Some(attachment) = structured_attachment(&text) {
+        attachments.push(attachment);
+    }
+    (text, attachments)
+}
+
+fn extract_inline_images(output: String) -> (String, Vec<OutputAttachment>) {
+    if !output.contains(INLINE_IMAGE_PREFIX) {
+        return (output, Vec::new());
+    }
+
+    let mut text = String::with_capacity(output.len());
+    let mut attachments = Vec::new();
+    let mut rest = output.as_str();
+    while let Some(start) = rest.find(INLINE_IMAGE_PREFIX) {
+        text.push_str(&rest[..start]);
+        let sequence = &rest[start + INLINE_IMAGE_PREFIX.len()..];
+        let Some((end, terminator_len)) = find_terminator(sequence) else {
+            // Not an image after all, or cut off: leave the output as it was.
+            rest = &rest[start..];
+            break;
+        };
+        let body = &sequence[..end];
+        rest = &sequence[end + terminator_len..];
+
+        let Some((_, encoded)) = body.split_once(':') else {
+            continue;
+        };
+        let Ok(data) = base64::engine::general_purpose::STANDARD.decode(encoded.trim()) else {
+            continue;
+        };
+        let Some(mime) = sniff_image_mime(&data) else {
+            text.push_str("[image in an unsupported format]\n");
+            continue;
+        };
+        text.push_str("[image]\n");
+        attachments.push(OutputAttachment::Image {
+            mime: mime.to_string(),
+            data,
+        });
+    }
+    text.push_str(rest);
+    (text, attachments)
+}
+
+fn find_terminator(sequence: &str) -> Option<(usize, usize)> {
+    let bell = sequence.find('\x07').map(|idx| (idx, 1));
+    let st = sequence.find("\x1b\\").map(|idx| (idx, 2));
+    match (bell, st) {
+        (Some(bell), Some(st)) => Some(if bell.0 < st.0 { bell } else { st }),
+        (bell, st) => bell.or(st),
+    }
+}
+
+/// The formats the client can draw; anything else, SVG included, is not attached.
+fn sniff_image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else {
+        None
+    }
+}
+
+/// Moves images over [`MAX_INLINE_IMAGE_BYTES`] into scratch files, so the result
+/// stays small and the client fetches them with the chunked transfer instead.
+pub async fn offload_large_images(attachments: &mut [OutputAttachment], scratch: &ScratchFiles) {
+    for attachment in attachments {
+        let OutputAttachment::Image { mime, data } = attachment else {
+            continue;
+        };
+        if data.len() <= MAX_INLINE_IMAGE_BYTES {
+            continue;
+        }
+        let extension = mime.rsplit('/').next().unwrap_or("img").to_string();
+        let size = data.len() as u64;
+        match scratch.write("output-image", &extension, data.clone()).await {
+            Ok(path) => {
+                *attachment = OutputAttachment::ImageFile {
+                    mime: std::mem::take(mime),
+                    path: path.to_string_lossy().to_string(),
+                    size,
+                };
+            }
+            Err(err) => warn!(%err, "failed to store a large image, sending it inline"),
+        }
+    }
+}
+
+fn structured_attachment(text: &str) -> Option<OutputAttachment> {
+    let trimmed = text.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    let value = serde_json::from_str::<Value>(trimmed).ok()?;
+    if let Some(table) = table_from_json(&value) {
+        return Some(table);
+    }
+    Some(OutputAttachment::Json {
+        value: serde_json::to_string_pretty(&value).ok()?,
+    })
+}
+
+fn table_from_json(value: &Value) -> Option<OutputAttachment> {
+    let rows = value.as_array()?;
+    if rows.is_empty() || !rows.iter().all(Value::is_object) {
+        return None;
+    }
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        for key in row.as_object()?.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rows = rows
+        .iter()
+        .take(MAX_TABLE_ROWS)
+        .filter_map(Value::as_object)
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| match row.get(column) {
+                    Some(Value::String(text)) => text.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(other) => other.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    Some(OutputAttachment::Table { columns, rows })
+}
@@ -1,13 +1,110 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+use rs_peer_workspace_shared::content::{content_hash, read_file_data, FileData};
+use rs_peer_workspace_shared::format;
+use rs_peer_workspace_shared::git;
+use rs_peer_workspace_shared::stat::{ensure_space, modified_secs, set_permissions, stat_entry};
 use tokio::fs;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
-use crate::protocol::{DirectoryEntry, RpcAction, RpcRequest, RpcResponse, RpcResult};
+use crate::protocol::{
+    DirectoryEntry, EnvVar, ListFilter, RpcAction, RpcRequest, RpcResponse, RpcResult,
+};
+use crate::access::AccessControl;
+use crate::diagnostics::Diagnostics;
+use crate::containers::{container_logs, exec_in_container, list_containers};
+use crate::kernels::KernelRegistry;
+use crate::languages::LanguageServers;
+use crate::locks::LockRegistry;
+use crate::rich::{extract_attachments, offload_large_images};
+use crate::scratch::ScratchFiles;
+use crate::search::{index_files, replace_in_files, search_files};
+use crate::sessions::SessionRegistry;
+use crate::commands::RunningCommands;
+use crate::processes::ProcessTable;
+use crate::system::SystemMonitors;
+use crate::tails::TailRegistry;
+use crate::tunnels::TunnelRegistry;
+use crate::services::{ServiceOperation, control_service, list_services, service_logs};
+use crate::tools::detect_tools;
+use crate::transfers::{checksum_chunks, read_chunk, write_chunk};
+use crate::trash::{rename_path, Trash};
 
-pub async fn handle_rpc(request: RpcRequest) -> RpcResponse {
+pub struct RpcContext {
+    pub access: AccessControl,
+    pub allow_service_control: bool,
+    pub commands: RunningCommands,
+    pub diagnostics: Diagnostics,
+    pub kernels: KernelRegistry,
+    pub languages: LanguageServers,
+    pub locks: LockRegistry,
+    pub max_read_size: u64,
+    pub processes: ProcessTable,
+    pub scratch: ScratchFiles,
+    pub sessions: Arc<SessionRegistry>,
+    pub system: SystemMonitors,
+    pub tails: TailRegistry,
+    pub trash: Trash,
+    pub tunnels: TunnelRegistry,
+}
+
+#[tracing::instrument(
+    name = "rpc",
+    skip_all,
+    fields(%session_id, request_id = %request.request_id, action = request.action.name())
+)]
+pub async fn handle_rpc(
+    context: &RpcContext,
+    session_id: Uuid,
+    request: RpcRequest,
+) -> RpcResponse {
+    debug!("handling request");
+    if request.action.needs_write_access()
+        && let Err(message) = context
+            .access
+            .require_write(session_id, request.action.name())
+            .await
+    {
+        warn!(%message, "request refused");
+        return RpcResponse {
+            request_id: request.request_id,
+            result: RpcResult::Error { message },
+        };
+    }
     let result = match request.action {
-        RpcAction::RunCommand { command } => RpcResult::CommandOutput {
-            output: execute_command(command).await,
+        RpcAction::RunCommand {
+            command,
+            shell,
+            cwd,
+            env,
+        } => {
+            match context
+                .commands
+                .run(session_id, request.request_id, execute_command(command, shell, cwd, env))
+                .await
+            {
+                Some((output, exit_code)) => {
+                    let (output, mut attachments) = extract_attachments(output);
+                    offload_large_images(&mut attachments, &context.scratch).await;
+                    RpcResult::CommandOutput {
+                        output,
+                        attachments,
+                        exit_code,
+                    }
+                }
+                None => RpcResult::Error {
+                    message: "command cancelled".to_string(),
+                },
+            }
+        }
+        RpcAction::CancelCommand { command_id } => match context.commands.cancel(session_id, command_id).await {
+            Ok(()) => RpcResult::CommandCancelled { command_id },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
         },
         RpcAction::ListRoots => match list_roots() {
             Ok(roots) => RpcResult::Roots { roots },
@@ -15,30 +112,574 @@ pub async fn handle_rpc(request: RpcRequest) -> RpcResponse {
                 message: err.to_string(),
             },
         },
-        RpcAction::ListDirectory { path } => match list_directory(&path).await {
+        RpcAction::ListDirectory { path, filter } => match list_directory(&path, &filter).await {
             Ok(entries) => RpcResult::DirectoryEntries { path, entries },
             Err(err) => RpcResult::Error {
                 message: err.to_string(),
             },
         },
-        RpcAction::ReadFile { path } => match fs::read_to_string(&path).await {
-            Ok(content) => RpcResult::FileContent { path, content },
+        RpcAction::ReadFile { path, known_hash } => match read_file(path.clone(), context.max_read_size).await {
+            Ok(FileData::Text(content)) => {
+                let hash = content_hash(content.as_bytes());
+                if known_hash.as_ref() == Some(&hash) {
+                    RpcResult::NotModified { path, hash }
+                } else {
+                    RpcResult::FileContent {
+                        hash: Some(hash),
+                        path,
+                        content,
+                    }
+                }
+            }
+            Ok(FileData::Binary { size, preview }) => RpcResult::BinaryContent {
+                path,
+                size,
+                preview,
+            },
+            Ok(FileData::TooLarge { size }) => RpcResult::TooLarge {
+                path,
+                size,
+                limit: context.max_read_size,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::WriteFile {
+            path,
+            content,
+            expected_hash,
+        } => match write_file(&path, content, expected_hash).await {
+            Ok(WriteOutcome::Written(hash)) => RpcResult::WriteComplete {
+                path,
+                hash: Some(hash),
+            },
+            Ok(WriteOutcome::Conflict { content, hash }) => {
+                info!(%path, "write refused: file changed since it was read");
+                RpcResult::Conflict {
+                    path,
+                    content,
+                    hash,
+                }
+            }
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StatEntry { path } => match stat_entry(&path) {
+            Ok(stat) => RpcResult::Stat {
+                stat: Box::new(stat),
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::SetPermissions { path, mode } => match set_permissions(&path, mode) {
+            Ok(stat) => {
+                info!(%path, mode = %format!("{mode:o}"), "changed permissions");
+                RpcResult::Stat {
+                    stat: Box::new(stat),
+                }
+            }
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::CreateFile { path, is_dir } => match create_entry(&path, is_dir).await {
+            Ok(()) => RpcResult::Created { path, is_dir },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::DeletePath { path } => match context.trash.delete(&path).await {
+            Ok((trash_path, hash)) => {
+                info!(%path, %trash_path, "moved path to trash");
+                RpcResult::Deleted {
+                    path,
+                    trash_path,
+                    hash,
+                }
+            }
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::RenamePath {
+            from,
+            to,
+            expected_hash,
+        } => match rename_path(&from, &to, expected_hash.as_deref()).await {
+            Ok(hash) => RpcResult::Renamed { from, to, hash },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::RestorePath {
+            trash_path,
+            path,
+            expected_hash,
+        } => {
+            match context
+                .trash
+                .restore(&trash_path, &path, expected_hash.as_deref())
+                .await
+            {
+                Ok(()) => RpcResult::Restored { path },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::SearchFiles {
+            root,
+            query,
+            replacement,
+        } => match search_files(root, query, replacement).await {
+            Ok((files, truncated)) => RpcResult::SearchResults { files, truncated },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::IndexTree { root, filter } => match index_files(root.clone(), filter).await {
+            Ok((paths, truncated)) => RpcResult::TreeIndex {
+                root,
+                paths,
+                truncated,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ReplaceInFiles {
+            query,
+            replacement,
+            files,
+        } => match replace_in_files(query, replacement, files).await {
+            Ok(files) => {
+                info!(files = files.len(), "replaced matches");
+                RpcResult::Replaced { files }
+            }
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ReadFileChunk {
+            path,
+            offset,
+            length,
+        } => match read_chunk(&path, offset, length).await {
+            Ok((data, total_size)) => {
+                context.scratch.served(&path, offset, data.len() as u64).await;
+                RpcResult::FileChunk {
+                    path,
+                    offset,
+                    checksum: Some(content_hash(&data)),
+                    data,
+                    total_size,
+                }
+            }
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::WriteFileChunk {
+            path,
+            offset,
+            data,
+            truncate,
+            total_size,
+            checksum,
+        } => match write_chunk(&path, offset, &data, truncate, total_size, checksum.as_deref()).await {
+            Ok(()) => RpcResult::ChunkWritten {
+                path,
+                offset,
+                length: data.len() as u64,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ChecksumChunks { path, chunks } => match checksum_chunks(&path, chunks).await {
+            Ok((checksums, total_size)) => RpcResult::ChunkChecksums {
+                path,
+                total_size,
+                checksums,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ListServices => match require_service_control(context) {
+            Ok(()) => match list_services().await {
+                Ok(services) => RpcResult::Services { services },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            },
+            Err(err) => err,
+        },
+        RpcAction::StartService { name } => {
+            service_operation(context, name, ServiceOperation::Start).await
+        }
+        RpcAction::StopService { name } => {
+            service_operation(context, name, ServiceOperation::Stop).await
+        }
+        RpcAction::RestartService { name } => {
+            service_operation(context, name, ServiceOperation::Restart).await
+        }
+        RpcAction::ServiceLogs { name, lines } => match require_service_control(context) {
+            Ok(()) => match service_logs(&name, lines).await {
+                Ok(logs) => RpcResult::ServiceLogs { name, logs },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            },
+            Err(err) => err,
+        },
+        RpcAction::DetectTools => RpcResult::Tools {
+            tools: detect_tools().await,
+        },
+        RpcAction::SystemInfo { interval_ms } => match context
+            .system
+            .start(
+                context.sessions.clone(),
+                session_id,
+                request.request_id,
+                interval_ms.map(Duration::from_millis),
+            )
+            .await
+        {
+            Ok(info) => RpcResult::SystemInfo { info: Box::new(info) },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StopSystemInfo { monitor_id } => match context.system.stop(session_id, monitor_id).await {
+            Ok(()) => RpcResult::SystemInfoStopped { monitor_id },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ListProcesses => match context.processes.list().await {
+            Ok(processes) => RpcResult::Processes { processes },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::KillProcess { pid, force } => match context.processes.kill(pid, force).await {
+            Ok(()) => RpcResult::ProcessKilled { pid },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::WatchProcess { pid, interval_ms } => match context
+            .processes
+            .watch(
+                context.sessions.clone(),
+                session_id,
+                request.request_id,
+                pid,
+                Duration::from_millis(interval_ms),
+            )
+            .await
+        {
+            Ok(process) => RpcResult::ProcessUpdate {
+                pid,
+                process: Some(process),
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StopWatchProcess { watch_id } => match context.processes.stop_watch(session_id, watch_id).await {
+            Ok(()) => RpcResult::ProcessWatchStopped { watch_id },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::TailFile { path, follow } => match context
+            .tails
+            .start(context.sessions.clone(), session_id, request.request_id, path.clone(), follow)
+            .await
+        {
+            Ok(lines) => RpcResult::TailLines {
+                path,
+                lines,
+                rotated: false,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StopTail { tail_id } => match context.tails.stop(session_id, tail_id).await {
+            Ok(()) => RpcResult::TailStopped { tail_id },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::CollectDiagnostics => match context.diagnostics.collect().await {
+            Ok((path, size)) => {
+                info!(path = %path.display(), size, "collected diagnostics");
+                RpcResult::DiagnosticsCollected {
+                    path: path.to_string_lossy().to_string(),
+                    size,
+                }
+            }
+            Err(err) => RpcResult::Error {
+                message: format!("failed to collect diagnostics: {err}"),
+            },
+        },
+        RpcAction::ListContainers => match list_containers().await {
+            Ok(containers) => RpcResult::Containers { containers },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ExecInContainer {
+            container,
+            command,
+            shell,
+            cwd,
+            env,
+        } => match exec_in_container(&container, command, shell, cwd, env) {
+            Ok(process) => {
+                let (output, exit_code) = collect_output(process).await;
+                let (output, mut attachments) = extract_attachments(output);
+                offload_large_images(&mut attachments, &context.scratch).await;
+                RpcResult::CommandOutput {
+                    output,
+                    attachments,
+                    exit_code,
+                }
+            }
             Err(err) => RpcResult::Error {
                 message: err.to_string(),
             },
         },
-        RpcAction::WriteFile { path, content } => {
-            let result = async {
-                if let Some(parent) = Path::new(&path).parent() {
-                    fs::create_dir_all(parent).await?;
+        RpcAction::StartKernel { kernel_name } => {
+            match context.kernels.start(session_id, kernel_name).await {
+                Ok(kernel) => RpcResult::KernelStarted { kernel },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::ListKernels => RpcResult::Kernels {
+            kernels: context.kernels.list(session_id).await,
+        },
+        RpcAction::ExecuteCell { kernel_id, code } => {
+            match context.kernels.execute(session_id, kernel_id, code).await {
+                Ok(mut cell) => {
+                    offload_large_images(&mut cell.attachments, &context.scratch).await;
+                    RpcResult::CellExecuted { cell }
                 }
-                fs::write(&path, content).await?;
-                anyhow::Ok(())
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::InterruptKernel { kernel_id } => {
+            match context.kernels.interrupt(session_id, kernel_id).await {
+                Ok(()) => RpcResult::KernelInterrupted { kernel_id },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::ShutdownKernel { kernel_id } => {
+            match context.kernels.shutdown(session_id, kernel_id).await {
+                Ok(()) => RpcResult::KernelStopped { kernel_id },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::Broadcast { message } => {
+            let message = message.trim().to_string();
+            if message.is_empty() {
+                RpcResult::Error {
+                    message: "broadcast message is empty".to_string(),
+                }
+            } else {
+                info!(%message, "broadcasting message");
+                match context.sessions.broadcast(session_id, message).await {
+                    Ok(recipients) => RpcResult::BroadcastSent { recipients },
+                    Err(err) => RpcResult::Error {
+                        message: err.to_string(),
+                    },
+                }
+            }
+        }
+        RpcAction::LockFile {
+            path,
+            owner,
+            ttl_secs,
+        } => {
+            let lock = context.locks.lock(session_id, &path, owner, ttl_secs).await;
+            RpcResult::FileLock {
+                path,
+                lock: Some(lock),
+            }
+        }
+        RpcAction::UnlockFile { path } => match context.locks.unlock(session_id, &path).await {
+            Ok(()) => RpcResult::FileLock { path, lock: None },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::FileLockStatus { path } => RpcResult::FileLock {
+            lock: context.locks.status(session_id, &path).await,
+            path,
+        },
+        RpcAction::ListFileLocks => RpcResult::FileLocks {
+            locks: context.locks.list(session_id).await,
+        },
+        RpcAction::ContainerLogs { container, lines } => {
+            match container_logs(&container, lines).await {
+                Ok(logs) => RpcResult::ContainerLogs { container, logs },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::SessionAccess => RpcResult::Access {
+            access: context.access.status(session_id).await,
+        },
+        RpcAction::Elevate { credential } => {
+            match context.access.elevate(session_id, &credential).await {
+                Ok(access) => RpcResult::Access { access },
+                Err(message) => RpcResult::Error { message },
             }
+        }
+        RpcAction::EndElevation => {
+            context.access.end_elevation(session_id, "ended by client").await;
+            RpcResult::Access {
+                access: context.access.status(session_id).await,
+            }
+        }
+        RpcAction::OpenTunnel { host, port } => match context
+            .tunnels
+            .open(context.sessions.clone(), session_id, request.request_id, &host, port)
+            .await
+        {
+            Ok(()) => RpcResult::TunnelOpened {
+                tunnel_id: request.request_id,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::ListenTunnel { port } => match context
+            .tunnels
+            .listen(context.sessions.clone(), session_id, request.request_id, port)
+            .await
+        {
+            Ok(port) => RpcResult::TunnelListening {
+                listener_id: request.request_id,
+                port,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::TunnelSend { tunnel_id, seq, data } => {
+            match context.tunnels.send(session_id, tunnel_id, seq, data).await {
+                Ok(()) => RpcResult::TunnelSent { tunnel_id, seq },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::CloseTunnel { tunnel_id } => match context.tunnels.close(session_id, tunnel_id).await {
+            Ok(()) => RpcResult::TunnelClosed {
+                tunnel_id,
+                error: None,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StartLanguageServer { command, args, root } => match context
+            .languages
+            .start(context.sessions.clone(), session_id, request.request_id, &command, &args, &root)
+            .await
+        {
+            Ok(()) => RpcResult::LanguageServerStarted {
+                server_id: request.request_id,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::LspSend {
+            server_id,
+            seq,
+            message,
+        } => match context.languages.send(session_id, server_id, seq, message).await {
+            Ok(()) => RpcResult::LspSent { server_id, seq },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::StopLanguageServer { server_id } => match context.languages.stop(session_id, server_id).await {
+            Ok(()) => RpcResult::LanguageServerStopped {
+                server_id,
+                error: None,
+            },
+            Err(err) => RpcResult::Error {
+                message: err.to_string(),
+            },
+        },
+        RpcAction::GitStatus { path } => {
+            let dir = path.clone();
+            match blocking(move || git::status(Path::new(&dir))).await {
+                Ok(status) => RpcResult::GitStatus { path, status },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::GitDiff { path, file } => {
+            let dir = path.clone();
+            match blocking(move || git::diff(Path::new(&dir), file.as_deref())).await {
+                Ok(diff) => RpcResult::GitDiff { path, diff },
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::GitCommit {
+            path,
+            message,
+            files,
+        } => {
+            let dir = path.clone();
+            match blocking(move || git::commit(Path::new(&dir), &message, &files)).await {
+                Ok(commit) => {
+                    info!(%path, %commit, "committed");
+                    RpcResult::GitCommitted { path, commit }
+                }
+                Err(err) => RpcResult::Error {
+                    message: err.to_string(),
+                },
+            }
+        }
+        RpcAction::FormatFile {
+            path,
+            formatter,
+            content,
+        } => {
+            let file = path.clone();
+            let formatted = blocking(move || {
+                let content = match content {
+                    Some(content) => content,
+                    None => std::fs::read_to_string(&file)?,
+                };
+                format::format(formatter, formatter.program(), Path::new(&file), &content)
+            })
             .await;
-
-            match result {
-                Ok(()) => RpcResult::WriteComplete { path },
+            match formatted {
+                Ok(content) => RpcResult::FileFormatted { path, content },
                 Err(err) => RpcResult::Error {
                     message: err.to_string(),
                 },
@@ -46,19 +687,50 @@ pub async fn handle_rpc(request: RpcRequest) -> RpcResponse {
         }
     };
 
+    if let RpcResult::Error { message } = &result {
+        warn!(%message, "request failed");
+    }
+
     RpcResponse {
         request_id: request.request_id,
         result,
     }
 }
 
+fn require_service_control(context: &RpcContext) -> Result<(), RpcResult> {
+    if context.allow_service_control {
+        Ok(())
+    } else {
+        Err(RpcResult::Error {
+            message: "service control is disabled on this server (start it with --enable-service-control)"
+                .to_string(),
+        })
+    }
+}
+
+async fn service_operation(
+    context: &RpcContext,
+    name: String,
+    operation: ServiceOperation,
+) -> RpcResult {
+    if let Err(err) = require_service_control(context) {
+        return err;
+    }
+    match control_service(&name, operation).await {
+        Ok(()) => RpcResult::ServiceUpdated { name },
+        Err(err) => RpcResult::Error {
+            message: err.to_string(),
+        },
+    }
+}
+
 fn list_roots() -> anyhow::Result<Vec<String>> {
     #[cfg(target_os = "windows")]
     {
         let mut roots = Vec::new();
         for letter in b'A'..=b'Z' {
             let drive = format!("{}:\\", letter as char);
-            if PathBuf::from(&drive).exists() {
+            if Path::new(&drive).exists() {
                 roots.push(drive);
             }
         }
@@ -71,16 +743,80 @@ fn list_roots() -> anyhow::Result<Vec<String>> {
     }
 }
 
-async fn list_directory(path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
+/// Runs blocking work, such as a git operation, off the async workers.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    tokio::task::spawn_blocking(work).await?
+}
+
+enum WriteOutcome {
+    Written(String),
+    Conflict { content: String, hash: String },
+}
+
+async fn read_file(path: String, max_text_len: u64) -> anyhow::Result<FileData> {
+    Ok(tokio::task::spawn_blocking(move || read_file_data(Path::new(&path), max_text_len)).await??)
+}
+
+/// Writes `content` unless the file exists and no longer hashes to `expected_hash`,
+/// in which case the current content is handed back instead.
+async fn write_file(
+    path: &str,
+    content: String,
+    expected_hash: Option<String>,
+) -> anyhow::Result<WriteOutcome> {
+    if let Some(expected) = expected_hash
+        && let Ok(current) = fs::read(path).await
+    {
+        let hash = content_hash(&current);
+        if hash != expected {
+            return Ok(WriteOutcome::Conflict {
+                content: String::from_utf8_lossy(&current).into_owned(),
+                hash,
+            });
+        }
+    }
+    let current = fs::metadata(path).await.map_or(0, |metadata| metadata.len());
+    ensure_space(Path::new(path), (content.len() as u64).saturating_sub(current))?;
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let hash = content_hash(content.as_bytes());
+    fs::write(path, content).await?;
+    Ok(WriteOutcome::Written(hash))
+}
+
+async fn create_entry(path: &str, is_dir: bool) -> anyhow::Result<()> {
+    if is_dir {
+        fs::create_dir(path).await?;
+    } else {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn list_directory(path: &str, filter: &ListFilter) -> anyhow::Result<Vec<DirectoryEntry>> {
     let mut dir = fs::read_dir(path).await?;
     let mut entries = Vec::new();
     while let Some(entry) = dir.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if filter.hides(&name) {
+            continue;
+        }
         let entry_path = entry.path();
         let metadata = entry.metadata().await?;
         entries.push(DirectoryEntry {
-            name: entry.file_name().to_string_lossy().to_string(),
+            name,
             path: entry_path.to_string_lossy().to_string(),
             is_dir: metadata.is_dir(),
+            size: (!metadata.is_dir()).then_some(metadata.len()),
+            modified: modified_secs(&metadata),
+            is_symlink: entry.file_type().await?.is_symlink(),
         });
     }
     entries.sort_by(|a, b| {
@@ -92,20 +828,25 @@ async fn list_directory(path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
     Ok(entries)
 }
 
-async fn execute_command(command: String) -> String {
-    #[cfg(target_os = "windows")]
-    let output_result = tokio::process::Command::new("powershell")
-        .arg("-Command")
-        .arg(command)
-        .output()
-        .await;
+async fn execute_command(
+    command: String,
+    shell: Option<String>,
+    cwd: Option<String>,
+    env: Vec<EnvVar>,
+) -> (String, Option<i32>) {
+    let mut process = shell_command(shell.as_deref(), command);
+    if let Some(cwd) = cwd.filter(|cwd| !cwd.trim().is_empty()) {
+        process.current_dir(cwd);
+    }
+    for var in env {
+        process.env(var.name, var.value);
+    }
+    process.kill_on_drop(true);
+    collect_output(process).await
+}
 
-    #[cfg(not(target_os = "windows"))]
-    let output_result = tokio::process::Command::new("sh")
-        .arg("-lc")
-        .arg(command)
-        .output()
-        .await;
+async fn collect_output(mut process: tokio::process::Command) -> (String, Option<i32>) {
+    let output_result = process.output().await;
 
     match output_result {
         Ok(output) => {
@@ -116,12 +857,39 @@ async fn execute_command(command: String) -> String {
             if !output.stderr.is_empty() {
                 combined.push_str(&String::from_utf8_lossy(&output.stderr));
             }
-            if combined.is_empty() {
+            let combined = if combined.is_empty() {
                 "<no output>".to_string()
             } else {
                 combined
-            }
+            };
+            (combined, output.status.code())
         }
-        Err(err) => format!("command execution failed: {err}"),
+        Err(err) => (format!("command execution failed: {err}"), None),
     }
 }
+
+fn shell_command(shell: Option<&str>, command: String) -> tokio::process::Command {
+    #[cfg(target_os = "windows")]
+    let default_shell = "powershell";
+    #[cfg(not(target_os = "windows"))]
+    let default_shell = "sh";
+
+    let shell = shell
+        .map(str::trim)
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or(default_shell);
+    let program = Path::new(shell)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let flag = match program.as_str() {
+        "powershell" | "pwsh" => "-Command",
+        "cmd" => "/C",
+        _ if cfg!(target_os = "windows") => "-c",
+        _ => "-lc",
+    };
+
+    let mut process = tokio::process::Command::new(shell);
+    process.arg(flag).arg(command);
+    process
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Files that are never downloaded in full are deleted after this long.
+const SCRATCH_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Files the server writes to the temp directory for a client to fetch with
+/// `read_file_chunk`, such as diagnostics bundles and large output images. Each is
+/// deleted once a client has read all of it, or after [`SCRATCH_TTL`].
+#[derive(Clone, Default)]
+pub struct ScratchFiles {
+    files: Arc<Mutex<HashMap<PathBuf, PendingFile>>>,
+}
+
+struct PendingFile {
+    size: u64,
+    /// Sorted, non-overlapping `start..end` ranges read by clients.
+    served: Vec<(u64, u64)>,
+}
+
+impl PendingFile {
+    /// Records a read and reports whether every byte has now been served.
+    fn serve(&mut self, start: u64, end: u64) -> bool {
+        self.served.push((start, end));
+        self.served.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.served.len());
+        for &(start, end) in &self.served {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.served = merged;
+        matches!(self.served.as_slice(), [(0, end)] if *end >= self.size)
+    }
+}
+
+impl ScratchFiles {
+    /// A new path in the temp directory. The random part keeps other local users from
+    /// guessing the name ahead of time.
+    pub fn path(name: &str, extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rs-peer-{name}-{}.{extension}", Uuid::new_v4()))
+    }
+
+    /// Writes `data` to a new private scratch file and tracks it.
+    pub async fn write(&self, name: &str, extension: &str, data: Vec<u8>) -> io::Result<PathBuf> {
+        let path = Self::path(name, extension);
+        let target = path.clone();
+        tokio::task::spawn_blocking(move || create_private(&target)?.write_all(&data))
+            .await
+            .map_err(io::Error::other)??;
+        self.track(path.clone()).await?;
+        Ok(path)
+    }
+
+    /// Starts tracking a file written at `path` and returns its size.
+    pub async fn track(&self, path: PathBuf) -> io::Result<u64> {
+        let size = tokio::fs::metadata(&path).await?.len();
+        self.files.lock().await.insert(
+            path.clone(),
+            PendingFile {
+                size,
+                served: Vec::new(),
+            },
+        );
+        let files = self.files.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(SCRATCH_TTL).await;
+            if files.lock().await.remove(&path).is_some() {
+                debug!(path = %path.display(), "deleting scratch file that was not downloaded");
+                remove(&path).await;
+            }
+        });
+        Ok(size)
+    }
+
+    /// Notes that `length` bytes at `offset` of `path` were sent to a client, and
+    /// deletes the file when it is a scratch file that has now been read in full.
+    pub async fn served(&self, path: &str, offset: u64, length: u64) {
+        if length == 0 {
+            return;
+        }
+        let path = Path::new(path);
+        let mut files = self.files.lock().await;
+        let Some(file) = files.get_mut(path) else {
+            return;
+        };
+        if file.serve(offset, offset.saturating_add(length)) {
+            files.remove(path);
+            drop(files);
+            debug!(path = %path.display(), "deleting downloaded scratch file");
+            remove(path).await;
+        }
+    }
+}
+
+async fn remove(path: &Path) {
+    if let Err(err) = tokio::fs::remove_file(path).await
+        && err.kind() != io::ErrorKind::NotFound
+    {
+        warn!(path = %path.display(), %err, "failed to delete scratch file");
+    }
+}
+
+/// Creates a file, failing if the name already exists and, on Unix, readable only
+/// by the server's user: scratch files can hold output other local users must not see.
+pub fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
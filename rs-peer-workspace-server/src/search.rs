@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use regex::Regex;
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::index::index_tree;
+use rs_peer_workspace_shared::search::{build_regex, expand, search_tree};
+use uuid::Uuid;
+
+use crate::protocol::{ListFilter, ReplaceFile, ReplacedFile, SearchFileMatches, SearchQuery};
+
+/// Searches `root` on a blocking thread; see [`search_tree`].
+pub async fn search_files(
+    root: String,
+    query: SearchQuery,
+    replacement: Option<String>,
+) -> anyhow::Result<(Vec<SearchFileMatches>, bool)> {
+    tokio::task::spawn_blocking(move || search_tree(&root, &query, replacement.as_deref(), None))
+        .await?
+}
+
+/// Lists the files under `root` for quick open.
+pub async fn index_files(root: String, filter: ListFilter) -> anyhow::Result<(Vec<String>, bool)> {
+    tokio::task::spawn_blocking(move || Ok(index_tree(Path::new(&root), &filter)?)).await?
+}
+
+/// Applies the selected replacements to every file, or to none of them if any file
+/// changed since it was searched or cannot be written.
+pub async fn replace_in_files(
+    query: SearchQuery,
+    replacement: String,
+    files: Vec<ReplaceFile>,
+) -> anyhow::Result<Vec<ReplacedFile>> {
+    tokio::task::spawn_blocking(move || replace_blocking(&query, &replacement, files)).await?
+}
+
+struct Edit {
+    path: String,
+    original: String,
+    updated: String,
+    replacements: usize,
+}
+
+fn replace_blocking(
+    query: &SearchQuery,
+    replacement: &str,
+    files: Vec<ReplaceFile>,
+) -> anyhow::Result<Vec<ReplacedFile>> {
+    let regex = build_regex(query)?;
+    let mut edits = Vec::new();
+    for file in files {
+        let original = fs::read_to_string(&file.path)
+            .with_context(|| format!("failed to read {}", file.path))?;
+        if content_hash(original.as_bytes()) != file.hash {
+            anyhow::bail!("{} changed since it was searched", file.path);
+        }
+        let selected: HashSet<(usize, usize)> = file.matches.into_iter().collect();
+        let (updated, replacements) = apply(query, &regex, &original, replacement, &selected);
+        if replacements != selected.len() {
+            anyhow::bail!("{} does not contain every selected match", file.path);
+        }
+        if replacements > 0 {
+            edits.push(Edit {
+                path: file.path,
+                original,
+                updated,
+                replacements,
+            });
+        }
+    }
+    commit(&edits)?;
+    Ok(edits
+        .into_iter()
+        .map(|edit| ReplacedFile {
+            hash: content_hash(edit.updated.as_bytes()),
+            path: edit.path,
+            replacements: edit.replacements,
+        })
+        .collect())
+}
+
+fn apply(
+    query: &SearchQuery,
+    regex: &Regex,
+    content: &str,
+    replacement: &str,
+    selected: &HashSet<(usize, usize)>,
+) -> (String, usize) {
+    let mut updated = String::with_capacity(content.len());
+    let mut replacements = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let body = line.strip_suffix('\n').unwrap_or(line);
+        let body = body.strip_suffix('\r').unwrap_or(body);
+        let mut last = 0;
+        for captures in regex.captures_iter(body) {
+            let found = captures.get(0).expect("group 0 always matches");
+            if found.is_empty() || !selected.contains(&(index + 1, found.start())) {
+                continue;
+            }
+            updated.push_str(&body[last..found.start()]);
+            expand(query, &captures, replacement, &mut updated);
+            last = found.end();
+            replacements += 1;
+        }
+        updated.push_str(&line[last..]);
+    }
+    (updated, replacements)
+}
+
+/// Writes every edit to a temporary file beside its target, then renames them into
+/// place. A failure at any point removes the temporary files and restores the files
+/// already replaced.
+fn commit(edits: &[Edit]) -> anyhow::Result<()> {
+    let mut staged: Vec<PathBuf> = Vec::new();
+    for edit in edits {
+        let temp = temp_path(Path::new(&edit.path));
+        let written = fs::write(&temp, &edit.updated).and_then(|()| {
+            let permissions = fs::metadata(&edit.path)?.permissions();
+            fs::set_permissions(&temp, permissions)
+        });
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temp);
+            for temp in &staged {
+                let _ = fs::remove_file(temp);
+            }
+            return Err(err).with_context(|| format!("failed to write {}", edit.path));
+        }
+        staged.push(temp);
+    }
+
+    for (index, (edit, temp)) in edits.iter().zip(&staged).enumerate() {
+        if let Err(err) = fs::rename(temp, &edit.path) {
+            for done in &edits[..index] {
+                let _ = fs::write(&done.path, &done.original);
+            }
+            for temp in &staged[index..] {
+                let _ = fs::remove_file(temp);
+            }
+            return Err(err).with_context(|| format!("failed to replace {}", edit.path));
+        }
+    }
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.replace", Uuid::new_v4()))
+}
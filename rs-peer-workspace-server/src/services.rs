@@ -0,0 +1,134 @@
+use tokio::process::Command;
+
+use crate::protocol::ServiceEntry;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ServiceOperation {
+    Start,
+    Stop,
+    Restart,
+}
+
+pub async fn list_services() -> anyhow::Result<Vec<ServiceEntry>> {
+    #[cfg(target_os = "windows")]
+    {
+        let stdout = run(Command::new("powershell").arg("-Command").arg(
+            "Get-Service | ForEach-Object { \"$($_.Name)|$($_.Status)|$($_.DisplayName)\" }",
+        ))
+        .await?;
+        let mut services: Vec<ServiceEntry> = stdout
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '|');
+                Some(ServiceEntry {
+                    name: parts.next()?.trim().to_string(),
+                    status: parts.next()?.trim().to_string(),
+                    description: parts.next().unwrap_or_default().trim().to_string(),
+                })
+            })
+            .collect();
+        services.sort_by_key(|a| a.name.to_lowercase());
+        Ok(services)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let stdout = run(Command::new("systemctl").args([
+            "list-units",
+            "--type=service",
+            "--all",
+            "--no-legend",
+            "--no-pager",
+            "--plain",
+        ]))
+        .await?;
+        let mut services: Vec<ServiceEntry> = stdout
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let name = parts.next()?.to_string();
+                let _load = parts.next()?;
+                let active = parts.next()?;
+                let sub = parts.next()?;
+                Some(ServiceEntry {
+                    name,
+                    status: format!("{active} ({sub})"),
+                    description: parts.collect::<Vec<_>>().join(" "),
+                })
+            })
+            .collect();
+        services.sort_by_key(|a| a.name.to_lowercase());
+        Ok(services)
+    }
+}
+
+pub async fn control_service(name: &str, operation: ServiceOperation) -> anyhow::Result<()> {
+    validate_service_name(name)?;
+
+    #[cfg(target_os = "windows")]
+    {
+        let cmdlet = match operation {
+            ServiceOperation::Start => "Start-Service",
+            ServiceOperation::Stop => "Stop-Service",
+            ServiceOperation::Restart => "Restart-Service",
+        };
+        run(Command::new("powershell")
+            .arg("-Command")
+            .arg(format!("{cmdlet} -Name '{name}'")))
+        .await?;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let verb = match operation {
+            ServiceOperation::Start => "start",
+            ServiceOperation::Stop => "stop",
+            ServiceOperation::Restart => "restart",
+        };
+        run(Command::new("systemctl").args([verb, name])).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn service_logs(name: &str, lines: usize) -> anyhow::Result<String> {
+    validate_service_name(name)?;
+    let lines = lines.clamp(1, 5000).to_string();
+
+    #[cfg(target_os = "windows")]
+    let stdout = run(Command::new("powershell").arg("-Command").arg(format!(
+        "Get-WinEvent -LogName System -MaxEvents {lines} | Where-Object {{ $_.Message -like '*{name}*' }} | Format-List TimeCreated,LevelDisplayName,Message"
+    )))
+    .await?;
+
+    #[cfg(not(target_os = "windows"))]
+    let stdout = run(Command::new("journalctl").args(["-u", name, "-n", &lines, "--no-pager"])).await?;
+
+    Ok(stdout)
+}
+
+fn validate_service_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-' | '@' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        anyhow::bail!("invalid service name '{name}'")
+    }
+}
+
+async fn run(command: &mut Command) -> anyhow::Result<String> {
+    let output = command.output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        anyhow::bail!(if stderr.is_empty() {
+            format!("command exited with {}", output.status)
+        } else {
+            stderr
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
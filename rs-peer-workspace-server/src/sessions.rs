@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use webrtc::data_channel::RTCDataChannel;
+
+use crate::protocol::PeerToProxy;
+use crate::{send_json, stream_json};
+use rs_peer_workspace_shared::app::{AppEnvelope, AppPayload, BroadcastMessage, Capabilities, RpcResponse};
+use rs_peer_workspace_shared::compression::{self, Codec};
+use rs_peer_workspace_shared::e2e::{self, KeyPair, SessionCipher};
+use rs_peer_workspace_shared::identity::{IdentityChallenge, ServerKey};
+use rs_peer_workspace_shared::outbox::{Outbox, Priority};
+use rs_peer_workspace_shared::relay::AuthRole;
+
+#[derive(Default)]
+struct SessionEntry {
+    capabilities: Capabilities,
+    /// Keys agreed with a client that offered end-to-end encryption.
+    cipher: Option<SessionCipher>,
+    /// Set once the identity proof, which signs the agreed keys, has gone out. Relayed
+    /// payloads are sealed in both directions from then on.
+    sealed: bool,
+}
+
+impl SessionEntry {
+    /// Serializes `envelope`, compressed when `compressible` and the client can decode it.
+    fn encode(&self, envelope: &AppEnvelope, compressible: bool) -> anyhow::Result<Vec<u8>> {
+        let payload = serde_json::to_vec(envelope)?;
+        Ok(match Codec::for_peer(&self.capabilities) {
+            Some(codec) if compressible => compression::compress(payload, codec),
+            _ => payload,
+        })
+    }
+
+    fn outgoing(&mut self, payload: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match &mut self.cipher {
+            Some(cipher) if self.sealed => cipher.seal(&payload),
+            _ => Ok(payload),
+        }
+    }
+}
+
+pub struct SessionRegistry {
+    ws_tx: Outbox<String>,
+    data_channels: Arc<Mutex<HashMap<Uuid, Arc<RTCDataChannel>>>>,
+    sessions: Mutex<HashMap<Uuid, SessionEntry>>,
+    /// `--require-e2e`: requests are only served on sealed sessions.
+    require_e2e: bool,
+}
+
+impl SessionRegistry {
+    pub fn new(
+        ws_tx: Outbox<String>,
+        data_channels: Arc<Mutex<HashMap<Uuid, Arc<RTCDataChannel>>>>,
+        require_e2e: bool,
+    ) -> Self {
+        Self {
+            ws_tx,
+            data_channels,
+            sessions: Mutex::new(HashMap::new()),
+            require_e2e,
+        }
+    }
+
+    pub async fn add(&self, session_id: Uuid) {
+        self.sessions
+            .lock()
+            .await
+            .insert(session_id, SessionEntry::default());
+    }
+
+    /// Records what the client supports and answers with what this server supports, so
+    /// the client knows how it may compress requests. When the client offered an
+    /// end-to-end key, agrees on session keys and answers with this side's key.
+    pub async fn set_capabilities(&self, session_id: Uuid, capabilities: Capabilities) -> anyhow::Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            return Ok(());
+        };
+        let mut supported = Capabilities::supported();
+        if let Some(client_key) = &capabilities.e2e_key {
+            let key_pair = KeyPair::generate()?;
+            supported.e2e_key = Some(key_pair.public_key());
+            entry.cipher = Some(key_pair.agree(client_key, session_id, &AuthRole::Server)?);
+        }
+        entry.capabilities = capabilities;
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::Capabilities(supported),
+        };
+        let payload = serde_json::to_vec(&envelope)?;
+        send_json(&self.ws_tx, &PeerToProxy::RelayData { session_id, payload, droppable: false })
+    }
+
+    /// Whether the client offered end-to-end encryption for the session.
+    pub async fn offers_e2e(&self, session_id: Uuid) -> bool {
+        self.sessions
+            .lock()
+            .await
+            .get(&session_id)
+            .is_some_and(|entry| entry.cipher.is_some())
+    }
+
+    /// Whether a request on the session must be refused because this server requires
+    /// end-to-end encryption and the session is not sealed yet. Without the check a
+    /// client that never sends an identity challenge, or a relay injecting requests
+    /// ahead of it, would be served in plain text.
+    pub async fn refuses_requests(&self, session_id: Uuid) -> bool {
+        self.require_e2e
+            && !self
+                .sessions
+                .lock()
+                .await
+                .get(&session_id)
+                .is_some_and(|entry| entry.sealed)
+    }
+
+    /// Unseals and decompresses a payload the client relayed. Once the session is
+    /// sealed, plain payloads are refused so the relay cannot inject requests.
+    pub async fn decode(&self, session_id: Uuid, payload: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            anyhow::bail!("session {session_id} has ended");
+        };
+        let payload = match &mut entry.cipher {
+            Some(cipher) if e2e::is_sealed(&payload) => cipher.open(&payload)?,
+            _ if entry.sealed => anyhow::bail!("unencrypted payload on an end-to-end encrypted session"),
+            _ => payload,
+        };
+        Ok(compression::decompress(payload)?)
+    }
+
+    pub async fn relay_response(&self, session_id: Uuid, response: RpcResponse) -> anyhow::Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            anyhow::bail!("session {session_id} has ended");
+        };
+        let compressible = response.result.compressible();
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::RpcResponse(response),
+        };
+        let payload = entry.encode(&envelope, compressible)?;
+        let payload = entry.outgoing(payload)?;
+        send_json(&self.ws_tx, &PeerToProxy::RelayData { session_id, payload, droppable: false })
+    }
+
+    /// Sends a further response to an earlier request, such as lines of a followed file.
+    pub async fn push_response(&self, session_id: Uuid, response: RpcResponse) -> anyhow::Result<()> {
+        self.push(session_id, response, Priority::Control).await
+    }
+
+    /// Like [`Self::push_response`] for periodic output such as tailed lines or monitor
+    /// samples; over the relay, older output is dropped when the proxy falls behind.
+    pub async fn push_output(&self, session_id: Uuid, response: RpcResponse) -> anyhow::Result<()> {
+        self.push(session_id, response, Priority::Stream).await
+    }
+
+    async fn push(&self, session_id: Uuid, response: RpcResponse, priority: Priority) -> anyhow::Result<()> {
+        let compressible = response.result.compressible();
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::RpcResponse(response),
+        };
+        self.send(session_id, &envelope, compressible, priority).await
+    }
+
+    /// Serializes a response for the session's P2P data channel, compressed the same
+    /// way as relayed responses.
+    pub async fn encode_response(&self, session_id: Uuid, response: RpcResponse) -> anyhow::Result<Vec<u8>> {
+        let compressible = response.result.compressible();
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::RpcResponse(response),
+        };
+        let sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get(&session_id) else {
+            anyhow::bail!("session {session_id} has ended");
+        };
+        entry.encode(&envelope, compressible)
+    }
+
+    /// Identity proofs always go over the relay, which is where the client waits for them.
+    /// The proof signs the session's end-to-end keys, and everything relayed after it is
+    /// sealed with them.
+    pub async fn relay_identity(
+        &self,
+        session_id: Uuid,
+        identity: &ServerKey,
+        challenge: &IdentityChallenge,
+    ) -> anyhow::Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            anyhow::bail!("session {session_id} has ended");
+        };
+        let exchange = entry.cipher.as_ref().map(|cipher| cipher.exchange());
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::Identity(identity.prove(session_id, challenge, exchange)),
+        };
+        let payload = serde_json::to_vec(&envelope)?;
+        send_json(&self.ws_tx, &PeerToProxy::RelayData { session_id, payload, droppable: false })?;
+        entry.sealed = entry.cipher.is_some();
+        Ok(())
+    }
+
+    pub async fn remove(&self, session_id: Uuid) {
+        self.sessions.lock().await.remove(&session_id);
+    }
+
+    pub async fn broadcast(&self, from: Uuid, message: String) -> anyhow::Result<usize> {
+        let envelope = AppEnvelope {
+            message_id: Uuid::new_v4(),
+            payload: AppPayload::Broadcast(BroadcastMessage {
+                from_session: from,
+                message,
+            }),
+        };
+        let sessions: Vec<Uuid> = self.sessions.lock().await.keys().copied().collect();
+        for session_id in &sessions {
+            self.send(*session_id, &envelope, false, Priority::Control).await?;
+        }
+        Ok(sessions.len())
+    }
+
+    async fn send(
+        &self,
+        session_id: Uuid,
+        envelope: &AppEnvelope,
+        compressible: bool,
+        priority: Priority,
+    ) -> anyhow::Result<()> {
+        let data_channel = self.data_channels.lock().await.get(&session_id).cloned();
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            anyhow::bail!("session {session_id} has ended");
+        };
+        let payload = entry.encode(envelope, compressible)?;
+        if let Some(dc) = data_channel {
+            drop(sessions);
+            dc.send(&Bytes::from(payload)).await?;
+            return Ok(());
+        }
+        let payload = entry.outgoing(payload)?;
+        let relayed = PeerToProxy::RelayData {
+            session_id,
+            payload,
+            droppable: priority == Priority::Stream,
+        };
+        match priority {
+            Priority::Control => send_json(&self.ws_tx, &relayed),
+            Priority::Stream => stream_json(&self.ws_tx, &relayed),
+        }
+    }
+}
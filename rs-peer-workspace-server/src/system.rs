@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rs_peer_workspace_shared::app::{DiskInfo, SystemInfo};
+use sysinfo::{Disks, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::protocol::{RpcResponse, RpcResult};
+use crate::sessions::SessionRegistry;
+
+/// Shortest interval a monitor may ask for.
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+struct Monitor {
+    session_id: Uuid,
+    task: AbortHandle,
+}
+
+/// Live system information subscriptions, keyed by the request id of their
+/// `system_info` call.
+#[derive(Default)]
+pub struct SystemMonitors {
+    monitors: Mutex<HashMap<Uuid, Monitor>>,
+}
+
+impl SystemMonitors {
+    /// Takes a snapshot. With `interval`, a task then pushes a fresh snapshot to the
+    /// session under `monitor_id` every interval.
+    pub async fn start(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        monitor_id: Uuid,
+        interval: Option<Duration>,
+    ) -> anyhow::Result<SystemInfo> {
+        let (probe, info) = tokio::task::spawn_blocking(|| {
+            let mut probe = Probe::new();
+            std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            let info = probe.snapshot();
+            (probe, info)
+        })
+        .await?;
+
+        if let Some(interval) = interval {
+            let task = tokio::spawn(monitor(sessions, session_id, monitor_id, probe, interval.max(MIN_INTERVAL)));
+            self.monitors.lock().await.insert(
+                monitor_id,
+                Monitor {
+                    session_id,
+                    task: task.abort_handle(),
+                },
+            );
+        }
+        Ok(info)
+    }
+
+    pub async fn stop(&self, session_id: Uuid, monitor_id: Uuid) -> anyhow::Result<()> {
+        let mut monitors = self.monitors.lock().await;
+        match monitors.get(&monitor_id) {
+            Some(monitor) if monitor.session_id == session_id => {
+                monitor.task.abort();
+                monitors.remove(&monitor_id);
+                Ok(())
+            }
+            _ => anyhow::bail!("no such system monitor"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.monitors.lock().await.retain(|_, monitor| {
+            if monitor.session_id == session_id {
+                monitor.task.abort();
+            }
+            monitor.session_id != session_id
+        });
+    }
+}
+
+async fn monitor(
+    sessions: Arc<SessionRegistry>,
+    session_id: Uuid,
+    monitor_id: Uuid,
+    mut probe: Probe,
+    interval: Duration,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.tick().await;
+    loop {
+        ticks.tick().await;
+        let Ok((returned, info)) = tokio::task::spawn_blocking(move || {
+            let info = probe.snapshot();
+            (probe, info)
+        })
+        .await
+        else {
+            return;
+        };
+        probe = returned;
+        let response = RpcResponse {
+            request_id: monitor_id,
+            result: RpcResult::SystemInfo { info: Box::new(info) },
+        };
+        if sessions.push_output(session_id, response).await.is_err() {
+            debug!(%monitor_id, "system monitor session gone");
+            return;
+        }
+    }
+}
+
+/// Keeps the previous CPU sample, which usage is measured against.
+struct Probe {
+    system: System,
+    disks: Disks,
+}
+
+impl Probe {
+    fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        Self {
+            system,
+            disks: Disks::new_with_refreshed_list(),
+        }
+    }
+
+    fn snapshot(&mut self) -> SystemInfo {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        self.disks.refresh(true);
+        let load = System::load_average();
+        SystemInfo {
+            host_name: System::host_name().unwrap_or_default(),
+            os: System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+            kernel: System::kernel_version().unwrap_or_default(),
+            arch: System::cpu_arch(),
+            uptime_secs: System::uptime(),
+            cpu_count: self.system.cpus().len(),
+            cpu_usage: self.system.global_cpu_usage(),
+            load_average: [load.one, load.five, load.fifteen],
+            memory_total: self.system.total_memory(),
+            memory_used: self.system.used_memory(),
+            swap_total: self.system.total_swap(),
+            swap_used: self.system.used_swap(),
+            disks: self
+                .disks
+                .list()
+                .iter()
+                .map(|disk| DiskInfo {
+                    mount_point: disk.mount_point().to_string_lossy().into_owned(),
+                    file_system: disk.file_system().to_string_lossy().into_owned(),
+                    total: disk.total_space(),
+                    available: disk.available_space(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::protocol::{RpcResponse, RpcResult};
+use crate::sessions::SessionRegistry;
+use crate::transfers::read_chunk;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Lines sent when tailing starts.
+const INITIAL_LINES: usize = 200;
+/// Bytes read from the end of the file to find the initial lines.
+const INITIAL_BYTES: u64 = 64 * 1024;
+/// Appended bytes read per poll, so a burst is sent in several messages.
+const MAX_POLL_BYTES: u64 = 1024 * 1024;
+
+struct Tail {
+    session_id: Uuid,
+    task: AbortHandle,
+}
+
+/// Followed files, keyed by the request id of their `tail_file` call.
+#[derive(Default)]
+pub struct TailRegistry {
+    tails: Mutex<HashMap<Uuid, Tail>>,
+}
+
+impl TailRegistry {
+    /// Reads the last lines of `path`. With `follow`, a task then polls the file and
+    /// pushes appended lines to the session under `tail_id`.
+    pub async fn start(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        tail_id: Uuid,
+        path: String,
+        follow: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        let (_, size) = read_chunk(&path, 0, 0).await?;
+        let start = size.saturating_sub(INITIAL_BYTES);
+        let (data, _) = read_chunk(&path, start, size - start).await?;
+        let mut data = data.as_slice();
+        if start > 0 {
+            // Skip the partial line the read started in.
+            let first = data.iter().position(|byte| *byte == b'\n').map_or(data.len(), |idx| idx + 1);
+            data = &data[first..];
+        }
+        let complete = data.iter().rposition(|byte| *byte == b'\n').map_or(0, |idx| idx + 1);
+        let (lines_data, rest) = if follow { data.split_at(complete) } else { (data, &[][..]) };
+        let mut lines = split_lines(lines_data);
+        lines.drain(..lines.len().saturating_sub(INITIAL_LINES));
+
+        if follow {
+            let offset = size - rest.len() as u64;
+            let task = tokio::spawn(follow_file(sessions, session_id, tail_id, path, offset));
+            self.tails.lock().await.insert(
+                tail_id,
+                Tail {
+                    session_id,
+                    task: task.abort_handle(),
+                },
+            );
+        }
+        Ok(lines)
+    }
+
+    pub async fn stop(&self, session_id: Uuid, tail_id: Uuid) -> anyhow::Result<()> {
+        let mut tails = self.tails.lock().await;
+        match tails.get(&tail_id) {
+            Some(tail) if tail.session_id == session_id => {
+                tail.task.abort();
+                tails.remove(&tail_id);
+                Ok(())
+            }
+            _ => anyhow::bail!("no such tail"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.tails.lock().await.retain(|_, tail| {
+            if tail.session_id == session_id {
+                tail.task.abort();
+            }
+            tail.session_id != session_id
+        });
+    }
+}
+
+async fn follow_file(
+    sessions: Arc<SessionRegistry>,
+    session_id: Uuid,
+    tail_id: Uuid,
+    path: String,
+    mut offset: u64,
+) {
+    let mut partial = Vec::new();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        // A rotated file may be missing for a moment; keep polling until it returns.
+        let Ok((_, size)) = read_chunk(&path, 0, 0).await else {
+            continue;
+        };
+        let rotated = size < offset;
+        if rotated {
+            offset = 0;
+            partial.clear();
+        }
+        if size == offset && !rotated {
+            continue;
+        }
+        let Ok((data, _)) = read_chunk(&path, offset, MAX_POLL_BYTES).await else {
+            continue;
+        };
+        offset += data.len() as u64;
+        partial.extend_from_slice(&data);
+        let complete = partial.iter().rposition(|byte| *byte == b'\n').map_or(0, |idx| idx + 1);
+        let lines = split_lines(&partial[..complete]);
+        partial.drain(..complete);
+        if lines.is_empty() && !rotated {
+            continue;
+        }
+        let response = RpcResponse {
+            request_id: tail_id,
+            result: RpcResult::TailLines {
+                path: path.clone(),
+                lines,
+                rotated,
+            },
+        };
+        if sessions.push_output(session_id, response).await.is_err() {
+            debug!(%tail_id, "tail session gone");
+            return;
+        }
+    }
+}
+
+fn split_lines(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
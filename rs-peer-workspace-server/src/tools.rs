@@ -0,0 +1,76 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use crate::protocol::ToolInfo;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const TOOLS: &[(&str, &[&str])] = &[
+    ("python", &["python3", "python"]),
+    ("node", &["node"]),
+    ("cargo", &["cargo"]),
+    ("git", &["git"]),
+    ("docker", &["docker"]),
+    ("runmat", &["runmat"]),
+];
+
+pub async fn detect_tools() -> Vec<ToolInfo> {
+    let mut probes = JoinSet::new();
+    for (index, (name, programs)) in TOOLS.iter().enumerate() {
+        probes.spawn(async move {
+            let mut version = None;
+            for program in *programs {
+                version = probe_version(program).await;
+                if version.is_some() {
+                    break;
+                }
+            }
+            (
+                index,
+                ToolInfo {
+                    name: name.to_string(),
+                    version,
+                },
+            )
+        });
+    }
+
+    let mut tools = Vec::with_capacity(TOOLS.len());
+    while let Some(result) = probes.join_next().await {
+        if let Ok(tool) = result {
+            tools.push(tool);
+        }
+    }
+    tools.sort_by_key(|(index, _)| *index);
+    tools.into_iter().map(|(_, tool)| tool).collect()
+}
+
+async fn probe_version(program: &str) -> Option<String> {
+    let output = timeout(
+        PROBE_TIMEOUT,
+        Command::new(program)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
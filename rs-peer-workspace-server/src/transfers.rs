@@ -0,0 +1,72 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+
+use rs_peer_workspace_shared::app::ChunkRange;
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::stat::ensure_space;
+use rs_peer_workspace_shared::transfer::range_checksums;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+const MAX_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_CHECKSUM_RANGES: usize = 64;
+
+pub async fn read_chunk(path: &str, offset: u64, length: u64) -> anyhow::Result<(Vec<u8>, u64)> {
+    let mut file = fs::File::open(path).await?;
+    let total_size = file.metadata().await?.len();
+    let length = length.min(MAX_CHUNK_SIZE).min(total_size.saturating_sub(offset));
+    let mut data = vec![0; length as usize];
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.read_exact(&mut data).await?;
+    Ok((data, total_size))
+}
+
+pub async fn write_chunk(
+    path: &str,
+    offset: u64,
+    data: &[u8],
+    truncate: bool,
+    total_size: Option<u64>,
+    checksum: Option<&str>,
+) -> anyhow::Result<()> {
+    if data.len() as u64 > MAX_CHUNK_SIZE {
+        anyhow::bail!("chunk exceeds {MAX_CHUNK_SIZE} bytes");
+    }
+    if let Some(checksum) = checksum
+        && content_hash(data) != checksum
+    {
+        anyhow::bail!("chunk at offset {offset} does not match its checksum");
+    }
+    let Some(end) = offset.checked_add(data.len() as u64) else {
+        anyhow::bail!("chunk at offset {offset} runs past the largest possible file size");
+    };
+    if let Some(total_size) = total_size
+        && end > total_size
+    {
+        anyhow::bail!("chunk at offset {offset} runs past the file size of {total_size} bytes");
+    }
+    let current = fs::metadata(path).await.map_or(0, |metadata| metadata.len());
+    let target = if truncate { total_size.unwrap_or(end).max(end) } else { end };
+    ensure_space(Path::new(path), target.saturating_sub(current))?;
+    if truncate && let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(truncate)
+        .open(path)
+        .await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.write_all(data).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+pub async fn checksum_chunks(path: &str, chunks: Vec<ChunkRange>) -> anyhow::Result<(Vec<Option<String>>, u64)> {
+    if chunks.len() > MAX_CHECKSUM_RANGES {
+        anyhow::bail!("at most {MAX_CHECKSUM_RANGES} chunks can be checksummed at once");
+    }
+    let path = PathBuf::from(path);
+    Ok(tokio::task::spawn_blocking(move || range_checksums(&path, &chunks)).await??)
+}
@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use bytes::Bytes;
-use tokio::sync::{mpsc, Mutex};
+use rs_peer_workspace_shared::outbox::Outbox;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
 use uuid::Uuid;
 use webrtc::api::media_engine::MediaEngine;
 use webrtc::api::APIBuilder;
@@ -13,16 +15,18 @@ use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
 use webrtc::peer_connection::RTCPeerConnection;
 
-use crate::protocol::{PeerToProxy, SignalPayload, TurnCredentials};
+use crate::protocol::{Freshness, PeerToProxy, SignalPayload, TurnCredentials};
 use crate::send_json;
-use crate::rpc::handle_rpc;
+use crate::rpc::{handle_rpc, RpcContext};
 use rs_peer_workspace_shared::app::{AppEnvelope, AppPayload};
+use rs_peer_workspace_shared::compression;
 
 pub async fn handle_client_signal(
     session_id: Uuid,
     signal: SignalPayload,
     turn: Option<TurnCredentials>,
-    ws_tx: mpsc::UnboundedSender<String>,
+    ws_tx: Outbox<String>,
+    rpc_context: Arc<RpcContext>,
     peer_connections: Arc<Mutex<HashMap<Uuid, Arc<RTCPeerConnection>>>>,
     data_channels: Arc<Mutex<HashMap<Uuid, Arc<RTCDataChannel>>>>,
 ) -> anyhow::Result<()> {
@@ -30,7 +34,7 @@ pub async fn handle_client_signal(
     let pc = if let Some(existing) = existing {
         existing
     } else {
-        let created = create_peer_connection(session_id, turn, ws_tx.clone(), data_channels.clone()).await?;
+        let created = create_peer_connection(session_id, turn, ws_tx.clone(), rpc_context, data_channels.clone()).await?;
         peer_connections.lock().await.insert(session_id, created.clone());
         created
     };
@@ -45,6 +49,7 @@ pub async fn handle_client_signal(
                 send_json(&ws_tx, &PeerToProxy::Signal {
                     session_id,
                     signal: SignalPayload::SdpAnswer { sdp: local.sdp },
+                    freshness: Freshness::new(),
                 })?;
             }
         }
@@ -61,7 +66,8 @@ pub async fn handle_client_signal(
 async fn create_peer_connection(
     session_id: Uuid,
     turn: Option<TurnCredentials>,
-    ws_tx: mpsc::UnboundedSender<String>,
+    ws_tx: Outbox<String>,
+    rpc_context: Arc<RpcContext>,
     data_channels: Arc<Mutex<HashMap<Uuid, Arc<RTCDataChannel>>>>,
 ) -> anyhow::Result<Arc<RTCPeerConnection>> {
     let mut media_engine = MediaEngine::default();
@@ -82,17 +88,18 @@ async fn create_peer_connection(
     pc.on_ice_candidate(Box::new(move |candidate| {
         let ws_tx_inner = ws_tx_ice.clone();
         Box::pin(async move {
-            if let Some(candidate) = candidate {
-                if let Ok(json) = candidate.to_json() {
-                    let _ = send_json(&ws_tx_inner, &PeerToProxy::Signal {
-                        session_id,
-                        signal: SignalPayload::IceCandidate {
-                            candidate: json.candidate,
-                            sdp_mid: json.sdp_mid,
-                            sdp_mline_index: json.sdp_mline_index,
-                        },
-                    });
-                }
+            if let Some(candidate) = candidate
+                && let Ok(json) = candidate.to_json()
+            {
+                let _ = send_json(&ws_tx_inner, &PeerToProxy::Signal {
+                    session_id,
+                    signal: SignalPayload::IceCandidate {
+                        candidate: json.candidate,
+                        sdp_mid: json.sdp_mid,
+                        sdp_mline_index: json.sdp_mline_index,
+                    },
+                    freshness: Freshness::new(),
+                });
             }
         })
     }));
@@ -100,22 +107,31 @@ async fn create_peer_connection(
     let channels = data_channels.clone();
     pc.on_data_channel(Box::new(move |dc| {
         let channels = channels.clone();
+        let rpc_context = rpc_context.clone();
         Box::pin(async move {
+            info!(%session_id, label = %dc.label(), "P2P data channel opened");
             channels.lock().await.insert(session_id, dc.clone());
             let dc_for_messages = dc.clone();
             dc.on_message(Box::new(move |msg| {
                 let dc_sender = dc_for_messages.clone();
+                let rpc_context = rpc_context.clone();
                 Box::pin(async move {
-                    let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&msg.data) else { return; };
+                    let Ok(data) = compression::decompress(msg.data.to_vec()) else { return; };
+                    let Ok(envelope) = serde_json::from_slice::<AppEnvelope>(&data) else { return; };
                     if let AppPayload::RpcRequest(request) = envelope.payload {
-                        let response = handle_rpc(request).await;
-                        let out = AppEnvelope {
-                            message_id: Uuid::new_v4(),
-                            payload: AppPayload::RpcResponse(response),
-                        };
-                        if let Ok(bytes) = serde_json::to_vec(&out) {
-                            let _ = dc_sender.send(&Bytes::from(bytes)).await;
+                        if rpc_context.sessions.refuses_requests(session_id).await {
+                            warn!(%session_id, "dropped request on a session that is not end-to-end encrypted");
+                            return;
                         }
+                        tokio::spawn(async move {
+                            let response = handle_rpc(&rpc_context, session_id, request).await;
+                            match rpc_context.sessions.encode_response(session_id, response).await {
+                                Ok(bytes) => {
+                                    let _ = dc_sender.send(&Bytes::from(bytes)).await;
+                                }
+                                Err(err) => warn!(%session_id, error = %err, "failed to encode response"),
+                            }
+                        });
                     }
                 })
             }));
@@ -0,0 +1,130 @@
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+/// Directory that deleted paths are moved into, so a client can restore them.
+pub struct Trash {
+    dir: PathBuf,
+}
+
+impl Trash {
+    pub fn new(dir: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            dir: std::path::absolute(dir)?,
+        })
+    }
+
+    /// Moves `path` into the trash, returning its trash location and, for files, the
+    /// content hash it had.
+    pub async fn delete(&self, path: &str) -> anyhow::Result<(String, Option<String>)> {
+        let source = Path::new(path);
+        let hash = file_hash(source).await?;
+        let name = source
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("cannot delete {path}"))?;
+        fs::create_dir_all(&self.dir).await?;
+        let target = self
+            .dir
+            .join(format!("{}-{}", Uuid::new_v4(), name.to_string_lossy()));
+        move_path(source, &target).await?;
+        Ok((target.to_string_lossy().to_string(), hash))
+    }
+
+    pub async fn restore(
+        &self,
+        trash_path: &str,
+        path: &str,
+        expected_hash: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let source = Path::new(trash_path);
+        let in_trash = source.parent() == Some(self.dir.as_path())
+            && matches!(source.components().next_back(), Some(Component::Normal(_)));
+        if !in_trash {
+            anyhow::bail!("{trash_path} is not in the trash");
+        }
+        if let Some(expected) = expected_hash
+            && file_hash(source).await?.as_deref() != Some(expected)
+        {
+            anyhow::bail!("{trash_path} changed since it was deleted");
+        }
+        ensure_absent(path).await?;
+        move_path(source, Path::new(path)).await
+    }
+}
+
+/// Renames or moves `from` to `to`, refusing to overwrite and, when `expected_hash`
+/// is given, refusing if the file's content changed since that hash was taken.
+pub async fn rename_path(
+    from: &str,
+    to: &str,
+    expected_hash: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let hash = file_hash(Path::new(from)).await?;
+    if let Some(expected) = expected_hash
+        && hash.as_deref() != Some(expected)
+    {
+        anyhow::bail!("{from} changed since the operation was recorded");
+    }
+    ensure_absent(to).await?;
+    move_path(Path::new(from), Path::new(to)).await?;
+    Ok(hash)
+}
+
+async fn ensure_absent(path: &str) -> anyhow::Result<()> {
+    if fs::try_exists(path).await? {
+        anyhow::bail!("{path} already exists");
+    }
+    Ok(())
+}
+
+async fn file_hash(path: &Path) -> anyhow::Result<Option<String>> {
+    if fs::metadata(path).await?.is_dir() {
+        return Ok(None);
+    }
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(Some(format!("{:x}", hasher.finalize())))
+}
+
+async fn move_path(from: &Path, to: &Path) -> anyhow::Result<()> {
+    match fs::rename(from, to).await {
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            let (from, to) = (from.to_path_buf(), to.to_path_buf());
+            tokio::task::spawn_blocking(move || {
+                copy_recursive(&from, &to)?;
+                if from.is_dir() {
+                    std::fs::remove_dir_all(&from)
+                } else {
+                    std::fs::remove_file(&from)
+                }
+            })
+            .await??;
+            Ok(())
+        }
+        result => Ok(result?),
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
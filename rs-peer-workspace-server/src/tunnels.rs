@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rs_peer_workspace_shared::tunnel::{InOrder, CHUNK_SIZE};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::AbortHandle;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::protocol::{RpcResponse, RpcResult};
+use crate::sessions::SessionRegistry;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A chunk for a tunnel's socket, with the sender waiting until it is written.
+struct Chunk {
+    data: Vec<u8>,
+    written: oneshot::Sender<()>,
+}
+
+struct Tunnel {
+    session_id: Uuid,
+    chunks: mpsc::UnboundedSender<(u64, Chunk)>,
+    task: AbortHandle,
+}
+
+struct Listener {
+    session_id: Uuid,
+    task: AbortHandle,
+}
+
+type Tunnels = Arc<Mutex<HashMap<Uuid, Tunnel>>>;
+
+/// Port forwarding tunnels and listeners, keyed by the request id of the
+/// `open_tunnel` or `listen_tunnel` call, or for accepted connections by the id
+/// announced in `tunnel_accepted`.
+#[derive(Default)]
+pub struct TunnelRegistry {
+    tunnels: Tunnels,
+    listeners: Mutex<HashMap<Uuid, Listener>>,
+}
+
+impl TunnelRegistry {
+    /// Connects to `host:port` and starts relaying the socket to the session.
+    pub async fn open(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        tunnel_id: Uuid,
+        host: &str,
+        port: u16,
+    ) -> anyhow::Result<()> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port)))
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out connecting to {host}:{port}"))??;
+        info!(%tunnel_id, %host, port, "tunnel opened");
+        start_tunnel(&self.tunnels, sessions, session_id, tunnel_id, tunnel_id, stream).await;
+        Ok(())
+    }
+
+    /// Listens on loopback `port` and reports accepted connections under `listener_id`.
+    pub async fn listen(
+        &self,
+        sessions: Arc<SessionRegistry>,
+        session_id: Uuid,
+        listener_id: Uuid,
+        port: u16,
+    ) -> anyhow::Result<u16> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let port = listener.local_addr()?.port();
+        info!(%listener_id, port, "tunnel listener started");
+        let tunnels = self.tunnels.clone();
+        let task = tokio::spawn(async move {
+            let error = loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(err) => break err.to_string(),
+                };
+                let tunnel_id = Uuid::new_v4();
+                let accepted = RpcResponse {
+                    request_id: listener_id,
+                    result: RpcResult::TunnelAccepted {
+                        listener_id,
+                        tunnel_id,
+                    },
+                };
+                if sessions.push_response(session_id, accepted).await.is_err() {
+                    return;
+                }
+                start_tunnel(&tunnels, sessions.clone(), session_id, listener_id, tunnel_id, stream).await;
+            };
+            let closed = RpcResponse {
+                request_id: listener_id,
+                result: RpcResult::TunnelClosed {
+                    tunnel_id: listener_id,
+                    error: Some(error),
+                },
+            };
+            let _ = sessions.push_response(session_id, closed).await;
+        });
+        self.listeners.lock().await.insert(
+            listener_id,
+            Listener {
+                session_id,
+                task: task.abort_handle(),
+            },
+        );
+        Ok(port)
+    }
+
+    /// Queues `data` for the tunnel's socket and waits until it has been written.
+    pub async fn send(&self, session_id: Uuid, tunnel_id: Uuid, seq: u64, data: Vec<u8>) -> anyhow::Result<()> {
+        let (written, done) = oneshot::channel();
+        {
+            let tunnels = self.tunnels.lock().await;
+            let tunnel = tunnels
+                .get(&tunnel_id)
+                .filter(|tunnel| tunnel.session_id == session_id)
+                .ok_or_else(|| anyhow::anyhow!("no such tunnel"))?;
+            tunnel
+                .chunks
+                .send((seq, Chunk { data, written }))
+                .map_err(|_| anyhow::anyhow!("tunnel closed"))?;
+        }
+        done.await.map_err(|_| anyhow::anyhow!("tunnel closed"))
+    }
+
+    pub async fn close(&self, session_id: Uuid, tunnel_id: Uuid) -> anyhow::Result<()> {
+        let mut listeners = self.listeners.lock().await;
+        if listeners.get(&tunnel_id).is_some_and(|listener| listener.session_id == session_id) {
+            if let Some(listener) = listeners.remove(&tunnel_id) {
+                listener.task.abort();
+            }
+            return Ok(());
+        }
+        let mut tunnels = self.tunnels.lock().await;
+        match tunnels.get(&tunnel_id) {
+            Some(tunnel) if tunnel.session_id == session_id => {
+                tunnel.task.abort();
+                tunnels.remove(&tunnel_id);
+                Ok(())
+            }
+            _ => anyhow::bail!("no such tunnel"),
+        }
+    }
+
+    pub async fn stop_session(&self, session_id: Uuid) {
+        self.listeners.lock().await.retain(|_, listener| {
+            if listener.session_id == session_id {
+                listener.task.abort();
+            }
+            listener.session_id != session_id
+        });
+        self.tunnels.lock().await.retain(|_, tunnel| {
+            if tunnel.session_id == session_id {
+                tunnel.task.abort();
+            }
+            tunnel.session_id != session_id
+        });
+    }
+}
+
+/// Relays `stream` until both directions have ended or one fails, pushing what it
+/// reads under `request_id`, then reports the tunnel closed.
+async fn start_tunnel(
+    tunnels: &Tunnels,
+    sessions: Arc<SessionRegistry>,
+    session_id: Uuid,
+    request_id: Uuid,
+    tunnel_id: Uuid,
+    stream: TcpStream,
+) {
+    let (chunks, mut chunk_rx) = mpsc::unbounded_channel::<(u64, Chunk)>();
+    let registry = tunnels.clone();
+    let mut tunnels = tunnels.lock().await;
+    let task = tokio::spawn(async move {
+        let (mut reader, mut writer) = stream.into_split();
+        let upload = async {
+            let mut order = InOrder::default();
+            while let Some((seq, chunk)) = chunk_rx.recv().await {
+                for chunk in order.push(seq, chunk) {
+                    if chunk.data.is_empty() {
+                        writer.shutdown().await?;
+                        let _ = chunk.written.send(());
+                        return Ok(());
+                    }
+                    writer.write_all(&chunk.data).await?;
+                    let _ = chunk.written.send(());
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let download = async {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            for seq in 0.. {
+                let len = reader.read(&mut buf).await?;
+                let response = RpcResponse {
+                    request_id,
+                    result: RpcResult::TunnelData {
+                        tunnel_id,
+                        seq,
+                        data: buf[..len].to_vec(),
+                    },
+                };
+                sessions.push_response(session_id, response).await?;
+                if len == 0 {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let error = tokio::try_join!(upload, download).err().map(|err| err.to_string());
+        debug!(%tunnel_id, ?error, "tunnel closed");
+        registry.lock().await.remove(&tunnel_id);
+        let closed = RpcResponse {
+            request_id,
+            result: RpcResult::TunnelClosed { tunnel_id, error },
+        };
+        let _ = sessions.push_response(session_id, closed).await;
+    });
+    tunnels.insert(
+        tunnel_id,
+        Tunnel {
+            session_id,
+            chunks,
+            task: task.abort_handle(),
+        },
+    );
+}
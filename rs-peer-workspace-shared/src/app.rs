@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::format::Formatter;
+use crate::identity::{IdentityChallenge, ServerIdentity};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppEnvelope {
     pub message_id: Uuid,
@@ -12,6 +15,38 @@ pub struct AppEnvelope {
 pub enum AppPayload {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
+    Broadcast(BroadcastMessage),
+    Capabilities(Capabilities),
+    IdentityChallenge(IdentityChallenge),
+    Identity(ServerIdentity),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    #[serde(default)]
+    pub zstd: bool,
+    #[serde(default)]
+    pub deflate: bool,
+    /// Base64 X25519 public key offering end-to-end encryption of relayed payloads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e2e_key: Option<String>,
+}
+
+impl Capabilities {
+    /// Everything this build can decode, without an end-to-end key.
+    pub fn supported() -> Self {
+        Self {
+            zstd: true,
+            deflate: true,
+            e2e_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastMessage {
+    pub from_session: Uuid,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,11 +58,350 @@ pub struct RpcRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum RpcAction {
-    RunCommand { command: String },
+    RunCommand {
+        command: String,
+        #[serde(default)]
+        shell: Option<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        #[serde(default)]
+        env: Vec<EnvVar>,
+    },
+    /// Kills the process of a `run_command` call that is still running, named by its
+    /// request id. The call is then answered with an error.
+    CancelCommand { command_id: Uuid },
     ListRoots,
-    ListDirectory { path: String },
-    ReadFile { path: String },
-    WriteFile { path: String, content: String },
+    ListDirectory {
+        path: String,
+        #[serde(default, skip_serializing_if = "ListFilter::is_empty")]
+        filter: ListFilter,
+    },
+    ReadFile {
+        path: String,
+        /// Hash of a copy the client already has. When the file still matches it, the
+        /// server answers `RpcResult::NotModified` instead of sending the content.
+        #[serde(default)]
+        known_hash: Option<String>,
+    },
+    WriteFile {
+        path: String,
+        content: String,
+        /// Refuse the write with `RpcResult::Conflict` unless the file's current
+        /// hash matches; `None` overwrites unconditionally.
+        #[serde(default)]
+        expected_hash: Option<String>,
+    },
+    StatEntry { path: String },
+    /// Sets the permission bits of `path` and answers with its new [`RpcResult::Stat`].
+    /// Unix servers apply `mode` as is; elsewhere only the read-only flag follows it,
+    /// set when `mode` has no write bit.
+    SetPermissions { path: String, mode: u32 },
+    /// Creates an empty file, or a directory when `is_dir`; fails if the path exists.
+    CreateFile {
+        path: String,
+        #[serde(default)]
+        is_dir: bool,
+    },
+    /// Moves `path` into the server's trash instead of removing it.
+    DeletePath { path: String },
+    /// Renames or moves a path; with `expected_hash`, only if the file still has it.
+    RenamePath {
+        from: String,
+        to: String,
+        #[serde(default)]
+        expected_hash: Option<String>,
+    },
+    RestorePath {
+        trash_path: String,
+        path: String,
+        #[serde(default)]
+        expected_hash: Option<String>,
+    },
+    /// Searches the text files under `root`; with `replacement`, every match also
+    /// carries its line as it would read after replacing.
+    SearchFiles {
+        root: String,
+        query: SearchQuery,
+        #[serde(default)]
+        replacement: Option<String>,
+    },
+    /// Lists every file under `root` for quick open; see
+    /// [`index_tree`](crate::index::index_tree).
+    IndexTree {
+        root: String,
+        #[serde(default, skip_serializing_if = "ListFilter::is_empty")]
+        filter: ListFilter,
+    },
+    /// Replaces the chosen matches of an earlier search. Each file must still have
+    /// the hash it was searched with; either every file is written or none is.
+    ReplaceInFiles {
+        query: SearchQuery,
+        replacement: String,
+        files: Vec<ReplaceFile>,
+    },
+    ReadFileChunk { path: String, offset: u64, length: u64 },
+    /// Answers with the last lines of `path`. With `follow`, the server keeps sending
+    /// `tail_lines` results under the same request id as lines are appended, until
+    /// `stop_tail` names that request id or the session ends.
+    TailFile { path: String, follow: bool },
+    StopTail { tail_id: Uuid },
+    /// Writes `data` at `offset`. The first chunk of a transfer sets `truncate` and
+    /// may carry the file's final `total_size`, so a file that cannot fit is refused
+    /// before anything is written. A chunk whose `checksum` does not match `data` is
+    /// refused too.
+    WriteFileChunk {
+        path: String,
+        offset: u64,
+        data: Vec<u8>,
+        #[serde(default)]
+        truncate: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        total_size: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checksum: Option<String>,
+    },
+    /// Checksums byte ranges of `path`, so an interrupted transfer can confirm the
+    /// chunks it already moved before resuming after them.
+    ChecksumChunks { path: String, chunks: Vec<ChunkRange> },
+    ListServices,
+    StartService { name: String },
+    StopService { name: String },
+    RestartService { name: String },
+    ServiceLogs { name: String, lines: usize },
+    DetectTools,
+    /// Answers with a `system_info` snapshot. With `interval_ms`, the server keeps
+    /// sending snapshots under the same request id at that interval, until
+    /// `stop_system_info` names that request id or the session ends.
+    SystemInfo {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interval_ms: Option<u64>,
+    },
+    StopSystemInfo { monitor_id: Uuid },
+    ListProcesses,
+    /// Asks the process to terminate, or kills it outright with `force`.
+    KillProcess {
+        pid: u32,
+        #[serde(default)]
+        force: bool,
+    },
+    /// Answers with a `process_update` for `pid`, then keeps sending them under the
+    /// same request id at `interval_ms` until the process exits, `stop_watch_process`
+    /// names that request id or the session ends.
+    WatchProcess { pid: u32, interval_ms: u64 },
+    StopWatchProcess { watch_id: Uuid },
+    /// Bundles system information and logs into an archive on the server, answered
+    /// with `diagnostics_collected` naming the archive to download.
+    CollectDiagnostics,
+    ListContainers,
+    ExecInContainer {
+        container: String,
+        command: String,
+        #[serde(default)]
+        shell: Option<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        #[serde(default)]
+        env: Vec<EnvVar>,
+    },
+    ContainerLogs { container: String, lines: usize },
+    StartKernel {
+        #[serde(default)]
+        kernel_name: Option<String>,
+    },
+    ListKernels,
+    ExecuteCell { kernel_id: Uuid, code: String },
+    InterruptKernel { kernel_id: Uuid },
+    ShutdownKernel { kernel_id: Uuid },
+    Broadcast { message: String },
+    LockFile {
+        path: String,
+        owner: String,
+        #[serde(default)]
+        ttl_secs: Option<u64>,
+    },
+    UnlockFile { path: String },
+    FileLockStatus { path: String },
+    ListFileLocks,
+    /// Reports whether this session is read-only and until when it is elevated.
+    SessionAccess,
+    /// Grants a read-only session write and command rights for the server's
+    /// elevation window, if `credential` matches its elevation password.
+    Elevate { credential: String },
+    /// Ends this session's elevation before its window runs out.
+    EndElevation,
+    /// Connects from the server to `host:port`. Answered with `tunnel_opened`; data
+    /// read from the socket then follows as `tunnel_data` under the same request id,
+    /// which is also the tunnel id.
+    OpenTunnel { host: String, port: u16 },
+    /// Listens on the server's loopback `port` (0 picks one), answered with
+    /// `tunnel_listening`. Each accepted connection is reported as `tunnel_accepted`
+    /// and its data follows as `tunnel_data`, all under the same request id.
+    ListenTunnel { port: u16 },
+    /// Writes `data` to a tunnel's socket, in `seq` order; empty `data` ends the
+    /// stream. Answered with `tunnel_sent` once written.
+    TunnelSend {
+        tunnel_id: Uuid,
+        seq: u64,
+        data: Vec<u8>,
+    },
+    /// Closes a tunnel, or stops a listener started with `listen_tunnel`.
+    CloseTunnel { tunnel_id: Uuid },
+    /// Branch and changed files of the git repository containing `path`.
+    GitStatus { path: String },
+    /// Diff of the repository containing `path` against `HEAD`, limited to `file`,
+    /// relative to the repository root, when given.
+    GitDiff {
+        path: String,
+        #[serde(default)]
+        file: Option<String>,
+    },
+    /// Commits `files`, relative to the repository root, or every change when
+    /// empty.
+    GitCommit {
+        path: String,
+        message: String,
+        #[serde(default)]
+        files: Vec<String>,
+    },
+    /// Runs a language server in `root` and relays its stdio. Answered with
+    /// `language_server_started`; the server's messages then follow as
+    /// `lsp_message` under the same request id, which is also the server id, and
+    /// `language_server_stopped` once it exits.
+    StartLanguageServer {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        root: String,
+    },
+    /// Writes a JSON-RPC message to a language server's stdin, in `seq` order.
+    LspSend {
+        server_id: Uuid,
+        seq: u64,
+        message: String,
+    },
+    StopLanguageServer { server_id: Uuid },
+    /// Runs `formatter` on `content`, or on the file as saved when it is `None`,
+    /// and returns the result without writing it.
+    FormatFile {
+        path: String,
+        formatter: Formatter,
+        #[serde(default)]
+        content: Option<String>,
+    },
+}
+
+impl RpcAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RpcAction::RunCommand { .. } => "run_command",
+            RpcAction::CancelCommand { .. } => "cancel_command",
+            RpcAction::ListRoots => "list_roots",
+            RpcAction::ListDirectory { .. } => "list_directory",
+            RpcAction::ReadFile { .. } => "read_file",
+            RpcAction::WriteFile { .. } => "write_file",
+            RpcAction::StatEntry { .. } => "stat_entry",
+            RpcAction::SetPermissions { .. } => "set_permissions",
+            RpcAction::CreateFile { .. } => "create_file",
+            RpcAction::DeletePath { .. } => "delete_path",
+            RpcAction::RenamePath { .. } => "rename_path",
+            RpcAction::RestorePath { .. } => "restore_path",
+            RpcAction::SearchFiles { .. } => "search_files",
+            RpcAction::IndexTree { .. } => "index_tree",
+            RpcAction::ReplaceInFiles { .. } => "replace_in_files",
+            RpcAction::ReadFileChunk { .. } => "read_file_chunk",
+            RpcAction::TailFile { .. } => "tail_file",
+            RpcAction::StopTail { .. } => "stop_tail",
+            RpcAction::WriteFileChunk { .. } => "write_file_chunk",
+            RpcAction::ChecksumChunks { .. } => "checksum_chunks",
+            RpcAction::ListServices => "list_services",
+            RpcAction::StartService { .. } => "start_service",
+            RpcAction::StopService { .. } => "stop_service",
+            RpcAction::RestartService { .. } => "restart_service",
+            RpcAction::ServiceLogs { .. } => "service_logs",
+            RpcAction::DetectTools => "detect_tools",
+            RpcAction::SystemInfo { .. } => "system_info",
+            RpcAction::StopSystemInfo { .. } => "stop_system_info",
+            RpcAction::ListProcesses => "list_processes",
+            RpcAction::KillProcess { .. } => "kill_process",
+            RpcAction::WatchProcess { .. } => "watch_process",
+            RpcAction::StopWatchProcess { .. } => "stop_watch_process",
+            RpcAction::CollectDiagnostics => "collect_diagnostics",
+            RpcAction::ListContainers => "list_containers",
+            RpcAction::ExecInContainer { .. } => "exec_in_container",
+            RpcAction::ContainerLogs { .. } => "container_logs",
+            RpcAction::StartKernel { .. } => "start_kernel",
+            RpcAction::ListKernels => "list_kernels",
+            RpcAction::ExecuteCell { .. } => "execute_cell",
+            RpcAction::InterruptKernel { .. } => "interrupt_kernel",
+            RpcAction::ShutdownKernel { .. } => "shutdown_kernel",
+            RpcAction::Broadcast { .. } => "broadcast",
+            RpcAction::LockFile { .. } => "lock_file",
+            RpcAction::UnlockFile { .. } => "unlock_file",
+            RpcAction::FileLockStatus { .. } => "file_lock_status",
+            RpcAction::ListFileLocks => "list_file_locks",
+            RpcAction::SessionAccess => "session_access",
+            RpcAction::Elevate { .. } => "elevate",
+            RpcAction::EndElevation => "end_elevation",
+            RpcAction::OpenTunnel { .. } => "open_tunnel",
+            RpcAction::ListenTunnel { .. } => "listen_tunnel",
+            RpcAction::TunnelSend { .. } => "tunnel_send",
+            RpcAction::CloseTunnel { .. } => "close_tunnel",
+            RpcAction::GitStatus { .. } => "git_status",
+            RpcAction::GitDiff { .. } => "git_diff",
+            RpcAction::GitCommit { .. } => "git_commit",
+            RpcAction::StartLanguageServer { .. } => "start_language_server",
+            RpcAction::LspSend { .. } => "lsp_send",
+            RpcAction::StopLanguageServer { .. } => "stop_language_server",
+            RpcAction::FormatFile { .. } => "format_file",
+        }
+    }
+
+    /// Whether the action changes files, runs code or messages other sessions, which
+    /// read-only sessions may only do while elevated.
+    pub fn needs_write_access(&self) -> bool {
+        matches!(
+            self,
+            RpcAction::RunCommand { .. }
+                | RpcAction::WriteFile { .. }
+                | RpcAction::SetPermissions { .. }
+                | RpcAction::CreateFile { .. }
+                | RpcAction::DeletePath { .. }
+                | RpcAction::RenamePath { .. }
+                | RpcAction::RestorePath { .. }
+                | RpcAction::ReplaceInFiles { .. }
+                | RpcAction::WriteFileChunk { .. }
+                | RpcAction::StartService { .. }
+                | RpcAction::StopService { .. }
+                | RpcAction::RestartService { .. }
+                | RpcAction::ExecInContainer { .. }
+                | RpcAction::StartKernel { .. }
+                | RpcAction::ExecuteCell { .. }
+                | RpcAction::KillProcess { .. }
+                | RpcAction::OpenTunnel { .. }
+                | RpcAction::ListenTunnel { .. }
+                | RpcAction::GitCommit { .. }
+                | RpcAction::StartLanguageServer { .. }
+                | RpcAction::LspSend { .. }
+                | RpcAction::FormatFile { .. }
+                | RpcAction::Broadcast { .. }
+        )
+    }
+}
+
+impl RpcResult {
+    pub fn compressible(&self) -> bool {
+        matches!(
+            self,
+            RpcResult::FileContent { .. }
+                | RpcResult::BinaryContent { .. }
+                | RpcResult::FileChunk { .. }
+                | RpcResult::SearchResults { .. }
+                | RpcResult::TreeIndex { .. }
+                | RpcResult::Conflict { .. }
+                | RpcResult::GitDiff { .. }
+                | RpcResult::FileFormatted { .. }
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,17 +413,489 @@ pub struct RpcResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "result", rename_all = "snake_case")]
 pub enum RpcResult {
-    CommandOutput { output: String },
+    CommandOutput {
+        output: String,
+        #[serde(default)]
+        attachments: Vec<OutputAttachment>,
+        /// `None` when the command could not be started or was ended by a signal.
+        #[serde(default)]
+        exit_code: Option<i32>,
+    },
     Roots { roots: Vec<String> },
     DirectoryEntries { path: String, entries: Vec<DirectoryEntry> },
-    FileContent { path: String, content: String },
-    WriteComplete { path: String },
+    FileContent {
+        path: String,
+        content: String,
+        #[serde(default)]
+        hash: Option<String>,
+    },
+    /// Answer to `read_file` when the file still has the `known_hash` the client sent.
+    NotModified { path: String, hash: String },
+    /// Answer to `read_file` for a file that is binary or not UTF-8: its size and
+    /// first bytes.
+    BinaryContent { path: String, size: u64, preview: Vec<u8> },
+    /// The text file is larger than the server's read limit; read it in ranges with
+    /// `read_file_chunk` instead.
+    TooLarge { path: String, size: u64, limit: u64 },
+    WriteComplete {
+        path: String,
+        #[serde(default)]
+        hash: Option<String>,
+    },
+    Conflict { path: String, content: String, hash: String },
+    Stat { stat: Box<EntryStat> },
+    Created { path: String, is_dir: bool },
+    Deleted {
+        path: String,
+        trash_path: String,
+        hash: Option<String>,
+    },
+    Renamed {
+        from: String,
+        to: String,
+        hash: Option<String>,
+    },
+    Restored { path: String },
+    SearchResults {
+        files: Vec<SearchFileMatches>,
+        /// Set when the search stopped at the server's match limit.
+        truncated: bool,
+    },
+    Replaced { files: Vec<ReplacedFile> },
+    TreeIndex {
+        root: String,
+        /// File paths relative to `root`, with `/` separators.
+        paths: Vec<String>,
+        /// Set when the index stopped at the server's entry limit.
+        truncated: bool,
+    },
+    FileChunk {
+        path: String,
+        offset: u64,
+        data: Vec<u8>,
+        total_size: u64,
+        /// Hex SHA-256 of `data`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checksum: Option<String>,
+    },
+    ChunkWritten { path: String, offset: u64, length: u64 },
+    ChunkChecksums {
+        path: String,
+        total_size: u64,
+        /// One per requested range, `None` where the range runs past the end of the file.
+        checksums: Vec<Option<String>>,
+    },
+    Services { services: Vec<ServiceEntry> },
+    ServiceUpdated { name: String },
+    ServiceLogs { name: String, logs: String },
+    Tools { tools: Vec<ToolInfo> },
+    DiagnosticsCollected { path: String, size: u64 },
+    /// Complete lines of a tailed file. `rotated` is set when the file shrank or was
+    /// replaced since the last lines, which then start from its beginning.
+    TailLines {
+        path: String,
+        lines: Vec<String>,
+        #[serde(default)]
+        rotated: bool,
+    },
+    TailStopped { tail_id: Uuid },
+    Containers { containers: Vec<ContainerEntry> },
+    ContainerLogs { container: String, logs: String },
+    KernelStarted { kernel: KernelEntry },
+    Kernels { kernels: Vec<KernelEntry> },
+    CellExecuted { cell: CellResult },
+    KernelInterrupted { kernel_id: Uuid },
+    KernelStopped { kernel_id: Uuid },
+    BroadcastSent { recipients: usize },
+    FileLock { path: String, lock: Option<FileLock> },
+    FileLocks { locks: Vec<FileLock> },
+    Access { access: SessionAccess },
+    TunnelOpened { tunnel_id: Uuid },
+    TunnelListening { listener_id: Uuid, port: u16 },
+    TunnelAccepted { listener_id: Uuid, tunnel_id: Uuid },
+    /// Bytes read from a tunnel's socket, in `seq` order; empty `data` means the
+    /// socket will send no more.
+    TunnelData {
+        tunnel_id: Uuid,
+        seq: u64,
+        data: Vec<u8>,
+    },
+    TunnelSent { tunnel_id: Uuid, seq: u64 },
+    /// The tunnel or listener is gone, with the reason if it failed.
+    TunnelClosed {
+        tunnel_id: Uuid,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    CommandCancelled { command_id: Uuid },
+    SystemInfo { info: Box<SystemInfo> },
+    SystemInfoStopped { monitor_id: Uuid },
+    Processes { processes: Vec<ProcessEntry> },
+    ProcessKilled { pid: u32 },
+    /// The watched process as it is now, or `None` once it has exited.
+    ProcessUpdate {
+        pid: u32,
+        process: Option<ProcessEntry>,
+    },
+    ProcessWatchStopped { watch_id: Uuid },
+    /// `None` when `path` is not inside a git working tree.
+    GitStatus {
+        path: String,
+        status: Option<GitStatus>,
+    },
+    GitDiff { path: String, diff: String },
+    GitCommitted { path: String, commit: String },
+    LanguageServerStarted { server_id: Uuid },
+    /// A JSON-RPC message from a language server, in `seq` order.
+    LspMessage {
+        server_id: Uuid,
+        seq: u64,
+        message: String,
+    },
+    LspSent { server_id: Uuid, seq: u64 },
+    /// The language server exited or was stopped, with the reason if it failed.
+    LanguageServerStopped {
+        server_id: Uuid,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    FileFormatted { path: String, content: String },
     Error { message: String },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputAttachment {
+    Image { mime: String, data: Vec<u8> },
+    /// An image too large to send inline, left in a server scratch file for the client
+    /// to fetch with `read_file_chunk`. The file is deleted once it has been read.
+    ImageFile { mime: String, path: String, size: u64 },
+    Table { columns: Vec<String>, rows: Vec<Vec<String>> },
+    Json { value: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+    /// The value is masked wherever it is shown.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+}
+
+/// `base` with `overrides` applied on top; a variable set in both takes the value
+/// from `overrides`.
+pub fn merge_env(base: &[EnvVar], overrides: &[EnvVar]) -> Vec<EnvVar> {
+    let mut env: Vec<EnvVar> = base
+        .iter()
+        .filter(|var| !overrides.iter().any(|other| other.name == var.name))
+        .cloned()
+        .collect();
+    env.extend(overrides.iter().cloned());
+    env
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceEntry {
+    pub name: String,
+    pub description: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerEntry {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KernelEntry {
+    pub kernel_id: Uuid,
+    pub kernel_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellResult {
+    pub kernel_id: Uuid,
+    pub execution_count: Option<u32>,
+    pub output: String,
+    #[serde(default)]
+    pub attachments: Vec<OutputAttachment>,
+    pub error: Option<String>,
+}
+
+/// Advisory lock on a server path. Times are Unix seconds; `mine` is true when the
+/// requesting session holds the lock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLock {
+    pub path: String,
+    pub owner: String,
+    pub acquired_at: u64,
+    pub expires_at: u64,
+    #[serde(default)]
+    pub mine: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// A snapshot of the server's host. Sizes are in bytes and usage in percent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub host_name: String,
+    pub os: String,
+    pub kernel: String,
+    pub arch: String,
+    pub uptime_secs: u64,
+    pub cpu_count: usize,
+    pub cpu_usage: f32,
+    /// One, five and fifteen minute load averages; zero where the OS has none.
+    pub load_average: [f64; 3],
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+    pub disks: Vec<DiskInfo>,
+}
+
+impl SystemInfo {
+    pub fn memory_usage(&self) -> f32 {
+        usage(self.memory_used, self.memory_total)
+    }
+
+    pub fn swap_usage(&self) -> f32 {
+        usage(self.swap_used, self.swap_total)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    #[serde(default)]
+    pub parent: Option<u32>,
+    pub name: String,
+    /// Command line, empty when the server may not read it.
+    #[serde(default)]
+    pub command: String,
+    pub status: String,
+    /// Percent of one core, so busy multithreaded processes exceed 100.
+    pub cpu_usage: f32,
+    /// Resident memory in bytes.
+    pub memory: u64,
+    pub run_time_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileState {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    /// Relative to the repository root, `/`-separated.
+    pub path: String,
+    pub state: GitFileState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitStatus {
+    /// Working tree of the repository.
+    pub root: String,
+    /// `None` on a detached `HEAD`.
+    pub branch: Option<String>,
+    pub files: Vec<GitFileStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiskInfo {
+    pub mount_point: String,
+    pub file_system: String,
+    pub total: u64,
+    pub available: u64,
+}
+
+impl DiskInfo {
+    pub fn usage(&self) -> f32 {
+        usage(self.total.saturating_sub(self.available), self.total)
+    }
+}
+
+fn usage(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        (used as f64 / total as f64 * 100.0) as f32
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    /// File size in bytes; not reported for directories.
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Last modification time in Unix seconds.
+    #[serde(default)]
+    pub modified: Option<u64>,
+    #[serde(default)]
+    pub is_symlink: bool,
+}
+
+/// Metadata of a file system entry. When `path` is a symlink, `symlink_target` is
+/// set and the other fields describe what it points at, if that exists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryStat {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    #[serde(default)]
+    pub modified: Option<u64>,
+    /// `rwxr-xr-x` style on Unix, `read-only` or `read-write` elsewhere.
+    pub permissions: String,
+    #[serde(default)]
+    pub mode: Option<u32>,
+    #[serde(default)]
+    pub readonly: bool,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+    /// Space on the file system holding the entry, when the platform reports it.
+    #[serde(default)]
+    pub disk: Option<DiskSpace>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionAccess {
+    /// Set when the server only allows writes and commands while elevated.
+    pub read_only: bool,
+    /// Unix time the current elevation ends.
+    #[serde(default)]
+    pub elevated_until: Option<u64>,
+}
+
+impl SessionAccess {
+    pub fn writable(&self, now: u64) -> bool {
+        !self.read_only || self.elevated_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiskSpace {
+    pub total: u64,
+    /// Bytes the server's user may still write.
+    pub available: u64,
+}
+
+/// Entries a directory listing leaves out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ListFilter {
+    /// Leave out names starting with a dot.
+    #[serde(default)]
+    pub hide_hidden: bool,
+    /// Name patterns to leave out, such as `node_modules` or `*.pyc`; `*` matches any
+    /// run of characters and `?` a single one.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+impl ListFilter {
+    pub fn is_empty(&self) -> bool {
+        !self.hide_hidden && self.excludes.is_empty()
+    }
+
+    pub fn hides(&self, name: &str) -> bool {
+        (self.hide_hidden && name.starts_with('.'))
+            || self.excludes.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchFileMatches {
+    pub path: String,
+    pub hash: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// A match within one line. `line` is 1-based; `start` and `end` are byte offsets
+/// into `text`, the line without its line ending.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    #[serde(default)]
+    pub preview: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRange {
+    pub offset: u64,
+    pub length: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceFile {
+    pub path: String,
+    pub hash: String,
+    /// `(line, start)` of each match to replace, as reported by the search.
+    pub matches: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacedFile {
+    pub path: String,
+    pub replacements: usize,
+    pub hash: String,
 }
@@ -0,0 +1,154 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::content::content_hash;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    connection: String,
+    path: String,
+    hash: String,
+    content: String,
+}
+
+/// Remote file contents kept on disk, keyed by connection and path, so reopening an
+/// unchanged file only needs its hash checked by the server.
+pub struct ContentCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl ContentCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: u64) {
+        self.max_bytes = max_bytes;
+        if self.enabled() {
+            let _ = self.evict(None);
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.max_bytes > 0
+    }
+
+    /// The cached hash and content of `path`. Entries whose content no longer matches
+    /// their hash are removed.
+    pub fn get(&self, connection_name: &str, path: &str) -> Option<(String, String)> {
+        if !self.enabled() {
+            return None;
+        }
+        let file = self.entry_path(connection_name, path);
+        let entry: Entry = fs::read(&file)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .filter(|entry: &Entry| {
+                entry.connection == connection_name
+                    && entry.path == path
+                    && content_hash(entry.content.as_bytes()) == entry.hash
+            })
+            .or_else(|| {
+                let _ = fs::remove_file(&file);
+                None
+            })?;
+        Some((entry.hash, entry.content))
+    }
+
+    /// Stores `content` for `path`, then removes the least recently written entries
+    /// until the cache fits its size limit again.
+    pub fn put(&self, connection_name: &str, path: &str, hash: &str, content: &str) -> io::Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        let entry = Entry {
+            connection: connection_name.to_string(),
+            path: path.to_string(),
+            hash: hash.to_string(),
+            content: content.to_string(),
+        };
+        let file = self.entry_path(connection_name, path);
+        fs::write(&file, serde_json::to_vec(&entry).map_err(io::Error::other)?)?;
+        self.evict(Some(&file))
+    }
+
+    pub fn remove(&self, connection_name: &str, path: &str) {
+        let _ = fs::remove_file(self.entry_path(connection_name, path));
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Bytes currently used by cached entries.
+    pub fn size(&self) -> u64 {
+        self.entries().iter().map(|(_, size, _)| size).sum()
+    }
+
+    fn entry_path(&self, connection_name: &str, path: &str) -> PathBuf {
+        let mut key = Sha256::new();
+        key.update(connection_name.as_bytes());
+        key.update([0]);
+        key.update(path.as_bytes());
+        self.dir.join(format!("{:x}.json", key.finalize()))
+    }
+
+    fn entries(&self) -> Vec<(PathBuf, u64, std::time::SystemTime)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().ok()?;
+                metadata
+                    .is_file()
+                    .then(|| (entry.path(), metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    fn evict(&self, keep: Option<&Path>) -> io::Result<()> {
+        let mut entries = self.entries();
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (file, size, _) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if Some(file.as_path()) == keep {
+                continue;
+            }
+            fs::remove_file(&file)?;
+            total -= size;
+        }
+        Ok(())
+    }
+}
+
+/// Per-user directory for the cached file contents of `app`.
+pub fn default_dir(app: &str) -> PathBuf {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    };
+    base.unwrap_or_else(std::env::temp_dir).join(app).join("content")
+}
@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::app::Capabilities;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// Payloads shorter than this are sent as they are.
+pub const MIN_COMPRESS_LEN: usize = 1024;
+/// Largest payload a compressed message may expand to, so a peer cannot exhaust memory
+/// with a tiny message that decompresses to gigabytes.
+pub const MAX_DECOMPRESSED_LEN: u64 = 256 * 1024 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Zstd,
+    /// zlib-wrapped deflate, for peers without zstd.
+    Deflate,
+}
+
+impl Codec {
+    /// The codec to compress with for a peer that advertised `capabilities`, zstd first.
+    pub fn for_peer(capabilities: &Capabilities) -> Option<Self> {
+        if capabilities.zstd {
+            Some(Codec::Zstd)
+        } else if capabilities.deflate {
+            Some(Codec::Deflate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Compresses `payload` when it is at least [`MIN_COMPRESS_LEN`] long and gets smaller,
+/// otherwise returns it unchanged.
+pub fn compress(payload: Vec<u8>, codec: Codec) -> Vec<u8> {
+    if payload.len() < MIN_COMPRESS_LEN {
+        return payload;
+    }
+    let compressed = match codec {
+        Codec::Zstd => zstd::bulk::compress(&payload, ZSTD_LEVEL).ok(),
+        Codec::Deflate => deflate(&payload).ok(),
+    };
+    match compressed {
+        Some(compressed) if compressed.len() < payload.len() => compressed,
+        _ => payload,
+    }
+}
+
+/// Undoes [`compress`], telling the codecs apart by their headers. Anything else, such
+/// as plain JSON, is returned unchanged.
+pub fn decompress(payload: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if payload.starts_with(&ZSTD_MAGIC) {
+        read_limited(zstd::stream::read::Decoder::new(payload.as_slice())?)
+    } else if is_zlib(&payload) {
+        read_limited(ZlibDecoder::new(payload.as_slice()))
+    } else {
+        Ok(payload)
+    }
+}
+
+fn deflate(payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload)?;
+    encoder.finish()
+}
+
+/// A zlib stream starts with 0x78 (deflate, 32 KiB window) and a flags byte that makes
+/// the first two bytes a multiple of 31.
+fn is_zlib(payload: &[u8]) -> bool {
+    matches!(payload, [0x78, flags, ..] if ((0x78u16 << 8) | u16::from(*flags)).is_multiple_of(31))
+}
+
+fn read_limited(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    reader.take(MAX_DECOMPRESSED_LEN + 1).read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > MAX_DECOMPRESSED_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "compressed payload expands beyond the size limit",
+        ));
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+/// Leading bytes inspected when deciding whether a file is binary.
+pub const SNIFF_LEN: usize = 8 * 1024;
+/// Leading bytes of a binary file read for its hex preview.
+pub const BINARY_PREVIEW_LEN: usize = 64 * 1024;
+/// Default size above which a text file is not read whole, only in byte ranges.
+pub const MAX_TEXT_LEN: u64 = 16 * 1024 * 1024;
+
+/// A file read for display: its text, the start of it when it is binary, or only its
+/// size when it is text larger than the limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileData {
+    Text(String),
+    Binary { size: u64, preview: Vec<u8> },
+    TooLarge { size: u64 },
+}
+
+/// Hex SHA-256 of a file's content, as servers report it with reads and writes.
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Whether `bytes` look like binary data, judged by a NUL byte among the first
+/// [`SNIFF_LEN`] bytes.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// Reads `path` as text, or only its first [`BINARY_PREVIEW_LEN`] bytes when it
+/// looks binary or is not valid UTF-8. Text files over `max_text_len` bytes are not
+/// read.
+pub fn read_file_data(path: &Path, max_text_len: u64) -> io::Result<FileData> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut bytes = Vec::new();
+    file.by_ref().take(SNIFF_LEN as u64).read_to_end(&mut bytes)?;
+    if !looks_binary(&bytes) {
+        if size > max_text_len {
+            return Ok(FileData::TooLarge { size });
+        }
+        file.read_to_end(&mut bytes)?;
+        match String::from_utf8(bytes) {
+            Ok(text) => return Ok(FileData::Text(text)),
+            Err(err) => bytes = err.into_bytes(),
+        }
+    }
+    if bytes.len() < BINARY_PREVIEW_LEN {
+        file.take((BINARY_PREVIEW_LEN - bytes.len()) as u64)
+            .read_to_end(&mut bytes)?;
+    }
+    bytes.truncate(BINARY_PREVIEW_LEN);
+    Ok(FileData::Binary {
+        size,
+        preview: bytes,
+    })
+}
+
+/// Up to `length` bytes of `path` from `offset`, and the file's size.
+pub fn read_range(path: &Path, offset: u64, length: u64) -> io::Result<(Vec<u8>, u64)> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    file.seek(SeekFrom::Start(offset.min(size)))?;
+    let mut data = Vec::new();
+    file.take(length).read_to_end(&mut data)?;
+    Ok((data, size))
+}
+
+/// Classic hex dump of `bytes`: an offset, sixteen hex bytes and their printable
+/// ASCII characters per line.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        dump.push_str(&format!("{:08x} ", line * 16));
+        for idx in 0..16 {
+            if idx == 8 {
+                dump.push(' ');
+            }
+            match chunk.get(idx) {
+                Some(byte) => dump.push_str(&format!(" {byte:02x}")),
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(chunk.iter().map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
@@ -0,0 +1,170 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::relay::unix_now;
+
+const TAIL_LINES: usize = 200;
+const SEEN_DIR: &str = "seen";
+
+static LOG_TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Log writer that keeps the last lines of output for crash reports;
+/// [`telemetry::init`](crate::telemetry::init) tees every event into it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogTail;
+
+impl io::Write for LogTail {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let mut tail = LOG_TAIL.lock().unwrap_or_else(PoisonError::into_inner);
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            tail.push_back(strip_ansi(line));
+            if tail.len() > TAIL_LINES {
+                tail.pop_front();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogTail {
+    type Writer = LogTail;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogTail
+    }
+}
+
+/// The last log lines kept for crash reports, oldest first.
+pub fn recent_log_lines() -> Vec<String> {
+    LOG_TAIL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Installs a panic hook that writes a crash report into `dir` before the default
+/// hook prints the panic. Reports hold the panic message and location, a backtrace,
+/// the version and the last log lines.
+pub fn install(app: &'static str, version: &'static str, dir: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(app, version, &dir, info) {
+            Ok(path) => eprintln!("crash report written to {}", path.display()),
+            Err(err) => eprintln!("failed to write crash report: {err}"),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Per-user directory for the crash reports of `app`.
+pub fn default_dir(app: &str) -> PathBuf {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Logs"))
+    } else {
+        env_dir("XDG_STATE_HOME")
+            .or_else(|| env_dir("HOME").map(|home| home.join(".local").join("state")))
+    };
+    base.unwrap_or_else(std::env::temp_dir).join(app).join("crashes")
+}
+
+/// Reports in `dir` that have not been acknowledged yet, newest first.
+pub fn pending_reports(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    reports.sort();
+    reports.reverse();
+    reports
+}
+
+/// Moves `report` out of the pending set and returns its new path.
+pub fn acknowledge(report: &Path) -> io::Result<PathBuf> {
+    let seen = report
+        .parent()
+        .ok_or_else(|| io::Error::other("crash report has no parent directory"))?
+        .join(SEEN_DIR);
+    fs::create_dir_all(&seen)?;
+    let target = seen.join(report.file_name().unwrap_or_default());
+    fs::rename(report, &target)?;
+    Ok(target)
+}
+
+fn write_report(
+    app: &str,
+    version: &str,
+    dir: &Path,
+    info: &PanicHookInfo<'_>,
+) -> io::Result<PathBuf> {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string());
+    let thread = std::thread::current();
+    let now = unix_now();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "{app} {version} crashed");
+    let _ = writeln!(report, "time: {now} (Unix seconds, UTC)");
+    let _ = writeln!(report, "os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    let _ = writeln!(report, "thread: {}", thread.name().unwrap_or("unnamed"));
+    let _ = writeln!(report, "panic: {message}");
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "location: {location}");
+    }
+    let _ = writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "last log lines:");
+    for line in recent_log_lines() {
+        let _ = writeln!(report, "{line}");
+    }
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{app}-{now}-{}.txt", std::process::id()));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            for ch in chars.by_ref() {
+                if ch.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(ch);
+        }
+    }
+    plain
+}
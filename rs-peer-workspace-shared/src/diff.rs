@@ -0,0 +1,180 @@
+/// Line-level difference between a local and a remote text. Lines keep their
+/// terminators, so joining a side's lines reproduces that text exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOp {
+    Equal(Vec<String>),
+    Change { local: Vec<String>, remote: Vec<String> },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextDiff {
+    pub ops: Vec<DiffOp>,
+}
+
+impl TextDiff {
+    pub fn new(local: &str, remote: &str) -> Self {
+        let local = split_lines(local);
+        let remote = split_lines(remote);
+
+        let prefix = local
+            .iter()
+            .zip(&remote)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = local[prefix..]
+            .iter()
+            .rev()
+            .zip(remote[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut ops = Vec::new();
+        push_equal(&mut ops, &local[..prefix]);
+        let local_mid = &local[prefix..local.len() - suffix];
+        let remote_mid = &remote[prefix..remote.len() - suffix];
+        for (equal, a, b) in myers(local_mid, remote_mid) {
+            if equal {
+                push_equal(&mut ops, &local_mid[a]);
+            } else {
+                ops.push(DiffOp::Change {
+                    local: local_mid[a].to_vec(),
+                    remote: remote_mid[b].to_vec(),
+                });
+            }
+        }
+        push_equal(&mut ops, &local[local.len() - suffix..]);
+        Self { ops }
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.hunk_count() == 0
+    }
+
+    pub fn hunk_count(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| matches!(op, DiffOp::Change { .. }))
+            .count()
+    }
+
+    /// Rebuilds the text, taking the remote side of hunk `n` when `take_remote(n)`.
+    pub fn merge(&self, take_remote: impl Fn(usize) -> bool) -> String {
+        let mut merged = String::new();
+        let mut hunk = 0;
+        for op in &self.ops {
+            match op {
+                DiffOp::Equal(lines) => merged.extend(lines.iter().map(String::as_str)),
+                DiffOp::Change { local, remote } => {
+                    let lines = if take_remote(hunk) { remote } else { local };
+                    merged.extend(lines.iter().map(String::as_str));
+                    hunk += 1;
+                }
+            }
+        }
+        merged
+    }
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(str::to_string).collect()
+}
+
+fn push_equal(ops: &mut Vec<DiffOp>, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    if let Some(DiffOp::Equal(existing)) = ops.last_mut() {
+        existing.extend_from_slice(lines);
+    } else {
+        ops.push(DiffOp::Equal(lines.to_vec()));
+    }
+}
+
+type Segment = (bool, std::ops::Range<usize>, std::ops::Range<usize>);
+
+/// Myers' O((N+M)D) shortest edit script, returned as alternating equal and changed
+/// ranges of `a` and `b`.
+fn myers(a: &[String], b: &[String]) -> Vec<Segment> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    if n == 0 && m == 0 {
+        return Vec::new();
+    }
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk the trace backwards, recording one move per edit.
+    let mut moves = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            moves.push((true, x - 1, y - 1));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                moves.push((false, -1, prev_y));
+            } else {
+                moves.push((false, prev_x, -1));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    moves.reverse();
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let (mut ai, mut bi) = (0usize, 0usize);
+    for (equal, mx, my) in moves {
+        let (next_a, next_b) = match (equal, mx, my) {
+            (true, _, _) => (ai + 1, bi + 1),
+            (false, -1, _) => (ai, bi + 1),
+            _ => (ai + 1, bi),
+        };
+        match segments.last_mut() {
+            Some((kind, ra, rb)) if *kind == equal => {
+                ra.end = next_a;
+                rb.end = next_b;
+            }
+            _ => segments.push((equal, ai..next_a, bi..next_b)),
+        }
+        ai = next_a;
+        bi = next_b;
+    }
+    segments
+}
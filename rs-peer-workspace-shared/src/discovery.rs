@@ -0,0 +1,325 @@
+//! LAN discovery of proxies over multicast DNS (DNS-SD).
+//!
+//! A proxy started with `--announce` answers queries for [`SERVICE`] with a PTR,
+//! SRV, TXT and A record; the TXT record carries the WebSocket URL as `url=...`.
+//! Clients send a one-shot query from an ephemeral port, which mDNS responders
+//! answer by unicast, so scanning needs neither port 5353 nor a system daemon.
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// DNS-SD service type announced by proxies.
+pub const SERVICE: &str = "_rs-peer-ws._tcp.local";
+pub const MDNS_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(224, 0, 0, 251), 5353);
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+/// Question class bit asking for a unicast reply; record class bit that flushes caches.
+const CLASS_TOP_BIT: u16 = 0x8000;
+const RECORD_TTL: u32 = 120;
+/// Instance name used when the proxy's name does not make a DNS label.
+const DEFAULT_NAME: &str = "rs-peer-workspace-proxy";
+
+/// A proxy found on the local network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcement {
+    /// Instance name, the proxy's host name unless it was given one.
+    pub name: String,
+    /// WebSocket URL such as `ws://192.168.1.20:9000/ws`.
+    pub url: String,
+}
+
+/// Query for every instance of [`SERVICE`], asking for unicast replies.
+pub fn query() -> Vec<u8> {
+    let mut packet = header(0, 0, 1, 0);
+    write_name(&mut packet, SERVICE);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&(CLASS_IN | CLASS_TOP_BIT).to_be_bytes());
+    packet
+}
+
+/// A query asking for [`SERVICE`], as found by [`parse_query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceQuery {
+    pub id: u16,
+    /// The querier asked for a unicast reply.
+    pub unicast: bool,
+}
+
+/// Returns the query in `packet` if it asks for [`SERVICE`]; responses and other
+/// questions give `None`.
+pub fn parse_query(packet: &[u8]) -> Option<ServiceQuery> {
+    let (id, flags, questions, _) = read_header(packet)?;
+    if flags & 0x8000 != 0 {
+        return None;
+    }
+    let mut offset = 12;
+    let mut found = None;
+    for _ in 0..questions {
+        let name = read_name(packet, &mut offset)?;
+        let kind = read_u16(packet, &mut offset)?;
+        let class = read_u16(packet, &mut offset)?;
+        if name.eq_ignore_ascii_case(SERVICE) && matches!(kind, TYPE_PTR | TYPE_ANY) {
+            let unicast = class & CLASS_TOP_BIT != 0;
+            found = Some(ServiceQuery { id, unicast });
+        }
+    }
+    found
+}
+
+/// Response announcing the proxy `name` listening on `addr`. Replies to queries from
+/// ports other than 5353 repeat the question and its `id`, as legacy resolvers
+/// expect; pass `id: 0` and `question: false` for unsolicited announcements.
+pub fn response(id: u16, question: bool, name: &str, addr: SocketAddrV4) -> Vec<u8> {
+    let label = instance_label(name);
+    let instance = format!("{label}.{SERVICE}");
+    let host = format!("{}.local", host_label(&label));
+    let url = format!("ws://{addr}/ws");
+
+    // Legacy resolvers would take the cache-flush bit for part of the class.
+    let unique = !question;
+    let mut packet = header(id, 0x8400, u16::from(question), 4);
+    if question {
+        write_name(&mut packet, SERVICE);
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    }
+
+    let mut ptr = Vec::new();
+    write_name(&mut ptr, &instance);
+    write_record(&mut packet, SERVICE, TYPE_PTR, false, &ptr);
+
+    let mut srv = Vec::new();
+    srv.extend_from_slice(&0u16.to_be_bytes());
+    srv.extend_from_slice(&0u16.to_be_bytes());
+    srv.extend_from_slice(&addr.port().to_be_bytes());
+    write_name(&mut srv, &host);
+    write_record(&mut packet, &instance, TYPE_SRV, unique, &srv);
+
+    let mut txt = Vec::new();
+    for entry in [format!("url={url}"), "txtvers=1".to_string()] {
+        txt.push(entry.len() as u8);
+        txt.extend_from_slice(entry.as_bytes());
+    }
+    write_record(&mut packet, &instance, TYPE_TXT, unique, &txt);
+
+    write_record(&mut packet, &host, TYPE_A, unique, &addr.ip().octets());
+    packet
+}
+
+/// Proxies announced in an mDNS response; anything else yields an empty list.
+pub fn parse_response(packet: &[u8]) -> Vec<Announcement> {
+    let Some((_, flags, questions, records)) = read_header(packet) else {
+        return Vec::new();
+    };
+    if flags & 0x8000 == 0 {
+        return Vec::new();
+    }
+    let mut offset = 12;
+    for _ in 0..questions {
+        if read_name(packet, &mut offset).is_none() || packet.len() < offset + 4 {
+            return Vec::new();
+        }
+        offset += 4;
+    }
+
+    let suffix = format!(".{SERVICE}");
+    let mut found = Vec::new();
+    for _ in 0..records {
+        let Some((name, kind, data)) = read_record(packet, &mut offset) else {
+            break;
+        };
+        if kind != TYPE_TXT || name.len() <= suffix.len() {
+            continue;
+        }
+        let (instance, service) = name.split_at(name.len() - suffix.len());
+        if !service.eq_ignore_ascii_case(&suffix) {
+            continue;
+        }
+        if let Some(url) =
+            txt_entries(data).find_map(|entry| entry.strip_prefix("url=").map(str::to_string))
+            && (url.starts_with("ws://") || url.starts_with("wss://"))
+        {
+            found.push(Announcement {
+                name: instance.to_string(),
+                url,
+            });
+        }
+    }
+    found
+}
+
+/// Sends [`query`] and collects the answers that arrive within `timeout`. Errors
+/// mean multicast is unavailable, for example without a network interface.
+pub fn scan(timeout: Duration) -> io::Result<Vec<Announcement>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket.send_to(&query(), MDNS_ADDR)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut found: Vec<Announcement> = Vec::new();
+    let mut buf = [0u8; 9000];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, _)) => {
+                for announcement in parse_response(&buf[..len]) {
+                    if !found.contains(&announcement) {
+                        found.push(announcement);
+                    }
+                }
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(found)
+}
+
+/// `name` as a single DNS label: dots are replaced and the length is capped.
+fn instance_label(name: &str) -> String {
+    let label: String = name.trim().replace('.', "-");
+    let mut end = label.len().min(63);
+    while !label.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == 0 {
+        DEFAULT_NAME.to_string()
+    } else {
+        label[..end].to_string()
+    }
+}
+
+/// Host name label for the SRV and A records: ASCII letters, digits and hyphens.
+fn host_label(label: &str) -> String {
+    let host: String = label
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    match host.trim_matches('-') {
+        "" => DEFAULT_NAME.to_string(),
+        host => host.to_string(),
+    }
+}
+
+fn header(id: u16, flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(512);
+    for value in [id, flags, questions, answers, 0, 0] {
+        packet.extend_from_slice(&value.to_be_bytes());
+    }
+    packet
+}
+
+fn write_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+}
+
+fn write_record(packet: &mut Vec<u8>, name: &str, kind: u16, unique: bool, data: &[u8]) {
+    write_name(packet, name);
+    packet.extend_from_slice(&kind.to_be_bytes());
+    let class = if unique {
+        CLASS_IN | CLASS_TOP_BIT
+    } else {
+        CLASS_IN
+    };
+    packet.extend_from_slice(&class.to_be_bytes());
+    packet.extend_from_slice(&RECORD_TTL.to_be_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+}
+
+/// Id, flags, question count and record count (answers, authorities and additionals).
+fn read_header(packet: &[u8]) -> Option<(u16, u16, u16, usize)> {
+    let mut offset = 0;
+    let mut values = [0u16; 6];
+    for value in &mut values {
+        *value = read_u16(packet, &mut offset)?;
+    }
+    let records = values[3..].iter().map(|&count| usize::from(count)).sum();
+    Some((values[0], values[1], values[2], records))
+}
+
+fn read_u16(packet: &[u8], offset: &mut usize) -> Option<u16> {
+    let bytes = packet.get(*offset..*offset + 2)?;
+    *offset += 2;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a possibly compressed name, leaving `offset` after it.
+fn read_name(packet: &[u8], offset: &mut usize) -> Option<String> {
+    let mut labels = Vec::new();
+    let mut position = *offset;
+    let mut jumped = false;
+    for _ in 0..128 {
+        let len = *packet.get(position)?;
+        match len {
+            0 => {
+                if !jumped {
+                    *offset = position + 1;
+                }
+                return Some(labels.join("."));
+            }
+            len if len & 0xC0 == 0xC0 => {
+                let low = *packet.get(position + 1)?;
+                if !jumped {
+                    *offset = position + 2;
+                    jumped = true;
+                }
+                position = (usize::from(len & 0x3F) << 8) | usize::from(low);
+            }
+            len => {
+                let label = packet.get(position + 1..position + 1 + usize::from(len))?;
+                labels.push(String::from_utf8_lossy(label).to_string());
+                position += 1 + usize::from(len);
+            }
+        }
+    }
+    None
+}
+
+fn read_record<'a>(packet: &'a [u8], offset: &mut usize) -> Option<(String, u16, &'a [u8])> {
+    let name = read_name(packet, offset)?;
+    let kind = read_u16(packet, offset)?;
+    *offset += 6;
+    let len = usize::from(read_u16(packet, offset)?);
+    let data = packet.get(*offset..*offset + len)?;
+    *offset += len;
+    Some((name, kind, data))
+}
+
+fn txt_entries(mut data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    std::iter::from_fn(move || {
+        let (&len, rest) = data.split_first()?;
+        let entry = rest.get(..usize::from(len))?;
+        data = &rest[usize::from(len)..];
+        Some(String::from_utf8_lossy(entry).to_string())
+    })
+}
@@ -0,0 +1,155 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use uuid::Uuid;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::relay::AuthRole;
+use crate::secrets::getrandom;
+
+/// Prefix of sealed payloads. Plain relayed payloads are JSON, zstd or zlib frames,
+/// which never start with it.
+const SEALED_MAGIC: [u8; 4] = *b"rpe1";
+const COUNTER_LEN: usize = 8;
+
+/// The public keys a client and server exchanged for a session. The server signs them
+/// into its identity proof, so a relay that swaps or strips them is caught.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyExchange {
+    pub client_key: String,
+    pub server_key: String,
+}
+
+/// One side's ephemeral X25519 key for a session.
+pub struct KeyPair {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl KeyPair {
+    pub fn generate() -> anyhow::Result<Self> {
+        let mut seed = [0u8; 32];
+        getrandom(&mut seed)?;
+        let secret = StaticSecret::from(seed);
+        let public = PublicKey::from(&secret);
+        Ok(Self { secret, public })
+    }
+
+    /// Base64 public key, as sent in [`crate::app::Capabilities`].
+    pub fn public_key(&self) -> String {
+        STANDARD.encode(self.public.as_bytes())
+    }
+
+    /// Derives the session's keys from the other side's public key. `role` is the side
+    /// this key pair belongs to.
+    pub fn agree(self, peer_key: &str, session_id: Uuid, role: &AuthRole) -> anyhow::Result<SessionCipher> {
+        let peer: [u8; 32] = STANDARD
+            .decode(peer_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("malformed end-to-end key"))?;
+        let shared = self.secret.diffie_hellman(&PublicKey::from(peer));
+        if !shared.was_contributory() {
+            anyhow::bail!("end-to-end key is not usable");
+        }
+
+        let own_key = self.public_key();
+        let exchange = match role {
+            AuthRole::Client => KeyExchange {
+                client_key: own_key,
+                server_key: peer_key.to_string(),
+            },
+            AuthRole::Server => KeyExchange {
+                client_key: peer_key.to_string(),
+                server_key: own_key,
+            },
+        };
+        let hkdf = Hkdf::<Sha256>::new(Some(session_id.as_bytes()), shared.as_bytes());
+        let cipher = |sender: &str| -> anyhow::Result<ChaCha20Poly1305> {
+            let info = format!(
+                "rs-peer-workspace e2e\0{sender}\0{}\0{}",
+                exchange.client_key, exchange.server_key
+            );
+            let mut key = [0u8; 32];
+            hkdf.expand(info.as_bytes(), &mut key)
+                .map_err(|_| anyhow::anyhow!("failed to derive end-to-end key"))?;
+            Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+        };
+        let (send, receive) = match role {
+            AuthRole::Client => (cipher("client")?, cipher("server")?),
+            AuthRole::Server => (cipher("server")?, cipher("client")?),
+        };
+        Ok(SessionCipher {
+            exchange,
+            send,
+            receive,
+            sent: 0,
+            received: None,
+        })
+    }
+}
+
+/// Seals relayed payloads with one key per direction and a counter as the nonce.
+/// Opening refuses counters that do not increase, so the relay can drop payloads but
+/// not replay or reorder them.
+pub struct SessionCipher {
+    exchange: KeyExchange,
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    sent: u64,
+    received: Option<u64>,
+}
+
+impl SessionCipher {
+    pub fn exchange(&self) -> &KeyExchange {
+        &self.exchange
+    }
+
+    pub fn seal(&mut self, payload: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let counter = self.sent;
+        self.sent = counter
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("end-to-end nonces exhausted"))?;
+        let ciphertext = self
+            .send
+            .encrypt(&nonce(counter), payload)
+            .map_err(|_| anyhow::anyhow!("failed to encrypt payload"))?;
+        let mut sealed = Vec::with_capacity(SEALED_MAGIC.len() + COUNTER_LEN + ciphertext.len());
+        sealed.extend_from_slice(&SEALED_MAGIC);
+        sealed.extend_from_slice(&counter.to_be_bytes());
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn open(&mut self, sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let Some(rest) = sealed.strip_prefix(SEALED_MAGIC.as_slice()) else {
+            anyhow::bail!("payload is not end-to-end encrypted");
+        };
+        if rest.len() < COUNTER_LEN {
+            anyhow::bail!("truncated encrypted payload");
+        }
+        let (counter, ciphertext) = rest.split_at(COUNTER_LEN);
+        let counter = u64::from_be_bytes(counter.try_into()?);
+        if self.received.is_some_and(|last| counter <= last) {
+            anyhow::bail!("replayed or reordered encrypted payload");
+        }
+        let payload = self
+            .receive
+            .decrypt(&nonce(counter), ciphertext)
+            .map_err(|_| anyhow::anyhow!("encrypted payload failed authentication"))?;
+        self.received = Some(counter);
+        Ok(payload)
+    }
+}
+
+pub fn is_sealed(payload: &[u8]) -> bool {
+    payload.starts_with(&SEALED_MAGIC)
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[12 - COUNTER_LEN..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::clone_from_slice(&nonce)
+}
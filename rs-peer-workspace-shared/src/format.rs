@@ -0,0 +1,106 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formatter {
+    Rustfmt,
+    Black,
+    Prettier,
+}
+
+impl Formatter {
+    pub const ALL: [Formatter; 3] = [Formatter::Rustfmt, Formatter::Black, Formatter::Prettier];
+
+    /// Program run when no other path is configured, looked up on `PATH`.
+    pub fn program(self) -> &'static str {
+        match self {
+            Formatter::Rustfmt => "rustfmt",
+            Formatter::Black => "black",
+            Formatter::Prettier => "prettier",
+        }
+    }
+
+    /// Formatter for a file, by its extension.
+    pub fn for_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit(['/', '\\']).next()?.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Formatter::Rustfmt),
+            "py" | "pyi" => Some(Formatter::Black),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "json" | "css" | "scss" | "less" | "html"
+            | "vue" | "md" | "yaml" | "yml" | "graphql" => Some(Formatter::Prettier),
+            _ => None,
+        }
+    }
+
+    /// Arguments that make it read the content of `path` from stdin and write the
+    /// formatted content to stdout.
+    fn args(self, path: &Path) -> Vec<String> {
+        let path_arg = path.to_string_lossy().into_owned();
+        match self {
+            // Piped code has no crate, so the edition comes from the nearest manifest.
+            Formatter::Rustfmt => match cargo_edition(path) {
+                Some(edition) => vec!["--edition".to_string(), edition],
+                None => Vec::new(),
+            },
+            Formatter::Black => vec![
+                "--quiet".to_string(),
+                "--stdin-filename".to_string(),
+                path_arg,
+                "-".to_string(),
+            ],
+            Formatter::Prettier => vec!["--stdin-filepath".to_string(), path_arg],
+        }
+    }
+}
+
+/// Formats `content`, the text of `path`, by piping it through `program`. The
+/// formatter runs in the file's directory so it finds its configuration there;
+/// the file itself is not touched.
+pub fn format(formatter: Formatter, program: &str, path: &Path, content: &str) -> anyhow::Result<String> {
+    let mut command = Command::new(program);
+    command
+        .args(formatter.args(path))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = path.parent().filter(|dir| dir.is_dir()) {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|err| anyhow::anyhow!("failed to start {program}: {err}"))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("{program} stdin unavailable"))?;
+    // Written from another thread so a formatter that writes before it has read
+    // everything cannot deadlock on a full pipe.
+    let input = content.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim() {
+            "" => anyhow::bail!("{program} exited with {}", output.status),
+            message => anyhow::bail!("{program} failed: {message}"),
+        }
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// `edition` of the closest `Cargo.toml` above `path` that sets one, which skips
+/// members that inherit it from their workspace.
+pub fn cargo_edition(path: &Path) -> Option<String> {
+    path.ancestors().skip(1).find_map(|dir| {
+        let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        manifest.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "edition").then(|| value.trim().trim_matches('"').to_string())
+        })
+    })
+}
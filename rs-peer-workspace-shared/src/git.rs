@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use git2::{
+    DiffFormat, DiffOptions, ErrorCode, IndexAddOption, Repository, Status, StatusOptions,
+};
+
+use crate::app::{GitFileState, GitFileStatus, GitStatus};
+
+/// Branch and changed files of the repository containing `path`, or `None` when
+/// `path` is not inside a git working tree.
+pub fn status(path: &Path) -> anyhow::Result<Option<GitStatus>> {
+    let repo = match Repository::discover(path) {
+        Ok(repo) => repo,
+        Err(err) if err.code() == ErrorCode::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let root = workdir(&repo)?;
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let files = repo
+        .statuses(Some(&mut options))?
+        .iter()
+        .filter_map(|entry| {
+            let state = file_state(entry.status())?;
+            let path = entry
+                .head_to_index()
+                .or_else(|| entry.index_to_workdir())
+                .and_then(|delta| delta.new_file().path().map(Path::to_path_buf))
+                .map(|path| path.to_string_lossy().replace('\\', "/"))
+                .or_else(|| entry.path().map(str::to_string))?;
+            Some(GitFileStatus { path, state })
+        })
+        .collect();
+
+    Ok(Some(GitStatus {
+        root,
+        branch: branch(&repo),
+        files,
+    }))
+}
+
+/// Unified diff of the working tree, staged changes included, against `HEAD`.
+/// `file` limits it to one path relative to the repository root.
+pub fn diff(path: &Path, file: Option<&str>) -> anyhow::Result<String> {
+    let repo = Repository::discover(path)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    if let Some(file) = file {
+        options.pathspec(file).disable_pathspec_match(true);
+    }
+    let diff = repo.diff_tree_to_workdir_with_index(head.as_ref(), Some(&mut options))?;
+
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(patch)
+}
+
+/// Stages `files`, relative to the repository root, or every change when it is
+/// empty, and commits them on the current branch. Returns the short commit id.
+pub fn commit(path: &Path, message: &str, files: &[String]) -> anyhow::Result<String> {
+    if message.trim().is_empty() {
+        anyhow::bail!("commit message is empty");
+    }
+    let repo = Repository::discover(path)?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("repository has no working tree"))?
+        .to_path_buf();
+    let mut index = repo.index()?;
+    if files.is_empty() {
+        index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"], None)?;
+    } else {
+        for file in files {
+            let file = Path::new(file);
+            if root.join(file).exists() {
+                index.add_path(file)?;
+            } else {
+                index.remove_path(file)?;
+            }
+        }
+    }
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo
+        .signature()
+        .map_err(|_| anyhow::anyhow!("set user.name and user.email in the git config first"))?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    let id = repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+    Ok(id.to_string()[..7].to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChangeKind {
+    Added,
+    Modified,
+    /// Lines were removed before this one.
+    Deleted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineChange {
+    /// 0-based line of the new version; a deletion at the end of the file is
+    /// reported one past its last line.
+    pub line: usize,
+    pub kind: LineChangeKind,
+}
+
+/// Lines of the new version that a unified diff of one file adds, modifies or
+/// removes lines before, for gutter markers. Added lines that follow removed
+/// ones count as modified.
+pub fn line_changes(patch: &str) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let mut line = 0;
+    let mut removed = 0;
+    let mut in_hunk = false;
+    for text in patch.lines() {
+        if let Some(header) = text.strip_prefix("@@ ") {
+            flush_removed(&mut changes, &mut removed, line);
+            in_hunk = true;
+            // "-a,b +c,d @@": an empty new side starts after line c rather than at it.
+            let new = header
+                .split(' ')
+                .find_map(|range| range.strip_prefix('+'))
+                .unwrap_or("1");
+            let (start, count) = new.split_once(',').unwrap_or((new, "1"));
+            let start: usize = start.parse().unwrap_or(1);
+            line = if count == "0" { start } else { start.saturating_sub(1) };
+            continue;
+        }
+        if text.starts_with("diff ") {
+            flush_removed(&mut changes, &mut removed, line);
+            in_hunk = false;
+        }
+        if !in_hunk {
+            continue;
+        }
+        match text.as_bytes().first() {
+            Some(b'-') => removed += 1,
+            Some(b'+') => {
+                let kind = if removed > 0 {
+                    removed -= 1;
+                    LineChangeKind::Modified
+                } else {
+                    LineChangeKind::Added
+                };
+                changes.push(LineChange { line, kind });
+                line += 1;
+            }
+            Some(b' ') => {
+                flush_removed(&mut changes, &mut removed, line);
+                line += 1;
+            }
+            _ => {}
+        }
+    }
+    flush_removed(&mut changes, &mut removed, line);
+    changes
+}
+
+/// Marks lines removed without replacement before `line`.
+fn flush_removed(changes: &mut Vec<LineChange>, removed: &mut usize, line: usize) {
+    if *removed > 0 {
+        changes.push(LineChange {
+            line,
+            kind: LineChangeKind::Deleted,
+        });
+        *removed = 0;
+    }
+}
+
+fn workdir(repo: &Repository) -> anyhow::Result<String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("repository has no working tree"))?
+        .to_string_lossy()
+        .into_owned();
+    Ok(match workdir.trim_end_matches(['/', '\\']) {
+        "" => workdir,
+        trimmed => trimmed.to_string(),
+    })
+}
+
+/// Name of the checked-out branch, also before its first commit; `None` on a
+/// detached `HEAD`.
+fn branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    Some(target.strip_prefix("refs/heads/").unwrap_or(target).to_string())
+}
+
+fn file_state(status: Status) -> Option<GitFileState> {
+    let state = if status.is_conflicted() {
+        GitFileState::Conflicted
+    } else if status.is_index_new() {
+        GitFileState::Added
+    } else if status.is_wt_new() {
+        GitFileState::Untracked
+    } else if status.is_index_deleted() || status.is_wt_deleted() {
+        GitFileState::Deleted
+    } else if status.is_index_renamed() || status.is_wt_renamed() {
+        GitFileState::Renamed
+    } else if status.is_index_modified()
+        || status.is_wt_modified()
+        || status.is_index_typechange()
+        || status.is_wt_typechange()
+    {
+        GitFileState::Modified
+    } else {
+        return None;
+    };
+    Some(state)
+}
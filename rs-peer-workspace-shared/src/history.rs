@@ -0,0 +1,96 @@
+use crate::index::fuzzy_match;
+
+/// Commands kept per terminal; older ones are dropped.
+pub const MAX_HISTORY: usize = 500;
+
+/// Commands run in a terminal, oldest first, recalled one at a time like a shell's
+/// history or searched.
+#[derive(Debug, Clone, Default)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    /// Entry shown while browsing, `None` when not browsing.
+    cursor: Option<usize>,
+    /// Input that was being typed when browsing started, restored past the newest
+    /// entry.
+    draft: String,
+}
+
+impl CommandHistory {
+    pub fn from_entries(mut entries: Vec<String>) -> Self {
+        entries.drain(..entries.len().saturating_sub(MAX_HISTORY));
+        Self {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Records a command that was run, moving an earlier copy of it to the end, and
+    /// stops browsing.
+    pub fn push(&mut self, command: &str) {
+        self.reset();
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != command);
+        self.entries.push(command.to_string());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+    }
+
+    /// The entry before the one shown, or the newest when browsing starts with
+    /// `input` as the draft. `None` when there is nothing older.
+    pub fn older(&mut self, input: &str) -> Option<&str> {
+        let idx = match self.cursor {
+            Some(0) => return None,
+            Some(idx) => idx - 1,
+            None => {
+                let idx = self.entries.len().checked_sub(1)?;
+                self.draft = input.to_string();
+                idx
+            }
+        };
+        self.cursor = Some(idx);
+        Some(&self.entries[idx])
+    }
+
+    /// The entry after the one shown, or the draft after the newest. `None` when not
+    /// browsing.
+    pub fn newer(&mut self) -> Option<&str> {
+        let idx = self.cursor? + 1;
+        if idx < self.entries.len() {
+            self.cursor = Some(idx);
+            Some(&self.entries[idx])
+        } else {
+            self.cursor = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Stops browsing, for example once the input is edited.
+    pub fn reset(&mut self) {
+        self.cursor = None;
+        self.draft.clear();
+    }
+
+    /// Entries fuzzy matching `query`, best first and newer first among equal
+    /// matches. An empty query lists every entry newest first.
+    pub fn search(&self, query: &str) -> Vec<&str> {
+        if query.trim().is_empty() {
+            return self.entries.iter().rev().map(String::as_str).collect();
+        }
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .rev()
+            .filter_map(|entry| Some((fuzzy_match(query, entry)?.score, entry.as_str())))
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
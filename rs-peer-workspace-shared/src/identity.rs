@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::e2e::KeyExchange;
+use crate::secrets::getrandom;
+
+/// Random challenge the client sends once its session is established.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityChallenge {
+    pub nonce: String,
+}
+
+/// The server's public key and its signature over the session and challenge, both
+/// base64.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerIdentity {
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// Long-lived Ed25519 key identifying a server across restarts and proxies.
+pub struct ServerKey {
+    signing_key: SigningKey,
+}
+
+impl ServerKey {
+    /// Reads the key stored at `path`, generating and saving a new one when the file
+    /// does not exist.
+    pub fn load_or_create(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            let encoded = fs::read_to_string(path)?;
+            let seed: [u8; 32] = STANDARD
+                .decode(encoded.trim())?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("{} is not an identity key", path.display()))?;
+            return Ok(Self {
+                signing_key: SigningKey::from_bytes(&seed),
+            });
+        }
+
+        let mut seed = [0u8; 32];
+        getrandom(&mut seed)?;
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        write_private(path, STANDARD.encode(seed).as_bytes())?;
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self.signing_key.verifying_key().as_bytes())
+    }
+
+    /// Signs the challenge for this session, along with the end-to-end keys when the
+    /// session exchanged them.
+    pub fn prove(
+        &self,
+        session_id: Uuid,
+        challenge: &IdentityChallenge,
+        exchange: Option<&KeyExchange>,
+    ) -> ServerIdentity {
+        let signature = self
+            .signing_key
+            .sign(&signed_message(session_id, &challenge.nonce, exchange));
+        ServerIdentity {
+            public_key: STANDARD.encode(self.signing_key.verifying_key().as_bytes()),
+            signature: STANDARD.encode(signature.to_bytes()),
+        }
+    }
+}
+
+/// Checks that `identity` signs `challenge` and the end-to-end keys for this session
+/// and returns the key's fingerprint.
+pub fn verify(
+    identity: &ServerIdentity,
+    session_id: Uuid,
+    challenge: &IdentityChallenge,
+    exchange: Option<&KeyExchange>,
+) -> anyhow::Result<String> {
+    let public_key: [u8; 32] = STANDARD
+        .decode(&identity.public_key)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("malformed server public key"))?;
+    let signature: [u8; 64] = STANDARD
+        .decode(&identity.signature)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("malformed server signature"))?;
+    VerifyingKey::from_bytes(&public_key)?
+        .verify(
+            &signed_message(session_id, &challenge.nonce, exchange),
+            &Signature::from_bytes(&signature),
+        )
+        .map_err(|_| anyhow::anyhow!("server identity signature is invalid"))?;
+    Ok(fingerprint(&public_key))
+}
+
+/// OpenSSH-style `SHA256:<base64>` fingerprint of a public key.
+pub fn fingerprint(public_key: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(public_key)))
+}
+
+fn signed_message(session_id: Uuid, nonce: &str, exchange: Option<&KeyExchange>) -> Vec<u8> {
+    let mut message = format!("rs-peer-workspace identity\0{session_id}\0{nonce}");
+    if let Some(exchange) = exchange {
+        message.push_str(&format!("\0{}\0{}", exchange.client_key, exchange.server_key));
+    }
+    message.into_bytes()
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(contents)
+}
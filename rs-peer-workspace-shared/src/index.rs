@@ -0,0 +1,108 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::app::ListFilter;
+
+/// Most files one index holds; larger trees are cut off and reported as truncated.
+pub const MAX_INDEX_ENTRIES: usize = 50_000;
+
+/// Paths of the files under `root`, relative to it with `/` separators, sorted, and
+/// whether the index stopped at [`MAX_INDEX_ENTRIES`]. Entries `filter` hides are
+/// skipped, hidden directories below `root` are not entered and symlinks are listed
+/// but never followed.
+pub fn index_tree(root: &Path, filter: &ListFilter) -> io::Result<(Vec<String>, bool)> {
+    if !root.is_dir() {
+        return Err(io::Error::other(format!("{} is not a directory", root.display())));
+    }
+    let mut files = Vec::new();
+    let mut truncated = false;
+    let mut pending = vec![String::new()];
+    'walk: while let Some(relative) = pending.pop() {
+        let Ok(entries) = fs::read_dir(root.join(&relative)) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if filter.hides(&name) {
+                continue;
+            }
+            let path = if relative.is_empty() {
+                name.clone()
+            } else {
+                format!("{relative}/{name}")
+            };
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                if !name.starts_with('.') {
+                    pending.push(path);
+                }
+            } else if files.len() == MAX_INDEX_ENTRIES {
+                truncated = true;
+                break 'walk;
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok((files, truncated))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Byte offsets of the matched characters in the candidate.
+    pub positions: Vec<usize>,
+}
+
+/// Matches the characters of `query` in order and ignoring case against
+/// `candidate`, a `/`-separated path. Matches at the start of a path segment or
+/// word, consecutive matches and matches in the file name score higher; `None`
+/// means some query character does not occur.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let file_name_start = candidate.rfind('/').map_or(0, |slash| slash + 1);
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut wanted = query.iter().peekable();
+    let mut previous: Option<(usize, char)> = None;
+    let mut last_match: Option<usize> = None;
+    for (offset, ch) in candidate.char_indices() {
+        let Some(&&next) = wanted.peek() else {
+            break;
+        };
+        if ch.to_lowercase().eq(std::iter::once(next)) {
+            wanted.next();
+            score += 1;
+            let boundary = match previous {
+                None => true,
+                Some((_, before)) => {
+                    matches!(before, '/' | '\\' | '_' | '-' | '.' | ' ')
+                        || (before.is_lowercase() && ch.is_uppercase())
+                }
+            };
+            if boundary {
+                score += 8;
+            }
+            if last_match.is_some() && last_match == previous.map(|(at, _)| at) {
+                score += 5;
+            }
+            if offset >= file_name_start {
+                score += 3;
+            }
+            positions.push(offset);
+            last_match = Some(offset);
+        }
+        previous = Some((offset, ch));
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    // Among equally good matches, prefer shorter paths.
+    score -= (candidate.len() / 8) as i32;
+    Some(FuzzyMatch { score, positions })
+}
@@ -1,3 +1,26 @@
 pub mod app;
+pub mod cache;
+pub mod compression;
+pub mod content;
+pub mod crash;
+pub mod diff;
+pub mod discovery;
+pub mod e2e;
+pub mod format;
+pub mod git;
+pub mod history;
+pub mod identity;
+pub mod index;
+pub mod lsp;
+pub mod media;
+pub mod merge;
+pub mod outbox;
+pub mod pipeline;
 pub mod project;
 pub mod relay;
+pub mod search;
+pub mod secrets;
+pub mod stat;
+pub mod telemetry;
+pub mod transfer;
+pub mod tunnel;
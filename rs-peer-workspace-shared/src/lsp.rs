@@ -0,0 +1,406 @@
+//! Language Server Protocol support shared by the server, which relays a language
+//! server's stdio, and the client, which speaks the protocol.
+//!
+//! Messages travel as numbered `lsp_send` and `lsp_message` RPCs in both
+//! directions; [`crate::tunnel::InOrder`] restores their order, which LSP relies on.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A language server the client starts for files with one of `extensions`.
+#[derive(Debug)]
+pub struct LanguageServerSpec {
+    pub name: &'static str,
+    pub command: &'static str,
+    pub args: &'static [&'static str],
+    pub language_id: &'static str,
+    pub extensions: &'static [&'static str],
+}
+
+pub const LANGUAGE_SERVERS: &[LanguageServerSpec] = &[
+    LanguageServerSpec {
+        name: "rust-analyzer",
+        command: "rust-analyzer",
+        args: &[],
+        language_id: "rust",
+        extensions: &["rs"],
+    },
+    LanguageServerSpec {
+        name: "pyright",
+        command: "pyright-langserver",
+        args: &["--stdio"],
+        language_id: "python",
+        extensions: &["py", "pyi"],
+    },
+];
+
+pub fn server_for_path(path: &str) -> Option<&'static LanguageServerSpec> {
+    let extension = path.rsplit(['/', '\\']).next()?.rsplit_once('.')?.1.to_ascii_lowercase();
+    LANGUAGE_SERVERS
+        .iter()
+        .find(|spec| spec.extensions.contains(&extension.as_str()))
+}
+
+/// `message` with the `Content-Length` header LSP frames it with.
+pub fn encode(message: &str) -> Vec<u8> {
+    let mut frame = format!("Content-Length: {}\r\n\r\n", message.len()).into_bytes();
+    frame.extend_from_slice(message.as_bytes());
+    frame
+}
+
+/// Splits a language server's output into messages.
+#[derive(Debug, Default)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+}
+
+impl FrameReader {
+    /// Adds output and returns the messages it completed.
+    pub fn push(&mut self, data: &[u8]) -> anyhow::Result<Vec<String>> {
+        self.buffer.extend_from_slice(data);
+        let mut messages = Vec::new();
+        while let Some(end) = self.buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let header = String::from_utf8_lossy(&self.buffer[..end]).into_owned();
+            let length: usize = header
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.trim()
+                        .eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().ok())?
+                })
+                .ok_or_else(|| anyhow::anyhow!("LSP frame without Content-Length: {header}"))?;
+            let start = end + 4;
+            if self.buffer.len() < start + length {
+                break;
+            }
+            messages.push(String::from_utf8_lossy(&self.buffer[start..start + length]).into_owned());
+            self.buffer.drain(..start + length);
+        }
+        Ok(messages)
+    }
+}
+
+/// A message from the language server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    Response {
+        id: i64,
+        result: Result<Value, String>,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+    /// A request the client has to answer, see [`reply`].
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+}
+
+pub fn parse(message: &str) -> anyhow::Result<Incoming> {
+    let mut value: Value = serde_json::from_str(message)?;
+    let method = value.get("method").and_then(Value::as_str).map(str::to_string);
+    let params = value.get_mut("params").map(Value::take).unwrap_or(Value::Null);
+    Ok(match (value.get("id").cloned(), method) {
+        (Some(id), Some(method)) => Incoming::Request { id, method, params },
+        (None, Some(method)) => Incoming::Notification { method, params },
+        (Some(id), None) => {
+            let id = id.as_i64().ok_or_else(|| anyhow::anyhow!("unexpected response id {id}"))?;
+            let result = match value.get("error") {
+                Some(error) => Err(error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("request failed")
+                    .to_string()),
+                None => Ok(value.get_mut("result").map(Value::take).unwrap_or(Value::Null)),
+            };
+            Incoming::Response { id, result }
+        }
+        (None, None) => anyhow::bail!("not an LSP message: {message}"),
+    })
+}
+
+pub fn request(id: i64, method: &str, params: Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string()
+}
+
+pub fn notification(method: &str, params: Value) -> String {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string()
+}
+
+/// Answer to a request from the server. The client has no settings to offer, so
+/// `workspace/configuration` gets one `null` per item and everything else `null`.
+pub fn reply(id: &Value, method: &str, params: &Value) -> String {
+    let result = match method {
+        "workspace/configuration" => {
+            let items = params.get("items").and_then(Value::as_array).map_or(0, Vec::len);
+            Value::Array(vec![Value::Null; items])
+        }
+        _ => Value::Null,
+    };
+    json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string()
+}
+
+pub fn initialize_params(root: &str) -> Value {
+    json!({
+        "processId": null,
+        "rootUri": file_uri(root),
+        "workspaceFolders": [{ "uri": file_uri(root), "name": root }],
+        "capabilities": {
+            "textDocument": {
+                "synchronization": { "didSave": true },
+                "hover": { "contentFormat": ["plaintext", "markdown"] },
+                "definition": {},
+                "publishDiagnostics": {},
+            },
+            "workspace": { "configuration": true, "workspaceFolders": true },
+        },
+    })
+}
+
+pub fn did_open(path: &str, language_id: &str, version: i32, text: &str) -> String {
+    notification(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": {
+                "uri": file_uri(path),
+                "languageId": language_id,
+                "version": version,
+                "text": text,
+            },
+        }),
+    )
+}
+
+/// Sends the whole new text; the client does not track edits.
+pub fn did_change(path: &str, version: i32, text: &str) -> String {
+    notification(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": file_uri(path), "version": version },
+            "contentChanges": [{ "text": text }],
+        }),
+    )
+}
+
+pub fn did_save(path: &str) -> String {
+    notification(
+        "textDocument/didSave",
+        json!({ "textDocument": { "uri": file_uri(path) } }),
+    )
+}
+
+pub fn did_close(path: &str) -> String {
+    notification(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": file_uri(path) } }),
+    )
+}
+
+/// Parameters of `textDocument/hover` and `textDocument/definition`.
+pub fn position_params(path: &str, position: Position) -> Value {
+    json!({
+        "textDocument": { "uri": file_uri(path) },
+        "position": position,
+    })
+}
+
+/// A position as LSP counts it: 0-based line and UTF-16 code unit in the line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    /// Position of character `index` of `text`.
+    pub fn from_index(text: &str, index: usize) -> Self {
+        let mut position = Self::default();
+        for ch in text.chars().take(index) {
+            if ch == '\n' {
+                position.line += 1;
+                position.character = 0;
+            } else {
+                position.character += ch.len_utf16() as u32;
+            }
+        }
+        position
+    }
+
+    /// Character index of the position in `text`, clamped to the end of its line.
+    pub fn to_index(self, text: &str) -> usize {
+        LineIndex::new(text).index(self)
+    }
+}
+
+/// Where the lines of a text start, to turn many positions into character indices
+/// without scanning the whole text for each.
+#[derive(Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// Byte and character offset of each line's start.
+    starts: Vec<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut starts = vec![(0, 0)];
+        for (chars, (byte, ch)) in text.char_indices().enumerate() {
+            if ch == '\n' {
+                starts.push((byte + 1, chars + 1));
+            }
+        }
+        Self { text, starts }
+    }
+
+    /// Character index of `position`, clamped to the end of its line and of the
+    /// text.
+    pub fn index(&self, position: Position) -> usize {
+        let Some(&(byte, chars)) = self.starts.get(position.line as usize) else {
+            return self.starts.last().map_or(0, |&(byte, chars)| chars + self.text[byte..].chars().count());
+        };
+        let mut units = 0;
+        let mut index = chars;
+        for ch in self.text[byte..].chars() {
+            if ch == '\n' || units >= position.character {
+                break;
+            }
+            units += ch.len_utf16() as u32;
+            index += 1;
+        }
+        index
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: Severity,
+    pub message: String,
+    /// Tool that reported it, such as `rustc` or `Pyright`.
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawDiagnostic {
+    range: Range,
+    #[serde(default)]
+    severity: Option<u8>,
+    message: String,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+/// Path and diagnostics of a `textDocument/publishDiagnostics` notification.
+pub fn parse_diagnostics(params: &Value) -> Option<(String, Vec<Diagnostic>)> {
+    let path = uri_path(params.get("uri")?.as_str()?)?;
+    let raw: Vec<RawDiagnostic> = serde_json::from_value(params.get("diagnostics")?.clone()).ok()?;
+    let diagnostics = raw
+        .into_iter()
+        .map(|diagnostic| Diagnostic {
+            range: diagnostic.range,
+            severity: match diagnostic.severity {
+                Some(2) => Severity::Warning,
+                Some(3) => Severity::Information,
+                Some(4) => Severity::Hint,
+                _ => Severity::Error,
+            },
+            message: diagnostic.message,
+            source: diagnostic.source,
+        })
+        .collect();
+    Some((path, diagnostics))
+}
+
+/// Text of a hover result, `None` when there is nothing to show.
+pub fn hover_text(result: &Value) -> Option<String> {
+    fn part(value: &Value) -> Option<String> {
+        match value {
+            Value::String(text) => Some(text.clone()),
+            Value::Object(object) => object.get("value")?.as_str().map(str::to_string),
+            _ => None,
+        }
+    }
+    let contents = result.get("contents")?;
+    let text = match contents {
+        Value::Array(parts) => parts.iter().filter_map(part).collect::<Vec<_>>().join("\n\n"),
+        other => part(other)?,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// First target of a definition result, which may be a location, a list of them
+/// or a list of location links.
+pub fn definition_target(result: &Value) -> Option<(String, Position)> {
+    let location = match result {
+        Value::Array(locations) => locations.first()?,
+        other => other,
+    };
+    let uri = location
+        .get("targetUri")
+        .or_else(|| location.get("uri"))?
+        .as_str()?;
+    let range = location
+        .get("targetSelectionRange")
+        .or_else(|| location.get("range"))?;
+    let range: Range = serde_json::from_value(range.clone()).ok()?;
+    Some((uri_path(uri)?, range.start))
+}
+
+/// `file://` URI of an absolute path, on a Unix or Windows server.
+pub fn file_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// Path of a `file://` URI, the reverse of [`file_uri`]. Windows paths keep their
+/// forward slashes.
+pub fn uri_path(uri: &str) -> Option<String> {
+    let rest = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut iter = rest.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // "/C:/dir" on Windows.
+    Some(match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    })
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+/// Largest file opened in a preview tab; bigger files have to be downloaded.
+pub const MAX_PREVIEW_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    /// PNG, JPEG or GIF, drawn in the tab.
+    Image,
+    Svg,
+    Pdf,
+}
+
+impl MediaKind {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" => Some(MediaKind::Image),
+            "svg" => Some(MediaKind::Svg),
+            "pdf" => Some(MediaKind::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Content of a read-only preview tab.
+#[derive(Debug, Clone)]
+pub struct MediaPreview {
+    /// Distinguishes this content from earlier loads of the same path in image caches.
+    pub id: Uuid,
+    pub kind: MediaKind,
+    pub data: Arc<[u8]>,
+}
+
+impl MediaPreview {
+    pub fn new(kind: MediaKind, data: Vec<u8>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            data: data.into(),
+        }
+    }
+}
+
+/// The version from a PDF's `%PDF-x.y` header.
+pub fn pdf_version(data: &[u8]) -> Option<String> {
+    let rest = data.strip_prefix(b"%PDF-")?;
+    let end = rest
+        .iter()
+        .position(|byte| !(byte.is_ascii_digit() || *byte == b'.'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| String::from_utf8_lossy(&rest[..end]).to_string())
+}
+
+/// Page objects in a PDF, counted from its uncompressed `/Type /Page` entries. PDFs
+/// that keep their objects in compressed streams report 0.
+pub fn pdf_page_count(data: &[u8]) -> usize {
+    let mut count = 0;
+    let mut rest = data;
+    while let Some(idx) = find(rest, b"/Type") {
+        rest = &rest[idx + b"/Type".len()..];
+        let value = trim_start(rest);
+        if let Some(after) = value.strip_prefix(b"/Page")
+            && !after.first().is_some_and(u8::is_ascii_alphanumeric)
+        {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
@@ -0,0 +1,187 @@
+use crate::diff::{DiffOp, TextDiff};
+
+/// Part of a three-way merge. Lines keep their terminators, as in [`TextDiff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeChunk {
+    /// Unchanged, or changed the same way or on one side only.
+    Resolved(Vec<String>),
+    Conflict {
+        base: Vec<String>,
+        mine: Vec<String>,
+        theirs: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThreeWayMerge {
+    pub chunks: Vec<MergeChunk>,
+}
+
+/// A side's replacement of `base[start..end]`.
+struct Edit {
+    start: usize,
+    end: usize,
+    lines: Vec<String>,
+}
+
+impl ThreeWayMerge {
+    /// Merges `mine` and `theirs`, two edits of `base`. Regions changed by only one
+    /// side, or identically by both, resolve automatically.
+    pub fn new(base: &str, mine: &str, theirs: &str) -> Self {
+        let base_lines: Vec<String> = base.split_inclusive('\n').map(str::to_string).collect();
+        let mine = edits(&TextDiff::new(base, mine));
+        let theirs = edits(&TextDiff::new(base, theirs));
+
+        let mut chunks = Vec::new();
+        let (mut next_mine, mut next_theirs) = (0, 0);
+        let mut pos = 0;
+        loop {
+            let start = match (mine.get(next_mine), theirs.get(next_theirs)) {
+                (None, None) => break,
+                (Some(edit), None) | (None, Some(edit)) => edit.start,
+                (Some(a), Some(b)) => a.start.min(b.start),
+            };
+            push_resolved(&mut chunks, &base_lines[pos..start]);
+
+            // Grow the region until no edit on either side overlaps it.
+            let (first_mine, first_theirs) = (next_mine, next_theirs);
+            let mut end = start;
+            loop {
+                let overlaps = |edit: &Edit| edit.start < end || edit.start == start;
+                if let Some(edit) = mine.get(next_mine).filter(|edit| overlaps(edit)) {
+                    end = end.max(edit.end);
+                    next_mine += 1;
+                } else if let Some(edit) = theirs.get(next_theirs).filter(|edit| overlaps(edit)) {
+                    end = end.max(edit.end);
+                    next_theirs += 1;
+                } else {
+                    break;
+                }
+            }
+
+            let region = &base_lines[start..end];
+            let mine_lines = apply(&base_lines, start, end, &mine[first_mine..next_mine]);
+            let theirs_lines = apply(&base_lines, start, end, &theirs[first_theirs..next_theirs]);
+            if first_theirs == next_theirs || mine_lines == theirs_lines {
+                push_resolved(&mut chunks, &mine_lines);
+            } else if first_mine == next_mine {
+                push_resolved(&mut chunks, &theirs_lines);
+            } else {
+                chunks.push(MergeChunk::Conflict {
+                    base: region.to_vec(),
+                    mine: mine_lines,
+                    theirs: theirs_lines,
+                });
+            }
+            pos = end;
+        }
+        push_resolved(&mut chunks, &base_lines[pos..]);
+        Self { chunks }
+    }
+
+    /// Reads the conflicts git leaves in a file, with `<<<<<<<`, `=======` and
+    /// `>>>>>>>` markers and an optional `|||||||` base section. Returns `None` when
+    /// the text has no complete conflict.
+    pub fn from_conflict_markers(text: &str) -> Option<Self> {
+        enum Section {
+            Outside,
+            Mine,
+            Base,
+            Theirs,
+        }
+        let mut chunks = Vec::new();
+        let mut section = Section::Outside;
+        let (mut base, mut mine, mut theirs) = (Vec::new(), Vec::new(), Vec::new());
+        for line in text.split_inclusive('\n') {
+            match section {
+                Section::Outside if line.starts_with("<<<<<<<") => section = Section::Mine,
+                Section::Outside => push_resolved(&mut chunks, &[line.to_string()]),
+                Section::Mine if line.starts_with("|||||||") => section = Section::Base,
+                Section::Mine | Section::Base if line.starts_with("=======") => {
+                    section = Section::Theirs;
+                }
+                Section::Mine => mine.push(line.to_string()),
+                Section::Base => base.push(line.to_string()),
+                Section::Theirs if line.starts_with(">>>>>>>") => {
+                    chunks.push(MergeChunk::Conflict {
+                        base: std::mem::take(&mut base),
+                        mine: std::mem::take(&mut mine),
+                        theirs: std::mem::take(&mut theirs),
+                    });
+                    section = Section::Outside;
+                }
+                Section::Theirs => theirs.push(line.to_string()),
+            }
+        }
+        let merge = Self { chunks };
+        (matches!(section, Section::Outside) && merge.conflict_count() > 0).then_some(merge)
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk, MergeChunk::Conflict { .. }))
+            .count()
+    }
+
+    /// Rebuilds the text, using `resolutions[n]` for conflict `n`.
+    pub fn result(&self, resolutions: &[String]) -> String {
+        let mut merged = String::new();
+        let mut conflict = 0;
+        for chunk in &self.chunks {
+            match chunk {
+                MergeChunk::Resolved(lines) => merged.extend(lines.iter().map(String::as_str)),
+                MergeChunk::Conflict { .. } => {
+                    if let Some(resolution) = resolutions.get(conflict) {
+                        merged.push_str(resolution);
+                    }
+                    conflict += 1;
+                }
+            }
+        }
+        merged
+    }
+}
+
+fn edits(diff: &TextDiff) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut pos = 0;
+    for op in &diff.ops {
+        match op {
+            DiffOp::Equal(lines) => pos += lines.len(),
+            DiffOp::Change { local, remote } => {
+                edits.push(Edit {
+                    start: pos,
+                    end: pos + local.len(),
+                    lines: remote.clone(),
+                });
+                pos += local.len();
+            }
+        }
+    }
+    edits
+}
+
+/// `base[start..end]` with `edits`, which all lie inside that range, applied.
+fn apply(base: &[String], start: usize, end: usize, edits: &[Edit]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pos = start;
+    for edit in edits {
+        lines.extend_from_slice(&base[pos..edit.start]);
+        lines.extend_from_slice(&edit.lines);
+        pos = edit.end;
+    }
+    lines.extend_from_slice(&base[pos..end]);
+    lines
+}
+
+fn push_resolved(chunks: &mut Vec<MergeChunk>, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    if let Some(MergeChunk::Resolved(existing)) = chunks.last_mut() {
+        existing.extend_from_slice(lines);
+    } else {
+        chunks.push(MergeChunk::Resolved(lines.to_vec()));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+/// Outgoing WebSocket messages a peer queues before its writer falls behind.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Protocol messages that must not be lost; refused once the queue is full.
+    Control,
+    /// Output such as relayed data, where the oldest queued item is dropped to make room.
+    Stream,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("outgoing queue is full")
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+struct Shared<T> {
+    items: Mutex<VecDeque<(Priority, T)>>,
+    capacity: usize,
+    ready: Notify,
+    senders: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+/// Bounded queue in front of a WebSocket writer task, so a slow peer costs at most
+/// `capacity` messages of memory instead of growing without limit.
+pub struct Outbox<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct OutboxReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub fn channel<T>(capacity: usize) -> (Outbox<T>, OutboxReceiver<T>) {
+    let shared = Arc::new(Shared {
+        items: Mutex::new(VecDeque::new()),
+        capacity: capacity.max(1),
+        ready: Notify::new(),
+        senders: AtomicUsize::new(1),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        Outbox {
+            shared: shared.clone(),
+        },
+        OutboxReceiver { shared },
+    )
+}
+
+impl<T> Outbox<T> {
+    pub fn send(&self, priority: Priority, item: T) -> Result<(), QueueFull> {
+        {
+            let mut items = self.shared.items.lock().unwrap_or_else(|err| err.into_inner());
+            if items.len() >= self.shared.capacity {
+                let oldest_stream = items
+                    .iter()
+                    .position(|(queued, _)| *queued == Priority::Stream);
+                match (priority, oldest_stream) {
+                    (Priority::Stream, Some(index)) => {
+                        items.remove(index);
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    (Priority::Stream, None) => {
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    (Priority::Control, _) => return Err(QueueFull),
+                }
+            }
+            items.push_back((priority, item));
+        }
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    pub fn send_control(&self, item: T) -> Result<(), QueueFull> {
+        self.send(Priority::Control, item)
+    }
+
+    /// Never fails: a full queue drops its oldest stream item, or this one if it holds
+    /// only control messages.
+    pub fn send_stream(&self, item: T) {
+        let _ = self.send(Priority::Stream, item);
+    }
+
+    /// Messages waiting for the writer.
+    pub fn depth(&self) -> usize {
+        self.shared.items.lock().unwrap_or_else(|err| err.into_inner()).len()
+    }
+
+    /// Stream items discarded because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Clone for Outbox<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Outbox<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.ready.notify_one();
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Outbox<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outbox")
+            .field("depth", &self.depth())
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl<T> OutboxReceiver<T> {
+    /// Next queued message, or `None` once every [`Outbox`] is gone and the queue is empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            {
+                let mut items = self.shared.items.lock().unwrap_or_else(|err| err.into_inner());
+                if let Some((_, item)) = items.pop_front() {
+                    return Some(item);
+                }
+                if self.shared.senders.load(Ordering::Acquire) == 0 {
+                    return None;
+                }
+            }
+            self.shared.ready.notified().await;
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::project::ProjectTask;
+
+/// A task scheduled in a pipeline. Stage 0 tasks have no dependencies; every other
+/// task is one stage after its latest dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedTask {
+    pub name: String,
+    pub stage: usize,
+}
+
+/// The tasks `target` needs, `target` included, ordered so that every task comes
+/// after its dependencies. Fails on unknown task names and dependency cycles.
+pub fn plan(tasks: &[ProjectTask], target: &str) -> anyhow::Result<Vec<PlannedTask>> {
+    let by_name: HashMap<&str, &ProjectTask> =
+        tasks.iter().map(|task| (task.name.as_str(), task)).collect();
+    let mut stages = HashMap::new();
+    let mut planned = Vec::new();
+    let mut path = Vec::new();
+    visit(target, &by_name, &mut stages, &mut path, &mut planned)?;
+    Ok(planned)
+}
+
+fn visit<'a>(
+    name: &'a str,
+    by_name: &HashMap<&'a str, &'a ProjectTask>,
+    stages: &mut HashMap<&'a str, usize>,
+    path: &mut Vec<&'a str>,
+    planned: &mut Vec<PlannedTask>,
+) -> anyhow::Result<usize> {
+    if let Some(stage) = stages.get(name) {
+        return Ok(*stage);
+    }
+    if let Some(start) = path.iter().position(|step| *step == name) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(name);
+        anyhow::bail!("tasks depend on each other: {}", cycle.join(" -> "));
+    }
+    let Some(task) = by_name.get(name) else {
+        match path.last() {
+            Some(parent) => anyhow::bail!("task {parent} depends on unknown task {name}"),
+            None => anyhow::bail!("unknown task {name}"),
+        }
+    };
+    path.push(name);
+    let mut stage = 0;
+    for dependency in &task.depends_on {
+        stage = stage.max(visit(dependency, by_name, stages, path, planned)? + 1);
+    }
+    path.pop();
+    stages.insert(name, stage);
+    planned.push(PlannedTask {
+        name: name.to_string(),
+        stage,
+    });
+    Ok(stage)
+}
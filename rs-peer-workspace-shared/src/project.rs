@@ -1,38 +1,556 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::app::{EnvVar, ListFilter, OutputAttachment};
+use crate::format::Formatter;
+use crate::history::CommandHistory;
+use crate::media::MediaPreview;
+use crate::secrets::EncryptedCredentials;
+use crate::transfer::ChunkLog;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectFile {
     pub connections: Vec<ProjectConnection>,
     pub folders: Vec<ProjectFolder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_credentials: Option<EncryptedCredentials>,
+    #[serde(default, skip_serializing_if = "SessionState::is_empty")]
+    pub session: SessionState,
+    #[serde(default, skip_serializing_if = "ExplorerSettings::is_default")]
+    pub explorer: ExplorerSettings,
+    #[serde(default, skip_serializing_if = "NotificationSettings::is_default")]
+    pub notifications: NotificationSettings,
+    #[serde(default, skip_serializing_if = "FormatSettings::is_default")]
+    pub formatting: FormatSettings,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<ProjectTask>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub port_forwards: Vec<PortForward>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_configs: Vec<RunConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<PinnedPath>,
+}
+
+impl ProjectFile {
+    /// Pins `pin`, or unpins it when it already is. Returns whether it is pinned now.
+    pub fn toggle_pin(&mut self, pin: PinnedPath) -> bool {
+        match self.pinned.iter().position(|pinned| pinned.source == pin.source) {
+            Some(index) => {
+                self.pinned.remove(index);
+                false
+            }
+            None => {
+                self.pinned.push(pin);
+                true
+            }
+        }
+    }
+
+    pub fn is_pinned(&self, source: &FolderSource) -> bool {
+        self.pinned.iter().any(|pinned| &pinned.source == source)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExplorerSettings {
+    #[serde(default)]
+    pub hide_hidden: bool,
+    #[serde(default = "enabled_by_default")]
+    pub show_size: bool,
+    #[serde(default = "enabled_by_default")]
+    pub show_modified: bool,
+    #[serde(default)]
+    pub sort: ExplorerSort,
+    /// Name patterns left out of local and remote listings.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    #[serde(default)]
+    pub open_on: OpenOn,
+    /// Whether activating a folder's name expands it, not only its arrow.
+    #[serde(default = "enabled_by_default")]
+    pub expand_on_label_click: bool,
+    /// Open files in a single preview tab that the next opened file replaces until
+    /// it is edited or double-clicked.
+    #[serde(default)]
+    pub preview_tabs: bool,
+    /// Extensions, without the dot, of files opened in the text editor. Other files
+    /// open in a read-only viewer. A file without an extension matches its name.
+    #[serde(default = "default_text_extensions")]
+    pub text_extensions: Vec<String>,
+    /// Prefetch small sibling files in the background when a remote file is opened.
+    #[serde(default)]
+    pub read_ahead: bool,
+}
+
+impl Default for ExplorerSettings {
+    fn default() -> Self {
+        Self {
+            hide_hidden: false,
+            show_size: true,
+            show_modified: true,
+            sort: ExplorerSort::default(),
+            excludes: Vec::new(),
+            open_on: OpenOn::default(),
+            expand_on_label_click: true,
+            preview_tabs: false,
+            text_extensions: default_text_extensions(),
+            read_ahead: false,
+        }
+    }
+}
+
+impl ExplorerSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn list_filter(&self) -> ListFilter {
+        ListFilter {
+            hide_hidden: self.hide_hidden,
+            excludes: self.excludes.clone(),
+        }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+pub fn default_text_extensions() -> Vec<String> {
+    [
+        "txt", "md", "rst", "log", "csv", "json", "toml", "yaml", "yml", "xml", "ini", "cfg",
+        "conf", "env", "rs", "py", "m", "c", "h", "cpp", "hpp", "cs", "go", "java", "kt", "js",
+        "ts", "jsx", "tsx", "html", "css", "scss", "sql", "sh", "bash", "ps1", "bat", "lua",
+        "rb", "php", "swift", "ron", "lock", "gitignore", "dockerfile", "makefile",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+/// When the client draws attention to terminal commands that finish while its
+/// window is in the background.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationSettings {
+    #[serde(default = "enabled_by_default")]
+    pub command_finished: bool,
+    /// Commands that finish sooner than this are not announced.
+    #[serde(default = "default_min_duration_secs")]
+    pub min_duration_secs: u64,
+    /// Announce commands whose output rings the terminal bell (BEL), however short.
+    #[serde(default = "enabled_by_default")]
+    pub bell: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            command_finished: true,
+            min_duration_secs: default_min_duration_secs(),
+            bell: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FormatSettings {
+    /// Format files that have a formatter before writing them.
+    #[serde(default)]
+    pub format_on_save: bool,
+    /// Programs run for local files instead of the formatter's name on `PATH`.
+    /// Remote files are formatted by the server with the formatter on its `PATH`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub local_paths: BTreeMap<Formatter, String>,
+}
+
+impl FormatSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Program that formats local files with `formatter`.
+    pub fn local_program(&self, formatter: Formatter) -> &str {
+        self.local_paths
+            .get(&formatter)
+            .map(|path| path.trim())
+            .filter(|path| !path.is_empty())
+            .unwrap_or(formatter.program())
+    }
+}
+
+fn default_min_duration_secs() -> u64 {
+    10
+}
+
+/// Order of entries within a folder; folders always come before files.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplorerSort {
+    #[default]
+    Name,
+    /// Largest first.
+    Size,
+    /// Most recently modified first.
+    Modified,
+}
+
+/// Click that opens a file or expands a folder in the explorer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenOn {
+    #[default]
+    SingleClick,
+    DoubleClick,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SessionState {
+    #[serde(default)]
+    pub editors: Vec<SavedEditor>,
+    #[serde(default)]
+    pub selected_editor: Option<String>,
+    #[serde(default)]
+    pub terminals: Vec<SavedTerminal>,
+    #[serde(default)]
+    pub selected_terminal: Option<usize>,
+    /// Uploads and downloads that had not finished; they resume once their
+    /// connection is up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transfers: Vec<SavedTransfer>,
+}
+
+impl SessionState {
+    pub fn is_empty(&self) -> bool {
+        self.editors.is_empty() && self.terminals.is_empty() && self.transfers.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedEditor {
+    pub path: String,
+    pub source: EditorSource,
+    #[serde(default)]
+    pub cursor: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+    Download,
+    Upload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTransfer {
+    pub connection_name: String,
+    pub direction: TransferDirection,
+    pub local_path: String,
+    pub remote_path: String,
+    #[serde(default)]
+    pub total_size: Option<u64>,
+    #[serde(default)]
+    pub chunks: ChunkLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTerminal {
+    pub connection_name: String,
+    pub title: String,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Commands run in the terminal, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ProjectConnection {
     pub name: String,
     pub proxy_addr: String,
+    /// Proxy account to log in with. When empty, `proxy_password` is the shared proxy
+    /// password; otherwise it is this account's password.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub proxy_username: String,
     pub proxy_password: String,
     pub server_name: String,
     pub server_password: String,
     pub prefer_p2p: bool,
+    /// Refuse to use the session unless the server agrees to end-to-end encrypt relayed
+    /// payloads. Without it, encryption is still used whenever the server supports it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_e2e: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_ref: Option<String>,
+    /// Identity key fingerprint pinned on first connect; a server presenting another
+    /// key is refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_fingerprint: Option<String>,
+    #[serde(default)]
+    pub terminal_profiles: Vec<TerminalProfile>,
+    /// HTTP or SOCKS proxy the WebSocket to `proxy_addr` is tunnelled through, for
+    /// networks that only allow egress that way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbound_proxy: Option<OutboundProxy>,
+    /// STUN and TURN servers used for P2P instead of the TURN server the proxy hands
+    /// out, for example a relay closer to this client.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ice_servers: Vec<IceServer>,
+    /// Variables set for every command run on the connection, under those of the
+    /// terminal profile or run configuration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<EnvVar>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutboundProxy {
+    pub kind: OutboundProxyKind,
+    /// `host:port` of the proxy.
+    pub addr: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundProxyKind {
+    /// HTTP proxy that supports `CONNECT` tunnels.
+    #[default]
+    Http,
+    Socks5,
+}
+
+impl OutboundProxyKind {
+    pub const ALL: [OutboundProxyKind; 2] = [OutboundProxyKind::Http, OutboundProxyKind::Socks5];
+
+    pub fn scheme(self) -> &'static str {
+        match self {
+            OutboundProxyKind::Http => "http",
+            OutboundProxyKind::Socks5 => "socks5",
+        }
+    }
+}
+
+impl OutboundProxy {
+    /// Parses `http://host:port` or `socks5://host:port`, optionally with
+    /// `user:password@` before the host.
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let (scheme, rest) = url
+            .trim()
+            .split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("outbound proxy {url} has no scheme"))?;
+        let kind = match scheme.to_ascii_lowercase().as_str() {
+            "http" => OutboundProxyKind::Http,
+            "socks5" | "socks5h" => OutboundProxyKind::Socks5,
+            other => anyhow::bail!("unsupported outbound proxy scheme {other}"),
+        };
+        let rest = rest.trim_end_matches('/');
+        let (userinfo, addr) = match rest.rsplit_once('@') {
+            Some((userinfo, addr)) => (Some(userinfo), addr),
+            None => (None, rest),
+        };
+        if addr.rsplit_once(':').is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err()) {
+            anyhow::bail!("outbound proxy address {addr} must be host:port");
+        }
+        let (username, password) = match userinfo {
+            Some(userinfo) => match userinfo.split_once(':') {
+                Some((username, password)) => (username.to_string(), password.to_string()),
+                None => (userinfo.to_string(), String::new()),
+            },
+            None => (String::new(), String::new()),
+        };
+        Ok(Self {
+            kind,
+            addr: addr.to_string(),
+            username,
+            password,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct IceServer {
+    /// `stun:`, `stuns:`, `turn:` or `turns:` URL such as `turn:relay.example.com:3478`.
+    pub url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+}
+
+impl IceServer {
+    /// Parses an ICE server URL, optionally with `user:password@` before the host.
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let url = url.trim();
+        let (scheme, rest) = url
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("ICE server {url} has no scheme"))?;
+        let (userinfo, addr) = match rest.rsplit_once('@') {
+            Some((userinfo, addr)) => (Some(userinfo), addr),
+            None => (None, rest),
+        };
+        let (username, password) = match userinfo {
+            Some(userinfo) => match userinfo.split_once(':') {
+                Some((username, password)) => (username.to_string(), password.to_string()),
+                None => (userinfo.to_string(), String::new()),
+            },
+            None => (String::new(), String::new()),
+        };
+        let server = Self {
+            url: format!("{}:{addr}", scheme.to_ascii_lowercase()),
+            username,
+            password,
+        };
+        server.validate()?;
+        Ok(server)
+    }
+
+    /// Checks the URL's scheme and host; TURN servers also need a username.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let (scheme, addr) = self
+            .url
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("ICE server {} has no scheme", self.url))?;
+        let turn = match scheme {
+            "stun" | "stuns" => false,
+            "turn" | "turns" => true,
+            other => anyhow::bail!("unsupported ICE server scheme {other}"),
+        };
+        let host = addr.split('?').next().unwrap_or_default();
+        if host.is_empty() || host.starts_with('/') || host.contains('@') {
+            anyhow::bail!("ICE server {} must be {scheme}:host[:port]", self.url);
+        }
+        if turn && self.username.is_empty() {
+            anyhow::bail!("TURN server {} needs a username", self.url);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct TerminalProfile {
+    pub name: String,
+    #[serde(default)]
+    pub shell: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: Vec<EnvVar>,
+    #[serde(default)]
+    pub startup_commands: Vec<String>,
+}
+
+/// A named command run on one of the project's connections. Running a task first
+/// runs the tasks it depends on, see [`crate::pipeline`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProjectTask {
+    pub name: String,
+    pub connection_name: String,
+    pub command: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Tasks that must succeed before this one starts.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// A command started and stopped from the toolbar, with its own working directory
+/// and environment.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RunConfig {
+    pub name: String,
+    pub connection_name: String,
+    pub command: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: Vec<EnvVar>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardDirection {
+    /// Listens on the client and connects from the server, like `ssh -L`.
+    #[default]
+    Local,
+    /// Listens on the server and connects from the client, like `ssh -R`.
+    Remote,
+}
+
+/// A TCP port forwarded through one of the project's connections while it is
+/// connected.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortForward {
+    pub id: Uuid,
+    pub connection_name: String,
+    #[serde(default)]
+    pub direction: ForwardDirection,
+    /// Loopback port listened on, on the client for [`ForwardDirection::Local`] and on
+    /// the server for [`ForwardDirection::Remote`]; 0 picks a free one.
+    pub listen_port: u16,
+    /// Where accepted connections go, as seen from the other end.
+    pub host: String,
+    pub port: u16,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectFolder {
     pub name: String,
     pub source: FolderSource,
+    /// Terminal profile of the folder's connection used by "Open Terminal Here".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_profile: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum FolderSource {
     Local { path: String },
     Remote { connection_name: String, path: String },
 }
 
-#[derive(Debug, Clone)]
+impl FolderSource {
+    pub fn path(&self) -> &str {
+        match self {
+            FolderSource::Local { path } | FolderSource::Remote { path, .. } => path,
+        }
+    }
+
+    pub fn connection_name(&self) -> Option<&str> {
+        match self {
+            FolderSource::Local { .. } => None,
+            FolderSource::Remote { connection_name, .. } => Some(connection_name),
+        }
+    }
+}
+
+/// A file or directory pinned to the top of the explorer, local or on a
+/// connection.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PinnedPath {
+    pub source: FolderSource,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum EditorSource {
     Local,
     Remote { connection_name: String },
@@ -45,6 +563,18 @@ pub struct EditorTab {
     pub source: EditorSource,
     pub content: String,
     pub dirty: bool,
+    /// Hash of the remote file as last read or written, sent back on save so the
+    /// server can refuse to overwrite changes made by someone else.
+    pub remote_hash: Option<String>,
+    /// Remote content as last read or written, the common ancestor when a save
+    /// conflict is merged.
+    pub base: Option<String>,
+    pub cursor: usize,
+    pub restore_cursor: bool,
+    /// Replaced by the next file opened as a preview; cleared once edited.
+    pub preview: bool,
+    /// Set for read-only image and PDF tabs, which have no text content.
+    pub media: Option<MediaPreview>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,7 +583,44 @@ pub struct TerminalTab {
     pub connection_name: String,
     pub title: String,
     pub input: String,
+    pub blocks: Vec<TerminalBlock>,
+    pub profile: Option<TerminalProfile>,
+    pub container: Option<String>,
+    pub history: CommandHistory,
+}
+
+#[derive(Debug, Clone)]
+pub struct TerminalBlock {
+    pub id: Uuid,
+    pub command: String,
     pub output: String,
+    pub attachments: Vec<OutputAttachment>,
+    pub running: bool,
+    pub collapsed: bool,
+    pub rerunnable: bool,
+}
+
+impl TerminalBlock {
+    pub fn new(command: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            command,
+            output: String::new(),
+            attachments: Vec::new(),
+            running: true,
+            collapsed: false,
+            rerunnable: true,
+        }
+    }
+
+    pub fn finish(&mut self, output: &str, attachments: Vec<OutputAttachment>) {
+        self.output.push_str(output);
+        if !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.attachments.extend(attachments);
+        self.running = false;
+    }
 }
 
 pub fn default_connection_form_addr() -> String {
@@ -67,9 +634,48 @@ pub fn display_name_for_path(path: &str) -> String {
         .unwrap_or_else(|| path.to_string())
 }
 
-pub fn is_text_file(path: &str) -> bool {
-    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
-        Some(ext) => matches!(ext.to_ascii_lowercase().as_str(), "txt" | "py" | "m"),
-        None => false,
+/// Whether `path` is absolute on a Unix or a Windows host.
+pub fn is_absolute_path(path: &str) -> bool {
+    path.starts_with(['/', '\\'])
+        || matches!(path.as_bytes(), [drive, b':', ..] if drive.is_ascii_alphabetic())
+}
+
+/// Name and full path of every directory from the root down to `path`, for a
+/// breadcrumb bar. The root keeps its separator, as in `/` or `C:\`.
+pub fn path_crumbs(path: &str) -> Vec<(String, String)> {
+    let separator = if path.contains('\\') && !path.contains('/') { '\\' } else { '/' };
+    let mut crumbs = Vec::new();
+    let mut current = String::new();
+    for (index, part) in path.split(['/', '\\']).enumerate() {
+        if index == 0 {
+            // Empty before a Unix root, the drive on Windows.
+            current = format!("{part}{separator}");
+            crumbs.push((current.clone(), current.clone()));
+            continue;
+        }
+        if part.is_empty() {
+            continue;
+        }
+        if !current.ends_with(separator) {
+            current.push(separator);
+        }
+        current.push_str(part);
+        crumbs.push((part.to_string(), current.clone()));
     }
+    crumbs
+}
+
+pub fn is_text_file(path: &str, extensions: &[String]) -> bool {
+    let path = Path::new(path);
+    let Some(key) = path
+        .extension()
+        .or_else(|| path.file_name())
+        .and_then(|key| key.to_str())
+    else {
+        return false;
+    };
+    let key = key.trim_start_matches('.');
+    extensions
+        .iter()
+        .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(key))
 }
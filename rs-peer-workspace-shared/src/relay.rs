@@ -1,4 +1,7 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -12,42 +15,159 @@ pub enum AuthRole {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PeerToProxy {
     AuthProxy {
-        proxy_password: String,
+        /// `auth_proof` of the proxy password, so the password never crosses the wire.
+        proof: String,
         role: AuthRole,
+        #[serde(flatten)]
+        freshness: Freshness,
+    },
+    /// Client authentication with a proxy user account, used instead of `auth_proxy`
+    /// when the proxy has a users file. The proxy only stores an argon2 hash, so the
+    /// password itself is sent.
+    Login {
+        username: String,
+        password: String,
+        #[serde(flatten)]
+        freshness: Freshness,
     },
     RegisterServer {
         server_name: String,
         server_password: String,
+        #[serde(default)]
+        metadata: ServerMetadata,
+        #[serde(default)]
+        resume_token: Option<String>,
+    },
+    ListServers,
+    SetBusy {
+        busy: bool,
     },
     ConnectServer {
         server_name: String,
-        server_password: String,
+        /// `auth_proof` of the server password.
+        proof: String,
         use_p2p: bool,
+        #[serde(flatten)]
+        freshness: Freshness,
     },
     DisconnectSession {
         session_id: Uuid,
+        #[serde(default)]
+        reason: Option<String>,
     },
     Signal {
         session_id: Uuid,
         signal: SignalPayload,
+        #[serde(flatten)]
+        freshness: Freshness,
     },
     RelayData {
         session_id: Uuid,
         payload: Vec<u8>,
+        /// Set by the sender for streamed output the proxy may shed when the far side
+        /// falls behind; anything else (requests, responses) is delivered or the
+        /// session is closed.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        droppable: bool,
     },
 }
 
+impl PeerToProxy {
+    /// The frame's `type` tag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PeerToProxy::AuthProxy { .. } => "auth_proxy",
+            PeerToProxy::Login { .. } => "login",
+            PeerToProxy::RegisterServer { .. } => "register_server",
+            PeerToProxy::ListServers => "list_servers",
+            PeerToProxy::SetBusy { .. } => "set_busy",
+            PeerToProxy::ConnectServer { .. } => "connect_server",
+            PeerToProxy::DisconnectSession { .. } => "disconnect_session",
+            PeerToProxy::Signal { .. } => "signal",
+            PeerToProxy::RelayData { .. } => "relay_data",
+        }
+    }
+}
+
+/// Single-use nonce and Unix timestamp (seconds) that let the proxy reject stale or
+/// replayed frames.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Freshness {
+    #[serde(default)]
+    pub nonce: String,
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl Freshness {
+    pub fn new() -> Self {
+        Self {
+            nonce: Uuid::new_v4().to_string(),
+            timestamp: unix_now(),
+        }
+    }
+}
+
+/// Hex SHA-256 over the secret and the message's freshness, binding a password to a
+/// single nonce.
+pub fn auth_proof(secret: &str, freshness: &Freshness) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(secret.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(freshness.nonce.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(freshness.timestamp.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Whether `proof` is the [`auth_proof`] of `secret` for `freshness`. The comparison
+/// takes the same time wherever the first mismatch is, so it leaks nothing about how
+/// close a guess came.
+pub fn verify_proof(secret: &str, freshness: &Freshness, proof: &str) -> bool {
+    constant_time_eq(&auth_proof(secret, freshness), proof)
+}
+
+/// Compares two secrets without stopping at the first differing byte, so response
+/// timing does not reveal how much of a guess was right.
+pub fn constant_time_eq(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Protocol timestamps are Unix seconds in UTC; clients convert them to local time
+/// only for display.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ProxyToPeer {
     AuthOk {
         role: AuthRole,
+        /// Account the client logged in as; `None` after `auth_proxy`.
+        #[serde(default)]
+        username: Option<String>,
     },
     AuthError {
         reason: String,
     },
     Registered {
         server_name: String,
+        #[serde(default)]
+        resume_token: Option<String>,
+        #[serde(default)]
+        outcome: RegistrationOutcome,
+    },
+    ServersList {
+        servers: Vec<ServerInfo>,
     },
     ConnectionError {
         reason: String,
@@ -63,6 +183,9 @@ pub enum ProxyToPeer {
         peer_id: Uuid,
         via_p2p: bool,
         turn: Option<TurnCredentials>,
+        /// Proxy account of the joining client, when it logged in with one.
+        #[serde(default)]
+        username: Option<String>,
     },
     SessionClosed {
         session_id: Uuid,
@@ -76,9 +199,47 @@ pub enum ProxyToPeer {
     RelayData {
         session_id: Uuid,
         payload: Vec<u8>,
+        /// Carried over from the sender's [`PeerToProxy::RelayData`].
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        droppable: bool,
     },
 }
 
+/// How the proxy settled a `register_server`; `server_name` in `registered` is the
+/// name actually taken.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationOutcome {
+    #[default]
+    New,
+    /// The name was held for this server's resume token.
+    Resumed,
+    /// Another connection held the name and was disconnected.
+    Replaced,
+    /// The name was taken, so a numbered suffix was appended.
+    Suffixed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ServerMetadata {
+    #[serde(default)]
+    pub os: String,
+    #[serde(default)]
+    pub arch: String,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub agent_version: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub name: String,
+    pub metadata: ServerMetadata,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnCredentials {
     pub url: String,
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use regex::{Captures, Regex, RegexBuilder};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::app::{SearchFileMatches, SearchMatch, SearchQuery};
+use crate::content::content_hash;
+
+const MAX_MATCHES: usize = 5000;
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Walks `root` and returns the matches grouped by file, and whether the search
+/// stopped early at the match limit. Hidden entries, files larger than 4 MiB and
+/// files that are not UTF-8 text are skipped. Setting `cancel` stops the walk with
+/// an error.
+pub fn search_tree(
+    root: &str,
+    query: &SearchQuery,
+    replacement: Option<&str>,
+    cancel: Option<&AtomicBool>,
+) -> anyhow::Result<(Vec<SearchFileMatches>, bool)> {
+    let regex = build_regex(query)?;
+    if !Path::new(root).is_dir() {
+        anyhow::bail!("{root} is not a directory");
+    }
+    let mut files = Vec::new();
+    let mut total = 0;
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry));
+    for entry in walker.filter_map(Result::ok) {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            anyhow::bail!("search cancelled");
+        }
+        if !entry.file_type().is_file()
+            || entry.metadata().map_or(true, |metadata| metadata.len() > MAX_FILE_SIZE)
+        {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let mut matches = Vec::new();
+        for (index, line) in content.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            for captures in regex.captures_iter(line) {
+                let found = captures.get(0).expect("group 0 always matches");
+                if found.is_empty() {
+                    continue;
+                }
+                let preview = replacement.map(|replacement| {
+                    let mut preview = line[..found.start()].to_string();
+                    expand(query, &captures, replacement, &mut preview);
+                    preview.push_str(&line[found.end()..]);
+                    preview
+                });
+                matches.push(SearchMatch {
+                    line: index + 1,
+                    start: found.start(),
+                    end: found.end(),
+                    text: line.to_string(),
+                    preview,
+                });
+            }
+        }
+        if matches.is_empty() {
+            continue;
+        }
+        total += matches.len();
+        files.push(SearchFileMatches {
+            path: entry.path().to_string_lossy().to_string(),
+            hash: content_hash(content.as_bytes()),
+            matches,
+        });
+        if total >= MAX_MATCHES {
+            return Ok((files, true));
+        }
+    }
+    Ok((files, false))
+}
+
+
+pub fn build_regex(query: &SearchQuery) -> anyhow::Result<Regex> {
+    if query.pattern.is_empty() {
+        anyhow::bail!("search pattern is empty");
+    }
+    let pattern = if query.regex {
+        query.pattern.clone()
+    } else {
+        regex::escape(&query.pattern)
+    };
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive)
+        .build()?)
+}
+
+/// Regex replacements may refer to groups as `$1` or `${name}`; plain ones are literal.
+pub fn expand(query: &SearchQuery, captures: &Captures, replacement: &str, out: &mut String) {
+    if query.regex {
+        captures.expand(replacement, out);
+    } else {
+        out.push_str(replacement);
+    }
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
@@ -0,0 +1,199 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::app::EnvVar;
+use crate::project::ProjectFile;
+
+pub const PASSPHRASE_ENV: &str = "RS_PEER_PROJECT_PASSPHRASE";
+
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedCredentials {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConnectionSecret {
+    name: String,
+    proxy_password: String,
+    server_password: String,
+    #[serde(default)]
+    outbound_proxy_password: String,
+    /// Passwords of the connection's ICE servers, in order.
+    #[serde(default)]
+    ice_server_passwords: Vec<String>,
+    /// Values of the connection's secret environment variables, in order.
+    #[serde(default)]
+    env_secrets: Vec<String>,
+}
+
+/// Values of a run configuration's secret environment variables, in order.
+#[derive(Debug, Serialize, Deserialize)]
+struct RunConfigSecret {
+    name: String,
+    env_secrets: Vec<String>,
+}
+
+/// Plaintext of [`EncryptedCredentials::ciphertext`].
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedSecrets {
+    connections: Vec<ConnectionSecret>,
+    run_configs: Vec<RunConfigSecret>,
+}
+
+pub fn seal_credentials(project: &ProjectFile, passphrase: &str) -> anyhow::Result<ProjectFile> {
+    let connections: Vec<ConnectionSecret> = project
+        .connections
+        .iter()
+        .map(|connection| ConnectionSecret {
+            name: connection.name.clone(),
+            proxy_password: connection.proxy_password.clone(),
+            server_password: connection.server_password.clone(),
+            outbound_proxy_password: connection
+                .outbound_proxy
+                .as_ref()
+                .map(|proxy| proxy.password.clone())
+                .unwrap_or_default(),
+            ice_server_passwords: connection
+                .ice_servers
+                .iter()
+                .map(|server| server.password.clone())
+                .collect(),
+            env_secrets: secret_values(&connection.env),
+        })
+        .collect();
+    let run_configs = project
+        .run_configs
+        .iter()
+        .map(|config| RunConfigSecret {
+            name: config.name.clone(),
+            env_secrets: secret_values(&config.env),
+        })
+        .collect();
+    let secrets = SealedSecrets { connections, run_configs };
+
+    let params = Params::default();
+    let mut salt = [0u8; SALT_LEN];
+    getrandom(&mut salt)?;
+    let cipher = cipher(passphrase, &salt, &params)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, serde_json::to_vec(&secrets)?.as_slice())
+        .map_err(|_| anyhow::anyhow!("failed to encrypt credentials"))?;
+
+    let mut sealed = project.clone();
+    for connection in &mut sealed.connections {
+        connection.proxy_password.clear();
+        connection.server_password.clear();
+        if let Some(proxy) = &mut connection.outbound_proxy {
+            proxy.password.clear();
+        }
+        for server in &mut connection.ice_servers {
+            server.password.clear();
+        }
+        for var in connection.env.iter_mut().filter(|var| var.secret) {
+            var.value.clear();
+        }
+    }
+    for config in &mut sealed.run_configs {
+        for var in config.env.iter_mut().filter(|var| var.secret) {
+            var.value.clear();
+        }
+    }
+    sealed.encrypted_credentials = Some(EncryptedCredentials {
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+    });
+    Ok(sealed)
+}
+
+pub fn unseal_credentials(project: &mut ProjectFile, passphrase: &str) -> anyhow::Result<()> {
+    let Some(encrypted) = &project.encrypted_credentials else {
+        return Ok(());
+    };
+    // The costs come from the project file; anything above what `seal_credentials`
+    // writes could only make opening a project take unbounded memory and time.
+    if encrypted.m_cost > Params::DEFAULT_M_COST
+        || encrypted.t_cost > Params::DEFAULT_T_COST
+        || encrypted.p_cost > Params::DEFAULT_P_COST
+    {
+        anyhow::bail!(
+            "key derivation parameters exceed m_cost {}, t_cost {}, p_cost {}",
+            Params::DEFAULT_M_COST,
+            Params::DEFAULT_T_COST,
+            Params::DEFAULT_P_COST
+        );
+    }
+    let params = Params::new(encrypted.m_cost, encrypted.t_cost, encrypted.p_cost, None)
+        .map_err(|err| anyhow::anyhow!("invalid key derivation parameters: {err}"))?;
+    let salt = STANDARD.decode(&encrypted.salt)?;
+    let nonce = STANDARD.decode(&encrypted.nonce)?;
+    if nonce.len() != 12 {
+        anyhow::bail!("invalid credentials nonce");
+    }
+    let ciphertext = STANDARD.decode(&encrypted.ciphertext)?;
+    let plaintext = cipher(passphrase, &salt, &params)?
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow::anyhow!("wrong passphrase or corrupted credentials"))?;
+    let SealedSecrets { connections, run_configs } = serde_json::from_slice(&plaintext)?;
+
+    for connection in &mut project.connections {
+        if let Some(secret) = connections.iter().find(|secret| secret.name == connection.name) {
+            connection.proxy_password = secret.proxy_password.clone();
+            connection.server_password = secret.server_password.clone();
+            if let Some(proxy) = &mut connection.outbound_proxy {
+                proxy.password = secret.outbound_proxy_password.clone();
+            }
+            for (server, password) in connection.ice_servers.iter_mut().zip(&secret.ice_server_passwords) {
+                server.password = password.clone();
+            }
+            restore_secret_values(&mut connection.env, &secret.env_secrets);
+        }
+    }
+    for config in &mut project.run_configs {
+        if let Some(secret) = run_configs.iter().find(|secret| secret.name == config.name) {
+            restore_secret_values(&mut config.env, &secret.env_secrets);
+        }
+    }
+    project.encrypted_credentials = None;
+    Ok(())
+}
+
+fn secret_values(env: &[EnvVar]) -> Vec<String> {
+    env.iter().filter(|var| var.secret).map(|var| var.value.clone()).collect()
+}
+
+fn restore_secret_values(env: &mut [EnvVar], values: &[String]) {
+    for (var, value) in env.iter_mut().filter(|var| var.secret).zip(values) {
+        var.value = value.clone();
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8], params: &Params) -> anyhow::Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow::anyhow!("failed to derive key: {err}"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+pub(crate) fn getrandom(buffer: &mut [u8]) -> anyhow::Result<()> {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    OsRng
+        .try_fill_bytes(buffer)
+        .map_err(|err| anyhow::anyhow!("failed to generate random bytes: {err}"))
+}
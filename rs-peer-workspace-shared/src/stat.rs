@@ -0,0 +1,175 @@
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::app::{DiskSpace, EntryStat};
+
+/// Reads the metadata of `path`, reporting a symlink's target rather than following
+/// it silently.
+pub fn stat_entry(path: &str) -> std::io::Result<EntryStat> {
+    let link = fs::symlink_metadata(path)?;
+    let symlink_target = if link.file_type().is_symlink() {
+        Some(fs::read_link(path)?.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    let metadata = if symlink_target.is_some() {
+        fs::metadata(path).unwrap_or(link)
+    } else {
+        link
+    };
+    Ok(EntryStat {
+        path: path.to_string(),
+        is_dir: metadata.is_dir(),
+        size: metadata.len(),
+        modified: modified_secs(&metadata),
+        permissions: permissions(&metadata),
+        mode: mode(&metadata),
+        readonly: metadata.permissions().readonly(),
+        owner: owner(&metadata),
+        symlink_target,
+        disk: disk_space(Path::new(path)).ok(),
+    })
+}
+
+/// Space on the file system that holds `path`, or would hold it once created.
+pub fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(path);
+    query_disk_space(existing)
+}
+
+/// Fails with [`io::ErrorKind::StorageFull`] when writing `needed` more bytes at
+/// `path` would not fit. If the free space cannot be determined the write is let
+/// through and fails on its own.
+pub fn ensure_space(path: &Path, needed: u64) -> io::Result<()> {
+    let Ok(space) = disk_space(path) else {
+        return Ok(());
+    };
+    if needed > space.available {
+        return Err(io::Error::new(
+            io::ErrorKind::StorageFull,
+            format!(
+                "not enough space for {}: {needed} bytes needed, {} bytes available",
+                path.display(),
+                space.available
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn query_disk_space(path: &Path) -> io::Result<DiskSpace> {
+    let stat = rustix::fs::statvfs(path)?;
+    Ok(DiskSpace {
+        total: stat.f_blocks.saturating_mul(stat.f_frsize),
+        available: stat.f_bavail.saturating_mul(stat.f_frsize),
+    })
+}
+
+#[cfg(windows)]
+fn query_disk_space(path: &Path) -> io::Result<DiskSpace> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0u64;
+    let mut total = 0u64;
+    // SAFETY: `wide` is NUL-terminated and the out pointers are valid for the call.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, std::ptr::null_mut())
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(DiskSpace { total, available })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn query_disk_space(_path: &Path) -> io::Result<DiskSpace> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Applies `mode` to `path` (only its write bits on platforms without Unix modes)
+/// and returns the updated metadata.
+pub fn set_permissions(path: &str, mode: u32) -> std::io::Result<EntryStat> {
+    fs::set_permissions(path, permissions_for(path, mode)?)?;
+    stat_entry(path)
+}
+
+#[cfg(unix)]
+fn permissions_for(_path: &str, mode: u32) -> std::io::Result<fs::Permissions> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn permissions_for(path: &str, mode: u32) -> std::io::Result<fs::Permissions> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(mode & 0o222 == 0);
+    Ok(permissions)
+}
+
+pub fn modified_secs(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = metadata.permissions().mode();
+    let mut text = String::with_capacity(9);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    text
+}
+
+#[cfg(not(unix))]
+fn permissions(metadata: &Metadata) -> String {
+    if metadata.permissions().readonly() {
+        "read-only".to_string()
+    } else {
+        "read-write".to_string()
+    }
+}
+
+/// The owning user's name from `/etc/passwd`, or the numeric uid if it has none.
+#[cfg(unix)]
+fn owner(metadata: &Metadata) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    let uid = metadata.uid();
+    let name = fs::read_to_string("/etc/passwd").ok().and_then(|passwd| {
+        passwd.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let entry_uid: u32 = fields.nth(1)?.parse().ok()?;
+            (entry_uid == uid).then(|| name.to_string())
+        })
+    });
+    Some(name.unwrap_or_else(|| uid.to_string()))
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &Metadata) -> Option<String> {
+    None
+}
@@ -0,0 +1,27 @@
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::crash::LogTail;
+
+pub const LOG_FORMAT_ENV: &str = "RS_PEER_LOG_FORMAT";
+
+pub fn init(default_filter: &str, json: bool) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let json = json
+        || std::env::var(LOG_FORMAT_ENV)
+            .map(|format| format.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_writer(std::io::stderr.and(LogTail));
+    let result = if json {
+        builder.json().flatten_event(true).with_current_span(true).try_init()
+    } else {
+        builder.try_init()
+    };
+    if let Err(err) = result {
+        eprintln!("failed to initialize tracing: {err}");
+    }
+}
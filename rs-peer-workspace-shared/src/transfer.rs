@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::app::ChunkRange;
+
+/// Verified chunks at the end of a transfer's verified prefix that are checksummed
+/// again on both sides before it resumes. Earlier chunks are only kept as a length.
+pub const RESUME_CHECK_CHUNKS: usize = 4;
+
+/// A chunk whose checksum the receiving side confirmed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedChunk {
+    pub offset: u64,
+    pub length: u64,
+    /// Hex SHA-256 of the chunk's bytes.
+    pub checksum: String,
+}
+
+impl VerifiedChunk {
+    pub fn range(&self) -> ChunkRange {
+        ChunkRange {
+            offset: self.offset,
+            length: self.length,
+        }
+    }
+}
+
+/// The verified chunks of an upload or download, from which it resumes after the
+/// connection drops.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkLog {
+    /// Length of the verified prefix that is no longer kept chunk by chunk.
+    #[serde(default)]
+    settled: u64,
+    /// Verified chunks from `settled` on, by offset. Chunks after a gap completed
+    /// while an earlier one was still in flight.
+    #[serde(default)]
+    chunks: Vec<VerifiedChunk>,
+}
+
+impl ChunkLog {
+    pub fn record(&mut self, chunk: VerifiedChunk) {
+        if chunk.offset < self.settled {
+            return;
+        }
+        let index = self.chunks.partition_point(|known| known.offset < chunk.offset);
+        match self.chunks.get_mut(index) {
+            Some(known) if known.offset == chunk.offset => *known = chunk,
+            _ => self.chunks.insert(index, chunk),
+        }
+        let settle = self.contiguous().saturating_sub(RESUME_CHECK_CHUNKS);
+        for chunk in self.chunks.drain(..settle) {
+            self.settled += chunk.length;
+        }
+    }
+
+    /// Bytes from the start of the file that are verified without a gap. A resumed
+    /// transfer continues from here.
+    pub fn verified_len(&self) -> u64 {
+        self.settled + self.resume_checks().iter().map(|chunk| chunk.length).sum::<u64>()
+    }
+
+    /// The last verified chunks before [`ChunkLog::verified_len`], which are checked
+    /// again before resuming.
+    pub fn resume_checks(&self) -> &[VerifiedChunk] {
+        &self.chunks[..self.contiguous()]
+    }
+
+    /// Decides where to resume from the checksums of [`ChunkLog::resume_checks`] on
+    /// the source and the destination. A source that changed restarts the transfer;
+    /// a destination that no longer matches rewinds it to the first bad chunk.
+    /// Returns the offset to resume from.
+    pub fn resume(&mut self, source: &[Option<String>], destination: &[Option<String>]) -> u64 {
+        let checks = self.resume_checks();
+        let matches = |checksums: &[Option<String>], index: usize, chunk: &VerifiedChunk| {
+            checksums.get(index).and_then(Option::as_deref) == Some(chunk.checksum.as_str())
+        };
+        if checks
+            .iter()
+            .enumerate()
+            .any(|(index, chunk)| !matches(source, index, chunk))
+        {
+            self.reset();
+            return 0;
+        }
+        let kept = checks
+            .iter()
+            .enumerate()
+            .take_while(|(index, chunk)| matches(destination, *index, chunk))
+            .count();
+        self.chunks.truncate(kept);
+        self.verified_len()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Number of chunks from `settled` on that follow each other without a gap.
+    fn contiguous(&self) -> usize {
+        let mut end = self.settled;
+        self.chunks
+            .iter()
+            .take_while(|chunk| {
+                let next = chunk.offset == end;
+                end += chunk.length;
+                next
+            })
+            .count()
+    }
+}
+
+/// Checksums `ranges` of the file at `path`, with `None` for ranges that run past
+/// its end, along with the file's size.
+pub fn range_checksums(path: &Path, ranges: &[ChunkRange]) -> io::Result<(Vec<Option<String>>, u64)> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let checksums = ranges
+        .iter()
+        .map(|range| {
+            if range.offset.checked_add(range.length).is_none_or(|end| end > size) {
+                return Ok(None);
+            }
+            file.seek(SeekFrom::Start(range.offset))?;
+            let mut hasher = Sha256::new();
+            let mut remaining = range.length;
+            while remaining > 0 {
+                let want = remaining.min(buffer.len() as u64) as usize;
+                file.read_exact(&mut buffer[..want])?;
+                hasher.update(&buffer[..want]);
+                remaining -= want as u64;
+            }
+            Ok(Some(format!("{:x}", hasher.finalize())))
+        })
+        .collect::<io::Result<_>>()?;
+    Ok((checksums, size))
+}
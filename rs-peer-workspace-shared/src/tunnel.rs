@@ -0,0 +1,42 @@
+//! Helpers shared by both ends of a port forwarding tunnel.
+//!
+//! Tunnel chunks travel as RPC messages, which the server handles concurrently and
+//! which may switch between the relay and the data channel mid-stream, so each
+//! chunk carries a sequence number and [`InOrder`] puts them back in order.
+
+use std::collections::BTreeMap;
+
+/// Bytes read from a tunnel socket per message.
+pub const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Releases numbered chunks in sequence order, holding back any that arrive early.
+#[derive(Debug)]
+pub struct InOrder<T> {
+    next: u64,
+    pending: BTreeMap<u64, T>,
+}
+
+impl<T> Default for InOrder<T> {
+    fn default() -> Self {
+        Self {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> InOrder<T> {
+    /// Adds chunk `seq` and returns the chunks now ready, in order. Chunks already
+    /// released are ignored.
+    pub fn push(&mut self, seq: u64, item: T) -> Vec<T> {
+        if seq >= self.next {
+            self.pending.insert(seq, item);
+        }
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        ready
+    }
+}
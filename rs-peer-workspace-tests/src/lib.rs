@@ -0,0 +1,352 @@
+//! End-to-end harness that runs the real proxy and server binaries on loopback.
+//!
+//! [`TestProxy`] starts the proxy on an ephemeral port with the admin API enabled,
+//! [`TestServer`] registers a server whose working directory is a fresh temp root,
+//! and clients talk to them through [`rs_peer_workspace_core::Connection`]. Both
+//! processes are killed when their handles are dropped.
+
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use rs_peer_workspace_shared::project::{OutboundProxy, OutboundProxyKind, ProjectConnection};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+pub const PROXY_PASSWORD: &str = "harness-proxy-secret";
+pub const SERVER_PASSWORD: &str = "harness-server-secret";
+pub const ADMIN_TOKEN: &str = "harness-admin-token";
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+
+pub struct TestProxy {
+    child: Child,
+    port: u16,
+}
+
+impl TestProxy {
+    /// Starts a proxy that advertises an unreachable TURN endpoint, so clients that
+    /// prefer P2P have to fall back to the WebSocket relay.
+    pub async fn start() -> anyhow::Result<Self> {
+        Self::start_with_args(&[]).await
+    }
+
+    /// Like [`TestProxy::start`], passing `args` on to the proxy binary.
+    pub async fn start_with_args(args: &[&str]) -> anyhow::Result<Self> {
+        let port = free_port()?;
+        let turn_port = free_port()?;
+        let child = Command::new(binary("rs-peer-workspace-proxy")?)
+            .args(["--bind", &format!("127.0.0.1:{port}")])
+            .args(["--proxy-password", PROXY_PASSWORD])
+            .args(["--admin-token", ADMIN_TOKEN])
+            .args(["--turn-url", &format!("turn:127.0.0.1:{turn_port}")])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to start proxy")?;
+        let proxy = Self { child, port };
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while std::net::TcpStream::connect(("127.0.0.1", port)).is_err() {
+            if Instant::now() > deadline {
+                anyhow::bail!("proxy did not start listening on port {port}");
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(proxy)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://127.0.0.1:{}/ws", self.port)
+    }
+
+    pub fn http_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// Fetches an admin endpoint such as `servers` or `sessions`.
+    pub async fn admin(&self, endpoint: &str) -> anyhow::Result<serde_json::Value> {
+        let response = reqwest::Client::new()
+            .get(format!("{}/admin/{endpoint}", self.http_url()))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+
+    /// Polls an admin endpoint until it lists exactly `count` entries.
+    pub async fn wait_for_count(&self, endpoint: &str, count: usize) -> anyhow::Result<()> {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let listed = self
+                .admin(endpoint)
+                .await?
+                .as_array()
+                .map_or(0, |items| items.len());
+            if listed == count {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                anyhow::bail!("/admin/{endpoint} lists {listed} entries, expected {count}");
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Project connection to `server_name` with the harness passwords.
+    pub fn connection(&self, server_name: &str, prefer_p2p: bool) -> ProjectConnection {
+        ProjectConnection {
+            name: server_name.to_string(),
+            proxy_addr: self.ws_url(),
+            proxy_username: String::new(),
+            proxy_password: PROXY_PASSWORD.to_string(),
+            server_name: server_name.to_string(),
+            server_password: SERVER_PASSWORD.to_string(),
+            prefer_p2p,
+            require_e2e: false,
+            credential_ref: None,
+            server_fingerprint: None,
+            terminal_profiles: Vec::new(),
+            outbound_proxy: None,
+            ice_servers: Vec::new(),
+            env: Vec::new(),
+        }
+    }
+}
+
+impl Drop for TestProxy {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub struct TestServer {
+    child: Child,
+    name: String,
+    root: PathBuf,
+}
+
+impl TestServer {
+    /// Starts a server registered under a random name and waits until the proxy lists it.
+    pub async fn start(proxy: &TestProxy) -> anyhow::Result<Self> {
+        Self::start_with_args(proxy, &[]).await
+    }
+
+    /// Like [`TestServer::start`], passing `args` on to the server binary.
+    pub async fn start_with_args(proxy: &TestProxy, args: &[&str]) -> anyhow::Result<Self> {
+        let name = format!("harness-{}", Uuid::new_v4());
+        let root = std::env::temp_dir().join(&name);
+        std::fs::create_dir_all(&root)?;
+        let child = Command::new(binary("rs-peer-workspace-server")?)
+            .current_dir(&root)
+            .args(["--proxy-url", &proxy.ws_url()])
+            .args(["--proxy-password", PROXY_PASSWORD])
+            .args(["--server-name", &name])
+            .args(["--server-password", SERVER_PASSWORD])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to start server")?;
+        let server = Self { child, name, root };
+        proxy.wait_for_count("servers", 1).await?;
+        Ok(server)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Temporary directory the server runs in; removed on drop.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.kill();
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Loopback HTTP `CONNECT` or SOCKS5 proxy that counts the tunnels it opens, standing
+/// in for the egress proxy of a corporate network.
+pub struct TestTunnel {
+    kind: OutboundProxyKind,
+    addr: SocketAddr,
+    tunnels: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+}
+
+impl TestTunnel {
+    pub async fn start(kind: OutboundProxyKind) -> anyhow::Result<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let tunnels = Arc::new(AtomicUsize::new(0));
+        let counter = tunnels.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((client, _)) = listener.accept().await {
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    if let Ok(target) = open_tunnel(kind, client).await {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        let (mut client, mut target) = target;
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut target).await;
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            kind,
+            addr,
+            tunnels,
+            task,
+        })
+    }
+
+    pub fn outbound_proxy(&self) -> OutboundProxy {
+        OutboundProxy {
+            kind: self.kind,
+            addr: self.addr.to_string(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+
+    pub fn tunnels(&self) -> usize {
+        self.tunnels.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for TestTunnel {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn open_tunnel(
+    kind: OutboundProxyKind,
+    mut client: TcpStream,
+) -> anyhow::Result<(TcpStream, TcpStream)> {
+    match kind {
+        OutboundProxyKind::Http => {
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(client.read_u8().await?);
+            }
+            let head = String::from_utf8(head)?;
+            let target = head
+                .strip_prefix("CONNECT ")
+                .and_then(|rest| rest.split_whitespace().next())
+                .context("expected a CONNECT request")?;
+            let target = TcpStream::connect(target).await?;
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await?;
+            Ok((client, target))
+        }
+        OutboundProxyKind::Socks5 => {
+            let mut greeting = [0u8; 2];
+            client.read_exact(&mut greeting).await?;
+            let mut methods = vec![0u8; greeting[1] as usize];
+            client.read_exact(&mut methods).await?;
+            client.write_all(&[0x05, 0x00]).await?;
+            let mut request = [0u8; 4];
+            client.read_exact(&mut request).await?;
+            let host = match request[3] {
+                0x01 => {
+                    let mut ip = [0u8; 4];
+                    client.read_exact(&mut ip).await?;
+                    std::net::Ipv4Addr::from(ip).to_string()
+                }
+                0x03 => {
+                    let mut name = vec![0u8; client.read_u8().await? as usize];
+                    client.read_exact(&mut name).await?;
+                    String::from_utf8(name)?
+                }
+                other => anyhow::bail!("unsupported address type {other}"),
+            };
+            let port = client.read_u16().await?;
+            let target = TcpStream::connect((host.as_str(), port)).await?;
+            client
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await?;
+            Ok((client, target))
+        }
+    }
+}
+
+/// Runs `future`, failing instead of hanging when the stack under test stalls.
+pub async fn within<T>(future: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    tokio::time::timeout(STARTUP_TIMEOUT, future)
+        .await
+        .context("timed out")?
+}
+
+fn free_port() -> anyhow::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+/// Path to a workspace binary, building the workspace binaries once per test process.
+///
+/// The whole workspace is built so the feature set matches `cargo build --workspace`
+/// and an up-to-date tree is not rebuilt.
+fn binary(name: &str) -> anyhow::Result<PathBuf> {
+    static BUILT: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    let dir = BUILT
+        .get_or_init(|| build_binaries().map_err(|err| format!("{err:#}")))
+        .as_ref()
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    Ok(dir.join(format!("{name}{}", std::env::consts::EXE_SUFFIX)))
+}
+
+fn build_binaries() -> anyhow::Result<PathBuf> {
+    let profile_dir = std::env::current_exe()?
+        .parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .context("test executable is not inside a cargo target directory")?;
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.toml");
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command
+        .args(["build", "--quiet", "--manifest-path"])
+        .arg(&manifest)
+        .args(["--workspace", "--bins"]);
+    // Cargo exports package variables to test processes; build scripts that watch them
+    // (ring, for one) would otherwise rebuild on every nested run.
+    for (key, _) in std::env::vars() {
+        if key.starts_with("CARGO_PKG_")
+            || key.starts_with("CARGO_MANIFEST_")
+            || key.starts_with("CARGO_CRATE_")
+            || key == "CARGO_PRIMARY_PACKAGE"
+        {
+            command.env_remove(key);
+        }
+    }
+    if profile_dir.ends_with("release") {
+        command.arg("--release");
+    }
+    if let Some(target_dir) = profile_dir.parent() {
+        command.arg("--target-dir").arg(target_dir);
+    }
+    let status = command.status().context("failed to run cargo build")?;
+    if !status.success() {
+        anyhow::bail!("cargo build of workspace binaries failed with {status}");
+    }
+    Ok(profile_dir)
+}
@@ -0,0 +1,50 @@
+use rs_peer_workspace_shared::cache::ContentCache;
+use rs_peer_workspace_shared::content::content_hash;
+use uuid::Uuid;
+
+#[test]
+fn caches_contents_by_connection_and_path() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("content-cache-{}", Uuid::new_v4()));
+    let cache = ContentCache::new(dir.clone(), 1024 * 1024);
+    let hash = content_hash(b"fn main() {}\n");
+    cache.put("alpha", "/src/main.rs", &hash, "fn main() {}\n")?;
+
+    assert_eq!(
+        cache.get("alpha", "/src/main.rs"),
+        Some((hash.clone(), "fn main() {}\n".to_string()))
+    );
+    assert_eq!(cache.get("beta", "/src/main.rs"), None);
+    assert_eq!(cache.get("alpha", "/src/lib.rs"), None);
+
+    cache.remove("alpha", "/src/main.rs");
+    assert_eq!(cache.get("alpha", "/src/main.rs"), None);
+
+    cache.put("alpha", "/src/main.rs", &hash, "tampered\n")?;
+    assert_eq!(cache.get("alpha", "/src/main.rs"), None);
+    assert_eq!(cache.size(), 0);
+
+    cache.clear()?;
+    assert!(!dir.exists());
+    Ok(())
+}
+
+#[test]
+fn evicts_oldest_entries_over_the_limit() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("content-cache-{}", Uuid::new_v4()));
+    let content = "x".repeat(400);
+    let hash = content_hash(content.as_bytes());
+    let cache = ContentCache::new(dir.clone(), 1200);
+    for path in ["/a", "/b", "/c", "/d"] {
+        cache.put("alpha", path, &hash, &content)?;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    assert!(cache.size() <= 1200);
+    assert_eq!(cache.get("alpha", "/a"), None);
+    assert!(cache.get("alpha", "/d").is_some());
+
+    let disabled = ContentCache::new(dir.clone(), 0);
+    assert_eq!(disabled.get("alpha", "/d"), None);
+    cache.clear()?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use rs_peer_workspace_shared::app::Capabilities;
+use rs_peer_workspace_shared::compression::{self, Codec, MAX_DECOMPRESSED_LEN, MIN_COMPRESS_LEN};
+
+fn repetitive(len: usize) -> Vec<u8> {
+    b"{\"line\":\"the same text again\"}\n".iter().copied().cycle().take(len).collect()
+}
+
+#[test]
+fn round_trips_both_codecs() -> anyhow::Result<()> {
+    let payload = repetitive(64 * 1024);
+    for codec in [Codec::Zstd, Codec::Deflate] {
+        let compressed = compression::compress(payload.clone(), codec);
+        assert!(compressed.len() < payload.len() / 10, "{codec:?} left {} bytes", compressed.len());
+        assert_eq!(compression::decompress(compressed)?, payload);
+    }
+    Ok(())
+}
+
+#[test]
+fn passes_small_and_plain_payloads_through() -> anyhow::Result<()> {
+    let small = repetitive(MIN_COMPRESS_LEN - 1);
+    assert_eq!(compression::compress(small.clone(), Codec::Zstd), small);
+    assert_eq!(compression::compress(small.clone(), Codec::Deflate), small);
+
+    let json = br#"{"message_id":"00000000-0000-0000-0000-000000000000"}"#.to_vec();
+    assert_eq!(compression::decompress(json.clone())?, json);
+
+    // Random bytes do not get smaller, so they are sent as they are.
+    let mut noise = vec![0u8; 4096];
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for byte in &mut noise {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        *byte = state as u8;
+    }
+    assert_eq!(compression::compress(noise.clone(), Codec::Deflate), noise);
+    Ok(())
+}
+
+#[test]
+fn prefers_zstd_when_negotiating() {
+    let mut capabilities = Capabilities::supported();
+    assert_eq!(Codec::for_peer(&capabilities), Some(Codec::Zstd));
+    capabilities.zstd = false;
+    assert_eq!(Codec::for_peer(&capabilities), Some(Codec::Deflate));
+    assert_eq!(Codec::for_peer(&Capabilities::default()), None);
+
+    // Peers from before deflate existed only send the zstd flag.
+    let old: Capabilities = serde_json::from_str(r#"{"zstd":true}"#).expect("capabilities parse");
+    assert!(!old.deflate);
+}
+
+#[test]
+fn refuses_payloads_that_expand_past_the_limit() {
+    let bomb = vec![0u8; MAX_DECOMPRESSED_LEN as usize + 1];
+    for codec in [Codec::Zstd, Codec::Deflate] {
+        let compressed = compression::compress(bomb.clone(), codec);
+        assert!(compressed.len() < 1024 * 1024);
+        assert!(compression::decompress(compressed).is_err(), "{codec:?} expanded past the limit");
+    }
+}
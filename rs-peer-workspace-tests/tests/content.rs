@@ -0,0 +1,63 @@
+use rs_peer_workspace_shared::content::{
+    hex_dump, read_file_data, read_range, FileData, BINARY_PREVIEW_LEN, MAX_TEXT_LEN,
+};
+
+#[test]
+fn read_file_data_sniffs_binary_content() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("rpw-content-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)?;
+
+    std::fs::write(dir.join("main.rs"), "fn main() {}\n")?;
+    assert_eq!(
+        read_file_data(&dir.join("main.rs"), MAX_TEXT_LEN)?,
+        FileData::Text("fn main() {}\n".to_string())
+    );
+
+    let mut image = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    image.resize(BINARY_PREVIEW_LEN * 2, 0xff);
+    std::fs::write(dir.join("image.png"), &image)?;
+    let FileData::Binary { size, preview } = read_file_data(&dir.join("image.png"), MAX_TEXT_LEN)?
+    else {
+        panic!("expected binary data");
+    };
+    assert_eq!(size, image.len() as u64);
+    assert_eq!(preview, image[..BINARY_PREVIEW_LEN]);
+
+    std::fs::write(dir.join("latin1.txt"), b"caf\xe9")?;
+    assert!(matches!(
+        read_file_data(&dir.join("latin1.txt"), MAX_TEXT_LEN)?,
+        FileData::Binary { size: 4, .. }
+    ));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn large_text_files_are_read_in_ranges() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("rpw-content-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("app.log");
+    std::fs::write(&path, "line 1\nline 2\nline 3\n")?;
+
+    assert_eq!(read_file_data(&path, 10)?, FileData::TooLarge { size: 21 });
+    assert_eq!(read_range(&path, 7, 7)?, (b"line 2\n".to_vec(), 21));
+    assert_eq!(read_range(&path, 14, 100)?, (b"line 3\n".to_vec(), 21));
+    assert_eq!(read_range(&path, 50, 10)?, (Vec::new(), 21));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn hex_dump_shows_offsets_bytes_and_ascii() {
+    let dump = hex_dump(b"Hello, world!\n\0\x01abc");
+    let lines: Vec<_> = dump.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|",
+            "00000010  61 62 63                                          |abc|",
+        ]
+    );
+}
@@ -0,0 +1,29 @@
+use std::io::Write;
+
+use rs_peer_workspace_shared::crash::{self, LogTail};
+use uuid::Uuid;
+
+#[test]
+fn writes_and_acknowledges_crash_reports() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("crash-{}", Uuid::new_v4()));
+    LogTail.write_all(b"\x1b[32m INFO\x1b[0m connected to proxy\n")?;
+    crash::install("crash-test", "1.2.3", dir.clone());
+
+    let result = std::thread::spawn(|| panic!("deliberate test panic")).join();
+    assert!(result.is_err());
+
+    let reports = crash::pending_reports(&dir);
+    assert_eq!(reports.len(), 1);
+    let report = std::fs::read_to_string(&reports[0])?;
+    assert!(report.starts_with("crash-test 1.2.3 crashed\n"), "{report}");
+    assert!(report.contains("panic: deliberate test panic\n"), "{report}");
+    assert!(report.contains("location: "), "{report}");
+    assert!(report.contains("backtrace:\n"), "{report}");
+    assert!(report.contains("last log lines:\n INFO connected to proxy\n"), "{report}");
+
+    let seen = crash::acknowledge(&reports[0])?;
+    assert!(seen.is_file());
+    assert!(crash::pending_reports(&dir).is_empty());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use rs_peer_workspace_shared::diff::{DiffOp, TextDiff};
+
+#[test]
+fn identical_texts_have_no_hunks() {
+    let diff = TextDiff::new("a\nb\n", "a\nb\n");
+    assert!(diff.is_identical());
+    assert_eq!(diff.merge(|_| true), "a\nb\n");
+}
+
+#[test]
+fn groups_changes_into_hunks() {
+    let local = "one\ntwo\nthree\nfour\nfive\n";
+    let remote = "one\n2\nthree\nfour\nfive\nsix\n";
+    let diff = TextDiff::new(local, remote);
+    assert_eq!(diff.hunk_count(), 2);
+    assert_eq!(
+        diff.ops[1],
+        DiffOp::Change {
+            local: vec!["two\n".to_string()],
+            remote: vec!["2\n".to_string()],
+        }
+    );
+}
+
+#[test]
+fn merges_selected_hunks() {
+    let local = "a\nb\nc\nd\ne\n";
+    let remote = "a\nB\nc\nd\nE\nf\n";
+    let diff = TextDiff::new(local, remote);
+    assert_eq!(diff.merge(|_| false), local);
+    assert_eq!(diff.merge(|_| true), remote);
+    assert_eq!(diff.merge(|hunk| hunk == 0), "a\nB\nc\nd\ne\n");
+}
+
+#[test]
+fn handles_interleaved_insertions_and_deletions() {
+    let local = "x\na\nb\nc\ny\nz";
+    let remote = "a\nq\nb\ny\nw\nz";
+    let diff = TextDiff::new(local, remote);
+    assert_eq!(diff.merge(|_| false), local);
+    assert_eq!(diff.merge(|_| true), remote);
+    assert!(diff
+        .ops
+        .iter()
+        .all(|op| !matches!(op, DiffOp::Change { local, remote } if local.is_empty() && remote.is_empty())));
+}
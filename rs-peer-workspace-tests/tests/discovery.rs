@@ -0,0 +1,61 @@
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use rs_peer_workspace_shared::discovery::{
+    parse_query, parse_response, query, response, Announcement, ServiceQuery,
+};
+
+#[test]
+fn announcements_round_trip() {
+    assert_eq!(
+        parse_query(&query()),
+        Some(ServiceQuery {
+            id: 0,
+            unicast: true
+        })
+    );
+
+    let addr = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 20), 9000);
+    let reply = response(0x1234, true, "build.box", addr);
+    assert_eq!(&reply[..2], &[0x12, 0x34]);
+    assert_eq!(
+        parse_response(&reply),
+        vec![Announcement {
+            name: "build-box".to_string(),
+            url: "ws://192.168.1.20:9000/ws".to_string(),
+        }]
+    );
+
+    // Responses are not queries, and queries announce nothing.
+    assert_eq!(parse_query(&reply), None);
+    assert!(parse_response(&query()).is_empty());
+    assert!(parse_response(&[0x00, 0x00, 0x84]).is_empty());
+}
+
+#[test]
+fn announcements_with_compressed_names_are_read() {
+    let mut packet = vec![0, 0, 0x84, 0x00, 0, 0, 0, 2, 0, 0, 0, 0];
+    // PTR _rs-peer-ws._tcp.local -> lab._rs-peer-ws._tcp.local
+    let service_at = packet.len() as u8;
+    for label in ["_rs-peer-ws", "_tcp", "local"] {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&[0, 12, 0, 1, 0, 0, 0, 120, 0, 6]);
+    let instance_at = packet.len() as u8;
+    packet.extend_from_slice(&[3, b'l', b'a', b'b', 0xC0, service_at]);
+    // TXT whose owner name points back into the PTR data.
+    let entry = b"url=ws://10.0.0.5:9000/ws";
+    packet.extend_from_slice(&[0xC0, instance_at, 0, 16, 0x80, 1, 0, 0, 0, 120]);
+    packet.extend_from_slice(&((entry.len() + 1) as u16).to_be_bytes());
+    packet.push(entry.len() as u8);
+    packet.extend_from_slice(entry);
+
+    assert_eq!(
+        parse_response(&packet),
+        vec![Announcement {
+            name: "lab".to_string(),
+            url: "ws://10.0.0.5:9000/ws".to_string(),
+        }]
+    );
+}
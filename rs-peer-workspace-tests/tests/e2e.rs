@@ -0,0 +1,2202 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use argon2::password_hash::{PasswordHasher, SaltString};
+use argon2::Argon2;
+use futures_util::{SinkExt, StreamExt};
+use rs_peer_workspace_core::{
+    test_connection, Connection, ConnectionEvent, ConnectionTest, ForwardStatus, Workspace,
+};
+use rs_peer_workspace_shared::app::{
+    AppEnvelope, AppPayload, Capabilities, ChunkRange, EnvVar, ListFilter, OutputAttachment, ReplaceFile, RpcAction,
+    RpcRequest, RpcResult, SearchQuery,
+};
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::format::Formatter;
+use rs_peer_workspace_shared::identity::IdentityChallenge;
+use rs_peer_workspace_shared::lsp;
+use rs_peer_workspace_shared::project::{
+    ForwardDirection, OutboundProxyKind, PortForward, ProjectFile, TerminalProfile,
+};
+use rs_peer_workspace_shared::relay::{
+    auth_proof, unix_now, AuthRole, Freshness, PeerToProxy, ProxyToPeer, RegistrationOutcome, ServerMetadata,
+    SignalPayload,
+};
+use rs_peer_workspace_tests::{
+    within, TestProxy, TestServer, TestTunnel, ADMIN_TOKEN, PROXY_PASSWORD, SERVER_PASSWORD,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_bad_credentials() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+
+    let mut wrong_proxy = proxy.connection(server.name(), false);
+    wrong_proxy.proxy_password = "wrong".to_string();
+    assert!(within(Connection::connect(wrong_proxy)).await.is_err());
+
+    let mut wrong_server = proxy.connection(server.name(), false);
+    wrong_server.server_password = "wrong".to_string();
+    let err = within(Connection::connect(wrong_server)).await.err().expect("connect should fail");
+    assert!(err.to_string().contains("invalid server password"), "{err:#}");
+
+    let err = within(Connection::connect(proxy.connection("missing", false)))
+        .await
+        .err()
+        .expect("connect should fail");
+    assert!(err.to_string().contains("unknown server name"), "{err:#}");
+
+    proxy.wait_for_count("sessions", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tests_connections_without_keeping_a_session() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+
+    let outcome = within(test_connection(&proxy.connection(server.name(), false))).await?;
+    assert_eq!(outcome, ConnectionTest::Reachable { turn: true });
+
+    let mut wrong_proxy = proxy.connection(server.name(), false);
+    wrong_proxy.proxy_password = "wrong".to_string();
+    let outcome = within(test_connection(&wrong_proxy)).await?;
+    assert!(matches!(outcome, ConnectionTest::ProxyAuthFailed(_)), "{outcome:?}");
+
+    let mut wrong_server = proxy.connection(server.name(), false);
+    wrong_server.server_password = "wrong".to_string();
+    let outcome = within(test_connection(&wrong_server)).await?;
+    assert_eq!(outcome, ConnectionTest::Rejected("invalid server password".to_string()));
+
+    let outcome = within(test_connection(&proxy.connection("missing", false))).await?;
+    assert_eq!(outcome, ConnectionTest::UnknownServer);
+
+    proxy.wait_for_count("sessions", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_replayed_auth() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let freshness = Freshness::new();
+    let frame = serde_json::to_string(&PeerToProxy::AuthProxy {
+        proof: auth_proof(PROXY_PASSWORD, &freshness),
+        role: AuthRole::Client,
+        freshness: freshness.clone(),
+    })?;
+    // A forged proof must not use up the nonce of the genuine frame that follows it.
+    let forged = serde_json::to_string(&PeerToProxy::AuthProxy {
+        proof: auth_proof("guess", &freshness),
+        role: AuthRole::Client,
+        freshness,
+    })?;
+    let long_nonce = Freshness {
+        nonce: "n".repeat(1024),
+        ..Freshness::new()
+    };
+    let oversized = serde_json::to_string(&PeerToProxy::AuthProxy {
+        proof: auth_proof(PROXY_PASSWORD, &long_nonce),
+        role: AuthRole::Client,
+        freshness: long_nonce,
+    })?;
+
+    let mut replies = Vec::new();
+    for frame in [&forged, &frame, &frame, &oversized] {
+        let (mut socket, _) = within(async { Ok(connect_async(proxy.ws_url()).await?) }).await?;
+        socket.send(Message::Text(frame.clone().into())).await?;
+        let reply = within(async {
+            while let Some(message) = socket.next().await {
+                if let Message::Text(text) = message? {
+                    return Ok(serde_json::from_str::<ProxyToPeer>(&text)?);
+                }
+            }
+            anyhow::bail!("proxy closed the connection without replying")
+        })
+        .await?;
+        replies.push(reply);
+    }
+    assert!(
+        matches!(&replies[0], ProxyToPeer::AuthError { reason } if reason == "invalid proxy password"),
+        "{:?}",
+        replies[0]
+    );
+    assert!(matches!(replies[1], ProxyToPeer::AuthOk { .. }), "{:?}", replies[1]);
+    assert!(
+        matches!(&replies[2], ProxyToPeer::AuthError { reason } if reason.contains("already used")),
+        "{:?}",
+        replies[2]
+    );
+    assert!(
+        matches!(&replies[3], ProxyToPeer::AuthError { reason } if reason == "message nonce is too long"),
+        "{:?}",
+        replies[3]
+    );
+    Ok(())
+}
+
+type RawSocket = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Opens a bare socket to the proxy and authenticates it as `role`.
+async fn open_raw(proxy: &TestProxy, role: AuthRole) -> anyhow::Result<RawSocket> {
+    let (mut socket, _) = within(async { Ok(connect_async(proxy.ws_url()).await?) }).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut socket, &PeerToProxy::AuthProxy {
+        proof: auth_proof(PROXY_PASSWORD, &freshness),
+        role,
+        freshness,
+    })
+    .await?;
+    Ok(socket)
+}
+
+async fn send_raw(socket: &mut RawSocket, frame: &PeerToProxy) -> anyhow::Result<()> {
+    Ok(socket.send(Message::Text(serde_json::to_string(frame)?.into())).await?)
+}
+
+/// Registers `name` over a bare socket and returns the proxy's answer to the registration.
+async fn register_raw(proxy: &TestProxy, name: &str) -> anyhow::Result<(RawSocket, ProxyToPeer)> {
+    let mut socket = open_raw(proxy, AuthRole::Server).await?;
+    send_raw(&mut socket, &PeerToProxy::RegisterServer {
+        server_name: name.to_string(),
+        server_password: "raw-secret".to_string(),
+        metadata: ServerMetadata::default(),
+        resume_token: None,
+    })
+    .await?;
+    let reply = next_after_auth(&mut socket).await?;
+    Ok((socket, reply))
+}
+
+async fn next_after_auth(socket: &mut RawSocket) -> anyhow::Result<ProxyToPeer> {
+    within(async {
+        while let Some(message) = socket.next().await {
+            if let Message::Text(text) = message? {
+                match serde_json::from_str::<ProxyToPeer>(&text)? {
+                    ProxyToPeer::AuthOk { .. } => {}
+                    reply => return Ok(reply),
+                }
+            }
+        }
+        anyhow::bail!("proxy closed the connection without replying")
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resolves_server_name_conflicts() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let (_held, first) = register_raw(&proxy, "shared").await?;
+    assert!(matches!(first, ProxyToPeer::Registered { outcome: RegistrationOutcome::New, .. }), "{first:?}");
+    let (_, second) = register_raw(&proxy, "shared").await?;
+    assert!(
+        matches!(&second, ProxyToPeer::ConnectionError { reason } if reason == "server name already registered"),
+        "{second:?}"
+    );
+
+    let proxy = TestProxy::start_with_args(&["--on-name-conflict", "suffix"]).await?;
+    let (_first, _) = register_raw(&proxy, "shared").await?;
+    let (_second, second) = register_raw(&proxy, "shared").await?;
+    assert!(
+        matches!(&second, ProxyToPeer::Registered { server_name, outcome: RegistrationOutcome::Suffixed, .. } if server_name == "shared-2"),
+        "{second:?}"
+    );
+    let (_, third) = register_raw(&proxy, "shared").await?;
+    assert!(matches!(&third, ProxyToPeer::Registered { server_name, .. } if server_name == "shared-3"), "{third:?}");
+
+    let proxy = TestProxy::start_with_args(&["--on-name-conflict", "replace"]).await?;
+    let (mut stale, _) = register_raw(&proxy, "shared").await?;
+    let (_fresh, fresh) = register_raw(&proxy, "shared").await?;
+    assert!(
+        matches!(&fresh, ProxyToPeer::Registered { server_name, outcome: RegistrationOutcome::Replaced, .. } if server_name == "shared"),
+        "{fresh:?}"
+    );
+    let kicked = next_after_auth(&mut stale).await?;
+    assert!(matches!(kicked, ProxyToPeer::ConnectionError { .. }), "{kicked:?}");
+    drop(stale);
+    // The stale connection's cleanup must leave the new registration in place.
+    proxy.wait_for_count("connections", 1).await?;
+    proxy.wait_for_count("servers", 1).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_servers_that_stop_answering_pings() -> anyhow::Result<()> {
+    let proxy = TestProxy::start_with_args(&["--ping-interval-secs", "1", "--ping-max-missed", "2"]).await?;
+    // Nothing reads this socket once it is registered, so the proxy's pings go unanswered.
+    let (_silent, _) = register_raw(&proxy, "silent").await?;
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: "silent".to_string(),
+        proof: auth_proof("raw-secret", &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let connected = next_after_auth(&mut client).await?;
+    assert!(matches!(connected, ProxyToPeer::Connected { .. }), "{connected:?}");
+
+    let closed = next_after_auth(&mut client).await?;
+    assert!(
+        matches!(&closed, ProxyToPeer::SessionClosed { reason, .. } if reason == "server disconnected"),
+        "{closed:?}"
+    );
+    proxy.wait_for_count("servers", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn closes_idle_sessions() -> anyhow::Result<()> {
+    let proxy = TestProxy::start_with_args(&["--session-idle-timeout-secs", "1"]).await?;
+    let (mut server, _) = register_raw(&proxy, "quiet").await?;
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: "quiet".to_string(),
+        proof: auth_proof("raw-secret", &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let connected = next_after_auth(&mut client).await?;
+    assert!(matches!(connected, ProxyToPeer::Connected { .. }), "{connected:?}");
+    let joined = next_after_auth(&mut server).await?;
+    assert!(matches!(joined, ProxyToPeer::PeerJoined { .. }), "{joined:?}");
+
+    for socket in [&mut client, &mut server] {
+        let closed = next_after_auth(socket).await?;
+        assert!(
+            matches!(&closed, ProxyToPeer::SessionClosed { reason, .. } if reason == "session idle timeout"),
+            "{closed:?}"
+        );
+    }
+    // Both peers stay connected; only the session is gone.
+    proxy.wait_for_count("sessions", 0).await?;
+    proxy.wait_for_count("connections", 2).await?;
+    let connections = proxy.admin("connections").await?;
+    for connection in connections.as_array().into_iter().flatten() {
+        assert!(connection["queue_depth"].is_u64(), "{connection}");
+        assert_eq!(connection["dropped_messages"], 0, "{connection}");
+    }
+    Ok(())
+}
+
+/// Reads past regular replies until the proxy answers with `connection_error`.
+async fn next_connection_error(socket: &mut RawSocket) -> anyhow::Result<String> {
+    loop {
+        if let ProxyToPeer::ConnectionError { reason } = next_after_auth(socket).await? {
+            return Ok(reason);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn limits_message_rate_and_payload_size() -> anyhow::Result<()> {
+    let proxy = TestProxy::start_with_args(&["--rate-limit-messages", "5"]).await?;
+    let mut flooder = open_raw(&proxy, AuthRole::Client).await?;
+    let started = std::time::Instant::now();
+    for _ in 0..20 {
+        send_raw(&mut flooder, &PeerToProxy::ListServers).await?;
+    }
+    // Every message is answered, just no faster than the limit allows.
+    for _ in 0..20 {
+        let reply = next_after_auth(&mut flooder).await?;
+        assert!(matches!(reply, ProxyToPeer::ServersList { .. }), "{reply:?}");
+    }
+    assert!(started.elapsed() >= std::time::Duration::from_secs(2), "{:?}", started.elapsed());
+    proxy.wait_for_count("connections", 1).await?;
+
+    let proxy = TestProxy::start_with_args(&["--max-payload-bytes", "16"]).await?;
+    let (_server, _) = register_raw(&proxy, "small").await?;
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: "small".to_string(),
+        proof: auth_proof("raw-secret", &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let ProxyToPeer::Connected { session_id, .. } = next_after_auth(&mut client).await? else {
+        anyhow::bail!("session was not established");
+    };
+    send_raw(&mut client, &PeerToProxy::RelayData { session_id, payload: vec![0; 64], droppable: false }).await?;
+    let closed = next_after_auth(&mut client).await?;
+    assert!(
+        matches!(&closed, ProxyToPeer::SessionClosed { reason, .. } if reason == "relayed payload exceeds 16 bytes"),
+        "{closed:?}"
+    );
+    proxy.wait_for_count("sessions", 0).await?;
+    proxy.wait_for_count("connections", 2).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn logs_clients_in_with_user_accounts() -> anyhow::Result<()> {
+    let salt = SaltString::encode_b64(b"harness-salt").map_err(|err| anyhow::anyhow!("{err}"))?;
+    let hash = |password: &str| -> anyhow::Result<String> {
+        Ok(Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|err| anyhow::anyhow!("{err}"))?
+            .to_string())
+    };
+    let users_file = std::env::temp_dir().join(format!("harness-users-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &users_file,
+        serde_json::to_vec(&serde_json::json!([
+            { "username": "alice", "password_hash": hash("alice-secret")?, "role": "user" },
+            { "username": "root", "password_hash": hash("root-secret")?, "role": "admin" },
+        ]))?,
+    )?;
+    let proxy = TestProxy::start_with_args(&["--users-file", &users_file.to_string_lossy()]).await?;
+    let server = TestServer::start(&proxy).await?;
+
+    let shared_password = proxy.connection(server.name(), false);
+    let err = within(Connection::connect(shared_password.clone())).await.err().expect("connect should fail");
+    assert!(err.to_string().contains("requires a username and password"), "{err:#}");
+
+    let mut wrong_password = shared_password.clone();
+    wrong_password.proxy_username = "alice".to_string();
+    wrong_password.proxy_password = "wrong".to_string();
+    let err = within(Connection::connect(wrong_password)).await.err().expect("connect should fail");
+    assert!(err.to_string().contains("invalid username or password"), "{err:#}");
+
+    let mut alice = shared_password;
+    alice.proxy_username = "alice".to_string();
+    alice.proxy_password = "alice-secret".to_string();
+    let session = within(Connection::connect(alice)).await?;
+    let sessions = proxy.admin("sessions").await?;
+    assert_eq!(sessions[0]["username"], "alice", "{sessions}");
+    session.disconnect();
+
+    // Only admin accounts may use the admin API.
+    for (username, password, status) in [("root", "root-secret", 200), ("alice", "alice-secret", 401)] {
+        let response = reqwest::Client::new()
+            .get(format!("{}/admin/sessions", proxy.http_url()))
+            .basic_auth(username, Some(password))
+            .send()
+            .await?;
+        assert_eq!(response.status().as_u16(), status, "{username}");
+    }
+    // Basic authentication sends the password itself, so it needs TLS as well.
+    let response = reqwest::Client::new()
+        .get(format!("{}/admin/sessions", proxy.http_url()))
+        .header("x-forwarded-for", "203.0.113.7")
+        .basic_auth("root", Some("root-secret"))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 403);
+
+    // A login relayed by a front end that did not report TLS is refused.
+    let mut request = proxy.ws_url().into_client_request()?;
+    request
+        .headers_mut()
+        .insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7"));
+    let (mut socket, _) = within(async { Ok(connect_async(request).await?) }).await?;
+    send_raw(&mut socket, &PeerToProxy::Login {
+        username: "alice".to_string(),
+        password: "alice-secret".to_string(),
+        freshness: Freshness::new(),
+    })
+    .await?;
+    let refused = next_after_auth(&mut socket).await?;
+    assert!(
+        matches!(&refused, ProxyToPeer::AuthError { reason } if reason.contains("only accepted over TLS")),
+        "{refused:?}"
+    );
+    let _ = std::fs::remove_file(&users_file);
+    proxy.wait_for_count("sessions", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_relays_into_foreign_sessions() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let (mut server, _) = register_raw(&proxy, "victim").await?;
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: "victim".to_string(),
+        proof: auth_proof("raw-secret", &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let ProxyToPeer::Connected { session_id, .. } = next_after_auth(&mut client).await? else {
+        anyhow::bail!("session was not established");
+    };
+    let joined = next_after_auth(&mut server).await?;
+    assert!(matches!(joined, ProxyToPeer::PeerJoined { .. }), "{joined:?}");
+
+    let spoofed = || [
+        PeerToProxy::RelayData { session_id, payload: b"spoofed".to_vec(), droppable: false },
+        PeerToProxy::Signal {
+            session_id,
+            signal: SignalPayload::IceCandidate {
+                candidate: "spoofed".to_string(),
+                sdp_mid: None,
+                sdp_mline_index: None,
+            },
+            freshness: Freshness::new(),
+        },
+        PeerToProxy::DisconnectSession { session_id, reason: None },
+    ];
+    let mut intruder = open_raw(&proxy, AuthRole::Client).await?;
+    for frame in &spoofed() {
+        send_raw(&mut intruder, frame).await?;
+    }
+    // Frames from one connection are handled in order, so the reply to this one means
+    // the spoofed frames above have been dealt with.
+    send_raw(&mut intruder, &PeerToProxy::ListServers).await?;
+    let listed = next_after_auth(&mut intruder).await?;
+    assert!(matches!(listed, ProxyToPeer::ServersList { .. }), "{listed:?}");
+
+    let (mut other_server, _) = register_raw(&proxy, "intruder").await?;
+    for frame in &spoofed() {
+        send_raw(&mut other_server, frame).await?;
+    }
+    send_raw(&mut other_server, &PeerToProxy::ListServers).await?;
+    assert_eq!(
+        next_connection_error(&mut other_server).await?,
+        "list_servers is not allowed for a server connection"
+    );
+    proxy.wait_for_count("sessions", 1).await?;
+
+    // The client's own signal is dropped too: signaling only belongs to P2P sessions.
+    send_raw(&mut client, &spoofed()[1]).await?;
+    send_raw(&mut client, &PeerToProxy::RelayData { session_id, payload: b"genuine".to_vec(), droppable: false }).await?;
+    let relayed = next_after_auth(&mut server).await?;
+    assert!(
+        matches!(&relayed, ProxyToPeer::RelayData { payload, .. } if payload == b"genuine"),
+        "{relayed:?}"
+    );
+
+    send_raw(&mut client, &PeerToProxy::SetBusy { busy: true }).await?;
+    assert_eq!(next_connection_error(&mut client).await?, "set_busy is not allowed for a client connection");
+    proxy.wait_for_count("sessions", 0).await
+}
+
+/// Authenticates a fresh socket as a client with `password` and returns the proxy's reply.
+/// Authenticates as the documentation address [`FORWARDED_IP`], so lockouts leave the
+/// harness's own admin requests from loopback alone. Needs `--trust-forwarded-for`.
+async fn auth_attempt(proxy: &TestProxy, password: &str) -> anyhow::Result<ProxyToPeer> {
+    let mut request = proxy.ws_url().into_client_request()?;
+    request
+        .headers_mut()
+        .insert("x-forwarded-for", HeaderValue::from_static(FORWARDED_IP));
+    let (mut socket, _) = within(async { Ok(connect_async(request).await?) }).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut socket, &PeerToProxy::AuthProxy {
+        proof: auth_proof(password, &freshness),
+        role: AuthRole::Client,
+        freshness,
+    })
+    .await?;
+    within(async {
+        while let Some(message) = socket.next().await {
+            if let Message::Text(text) = message? {
+                return Ok(serde_json::from_str::<ProxyToPeer>(&text)?);
+            }
+        }
+        anyhow::bail!("proxy closed the connection without replying")
+    })
+    .await
+}
+
+const FORWARDED_IP: &str = "203.0.113.9";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn throttles_and_bans_failed_authentication() -> anyhow::Result<()> {
+    let proxy = TestProxy::start_with_args(&[
+        "--auth-max-failures",
+        "2",
+        "--auth-lockout-secs",
+        "60",
+        "--trust-forwarded-for",
+    ])
+    .await?;
+    // Stale frames are refused without counting toward the lockout.
+    for _ in 0..3 {
+        let (mut socket, _) = within(async { Ok(connect_async(proxy.ws_url()).await?) }).await?;
+        let freshness = Freshness {
+            timestamp: 0,
+            ..Freshness::new()
+        };
+        send_raw(&mut socket, &PeerToProxy::AuthProxy {
+            proof: auth_proof(PROXY_PASSWORD, &freshness),
+            role: AuthRole::Client,
+            freshness,
+        })
+        .await?;
+        let reply = next_after_auth(&mut socket).await?;
+        assert!(
+            matches!(&reply, ProxyToPeer::AuthError { reason } if reason.contains("outside the accepted window")),
+            "{reply:?}"
+        );
+    }
+    for _ in 0..2 {
+        let reply = auth_attempt(&proxy, "wrong").await?;
+        assert!(
+            matches!(&reply, ProxyToPeer::AuthError { reason } if reason == "invalid proxy password"),
+            "{reply:?}"
+        );
+    }
+    let reply = auth_attempt(&proxy, PROXY_PASSWORD).await?;
+    assert!(
+        matches!(&reply, ProxyToPeer::AuthError { reason } if reason.contains("too many failed attempts")),
+        "{reply:?}"
+    );
+    assert_eq!(proxy.admin("bans").await?, serde_json::json!([]));
+
+    // Wrong admin tokens count toward the same lockout, which then refuses the right one.
+    for (token, status) in [("wrong", 401), ("wrong", 401), (ADMIN_TOKEN, 429)] {
+        let response = reqwest::Client::new()
+            .get(format!("{}/admin/bans", proxy.http_url()))
+            .bearer_auth(token)
+            .send()
+            .await?;
+        assert_eq!(response.status().as_u16(), status, "{token}");
+    }
+
+    let ban_file = std::env::temp_dir().join(format!("harness-bans-{}.json", uuid::Uuid::new_v4()));
+    let ban_path = ban_file.to_string_lossy().into_owned();
+    let proxy = TestProxy::start_with_args(&[
+        "--auth-max-failures",
+        "2",
+        "--ban-file",
+        &ban_path,
+        "--ban-after-lockouts",
+        "1",
+        "--trust-forwarded-for",
+    ])
+    .await?;
+    for _ in 0..2 {
+        auth_attempt(&proxy, "wrong").await?;
+    }
+    let reply = auth_attempt(&proxy, PROXY_PASSWORD).await?;
+    assert!(
+        matches!(&reply, ProxyToPeer::AuthError { reason } if reason.contains("banned")),
+        "{reply:?}"
+    );
+    let bans = proxy.admin("bans").await?;
+    assert_eq!(bans[0]["ip"], FORWARDED_IP, "{bans}");
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&ban_file)?)?;
+    assert_eq!(saved[0]["ip"], FORWARDED_IP, "{saved}");
+
+    drop(proxy);
+    let proxy = TestProxy::start_with_args(&["--ban-file", &ban_path, "--trust-forwarded-for"]).await?;
+    let reply = auth_attempt(&proxy, PROXY_PASSWORD).await?;
+    assert!(
+        matches!(&reply, ProxyToPeer::AuthError { reason } if reason.contains("banned")),
+        "{reply:?}"
+    );
+    let _ = std::fs::remove_file(&ban_file);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pins_server_identity() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let first = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let fingerprint = first.server_fingerprint().to_string();
+    assert!(fingerprint.starts_with("SHA256:"), "{fingerprint}");
+    first.disconnect();
+
+    let mut pinned = proxy.connection(server.name(), false);
+    pinned.server_fingerprint = Some(fingerprint.clone());
+    let again = within(Connection::connect(pinned.clone())).await?;
+    assert_eq!(again.server_fingerprint(), fingerprint);
+    again.disconnect();
+
+    pinned.server_fingerprint = Some("SHA256:someone-else".to_string());
+    let err = match within(Connection::connect(pinned)).await {
+        Ok(_) => panic!("connected to a server with a different identity"),
+        Err(err) => err,
+    };
+    assert!(err.to_string().contains("server identity changed"), "{err}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relays_file_rpcs() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("notes.txt").to_string_lossy().to_string();
+
+    let written = within(connection.request(RpcAction::WriteFile {
+        path: path.clone(),
+        content: "hello from the harness".to_string(),
+        expected_hash: None,
+    }))
+    .await?;
+    assert!(matches!(written, RpcResult::WriteComplete { .. }), "{written:?}");
+
+    let listed = within(connection.request(RpcAction::ListDirectory {
+        path: server.root().to_string_lossy().to_string(),
+        filter: ListFilter::default(),
+    }))
+    .await?;
+    let RpcResult::DirectoryEntries { entries, .. } = listed else {
+        panic!("unexpected result {listed:?}");
+    };
+    assert!(entries.iter().any(|entry| entry.name == "notes.txt" && !entry.is_dir));
+
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: path.clone(),
+        known_hash: None,
+    }))
+    .await?;
+    let RpcResult::FileContent { content, .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+    assert_eq!(content, "hello from the harness");
+
+    let chunk = within(connection.request(RpcAction::ReadFileChunk {
+        path,
+        offset: 6,
+        length: 4,
+    }))
+    .await?;
+    let RpcResult::FileChunk { data, total_size, .. } = chunk else {
+        panic!("unexpected result {chunk:?}");
+    };
+    assert_eq!(data, b"from");
+    assert_eq!(total_size, 22);
+
+    let binary = server.root().join("image.bin");
+    std::fs::write(&binary, b"\x89PNG\0\0\x01\x02")?;
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: binary.to_string_lossy().to_string(),
+        known_hash: None,
+    }))
+    .await?;
+    let RpcResult::BinaryContent { size, preview, .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+    assert_eq!(size, 8);
+    assert_eq!(preview, b"\x89PNG\0\0\x01\x02");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compresses_relayed_payloads() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("repetitive.txt").to_string_lossy().to_string();
+    let content = "the same line over and over\n".repeat(16 * 1024);
+
+    connection.write_file(&path, &content).await?;
+    assert_eq!(std::fs::read_to_string(&path)?, content);
+    assert_eq!(connection.read_file(&path).await?, content);
+
+    let mut events = connection.subscribe_events();
+    within(async {
+        loop {
+            if let ConnectionEvent::TransportStats { stats, .. } = events.recv().await? {
+                assert!(stats.relay_bytes_sent < content.len() as u64 / 10, "{stats:?}");
+                assert!(stats.relay_bytes_received < content.len() as u64 / 10, "{stats:?}");
+                return Ok(());
+            }
+        }
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn encrypts_relayed_payloads_end_to_end() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start_with_args(&proxy, &["--require-e2e"]).await?;
+    let mut settings = proxy.connection(server.name(), false);
+    settings.require_e2e = true;
+    let connection = within(Connection::connect(settings)).await?;
+    let mut events = connection.subscribe_events();
+    let path = server.root().join("sealed.txt").to_string_lossy().to_string();
+    connection.write_file(&path, "not for the proxy").await?;
+    assert_eq!(connection.read_file(&path).await?, "not for the proxy");
+    within(async {
+        loop {
+            if let ConnectionEvent::TransportStats { stats, .. } = events.recv().await? {
+                assert!(stats.relay_encrypted, "{stats:?}");
+                return Ok(());
+            }
+        }
+    })
+    .await?;
+    connection.disconnect();
+    proxy.wait_for_count("sessions", 0).await?;
+
+    // A client that offers no key is turned away before the server proves its identity.
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: server.name().to_string(),
+        proof: auth_proof(SERVER_PASSWORD, &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let ProxyToPeer::Connected { session_id, .. } = next_after_auth(&mut client).await? else {
+        anyhow::bail!("session was not established");
+    };
+    for payload in [
+        AppPayload::Capabilities(Capabilities::default()),
+        AppPayload::IdentityChallenge(IdentityChallenge { nonce: "plain".to_string() }),
+    ] {
+        let payload = serde_json::to_vec(&AppEnvelope { message_id: uuid::Uuid::new_v4(), payload })?;
+        send_raw(&mut client, &PeerToProxy::RelayData { session_id, payload, droppable: false }).await?;
+    }
+    // The server answers the capabilities before it sees the plain challenge.
+    let closed = loop {
+        match next_after_auth(&mut client).await? {
+            ProxyToPeer::RelayData { .. } => {}
+            reply => break reply,
+        }
+    };
+    assert!(
+        matches!(&closed, ProxyToPeer::SessionClosed { reason, .. } if reason == "this server requires end-to-end encryption"),
+        "{closed:?}"
+    );
+    proxy.wait_for_count("sessions", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_plain_requests_when_encryption_is_required() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start_with_args(&proxy, &["--require-e2e"]).await?;
+
+    // A client that skips the identity challenge must not get its requests served in plain text.
+    let mut client = open_raw(&proxy, AuthRole::Client).await?;
+    let freshness = Freshness::new();
+    send_raw(&mut client, &PeerToProxy::ConnectServer {
+        server_name: server.name().to_string(),
+        proof: auth_proof(SERVER_PASSWORD, &freshness),
+        use_p2p: false,
+        freshness,
+    })
+    .await?;
+    let ProxyToPeer::Connected { session_id, .. } = next_after_auth(&mut client).await? else {
+        anyhow::bail!("session was not established");
+    };
+    let payload = serde_json::to_vec(&AppEnvelope {
+        message_id: uuid::Uuid::new_v4(),
+        payload: AppPayload::RpcRequest(RpcRequest {
+            request_id: uuid::Uuid::new_v4(),
+            action: RpcAction::ListRoots,
+        }),
+    })?;
+    send_raw(&mut client, &PeerToProxy::RelayData { session_id, payload, droppable: false }).await?;
+    let closed = next_after_auth(&mut client).await?;
+    assert!(
+        matches!(&closed, ProxyToPeer::SessionClosed { reason, .. } if reason == "this server requires end-to-end encryption"),
+        "{closed:?}"
+    );
+    proxy.wait_for_count("sessions", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connects_through_outbound_proxies() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    for kind in OutboundProxyKind::ALL {
+        let tunnel = TestTunnel::start(kind).await?;
+        let mut project_connection = proxy.connection(server.name(), false);
+        project_connection.outbound_proxy = Some(tunnel.outbound_proxy());
+        let connection = within(Connection::connect(project_connection)).await?;
+        let listed = within(connection.request(RpcAction::ListDirectory {
+            path: server.root().to_string_lossy().to_string(),
+            filter: ListFilter::default(),
+        }))
+        .await?;
+        assert!(matches!(listed, RpcResult::DirectoryEntries { .. }), "{kind:?}: {listed:?}");
+        assert_eq!(tunnel.tunnels(), 1, "{kind:?}");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn falls_back_to_relay_when_p2p_is_unavailable() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), true))).await?;
+
+    let roots = within(connection.request(RpcAction::ListRoots)).await?;
+    assert!(matches!(roots, RpcResult::Roots { ref roots } if !roots.is_empty()), "{roots:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cleans_up_sessions() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let mut server = TestServer::start(&proxy).await?;
+
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    proxy.wait_for_count("sessions", 1).await?;
+    drop(connection);
+    proxy.wait_for_count("sessions", 0).await?;
+
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut events = connection.subscribe_events();
+    proxy.wait_for_count("sessions", 1).await?;
+    server.kill();
+    within(async {
+        loop {
+            if let ConnectionEvent::Closed { reason, .. } = events.recv().await? {
+                assert!(reason.contains("server disconnected"), "{reason}");
+                return Ok(());
+            }
+        }
+    })
+    .await?;
+    proxy.wait_for_count("sessions", 0).await?;
+    proxy.wait_for_count("servers", 0).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shares_advisory_file_locks() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let alice = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let bob = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("shared.txt").to_string_lossy().to_string();
+    let lock = |owner: &str| RpcAction::LockFile {
+        path: path.clone(),
+        owner: owner.to_string(),
+        ttl_secs: None,
+    };
+
+    let taken = within(alice.request(lock("alice"))).await?;
+    assert!(matches!(taken, RpcResult::FileLock { lock: Some(ref lock), .. } if lock.mine), "{taken:?}");
+
+    let denied = within(bob.request(lock("bob"))).await?;
+    let RpcResult::FileLock { lock: Some(held), .. } = denied else {
+        panic!("unexpected result {denied:?}");
+    };
+    assert_eq!(held.owner, "alice");
+    assert!(!held.mine);
+    let unlock = RpcAction::UnlockFile { path: path.clone() };
+    assert!(matches!(within(bob.request(unlock.clone())).await?, RpcResult::Error { .. }));
+
+    let released = within(alice.request(unlock)).await?;
+    assert!(matches!(released, RpcResult::FileLock { lock: None, .. }), "{released:?}");
+    let taken = within(bob.request(lock("bob"))).await?;
+    assert!(matches!(taken, RpcResult::FileLock { lock: Some(ref lock), .. } if lock.mine), "{taken:?}");
+
+    drop(bob);
+    proxy.wait_for_count("sessions", 1).await?;
+    let status = within(alice.request(RpcAction::FileLockStatus { path })).await?;
+    assert!(matches!(status, RpcResult::FileLock { lock: None, .. }), "{status:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_conflicting_writes() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("shared.txt").to_string_lossy().to_string();
+    within(connection.write_file(&path, "original\n")).await?;
+
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: path.clone(),
+        known_hash: None,
+    }))
+    .await?;
+    let RpcResult::FileContent { hash: Some(hash), .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+
+    within(connection.write_file(&path, "changed elsewhere\n")).await?;
+    let refused = within(connection.request(RpcAction::WriteFile {
+        path: path.clone(),
+        content: "my edit\n".to_string(),
+        expected_hash: Some(hash),
+    }))
+    .await?;
+    let RpcResult::Conflict { content, hash, .. } = refused else {
+        panic!("unexpected result {refused:?}");
+    };
+    assert_eq!(content, "changed elsewhere\n");
+
+    let written = within(connection.request(RpcAction::WriteFile {
+        path: path.clone(),
+        content: "merged\n".to_string(),
+        expected_hash: Some(hash.clone()),
+    }))
+    .await?;
+    let RpcResult::WriteComplete { hash: Some(new_hash), .. } = written else {
+        panic!("unexpected result {written:?}");
+    };
+    assert_ne!(new_hash, hash);
+    assert_eq!(within(connection.read_file(&path)).await?, "merged\n");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skips_unchanged_reads() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("cached.txt").to_string_lossy().to_string();
+    within(connection.write_file(&path, "cached\n")).await?;
+    let hash = content_hash(b"cached\n");
+
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: path.clone(),
+        known_hash: Some(hash.clone()),
+    }))
+    .await?;
+    assert!(
+        matches!(&read, RpcResult::NotModified { hash: same, .. } if *same == hash),
+        "{read:?}"
+    );
+
+    within(connection.write_file(&path, "changed\n")).await?;
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: path.clone(),
+        known_hash: Some(hash),
+    }))
+    .await?;
+    let RpcResult::FileContent { content, .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+    assert_eq!(content, "changed\n");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn creates_files_and_folders() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let dir = server.root().join("notes").to_string_lossy().to_string();
+    let file = server.root().join("notes").join("todo.txt").to_string_lossy().to_string();
+
+    let created = within(connection.request(RpcAction::CreateFile {
+        path: dir.clone(),
+        is_dir: true,
+    }))
+    .await?;
+    assert!(matches!(created, RpcResult::Created { is_dir: true, .. }), "{created:?}");
+    let created = within(connection.request(RpcAction::CreateFile {
+        path: file.clone(),
+        is_dir: false,
+    }))
+    .await?;
+    assert!(matches!(created, RpcResult::Created { is_dir: false, .. }), "{created:?}");
+    assert_eq!(within(connection.read_file(&file)).await?, "");
+
+    within(connection.write_file(&file, "keep me")).await?;
+    let refused = within(connection.request(RpcAction::CreateFile {
+        path: file.clone(),
+        is_dir: false,
+    }))
+    .await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+    assert_eq!(within(connection.read_file(&file)).await?, "keep me");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_entry_metadata() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let dir = server.root().join("stats");
+    let file = dir.join("data.txt").to_string_lossy().to_string();
+    within(connection.write_file(&file, "twelve bytes")).await?;
+
+    let stat = within(connection.request(RpcAction::StatEntry { path: file.clone() })).await?;
+    let RpcResult::Stat { stat } = stat else {
+        panic!("unexpected result {stat:?}");
+    };
+    assert!(!stat.is_dir);
+    assert_eq!(stat.size, 12);
+    assert!(stat.modified.is_some());
+    assert!(!stat.permissions.is_empty());
+    assert_eq!(stat.symlink_target, None);
+
+    let listed = within(connection.request(RpcAction::ListDirectory {
+        path: server.root().to_string_lossy().to_string(),
+        filter: ListFilter::default(),
+    }))
+    .await?;
+    let RpcResult::DirectoryEntries { entries, .. } = listed else {
+        panic!("unexpected result {listed:?}");
+    };
+    let folder = entries.iter().find(|entry| entry.name == "stats").expect("stats listed");
+    assert_eq!(folder.size, None);
+    assert!(folder.modified.is_some());
+
+    #[cfg(unix)]
+    {
+        let link = dir.join("link.txt");
+        std::os::unix::fs::symlink(&file, &link)?;
+        let stat = within(connection.request(RpcAction::StatEntry {
+            path: link.to_string_lossy().to_string(),
+        }))
+        .await?;
+        let RpcResult::Stat { stat } = stat else {
+            panic!("unexpected result {stat:?}");
+        };
+        assert_eq!(stat.symlink_target.as_deref(), Some(file.as_str()));
+        assert_eq!(stat.size, 12);
+    }
+
+    let missing = within(connection.request(RpcAction::StatEntry {
+        path: dir.join("missing").to_string_lossy().to_string(),
+    }))
+    .await?;
+    assert!(matches!(missing, RpcResult::Error { .. }), "{missing:?}");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_writes_that_do_not_fit() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().to_string_lossy().to_string();
+
+    let stat = within(connection.request(RpcAction::StatEntry { path: root })).await?;
+    let RpcResult::Stat { stat } = stat else {
+        panic!("unexpected result {stat:?}");
+    };
+    let disk = stat.disk.expect("disk space reported");
+    assert!(disk.available <= disk.total);
+
+    let target = server.root().join("uploads").join("huge.bin");
+    let refused = within(connection.request(RpcAction::WriteFileChunk {
+        path: target.to_string_lossy().to_string(),
+        offset: 0,
+        data: vec![0; 16],
+        truncate: true,
+        total_size: Some(disk.total.saturating_mul(2).max(1 << 40)),
+        checksum: None,
+    }))
+    .await?;
+    let RpcResult::Error { message } = refused else {
+        panic!("unexpected result {refused:?}");
+    };
+    assert!(message.contains("not enough space"), "{message}");
+    assert!(!target.exists());
+
+    let written = within(connection.request(RpcAction::WriteFileChunk {
+        path: target.to_string_lossy().to_string(),
+        offset: 0,
+        data: vec![0; 16],
+        truncate: true,
+        total_size: Some(16),
+        checksum: None,
+    }))
+    .await?;
+    assert!(matches!(written, RpcResult::ChunkWritten { length: 16, .. }), "{written:?}");
+
+    for (offset, total_size) in [(8, Some(16)), (u64::MAX - 4, None)] {
+        let refused = within(connection.request(RpcAction::WriteFileChunk {
+            path: target.to_string_lossy().to_string(),
+            offset,
+            data: vec![0; 16],
+            truncate: false,
+            total_size,
+            checksum: None,
+        }))
+        .await?;
+        let RpcResult::Error { message } = refused else {
+            panic!("unexpected result {refused:?}");
+        };
+        assert!(message.contains("runs past"), "{message}");
+    }
+    assert_eq!(std::fs::metadata(&target)?.len(), 16);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn checksums_transfer_chunks() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("upload.bin").to_string_lossy().to_string();
+    let first = vec![1u8; 32];
+    let second = vec![2u8; 16];
+    let write = |offset: u64, data: &[u8], checksum: String| RpcAction::WriteFileChunk {
+        path: path.clone(),
+        offset,
+        data: data.to_vec(),
+        truncate: offset == 0,
+        total_size: None,
+        checksum: Some(checksum),
+    };
+
+    let written = within(connection.request(write(0, &first, content_hash(&first)))).await?;
+    assert!(matches!(written, RpcResult::ChunkWritten { length: 32, .. }), "{written:?}");
+    let refused = within(connection.request(write(32, &second, content_hash(&first)))).await?;
+    let RpcResult::Error { message } = refused else {
+        panic!("unexpected result {refused:?}");
+    };
+    assert!(message.contains("does not match its checksum"), "{message}");
+    assert_eq!(std::fs::metadata(&path)?.len(), 32);
+
+    let read = within(connection.request(RpcAction::ReadFileChunk {
+        path: path.clone(),
+        offset: 0,
+        length: 32,
+    }))
+    .await?;
+    let RpcResult::FileChunk { checksum, .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+    assert_eq!(checksum, Some(content_hash(&first)));
+
+    // A resuming transfer checks its last chunks; ranges past the end come back empty.
+    let checked = within(connection.request(RpcAction::ChecksumChunks {
+        path,
+        chunks: vec![
+            ChunkRange { offset: 0, length: 16 },
+            ChunkRange { offset: 16, length: 16 },
+            ChunkRange { offset: 32, length: 16 },
+        ],
+    }))
+    .await?;
+    let RpcResult::ChunkChecksums { checksums, total_size, .. } = checked else {
+        panic!("unexpected result {checked:?}");
+    };
+    assert_eq!(total_size, 32);
+    assert_eq!(
+        checksums,
+        vec![Some(content_hash(&first[..16])), Some(content_hash(&first[16..])), None]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_to_read_text_files_over_the_limit() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start_with_args(&proxy, &["--max-read-size", "16"]).await?;
+    let path = server.root().join("app.log");
+    std::fs::write(&path, "first line\nsecond line\nthird line\n")?;
+    let path = path.to_string_lossy().to_string();
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+
+    let read = within(connection.request(RpcAction::ReadFile {
+        path: path.clone(),
+        known_hash: None,
+    }))
+    .await?;
+    let RpcResult::TooLarge { size, limit, .. } = read else {
+        panic!("unexpected result {read:?}");
+    };
+    assert_eq!((size, limit), (34, 16));
+    assert!(within(connection.read_file(&path)).await.is_err());
+
+    let tail = within(connection.request(RpcAction::ReadFileChunk {
+        path,
+        offset: size - 11,
+        length: 16,
+    }))
+    .await?;
+    let RpcResult::FileChunk { data, .. } = tail else {
+        panic!("unexpected result {tail:?}");
+    };
+    assert_eq!(data, b"third line\n");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tails_appended_lines() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let path = server.root().join("app.log");
+    std::fs::write(&path, "first\nsecond\n")?;
+    let path_str = path.to_string_lossy().to_string();
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut events = connection.subscribe_events();
+
+    let tail_id = uuid::Uuid::new_v4();
+    connection.send_rpc(RpcRequest {
+        request_id: tail_id,
+        action: RpcAction::TailFile {
+            path: path_str.clone(),
+            follow: true,
+        },
+    })?;
+    let mut next_lines = async || -> anyhow::Result<(Vec<String>, bool)> {
+        loop {
+            if let ConnectionEvent::RpcResponse { response, .. } = events.recv().await?
+                && response.request_id == tail_id
+            {
+                let RpcResult::TailLines { lines, rotated, .. } = response.result else {
+                    panic!("unexpected result {:?}", response.result);
+                };
+                return Ok((lines, rotated));
+            }
+        }
+    };
+    assert_eq!(within(next_lines()).await?, (vec!["first".to_string(), "second".to_string()], false));
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(b"third\nfou")?;
+    assert_eq!(within(next_lines()).await?, (vec!["third".to_string()], false));
+    file.write_all(b"rth\n")?;
+    assert_eq!(within(next_lines()).await?, (vec!["fourth".to_string()], false));
+
+    std::fs::write(&path, "new\n")?;
+    assert_eq!(within(next_lines()).await?, (vec!["new".to_string()], true));
+
+    let stopped = within(connection.request(RpcAction::StopTail { tail_id })).await?;
+    assert!(matches!(stopped, RpcResult::TailStopped { tail_id: id } if id == tail_id), "{stopped:?}");
+    let again = within(connection.request(RpcAction::StopTail { tail_id })).await?;
+    assert!(matches!(again, RpcResult::Error { .. }), "{again:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_and_monitors_system_info() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+
+    let snapshot = within(connection.request(RpcAction::SystemInfo { interval_ms: None })).await?;
+    let RpcResult::SystemInfo { info } = snapshot else {
+        panic!("unexpected result {snapshot:?}");
+    };
+    assert!(info.cpu_count > 0);
+    assert!(info.memory_total > 0 && info.memory_used <= info.memory_total);
+    assert!((0.0..=100.0).contains(&info.memory_usage()));
+
+    let mut events = connection.subscribe_events();
+    let monitor_id = uuid::Uuid::new_v4();
+    connection.send_rpc(RpcRequest {
+        request_id: monitor_id,
+        action: RpcAction::SystemInfo { interval_ms: Some(100) },
+    })?;
+    // The first snapshot answers the call; later ones follow at the interval.
+    for _ in 0..2 {
+        within(async {
+            loop {
+                if let ConnectionEvent::RpcResponse { response, .. } = events.recv().await?
+                    && response.request_id == monitor_id
+                {
+                    assert!(matches!(response.result, RpcResult::SystemInfo { .. }), "{:?}", response.result);
+                    return Ok(());
+                }
+            }
+        })
+        .await?;
+    }
+
+    let stopped = within(connection.request(RpcAction::StopSystemInfo { monitor_id })).await?;
+    assert!(matches!(stopped, RpcResult::SystemInfoStopped { monitor_id: id } if id == monitor_id), "{stopped:?}");
+    let again = within(connection.request(RpcAction::StopSystemInfo { monitor_id })).await?;
+    assert!(matches!(again, RpcResult::Error { .. }), "{again:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lists_watches_and_ends_processes() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut child = std::process::Command::new("sleep").arg("30").spawn()?;
+    let pid = child.id();
+
+    let listed = within(connection.request(RpcAction::ListProcesses)).await?;
+    let RpcResult::Processes { processes } = listed else {
+        panic!("unexpected result {listed:?}");
+    };
+    let sleeper = processes.iter().find(|process| process.pid == pid).expect("sleep is listed");
+    assert_eq!(sleeper.name, "sleep");
+    assert_eq!(sleeper.parent, Some(std::process::id()));
+
+    let mut events = connection.subscribe_events();
+    let watch_id = uuid::Uuid::new_v4();
+    connection.send_rpc(RpcRequest {
+        request_id: watch_id,
+        action: RpcAction::WatchProcess { pid, interval_ms: 100 },
+    })?;
+    let mut next_update = async || -> anyhow::Result<Option<_>> {
+        loop {
+            if let ConnectionEvent::RpcResponse { response, .. } = events.recv().await?
+                && response.request_id == watch_id
+            {
+                let RpcResult::ProcessUpdate { process, .. } = response.result else {
+                    panic!("unexpected result {:?}", response.result);
+                };
+                return Ok(process);
+            }
+        }
+    };
+    assert_eq!(within(next_update()).await?.map(|process| process.pid), Some(pid));
+
+    let killed = within(connection.request(RpcAction::KillProcess { pid, force: false })).await?;
+    assert!(matches!(killed, RpcResult::ProcessKilled { pid: id } if id == pid), "{killed:?}");
+    assert!(!child.wait()?.success());
+    // Updates end with the process gone.
+    while within(next_update()).await?.is_some() {}
+
+    let missing = within(connection.request(RpcAction::KillProcess { pid, force: true })).await?;
+    assert!(matches!(missing, RpcResult::Error { .. }), "{missing:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn collects_a_diagnostics_bundle() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start_with_args(&proxy, &["--diagnostics-path", "logs"]).await?;
+    std::fs::create_dir_all(server.root().join("logs"))?;
+    std::fs::write(server.root().join("logs/app.log"), "disk full at 03:00\n")?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+
+    let collected = within(connection.request(RpcAction::CollectDiagnostics)).await?;
+    let RpcResult::DiagnosticsCollected { path, size } = collected else {
+        panic!("unexpected result {collected:?}");
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+    }
+
+    // Downloading the bundle in chunks, with one chunk read twice, deletes it once every
+    // byte has been served.
+    let mut compressed = Vec::new();
+    for offset in [0, 0, 64] {
+        let chunk = within(connection.request(RpcAction::ReadFileChunk {
+            path: path.clone(),
+            offset,
+            length: 64,
+        }))
+        .await?;
+        let RpcResult::FileChunk { data, .. } = chunk else {
+            panic!("unexpected result {chunk:?}");
+        };
+        compressed.truncate(offset as usize);
+        compressed.extend(data);
+    }
+    assert!(std::path::Path::new(&path).exists());
+    let rest = within(connection.request(RpcAction::ReadFileChunk {
+        path: path.clone(),
+        offset: 128,
+        length: size,
+    }))
+    .await?;
+    let RpcResult::FileChunk { data, .. } = rest else {
+        panic!("unexpected result {rest:?}");
+    };
+    compressed.extend(data);
+    assert_eq!(compressed.len() as u64, size);
+    assert!(!std::path::Path::new(&path).exists(), "bundle was not deleted");
+
+    let mut entries = HashMap::new();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(compressed.as_slice()));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut data = String::new();
+        entry.read_to_string(&mut data)?;
+        entries.insert(name, data);
+    }
+    let find = |suffix: &str| {
+        entries
+            .iter()
+            .find(|(name, _)| name.ends_with(suffix))
+            .map(|(_, data)| data.as_str())
+    };
+    assert!(find("/system.txt").is_some_and(|info| info.contains("agent version")));
+    assert_eq!(find("/logs/app.log"), Some("disk full at 03:00\n"));
+    assert!(find("/manifest.txt").is_some());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn elevates_read_only_sessions() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start_with_args(
+        &proxy,
+        &["--read-only", "--elevation-password", "sudo", "--audit-log", "audit.log"],
+    )
+    .await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let file = server.root().join("notes.txt").to_string_lossy().to_string();
+    let write = || RpcAction::WriteFile {
+        path: file.clone(),
+        content: "hello".to_string(),
+        expected_hash: None,
+    };
+
+    let access = within(connection.request(RpcAction::SessionAccess)).await?;
+    let RpcResult::Access { access } = access else {
+        panic!("unexpected result {access:?}");
+    };
+    assert!(access.read_only);
+    assert_eq!(access.elevated_until, None);
+    let refused = within(connection.request(write())).await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+    let refused = within(connection.request(RpcAction::Broadcast {
+        message: "hello everyone".to_string(),
+    }))
+    .await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+    let listed = within(connection.request(RpcAction::ListRoots)).await?;
+    assert!(matches!(listed, RpcResult::Roots { .. }), "{listed:?}");
+
+    let wrong = within(connection.request(RpcAction::Elevate {
+        credential: "guess".to_string(),
+    }))
+    .await?;
+    assert!(matches!(wrong, RpcResult::Error { .. }), "{wrong:?}");
+    let elevated = within(connection.request(RpcAction::Elevate {
+        credential: "sudo".to_string(),
+    }))
+    .await?;
+    let RpcResult::Access { access } = elevated else {
+        panic!("unexpected result {elevated:?}");
+    };
+    assert!(access.elevated_until.is_some_and(|until| until > unix_now() + 500));
+    let written = within(connection.request(write())).await?;
+    assert!(matches!(written, RpcResult::WriteComplete { .. }), "{written:?}");
+
+    let ended = within(connection.request(RpcAction::EndElevation)).await?;
+    assert!(
+        matches!(ended, RpcResult::Access { access } if access.elevated_until.is_none()),
+        "{ended:?}"
+    );
+    let refused = within(connection.request(write())).await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+
+    let audit = std::fs::read_to_string(server.root().join("audit.log"))?;
+    let events = audit
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line)?;
+            Ok(entry["event"].as_str().unwrap_or_default().to_string())
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    assert_eq!(
+        events,
+        ["session_joined", "elevation_refused", "elevation_granted", "elevated_action", "elevation_ended"]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sets_permissions() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let script = server.root().join("deploy.sh").to_string_lossy().to_string();
+    within(connection.write_file(&script, "#!/bin/sh\necho deployed\n")).await?;
+
+    let updated = within(connection.request(RpcAction::SetPermissions {
+        path: script.clone(),
+        mode: 0o555,
+    }))
+    .await?;
+    let RpcResult::Stat { stat } = updated else {
+        panic!("unexpected result {updated:?}");
+    };
+    assert!(stat.readonly);
+    #[cfg(unix)]
+    {
+        assert_eq!(stat.mode, Some(0o555));
+        assert_eq!(stat.permissions, "r-xr-xr-x");
+    }
+
+    let updated = within(connection.request(RpcAction::SetPermissions {
+        path: script.clone(),
+        mode: 0o755,
+    }))
+    .await?;
+    let RpcResult::Stat { stat } = updated else {
+        panic!("unexpected result {updated:?}");
+    };
+    assert!(!stat.readonly);
+
+    let missing = within(connection.request(RpcAction::SetPermissions {
+        path: server.root().join("missing.sh").to_string_lossy().to_string(),
+        mode: 0o755,
+    }))
+    .await?;
+    assert!(matches!(missing, RpcResult::Error { .. }), "{missing:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn filters_directory_listings() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().join("listing");
+    std::fs::create_dir_all(root.join("node_modules"))?;
+    std::fs::create_dir_all(root.join("src"))?;
+    for name in [".env", "build.log", "notes.txt"] {
+        std::fs::write(root.join(name), name)?;
+    }
+
+    let list = |filter: ListFilter| {
+        let connection = &connection;
+        let path = root.to_string_lossy().to_string();
+        async move {
+            let listed = within(connection.request(RpcAction::ListDirectory { path, filter })).await?;
+            let RpcResult::DirectoryEntries { entries, .. } = listed else {
+                panic!("unexpected result {listed:?}");
+            };
+            let mut names: Vec<String> = entries.into_iter().map(|entry| entry.name).collect();
+            names.sort();
+            anyhow::Ok(names)
+        }
+    };
+
+    assert_eq!(
+        list(ListFilter::default()).await?,
+        [".env", "build.log", "node_modules", "notes.txt", "src"]
+    );
+    assert_eq!(
+        list(ListFilter {
+            hide_hidden: true,
+            excludes: vec!["node_modules".to_string(), "*.log".to_string()],
+        })
+        .await?,
+        ["notes.txt", "src"]
+    );
+    assert_eq!(
+        list(ListFilter {
+            hide_hidden: false,
+            excludes: vec!["n?tes.*".to_string()],
+        })
+        .await?,
+        [".env", "build.log", "node_modules", "src"]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexes_trees_for_quick_open() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().join("project");
+    std::fs::create_dir_all(root.join("src/nested"))?;
+    std::fs::create_dir_all(root.join("node_modules/left-pad"))?;
+    for file in ["main.py", "src/nested/util.py", "node_modules/left-pad/index.js"] {
+        std::fs::write(root.join(file), file)?;
+    }
+    let root = root.to_string_lossy().to_string();
+
+    let indexed = within(connection.request(RpcAction::IndexTree {
+        root: root.clone(),
+        filter: ListFilter {
+            hide_hidden: false,
+            excludes: vec!["node_modules".to_string()],
+        },
+    }))
+    .await?;
+    let RpcResult::TreeIndex {
+        root: indexed_root,
+        paths,
+        truncated,
+    } = indexed
+    else {
+        panic!("unexpected result {indexed:?}");
+    };
+    assert_eq!(indexed_root, root);
+    assert_eq!(paths, ["main.py", "src/nested/util.py"]);
+    assert!(!truncated);
+
+    let missing = within(connection.request(RpcAction::IndexTree {
+        root: format!("{root}/missing"),
+        filter: ListFilter::default(),
+    }))
+    .await?;
+    assert!(matches!(missing, RpcResult::Error { .. }), "{missing:?}");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn marks_symlinks_in_listings() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().join("links");
+    std::fs::create_dir_all(&root)?;
+    std::fs::write(root.join("target.txt"), "target")?;
+    std::os::unix::fs::symlink(root.join("target.txt"), root.join("link.txt"))?;
+
+    let listed = within(connection.request(RpcAction::ListDirectory {
+        path: root.to_string_lossy().to_string(),
+        filter: ListFilter::default(),
+    }))
+    .await?;
+    let RpcResult::DirectoryEntries { entries, .. } = listed else {
+        panic!("unexpected result {listed:?}");
+    };
+    let symlink = |name: &str| entries.iter().find(|entry| entry.name == name).map(|entry| entry.is_symlink);
+    assert_eq!(symlink("link.txt"), Some(true));
+    assert_eq!(symlink("target.txt"), Some(false));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reverses_deletes_and_renames()-> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("report.txt").to_string_lossy().to_string();
+    let moved = server.root().join("archive.txt").to_string_lossy().to_string();
+    within(connection.write_file(&path, "quarterly numbers")).await?;
+
+    let deleted = within(connection.request(RpcAction::DeletePath { path: path.clone() })).await?;
+    let RpcResult::Deleted { trash_path, hash, .. } = deleted else {
+        panic!("unexpected result {deleted:?}");
+    };
+    assert!(!server.root().join("report.txt").exists());
+    let restored = within(connection.request(RpcAction::RestorePath {
+        trash_path,
+        path: path.clone(),
+        expected_hash: hash,
+    }))
+    .await?;
+    assert!(matches!(restored, RpcResult::Restored { .. }), "{restored:?}");
+    assert_eq!(within(connection.read_file(&path)).await?, "quarterly numbers");
+
+    let renamed = within(connection.request(RpcAction::RenamePath {
+        from: path.clone(),
+        to: moved.clone(),
+        expected_hash: None,
+    }))
+    .await?;
+    let RpcResult::Renamed { hash: Some(hash), .. } = renamed else {
+        panic!("unexpected result {renamed:?}");
+    };
+    within(connection.write_file(&moved, "edited after the move")).await?;
+    let refused = within(connection.request(RpcAction::RenamePath {
+        from: moved.clone(),
+        to: path.clone(),
+        expected_hash: Some(hash),
+    }))
+    .await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+    assert_eq!(within(connection.read_file(&moved)).await?, "edited after the move");
+
+    let outside = within(connection.request(RpcAction::RestorePath {
+        trash_path: moved.clone(),
+        path: path.clone(),
+        expected_hash: None,
+    }))
+    .await?;
+    assert!(matches!(outside, RpcResult::Error { .. }), "{outside:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replaces_across_files() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().join("project");
+    let first = root.join("first.txt").to_string_lossy().to_string();
+    let second = root.join("nested").join("second.txt").to_string_lossy().to_string();
+    within(connection.write_file(&first, "let count = 1;\nprint(count)\n")).await?;
+    within(connection.write_file(&second, "Count: count\r\n")).await?;
+    let query = SearchQuery {
+        pattern: "count".to_string(),
+        regex: false,
+        case_sensitive: true,
+    };
+    let search = |query: SearchQuery| {
+        connection.request(RpcAction::SearchFiles {
+            root: root.to_string_lossy().to_string(),
+            query,
+            replacement: Some("total".to_string()),
+        })
+    };
+
+    let found = within(search(query.clone())).await?;
+    let RpcResult::SearchResults { files, truncated: false } = found else {
+        panic!("unexpected result {found:?}");
+    };
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path, first);
+    assert_eq!(files[0].matches.len(), 2);
+    assert_eq!(files[0].matches[1].preview.as_deref(), Some("print(total)"));
+    assert_eq!(files[1].matches.len(), 1);
+    let select_all = |files: &[rs_peer_workspace_shared::app::SearchFileMatches]| {
+        files
+            .iter()
+            .map(|file| ReplaceFile {
+                path: file.path.clone(),
+                hash: file.hash.clone(),
+                matches: file.matches.iter().map(|found| (found.line, found.start)).collect(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    within(connection.write_file(&second, "count changed meanwhile\n")).await?;
+    let refused = within(connection.request(RpcAction::ReplaceInFiles {
+        query: query.clone(),
+        replacement: "total".to_string(),
+        files: select_all(&files),
+    }))
+    .await?;
+    assert!(matches!(refused, RpcResult::Error { .. }), "{refused:?}");
+    assert_eq!(within(connection.read_file(&first)).await?, "let count = 1;\nprint(count)\n");
+
+    within(connection.write_file(&second, "Count: count\r\n")).await?;
+    let mut files = select_all(&files);
+    files[0].matches.truncate(1);
+    let replaced = within(connection.request(RpcAction::ReplaceInFiles {
+        query,
+        replacement: "total".to_string(),
+        files,
+    }))
+    .await?;
+    let RpcResult::Replaced { files } = replaced else {
+        panic!("unexpected result {replaced:?}");
+    };
+    assert_eq!(files.iter().map(|file| file.replacements).sum::<usize>(), 2);
+    assert_eq!(within(connection.read_file(&first)).await?, "let total = 1;\nprint(count)\n");
+    assert_eq!(within(connection.read_file(&second)).await?, "Count: total\r\n");
+
+    let regex = SearchQuery {
+        pattern: r"(\w+) = (\d+)".to_string(),
+        regex: true,
+        case_sensitive: false,
+    };
+    let found = within(search(regex.clone())).await?;
+    let RpcResult::SearchResults { files, .. } = found else {
+        panic!("unexpected result {found:?}");
+    };
+    let replaced = within(connection.request(RpcAction::ReplaceInFiles {
+        query: regex,
+        replacement: "$1 = $2 + 1".to_string(),
+        files: select_all(&files),
+    }))
+    .await?;
+    assert!(matches!(replaced, RpcResult::Replaced { .. }), "{replaced:?}");
+    assert_eq!(within(connection.read_file(&first)).await?, "let total = 1 + 1;\nprint(count)\n");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn automates_a_project_without_the_gui() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let mut connection = proxy.connection(server.name(), false);
+    connection.terminal_profiles.push(TerminalProfile {
+        name: "root".to_string(),
+        cwd: Some(server.root().to_string_lossy().to_string()),
+        ..Default::default()
+    });
+    let workspace = Workspace::from_project(
+        ProjectFile {
+            connections: vec![connection],
+            ..Default::default()
+        },
+        None,
+    )?;
+
+    let session = within(workspace.connect(server.name())).await?;
+    let path = server.root().join("task.txt").to_string_lossy().to_string();
+    within(session.write_file(&path, "task output")).await?;
+    assert_eq!(within(session.read_file(&path)).await?, "task output");
+
+    let profile = workspace
+        .terminal_profile(server.name(), "root")
+        .expect("profile exists");
+    let command = if cfg!(windows) { "type task.txt" } else { "cat task.txt" };
+    let result = within(session.run_in_profile(profile, command)).await?;
+    assert!(result.output.contains("task output"), "{}", result.output);
+    assert_eq!(result.exit_code, Some(0));
+
+    let failed = within(session.run_command("exit 3")).await?;
+    assert_eq!(failed.exit_code, Some(3));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sets_connection_and_profile_variables() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let mut connection = proxy.connection(server.name(), false);
+    connection.env = vec![
+        EnvVar {
+            name: "RPW_MODE".to_string(),
+            value: "debug".to_string(),
+            secret: false,
+        },
+        EnvVar {
+            name: "RPW_TOKEN".to_string(),
+            value: "s3cret".to_string(),
+            secret: true,
+        },
+    ];
+    let session = within(Connection::connect(connection)).await?;
+
+    let command = "echo \"$RPW_MODE $RPW_TOKEN\"";
+    let result = within(session.run_command(command)).await?;
+    assert_eq!(result.output.trim(), "debug s3cret");
+
+    let profile = TerminalProfile {
+        name: "release".to_string(),
+        env: vec![EnvVar {
+            name: "RPW_MODE".to_string(),
+            value: "release".to_string(),
+            secret: false,
+        }],
+        ..Default::default()
+    };
+    let result = within(session.run_in_profile(&profile, command)).await?;
+    assert_eq!(result.output.trim(), "release s3cret");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn attaches_images_from_command_output() -> anyhow::Result<()> {
+    use base64::Engine;
+
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let encode = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+    let mut image = b"\x89PNG\r\n\x1a\n".to_vec();
+    image.extend((0..200 * 1024).map(|idx| (idx % 251) as u8));
+    let outputs = [
+        ("large.txt", format!("before\x1b]1337;File=inline=1:{}\x07after\n", encode(&image))),
+        ("svg.txt", format!("\x1b]1337;File=inline=1:{}\x07", encode(b"<svg/>"))),
+        ("cut.txt", format!("before\x1b]1337;File=inline=1:{}", encode(&image[..64]))),
+    ];
+    let mut results = HashMap::new();
+    for (name, content) in &outputs {
+        let path = server.root().join(name);
+        std::fs::write(&path, content)?;
+        let result = within(connection.request(RpcAction::RunCommand {
+            command: format!("cat '{}'", path.display()),
+            shell: None,
+            cwd: None,
+            env: Vec::new(),
+        }))
+        .await?;
+        let RpcResult::CommandOutput { output, attachments, .. } = result else {
+            panic!("unexpected result {result:?}");
+        };
+        results.insert(*name, (output, attachments));
+    }
+
+    // Large images stay on the server until the client reads them in chunks.
+    let (output, attachments) = &results["large.txt"];
+    assert_eq!(output, "before[image]\nafter\n");
+    let [OutputAttachment::ImageFile { mime, path, size }] = attachments.as_slice() else {
+        panic!("unexpected attachments {attachments:?}");
+    };
+    assert_eq!((mime.as_str(), *size), ("image/png", image.len() as u64));
+    let chunk = within(connection.request(RpcAction::ReadFileChunk {
+        path: path.clone(),
+        offset: 0,
+        length: *size,
+    }))
+    .await?;
+    let RpcResult::FileChunk { data, .. } = chunk else {
+        panic!("unexpected result {chunk:?}");
+    };
+    assert!(data == image, "image bytes differ");
+    assert!(!std::path::Path::new(path).exists(), "image file was not deleted");
+
+    let (output, attachments) = &results["svg.txt"];
+    assert_eq!(output, "[image in an unsupported format]\n");
+    assert!(attachments.is_empty(), "{attachments:?}");
+
+    // An escape that never ends is left in the output as it was.
+    let (output, attachments) = &results["cut.txt"];
+    assert_eq!(output, &outputs[2].1);
+    assert!(attachments.is_empty(), "{attachments:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancels_a_running_command() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut events = connection.subscribe_events();
+    let command_id = uuid::Uuid::new_v4();
+    let started = std::time::Instant::now();
+    connection.send_rpc(RpcRequest {
+        request_id: command_id,
+        action: RpcAction::RunCommand {
+            command: "sleep 30".to_string(),
+            shell: None,
+            cwd: None,
+            env: Vec::new(),
+        },
+    })?;
+
+    // The command may not have started when the first cancel arrives.
+    within(async {
+        loop {
+            match connection.request(RpcAction::CancelCommand { command_id }).await? {
+                RpcResult::CommandCancelled { command_id: id } if id == command_id => return Ok(()),
+                RpcResult::Error { .. } => tokio::time::sleep(std::time::Duration::from_millis(50)).await,
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    })
+    .await?;
+    let result = within(async {
+        loop {
+            if let ConnectionEvent::RpcResponse { response, .. } = events.recv().await?
+                && response.request_id == command_id
+            {
+                return Ok(response.result);
+            }
+        }
+    })
+    .await?;
+    assert!(
+        matches!(&result, RpcResult::Error { message } if message == "command cancelled"),
+        "{result:?}"
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(30));
+
+    let missing = within(connection.request(RpcAction::CancelCommand { command_id })).await?;
+    assert!(matches!(missing, RpcResult::Error { .. }), "{missing:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_and_commits_git_changes() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let root = server.root().join("repo");
+    std::fs::create_dir_all(&root)?;
+    let path = root.to_string_lossy().to_string();
+
+    let status = within(connection.request(RpcAction::GitStatus { path: path.clone() })).await?;
+    assert!(matches!(status, RpcResult::GitStatus { status: None, .. }), "{status:?}");
+
+    let mut config = git2::Repository::init(&root)?.config()?;
+    config.set_str("user.name", "Test User")?;
+    config.set_str("user.email", "test@example.com")?;
+    std::fs::write(root.join("readme.md"), "hello\n")?;
+
+    let status = within(connection.request(RpcAction::GitStatus { path: path.clone() })).await?;
+    let RpcResult::GitStatus { status: Some(status), .. } = status else {
+        panic!("unexpected result {status:?}");
+    };
+    assert_eq!(status.files.len(), 1);
+    assert_eq!(status.files[0].path, "readme.md");
+
+    let diff = within(connection.request(RpcAction::GitDiff {
+        path: path.clone(),
+        file: Some("readme.md".to_string()),
+    }))
+    .await?;
+    assert!(
+        matches!(&diff, RpcResult::GitDiff { diff, .. } if diff.contains("+hello")),
+        "{diff:?}"
+    );
+
+    let committed = within(connection.request(RpcAction::GitCommit {
+        path: path.clone(),
+        message: "Add readme".to_string(),
+        files: vec!["readme.md".to_string()],
+    }))
+    .await?;
+    assert!(matches!(committed, RpcResult::GitCommitted { .. }), "{committed:?}");
+    let status = within(connection.request(RpcAction::GitStatus { path })).await?;
+    assert!(
+        matches!(&status, RpcResult::GitStatus { status: Some(status), .. } if status.files.is_empty()),
+        "{status:?}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relays_a_language_server() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut events = connection.subscribe_events();
+    let server_id = uuid::Uuid::new_v4();
+    // `cat` echoes every frame back, like a server answering each message.
+    connection.send_rpc(RpcRequest {
+        request_id: server_id,
+        action: RpcAction::StartLanguageServer {
+            command: "cat".to_string(),
+            args: Vec::new(),
+            root: server.root().to_string_lossy().to_string(),
+        },
+    })?;
+
+    let messages = [
+        lsp::request(1, "initialize", serde_json::json!({})),
+        lsp::notification("initialized", serde_json::json!({})),
+    ];
+    let mut echoed = Vec::new();
+    within(async {
+        loop {
+            let ConnectionEvent::RpcResponse { response, .. } = events.recv().await? else {
+                continue;
+            };
+            if response.request_id != server_id {
+                continue;
+            }
+            match response.result {
+                RpcResult::LanguageServerStarted { .. } => {
+                    // Sent last first; the server writes them in sequence order.
+                    for (seq, message) in messages.iter().enumerate().rev() {
+                        let sent = connection
+                            .request(RpcAction::LspSend {
+                                server_id,
+                                seq: seq as u64,
+                                message: message.clone(),
+                            })
+                            .await?;
+                        assert!(matches!(sent, RpcResult::LspSent { .. }), "{sent:?}");
+                    }
+                }
+                RpcResult::LspMessage { seq, message, .. } => {
+                    echoed.push((seq, message));
+                    if echoed.len() == messages.len() {
+                        return Ok(());
+                    }
+                }
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    })
+    .await?;
+    assert_eq!(
+        echoed,
+        vec![(0, messages[0].clone()), (1, messages[1].clone())]
+    );
+
+    let stopped = within(connection.request(RpcAction::StopLanguageServer { server_id })).await?;
+    assert!(matches!(stopped, RpcResult::LanguageServerStopped { .. }), "{stopped:?}");
+    let sent = within(connection.request(RpcAction::LspSend {
+        server_id,
+        seq: 2,
+        message: messages[1].clone(),
+    }))
+    .await?;
+    assert!(matches!(sent, RpcResult::Error { .. }), "{sent:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn formats_files_with_rustfmt() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let path = server.root().join("main.rs");
+    std::fs::write(&path, "fn main( ){let x=1;}")?;
+    let path = path.to_string_lossy().to_string();
+
+    let formatted = within(connection.request(RpcAction::FormatFile {
+        path: path.clone(),
+        formatter: Formatter::Rustfmt,
+        content: None,
+    }))
+    .await?;
+    assert!(
+        matches!(&formatted, RpcResult::FileFormatted { content, .. } if content == "fn main() {\n    let x = 1;\n}\n"),
+        "{formatted:?}"
+    );
+    // The file itself is left alone.
+    assert_eq!(std::fs::read_to_string(&path)?, "fn main( ){let x=1;}");
+
+    let unsaved = within(connection.request(RpcAction::FormatFile {
+        path: path.clone(),
+        formatter: Formatter::Rustfmt,
+        content: Some("fn f(){}".to_string()),
+    }))
+    .await?;
+    assert!(
+        matches!(&unsaved, RpcResult::FileFormatted { content, .. } if content == "fn f() {}\n"),
+        "{unsaved:?}"
+    );
+
+    let broken = within(connection.request(RpcAction::FormatFile {
+        path,
+        formatter: Formatter::Rustfmt,
+        content: Some("fn {".to_string()),
+    }))
+    .await?;
+    assert!(matches!(broken, RpcResult::Error { .. }), "{broken:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn forwards_ports_both_ways() -> anyhow::Result<()> {
+    let proxy = TestProxy::start().await?;
+    let server = TestServer::start(&proxy).await?;
+    let echo = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let echo_port = echo.local_addr()?.port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = echo.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.into_split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                let _ = writer.shutdown().await;
+            });
+        }
+    });
+    let connection = within(Connection::connect(proxy.connection(server.name(), false))).await?;
+    let mut events = connection.subscribe_events();
+    let payload: Vec<u8> = (0..200_000u32).map(|idx| (idx % 251) as u8).collect();
+
+    for direction in [ForwardDirection::Local, ForwardDirection::Remote] {
+        let forward = PortForward {
+            id: uuid::Uuid::new_v4(),
+            connection_name: server.name().to_string(),
+            direction,
+            listen_port: 0,
+            host: "127.0.0.1".to_string(),
+            port: echo_port,
+            enabled: true,
+        };
+        connection.start_forward(forward.clone())?;
+        let port = within(forward_port(&mut events, forward.id)).await?;
+
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        stream.write_all(&payload).await?;
+        stream.shutdown().await?;
+        let mut echoed = Vec::new();
+        within(async { Ok(stream.read_to_end(&mut echoed).await?) }).await?;
+        assert!(echoed == payload, "{direction:?} forward echoed {} bytes", echoed.len());
+
+        connection.stop_forward(forward.id)?;
+        within(async {
+            loop {
+                if let ConnectionEvent::Forwards { forwards, .. } = events.recv().await?
+                    && !forwards.contains_key(&forward.id)
+                {
+                    return Ok(());
+                }
+            }
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Waits until the forward `id` listens, returning its port.
+async fn forward_port(
+    events: &mut broadcast::Receiver<ConnectionEvent>,
+    id: uuid::Uuid,
+) -> anyhow::Result<u16> {
+    loop {
+        if let ConnectionEvent::Forwards { forwards, .. } = events.recv().await?
+            && let Some(state) = forwards.get(&id)
+        {
+            match &state.status {
+                ForwardStatus::Listening { port } => return Ok(*port),
+                ForwardStatus::Failed(message) => anyhow::bail!("forward failed: {message}"),
+                ForwardStatus::Starting => {}
+            }
+        }
+    }
+}
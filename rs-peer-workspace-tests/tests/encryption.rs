@@ -0,0 +1,86 @@
+use rs_peer_workspace_shared::e2e::{self, KeyExchange, KeyPair, SessionCipher};
+use rs_peer_workspace_shared::identity::{self, IdentityChallenge, ServerKey};
+use rs_peer_workspace_shared::relay::AuthRole;
+use uuid::Uuid;
+
+fn session_pair(session_id: Uuid) -> anyhow::Result<(SessionCipher, SessionCipher)> {
+    let client = KeyPair::generate()?;
+    let server = KeyPair::generate()?;
+    let client_key = client.public_key();
+    let server_key = server.public_key();
+    Ok((
+        client.agree(&server_key, session_id, &AuthRole::Client)?,
+        server.agree(&client_key, session_id, &AuthRole::Server)?,
+    ))
+}
+
+#[test]
+fn seals_and_opens_in_both_directions() -> anyhow::Result<()> {
+    let (mut client, mut server) = session_pair(Uuid::new_v4())?;
+    assert_eq!(client.exchange(), server.exchange());
+
+    let sealed = client.seal(b"{\"kind\":\"rpc_request\"}")?;
+    assert!(e2e::is_sealed(&sealed));
+    assert!(!sealed.windows(8).any(|window| window == b"rpc_requ"));
+    assert_eq!(server.open(&sealed)?, b"{\"kind\":\"rpc_request\"}");
+
+    let reply = server.seal(b"response")?;
+    assert_eq!(client.open(&reply)?, b"response");
+    // Each direction has its own key, so a payload cannot be reflected to its sender.
+    assert!(server.open(&reply).is_err());
+    assert!(!e2e::is_sealed(b"{\"message_id\":\"\"}"));
+    Ok(())
+}
+
+#[test]
+fn refuses_replayed_reordered_and_tampered_payloads() -> anyhow::Result<()> {
+    let (mut client, mut server) = session_pair(Uuid::new_v4())?;
+    let first = client.seal(b"first")?;
+    let second = client.seal(b"second")?;
+    let third = client.seal(b"third")?;
+
+    assert_eq!(server.open(&second)?, b"second");
+    assert!(server.open(&second).is_err(), "replayed payload was accepted");
+    assert!(server.open(&first).is_err(), "reordered payload was accepted");
+
+    let mut tampered = third.clone();
+    *tampered.last_mut().expect("sealed payload is not empty") ^= 1;
+    assert!(server.open(&tampered).is_err());
+    assert_eq!(server.open(&third)?, b"third");
+    assert!(server.open(b"plain").is_err());
+
+    let (_, mut other_session) = session_pair(Uuid::new_v4())?;
+    let fourth = client.seal(b"fourth")?;
+    assert!(other_session.open(&fourth).is_err());
+    Ok(())
+}
+
+#[test]
+fn identity_proofs_sign_the_key_exchange() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("identity-{}.key", Uuid::new_v4()));
+    let key = ServerKey::load_or_create(&path)?;
+    let session_id = Uuid::new_v4();
+    let challenge = IdentityChallenge {
+        nonce: Uuid::new_v4().to_string(),
+    };
+    let (client, _) = session_pair(session_id)?;
+    let exchange = client.exchange().clone();
+
+    let proof = key.prove(session_id, &challenge, Some(&exchange));
+    assert_eq!(
+        identity::verify(&proof, session_id, &challenge, Some(&exchange))?,
+        key.fingerprint()
+    );
+    // A relay that strips or swaps the keys cannot make the proof match.
+    assert!(identity::verify(&proof, session_id, &challenge, None).is_err());
+    let swapped = KeyExchange {
+        client_key: exchange.client_key.clone(),
+        server_key: KeyPair::generate()?.public_key(),
+    };
+    assert!(identity::verify(&proof, session_id, &challenge, Some(&swapped)).is_err());
+
+    let plain = key.prove(session_id, &challenge, None);
+    assert!(identity::verify(&plain, session_id, &challenge, None).is_ok());
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use rs_peer_workspace_shared::app::{merge_env, EnvVar};
+use rs_peer_workspace_shared::project::{ProjectConnection, ProjectFile, RunConfig};
+use rs_peer_workspace_shared::secrets::{seal_credentials, unseal_credentials, EncryptedCredentials};
+
+fn var(name: &str, value: &str, secret: bool) -> EnvVar {
+    EnvVar {
+        name: name.to_string(),
+        value: value.to_string(),
+        secret,
+    }
+}
+
+#[test]
+fn overrides_replace_base_variables() {
+    let base = [var("MODE", "debug", false), var("TOKEN", "abc", true)];
+    let overrides = [var("MODE", "release", false), var("EXTRA", "1", false)];
+    let merged = merge_env(&base, &overrides);
+    assert_eq!(
+        merged,
+        vec![var("TOKEN", "abc", true), var("MODE", "release", false), var("EXTRA", "1", false)]
+    );
+    assert_eq!(merge_env(&base, &[]), base.to_vec());
+}
+
+#[test]
+fn secret_variables_are_sealed() {
+    let connection = ProjectConnection {
+        name: "demo".to_string(),
+        proxy_addr: "ws://127.0.0.1:9000/ws".to_string(),
+        proxy_username: String::new(),
+        proxy_password: "proxy-pass".to_string(),
+        server_name: "demo".to_string(),
+        server_password: "server-pass".to_string(),
+        prefer_p2p: true,
+        require_e2e: false,
+        credential_ref: None,
+        server_fingerprint: None,
+        terminal_profiles: Vec::new(),
+        outbound_proxy: None,
+        ice_servers: Vec::new(),
+        env: vec![var("MODE", "debug", false), var("TOKEN", "abc", true)],
+    };
+    let run_config = RunConfig {
+        name: "serve".to_string(),
+        connection_name: "demo".to_string(),
+        command: "cargo run".to_string(),
+        cwd: None,
+        env: vec![var("PORT", "8080", false), var("API_KEY", "xyz", true)],
+    };
+    let project = ProjectFile {
+        connections: vec![connection],
+        run_configs: vec![run_config],
+        ..Default::default()
+    };
+
+    let mut sealed = seal_credentials(&project, "passphrase").unwrap();
+    assert_eq!(
+        sealed.connections[0].env,
+        vec![var("MODE", "debug", false), var("TOKEN", "", true)]
+    );
+    assert_eq!(
+        sealed.run_configs[0].env,
+        vec![var("PORT", "8080", false), var("API_KEY", "", true)]
+    );
+
+    unseal_credentials(&mut sealed, "passphrase").unwrap();
+    assert_eq!(sealed.connections[0].env, project.connections[0].env);
+    assert_eq!(sealed.run_configs, project.run_configs);
+}
+
+#[test]
+fn refuses_excessive_key_derivation_costs() {
+    let sealed = seal_credentials(&ProjectFile::default(), "passphrase").unwrap();
+    let tampers: [fn(&mut EncryptedCredentials); 3] = [
+        |costs| costs.m_cost = u32::MAX,
+        |costs| costs.t_cost = u32::MAX,
+        |costs| costs.p_cost = 1024,
+    ];
+    for tamper in tampers {
+        let mut tampered = sealed.clone();
+        tamper(tampered.encrypted_credentials.as_mut().unwrap());
+        let err = unseal_credentials(&mut tampered, "passphrase").unwrap_err();
+        assert!(err.to_string().contains("exceed"), "{err:#}");
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use rs_peer_workspace_shared::format::{self, cargo_edition, Formatter};
+use rs_peer_workspace_shared::project::FormatSettings;
+use uuid::Uuid;
+
+#[test]
+fn picks_formatters_by_extension() {
+    assert_eq!(Formatter::for_path("/srv/app/src/main.rs"), Some(Formatter::Rustfmt));
+    assert_eq!(Formatter::for_path("C:\\app\\tool.PY"), Some(Formatter::Black));
+    assert_eq!(Formatter::for_path("web/app.tsx"), Some(Formatter::Prettier));
+    assert_eq!(Formatter::for_path("notes.txt"), None);
+    assert_eq!(Formatter::for_path("Makefile"), None);
+
+    let mut settings = FormatSettings::default();
+    assert_eq!(settings.local_program(Formatter::Black), "black");
+    settings
+        .local_paths
+        .insert(Formatter::Black, "/opt/venv/bin/black".to_string());
+    assert_eq!(settings.local_program(Formatter::Black), "/opt/venv/bin/black");
+}
+
+#[test]
+fn reads_the_edition_of_the_nearest_manifest() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("format-{}", Uuid::new_v4()));
+    let member = dir.join("member");
+    fs::create_dir_all(member.join("src"))?;
+    fs::write(dir.join("Cargo.toml"), "[workspace.package]\nedition = \"2024\"\n")?;
+    fs::write(member.join("Cargo.toml"), "[package]\nname = \"member\"\nedition.workspace = true\n")?;
+    assert_eq!(cargo_edition(&member.join("src/main.rs")).as_deref(), Some("2024"));
+
+    fs::write(member.join("Cargo.toml"), "[package]\nname = \"member\"\nedition = \"2021\"\n")?;
+    assert_eq!(cargo_edition(&member.join("src/main.rs")).as_deref(), Some("2021"));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn pipes_content_through_the_program() -> anyhow::Result<()> {
+    // Outside a crate rustfmt gets no arguments, so `cat` can stand in for it as a
+    // formatter that leaves the content as it is.
+    let path = std::env::temp_dir().join(format!("{}.rs", Uuid::new_v4()));
+    let content = "const A: u8 = 1;\n".repeat(20_000);
+    assert_eq!(format::format(Formatter::Rustfmt, "cat", &path, &content)?, content);
+
+    let failed = format::format(Formatter::Rustfmt, "false", &path, "x").unwrap_err();
+    assert!(failed.to_string().contains("false exited"), "{failed}");
+    let missing = format::format(Formatter::Rustfmt, "no-such-formatter", &path, "x").unwrap_err();
+    assert!(missing.to_string().contains("failed to start"), "{missing}");
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+
+use rs_peer_workspace_shared::app::{GitFileState, GitFileStatus};
+use rs_peer_workspace_shared::git::{self, LineChange, LineChangeKind};
+use uuid::Uuid;
+
+fn init_repo(dir: &Path) -> anyhow::Result<()> {
+    let repo = git2::Repository::init(dir)?;
+    let mut config = repo.config()?;
+    config.set_str("user.name", "Test User")?;
+    config.set_str("user.email", "test@example.com")?;
+    Ok(())
+}
+
+#[test]
+fn reports_and_commits_changes() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("git-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir)?;
+    assert_eq!(git::status(&dir)?, None);
+
+    init_repo(&dir)?;
+    fs::write(dir.join("notes.txt"), "one\n")?;
+    let status = git::status(&dir)?.expect("repository");
+    assert!(status.branch.is_some());
+    assert_eq!(
+        status.files,
+        vec![GitFileStatus {
+            path: "notes.txt".to_string(),
+            state: GitFileState::Untracked,
+        }]
+    );
+
+    assert!(git::commit(&dir, "  ", &[]).is_err());
+    let first = git::commit(&dir, "Initial commit", &[])?;
+    assert_eq!(first.len(), 7);
+    assert!(git::status(&dir)?.expect("repository").files.is_empty());
+
+    fs::write(dir.join("notes.txt"), "two\n")?;
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+    let status = git::status(&dir.join("src"))?.expect("repository");
+    assert_eq!(fs::canonicalize(&status.root)?, fs::canonicalize(&dir)?);
+    assert_eq!(
+        status.files,
+        vec![
+            GitFileStatus {
+                path: "notes.txt".to_string(),
+                state: GitFileState::Modified,
+            },
+            GitFileStatus {
+                path: "src/main.rs".to_string(),
+                state: GitFileState::Untracked,
+            },
+        ]
+    );
+
+    let diff = git::diff(&dir, Some("notes.txt"))?;
+    assert!(diff.contains("-one\n"), "{diff}");
+    assert!(diff.contains("+two\n"), "{diff}");
+    assert!(!diff.contains("main.rs"), "{diff}");
+
+    git::commit(&dir, "Update notes", &["notes.txt".to_string()])?;
+    let status = git::status(&dir)?.expect("repository");
+    assert_eq!(
+        status.files,
+        vec![GitFileStatus {
+            path: "src/main.rs".to_string(),
+            state: GitFileState::Untracked,
+        }]
+    );
+
+    fs::remove_file(dir.join("notes.txt"))?;
+    let status = git::status(&dir)?.expect("repository");
+    assert!(status.files.contains(&GitFileStatus {
+        path: "notes.txt".to_string(),
+        state: GitFileState::Deleted,
+    }));
+    git::commit(&dir, "Remove notes", &["notes.txt".to_string()])?;
+    assert_eq!(git::status(&dir)?.expect("repository").files.len(), 1);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn maps_a_diff_to_gutter_markers() {
+    let patch = "\
+diff --git a/main.rs b/main.rs
+--- a/main.rs
++++ b/main.rs
+@@ -1,6 +1,7 @@
+ fn main() {
+-    println!(\"one\");
++    println!(\"two\");
++    println!(\"three\");
+     let x = 1;
+-    let y = 2;
+     x
+ }
+@@ -10,0 +11,2 @@ fn helper() {
++// added
++// lines
+";
+    assert_eq!(
+        git::line_changes(patch),
+        vec![
+            LineChange {
+                line: 1,
+                kind: LineChangeKind::Modified,
+            },
+            LineChange {
+                line: 2,
+                kind: LineChangeKind::Added,
+            },
+            LineChange {
+                line: 4,
+                kind: LineChangeKind::Deleted,
+            },
+            LineChange {
+                line: 10,
+                kind: LineChangeKind::Added,
+            },
+            LineChange {
+                line: 11,
+                kind: LineChangeKind::Added,
+            },
+        ]
+    );
+
+    let removed_at_end = "@@ -3,2 +2,0 @@\n-a\n-b\n";
+    assert_eq!(
+        git::line_changes(removed_at_end),
+        vec![LineChange {
+            line: 2,
+            kind: LineChangeKind::Deleted,
+        }]
+    );
+}
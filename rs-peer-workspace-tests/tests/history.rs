@@ -0,0 +1,52 @@
+use rs_peer_workspace_shared::history::{CommandHistory, MAX_HISTORY};
+
+fn history(commands: &[&str]) -> CommandHistory {
+    let mut history = CommandHistory::default();
+    for command in commands {
+        history.push(command);
+    }
+    history
+}
+
+#[test]
+fn recalls_commands_and_the_draft() {
+    let mut history = history(&["ls", "cargo build", "cargo test"]);
+    assert_eq!(history.newer(), None);
+    assert_eq!(history.older("git st"), Some("cargo test"));
+    assert_eq!(history.older("ignored"), Some("cargo build"));
+    assert_eq!(history.older(""), Some("ls"));
+    assert_eq!(history.older(""), None);
+    assert_eq!(history.newer(), Some("cargo build"));
+    assert_eq!(history.newer(), Some("cargo test"));
+    assert_eq!(history.newer(), Some("git st"));
+    assert_eq!(history.newer(), None);
+
+    history.older("");
+    history.push("ls");
+    assert_eq!(history.entries(), ["cargo build", "cargo test", "ls"]);
+    assert_eq!(history.older(""), Some("ls"));
+}
+
+#[test]
+fn keeps_the_newest_commands() {
+    let mut history = history(&["  ", "first"]);
+    assert_eq!(history.entries(), ["first"]);
+    for idx in 0..MAX_HISTORY {
+        history.push(&format!("echo {idx}"));
+    }
+    assert_eq!(history.entries().len(), MAX_HISTORY);
+    assert_eq!(history.entries()[0], "echo 0");
+
+    let restored = CommandHistory::from_entries((0..MAX_HISTORY + 2).map(|idx| idx.to_string()).collect());
+    assert_eq!(restored.entries().len(), MAX_HISTORY);
+    assert_eq!(restored.entries()[0], "2");
+}
+
+#[test]
+fn searches_best_and_newest_first() {
+    let history = history(&["cargo test", "ls -la", "cargo build", "git status"]);
+    assert_eq!(history.search(""), ["git status", "cargo build", "ls -la", "cargo test"]);
+    assert_eq!(history.search("cargo"), ["cargo build", "cargo test"]);
+    assert_eq!(history.search("ctst")[0], "cargo test");
+    assert!(history.search("xyz").is_empty());
+}
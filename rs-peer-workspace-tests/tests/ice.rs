@@ -0,0 +1,49 @@
+use rs_peer_workspace_shared::project::{IceServer, ProjectConnection, ProjectFile};
+use rs_peer_workspace_shared::secrets::{seal_credentials, unseal_credentials};
+
+#[test]
+fn ice_server_urls_are_parsed_and_checked() {
+    let server = IceServer::parse("TURN:alice:s3cret@relay.example.com:3478?transport=tcp").unwrap();
+    assert_eq!(server.url, "turn:relay.example.com:3478?transport=tcp");
+    assert_eq!(server.username, "alice");
+    assert_eq!(server.password, "s3cret");
+
+    let server = IceServer::parse("stun:stun.example.com:19302").unwrap();
+    assert!(server.username.is_empty());
+
+    assert!(IceServer::parse("turn:relay.example.com:3478").is_err());
+    assert!(IceServer::parse("http://relay.example.com").is_err());
+    assert!(IceServer::parse("stun:").is_err());
+    assert!(IceServer::parse("relay.example.com").is_err());
+}
+
+#[test]
+fn ice_server_passwords_are_sealed() {
+    let connection = ProjectConnection {
+        name: "demo".to_string(),
+        proxy_addr: "ws://127.0.0.1:9000/ws".to_string(),
+        proxy_username: String::new(),
+        proxy_password: "proxy-pass".to_string(),
+        server_name: "demo".to_string(),
+        server_password: "server-pass".to_string(),
+        prefer_p2p: true,
+        require_e2e: false,
+        credential_ref: None,
+        server_fingerprint: None,
+        terminal_profiles: Vec::new(),
+        outbound_proxy: None,
+        ice_servers: vec![IceServer::parse("turn:bob:relay-pass@relay.example.com:3478").unwrap()],
+        env: Vec::new(),
+    };
+    let project = ProjectFile {
+        connections: vec![connection],
+        ..Default::default()
+    };
+
+    let mut sealed = seal_credentials(&project, "passphrase").unwrap();
+    assert!(sealed.connections[0].ice_servers[0].password.is_empty());
+    assert_eq!(sealed.connections[0].ice_servers[0].username, "bob");
+
+    unseal_credentials(&mut sealed, "passphrase").unwrap();
+    assert_eq!(sealed.connections[0].ice_servers[0].password, "relay-pass");
+}
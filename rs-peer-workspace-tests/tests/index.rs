@@ -0,0 +1,45 @@
+use rs_peer_workspace_shared::app::ListFilter;
+use rs_peer_workspace_shared::index::{fuzzy_match, index_tree};
+
+#[test]
+fn fuzzy_match_requires_every_query_character_in_order() {
+    assert!(fuzzy_match("mrs", "src/main.rs").is_some());
+    assert!(fuzzy_match("MAIN", "src/main.rs").is_some());
+    assert!(fuzzy_match("srm", "src/main.rs").is_some());
+    assert!(fuzzy_match("rsm", "src/main.rs").is_none());
+    assert_eq!(fuzzy_match("main", "src/main.rs").unwrap().positions, [4, 5, 6, 7]);
+}
+
+#[test]
+fn fuzzy_match_prefers_file_names_and_word_starts() {
+    let score = |candidate: &str| fuzzy_match("main", candidate).unwrap().score;
+    assert!(score("src/main.rs") > score("domain/lib.rs"));
+    assert!(score("src/main.rs") > score("maintenance/docs/index.md"));
+    assert!(score("src/main.rs") > score("src/very/deeply/nested/module/main.rs"));
+}
+
+#[test]
+fn index_tree_lists_files_relative_to_the_root() -> anyhow::Result<()> {
+    let root = std::env::temp_dir().join(format!("rpw-index-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("src/app"))?;
+    std::fs::create_dir_all(root.join(".git/objects"))?;
+    std::fs::create_dir_all(root.join("target"))?;
+    for file in ["README.md", ".env", "src/main.rs", "src/app/state.rs", ".git/HEAD", "target/out.o"] {
+        std::fs::write(root.join(file), file)?;
+    }
+
+    let (paths, truncated) = index_tree(&root, &ListFilter::default())?;
+    assert!(!truncated);
+    assert_eq!(paths, [".env", "README.md", "src/app/state.rs", "src/main.rs", "target/out.o"]);
+
+    let filter = ListFilter {
+        hide_hidden: true,
+        excludes: vec!["target".to_string()],
+    };
+    let (paths, _) = index_tree(&root, &filter)?;
+    assert_eq!(paths, ["README.md", "src/app/state.rs", "src/main.rs"]);
+
+    assert!(index_tree(&root.join("README.md"), &filter).is_err());
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
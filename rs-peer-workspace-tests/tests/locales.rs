@@ -0,0 +1,38 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use fluent_syntax::ast::Entry;
+use fluent_syntax::parser;
+
+fn locales_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../rs-peer-workspace-client/locales")
+}
+
+fn message_ids(locale: &str) -> BTreeSet<String> {
+    let path = locales_dir().join(locale).join("client.ftl");
+    let source = std::fs::read_to_string(&path).expect("read locale file");
+    let resource = parser::parse(source.as_str())
+        .unwrap_or_else(|(_, errors)| panic!("{} has syntax errors: {errors:?}", path.display()));
+    resource
+        .body
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Entry::Message(message) => Some(message.id.name.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn every_locale_translates_every_message() {
+    let english = message_ids("en-US");
+    assert!(!english.is_empty());
+    for entry in std::fs::read_dir(locales_dir()).expect("list locales") {
+        let locale = entry.expect("locale entry").file_name().to_string_lossy().to_string();
+        let ids = message_ids(&locale);
+        let missing: Vec<_> = english.difference(&ids).collect();
+        let extra: Vec<_> = ids.difference(&english).collect();
+        assert!(missing.is_empty(), "{locale} is missing {missing:?}");
+        assert!(extra.is_empty(), "{locale} has unknown messages {extra:?}");
+    }
+}
@@ -0,0 +1,111 @@
+use rs_peer_workspace_shared::lsp::{self, Incoming, LineIndex, Position, Severity};
+use serde_json::json;
+
+#[test]
+fn frames_and_splits_messages() -> anyhow::Result<()> {
+    let first = lsp::request(1, "initialize", json!({}));
+    let second = lsp::notification("initialized", json!({}));
+    assert!(String::from_utf8(lsp::encode(&first))?.starts_with(&format!("Content-Length: {}\r\n\r\n", first.len())));
+
+    let mut stream = lsp::encode(&first);
+    stream.extend(lsp::encode(&second));
+    let mut reader = lsp::FrameReader::default();
+    let (head, tail) = stream.split_at(10);
+    assert!(reader.push(head)?.is_empty());
+    assert_eq!(reader.push(tail)?, vec![first, second]);
+
+    assert!(lsp::FrameReader::default().push(b"Bogus: 1\r\n\r\n{}").is_err());
+    Ok(())
+}
+
+#[test]
+fn parses_incoming_messages() -> anyhow::Result<()> {
+    assert_eq!(
+        lsp::parse(r#"{"jsonrpc":"2.0","id":3,"result":{"ok":true}}"#)?,
+        Incoming::Response {
+            id: 3,
+            result: Ok(json!({ "ok": true })),
+        }
+    );
+    assert_eq!(
+        lsp::parse(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32601,"message":"no such method"}}"#)?,
+        Incoming::Response {
+            id: 4,
+            result: Err("no such method".to_string()),
+        }
+    );
+    let Incoming::Request { id, method, params } = lsp::parse(
+        r#"{"jsonrpc":"2.0","id":"c1","method":"workspace/configuration","params":{"items":[{},{}]}}"#,
+    )?
+    else {
+        panic!("expected a request");
+    };
+    let reply: serde_json::Value = serde_json::from_str(&lsp::reply(&id, &method, &params))?;
+    assert_eq!(reply["id"], "c1");
+    assert_eq!(reply["result"], json!([null, null]));
+    assert!(lsp::parse(r#"{"jsonrpc":"2.0"}"#).is_err());
+    Ok(())
+}
+
+#[test]
+fn converts_positions_and_uris() {
+    let text = "fn main() {\n    let s = \"😀x\";\n}";
+    let index = text.chars().position(|ch| ch == 'x').unwrap();
+    let position = Position::from_index(text, index);
+    // The emoji counts as two UTF-16 code units.
+    assert_eq!(position, Position { line: 1, character: 15 });
+    assert_eq!(position.to_index(text), index);
+    let lines = LineIndex::new(text);
+    assert_eq!(lines.index(Position { line: 0, character: 99 }), 11);
+    assert_eq!(lines.index(Position { line: 9, character: 0 }), text.chars().count());
+
+    assert_eq!(lsp::file_uri("/srv/my app/main.rs"), "file:///srv/my%20app/main.rs");
+    assert_eq!(lsp::uri_path("file:///srv/my%20app/main.rs").as_deref(), Some("/srv/my app/main.rs"));
+    assert_eq!(lsp::file_uri("C:\\work\\main.rs"), "file:///C:/work/main.rs");
+    assert_eq!(lsp::uri_path("file:///C:/work/main.rs").as_deref(), Some("C:/work/main.rs"));
+    assert_eq!(lsp::server_for_path("/srv/app/main.rs").map(|spec| spec.name), Some("rust-analyzer"));
+    assert_eq!(lsp::server_for_path("C:\\app\\tool.PY").map(|spec| spec.name), Some("pyright"));
+    assert!(lsp::server_for_path("/srv/app/README").is_none());
+}
+
+#[test]
+fn reads_diagnostics_hover_and_definitions() {
+    let range = json!({ "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 9 } });
+    let (path, diagnostics) = lsp::parse_diagnostics(&json!({
+        "uri": "file:///srv/app/main.rs",
+        "diagnostics": [
+            { "range": range, "severity": 2, "message": "unused variable", "source": "rustc" },
+            { "range": range, "message": "mismatched types" },
+        ],
+    }))
+    .expect("diagnostics");
+    assert_eq!(path, "/srv/app/main.rs");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].source.as_deref(), Some("rustc"));
+    assert_eq!(diagnostics[1].severity, Severity::Error);
+
+    assert_eq!(
+        lsp::hover_text(&json!({ "contents": { "kind": "markdown", "value": "fn main()" } })).as_deref(),
+        Some("fn main()")
+    );
+    assert_eq!(
+        lsp::hover_text(&json!({ "contents": ["a", { "language": "rust", "value": "b" }] })).as_deref(),
+        Some("a\n\nb")
+    );
+    assert_eq!(lsp::hover_text(&json!({ "contents": "" })), None);
+
+    let target = Some(("/srv/app/lib.rs".to_string(), Position { line: 2, character: 4 }));
+    assert_eq!(
+        lsp::definition_target(&json!({ "uri": "file:///srv/app/lib.rs", "range": range })),
+        target
+    );
+    assert_eq!(
+        lsp::definition_target(&json!([{
+            "targetUri": "file:///srv/app/lib.rs",
+            "targetRange": range,
+            "targetSelectionRange": range,
+        }])),
+        target
+    );
+    assert_eq!(lsp::definition_target(&json!([])), None);
+}
@@ -0,0 +1,22 @@
+use rs_peer_workspace_shared::media::{pdf_page_count, pdf_version, MediaKind};
+
+#[test]
+fn media_kind_follows_the_extension() {
+    assert_eq!(MediaKind::from_path("/srv/logo.PNG"), Some(MediaKind::Image));
+    assert_eq!(MediaKind::from_path("photo.jpeg"), Some(MediaKind::Image));
+    assert_eq!(MediaKind::from_path("icon.svg"), Some(MediaKind::Svg));
+    assert_eq!(MediaKind::from_path("manual.pdf"), Some(MediaKind::Pdf));
+    assert_eq!(MediaKind::from_path("main.rs"), None);
+    assert_eq!(MediaKind::from_path("Makefile"), None);
+}
+
+#[test]
+fn pdf_info_reads_version_and_pages() {
+    let pdf = b"%PDF-1.7\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+        2 0 obj << /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >> endobj\n\
+        3 0 obj << /Type /Page /Parent 2 0 R >> endobj\n\
+        4 0 obj <</Type/Page/Parent 2 0 R>> endobj\n%%EOF";
+    assert_eq!(pdf_version(pdf).as_deref(), Some("1.7"));
+    assert_eq!(pdf_page_count(pdf), 2);
+    assert_eq!(pdf_version(b"GIF89a"), None);
+}
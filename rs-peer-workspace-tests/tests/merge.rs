@@ -0,0 +1,65 @@
+use rs_peer_workspace_shared::merge::{MergeChunk, ThreeWayMerge};
+
+fn lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(str::to_string).collect()
+}
+
+#[test]
+fn takes_changes_made_on_one_side() {
+    let base = "a\nb\nc\nd\ne\n";
+    let mine = "a\nB\nc\nd\ne\n";
+    let theirs = "a\nb\nc\nd\nE\nf\n";
+    let merge = ThreeWayMerge::new(base, mine, theirs);
+    assert_eq!(merge.conflict_count(), 0);
+    assert_eq!(merge.result(&[]), "a\nB\nc\nd\nE\nf\n");
+}
+
+#[test]
+fn identical_changes_do_not_conflict() {
+    let merge = ThreeWayMerge::new("a\nb\nc\n", "a\nx\nc\n", "a\nx\nc\n");
+    assert_eq!(merge.conflict_count(), 0);
+    assert_eq!(merge.result(&[]), "a\nx\nc\n");
+}
+
+#[test]
+fn overlapping_changes_conflict() {
+    let base = "one\ntwo\nthree\nfour\n";
+    let mine = "one\n2\nthree\nfour\n";
+    let theirs = "one\nzwei\nthree\nvier\n";
+    let merge = ThreeWayMerge::new(base, mine, theirs);
+    assert_eq!(merge.conflict_count(), 1);
+    assert_eq!(
+        merge.chunks[1],
+        MergeChunk::Conflict {
+            base: lines("two\n"),
+            mine: lines("2\n"),
+            theirs: lines("zwei\n"),
+        }
+    );
+    assert_eq!(
+        merge.result(&["2\nzwei\n".to_string()]),
+        "one\n2\nzwei\nthree\nvier\n"
+    );
+}
+
+#[test]
+fn reads_git_conflict_markers() {
+    let text = "start\n<<<<<<< HEAD\nmine\n||||||| base\nold\n=======\ntheirs\n>>>>>>> feature\nend\n";
+    let merge = ThreeWayMerge::from_conflict_markers(text).unwrap();
+    assert_eq!(
+        merge.chunks,
+        vec![
+            MergeChunk::Resolved(lines("start\n")),
+            MergeChunk::Conflict {
+                base: lines("old\n"),
+                mine: lines("mine\n"),
+                theirs: lines("theirs\n"),
+            },
+            MergeChunk::Resolved(lines("end\n")),
+        ]
+    );
+    assert_eq!(merge.result(&["theirs\n".to_string()]), "start\ntheirs\nend\n");
+
+    assert!(ThreeWayMerge::from_conflict_markers("no conflicts\n").is_none());
+    assert!(ThreeWayMerge::from_conflict_markers("<<<<<<< HEAD\nmine\n=======\n").is_none());
+}
@@ -0,0 +1,31 @@
+use rs_peer_workspace_shared::project::{is_absolute_path, path_crumbs};
+
+fn assert_crumbs(path: &str, expected: &[(&str, &str)]) {
+    let crumbs = path_crumbs(path);
+    let crumbs: Vec<(&str, &str)> = crumbs
+        .iter()
+        .map(|(name, path)| (name.as_str(), path.as_str()))
+        .collect();
+    assert_eq!(crumbs, expected);
+}
+
+#[test]
+fn splits_unix_and_windows_paths_into_crumbs() {
+    assert_crumbs("/", &[("/", "/")]);
+    assert_crumbs("/srv//app/", &[("/", "/"), ("srv", "/srv"), ("app", "/srv/app")]);
+    assert_crumbs(
+        "C:\\Users\\me",
+        &[("C:\\", "C:\\"), ("Users", "C:\\Users"), ("me", "C:\\Users\\me")],
+    );
+    assert_crumbs("D:/data", &[("D:/", "D:/"), ("data", "D:/data")]);
+}
+
+#[test]
+fn recognises_absolute_paths() {
+    assert!(is_absolute_path("/etc"));
+    assert!(is_absolute_path("C:\\Windows"));
+    assert!(is_absolute_path("\\\\server\\share"));
+    assert!(!is_absolute_path("src/main.rs"));
+    assert!(!is_absolute_path("~/notes"));
+    assert!(!is_absolute_path(""));
+}
@@ -0,0 +1,41 @@
+use rs_peer_workspace_shared::project::{FolderSource, PinnedPath, ProjectFile};
+
+fn remote(path: &str) -> FolderSource {
+    FolderSource::Remote {
+        connection_name: "build".to_string(),
+        path: path.to_string(),
+    }
+}
+
+#[test]
+fn toggles_pins() {
+    let mut project = ProjectFile::default();
+    let logs = PinnedPath {
+        source: remote("/var/log"),
+        is_dir: true,
+    };
+    assert!(project.toggle_pin(logs.clone()));
+    assert!(project.is_pinned(&remote("/var/log")));
+    // The same path on another connection or locally is a different pin.
+    assert!(!project.is_pinned(&FolderSource::Local {
+        path: "/var/log".to_string()
+    }));
+    assert!(!project.toggle_pin(logs));
+    assert!(project.pinned.is_empty());
+}
+
+#[test]
+fn saves_pins_in_the_project() -> anyhow::Result<()> {
+    let empty = serde_json::to_value(ProjectFile::default())?;
+    assert!(empty.get("pinned").is_none());
+
+    let mut project = ProjectFile::default();
+    project.toggle_pin(PinnedPath {
+        source: remote("/etc/nginx/nginx.conf"),
+        is_dir: false,
+    });
+    let saved = serde_json::to_string(&project)?;
+    let loaded: ProjectFile = serde_json::from_str(&saved)?;
+    assert_eq!(loaded.pinned, project.pinned);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use rs_peer_workspace_shared::pipeline::plan;
+use rs_peer_workspace_shared::project::ProjectTask;
+
+fn task(name: &str, depends_on: &[&str]) -> ProjectTask {
+    ProjectTask {
+        name: name.to_string(),
+        connection_name: "server".to_string(),
+        command: format!("echo {name}"),
+        depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plan_orders_dependencies_first_in_stages() -> anyhow::Result<()> {
+    let tasks = [
+        task("deploy", &["test", "lint"]),
+        task("test", &["build"]),
+        task("lint", &[]),
+        task("build", &[]),
+        task("docs", &[]),
+    ];
+    let planned = plan(&tasks, "deploy")?;
+    let steps: Vec<_> = planned
+        .iter()
+        .map(|step| (step.name.as_str(), step.stage))
+        .collect();
+    assert_eq!(steps, [("build", 0), ("test", 1), ("lint", 0), ("deploy", 2)]);
+
+    assert_eq!(plan(&tasks, "lint")?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn plan_rejects_unknown_tasks_and_cycles() {
+    let tasks = [task("a", &["b"]), task("b", &["c"]), task("c", &["a"]), task("d", &["missing"])];
+    let cycle = plan(&tasks, "a").unwrap_err().to_string();
+    assert!(cycle.contains("a -> b -> c -> a"), "{cycle}");
+    let unknown = plan(&tasks, "d").unwrap_err().to_string();
+    assert!(unknown.contains("unknown task missing"), "{unknown}");
+    assert!(plan(&tasks, "nope").is_err());
+}
@@ -0,0 +1,38 @@
+use std::fs;
+use std::sync::atomic::AtomicBool;
+
+use rs_peer_workspace_shared::app::SearchQuery;
+use rs_peer_workspace_shared::search::search_tree;
+use uuid::Uuid;
+
+#[test]
+fn searches_a_local_tree() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("search-{}", Uuid::new_v4()));
+    fs::create_dir_all(dir.join("src"))?;
+    fs::create_dir_all(dir.join(".git"))?;
+    fs::write(dir.join("src/main.rs"), "fn main() {\n    todo!();\n}\n")?;
+    fs::write(dir.join("notes.txt"), "TODO: write docs\r\n")?;
+    fs::write(dir.join(".git/config"), "todo\n")?;
+    let root = dir.to_string_lossy();
+
+    let query = SearchQuery {
+        pattern: "todo".to_string(),
+        regex: false,
+        case_sensitive: false,
+    };
+    let (files, truncated) = search_tree(&root, &query, None, None)?;
+    assert!(!truncated);
+    let found: Vec<_> = files
+        .iter()
+        .map(|file| (file.path.strip_prefix(root.as_ref()).unwrap().to_string(), file.matches[0].line))
+        .collect();
+    assert_eq!(found, [("/notes.txt".to_string(), 1), ("/src/main.rs".to_string(), 2)]);
+    assert_eq!(files[0].matches[0].text, "TODO: write docs");
+
+    let cancelled = AtomicBool::new(true);
+    let err = search_tree(&root, &query, None, Some(&cancelled)).unwrap_err();
+    assert_eq!(err.to_string(), "search cancelled");
+    assert!(search_tree(&dir.join("missing").to_string_lossy(), &query, None, None).is_err());
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
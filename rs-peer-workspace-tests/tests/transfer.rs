@@ -0,0 +1,88 @@
+use rs_peer_workspace_shared::app::ChunkRange;
+use rs_peer_workspace_shared::content::content_hash;
+use rs_peer_workspace_shared::transfer::{self, ChunkLog, RESUME_CHECK_CHUNKS, VerifiedChunk};
+use uuid::Uuid;
+
+fn chunk(offset: u64, length: u64) -> VerifiedChunk {
+    VerifiedChunk {
+        offset,
+        length,
+        checksum: format!("sum-{offset}"),
+    }
+}
+
+fn sums(chunks: &[VerifiedChunk]) -> Vec<Option<String>> {
+    chunks.iter().map(|chunk| Some(chunk.checksum.clone())).collect()
+}
+
+#[test]
+fn resumes_after_the_contiguous_verified_chunks() {
+    let mut log = ChunkLog::default();
+    log.record(chunk(0, 10));
+    log.record(chunk(20, 10));
+    assert_eq!(log.verified_len(), 10, "a chunk after a gap is not verified yet");
+    log.record(chunk(10, 10));
+    assert_eq!(log.verified_len(), 30);
+
+    for index in 3..10 {
+        log.record(chunk(index * 10, 10));
+    }
+    assert_eq!(log.verified_len(), 100);
+    let checks = log.resume_checks().to_vec();
+    assert_eq!(checks.len(), RESUME_CHECK_CHUNKS);
+    assert_eq!(checks.last().map(|chunk| chunk.offset), Some(90));
+
+    let mut restored: ChunkLog = serde_json::from_str(&serde_json::to_string(&log).expect("log serializes"))
+        .expect("log parses");
+    assert_eq!(restored.resume(&sums(&checks), &sums(&checks)), 100);
+    assert_eq!(restored, log);
+}
+
+#[test]
+fn rewinds_a_damaged_destination_and_restarts_a_changed_source() {
+    let mut log = ChunkLog::default();
+    for index in 0..8 {
+        log.record(chunk(index * 10, 10));
+    }
+    let checks = log.resume_checks().to_vec();
+    let mut damaged = sums(&checks);
+    damaged[2] = Some("truncated".to_string());
+
+    let mut rewound = log.clone();
+    assert_eq!(rewound.resume(&sums(&checks), &damaged), 60);
+    assert_eq!(rewound.verified_len(), 60);
+
+    let mut short = log.clone();
+    assert_eq!(short.resume(&sums(&checks), &sums(&checks)[..1]), 50);
+
+    let mut restarted = log;
+    assert_eq!(restarted.resume(&damaged, &sums(&checks)), 0);
+    assert_eq!(restarted, ChunkLog::default());
+}
+
+#[test]
+fn checksums_file_ranges() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("chunks-{}.bin", Uuid::new_v4()));
+    let data: Vec<u8> = (0..200_000u32).map(|value| value as u8).collect();
+    std::fs::write(&path, &data)?;
+
+    let ranges = [
+        ChunkRange { offset: 0, length: 100_000 },
+        ChunkRange { offset: 150_000, length: 50_000 },
+        ChunkRange { offset: 150_000, length: 50_001 },
+        ChunkRange { offset: u64::MAX, length: 2 },
+    ];
+    let (checksums, size) = transfer::range_checksums(&path, &ranges)?;
+    assert_eq!(size, 200_000);
+    assert_eq!(
+        checksums,
+        vec![
+            Some(content_hash(&data[..100_000])),
+            Some(content_hash(&data[150_000..])),
+            None,
+            None,
+        ]
+    );
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
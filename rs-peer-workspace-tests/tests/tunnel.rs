@@ -0,0 +1,13 @@
+use rs_peer_workspace_shared::tunnel::InOrder;
+
+#[test]
+fn chunks_are_released_in_order() {
+    let mut order = InOrder::default();
+    assert!(order.push(1, "b").is_empty());
+    assert!(order.push(3, "d").is_empty());
+    assert_eq!(order.push(0, "a"), vec!["a", "b"]);
+    // A repeated chunk is dropped.
+    assert!(order.push(1, "b").is_empty());
+    assert_eq!(order.push(2, "c"), vec!["c", "d"]);
+    assert_eq!(order.push(4, "e"), vec!["e"]);
+}